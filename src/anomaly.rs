@@ -0,0 +1,406 @@
+//! Per-packet anomaly rules, a first stage for intrusion detection.
+//!
+//! [`check`] runs the built-in rules on a parsed [`Packet`], reporting the
+//! [`Anomaly`] kinds found. [`AnomalyEngine`] adds user rules, and allows
+//! to disable built-in rules which are too noisy for a network, e.g. the
+//! bogon sources behind a NAT.
+
+use core::{
+    fmt,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+};
+
+use crate::packet::{NetworkHdr, Packet, TransportHdr};
+
+/// Anomalies found by the built-in rules.
+#[repr(u8)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum Anomaly {
+    /// Same source and destination address and ports, the LAND attack.
+    Land = 0,
+    /// TCP segment with both SYN and FIN.
+    SynFin = 1,
+    /// TCP segment with both SYN and RST.
+    SynRst = 2,
+    /// TCP segment without any flag, used by NULL scans.
+    NullFlags = 3,
+    /// TCP segment with FIN, PSH and URG, used by Christmas tree scans.
+    XmasTree = 4,
+    /// TCP segment with FIN but without ACK, used by FIN scans.
+    FinWithoutAck = 5,
+    /// Source address from a range which is never a valid source, such as
+    /// loopback, multicast, reserved or documentation addresses.
+    BogonSource = 6,
+    /// Source address from the private or shared ranges of IPv4, or the
+    /// unique local range of IPv6.
+    PrivateSource = 7,
+    /// Zero TTL or hop limit, which routers never forward.
+    TtlZero = 8,
+    /// UDP length shorter than the UDP header.
+    UdpShortLength = 9,
+}
+
+impl Anomaly {
+    /// All the built-in anomalies.
+    pub const ALL: [Anomaly; 10] = [
+        Anomaly::Land,
+        Anomaly::SynFin,
+        Anomaly::SynRst,
+        Anomaly::NullFlags,
+        Anomaly::XmasTree,
+        Anomaly::FinWithoutAck,
+        Anomaly::BogonSource,
+        Anomaly::PrivateSource,
+        Anomaly::TtlZero,
+        Anomaly::UdpShortLength,
+    ];
+
+    /// Short name of the anomaly.
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Anomaly::Land => "land",
+            Anomaly::SynFin => "syn-fin",
+            Anomaly::SynRst => "syn-rst",
+            Anomaly::NullFlags => "null-flags",
+            Anomaly::XmasTree => "xmas-tree",
+            Anomaly::FinWithoutAck => "fin-without-ack",
+            Anomaly::BogonSource => "bogon-source",
+            Anomaly::PrivateSource => "private-source",
+            Anomaly::TtlZero => "ttl-zero",
+            Anomaly::UdpShortLength => "udp-short-length",
+        }
+    }
+}
+
+impl fmt::Display for Anomaly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A set of [`Anomaly`] kinds.
+#[derive(PartialEq, Eq, Hash, Debug, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct Anomalies(u16);
+
+impl Anomalies {
+    pub const EMPTY: Anomalies = Anomalies(0);
+    pub const ALL: Anomalies = Anomalies((1 << Anomaly::ALL.len()) - 1);
+
+    #[inline]
+    pub const fn contains(&self, anomaly: Anomaly) -> bool {
+        self.0 & (1 << anomaly as u8) != 0
+    }
+
+    #[inline]
+    pub const fn insert(&mut self, anomaly: Anomaly) {
+        self.0 |= 1 << anomaly as u8;
+    }
+
+    #[inline]
+    pub const fn remove(&mut self, anomaly: Anomaly) {
+        self.0 &= !(1 << anomaly as u8);
+    }
+
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    #[inline]
+    pub const fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Anomaly> + '_ {
+        Anomaly::ALL
+            .into_iter()
+            .filter(|&anomaly| self.contains(anomaly))
+    }
+}
+
+impl FromIterator<Anomaly> for Anomalies {
+    fn from_iter<T: IntoIterator<Item = Anomaly>>(iter: T) -> Self {
+        let mut anomalies = Anomalies::EMPTY;
+        iter.into_iter()
+            .for_each(|anomaly| anomalies.insert(anomaly));
+        anomalies
+    }
+}
+
+/// IPv4 ranges which are never valid sources, the unspecified address
+/// excepted since it is used by DHCP clients.
+const BOGONS_V4: [(u32, u8); 8] = [
+    (0x0000_0000, 8),
+    (0x7f00_0000, 8),
+    (0xc000_0200, 24),
+    (0xc612_0000, 15),
+    (0xc633_6400, 24),
+    (0xcb00_7100, 24),
+    (0xe000_0000, 4),
+    (0xf000_0000, 4),
+];
+
+const PRIVATE_V4: [(u32, u8); 4] = [
+    (0x0a00_0000, 8),
+    (0x6440_0000, 10),
+    (0xac10_0000, 12),
+    (0xc0a8_0000, 16),
+];
+
+#[inline]
+fn in_v4(ranges: &[(u32, u8)], addr: Ipv4Addr) -> bool {
+    let addr = u32::from(addr);
+    ranges
+        .iter()
+        .any(|&(prefix, len)| addr >> (32 - len) == prefix >> (32 - len))
+}
+
+/// Whether `addr` is never a valid source address.
+///
+/// The unspecified addresses are not bogons, being the source of DHCP
+/// discovery and of IPv6 duplicate address detection.
+pub fn is_bogon_source(addr: IpAddr) -> bool {
+    match addr {
+        IpAddr::V4(addr) => !addr.is_unspecified() && in_v4(&BOGONS_V4, addr),
+        IpAddr::V6(addr) => {
+            let segments = addr.segments();
+            addr == Ipv6Addr::LOCALHOST
+                || addr.is_multicast()
+                || addr.to_ipv4_mapped().is_some()
+                || segments[..2] == [0x2001, 0x0db8]
+        }
+    }
+}
+
+/// Whether `addr` is a private, shared or unique local address.
+pub fn is_private_source(addr: IpAddr) -> bool {
+    match addr {
+        IpAddr::V4(addr) => in_v4(&PRIVATE_V4, addr),
+        IpAddr::V6(addr) => addr.segments()[0] & 0xfe00 == 0xfc00,
+    }
+}
+
+/// Runs the built-in rules on `packet`.
+///
+/// ```
+/// use ether_packet::{anomaly::{self, Anomaly}, packet::Packet};
+///
+/// #[rustfmt::skip]
+/// let frame = [
+///     0, 1, 2, 3, 4, 5, 0, 6, 7, 8, 9, 10, 0x08, 0x00,
+///     0x45, 0, 0, 40, 0, 0, 0, 0, 64, 6, 0, 0, 192, 0, 2, 1, 192, 0, 2, 1,
+///     // SYN and FIN from port 80 to port 80.
+///     0, 80, 0, 80, 0, 0, 0, 0, 0, 0, 0, 0, 0x50, 0x03, 0, 0, 0, 0, 0, 0,
+/// ];
+/// let found = anomaly::check(&Packet::parse(&frame).unwrap());
+/// assert!(found.contains(Anomaly::Land));
+/// assert!(found.contains(Anomaly::SynFin));
+/// assert!(found.contains(Anomaly::BogonSource));
+/// ```
+pub fn check(packet: &Packet<'_>) -> Anomalies {
+    let mut found = Anomalies::EMPTY;
+    let Some(network) = packet.network() else {
+        return found;
+    };
+    let (src, dst) = (network.src_addr(), network.dst_addr());
+    let ttl = match network {
+        NetworkHdr::Ipv4(hdr) => hdr.ttl,
+        NetworkHdr::Ipv6(hdr) => hdr.hop_limit,
+    };
+    if ttl == 0 {
+        found.insert(Anomaly::TtlZero);
+    }
+    if is_bogon_source(src) {
+        found.insert(Anomaly::BogonSource);
+    }
+    if is_private_source(src) {
+        found.insert(Anomaly::PrivateSource);
+    }
+
+    let transport = packet.transport();
+    let ports = transport.and_then(|hdr| Some((hdr.src_port()?, hdr.dst_port()?)));
+    // Loopback captures see the host talking to itself.
+    if src == dst
+        && !src.is_unspecified()
+        && !src.is_loopback()
+        && ports.is_none_or(|(s, d)| s == d)
+    {
+        found.insert(Anomaly::Land);
+    }
+    match transport {
+        Some(TransportHdr::Tcp(tcp)) => {
+            let (fin, syn, rst) = (tcp.fin() != 0, tcp.syn() != 0, tcp.rst() != 0);
+            let (psh, ack, urg) = (tcp.psh() != 0, tcp.ack() != 0, tcp.urg() != 0);
+            if syn && fin {
+                found.insert(Anomaly::SynFin);
+            }
+            if syn && rst {
+                found.insert(Anomaly::SynRst);
+            }
+            if !(fin || syn || rst || psh || ack || urg) {
+                found.insert(Anomaly::NullFlags);
+            }
+            if fin && psh && urg {
+                found.insert(Anomaly::XmasTree);
+            }
+            if fin && !ack {
+                found.insert(Anomaly::FinWithoutAck);
+            }
+        }
+        Some(TransportHdr::Udp(udp)) if udp.len.to_bits() < 8 => {
+            found.insert(Anomaly::UdpShortLength);
+        }
+        _ => {}
+    }
+    found
+}
+
+/// A user rule of an [`AnomalyEngine`].
+pub trait AnomalyRule {
+    /// Whether `packet` is anomalous.
+    fn matches(&self, packet: &Packet<'_>) -> bool;
+}
+
+impl<F: Fn(&Packet<'_>) -> bool> AnomalyRule for F {
+    #[inline]
+    fn matches(&self, packet: &Packet<'_>) -> bool {
+        self(packet)
+    }
+}
+
+/// Findings of an [`AnomalyEngine`] for a packet.
+#[derive(PartialEq, Eq, Hash, Debug, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct Findings {
+    pub anomalies: Anomalies,
+    /// Bit `i` is set if the user rule at index `i` matched.
+    pub rules: u64,
+}
+
+impl Findings {
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.anomalies.is_empty() && self.rules == 0
+    }
+
+    /// Indexes of the user rules which matched.
+    pub fn matched_rules(&self) -> impl Iterator<Item = usize> {
+        let rules = self.rules;
+        (0..64).filter(move |i| rules & (1 << i) != 0)
+    }
+}
+
+/// Runs the enabled built-in rules and up to 64 user rules on packets.
+///
+/// ```
+/// use ether_packet::{
+///     anomaly::{Anomaly, AnomalyEngine, AnomalyRule},
+///     packet::Packet,
+/// };
+///
+/// let jumbo = |packet: &Packet<'_>| packet.data().len() > 1518;
+/// let rules: [&dyn AnomalyRule; 1] = [&jumbo];
+/// let mut engine = AnomalyEngine::new(&rules);
+/// engine.disable(Anomaly::PrivateSource);
+///
+/// let mut frame = [0u8; 2000];
+/// frame[12..14].copy_from_slice(&[0x88, 0xb5]);
+/// let findings = engine.check(&Packet::parse(&frame).unwrap());
+/// assert!(findings.anomalies.is_empty());
+/// assert!(findings.matched_rules().eq([0]));
+/// ```
+pub struct AnomalyEngine<'r> {
+    enabled: Anomalies,
+    rules: &'r [&'r dyn AnomalyRule],
+}
+
+impl<'r> AnomalyEngine<'r> {
+    /// Creates an engine with all the built-in rules and `rules`, of which
+    /// only the first 64 are used.
+    #[inline]
+    pub fn new(rules: &'r [&'r dyn AnomalyRule]) -> Self {
+        Self {
+            enabled: Anomalies::ALL,
+            rules: &rules[..rules.len().min(64)],
+        }
+    }
+
+    #[inline]
+    pub fn enabled(&self) -> Anomalies {
+        self.enabled
+    }
+
+    #[inline]
+    pub fn enable(&mut self, anomaly: Anomaly) -> &mut Self {
+        self.enabled.insert(anomaly);
+        self
+    }
+
+    #[inline]
+    pub fn disable(&mut self, anomaly: Anomaly) -> &mut Self {
+        self.enabled.remove(anomaly);
+        self
+    }
+
+    pub fn check(&self, packet: &Packet<'_>) -> Findings {
+        let mut anomalies = check(packet);
+        anomalies.0 &= self.enabled.0;
+        let rules = self
+            .rules
+            .iter()
+            .enumerate()
+            .filter(|(_, rule)| rule.matches(packet))
+            .fold(0, |rules, (i, _)| rules | 1 << i);
+        Findings { anomalies, rules }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check, is_bogon_source, is_private_source, Anomaly, AnomalyEngine};
+    use crate::packet::Packet;
+
+    #[test]
+    fn test_anomalies() {
+        #[rustfmt::skip]
+        let mut frame = [
+            0, 1, 2, 3, 4, 5, 0, 6, 7, 8, 9, 10, 0x08, 0x00,
+            0x45, 0, 0, 40, 0, 0, 0, 0, 64, 6, 0, 0, 10, 0, 0, 1, 8, 8, 8, 8,
+            // FIN, PSH and URG.
+            0x30, 0x39, 0, 80, 0, 0, 0, 0, 0, 0, 0, 0, 0x50, 0x29, 0, 0, 0, 0, 0, 0,
+        ];
+        let found = check(&Packet::parse(&frame).unwrap());
+        assert!(found.iter().eq([
+            Anomaly::XmasTree,
+            Anomaly::FinWithoutAck,
+            Anomaly::PrivateSource
+        ]));
+
+        // No flags, zero TTL.
+        (frame[22], frame[47]) = (0, 0);
+        let engine = AnomalyEngine::new(&[]);
+        let findings = engine.check(&Packet::parse(&frame).unwrap());
+        assert!(findings.anomalies.contains(Anomaly::NullFlags));
+        assert!(findings.anomalies.contains(Anomaly::TtlZero));
+        assert_eq!(findings.anomalies.len(), 3);
+
+        // UDP with a length of 4.
+        (frame[23], frame[39]) = (17, 4);
+        let found = check(&Packet::parse(&frame).unwrap());
+        assert!(found.contains(Anomaly::UdpShortLength));
+        assert!(!found.contains(Anomaly::NullFlags));
+
+        assert!(is_bogon_source("127.0.0.1".parse().unwrap()));
+        assert!(is_bogon_source("240.0.0.1".parse().unwrap()));
+        assert!(!is_bogon_source("0.0.0.0".parse().unwrap()));
+        assert!(!is_bogon_source("198.20.0.1".parse().unwrap()));
+        assert!(is_bogon_source("ff02::1".parse().unwrap()));
+        assert!(is_bogon_source("2001:db8::1".parse().unwrap()));
+        assert!(!is_bogon_source("::".parse().unwrap()));
+        assert!(is_private_source("100.127.0.1".parse().unwrap()));
+        assert!(is_private_source("fd00::1".parse().unwrap()));
+        assert!(!is_private_source("172.32.0.1".parse().unwrap()));
+    }
+}
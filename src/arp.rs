@@ -0,0 +1,109 @@
+//! Address Resolution Protocol
+//! ([RFC 826](https://datatracker.ietf.org/doc/html/rfc826)) for IPv4 over
+//! Ethernet.
+
+use core::{mem, net::Ipv4Addr};
+
+use crate::{header::impl_header, types::U16};
+
+/// Hardware type of Ethernet.
+pub const ARP_HTYPE_ETHERNET: u16 = 1;
+
+/// ARP operations.
+#[repr(u16)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum ArpOp {
+    Request = 1,
+    Reply = 2,
+}
+
+impl TryFrom<u16> for ArpOp {
+    type Error = ();
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(ArpOp::Request),
+            2 => Ok(ArpOp::Reply),
+            _ => Err(()),
+        }
+    }
+}
+
+/// ARP packet mapping IPv4 addresses to Ethernet addresses.
+/// ```text
+/// +--------------------------------+--------------------------------+
+/// |      Hardware Type (2)         |      Protocol Type (2)         |
+/// +----------------+---------------+--------------------------------+
+/// | HW Addr Len (1)| Proto Len (1) |        Operation (2)           |
+/// +----------------+---------------+--------------------------------+
+/// |                   Sender Hardware Address (6)                   |
+/// +-----------------------------------------------------------------+
+/// |                   Sender Protocol Address (4)                   |
+/// +-----------------------------------------------------------------+
+/// |                   Target Hardware Address (6)                   |
+/// +-----------------------------------------------------------------+
+/// |                   Target Protocol Address (4)                   |
+/// +-----------------------------------------------------------------+
+/// ```
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct ArpHdr {
+    pub htype: U16,
+    /// An [`EtherType`](crate::eth::EtherType), IPv4 for the packets
+    /// described by this header.
+    pub ptype: U16,
+    pub hlen: u8,
+    pub plen: u8,
+    pub op: U16,
+    pub sha: [u8; 6],
+    pub spa: Ipv4Addr,
+    pub tha: [u8; 6],
+    pub tpa: Ipv4Addr,
+}
+
+impl ArpHdr {
+    pub const LEN: usize = mem::size_of::<ArpHdr>();
+
+    #[inline]
+    pub fn op(&self) -> Option<ArpOp> {
+        self.op.to_bits().try_into().ok()
+    }
+
+    /// Whether the packet announces the sender's own address (gratuitous
+    /// ARP), i.e. the sender and target protocol addresses are equal.
+    #[inline]
+    pub fn is_gratuitous(&self) -> bool {
+        self.spa == self.tpa
+    }
+}
+
+// Only IPv4 over Ethernet fits the fixed layout above.
+impl_header!(
+    ArpHdr,
+    validate = |b: &[u8]| b[0..6] == [0, 1, 0x08, 0x00, 6, 4]
+);
+
+#[cfg(test)]
+mod tests {
+    use core::net::Ipv4Addr;
+
+    use super::{ArpHdr, ArpOp};
+    use crate::header::Header;
+
+    #[test]
+    fn test_arp_reply() {
+        let bytes = [
+            0, 1, 0x08, 0, 6, 4, 0, 2, 2, 0, 0, 0, 0, 1, 10, 0, 0, 1, 2, 0, 0, 0, 0, 2, 10, 0, 0, 2,
+        ];
+        let arp = ArpHdr::from_bytes(&bytes).unwrap();
+        assert_eq!(arp.op(), Some(ArpOp::Reply));
+        assert_eq!(arp.spa, Ipv4Addr::new(10, 0, 0, 1));
+        assert_eq!(arp.sha, [2, 0, 0, 0, 0, 1]);
+        assert!(!arp.is_gratuitous());
+
+        let mut ipv6 = bytes;
+        ipv6[2] = 0x86;
+        assert!(ArpHdr::from_bytes(&ipv6).is_none());
+    }
+}
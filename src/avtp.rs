@@ -0,0 +1,438 @@
+//! Audio Video Transport Protocol (IEEE 1722-2016), carrying the media
+//! streams of AVB and TSN networks with
+//! [`EtherType::AVTP`](crate::eth::EtherType::AVTP).
+//!
+//! Stream PDUs carry the time at which their samples are to be presented,
+//! as the low 32 bits of the gPTP time in nanoseconds, which
+//! [`presentation_time`] extends to the full gPTP time.
+
+use core::mem;
+
+use crate::{
+    header::{impl_header, Header},
+    types::{U16, U32, U64},
+};
+
+/// AVTP subtypes.
+#[repr(u8)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum AvtpSubtype {
+    /// IEC 61883/IIDC format.
+    Iec61883 = 0x00,
+    /// MMA streams.
+    Mma = 0x01,
+    /// AVTP audio format.
+    Aaf = 0x02,
+    /// Compressed video format.
+    Cvf = 0x03,
+    /// Clock reference format.
+    Crf = 0x04,
+    /// Time-synchronous control format.
+    Tscf = 0x05,
+    /// SDI video format.
+    Svf = 0x06,
+    /// Raw video format.
+    Rvf = 0x07,
+    /// Non-time-synchronous control format.
+    Ntscf = 0x82,
+    /// AVDECC discovery protocol.
+    Adp = 0xfa,
+    /// AVDECC enumeration and control protocol.
+    Aecp = 0xfb,
+    /// AVDECC connection management protocol.
+    Acmp = 0xfc,
+    /// MAC address acquisition protocol.
+    Maap = 0xfe,
+}
+
+impl TryFrom<u8> for AvtpSubtype {
+    type Error = ();
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x00 => Ok(AvtpSubtype::Iec61883),
+            0x01 => Ok(AvtpSubtype::Mma),
+            0x02 => Ok(AvtpSubtype::Aaf),
+            0x03 => Ok(AvtpSubtype::Cvf),
+            0x04 => Ok(AvtpSubtype::Crf),
+            0x05 => Ok(AvtpSubtype::Tscf),
+            0x06 => Ok(AvtpSubtype::Svf),
+            0x07 => Ok(AvtpSubtype::Rvf),
+            0x82 => Ok(AvtpSubtype::Ntscf),
+            0xfa => Ok(AvtpSubtype::Adp),
+            0xfb => Ok(AvtpSubtype::Aecp),
+            0xfc => Ok(AvtpSubtype::Acmp),
+            0xfe => Ok(AvtpSubtype::Maap),
+            _ => Err(()),
+        }
+    }
+}
+
+/// AVTP common header, shared by stream and control PDUs.
+/// ```text
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |    subtype    |S|Vers.|M| F |T| sequence_num  |  format  |  U|
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                                                               |
+/// +                           stream_id                           +
+/// |                                                               |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+/// The M, T and U bits, and the sequence number, are those of stream PDUs.
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct AvtpHdr {
+    /// See [`AvtpSubtype`].
+    pub subtype: u8,
+    /// **sv** (1 bit), **version** (3 bits) and subtype-specific bits.
+    pub flags: u8,
+    pub sequence_num: u8,
+    /// Format-specific bits, with the **tu** flag of stream PDUs.
+    pub format_flags: u8,
+    pub stream_id: U64,
+}
+
+impl AvtpHdr {
+    pub const LEN: usize = mem::size_of::<AvtpHdr>();
+
+    #[inline]
+    pub fn subtype(&self) -> Option<AvtpSubtype> {
+        self.subtype.try_into().ok()
+    }
+
+    /// **sv**: the stream ID is valid.
+    #[inline]
+    pub const fn sv(&self) -> bool {
+        self.flags & 0x80 != 0
+    }
+
+    /// AVTP version, 0.
+    #[inline]
+    pub const fn version(&self) -> u8 {
+        (self.flags >> 4) & 0x7
+    }
+
+    /// Stream ID, usually the MAC address of the talker followed by a
+    /// 16-bit unique ID.
+    #[inline]
+    pub const fn stream_id(&self) -> Option<u64> {
+        if self.sv() {
+            Some(self.stream_id.to_bits())
+        } else {
+            None
+        }
+    }
+
+    /// **mr**: the media clock source changed, in stream PDUs.
+    #[inline]
+    pub const fn media_clock_restart(&self) -> bool {
+        self.flags & 0x08 != 0
+    }
+
+    /// **tv**: the AVTP timestamp is valid, in stream PDUs.
+    #[inline]
+    pub const fn timestamp_valid(&self) -> bool {
+        self.flags & 0x01 != 0
+    }
+
+    /// **tu**: the gPTP time of the talker is uncertain, in stream PDUs.
+    #[inline]
+    pub const fn timestamp_uncertain(&self) -> bool {
+        self.format_flags & 0x01 != 0
+    }
+
+    /// Parses the AVTP stream PDU of the AAF or CVF format stored in `pdu`.
+    pub fn parse_stream(pdu: &[u8]) -> Option<AvtpStream<'_>> {
+        let hdr = AvtpHdr::from_bytes(pdu)?;
+        let body = &pdu[Self::LEN..];
+        let (format, len) = match hdr.subtype() {
+            Some(AvtpSubtype::Aaf) => {
+                let aaf = AafHdr::from_bytes(body)?;
+                (AvtpFormat::Aaf(aaf), aaf.stream_data_length.to_bits())
+            }
+            Some(AvtpSubtype::Cvf) => {
+                let cvf = CvfHdr::from_bytes(body)?;
+                (AvtpFormat::Cvf(cvf), cvf.stream_data_length.to_bits())
+            }
+            _ => return None,
+        };
+        // Both formats put the AVTP timestamp first.
+        let avtp_timestamp = u32::from_be_bytes(body[..4].try_into().ok()?);
+        let data = body[AafHdr::LEN..].get(..len as usize)?;
+        Some(AvtpStream {
+            hdr,
+            format,
+            avtp_timestamp: hdr.timestamp_valid().then_some(avtp_timestamp),
+            data,
+        })
+    }
+}
+
+/// Sample formats of AAF.
+#[repr(u8)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum AafFormat {
+    User = 0,
+    Float32 = 1,
+    Int32 = 2,
+    Int24 = 3,
+    Int16 = 4,
+    Aes3 = 5,
+}
+
+impl TryFrom<u8> for AafFormat {
+    type Error = ();
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(AafFormat::User),
+            1 => Ok(AafFormat::Float32),
+            2 => Ok(AafFormat::Int32),
+            3 => Ok(AafFormat::Int24),
+            4 => Ok(AafFormat::Int16),
+            5 => Ok(AafFormat::Aes3),
+            _ => Err(()),
+        }
+    }
+}
+
+/// AAF header, following the [`AvtpHdr`] of PCM audio stream PDUs.
+/// ```text
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                        avtp_timestamp                         |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |    format     |  nsr  |rsv|channels_per_frame |   bit_depth   |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |      stream_data_length       |rsv|S|  evt  |   reserved    |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct AafHdr {
+    pub avtp_timestamp: U32,
+    /// See [`AafFormat`].
+    pub format: u8,
+    /// **nsr** (4 bits) and the high bits of **channels_per_frame**.
+    pub nsr_channels: u8,
+    pub channels: u8,
+    pub bit_depth: u8,
+    pub stream_data_length: U16,
+    /// **sp** (1 bit) and **evt** (4 bits).
+    pub sp_evt: u8,
+    pub _reserved: u8,
+}
+
+impl AafHdr {
+    pub const LEN: usize = mem::size_of::<AafHdr>();
+
+    #[inline]
+    pub fn format(&self) -> Option<AafFormat> {
+        self.format.try_into().ok()
+    }
+
+    /// Nominal sample rate, in Hz.
+    #[inline]
+    pub const fn sample_rate(&self) -> Option<u32> {
+        match self.nsr_channels >> 4 {
+            1 => Some(8_000),
+            2 => Some(16_000),
+            3 => Some(32_000),
+            4 => Some(44_100),
+            5 => Some(48_000),
+            6 => Some(88_200),
+            7 => Some(96_000),
+            8 => Some(176_400),
+            9 => Some(192_000),
+            10 => Some(24_000),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub const fn channels_per_frame(&self) -> u16 {
+        ((self.nsr_channels & 0x3) as u16) << 8 | self.channels as u16
+    }
+
+    /// Number of valid bits of each sample.
+    #[inline]
+    pub const fn bit_depth(&self) -> u8 {
+        self.bit_depth
+    }
+
+    /// **sp**: sparse timestamp mode, where only every eighth PDU carries a
+    /// timestamp.
+    #[inline]
+    pub const fn sparse(&self) -> bool {
+        self.sp_evt & 0x10 != 0
+    }
+}
+
+/// Compressed video formats of CVF.
+#[repr(u8)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum CvfFormat {
+    Mjpeg = 0,
+    H264 = 1,
+    Jpeg2000 = 2,
+}
+
+impl TryFrom<u8> for CvfFormat {
+    type Error = ();
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(CvfFormat::Mjpeg),
+            1 => Ok(CvfFormat::H264),
+            2 => Ok(CvfFormat::Jpeg2000),
+            _ => Err(()),
+        }
+    }
+}
+
+/// CVF header, following the [`AvtpHdr`] of compressed video stream PDUs.
+/// ```text
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                        avtp_timestamp                         |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |    format     | format_subtype|           reserved            |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |      stream_data_length       |r|P|M|  evt  |   reserved    |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct CvfHdr {
+    pub avtp_timestamp: U32,
+    /// 2, for the RTP payload formats.
+    pub format: u8,
+    /// See [`CvfFormat`].
+    pub format_subtype: u8,
+    pub _reserved: U16,
+    pub stream_data_length: U16,
+    /// **ptv** (1 bit), **M** (1 bit) and **evt** (4 bits).
+    pub flags: u8,
+    pub _reserved2: u8,
+}
+
+impl CvfHdr {
+    pub const LEN: usize = mem::size_of::<CvfHdr>();
+
+    #[inline]
+    pub fn format_subtype(&self) -> Option<CvfFormat> {
+        self.format_subtype.try_into().ok()
+    }
+
+    /// **ptv**: the H.264 timestamp starting the stream data is valid.
+    #[inline]
+    pub const fn ptv(&self) -> bool {
+        self.flags & 0x20 != 0
+    }
+
+    /// **M**: the PDU carries the end of a video frame.
+    #[inline]
+    pub const fn marker(&self) -> bool {
+        self.flags & 0x10 != 0
+    }
+
+    /// H.264 timestamp of the stream `data`, which starts with it.
+    #[inline]
+    pub fn h264_timestamp(&self, data: &[u8]) -> Option<u32> {
+        let ts = data.get(..4)?;
+        (self.format_subtype() == Some(CvfFormat::H264) && self.ptv())
+            .then(|| u32::from_be_bytes([ts[0], ts[1], ts[2], ts[3]]))
+    }
+}
+
+impl_header!(AvtpHdr, AafHdr, CvfHdr);
+
+/// Format-specific header of a stream PDU.
+#[derive(Debug, Copy, Clone)]
+pub enum AvtpFormat<'a> {
+    Aaf(&'a AafHdr),
+    Cvf(&'a CvfHdr),
+}
+
+/// A parsed AVTP stream PDU.
+#[derive(Debug, Copy, Clone)]
+pub struct AvtpStream<'a> {
+    pub hdr: &'a AvtpHdr,
+    pub format: AvtpFormat<'a>,
+    /// Presentation time in gPTP nanoseconds, modulo 2^32, if valid.
+    pub avtp_timestamp: Option<u32>,
+    /// Stream data, as long as given by the header.
+    pub data: &'a [u8],
+}
+
+impl AvtpStream<'_> {
+    /// Presentation time in gPTP nanoseconds, see [`presentation_time`].
+    #[inline]
+    pub fn presentation_time(&self, reference: u64) -> Option<u64> {
+        Some(presentation_time(self.avtp_timestamp?, reference))
+    }
+}
+
+/// Extends an AVTP timestamp to the gPTP time closest to `reference`, in
+/// nanoseconds, e.g. to the time the PDU was received at.
+///
+/// ```
+/// use ether_packet::avtp::presentation_time;
+///
+/// // Received just before the low 32 bits wrap, presented 2 ms later.
+/// let received = 5 << 32 | 0xffff_0000;
+/// assert_eq!(presentation_time(0x001d_8480, received), received + 2_000_000);
+/// ```
+#[inline]
+pub const fn presentation_time(avtp_timestamp: u32, reference: u64) -> u64 {
+    let delta = avtp_timestamp.wrapping_sub(reference as u32) as i32;
+    reference.wrapping_add_signed(delta as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AafFormat, AvtpFormat, AvtpHdr, AvtpSubtype};
+
+    #[test]
+    fn test_aaf_stream() {
+        #[rustfmt::skip]
+        let mut pdu = [
+            0x02, 0x81, 7, 0,
+            0x00, 0x1b, 0x21, 0x01, 0x02, 0x03, 0x00, 0x01,
+            0x00, 0x00, 0x10, 0x00,
+            // Int16, 48 kHz, 2 channels.
+            4, 0x50, 2, 16, 0, 8, 0, 0,
+            1, 2, 3, 4, 5, 6, 7, 8,
+        ];
+        let stream = AvtpHdr::parse_stream(&pdu).unwrap();
+        assert_eq!(stream.hdr.subtype(), Some(AvtpSubtype::Aaf));
+        assert_eq!(stream.hdr.stream_id(), Some(0x001b_2101_0203_0001));
+        assert_eq!(stream.hdr.sequence_num, 7);
+        let AvtpFormat::Aaf(aaf) = stream.format else {
+            panic!("expected AAF");
+        };
+        assert_eq!(aaf.format(), Some(AafFormat::Int16));
+        assert_eq!(
+            (aaf.sample_rate(), aaf.channels_per_frame()),
+            (Some(48_000), 2)
+        );
+        assert_eq!(stream.data.len(), 8);
+        assert_eq!(
+            stream.presentation_time(3 << 32 | 0x0fff),
+            Some(3 << 32 | 0x1000)
+        );
+
+        // Timestamp not valid, data longer than the PDU.
+        pdu[1] = 0x80;
+        assert_eq!(AvtpHdr::parse_stream(&pdu).unwrap().avtp_timestamp, None);
+        pdu[21] = 9;
+        assert!(AvtpHdr::parse_stream(&pdu).is_none());
+    }
+}
@@ -0,0 +1,382 @@
+//! Babel routing protocol ([RFC 8966](https://datatracker.ietf.org/doc/html/rfc8966)),
+//! carried over UDP port 6696.
+
+use core::{
+    mem,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+};
+
+use crate::{
+    header::{impl_header, Header, Limited, ParseConfig},
+    types::U16,
+};
+
+pub const BABEL_PORT: u16 = 6696;
+pub const BABEL_MAGIC: u8 = 42;
+pub const BABEL_VERSION: u8 = 2;
+
+/// Babel packet header, followed by `body_len` bytes of TLVs and an optional
+/// trailer.
+/// ```text
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |   Magic = 42  |  Version = 2  |          Body length          |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct BabelHdr {
+    pub magic: u8,
+    pub version: u8,
+    pub body_len: U16,
+}
+
+impl BabelHdr {
+    pub const LEN: usize = mem::size_of::<BabelHdr>();
+
+    /// Parses the Babel packet stored in `packet`, returning its header and
+    /// an iterator over the TLVs of its body.
+    pub fn parse(packet: &[u8]) -> Option<(&BabelHdr, BabelTlvs<'_>)> {
+        let hdr = BabelHdr::from_bytes(packet)?;
+        if hdr.magic != BABEL_MAGIC || hdr.version != BABEL_VERSION {
+            return None;
+        }
+        let body = packet.get(Self::LEN..Self::LEN + hdr.body_len.to_bits() as usize)?;
+        Some((hdr, BabelTlvs { data: body }))
+    }
+}
+
+/// Babel TLV types.
+#[repr(u8)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum BabelTlvType {
+    Pad1 = 0,
+    PadN = 1,
+    AckReq = 2,
+    Ack = 3,
+    Hello = 4,
+    Ihu = 5,
+    RouterId = 6,
+    NextHop = 7,
+    Update = 8,
+    RouteRequest = 9,
+    SeqnoRequest = 10,
+}
+
+impl TryFrom<u8> for BabelTlvType {
+    type Error = ();
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(BabelTlvType::Pad1),
+            1 => Ok(BabelTlvType::PadN),
+            2 => Ok(BabelTlvType::AckReq),
+            3 => Ok(BabelTlvType::Ack),
+            4 => Ok(BabelTlvType::Hello),
+            5 => Ok(BabelTlvType::Ihu),
+            6 => Ok(BabelTlvType::RouterId),
+            7 => Ok(BabelTlvType::NextHop),
+            8 => Ok(BabelTlvType::Update),
+            9 => Ok(BabelTlvType::RouteRequest),
+            10 => Ok(BabelTlvType::SeqnoRequest),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Fixed part of a Hello TLV body, followed by sub-TLVs.
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct BabelHelloHdr {
+    /// Bit 0 (the U bit) marks unicast Hellos.
+    pub flags: U16,
+    pub seqno: U16,
+    /// Upper bound on the time before the next Hello, in centiseconds.
+    pub interval: U16,
+}
+
+impl BabelHelloHdr {
+    pub const LEN: usize = mem::size_of::<BabelHelloHdr>();
+
+    #[inline]
+    pub const fn unicast(&self) -> bool {
+        self.flags.to_bits() & 0x8000 != 0
+    }
+}
+
+/// Fixed part of an IHU ("I Heard You") TLV body, followed by the address of
+/// the neighbour.
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct BabelIhuHdr {
+    /// Address encoding of the address, see [`decode_address`].
+    pub ae: u8,
+    pub _reserved: u8,
+    /// Link cost in the direction from the neighbour to the sender.
+    pub rxcost: U16,
+    /// Upper bound on the time before the next IHU, in centiseconds.
+    pub interval: U16,
+}
+
+impl BabelIhuHdr {
+    pub const LEN: usize = mem::size_of::<BabelIhuHdr>();
+}
+
+/// Fixed part of an Update TLV body, followed by the (compressed) prefix.
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct BabelUpdateHdr {
+    /// Address encoding of the prefix, see [`decode_address`].
+    pub ae: u8,
+    /// 0x80: set the default prefix; 0x40: set the default Router-Id.
+    pub flags: u8,
+    /// Length of the advertised prefix, in bits.
+    pub plen: u8,
+    /// Number of leading prefix octets omitted and taken from the previous
+    /// Update with the same address encoding.
+    pub omitted: u8,
+    pub interval: U16,
+    pub seqno: U16,
+    /// Sender's metric for the prefix, 0xFFFF for a retraction.
+    pub metric: U16,
+}
+
+impl BabelUpdateHdr {
+    pub const LEN: usize = mem::size_of::<BabelUpdateHdr>();
+
+    #[inline]
+    pub const fn is_retraction(&self) -> bool {
+        self.metric.to_bits() == 0xffff
+    }
+}
+
+/// Fixed part of a Route Request TLV body, followed by the prefix.
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct BabelRouteRequestHdr {
+    /// Address encoding of the prefix, 0 requests a full routing table dump.
+    pub ae: u8,
+    pub plen: u8,
+}
+
+impl BabelRouteRequestHdr {
+    pub const LEN: usize = mem::size_of::<BabelRouteRequestHdr>();
+}
+
+impl_header!(
+    BabelHdr,
+    BabelHelloHdr,
+    BabelIhuHdr,
+    BabelUpdateHdr,
+    BabelRouteRequestHdr
+);
+
+/// A TLV of a Babel packet body.
+#[derive(Debug, Copy, Clone)]
+pub struct BabelTlv<'a> {
+    pub tlv_type: u8,
+    /// Body of the TLV, without the type and length octets.
+    pub body: &'a [u8],
+}
+
+impl<'a> BabelTlv<'a> {
+    #[inline]
+    pub fn kind(&self) -> Option<BabelTlvType> {
+        self.tlv_type.try_into().ok()
+    }
+
+    /// Hello header and sub-TLVs.
+    pub fn hello(&self) -> Option<(&'a BabelHelloHdr, &'a [u8])> {
+        self.fixed(BabelTlvType::Hello)
+    }
+
+    /// IHU header and the neighbour address.
+    pub fn ihu(&self) -> Option<(&'a BabelIhuHdr, Option<IpAddr>)> {
+        let (hdr, rest) = self.fixed::<BabelIhuHdr>(BabelTlvType::Ihu)?;
+        Some((hdr, decode_address(hdr.ae, rest)))
+    }
+
+    /// Update header and the prefix octets which were not omitted.
+    pub fn update(&self) -> Option<(&'a BabelUpdateHdr, &'a [u8])> {
+        let (hdr, rest) = self.fixed::<BabelUpdateHdr>(BabelTlvType::Update)?;
+        let len = (hdr.plen as usize)
+            .div_ceil(8)
+            .saturating_sub(hdr.omitted as usize);
+        Some((hdr, rest.get(..len)?))
+    }
+
+    /// Route Request header and the requested prefix octets.
+    pub fn route_request(&self) -> Option<(&'a BabelRouteRequestHdr, &'a [u8])> {
+        let (hdr, rest) = self.fixed::<BabelRouteRequestHdr>(BabelTlvType::RouteRequest)?;
+        Some((hdr, rest.get(..(hdr.plen as usize).div_ceil(8))?))
+    }
+
+    fn fixed<H: Header>(&self, kind: BabelTlvType) -> Option<(&'a H, &'a [u8])> {
+        if self.tlv_type != kind as u8 {
+            return None;
+        }
+        let hdr = H::from_bytes(self.body)?;
+        Some((hdr, &self.body[mem::size_of::<H>()..]))
+    }
+}
+
+/// Iterator over the TLVs of a Babel packet body.
+#[derive(Debug, Clone)]
+pub struct BabelTlvs<'a> {
+    data: &'a [u8],
+}
+
+impl BabelTlvs<'_> {
+    /// Bounds the walk to [`ParseConfig::max_options`] TLVs.
+    #[inline]
+    pub fn limit(self, config: &ParseConfig) -> Limited<Self> {
+        Limited::new(self, config.max_options)
+    }
+}
+
+impl<'a> Iterator for BabelTlvs<'a> {
+    type Item = BabelTlv<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (&tlv_type, rest) = self.data.split_first()?;
+        if tlv_type == BabelTlvType::Pad1 as u8 {
+            self.data = rest;
+            return Some(BabelTlv {
+                tlv_type,
+                body: &[],
+            });
+        }
+        let Some((&len, rest)) = rest.split_first() else {
+            self.data = &[];
+            return None;
+        };
+        let Some(body) = rest.get(..len as usize) else {
+            self.data = &[];
+            return None;
+        };
+        self.data = &rest[len as usize..];
+        Some(BabelTlv { tlv_type, body })
+    }
+}
+
+/// Decodes an address stored with the Babel address encoding `ae`.
+///
+/// Returns `None` for the wildcard encoding (0) and unknown encodings.
+pub fn decode_address(ae: u8, bytes: &[u8]) -> Option<IpAddr> {
+    match ae {
+        1 => {
+            let a: [u8; 4] = bytes.get(..4)?.try_into().ok()?;
+            Some(IpAddr::V4(Ipv4Addr::from(a)))
+        }
+        2 => {
+            let a: [u8; 16] = bytes.get(..16)?.try_into().ok()?;
+            Some(IpAddr::V6(Ipv6Addr::from(a)))
+        }
+        3 => {
+            // Link-local IPv6 address, only the interface identifier is sent.
+            let mut a = [0u8; 16];
+            a[0] = 0xfe;
+            a[1] = 0x80;
+            a[8..].copy_from_slice(bytes.get(..8)?);
+            Some(IpAddr::V6(Ipv6Addr::from(a)))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::net::{IpAddr, Ipv6Addr};
+
+    use super::{BabelHdr, BabelTlv, BabelTlvType};
+    use crate::header::{ParseConfig, ParseError};
+
+    #[test]
+    fn test_babel_tlvs() {
+        let packet = [
+            42, 2, 0, 25, // header
+            4, 6, 0, 0, 0, 7, 1, 144, // Hello, seqno 7, interval 4s
+            0,   // Pad1
+            5, 14, 3, 0, 0, 96, 1, 144, 0, 0, 0, 0, 0, 0, 0, 1, // IHU, link-local
+        ];
+        let (hdr, mut tlvs) = BabelHdr::parse(&packet).unwrap();
+        assert_eq!(hdr.body_len.to_bits(), 25);
+
+        let (hello, sub_tlvs) = tlvs.next().unwrap().hello().unwrap();
+        assert_eq!(hello.seqno.to_bits(), 7);
+        assert_eq!(hello.interval.to_bits(), 400);
+        assert!(sub_tlvs.is_empty());
+
+        assert_eq!(tlvs.next().unwrap().kind(), Some(BabelTlvType::Pad1));
+
+        let (ihu, addr) = tlvs.next().unwrap().ihu().unwrap();
+        assert_eq!(ihu.rxcost.to_bits(), 96);
+        assert_eq!(
+            addr,
+            Some(IpAddr::V6(Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1)))
+        );
+        assert!(tlvs.next().is_none());
+
+        let one = ParseConfig {
+            max_options: 1,
+            ..ParseConfig::DEFAULT
+        };
+        let (_, tlvs) = BabelHdr::parse(&packet).unwrap();
+        let kinds = tlvs.limit(&one).map(|tlv| tlv.map(|tlv| tlv.kind()));
+        assert!(kinds.eq([
+            Ok(Some(BabelTlvType::Hello)),
+            Err(ParseError::LimitExceeded)
+        ]));
+    }
+
+    #[test]
+    fn test_babel_update_and_route_request() {
+        let update = |body| BabelTlv {
+            tlv_type: BabelTlvType::Update as u8,
+            body,
+        };
+        // 192.0.2.0/24 with 2 octets omitted, followed by sub-TLVs.
+        let body = [1, 0, 24, 2, 1, 144, 0, 3, 0, 96, 2, 0, 0];
+        let (hdr, prefix) = update(&body).update().unwrap();
+        assert_eq!((hdr.ae, hdr.plen, hdr.omitted), (1, 24, 2));
+        assert_eq!(hdr.metric.to_bits(), 96);
+        assert!(!hdr.is_retraction());
+        assert_eq!(prefix, [2]);
+
+        // 2001:db8::/35 spans 5 octets, the last one partially.
+        let body = [
+            2, 0, 35, 0, 1, 144, 0, 3, 0xff, 0xff, 0x20, 0x01, 0x0d, 0xb8, 0,
+        ];
+        let (hdr, prefix) = update(&body).update().unwrap();
+        assert!(hdr.is_retraction());
+        assert_eq!(prefix, [0x20, 0x01, 0x0d, 0xb8, 0]);
+        assert!(update(&body[..14]).update().is_none());
+
+        let route_request = |body| BabelTlv {
+            tlv_type: BabelTlvType::RouteRequest as u8,
+            body,
+        };
+        // Wildcard, IPv4, IPv6 and link-local IPv6 prefixes.
+        let requests: [(&[u8], &[u8]); 4] = [
+            (&[0, 0], &[]),
+            (&[1, 9, 10, 128], &[10, 128]),
+            (&[2, 16, 0x20, 0x01], &[0x20, 0x01]),
+            (&[3, 64, 0, 0, 0, 0, 0, 0, 0, 1], &[0, 0, 0, 0, 0, 0, 0, 1]),
+        ];
+        for (ae, (body, prefix)) in requests.into_iter().enumerate() {
+            let (hdr, bytes) = route_request(body).route_request().unwrap();
+            assert_eq!(hdr.ae as usize, ae);
+            assert_eq!(bytes, prefix);
+            assert!(route_request(&body[..body.len() - 1])
+                .route_request()
+                .is_none());
+        }
+        assert!(update(&[0, 0]).route_request().is_none());
+    }
+}
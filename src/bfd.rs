@@ -0,0 +1,283 @@
+//! Bidirectional Forwarding Detection ([RFC 5880](https://datatracker.ietf.org/doc/html/rfc5880)),
+//! carried over UDP ([RFC 5881](https://datatracker.ietf.org/doc/html/rfc5881),
+//! [RFC 5883](https://datatracker.ietf.org/doc/html/rfc5883)).
+
+use core::mem;
+
+use crate::{bitfield::BitfieldUnit, header::impl_header, types::U32};
+
+/// UDP destination port of single-hop BFD control packets.
+pub const BFD_CONTROL_PORT: u16 = 3784;
+/// UDP destination port of BFD echo packets.
+pub const BFD_ECHO_PORT: u16 = 3785;
+/// UDP destination port of multihop BFD control packets.
+pub const BFD_MULTIHOP_CONTROL_PORT: u16 = 4784;
+
+/// Reason for the last change in the local session state.
+#[repr(u8)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum BfdDiag {
+    NoDiagnostic = 0,
+    ControlDetectionTimeExpired = 1,
+    EchoFunctionFailed = 2,
+    NeighborSignaledSessionDown = 3,
+    ForwardingPlaneReset = 4,
+    PathDown = 5,
+    ConcatenatedPathDown = 6,
+    AdministrativelyDown = 7,
+    ReverseConcatenatedPathDown = 8,
+}
+
+impl TryFrom<u8> for BfdDiag {
+    type Error = ();
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(BfdDiag::NoDiagnostic),
+            1 => Ok(BfdDiag::ControlDetectionTimeExpired),
+            2 => Ok(BfdDiag::EchoFunctionFailed),
+            3 => Ok(BfdDiag::NeighborSignaledSessionDown),
+            4 => Ok(BfdDiag::ForwardingPlaneReset),
+            5 => Ok(BfdDiag::PathDown),
+            6 => Ok(BfdDiag::ConcatenatedPathDown),
+            7 => Ok(BfdDiag::AdministrativelyDown),
+            8 => Ok(BfdDiag::ReverseConcatenatedPathDown),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Session state, as seen by the transmitting system.
+#[repr(u8)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum BfdState {
+    AdminDown = 0,
+    Down = 1,
+    Init = 2,
+    Up = 3,
+}
+
+/// BFD control packet, mandatory section.
+///
+/// ```text
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |Vers |  Diag   |Sta|P|F|C|A|D|M|  Detect Mult  |    Length     |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                       My Discriminator                        |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                      Your Discriminator                       |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                    Desired Min TX Interval                    |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                   Required Min RX Interval                    |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                 Required Min Echo RX Interval                 |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+/// An optional authentication section follows when the A bit is set.
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct BfdControlHdr {
+    /// **Vers** (3 bits) and **Diag** (5 bits).
+    pub vers_diag: BitfieldUnit<[u8; 1usize]>,
+    /// **Sta** (2 bits) followed by the P, F, C, A, D and M flags.
+    pub state_flags: BitfieldUnit<[u8; 1usize]>,
+    /// Detection time multiplier.
+    pub detect_mult: u8,
+    /// Length of the whole control packet, in bytes.
+    pub length: u8,
+    pub my_discriminator: U32,
+    pub your_discriminator: U32,
+    /// Desired minimum TX interval, in microseconds.
+    pub desired_min_tx_interval: U32,
+    /// Required minimum RX interval, in microseconds.
+    pub required_min_rx_interval: U32,
+    /// Required minimum echo RX interval, in microseconds.
+    pub required_min_echo_rx_interval: U32,
+}
+
+impl BfdControlHdr {
+    pub const LEN: usize = mem::size_of::<BfdControlHdr>();
+
+    #[inline]
+    pub const fn version(&self) -> u8 {
+        self.vers_diag.get(5, 3) as u8
+    }
+
+    #[inline]
+    pub const fn set_version(&mut self, val: u8) {
+        self.vers_diag.set(5, 3, val as u64)
+    }
+
+    #[inline]
+    pub fn diag(&self) -> Option<BfdDiag> {
+        (self.vers_diag.get(0, 5) as u8).try_into().ok()
+    }
+
+    #[inline]
+    pub const fn set_diag(&mut self, val: BfdDiag) {
+        self.vers_diag.set(0, 5, val as u64)
+    }
+
+    #[inline]
+    pub const fn state(&self) -> BfdState {
+        match self.state_flags.get(6, 2) {
+            0 => BfdState::AdminDown,
+            1 => BfdState::Down,
+            2 => BfdState::Init,
+            _ => BfdState::Up,
+        }
+    }
+
+    #[inline]
+    pub const fn set_state(&mut self, val: BfdState) {
+        self.state_flags.set(6, 2, val as u64)
+    }
+
+    /// **Poll**: the transmitting system is requesting verification of
+    /// connectivity or of a parameter change.
+    #[inline]
+    pub const fn poll(&self) -> bool {
+        self.state_flags.get_bit(5)
+    }
+
+    #[inline]
+    pub const fn set_poll(&mut self, val: bool) {
+        self.state_flags.set_bit(5, val)
+    }
+
+    /// **Final**: the packet responds to a received packet with Poll set.
+    #[inline]
+    pub const fn is_final(&self) -> bool {
+        self.state_flags.get_bit(4)
+    }
+
+    #[inline]
+    pub const fn set_final(&mut self, val: bool) {
+        self.state_flags.set_bit(4, val)
+    }
+
+    /// **Control Plane Independent**
+    #[inline]
+    pub const fn control_plane_independent(&self) -> bool {
+        self.state_flags.get_bit(3)
+    }
+
+    #[inline]
+    pub const fn set_control_plane_independent(&mut self, val: bool) {
+        self.state_flags.set_bit(3, val)
+    }
+
+    /// **Authentication Present**
+    #[inline]
+    pub const fn auth_present(&self) -> bool {
+        self.state_flags.get_bit(2)
+    }
+
+    #[inline]
+    pub const fn set_auth_present(&mut self, val: bool) {
+        self.state_flags.set_bit(2, val)
+    }
+
+    /// **Demand**: demand mode is active in the transmitting system.
+    #[inline]
+    pub const fn demand(&self) -> bool {
+        self.state_flags.get_bit(1)
+    }
+
+    #[inline]
+    pub const fn set_demand(&mut self, val: bool) {
+        self.state_flags.set_bit(1, val)
+    }
+
+    /// **Multipoint**, reserved for future point-to-multipoint extensions.
+    #[inline]
+    pub const fn multipoint(&self) -> bool {
+        self.state_flags.get_bit(0)
+    }
+
+    #[inline]
+    pub const fn my_discriminator(&self) -> u32 {
+        self.my_discriminator.to_bits()
+    }
+
+    #[inline]
+    pub const fn your_discriminator(&self) -> u32 {
+        self.your_discriminator.to_bits()
+    }
+
+    #[inline]
+    pub const fn desired_min_tx_interval(&self) -> u32 {
+        self.desired_min_tx_interval.to_bits()
+    }
+
+    #[inline]
+    pub const fn required_min_rx_interval(&self) -> u32 {
+        self.required_min_rx_interval.to_bits()
+    }
+
+    #[inline]
+    pub const fn required_min_echo_rx_interval(&self) -> u32 {
+        self.required_min_echo_rx_interval.to_bits()
+    }
+
+    /// Authentication section of the control packet stored in `packet`,
+    /// which starts with this header.
+    pub fn auth_section<'a>(&self, packet: &'a [u8]) -> Option<&'a [u8]> {
+        if !self.auth_present() {
+            return None;
+        }
+        packet.get(Self::LEN..self.length as usize)
+    }
+}
+
+impl_header!(BfdControlHdr);
+
+#[cfg(test)]
+mod tests {
+    use super::{BfdControlHdr, BfdDiag, BfdState};
+    use crate::header::Header;
+
+    #[test]
+    fn test_bfd_control() {
+        #[rustfmt::skip]
+        let mut packet = [
+            // Version 1, neighbor signaled session down, Up, Poll and
+            // Authentication Present, detect multiplier 3.
+            0x23, 0xe4, 3, 28,
+            0, 0, 0, 1,
+            0, 0, 0, 2,
+            0, 0x0f, 0x42, 0x40,
+            0, 0x07, 0xa1, 0x20,
+            0, 0, 0, 0,
+            // Simple password authentication.
+            1, 4, 1, b'x',
+        ];
+        let hdr = BfdControlHdr::from_bytes(&packet).unwrap();
+        assert_eq!(hdr.version(), 1);
+        assert_eq!(hdr.diag(), Some(BfdDiag::NeighborSignaledSessionDown));
+        assert_eq!(hdr.state(), BfdState::Up);
+        assert!(hdr.poll() && hdr.auth_present());
+        assert!(!hdr.is_final() && !hdr.control_plane_independent());
+        assert!(!hdr.demand() && !hdr.multipoint());
+        assert_eq!((hdr.detect_mult, hdr.length), (3, 28));
+        assert_eq!((hdr.my_discriminator(), hdr.your_discriminator()), (1, 2));
+        assert_eq!(hdr.desired_min_tx_interval(), 1_000_000);
+        assert_eq!(hdr.required_min_rx_interval(), 500_000);
+        assert_eq!(hdr.required_min_echo_rx_interval(), 0);
+        assert_eq!(hdr.auth_section(&packet), Some(&[1, 4, 1, b'x'][..]));
+
+        let hdr = BfdControlHdr::from_bytes_mut(&mut packet).unwrap();
+        hdr.set_state(BfdState::Down);
+        hdr.set_diag(BfdDiag::ControlDetectionTimeExpired);
+        hdr.set_poll(false);
+        hdr.set_final(true);
+        hdr.set_auth_present(false);
+        assert_eq!(hdr.auth_section(&[]), None);
+        assert_eq!(&packet[..2], &[0x21, 0x50]);
+    }
+}
@@ -16,16 +16,13 @@ impl<Storage> BitfieldUnit<Storage> {
     }
 }
 
-impl<Storage> BitfieldUnit<Storage>
-where
-    Storage: AsRef<[u8]> + AsMut<[u8]>,
-{
+impl<const N: usize> BitfieldUnit<[u8; N]> {
     #[inline]
-    pub fn get_bit(&self, index: usize) -> bool {
-        debug_assert!(index / 8 < self.storage.as_ref().len());
+    pub const fn get_bit(&self, index: usize) -> bool {
+        debug_assert!(index / 8 < N);
 
         let byte_index = index / 8;
-        let byte = self.storage.as_ref()[byte_index];
+        let byte = self.storage[byte_index];
 
         let bit_index = if cfg!(target_endian = "big") {
             7 - (index % 8)
@@ -39,11 +36,11 @@ where
     }
 
     #[inline]
-    pub fn set_bit(&mut self, index: usize, val: bool) {
-        debug_assert!(index / 8 < self.storage.as_ref().len());
+    pub const fn set_bit(&mut self, index: usize, val: bool) {
+        debug_assert!(index / 8 < N);
 
         let byte_index = index / 8;
-        let byte = &mut self.storage.as_mut()[byte_index];
+        let byte = &mut self.storage[byte_index];
 
         let bit_index = if cfg!(target_endian = "big") {
             7 - (index % 8)
@@ -60,17 +57,15 @@ where
     }
 
     #[inline]
-    pub fn get(&self, bit_offset: usize, bit_width: u8) -> u64 {
+    pub const fn get(&self, bit_offset: usize, bit_width: u8) -> u64 {
         debug_assert!(bit_width <= 64);
-        debug_assert!(bit_offset / 8 < self.storage.as_ref().len());
-        debug_assert!(
-            (bit_offset + (bit_width as usize)) / 8 <=
-                self.storage.as_ref().len()
-        );
+        debug_assert!(bit_offset / 8 < N);
+        debug_assert!((bit_offset + (bit_width as usize)) / 8 <= N);
 
         let mut val = 0;
 
-        for i in 0..(bit_width as usize) {
+        let mut i = 0;
+        while i < bit_width as usize {
             if self.get_bit(i + bit_offset) {
                 let index = if cfg!(target_endian = "big") {
                     bit_width as usize - 1 - i
@@ -79,21 +74,20 @@ where
                 };
                 val |= 1 << index;
             }
+            i += 1;
         }
 
         val
     }
 
     #[inline]
-    pub fn set(&mut self, bit_offset: usize, bit_width: u8, val: u64) {
+    pub const fn set(&mut self, bit_offset: usize, bit_width: u8, val: u64) {
         debug_assert!(bit_width <= 64);
-        debug_assert!(bit_offset / 8 < self.storage.as_ref().len());
-        debug_assert!(
-            (bit_offset + (bit_width as usize)) / 8 <=
-                self.storage.as_ref().len()
-        );
+        debug_assert!(bit_offset / 8 < N);
+        debug_assert!((bit_offset + (bit_width as usize)) / 8 <= N);
 
-        for i in 0..(bit_width as usize) {
+        let mut i = 0;
+        while i < bit_width as usize {
             let mask = 1 << i;
             let val_bit_is_set = val & mask == mask;
             let index = if cfg!(target_endian = "big") {
@@ -102,6 +96,7 @@ where
                 i
             };
             self.set_bit(index + bit_offset, val_bit_is_set);
+            i += 1;
         }
     }
 }
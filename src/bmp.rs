@@ -0,0 +1,341 @@
+//! BGP Monitoring Protocol ([RFC 7854](https://datatracker.ietf.org/doc/html/rfc7854)),
+//! carried over TCP.
+//!
+//! The BGP PDUs encapsulated in BMP messages are handed out as raw byte
+//! slices starting with the BGP message header.
+
+use core::{
+    mem,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+};
+
+use crate::{
+    header::{impl_header, Header},
+    types::{U16, U32, U64},
+};
+
+pub const BMP_VERSION: u8 = 3;
+
+/// BMP message types.
+#[repr(u8)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum BmpMsgType {
+    RouteMonitoring = 0,
+    StatisticsReport = 1,
+    PeerDown = 2,
+    PeerUp = 3,
+    Initiation = 4,
+    Termination = 5,
+    RouteMirroring = 6,
+}
+
+impl TryFrom<u8> for BmpMsgType {
+    type Error = ();
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(BmpMsgType::RouteMonitoring),
+            1 => Ok(BmpMsgType::StatisticsReport),
+            2 => Ok(BmpMsgType::PeerDown),
+            3 => Ok(BmpMsgType::PeerUp),
+            4 => Ok(BmpMsgType::Initiation),
+            5 => Ok(BmpMsgType::Termination),
+            6 => Ok(BmpMsgType::RouteMirroring),
+            _ => Err(()),
+        }
+    }
+}
+
+/// BMP common header.
+/// ```text
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+
+/// |    Version    |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                        Message Length                         |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |   Msg. Type   |
+/// +---------------+
+/// ```
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct BmpHdr {
+    pub version: u8,
+    /// Length of the whole message, including this header.
+    pub length: U32,
+    /// See [`BmpMsgType`].
+    pub msg_type: u8,
+}
+
+impl BmpHdr {
+    pub const LEN: usize = mem::size_of::<BmpHdr>();
+
+    #[inline]
+    pub fn msg_type(&self) -> Option<BmpMsgType> {
+        self.msg_type.try_into().ok()
+    }
+}
+
+/// Per-peer header, present in all messages except Initiation and
+/// Termination.
+/// ```text
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |   Peer Type   |  Peer Flags   |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |         Peer Distinguisher (present based on peer type)       |
+/// |                                                               |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                 Peer Address (16 bytes)                       |
+/// ~                                                               ~
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                           Peer AS                             |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                         Peer BGP ID                           |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                    Timestamp (seconds)                        |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                  Timestamp (microseconds)                     |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct BmpPeerHdr {
+    /// 0: global instance, 1: RD instance, 2: local instance.
+    pub peer_type: u8,
+    pub peer_flags: u8,
+    pub peer_distinguisher: U64,
+    /// IPv6 address, or IPv4 address in the last 4 octets.
+    pub peer_addr: [u8; 16],
+    pub peer_as: U32,
+    pub peer_bgp_id: Ipv4Addr,
+    pub timestamp_sec: U32,
+    pub timestamp_usec: U32,
+}
+
+impl BmpPeerHdr {
+    pub const LEN: usize = mem::size_of::<BmpPeerHdr>();
+
+    /// **V flag**: the peer address is an IPv6 address.
+    #[inline]
+    pub const fn ipv6(&self) -> bool {
+        self.peer_flags & 0x80 != 0
+    }
+
+    /// **L flag**: the routes are post-policy Adj-RIB-In.
+    #[inline]
+    pub const fn post_policy(&self) -> bool {
+        self.peer_flags & 0x40 != 0
+    }
+
+    /// **A flag**: the BGP messages use the legacy 2-byte AS_PATH format.
+    #[inline]
+    pub const fn legacy_as_path(&self) -> bool {
+        self.peer_flags & 0x20 != 0
+    }
+
+    #[inline]
+    pub fn peer_addr(&self) -> IpAddr {
+        let addr = self.peer_addr;
+        if self.ipv6() {
+            IpAddr::V6(Ipv6Addr::from(addr))
+        } else {
+            IpAddr::V4(Ipv4Addr::new(addr[12], addr[13], addr[14], addr[15]))
+        }
+    }
+
+    #[inline]
+    pub const fn peer_as(&self) -> u32 {
+        self.peer_as.to_bits()
+    }
+}
+
+/// Statistics TLV header of a Statistics Report message.
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct BmpStatHdr {
+    pub stat_type: U16,
+    /// Length of the statistic value, in octets.
+    pub stat_len: U16,
+}
+
+impl BmpStatHdr {
+    pub const LEN: usize = mem::size_of::<BmpStatHdr>();
+}
+
+impl_header!(BmpHdr, BmpPeerHdr, BmpStatHdr);
+
+/// A BMP message.
+#[derive(Debug, Copy, Clone)]
+pub struct BmpMessage<'a> {
+    pub hdr: &'a BmpHdr,
+    /// Message body, without the common header.
+    pub body: &'a [u8],
+}
+
+impl<'a> BmpMessage<'a> {
+    /// Per-peer header and the encapsulated BGP UPDATE message of a Route
+    /// Monitoring message.
+    pub fn route_monitoring(&self) -> Option<(&'a BmpPeerHdr, &'a [u8])> {
+        let (peer, bgp) = self.per_peer(BmpMsgType::RouteMonitoring)?;
+        // BGP header: 16 octet marker, length and type (2 for UPDATE).
+        if bgp.len() < 19 || bgp[..16] != [0xff; 16] || bgp[18] != 2 {
+            return None;
+        }
+        Some((peer, bgp))
+    }
+
+    /// Per-peer header and the statistics of a Statistics Report message.
+    pub fn stats_report(&self) -> Option<(&'a BmpPeerHdr, BmpStats<'a>)> {
+        let (peer, body) = self.per_peer(BmpMsgType::StatisticsReport)?;
+        let count = u32::from_be_bytes(body.get(..4)?.try_into().ok()?);
+        Some((
+            peer,
+            BmpStats {
+                count,
+                data: &body[4..],
+            },
+        ))
+    }
+
+    fn per_peer(&self, kind: BmpMsgType) -> Option<(&'a BmpPeerHdr, &'a [u8])> {
+        if self.hdr.msg_type != kind as u8 {
+            return None;
+        }
+        let peer = BmpPeerHdr::from_bytes(self.body)?;
+        Some((peer, &self.body[BmpPeerHdr::LEN..]))
+    }
+}
+
+/// Iterator over the BMP messages stored back to back in a TCP stream
+/// segment. Iteration stops at the first incomplete message.
+#[derive(Debug, Clone)]
+pub struct BmpMessages<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> BmpMessages<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    /// Bytes which have not been consumed yet, e.g. the start of a message
+    /// continued in the next segment.
+    pub fn remainder(&self) -> &'a [u8] {
+        self.data
+    }
+}
+
+impl<'a> Iterator for BmpMessages<'a> {
+    type Item = BmpMessage<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let hdr = BmpHdr::from_bytes(self.data)?;
+        let len = hdr.length.to_bits() as usize;
+        if hdr.version != BMP_VERSION || len < BmpHdr::LEN || len > self.data.len() {
+            return None;
+        }
+        let body = &self.data[BmpHdr::LEN..len];
+        self.data = &self.data[len..];
+        Some(BmpMessage { hdr, body })
+    }
+}
+
+/// A statistic of a Statistics Report message.
+#[derive(Debug, Copy, Clone)]
+pub struct BmpStat<'a> {
+    pub hdr: &'a BmpStatHdr,
+    pub data: &'a [u8],
+}
+
+impl BmpStat<'_> {
+    #[inline]
+    pub const fn stat_type(&self) -> u16 {
+        self.hdr.stat_type.to_bits()
+    }
+
+    /// Value of a 32-bit counter or 64-bit gauge statistic.
+    pub fn value(&self) -> Option<u64> {
+        match *self.data {
+            [a, b, c, d] => Some(u32::from_be_bytes([a, b, c, d]) as u64),
+            [a, b, c, d, e, f, g, h] => Some(u64::from_be_bytes([a, b, c, d, e, f, g, h])),
+            _ => None,
+        }
+    }
+}
+
+/// Iterator over the statistics of a Statistics Report message.
+#[derive(Debug, Clone)]
+pub struct BmpStats<'a> {
+    /// Number of statistics announced by the message.
+    pub count: u32,
+    data: &'a [u8],
+}
+
+impl<'a> Iterator for BmpStats<'a> {
+    type Item = BmpStat<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let hdr = BmpStatHdr::from_bytes(self.data)?;
+        let end = BmpStatHdr::LEN + hdr.stat_len.to_bits() as usize;
+        let Some(data) = self.data.get(BmpStatHdr::LEN..end) else {
+            self.data = &[];
+            return None;
+        };
+        self.data = &self.data[end..];
+        Some(BmpStat { hdr, data })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::net::{IpAddr, Ipv4Addr};
+
+    use super::{BmpHdr, BmpMessages, BmpMsgType, BmpPeerHdr};
+
+    fn message(msg_type: u8, body: &[u8], out: &mut [u8]) -> usize {
+        let mut peer = [0u8; BmpPeerHdr::LEN];
+        peer[22..26].copy_from_slice(&[192, 0, 2, 1]);
+        peer[26..30].copy_from_slice(&65001u32.to_be_bytes());
+        let len = BmpHdr::LEN + peer.len() + body.len();
+        out[0] = 3;
+        out[1..5].copy_from_slice(&(len as u32).to_be_bytes());
+        out[5] = msg_type;
+        out[6..6 + peer.len()].copy_from_slice(&peer);
+        out[6 + peer.len()..len].copy_from_slice(body);
+        len
+    }
+
+    #[test]
+    fn test_bmp_messages() {
+        let mut stream = [0u8; 256];
+        let mut update = [0xffu8; 23];
+        update[16..].copy_from_slice(&[0, 23, 2, 0, 0, 0, 0]);
+        let mut len = message(0, &update, &mut stream);
+        let stats = [0, 0, 0, 1, 0, 7, 0, 8, 0, 0, 0, 0, 0, 0, 0, 42];
+        len += message(1, &stats, &mut stream[len..]);
+
+        let mut msgs = BmpMessages::new(&stream[..len + 3]);
+        let msg = msgs.next().unwrap();
+        assert_eq!(msg.hdr.msg_type(), Some(BmpMsgType::RouteMonitoring));
+        let (peer, bgp) = msg.route_monitoring().unwrap();
+        assert_eq!(peer.peer_addr(), IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)));
+        assert_eq!(peer.peer_as(), 65001);
+        assert_eq!(bgp, &update);
+
+        let (_, mut stats) = msgs.next().unwrap().stats_report().unwrap();
+        assert_eq!(stats.count, 1);
+        let stat = stats.next().unwrap();
+        assert_eq!(stat.stat_type(), 7);
+        assert_eq!(stat.value(), Some(42));
+        assert!(stats.next().is_none());
+
+        assert!(msgs.next().is_none());
+        assert_eq!(msgs.remainder().len(), 3);
+    }
+}
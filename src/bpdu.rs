@@ -0,0 +1,267 @@
+//! Spanning tree bridge protocol data units (IEEE 802.1D/802.1Q), carried
+//! over 802.2 LLC, including the Cisco PVST+ encapsulation over SNAP.
+
+use core::mem;
+
+use crate::{
+    header::{impl_header, Header},
+    types::{U16, U32},
+};
+
+/// Destination MAC address of IEEE BPDUs.
+pub const STP_MAC: [u8; 6] = [0x01, 0x80, 0xc2, 0x00, 0x00, 0x00];
+/// Destination MAC address of Cisco PVST+ BPDUs.
+pub const PVST_MAC: [u8; 6] = [0x01, 0x00, 0x0c, 0xcc, 0xcc, 0xcd];
+/// LLC service access point of spanning tree BPDUs.
+pub const STP_SAP: u8 = 0x42;
+/// Cisco organizationally unique identifier used in the SNAP header.
+pub const CISCO_OUI: [u8; 3] = [0x00, 0x00, 0x0c];
+/// SNAP protocol identifier of PVST+ BPDUs.
+pub const PVST_PID: u16 = 0x010b;
+
+/// BPDU types.
+#[repr(u8)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum BpduType {
+    /// Configuration BPDU (STP).
+    Config = 0x00,
+    /// Rapid spanning tree BPDU (RSTP and MSTP).
+    Rst = 0x02,
+    /// Topology change notification BPDU.
+    Tcn = 0x80,
+}
+
+impl TryFrom<u8> for BpduType {
+    type Error = ();
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x00 => Ok(BpduType::Config),
+            0x02 => Ok(BpduType::Rst),
+            0x80 => Ok(BpduType::Tcn),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Port roles carried in the flags of RST BPDUs.
+#[repr(u8)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum PortRole {
+    Unknown = 0,
+    AlternateBackup = 1,
+    Root = 2,
+    Designated = 3,
+}
+
+/// Header common to all BPDUs, making up the whole TCN BPDU.
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct BpduHdr {
+    /// Always 0.
+    pub protocol_id: U16,
+    /// 0 for STP, 2 for RSTP and 3 for MSTP.
+    pub version: u8,
+    /// See [`BpduType`].
+    pub bpdu_type: u8,
+}
+
+impl BpduHdr {
+    pub const LEN: usize = mem::size_of::<BpduHdr>();
+
+    #[inline]
+    pub fn bpdu_type(&self) -> Option<BpduType> {
+        self.bpdu_type.try_into().ok()
+    }
+}
+
+/// Configuration and RST BPDU.
+/// ```text
+/// +----------------+---------+-----------+-------+
+/// | Protocol Id(2) | Ver (1) | Type (1)  | Flags |
+/// +----------------+---------+-----------+-------+
+/// | Root Identifier (8)                          |
+/// +----------------------------------------------+
+/// | Root Path Cost (4)                           |
+/// +----------------------------------------------+
+/// | Bridge Identifier (8)                        |
+/// +----------------------------------------------+
+/// | Port Id (2) | Message Age (2) | Max Age (2)  |
+/// +----------------------------------------------+
+/// | Hello Time (2) | Forward Delay (2)           |
+/// +----------------------------------------------+
+/// ```
+/// RST BPDUs are followed by the Version 1 Length octet. Timers are in
+/// units of 1/256 second.
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct ConfigBpdu {
+    pub hdr: BpduHdr,
+    pub flags: u8,
+    /// Priority (2 octets) and MAC address of the root bridge.
+    pub root_id: [u8; 8],
+    pub root_path_cost: U32,
+    /// Priority (2 octets) and MAC address of the transmitting bridge.
+    pub bridge_id: [u8; 8],
+    pub port_id: U16,
+    pub message_age: U16,
+    pub max_age: U16,
+    pub hello_time: U16,
+    pub forward_delay: U16,
+}
+
+impl ConfigBpdu {
+    pub const LEN: usize = mem::size_of::<ConfigBpdu>();
+
+    /// **Topology Change**
+    #[inline]
+    pub const fn topology_change(&self) -> bool {
+        self.flags & 0x01 != 0
+    }
+
+    /// **Proposal** (RSTP)
+    #[inline]
+    pub const fn proposal(&self) -> bool {
+        self.flags & 0x02 != 0
+    }
+
+    /// **Port Role** (RSTP)
+    #[inline]
+    pub const fn port_role(&self) -> PortRole {
+        match (self.flags >> 2) & 0x3 {
+            1 => PortRole::AlternateBackup,
+            2 => PortRole::Root,
+            3 => PortRole::Designated,
+            _ => PortRole::Unknown,
+        }
+    }
+
+    /// **Learning** (RSTP)
+    #[inline]
+    pub const fn learning(&self) -> bool {
+        self.flags & 0x10 != 0
+    }
+
+    /// **Forwarding** (RSTP)
+    #[inline]
+    pub const fn forwarding(&self) -> bool {
+        self.flags & 0x20 != 0
+    }
+
+    /// **Agreement** (RSTP)
+    #[inline]
+    pub const fn agreement(&self) -> bool {
+        self.flags & 0x40 != 0
+    }
+
+    /// **Topology Change Acknowledgment**
+    #[inline]
+    pub const fn tc_ack(&self) -> bool {
+        self.flags & 0x80 != 0
+    }
+
+    #[inline]
+    pub const fn root_priority(&self) -> u16 {
+        u16::from_be_bytes([self.root_id[0], self.root_id[1]])
+    }
+
+    #[inline]
+    pub const fn root_path_cost(&self) -> u32 {
+        self.root_path_cost.to_bits()
+    }
+
+    #[inline]
+    pub const fn bridge_priority(&self) -> u16 {
+        u16::from_be_bytes([self.bridge_id[0], self.bridge_id[1]])
+    }
+
+    #[inline]
+    pub const fn port_id(&self) -> u16 {
+        self.port_id.to_bits()
+    }
+}
+
+impl_header!(BpduHdr, ConfigBpdu);
+
+/// A spanning tree BPDU.
+#[derive(Debug, Copy, Clone)]
+pub enum Bpdu<'a> {
+    /// Configuration or RST BPDU.
+    Config(&'a ConfigBpdu),
+    /// Topology change notification.
+    Tcn(&'a BpduHdr),
+}
+
+impl<'a> Bpdu<'a> {
+    /// Parses the BPDU at the start of `bytes`.
+    pub fn parse(bytes: &'a [u8]) -> Option<Self> {
+        let hdr = BpduHdr::from_bytes(bytes)?;
+        match hdr.bpdu_type()? {
+            BpduType::Tcn => Some(Bpdu::Tcn(hdr)),
+            _ => ConfigBpdu::from_bytes(bytes).map(Bpdu::Config),
+        }
+    }
+
+    /// Parses the IEEE BPDU stored in `llc`, an 802.3 payload starting with
+    /// the LLC header.
+    pub fn parse_llc(llc: &'a [u8]) -> Option<Self> {
+        match llc {
+            [STP_SAP, STP_SAP, 0x03, bpdu @ ..] => Self::parse(bpdu),
+            _ => None,
+        }
+    }
+
+    /// Parses the Cisco PVST+ BPDU stored in `llc`, an 802.3 payload starting
+    /// with the LLC/SNAP header, returning the BPDU and the originating VLAN
+    /// carried in the trailing PVID TLV.
+    pub fn parse_pvst(llc: &'a [u8]) -> Option<(Self, Option<u16>)> {
+        let bpdu = match llc {
+            [0xaa, 0xaa, 0x03, 0x00, 0x00, 0x0c, 0x01, 0x0b, bpdu @ ..] => bpdu,
+            _ => return None,
+        };
+        let parsed = Self::parse(bpdu)?;
+        // The PVID TLV (type 0, length 2) follows the configuration BPDU,
+        // padded to the size of an RST BPDU.
+        let vlan = match bpdu.get(ConfigBpdu::LEN + 1..ConfigBpdu::LEN + 7) {
+            Some([0, 0, 0, 2, hi, lo]) => Some(u16::from_be_bytes([*hi, *lo]) & 0xfff),
+            _ => None,
+        };
+        Some((parsed, vlan))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Bpdu, ConfigBpdu, PortRole};
+
+    #[test]
+    fn test_pvst() {
+        let mut llc = [0u8; 8 + ConfigBpdu::LEN + 7];
+        llc[..8].copy_from_slice(&[0xaa, 0xaa, 0x03, 0x00, 0x00, 0x0c, 0x01, 0x0b]);
+        let bpdu = &mut llc[8..];
+        bpdu[2] = 2; // RSTP
+        bpdu[3] = 0x02; // RST BPDU
+        bpdu[4] = 0x3c; // designated, learning, forwarding
+        bpdu[5..7].copy_from_slice(&0x800au16.to_be_bytes());
+        bpdu[ConfigBpdu::LEN + 1..].copy_from_slice(&[0, 0, 0, 2, 0, 10]);
+
+        let (parsed, vlan) = Bpdu::parse_pvst(&llc).unwrap();
+        assert_eq!(vlan, Some(10));
+        match parsed {
+            Bpdu::Config(bpdu) => {
+                assert_eq!(bpdu.port_role(), PortRole::Designated);
+                assert!(bpdu.learning() && bpdu.forwarding());
+                assert!(!bpdu.topology_change());
+                assert_eq!(bpdu.root_priority(), 0x800a);
+            }
+            Bpdu::Tcn(_) => panic!("expected an RST BPDU"),
+        }
+        assert!(Bpdu::parse_llc(&llc).is_none());
+
+        let tcn = [0x42, 0x42, 0x03, 0, 0, 0, 0x80];
+        assert!(matches!(Bpdu::parse_llc(&tcn), Some(Bpdu::Tcn(_))));
+    }
+}
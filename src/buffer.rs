@@ -0,0 +1,218 @@
+//! Packets scattered over several segments, such as multi-segment DPDK
+//! mbufs, virtio descriptor chains or io_uring provided buffers.
+//!
+//! [`Buffer`] reads headers across segment boundaries without first
+//! linearizing the packet: a header lying within a segment is borrowed in
+//! place, and only one straddling two segments is copied.
+//!
+//! [`Packet`](crate::packet::Packet) parsing, however, needs a contiguous
+//! prefix of the packet holding all its headers: [`Buffer::captured`]
+//! provides one, borrowed from the first segment if it is long enough and
+//! copied otherwise.
+
+use core::{mem, ops::Deref};
+
+use crate::{
+    capture::Captured,
+    header::{Header, ParseError},
+};
+
+/// A header read from a [`Buffer`].
+#[derive(Debug, Copy, Clone)]
+pub enum Chunk<'a, T> {
+    /// The header lies within a segment.
+    Borrowed(&'a T),
+    /// The header straddles segments and was copied.
+    Copied(T),
+}
+
+impl<T> Deref for Chunk<'_, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        match self {
+            Chunk::Borrowed(hdr) => hdr,
+            Chunk::Copied(hdr) => hdr,
+        }
+    }
+}
+
+/// A packet made of the concatenation of its segments.
+///
+/// ```
+/// use ether_packet::{buffer::{Buffer, Chunk}, eth::EthHdr, ip::Ipv4Hdr};
+///
+/// #[rustfmt::skip]
+/// let head = [
+///     0, 1, 2, 3, 4, 5, 0, 6, 7, 8, 9, 10, 0x08, 0x00,
+///     0x45, 0, 0, 28, 0, 0, 0, 0, 64, 17,
+/// ];
+/// let tail = [0, 0, 10, 0, 0, 1, 10, 0, 0, 2, 0x30, 0x39, 0, 53, 0, 8, 0, 0];
+/// let segments = [&head[..], &tail[..]];
+/// let buffer = Buffer::new(&segments);
+/// assert_eq!(buffer.len(), 42);
+/// assert!(matches!(buffer.header::<EthHdr>(0), Ok(Chunk::Borrowed(_))));
+/// let ip = buffer.header::<Ipv4Hdr>(EthHdr::LEN).unwrap();
+/// assert!(matches!(ip, Chunk::Copied(_)));
+/// assert_eq!(ip.dst_addr, core::net::Ipv4Addr::new(10, 0, 0, 2));
+/// ```
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub struct Buffer<'a> {
+    segments: &'a [&'a [u8]],
+    len: usize,
+}
+
+impl<'a> Buffer<'a> {
+    pub fn new(segments: &'a [&'a [u8]]) -> Self {
+        Self {
+            segments,
+            len: segments.iter().map(|segment| segment.len()).sum(),
+        }
+    }
+
+    #[inline]
+    pub fn segments(&self) -> &'a [&'a [u8]] {
+        self.segments
+    }
+
+    /// Total length of the packet.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the segment holding the byte at `offset`, from that byte.
+    fn segment_at(&self, mut offset: usize) -> Option<&'a [u8]> {
+        for segment in self.segments {
+            match segment.get(offset..) {
+                Some(rest) if !rest.is_empty() => return Some(rest),
+                _ => offset -= segment.len(),
+            }
+        }
+        None
+    }
+
+    /// Returns the `len` bytes at `offset` if they lie within a segment.
+    #[inline]
+    pub fn bytes(&self, offset: usize, len: usize) -> Option<&'a [u8]> {
+        self.segment_at(offset)?.get(..len)
+    }
+
+    /// Copies the bytes at `offset` to `dst`, which they must fill.
+    pub fn copy_to(&self, offset: usize, dst: &mut [u8]) -> Result<(), ParseError> {
+        if offset
+            .checked_add(dst.len())
+            .is_none_or(|end| end > self.len)
+        {
+            return Err(ParseError::Truncated);
+        }
+        let mut copied = 0;
+        while copied < dst.len() {
+            // The length was checked, there is a segment for each byte.
+            let segment = self.segment_at(offset + copied).unwrap_or_default();
+            let len = segment.len().min(dst.len() - copied);
+            dst[copied..copied + len].copy_from_slice(&segment[..len]);
+            copied += len;
+        }
+        Ok(())
+    }
+
+    /// Returns the header at `offset`, copying it only if it straddles
+    /// segments. Fails as [`Header::parse`] does.
+    pub fn header<T: Header>(&self, offset: usize) -> Result<Chunk<'a, T>, ParseError> {
+        if let Some(bytes) = self.bytes(offset, mem::size_of::<T>()) {
+            return T::parse(bytes).map(Chunk::Borrowed);
+        }
+        let mut hdr = mem::MaybeUninit::<T>::zeroed();
+        // SAFETY: the zeroed bytes of `hdr` are initialized and `T` has no
+        // padding, being `#[repr(C, packed)]`.
+        let bytes = unsafe {
+            core::slice::from_raw_parts_mut(hdr.as_mut_ptr() as *mut u8, mem::size_of::<T>())
+        };
+        self.copy_to(offset, bytes)?;
+        if !T::validate(bytes) {
+            return Err(ParseError::Malformed);
+        }
+        // SAFETY: the bytes were validated as a `T`.
+        Ok(Chunk::Copied(unsafe { hdr.assume_init() }))
+    }
+
+    /// Makes the first `len` bytes of the packet contiguous for the
+    /// slice-based parsers, e.g. [`Packet::parse_captured`], the rest of
+    /// the packet being seen as cut by a snapshot length. They are
+    /// borrowed from the first segment if it holds them, and copied to
+    /// `scratch` otherwise, up to its length.
+    ///
+    /// ```
+    /// use ether_packet::{buffer::Buffer, ip::IpProto, packet::Packet};
+    ///
+    /// #[rustfmt::skip]
+    /// let head = [
+    ///     0, 1, 2, 3, 4, 5, 0, 6, 7, 8, 9, 10, 0x08, 0x00,
+    ///     0x45, 0, 0, 32, 0, 0, 0, 0, 64, 17, 0, 0, 10, 0, 0, 1, 10, 0, 0, 2,
+    ///     0x30, 0x39,
+    /// ];
+    /// let tail = [0, 53, 0, 12, 0, 0, 1, 2, 3, 4];
+    /// let segments = [&head[..], &tail[..]];
+    /// let buffer = Buffer::new(&segments);
+    /// let mut scratch = [0; 128];
+    /// let packet = Packet::parse_captured(buffer.captured(64, &mut scratch)).unwrap();
+    /// assert_eq!(packet.proto(), Some(IpProto::Udp));
+    /// assert_eq!(packet.transport().unwrap().dst_port(), Some(53));
+    /// ```
+    ///
+    /// [`Packet::parse_captured`]: crate::packet::Packet::parse_captured
+    pub fn captured<'b>(&self, len: usize, scratch: &'b mut [u8]) -> Captured<'b>
+    where
+        'a: 'b,
+    {
+        let len = len.min(self.len);
+        let data = match self.bytes(0, len) {
+            Some(data) => data,
+            None => {
+                let len = len.min(scratch.len());
+                // `len` is within the packet.
+                let _ = self.copy_to(0, &mut scratch[..len]);
+                &scratch[..len]
+            }
+        };
+        Captured::new(data, self.len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Buffer, Chunk};
+    use crate::{header::ParseError, ip::Ipv4Hdr};
+
+    #[test]
+    fn test_buffer() {
+        #[rustfmt::skip]
+        let ip = [
+            0x45, 0, 0, 20, 0, 0, 0, 0, 64, 17, 0, 0, 10, 0, 0, 1, 10, 0, 0, 2,
+        ];
+        // The header is split over three segments, one of them empty.
+        let segments = [&ip[..3], &[][..], &ip[3..11], &ip[11..]];
+        let buffer = Buffer::new(&segments);
+        assert_eq!((buffer.len(), buffer.bytes(3, 8)), (20, Some(&ip[3..11])));
+        assert_eq!(buffer.bytes(2, 2), None);
+        match buffer.header::<Ipv4Hdr>(0) {
+            Ok(Chunk::Copied(hdr)) => assert_eq!(hdr.ttl, 64),
+            hdr => panic!("unexpected header {hdr:?}"),
+        }
+        let mut tail = [0; 4];
+        buffer.copy_to(16, &mut tail).unwrap();
+        assert_eq!(tail, [10, 0, 0, 2]);
+        assert_eq!(buffer.copy_to(17, &mut tail), Err(ParseError::Truncated));
+        assert_eq!(
+            buffer.header::<Ipv4Hdr>(1).unwrap_err(),
+            ParseError::Truncated
+        );
+    }
+}
@@ -0,0 +1,506 @@
+//! Helpers shared by the packet builders of this crate, which all write into
+//! a caller-provided buffer without allocating, and [`EthFrameBuilder`],
+//! stacking the headers of a whole Ethernet frame.
+
+use core::{
+    fmt,
+    net::{Ipv4Addr, Ipv6Addr},
+};
+
+use crate::{
+    arp::{ArpOp, ARP_HTYPE_ETHERNET},
+    checksum,
+    eth::{self, EthHdr, EtherType},
+    ip::{IpProto, Ipv4Hdr, Ipv6Hdr},
+    tcp::TcpHdr,
+    udp::UdpHdr,
+};
+
+/// Error returned by the packet builders.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum BuildError {
+    /// The buffer is too small to hold the packet.
+    BufferTooSmall,
+    /// A value does not fit into the field which has to carry it, e.g. a
+    /// payload too large for a 16-bit length field.
+    FieldOverflow,
+    /// The frame exceeds the configured [`FrameSize`].
+    ///
+    /// [`FrameSize`]: crate::eth::FrameSize
+    FrameTooLarge,
+    /// A header was added where the previous layers cannot carry it, e.g.
+    /// UDP without an IP header.
+    MisplacedHeader,
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::BufferTooSmall => f.write_str("buffer too small"),
+            BuildError::FieldOverflow => f.write_str("value does not fit into its field"),
+            BuildError::FrameTooLarge => f.write_str("frame too large"),
+            BuildError::MisplacedHeader => f.write_str("header cannot follow the previous layer"),
+        }
+    }
+}
+
+/// Cursor appending big-endian values to a buffer.
+pub(crate) struct Writer<'a> {
+    buf: &'a mut [u8],
+    pos: usize,
+}
+
+impl<'a> Writer<'a> {
+    #[inline]
+    pub(crate) fn new(buf: &'a mut [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+
+    /// Number of bytes written so far.
+    #[inline]
+    pub(crate) fn pos(&self) -> usize {
+        self.pos
+    }
+
+    /// Reserves `len` bytes, returning them zeroed.
+    #[inline]
+    pub(crate) fn reserve(&mut self, len: usize) -> Result<&mut [u8], BuildError> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.buf.len())
+            .ok_or(BuildError::BufferTooSmall)?;
+        let out = &mut self.buf[self.pos..end];
+        out.fill(0);
+        self.pos = end;
+        Ok(out)
+    }
+
+    #[inline]
+    pub(crate) fn put(&mut self, bytes: &[u8]) -> Result<(), BuildError> {
+        self.reserve(bytes.len())?.copy_from_slice(bytes);
+        Ok(())
+    }
+
+    #[inline]
+    pub(crate) fn put_u8(&mut self, val: u8) -> Result<(), BuildError> {
+        self.put(&[val])
+    }
+
+    /// Overwrites a big-endian `u16` previously written at `offset`.
+    #[inline]
+    pub(crate) fn set_u16(&mut self, offset: usize, val: u16) {
+        self.buf[offset..offset + 2].copy_from_slice(&val.to_be_bytes());
+    }
+
+    /// Bytes written so far.
+    #[inline]
+    pub(crate) fn written(&self) -> &[u8] {
+        &self.buf[..self.pos]
+    }
+
+    #[inline]
+    pub(crate) fn written_mut(&mut self) -> &mut [u8] {
+        &mut self.buf[..self.pos]
+    }
+}
+
+/// Builds an Ethernet frame into a caller-provided buffer, one header at a
+/// time from the outermost. Lengths and checksums are filled in by
+/// [`EthFrameBuilder::finish`].
+///
+/// ```
+/// use core::net::Ipv4Addr;
+/// use ether_packet::{builder::EthFrameBuilder, ip::IpProto, packet::Packet};
+///
+/// let mut buf = [0u8; 128];
+/// let mut frame = EthFrameBuilder::new(&mut buf, [0xff; 6], [2, 0, 0, 0, 0, 1]).unwrap();
+/// frame
+///     .vlan(100)
+///     .unwrap()
+///     .ipv4(Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::new(10, 0, 0, 2), IpProto::Udp)
+///     .unwrap()
+///     .udp(12345, 53)
+///     .unwrap()
+///     .payload(b"hello")
+///     .unwrap();
+/// let len = frame.finish().unwrap();
+/// assert_eq!(len, 18 + 20 + 8 + 5);
+///
+/// let packet = Packet::parse(&buf[..len]).unwrap();
+/// assert_eq!(packet.vid(), Some(100));
+/// assert!(packet.ipv4().unwrap().verify_checksum());
+/// ```
+pub struct EthFrameBuilder<'a> {
+    w: Writer<'a>,
+    /// Offset of the EtherType set by the next layer.
+    ether_type_at: usize,
+    ip: Option<IpLayer>,
+    l4: Option<(usize, IpProto)>,
+    fcs: bool,
+}
+
+#[derive(Copy, Clone)]
+enum IpLayer {
+    V4(usize, Ipv4Addr, Ipv4Addr),
+    V6(usize, Ipv6Addr, Ipv6Addr),
+}
+
+impl IpLayer {
+    #[inline]
+    fn offset(&self) -> usize {
+        match *self {
+            IpLayer::V4(offset, ..) | IpLayer::V6(offset, ..) => offset,
+        }
+    }
+
+    /// Offset of the end of the header.
+    #[inline]
+    fn end(&self) -> usize {
+        match *self {
+            IpLayer::V4(offset, ..) => offset + Ipv4Hdr::LEN,
+            IpLayer::V6(offset, ..) => offset + Ipv6Hdr::LEN,
+        }
+    }
+}
+
+/// Default TTL and hop limit of the IP headers.
+const DEFAULT_TTL: u8 = 64;
+
+impl<'a> EthFrameBuilder<'a> {
+    /// Starts a frame with the given destination and source addresses.
+    pub fn new(
+        buf: &'a mut [u8],
+        dst_addr: [u8; 6],
+        src_addr: [u8; 6],
+    ) -> Result<Self, BuildError> {
+        let mut w = Writer::new(buf);
+        let eth = w.reserve(EthHdr::LEN)?;
+        eth[..6].copy_from_slice(&dst_addr);
+        eth[6..12].copy_from_slice(&src_addr);
+        Ok(Self {
+            w,
+            ether_type_at: 12,
+            ip: None,
+            l4: None,
+            fcs: false,
+        })
+    }
+
+    #[inline]
+    fn check_l2(&self) -> Result<(), BuildError> {
+        match (self.ip, self.w.pos() - self.ether_type_at) {
+            (None, 2) => Ok(()),
+            _ => Err(BuildError::MisplacedHeader),
+        }
+    }
+
+    /// Sets the EtherType of the frame, for payloads added with
+    /// [`EthFrameBuilder::payload`].
+    pub fn ether_type(&mut self, ether_type: EtherType) -> Result<&mut Self, BuildError> {
+        self.check_l2()?;
+        self.w.set_u16(self.ether_type_at, ether_type as u16);
+        Ok(self)
+    }
+
+    /// Appends an 802.1Q tag with the given VID and a PCP of 0.
+    pub fn vlan(&mut self, vid: u16) -> Result<&mut Self, BuildError> {
+        self.tag(EtherType::VLAN, vid & 0xfff)
+    }
+
+    /// Appends an 802.1Q or 802.1ad tag.
+    pub fn tag(&mut self, tpid: EtherType, tci: u16) -> Result<&mut Self, BuildError> {
+        self.ether_type(tpid)?;
+        self.w.put(&tci.to_be_bytes())?;
+        self.w.put(&[0, 0])?;
+        self.ether_type_at = self.w.pos() - 2;
+        Ok(self)
+    }
+
+    /// Appends an ARP packet for IPv4 over Ethernet, e.g. a probe when
+    /// `spa` is unspecified.
+    pub fn arp(
+        &mut self,
+        op: ArpOp,
+        sha: [u8; 6],
+        spa: Ipv4Addr,
+        tha: [u8; 6],
+        tpa: Ipv4Addr,
+    ) -> Result<&mut Self, BuildError> {
+        self.ether_type(EtherType::Arp)?;
+        self.w.put(&ARP_HTYPE_ETHERNET.to_be_bytes())?;
+        self.w.put(&(EtherType::Ipv4 as u16).to_be_bytes())?;
+        self.w.put(&[6, 4])?;
+        self.w.put(&(op as u16).to_be_bytes())?;
+        self.w.put(&sha)?;
+        self.w.put(&spa.octets())?;
+        self.w.put(&tha)?;
+        self.w.put(&tpa.octets())?;
+        // Nothing can follow an ARP packet.
+        self.ether_type_at = 0;
+        Ok(self)
+    }
+
+    /// Appends an IPv4 header without options, with a TTL of 64 and the DF
+    /// flag set.
+    pub fn ipv4(
+        &mut self,
+        src: Ipv4Addr,
+        dst: Ipv4Addr,
+        proto: IpProto,
+    ) -> Result<&mut Self, BuildError> {
+        self.ether_type(EtherType::Ipv4)?;
+        let offset = self.w.pos();
+        let hdr = self.w.reserve(Ipv4Hdr::LEN)?;
+        hdr[0] = 0x45;
+        hdr[6] = 0x40;
+        hdr[8] = DEFAULT_TTL;
+        hdr[9] = proto as u8;
+        hdr[12..16].copy_from_slice(&src.octets());
+        hdr[16..20].copy_from_slice(&dst.octets());
+        self.ip = Some(IpLayer::V4(offset, src, dst));
+        Ok(self)
+    }
+
+    /// Appends an IPv6 header with a hop limit of 64.
+    pub fn ipv6(
+        &mut self,
+        src: Ipv6Addr,
+        dst: Ipv6Addr,
+        next_hdr: IpProto,
+    ) -> Result<&mut Self, BuildError> {
+        self.ether_type(EtherType::Ipv6)?;
+        let offset = self.w.pos();
+        let hdr = self.w.reserve(Ipv6Hdr::LEN)?;
+        hdr[0] = 0x60;
+        hdr[6] = next_hdr as u8;
+        hdr[7] = DEFAULT_TTL;
+        hdr[8..24].copy_from_slice(&src.octets());
+        hdr[24..40].copy_from_slice(&dst.octets());
+        self.ip = Some(IpLayer::V6(offset, src, dst));
+        Ok(self)
+    }
+
+    /// Sets the TTL or hop limit of the IP header.
+    pub fn ttl(&mut self, ttl: u8) -> Result<&mut Self, BuildError> {
+        let at = match self.ip {
+            Some(IpLayer::V4(offset, ..)) => offset + 8,
+            Some(IpLayer::V6(offset, ..)) => offset + 7,
+            None => return Err(BuildError::MisplacedHeader),
+        };
+        self.w.written_mut()[at] = ttl;
+        Ok(self)
+    }
+
+    /// Reserves the transport header of protocol `proto`, setting the
+    /// protocol of the IP header.
+    fn l4(&mut self, proto: IpProto, len: usize) -> Result<&mut [u8], BuildError> {
+        let (Some(ip), None) = (self.ip, self.l4) else {
+            return Err(BuildError::MisplacedHeader);
+        };
+        if self.w.pos() != ip.end() {
+            return Err(BuildError::MisplacedHeader);
+        }
+        let at = match ip {
+            IpLayer::V4(offset, ..) => offset + 9,
+            IpLayer::V6(offset, ..) => offset + 6,
+        };
+        self.w.written_mut()[at] = proto as u8;
+        self.l4 = Some((self.w.pos(), proto));
+        self.w.reserve(len)
+    }
+
+    /// Appends a UDP header.
+    pub fn udp(&mut self, src_port: u16, dst_port: u16) -> Result<&mut Self, BuildError> {
+        let hdr = self.l4(IpProto::Udp, UdpHdr::LEN)?;
+        hdr[..2].copy_from_slice(&src_port.to_be_bytes());
+        hdr[2..4].copy_from_slice(&dst_port.to_be_bytes());
+        Ok(self)
+    }
+
+    /// Appends a TCP header without options, `flags` being the byte holding
+    /// CWR to FIN, e.g. `0x02` for SYN.
+    pub fn tcp(
+        &mut self,
+        src_port: u16,
+        dst_port: u16,
+        seq: u32,
+        ack_seq: u32,
+        flags: u8,
+        window: u16,
+    ) -> Result<&mut Self, BuildError> {
+        let hdr = self.l4(IpProto::Tcp, TcpHdr::LEN)?;
+        hdr[..2].copy_from_slice(&src_port.to_be_bytes());
+        hdr[2..4].copy_from_slice(&dst_port.to_be_bytes());
+        hdr[4..8].copy_from_slice(&seq.to_be_bytes());
+        hdr[8..12].copy_from_slice(&ack_seq.to_be_bytes());
+        hdr[12] = ((TcpHdr::LEN / 4) as u8) << 4;
+        hdr[13] = flags;
+        hdr[14..16].copy_from_slice(&window.to_be_bytes());
+        Ok(self)
+    }
+
+    /// Appends an ICMP Echo Request header, or an ICMPv6 one over IPv6.
+    pub fn icmp_echo(&mut self, id: u16, sequence: u16) -> Result<&mut Self, BuildError> {
+        let (proto, icmp_type) = match self.ip {
+            Some(IpLayer::V6(..)) => (IpProto::Ipv6Icmp, 128),
+            _ => (IpProto::Icmp, 8),
+        };
+        let hdr = self.l4(proto, 8)?;
+        hdr[0] = icmp_type;
+        hdr[4..6].copy_from_slice(&id.to_be_bytes());
+        hdr[6..8].copy_from_slice(&sequence.to_be_bytes());
+        Ok(self)
+    }
+
+    /// Appends the payload of the innermost header.
+    pub fn payload(&mut self, payload: &[u8]) -> Result<&mut Self, BuildError> {
+        self.w.put(payload)?;
+        Ok(self)
+    }
+
+    /// Appends the FCS to the frame in [`EthFrameBuilder::finish`], for
+    /// interfaces sending frames as given, e.g. when injecting corrupted
+    /// frames or when the FCS offload is disabled.
+    pub fn fcs(&mut self, append: bool) -> &mut Self {
+        self.fcs = append;
+        self
+    }
+
+    /// Fills in the length fields and the checksums of the IP and transport
+    /// headers, returning the length of the frame. Short frames are not
+    /// padded to the Ethernet minimum.
+    pub fn finish(mut self) -> Result<usize, BuildError> {
+        self.fill()?;
+        if self.fcs {
+            let fcs = eth::fcs(self.w.written());
+            self.w.put(&fcs)?;
+        }
+        Ok(self.w.pos())
+    }
+
+    fn fill(&mut self) -> Result<(), BuildError> {
+        let len = self.w.pos();
+        let Some(ip) = self.ip else {
+            return Ok(());
+        };
+        let ip_len = len - ip.offset();
+        match ip {
+            IpLayer::V4(offset, ..) => {
+                let tot_len = u16::try_from(ip_len).map_err(|_| BuildError::FieldOverflow)?;
+                self.w.set_u16(offset + 2, tot_len);
+                let check = checksum::checksum(&self.w.written()[offset..offset + Ipv4Hdr::LEN]);
+                self.w.set_u16(offset + 10, check);
+            }
+            IpLayer::V6(offset, ..) => {
+                let payload_len =
+                    u16::try_from(ip_len - Ipv6Hdr::LEN).map_err(|_| BuildError::FieldOverflow)?;
+                self.w.set_u16(offset + 4, payload_len);
+            }
+        }
+
+        let Some((l4, proto)) = self.l4 else {
+            return Ok(());
+        };
+        let check_at = match proto {
+            IpProto::Udp => {
+                // Fits, being smaller than the IP length checked above.
+                self.w.set_u16(l4 + 4, (len - l4) as u16);
+                l4 + 6
+            }
+            IpProto::Tcp => l4 + 16,
+            _ => l4 + 2,
+        };
+        let segment = &self.w.written()[l4..];
+        let check = match (proto, ip) {
+            (IpProto::Icmp, _) => checksum::checksum(segment),
+            (_, IpLayer::V4(_, src, dst)) => checksum::l4_checksum_v4(src, dst, proto, segment),
+            (_, IpLayer::V6(_, src, dst)) => checksum::l4_checksum_v6(src, dst, proto, segment),
+        };
+        self.w.set_u16(check_at, check);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::net::{Ipv4Addr, Ipv6Addr};
+
+    use super::{BuildError, EthFrameBuilder};
+    use crate::{
+        checksum,
+        ip::IpProto,
+        packet::{Packet, TransportHdr},
+    };
+
+    #[test]
+    fn test_eth_frame_builder() {
+        let (src, dst) = (Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::new(10, 0, 0, 2));
+        let mut buf = [0u8; 128];
+        let mut frame =
+            EthFrameBuilder::new(&mut buf, [2, 0, 0, 0, 0, 2], [2, 0, 0, 0, 0, 1]).unwrap();
+        frame
+            .ipv4(src, dst, IpProto::Tcp)
+            .unwrap()
+            .ttl(1)
+            .unwrap()
+            .tcp(40000, 443, 1, 0, 0x02, 65535)
+            .unwrap();
+        assert_eq!(frame.udp(1, 2).err(), Some(BuildError::MisplacedHeader));
+        let len = frame.finish().unwrap();
+        let packet = Packet::parse(&buf[..len]).unwrap();
+        let ip = packet.ipv4().unwrap();
+        assert_eq!((ip.tot_len.to_bits(), ip.ttl), (40, 1));
+        assert!(ip.verify_checksum());
+        let Some(TransportHdr::Tcp(tcp)) = packet.transport() else {
+            panic!("not TCP");
+        };
+        assert_eq!((tcp.syn(), tcp.hdrlen()), (1, 20));
+        assert!(checksum::verify_l4_v4(
+            src,
+            dst,
+            IpProto::Tcp,
+            &buf[34..len]
+        ));
+
+        let (src, dst): (Ipv6Addr, Ipv6Addr) =
+            ("fe80::1".parse().unwrap(), "fe80::2".parse().unwrap());
+        let mut frame =
+            EthFrameBuilder::new(&mut buf, [2, 0, 0, 0, 0, 2], [2, 0, 0, 0, 0, 1]).unwrap();
+        frame
+            .ipv6(src, dst, IpProto::Ipv6NoNxt)
+            .unwrap()
+            .icmp_echo(7, 1)
+            .unwrap()
+            .payload(&[0xaa; 4])
+            .unwrap();
+        let len = frame.finish().unwrap();
+        let packet = Packet::parse(&buf[..len]).unwrap();
+        assert_eq!(packet.proto(), Some(IpProto::Ipv6Icmp));
+        assert_eq!(packet.fcs_valid(), None);
+        assert_eq!(packet.ipv6().unwrap().payload_len.to_bits(), 12);
+        assert!(checksum::verify_l4_v6(
+            src,
+            dst,
+            IpProto::Ipv6Icmp,
+            &buf[54..len]
+        ));
+
+        let mut frame =
+            EthFrameBuilder::new(&mut buf, [2, 0, 0, 0, 0, 2], [2, 0, 0, 0, 0, 1]).unwrap();
+        frame
+            .fcs(true)
+            .ipv6(src, dst, IpProto::Udp)
+            .unwrap()
+            .udp(1, 2)
+            .unwrap();
+        let len = frame.finish().unwrap();
+        assert_eq!(len, 14 + 40 + 8 + 4);
+        let packet = Packet::parse_with_fcs(&buf[..len]).unwrap();
+        assert_eq!(packet.data().len(), len - 4);
+        assert!(packet.payload().len() == 8 && packet.fcs_valid() == Some(true));
+        buf[len - 1] ^= 1;
+        let packet = Packet::parse_with_fcs(&buf[..len]).unwrap();
+        assert_eq!(packet.fcs_valid(), Some(false));
+    }
+}
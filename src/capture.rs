@@ -0,0 +1,216 @@
+//! Parsing of packets cut at the snapshot length of a capture.
+//!
+//! Captures often keep only the first bytes of each packet, so headers may
+//! extend past the captured data while the packet on the wire was fine.
+//! [`Captured`] knows both lengths and reports such headers as
+//! [`Field::Truncated`], keeping errors for packets which are really
+//! malformed.
+
+use core::mem;
+
+use crate::{
+    eth::FCS_LEN,
+    header::{Header, ParseError},
+};
+
+/// A header or field of a captured packet.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum Field<T> {
+    Present(T),
+    /// Cut by the snapshot length.
+    Truncated,
+}
+
+impl<T> Field<T> {
+    /// Returns the value, if it was captured.
+    #[inline]
+    pub fn present(self) -> Option<T> {
+        match self {
+            Field::Present(val) => Some(val),
+            Field::Truncated => None,
+        }
+    }
+
+    #[inline]
+    pub fn is_truncated(&self) -> bool {
+        matches!(self, Field::Truncated)
+    }
+}
+
+/// The captured bytes of a packet, along with its length on the wire.
+///
+/// ```
+/// use ether_packet::{capture::{Captured, Field}, eth::EthHdr, ip::Ipv4Hdr};
+///
+/// // Only the Ethernet header and 6 bytes of the IPv4 header were captured.
+/// let data = [
+///     0, 1, 2, 3, 4, 5, 0, 6, 7, 8, 9, 10, 0x08, 0x00, 0x45, 0, 0, 40, 0, 0,
+/// ];
+/// let packet = Captured::new(&data, 54);
+/// assert!(matches!(packet.header::<EthHdr>(0), Ok(Field::Present(_))));
+/// assert!(packet.header::<Ipv4Hdr>(EthHdr::LEN).unwrap().is_truncated());
+/// ```
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub struct Captured<'a> {
+    data: &'a [u8],
+    original_len: usize,
+    fcs: Option<[u8; FCS_LEN]>,
+}
+
+impl<'a> Captured<'a> {
+    /// `data` holds the first bytes of a packet of `original_len` bytes on
+    /// the wire. An `original_len` smaller than `data` is raised to its
+    /// length.
+    #[inline]
+    pub fn new(data: &'a [u8], original_len: usize) -> Self {
+        Self {
+            data,
+            original_len: original_len.max(data.len()),
+            fcs: None,
+        }
+    }
+
+    /// The packet ends with the FCS of an Ethernet frame, as captured by
+    /// interfaces keeping it. The FCS is removed from the data and from the
+    /// length on the wire, and kept if it was captured, see
+    /// [`Captured::fcs`].
+    pub fn with_fcs(self) -> Self {
+        let original_len = self.original_len.saturating_sub(FCS_LEN);
+        let fcs = self
+            .data
+            .get(original_len..)
+            .and_then(|fcs| fcs.try_into().ok());
+        Self {
+            data: &self.data[..self.data.len().min(original_len)],
+            original_len,
+            fcs,
+        }
+    }
+
+    /// The FCS removed by [`Captured::with_fcs`], unless it was cut by the
+    /// snapshot length.
+    #[inline]
+    pub fn fcs(&self) -> Option<[u8; FCS_LEN]> {
+        self.fcs
+    }
+
+    /// A packet captured in full.
+    #[inline]
+    pub fn complete(data: &'a [u8]) -> Self {
+        Self::new(data, data.len())
+    }
+
+    #[inline]
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    #[inline]
+    pub fn captured_len(&self) -> usize {
+        self.data.len()
+    }
+
+    #[inline]
+    pub fn original_len(&self) -> usize {
+        self.original_len
+    }
+
+    #[inline]
+    pub fn is_truncated(&self) -> bool {
+        self.data.len() < self.original_len
+    }
+
+    /// Checks that bytes up to `end` exist on the wire, given that they
+    /// were not captured.
+    #[inline]
+    fn missing<T>(&self, end: Option<usize>) -> Result<Field<T>, ParseError> {
+        match end {
+            Some(end) if end <= self.original_len => Ok(Field::Truncated),
+            _ => Err(ParseError::Malformed),
+        }
+    }
+
+    /// Returns the header at `offset`, or [`Field::Truncated`] if it was cut
+    /// by the snapshot length. Fails if the header is invalid or extends
+    /// past the end of the packet on the wire.
+    pub fn header<T: Header>(&self, offset: usize) -> Result<Field<&'a T>, ParseError> {
+        let bytes = self.data.get(offset..).unwrap_or(&[]);
+        match T::parse(bytes) {
+            Ok(hdr) => Ok(Field::Present(hdr)),
+            Err(ParseError::Truncated) => self.missing(offset.checked_add(mem::size_of::<T>())),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Returns the `len` bytes at `offset`, or [`Field::Truncated`] if they
+    /// were not all captured.
+    pub fn bytes(&self, offset: usize, len: usize) -> Result<Field<&'a [u8]>, ParseError> {
+        let end = offset.checked_add(len);
+        match end.and_then(|end| self.data.get(offset..end)) {
+            Some(bytes) => Ok(Field::Present(bytes)),
+            None => self.missing(end),
+        }
+    }
+
+    /// Returns the captured part of the `len` bytes at `offset`, e.g. of a
+    /// payload whose length is given by a header.
+    pub fn partial(&self, offset: usize, len: usize) -> Result<&'a [u8], ParseError> {
+        match offset.checked_add(len) {
+            Some(end) if end <= self.original_len => {
+                let end = end.min(self.data.len());
+                Ok(self.data.get(offset..end).unwrap_or(&[]))
+            }
+            _ => Err(ParseError::Malformed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Captured, Field};
+    use crate::{
+        header::{Header, ParseError},
+        ip::Ipv4Hdr,
+    };
+
+    #[test]
+    fn test_captured() {
+        let mut data = [
+            0x45, 0, 0, 28, 0, 0, 0, 0, 64, 17, 0, 0, 10, 0, 0, 1, 10, 0, 0, 2, 0x30, 0x39,
+        ];
+        assert_eq!(
+            Ipv4Hdr::parse(&data[..10]).unwrap_err(),
+            ParseError::Truncated
+        );
+
+        let packet = Captured::new(&data, 28);
+        assert!(packet.is_truncated());
+        let hdr = packet.header::<Ipv4Hdr>(0).unwrap().present().unwrap();
+        assert_eq!(hdr.tot_len.to_bits(), 28);
+        assert_eq!(packet.bytes(20, 8), Ok(Field::Truncated));
+        assert_eq!(packet.bytes(20, 9), Err(ParseError::Malformed));
+        assert_eq!(packet.partial(20, 8), Ok(&[0x30, 0x39][..]));
+
+        // Not cut by the snapshot length, the packet is too short.
+        let packet = Captured::complete(&data[..10]);
+        assert_eq!(
+            packet.header::<Ipv4Hdr>(0).unwrap_err(),
+            ParseError::Malformed
+        );
+
+        // The FCS is not part of the packet.
+        let packet = Captured::new(&data, 32).with_fcs();
+        assert_eq!((packet.captured_len(), packet.original_len()), (22, 28));
+        assert_eq!(packet.fcs(), None);
+        let packet = Captured::complete(&data).with_fcs();
+        assert_eq!(packet.data(), &data[..18]);
+        assert_eq!(packet.fcs(), Some([0, 2, 0x30, 0x39]));
+
+        data[9] = 150;
+        let packet = Captured::new(&data, 28);
+        assert_eq!(
+            packet.header::<Ipv4Hdr>(0).unwrap_err(),
+            ParseError::Malformed
+        );
+    }
+}
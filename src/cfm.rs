@@ -0,0 +1,390 @@
+//! Connectivity Fault Management (IEEE 802.1ag / 802.1Q clause 21) and the
+//! ITU-T Y.1731 OAM extensions, carried with [`EtherType::CFM`](crate::eth::EtherType::CFM).
+
+use core::mem;
+
+use crate::{
+    bitfield::BitfieldUnit,
+    header::{impl_header, Header, Limited, ParseConfig, ParseError},
+    types::{U16, U32},
+};
+
+/// CFM and Y.1731 OpCodes.
+#[repr(u8)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum CfmOpcode {
+    /// Continuity Check Message
+    Ccm = 1,
+    /// Loopback Reply
+    Lbr = 2,
+    /// Loopback Message
+    Lbm = 3,
+    /// Linktrace Reply
+    Ltr = 4,
+    /// Linktrace Message
+    Ltm = 5,
+    /// Alarm Indication Signal
+    Ais = 33,
+    /// Locked Signal
+    Lck = 35,
+    /// Test Signal
+    Tst = 37,
+    /// Automatic Protection Switching
+    Aps = 39,
+    /// Maintenance Communication Channel
+    Mcc = 41,
+    /// Loss Measurement Reply
+    Lmr = 42,
+    /// Loss Measurement Message
+    Lmm = 43,
+    /// One-way Delay Measurement
+    OneDm = 45,
+    /// Delay Measurement Reply
+    Dmr = 46,
+    /// Delay Measurement Message
+    Dmm = 47,
+}
+
+impl TryFrom<u8> for CfmOpcode {
+    type Error = ();
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(CfmOpcode::Ccm),
+            2 => Ok(CfmOpcode::Lbr),
+            3 => Ok(CfmOpcode::Lbm),
+            4 => Ok(CfmOpcode::Ltr),
+            5 => Ok(CfmOpcode::Ltm),
+            33 => Ok(CfmOpcode::Ais),
+            35 => Ok(CfmOpcode::Lck),
+            37 => Ok(CfmOpcode::Tst),
+            39 => Ok(CfmOpcode::Aps),
+            41 => Ok(CfmOpcode::Mcc),
+            42 => Ok(CfmOpcode::Lmr),
+            43 => Ok(CfmOpcode::Lmm),
+            45 => Ok(CfmOpcode::OneDm),
+            46 => Ok(CfmOpcode::Dmr),
+            47 => Ok(CfmOpcode::Dmm),
+            _ => Err(()),
+        }
+    }
+}
+
+/// CFM common header.
+/// ```text
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |MD L.| Version |    OpCode     |     Flags     |First TLV Offs.|
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct CfmHdr {
+    /// **MD Level** (3 bits) and **Version** (5 bits).
+    pub level_version: BitfieldUnit<[u8; 1usize]>,
+    /// See [`CfmOpcode`].
+    pub opcode: u8,
+    pub flags: u8,
+    /// Offset of the first TLV, counted from the end of this header.
+    pub first_tlv_offset: u8,
+}
+
+impl CfmHdr {
+    pub const LEN: usize = mem::size_of::<CfmHdr>();
+
+    /// Maintenance domain level, 0 to 7.
+    #[inline]
+    pub const fn md_level(&self) -> u8 {
+        self.level_version.get(5, 3) as u8
+    }
+
+    #[inline]
+    pub const fn set_md_level(&mut self, val: u8) {
+        self.level_version.set(5, 3, val as u64)
+    }
+
+    #[inline]
+    pub const fn version(&self) -> u8 {
+        self.level_version.get(0, 5) as u8
+    }
+
+    #[inline]
+    pub const fn set_version(&mut self, val: u8) {
+        self.level_version.set(0, 5, val as u64)
+    }
+
+    #[inline]
+    pub fn opcode(&self) -> Option<CfmOpcode> {
+        self.opcode.try_into().ok()
+    }
+
+    /// **RDI** flag of CCMs: the sending MEP detected a defect.
+    #[inline]
+    pub const fn rdi(&self) -> bool {
+        self.flags & 0x80 != 0
+    }
+
+    /// CCM transmission interval code, from 1 (3.33 ms) to 7 (10 min).
+    #[inline]
+    pub const fn ccm_interval(&self) -> u8 {
+        self.flags & 0x07
+    }
+
+    /// **UseFDBonly** flag of LTMs and LTRs.
+    #[inline]
+    pub const fn use_fdb_only(&self) -> bool {
+        self.flags & 0x80 != 0
+    }
+
+    /// **FwdYes** flag of LTRs.
+    #[inline]
+    pub const fn fwd_yes(&self) -> bool {
+        self.flags & 0x40 != 0
+    }
+
+    /// **TerminalMEP** flag of LTRs.
+    #[inline]
+    pub const fn terminal_mep(&self) -> bool {
+        self.flags & 0x20 != 0
+    }
+
+    /// Parses the CFM PDU stored in `pdu`, returning its header, decoded
+    /// message body and an iterator over its TLVs.
+    pub fn parse(pdu: &[u8]) -> Option<(&CfmHdr, CfmMessage<'_>, CfmTlvs<'_>)> {
+        let hdr = CfmHdr::from_bytes(pdu)?;
+        let body = &pdu[Self::LEN..];
+        let msg = match hdr.opcode() {
+            Some(CfmOpcode::Ccm) => CfmMessage::Ccm(CcmBody::from_bytes(body)?),
+            Some(CfmOpcode::Lbm) => CfmMessage::Lbm(LbBody::from_bytes(body)?),
+            Some(CfmOpcode::Lbr) => CfmMessage::Lbr(LbBody::from_bytes(body)?),
+            Some(CfmOpcode::Ltm) => CfmMessage::Ltm(LtmBody::from_bytes(body)?),
+            Some(CfmOpcode::Ltr) => CfmMessage::Ltr(LtrBody::from_bytes(body)?),
+            Some(CfmOpcode::Dmm) => CfmMessage::Dmm(DmBody::from_bytes(body)?),
+            Some(CfmOpcode::Dmr) => CfmMessage::Dmr(DmBody::from_bytes(body)?),
+            _ => CfmMessage::Other(body),
+        };
+        let tlvs = body.get(hdr.first_tlv_offset as usize..)?;
+        Some((hdr, msg, CfmTlvs { data: tlvs }))
+    }
+}
+
+/// Continuity Check Message body.
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct CcmBody {
+    pub seq: U32,
+    /// Maintenance association End Point Identifier.
+    pub mep_id: U16,
+    /// Maintenance Association Identifier.
+    pub maid: [u8; 48],
+    /// Y.1731 frame loss counters, zero when unused.
+    pub tx_fcf: U32,
+    pub rx_fcb: U32,
+    pub tx_fcb: U32,
+    pub _reserved: U32,
+}
+
+impl CcmBody {
+    pub const LEN: usize = mem::size_of::<CcmBody>();
+
+    #[inline]
+    pub const fn mep_id(&self) -> u16 {
+        self.mep_id.to_bits() & 0x1fff
+    }
+}
+
+/// Loopback Message and Reply body.
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct LbBody {
+    pub transaction_id: U32,
+}
+
+impl LbBody {
+    pub const LEN: usize = mem::size_of::<LbBody>();
+}
+
+/// Linktrace Message body.
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct LtmBody {
+    pub transaction_id: U32,
+    pub ttl: u8,
+    pub original_mac: [u8; 6],
+    pub target_mac: [u8; 6],
+}
+
+impl LtmBody {
+    pub const LEN: usize = mem::size_of::<LtmBody>();
+}
+
+/// Linktrace Reply body.
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct LtrBody {
+    pub transaction_id: U32,
+    pub reply_ttl: u8,
+    /// 1: RlyHit, 2: RlyFDB, 3: RlyMPDB.
+    pub relay_action: u8,
+}
+
+impl LtrBody {
+    pub const LEN: usize = mem::size_of::<LtrBody>();
+}
+
+/// Timestamp in the IEEE 1588 format used by Y.1731.
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct CfmTimestamp {
+    pub secs: U32,
+    pub nanos: U32,
+}
+
+impl CfmTimestamp {
+    /// The timestamp in nanoseconds.
+    #[inline]
+    pub const fn as_nanos(&self) -> u64 {
+        self.secs.to_bits() as u64 * 1_000_000_000 + self.nanos.to_bits() as u64
+    }
+}
+
+/// Y.1731 Delay Measurement Message and Reply body.
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct DmBody {
+    pub tx_timestamp_f: CfmTimestamp,
+    /// Zero in DMMs.
+    pub rx_timestamp_f: CfmTimestamp,
+    /// Zero in DMMs.
+    pub tx_timestamp_b: CfmTimestamp,
+    /// Reserved for the receiving end of the DMR.
+    pub rx_timestamp_b: CfmTimestamp,
+}
+
+impl DmBody {
+    pub const LEN: usize = mem::size_of::<DmBody>();
+
+    /// Two-way frame delay of a DMR received at `rx_timestamp_b`, in
+    /// nanoseconds, excluding the processing time of the responder.
+    #[inline]
+    pub const fn frame_delay(&self, rx_timestamp_b: u64) -> u64 {
+        let total = rx_timestamp_b.saturating_sub(self.tx_timestamp_f.as_nanos());
+        let processing = self
+            .tx_timestamp_b
+            .as_nanos()
+            .saturating_sub(self.rx_timestamp_f.as_nanos());
+        total.saturating_sub(processing)
+    }
+}
+
+impl_header!(
+    CfmHdr,
+    CcmBody,
+    LbBody,
+    LtmBody,
+    LtrBody,
+    CfmTimestamp,
+    DmBody
+);
+
+/// Decoded body of a CFM PDU.
+#[derive(Debug, Copy, Clone)]
+pub enum CfmMessage<'a> {
+    Ccm(&'a CcmBody),
+    Lbm(&'a LbBody),
+    Lbr(&'a LbBody),
+    Ltm(&'a LtmBody),
+    Ltr(&'a LtrBody),
+    Dmm(&'a DmBody),
+    Dmr(&'a DmBody),
+    /// Any other OpCode, with the bytes following the common header.
+    Other(&'a [u8]),
+}
+
+/// A CFM TLV.
+#[derive(Debug, Copy, Clone)]
+pub struct CfmTlv<'a> {
+    pub tlv_type: u8,
+    pub value: &'a [u8],
+}
+
+/// Iterator over the TLVs of a CFM PDU, stopping at the End TLV.
+#[derive(Debug, Clone)]
+pub struct CfmTlvs<'a> {
+    data: &'a [u8],
+}
+
+impl CfmTlvs<'_> {
+    /// Bounds the walk to [`ParseConfig::max_options`] TLVs.
+    ///
+    /// Fails with [`ParseError::LimitExceeded`] if the TLVs are longer
+    /// than [`ParseConfig::max_options_len`].
+    pub fn limit(self, config: &ParseConfig) -> Result<Limited<Self>, ParseError> {
+        let len = self.data.len();
+        config.limit_options(self, len)
+    }
+}
+
+impl<'a> Iterator for CfmTlvs<'a> {
+    type Item = CfmTlv<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let [tlv_type, hi, lo, ref rest @ ..] = *self.data else {
+            return None;
+        };
+        let len = u16::from_be_bytes([hi, lo]) as usize;
+        let value = rest.get(..len).filter(|_| tlv_type != 0);
+        self.data = value.map_or(&[], |value| &rest[value.len()..]);
+        Some(CfmTlv {
+            tlv_type,
+            value: value?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CfmHdr, CfmMessage, CfmOpcode};
+
+    #[test]
+    fn test_ccm_and_dmr() {
+        let mut ccm = [0u8; 4 + 70 + 8];
+        ccm[..4].copy_from_slice(&[0xa0, 1, 0x04, 70]);
+        ccm[4..10].copy_from_slice(&[0, 0, 0, 9, 0, 101]);
+        ccm[74..].copy_from_slice(&[2, 0, 1, 1, 4, 0, 1, 2]); // Port Status, Interface Status
+        let (hdr, msg, mut tlvs) = CfmHdr::parse(&ccm).unwrap();
+        assert_eq!(hdr.md_level(), 5);
+        assert_eq!(hdr.opcode(), Some(CfmOpcode::Ccm));
+        assert_eq!(hdr.ccm_interval(), 4);
+        match msg {
+            CfmMessage::Ccm(body) => {
+                assert_eq!(body.seq.to_bits(), 9);
+                assert_eq!(body.mep_id(), 101);
+            }
+            _ => panic!("expected a CCM"),
+        }
+        assert_eq!(tlvs.next().unwrap().tlv_type, 2);
+        assert_eq!(tlvs.next().unwrap().value, &[2]);
+        assert!(tlvs.next().is_none());
+
+        let mut dmr = [0u8; 4 + 32 + 1];
+        dmr[..4].copy_from_slice(&[0xa0, 46, 0, 32]);
+        dmr[4..12].copy_from_slice(&[0, 0, 0, 1, 0, 0, 0, 0]); // TxTimeStampf 1s
+        dmr[12..20].copy_from_slice(&[0, 0, 0, 1, 0, 0, 0x03, 0xe8]); // RxTimeStampf
+        dmr[20..28].copy_from_slice(&[0, 0, 0, 1, 0, 0, 0x07, 0xd0]); // TxTimeStampb
+        let (_, msg, mut tlvs) = CfmHdr::parse(&dmr).unwrap();
+        match msg {
+            CfmMessage::Dmr(body) => assert_eq!(body.frame_delay(1_000_005_000), 4000),
+            _ => panic!("expected a DMR"),
+        }
+        assert!(tlvs.next().is_none());
+    }
+}
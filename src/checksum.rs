@@ -0,0 +1,197 @@
+//! Internet checksum ([RFC 1071](https://datatracker.ietf.org/doc/html/rfc1071))
+//! helpers.
+//!
+//! The functions are `no_std` and free of allocations, so they can be used
+//! from eBPF programs. NAT-style rewrites should use the incremental
+//! updates, e.g. [`csum_replace_ipv4_addr`], rather than summing the whole
+//! packet again.
+
+use core::net::{Ipv4Addr, Ipv6Addr};
+
+use crate::ip::IpProto;
+
+/// Adds `data`, read as a sequence of big-endian 16-bit words, to the ones'
+/// complement sum `initial`. An odd trailing byte is padded with zero.
+///
+/// The result is not folded, use [`fold`] to turn it into a checksum.
+#[inline]
+pub fn sum(data: &[u8], initial: u32) -> u32 {
+    let mut acc = initial as u64;
+    let mut chunks = data.chunks_exact(2);
+    for word in &mut chunks {
+        acc += u16::from_be_bytes([word[0], word[1]]) as u64;
+    }
+    if let [last] = chunks.remainder() {
+        acc += (*last as u64) << 8;
+    }
+    while acc >> 32 != 0 {
+        acc = (acc & 0xffff_ffff) + (acc >> 32);
+    }
+    acc as u32
+}
+
+/// Folds a 32-bit ones' complement sum into 16 bits and complements it.
+#[inline]
+pub const fn fold(sum: u32) -> u16 {
+    let sum = (sum & 0xffff) + (sum >> 16);
+    let sum = (sum & 0xffff) + (sum >> 16);
+    !(sum as u16)
+}
+
+/// Computes the Internet checksum of `data`.
+///
+/// Computing the checksum over data which includes a correct checksum
+/// yields 0.
+#[inline]
+pub fn checksum(data: &[u8]) -> u16 {
+    fold(sum(data, 0))
+}
+
+/// Updates the checksum `check` after a 16-bit word of the covered data
+/// changed from `old` to `new`, without summing the data again
+/// ([RFC 1624](https://datatracker.ietf.org/doc/html/rfc1624), equation 3).
+#[inline]
+pub const fn update(check: u16, old: u16, new: u16) -> u16 {
+    fold(!check as u32 + !old as u32 + new as u32)
+}
+
+/// Same as [`update`], named after the Linux kernel helper.
+#[inline]
+pub const fn csum_replace_u16(check: u16, old: u16, new: u16) -> u16 {
+    update(check, old, new)
+}
+
+/// Updates the checksum `check` after a 32-bit word of the covered data,
+/// aligned on 16 bits, changed from `old` to `new`.
+#[inline]
+pub const fn csum_replace_u32(check: u16, old: u32, new: u32) -> u16 {
+    let old = !old;
+    fold(!check as u32 + (old >> 16) + (old & 0xffff) + (new >> 16) + (new & 0xffff))
+}
+
+/// Updates the checksum `check` after an IPv4 address covered by it was
+/// rewritten, e.g. the IP header checksum or the TCP and UDP checksums
+/// through the pseudo-header.
+#[inline]
+pub const fn csum_replace_ipv4_addr(check: u16, old: Ipv4Addr, new: Ipv4Addr) -> u16 {
+    csum_replace_u32(check, old.to_bits(), new.to_bits())
+}
+
+/// Updates the TCP, UDP or ICMPv6 checksum `check` after an IPv6 address
+/// of the pseudo-header was rewritten.
+#[inline]
+pub const fn csum_replace_ipv6_addr(check: u16, old: Ipv6Addr, new: Ipv6Addr) -> u16 {
+    let (old, new) = (old.to_bits(), new.to_bits());
+    let mut check = check;
+    let mut i = 0;
+    while i < 4 {
+        let shift = 96 - i * 32;
+        check = csum_replace_u32(check, (old >> shift) as u32, (new >> shift) as u32);
+        i += 1;
+    }
+    check
+}
+
+/// Ones' complement sum of the IPv4 pseudo-header used by upper-layer
+/// checksums, to be passed as `initial` to [`sum`].
+#[inline]
+pub fn pseudo_header_v4(src: Ipv4Addr, dst: Ipv4Addr, proto: IpProto, len: u16) -> u32 {
+    let s = sum(&src.octets(), 0);
+    let s = sum(&dst.octets(), s);
+    let s = sum(&[0, proto as u8], s);
+    sum(&len.to_be_bytes(), s)
+}
+
+/// Ones' complement sum of the IPv6 pseudo-header used by upper-layer
+/// checksums, to be passed as `initial` to [`sum`].
+#[inline]
+pub fn pseudo_header_v6(src: Ipv6Addr, dst: Ipv6Addr, next_hdr: IpProto, len: u32) -> u32 {
+    let s = sum(&src.octets(), 0);
+    let s = sum(&dst.octets(), s);
+    let s = sum(&len.to_be_bytes(), s);
+    sum(&[0, 0, 0, next_hdr as u8], s)
+}
+
+/// Computes the checksum of a TCP or UDP `segment` (header and payload)
+/// carried over IPv4, its checksum field being zero.
+///
+/// A computed UDP checksum of 0 is sent as `0xffff`, 0 meaning that the
+/// datagram has no checksum.
+#[inline]
+pub fn l4_checksum_v4(src: Ipv4Addr, dst: Ipv4Addr, proto: IpProto, segment: &[u8]) -> u16 {
+    let check = fold(sum(
+        segment,
+        pseudo_header_v4(src, dst, proto, segment.len() as u16),
+    ));
+    udp_nonzero(proto, check)
+}
+
+/// Computes the checksum of a TCP, UDP or ICMPv6 `segment` carried over
+/// IPv6, its checksum field being zero. See [`l4_checksum_v4`].
+#[inline]
+pub fn l4_checksum_v6(src: Ipv6Addr, dst: Ipv6Addr, proto: IpProto, segment: &[u8]) -> u16 {
+    let check = fold(sum(
+        segment,
+        pseudo_header_v6(src, dst, proto, segment.len() as u32),
+    ));
+    udp_nonzero(proto, check)
+}
+
+/// Checks the checksum of a TCP or UDP `segment` carried over IPv4.
+#[inline]
+pub fn verify_l4_v4(src: Ipv4Addr, dst: Ipv4Addr, proto: IpProto, segment: &[u8]) -> bool {
+    fold(sum(
+        segment,
+        pseudo_header_v4(src, dst, proto, segment.len() as u16),
+    )) == 0
+}
+
+/// Checks the checksum of a TCP, UDP or ICMPv6 `segment` carried over IPv6.
+#[inline]
+pub fn verify_l4_v6(src: Ipv6Addr, dst: Ipv6Addr, proto: IpProto, segment: &[u8]) -> bool {
+    fold(sum(
+        segment,
+        pseudo_header_v6(src, dst, proto, segment.len() as u32),
+    )) == 0
+}
+
+#[inline]
+const fn udp_nonzero(proto: IpProto, check: u16) -> u16 {
+    // 0 and 0xffff are the same value in ones' complement.
+    let udp = matches!(proto, IpProto::Udp) as u16;
+    check | ((check == 0) as u16 * udp * 0xffff)
+}
+
+#[cfg(test)]
+mod tests {
+    use core::net::Ipv4Addr;
+
+    use super::{csum_replace_ipv4_addr, l4_checksum_v4, verify_l4_v4};
+    use crate::ip::{IpProto, Ipv4Hdr};
+
+    #[test]
+    fn test_l4_checksum() {
+        let (src, dst) = (Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::new(10, 0, 0, 2));
+        let mut udp = [0x30, 0x39, 0, 53, 0, 10, 0, 0, 0xab, 0xcd];
+        let check = l4_checksum_v4(src, dst, IpProto::Udp, &udp);
+        udp[6..8].copy_from_slice(&check.to_be_bytes());
+        assert!(verify_l4_v4(src, dst, IpProto::Udp, &udp));
+
+        // SNAT to 192.0.2.1, updating the checksum incrementally.
+        let nat = Ipv4Addr::new(192, 0, 2, 1);
+        let check = csum_replace_ipv4_addr(check, src, nat);
+        udp[6..8].copy_from_slice(&check.to_be_bytes());
+        assert!(verify_l4_v4(nat, dst, IpProto::Udp, &udp));
+        assert!(!verify_l4_v4(src, dst, IpProto::Udp, &udp));
+
+        let mut ip = Ipv4Hdr::parse_const(&[
+            0x45, 0, 0, 30, 0, 0, 0x40, 0, 64, 17, 0, 0, 10, 0, 0, 1, 10, 0, 0, 2,
+        ]);
+        assert!(!ip.verify_checksum());
+        ip.check = ip.compute_checksum().into();
+        assert!(ip.verify_checksum());
+        let check = csum_replace_ipv4_addr(ip.check.to_bits(), src, nat);
+        ip.src_addr = nat;
+        assert_eq!(check, ip.compute_checksum());
+    }
+}
@@ -0,0 +1,453 @@
+//! Packet classifier matching prioritized n-tuple rules.
+//!
+//! Rules match the ingress VLAN, source and destination prefixes, protocol,
+//! port ranges and DSCP of a packet, any of which may be a wildcard. The
+//! classifier uses tuple space search: rules with the same set of exactly
+//! matched fields and prefix lengths share a tuple, in which they are found
+//! by a single lookup of the masked packet fields. Tuples are visited in
+//! the order of their best rule, so that the search stops as soon as no
+//! remaining tuple can hold a better match. Port ranges, which do not fit a
+//! lookup, are checked on the few rules sharing the masked fields.
+
+use alloc::{collections::BTreeMap, vec::Vec};
+use core::{
+    net::{IpAddr, Ipv4Addr},
+    ops::RangeInclusive,
+};
+
+use crate::{flow::FlowKey, ip::IpProto, packet::Packet, qos};
+
+/// IPv4 or IPv6 prefix.
+#[derive(PartialEq, Eq, Hash, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct IpPrefix {
+    addr: IpAddr,
+    len: u8,
+}
+
+impl IpPrefix {
+    /// Creates the prefix of length `len` of `addr`, clearing the host bits.
+    /// Returns `None` if `len` exceeds the address length.
+    pub fn new(addr: IpAddr, len: u8) -> Option<Self> {
+        let addr = match addr {
+            IpAddr::V4(v4) if len <= 32 => {
+                let mask = u32::MAX.checked_shl(32 - len as u32).unwrap_or(0);
+                IpAddr::V4(Ipv4Addr::from(u32::from(v4) & mask))
+            }
+            IpAddr::V6(v6) if len <= 128 => {
+                let mask = u128::MAX.checked_shl(128 - len as u32).unwrap_or(0);
+                IpAddr::V6((u128::from(v6) & mask).into())
+            }
+            _ => return None,
+        };
+        Some(Self { addr, len })
+    }
+
+    #[inline]
+    pub const fn addr(&self) -> IpAddr {
+        self.addr
+    }
+
+    #[inline]
+    pub const fn prefix_len(&self) -> u8 {
+        self.len
+    }
+
+    /// Whether `addr` belongs to the prefix.
+    #[inline]
+    pub fn contains(&self, addr: IpAddr) -> bool {
+        IpPrefix::new(addr, self.len).is_some_and(|prefix| prefix.addr == self.addr)
+    }
+}
+
+/// Fields of a packet matched by the rules.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub struct ClassifyKey {
+    /// VLAN the frame was received on.
+    pub vlan: Option<u16>,
+    pub flow: FlowKey,
+    pub dscp: u8,
+}
+
+impl ClassifyKey {
+    /// Extracts the fields of a parsed IPv4 or IPv6 frame.
+    pub fn from_packet(packet: &Packet<'_>) -> Option<Self> {
+        let ip = packet.data().get(packet.l3_offset()?..)?;
+        Some(Self {
+            vlan: packet.vid(),
+            flow: FlowKey::from_ip(ip)?,
+            dscp: qos::dscp(ip)?,
+        })
+    }
+}
+
+/// Classification rule, whose unset fields are wildcards.
+///
+/// Among the matching rules, the one with the lowest `priority` wins, and
+/// then the one inserted first.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Rule {
+    pub priority: u32,
+    pub vlan: Option<u16>,
+    pub src: Option<IpPrefix>,
+    pub dst: Option<IpPrefix>,
+    pub proto: Option<IpProto>,
+    pub src_ports: RangeInclusive<u16>,
+    pub dst_ports: RangeInclusive<u16>,
+    pub dscp: Option<u8>,
+}
+
+impl Rule {
+    /// Creates a rule matching every packet.
+    pub fn new(priority: u32) -> Self {
+        Self {
+            priority,
+            vlan: None,
+            src: None,
+            dst: None,
+            proto: None,
+            src_ports: 0..=u16::MAX,
+            dst_ports: 0..=u16::MAX,
+            dscp: None,
+        }
+    }
+
+    /// Whether the rule matches `key`.
+    pub fn matches(&self, key: &ClassifyKey) -> bool {
+        self.vlan.is_none_or(|vlan| key.vlan == Some(vlan))
+            && self.src.is_none_or(|src| src.contains(key.flow.src_addr))
+            && self.dst.is_none_or(|dst| dst.contains(key.flow.dst_addr))
+            && self.proto.is_none_or(|proto| proto == key.flow.proto)
+            && self.dscp.is_none_or(|dscp| dscp == key.dscp)
+            && self.ports_match(key)
+    }
+
+    #[inline]
+    fn ports_match(&self, key: &ClassifyKey) -> bool {
+        self.src_ports.contains(&key.flow.src_port) && self.dst_ports.contains(&key.flow.dst_port)
+    }
+
+    fn shape(&self) -> Shape {
+        let prefix = |prefix: Option<IpPrefix>| prefix.map(|p| (p.addr.is_ipv6(), p.len));
+        Shape {
+            vlan: self.vlan.is_some(),
+            src: prefix(self.src),
+            dst: prefix(self.dst),
+            proto: self.proto.is_some(),
+            dscp: self.dscp.is_some(),
+        }
+    }
+
+    fn masked(&self) -> Masked {
+        let addr = |prefix: Option<IpPrefix>| prefix.map(|p| to_bits(p.addr));
+        Masked {
+            vlan: self.vlan,
+            src: addr(self.src),
+            dst: addr(self.dst),
+            proto: self.proto.map(|proto| proto as u8),
+            dscp: self.dscp,
+        }
+    }
+}
+
+/// Identifier of a rule, returned by [`Classifier::insert`].
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Copy, Clone)]
+pub struct RuleId(usize);
+
+/// Fields matched exactly by the rules of a tuple, with the family and
+/// length of the prefixes.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+struct Shape {
+    vlan: bool,
+    src: Option<(bool, u8)>,
+    dst: Option<(bool, u8)>,
+    proto: bool,
+    dscp: bool,
+}
+
+impl Shape {
+    /// Fields of `key` under the shape, `None` if an address is not of the
+    /// family of the prefix.
+    fn mask(&self, key: &ClassifyKey) -> Option<Masked> {
+        let addr = |prefix: Option<(bool, u8)>, addr: IpAddr| match prefix {
+            None => Some(None),
+            Some((v6, len)) if v6 == addr.is_ipv6() => {
+                IpPrefix::new(addr, len).map(|p| Some(to_bits(p.addr)))
+            }
+            Some(_) => None,
+        };
+        Some(Masked {
+            vlan: if self.vlan { Some(key.vlan?) } else { None },
+            src: addr(self.src, key.flow.src_addr)?,
+            dst: addr(self.dst, key.flow.dst_addr)?,
+            proto: self.proto.then_some(key.flow.proto as u8),
+            dscp: self.dscp.then_some(key.dscp),
+        })
+    }
+}
+
+/// Packet fields under the shape of a tuple.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Copy, Clone)]
+struct Masked {
+    vlan: Option<u16>,
+    src: Option<u128>,
+    dst: Option<u128>,
+    proto: Option<u8>,
+    dscp: Option<u8>,
+}
+
+#[inline]
+fn to_bits(addr: IpAddr) -> u128 {
+    match addr {
+        IpAddr::V4(v4) => u32::from(v4) as u128,
+        IpAddr::V6(v6) => u128::from(v6),
+    }
+}
+
+/// Rank of a rule, the lowest being the best.
+type Rank = (u32, usize);
+
+#[derive(Debug, Clone)]
+struct Tuple {
+    shape: Shape,
+    /// Rank of the best rule of the tuple.
+    best: Rank,
+    /// Ranks of the rules of each masked key, sorted.
+    buckets: BTreeMap<Masked, Vec<Rank>>,
+}
+
+/// Classifier returning the action `T` of the best matching rule.
+///
+/// ```
+/// use core::net::Ipv4Addr;
+/// use ether_packet::{
+///     classifier::{Classifier, ClassifyKey, IpPrefix, Rule},
+///     flow::FlowKey,
+///     ip::IpProto,
+/// };
+///
+/// let mut classifier = Classifier::new();
+/// let net = IpPrefix::new(Ipv4Addr::new(10, 0, 0, 0).into(), 8).unwrap();
+/// classifier.insert(Rule { dst: Some(net), proto: Some(IpProto::Tcp), dst_ports: 80..=80, ..Rule::new(10) }, "web");
+/// classifier.insert(Rule { dst: Some(net), ..Rule::new(20) }, "internal");
+///
+/// let flow = FlowKey {
+///     src_addr: Ipv4Addr::new(192, 0, 2, 1).into(),
+///     dst_addr: Ipv4Addr::new(10, 1, 2, 3).into(),
+///     proto: IpProto::Tcp,
+///     src_port: 49152,
+///     dst_port: 80,
+/// };
+/// let key = ClassifyKey { vlan: None, flow, dscp: 0 };
+/// assert_eq!(classifier.classify(&key), Some(&"web"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Classifier<T> {
+    /// Rules and actions, indexed by the insertion sequence number.
+    rules: Vec<Option<(Rule, T)>>,
+    /// Tuples, sorted by their best rule.
+    tuples: Vec<Tuple>,
+    len: usize,
+}
+
+impl<T> Default for Classifier<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Classifier<T> {
+    pub const fn new() -> Self {
+        Self {
+            rules: Vec::new(),
+            tuples: Vec::new(),
+            len: 0,
+        }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[inline]
+    pub fn get(&self, id: RuleId) -> Option<&(Rule, T)> {
+        self.rules.get(id.0)?.as_ref()
+    }
+
+    /// Adds a rule with its action.
+    pub fn insert(&mut self, rule: Rule, action: T) -> RuleId {
+        let rank = (rule.priority, self.rules.len());
+        let shape = rule.shape();
+        let i = match self.tuples.iter().position(|t| t.shape == shape) {
+            Some(i) => i,
+            None => {
+                self.tuples.push(Tuple {
+                    shape,
+                    best: rank,
+                    buckets: BTreeMap::new(),
+                });
+                self.tuples.len() - 1
+            }
+        };
+        let tuple = &mut self.tuples[i];
+        let bucket = tuple.buckets.entry(rule.masked()).or_default();
+        let pos = bucket.partition_point(|r| *r < rank);
+        bucket.insert(pos, rank);
+        tuple.best = tuple.best.min(rank);
+        self.rules.push(Some((rule, action)));
+        self.len += 1;
+        self.sort_tuples();
+        RuleId(rank.1)
+    }
+
+    /// Removes a rule, returning it with its action.
+    pub fn remove(&mut self, id: RuleId) -> Option<(Rule, T)> {
+        let (rule, action) = self.rules.get_mut(id.0)?.take()?;
+        let shape = rule.shape();
+        let masked = rule.masked();
+        if let Some(i) = self.tuples.iter().position(|t| t.shape == shape) {
+            let tuple = &mut self.tuples[i];
+            if let Some(bucket) = tuple.buckets.get_mut(&masked) {
+                bucket.retain(|&(_, seq)| seq != id.0);
+                if bucket.is_empty() {
+                    tuple.buckets.remove(&masked);
+                }
+            }
+            match tuple.buckets.values().filter_map(|b| b.first()).min() {
+                Some(&best) => tuple.best = best,
+                None => {
+                    self.tuples.remove(i);
+                }
+            }
+            self.sort_tuples();
+        }
+        self.len -= 1;
+        Some((rule, action))
+    }
+
+    #[inline]
+    fn sort_tuples(&mut self) {
+        self.tuples.sort_by_key(|t| t.best);
+    }
+
+    /// Returns the identifier and the action of the best rule matching
+    /// `key`.
+    pub fn lookup(&self, key: &ClassifyKey) -> Option<(RuleId, &T)> {
+        let mut found: Option<Rank> = None;
+        for tuple in &self.tuples {
+            if found.is_some_and(|found| found < tuple.best) {
+                break;
+            }
+            let Some(bucket) = tuple.shape.mask(key).and_then(|m| tuple.buckets.get(&m)) else {
+                continue;
+            };
+            let matched = bucket.iter().find(|&&(_, seq)| {
+                self.rules[seq]
+                    .as_ref()
+                    .is_some_and(|(rule, _)| rule.ports_match(key))
+            });
+            if let Some(&rank) = matched {
+                found = Some(found.map_or(rank, |found| found.min(rank)));
+            }
+        }
+        let seq = found?.1;
+        self.rules[seq]
+            .as_ref()
+            .map(|(_, action)| (RuleId(seq), action))
+    }
+
+    /// Returns the action of the best rule matching `key`.
+    #[inline]
+    pub fn classify(&self, key: &ClassifyKey) -> Option<&T> {
+        self.lookup(key).map(|(_, action)| action)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::net::{IpAddr, Ipv4Addr};
+
+    use super::{Classifier, ClassifyKey, IpPrefix, Rule};
+    use crate::{flow::FlowKey, ip::IpProto};
+
+    fn key(dst: [u8; 4], proto: IpProto, dst_port: u16, vlan: Option<u16>) -> ClassifyKey {
+        ClassifyKey {
+            vlan,
+            flow: FlowKey {
+                src_addr: Ipv4Addr::new(192, 0, 2, 1).into(),
+                dst_addr: Ipv4Addr::from(dst).into(),
+                proto,
+                src_port: 40000,
+                dst_port,
+            },
+            dscp: 46,
+        }
+    }
+
+    #[test]
+    fn test_classifier() {
+        let prefix = |a: [u8; 4], len| IpPrefix::new(IpAddr::from(a), len).unwrap();
+        assert_eq!(prefix([10, 1, 2, 3], 8).addr(), IpAddr::from([10, 0, 0, 0]));
+        assert!(IpPrefix::new("::1".parse().unwrap(), 129).is_none());
+
+        let mut classifier = Classifier::new();
+        let any = classifier.insert(Rule::new(u32::MAX), "default");
+        classifier.insert(
+            Rule {
+                dst: Some(prefix([10, 0, 0, 0], 8)),
+                ..Rule::new(100)
+            },
+            "internal",
+        );
+        let voice = classifier.insert(
+            Rule {
+                vlan: Some(20),
+                dscp: Some(46),
+                proto: Some(IpProto::Udp),
+                dst_ports: 16384..=32767,
+                ..Rule::new(10)
+            },
+            "voice",
+        );
+        // Same tuple and masked fields as "internal", other ports.
+        classifier.insert(
+            Rule {
+                dst: Some(prefix([10, 9, 9, 9], 8)),
+                dst_ports: 22..=22,
+                ..Rule::new(50)
+            },
+            "ssh",
+        );
+        // IPv6 prefixes never match IPv4 packets.
+        classifier.insert(
+            Rule {
+                dst: IpPrefix::new("::".parse().unwrap(), 0),
+                ..Rule::new(0)
+            },
+            "v6",
+        );
+        assert_eq!(classifier.len(), 5);
+
+        let voip = key([10, 0, 0, 5], IpProto::Udp, 20000, Some(20));
+        assert_eq!(classifier.classify(&voip), Some(&"voice"));
+        let other_vlan = key([10, 0, 0, 5], IpProto::Udp, 20000, Some(30));
+        assert_eq!(classifier.classify(&other_vlan), Some(&"internal"));
+        let ssh = key([10, 0, 0, 5], IpProto::Tcp, 22, None);
+        assert_eq!(classifier.classify(&ssh), Some(&"ssh"));
+        let outside = key([8, 8, 8, 8], IpProto::Udp, 20000, None);
+        assert_eq!(classifier.lookup(&outside), Some((any, &"default")));
+
+        assert_eq!(classifier.remove(voice).unwrap().1, "voice");
+        assert_eq!(classifier.remove(voice), None);
+        assert_eq!(classifier.classify(&voip), Some(&"internal"));
+        classifier.remove(any);
+        assert_eq!(classifier.classify(&outside), None);
+        assert_eq!(classifier.len(), 3);
+    }
+}
@@ -0,0 +1,506 @@
+//! Metadata passed from an XDP or TC program to userspace.
+//!
+//! [`DatapathMeta`] has a fixed `#[repr(C)]` layout, so that the eBPF
+//! program can write it with the types of this crate, into a perf or ring
+//! buffer, and userspace can read it back with [`Header::from_bytes`]. Both
+//! sides then share one definition instead of keeping two in sync.
+//!
+//! [`DatapathFlowKey`], [`DatapathFlowStats`] and [`DatapathTunnel`] are
+//! laid out the same way, as key and value types of the eBPF maps the
+//! program and userspace share.
+//!
+//! Unlike the protocol headers, the fields are in host byte order, as the
+//! structures never leave the host.
+//!
+//! [`Header::from_bytes`]: crate::header::Header::from_bytes
+
+use core::{
+    mem,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    time::Duration,
+};
+
+use crate::{
+    flow::{FlowKey, TunnelId},
+    header::impl_header,
+    ip::IpProto,
+};
+
+/// Decision taken by the eBPF program, using the values of the XDP actions.
+#[repr(u8)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum Verdict {
+    Aborted = 0,
+    Drop = 1,
+    Pass = 2,
+    /// Sent back out of the receiving interface.
+    Tx = 3,
+    Redirect = 4,
+}
+
+impl TryFrom<u8> for Verdict {
+    type Error = ();
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Verdict::Aborted),
+            1 => Ok(Verdict::Drop),
+            2 => Ok(Verdict::Pass),
+            3 => Ok(Verdict::Tx),
+            4 => Ok(Verdict::Redirect),
+            _ => Err(()),
+        }
+    }
+}
+
+const TUNNEL_NONE: u8 = 0;
+const TUNNEL_VNI: u8 = 1;
+const TUNNEL_TEID: u8 = 2;
+const TUNNEL_SPI: u8 = 3;
+const TUNNEL_GRE_KEY: u8 = 4;
+const TUNNEL_MPLS_LABEL: u8 = 5;
+
+/// Metadata of a packet, as recorded by the eBPF datapath.
+///
+/// Enumerations are stored as integers, which keeps every byte pattern
+/// valid, and are decoded by the accessors.
+/// ```text
+///  0               1               2               3
+/// +---------------+---------------+---------------+---------------+
+/// |    Version    |  IP version   |     Proto     |    Verdict    |
+/// +---------------+---------------+---------------+---------------+
+/// |                            Ifindex                            |
+/// +---------------------------------------------------------------+
+/// |                     Source address (16)                       |
+/// +---------------------------------------------------------------+
+/// |                   Destination address (16)                    |
+/// +-------------------------------+-------------------------------+
+/// |          Source port          |       Destination port        |
+/// +---------------+---------------+-------------------------------+
+/// |  Tunnel kind  |    Reason     |           VLAN TCI            |
+/// +---------------+---------------+-------------------------------+
+/// |                           Tunnel ID                           |
+/// +---------------------------------------------------------------+
+/// |                             Mark                              |
+/// +---------------------------------------------------------------+
+/// |                         Packet length                         |
+/// +---------------------------------------------------------------+
+/// |                           Reserved                            |
+/// +---------------------------------------------------------------+
+/// |                        Timestamp (ns)                         |
+/// |                                                               |
+/// +---------------------------------------------------------------+
+/// ```
+#[repr(C, packed)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct DatapathMeta {
+    /// [`DatapathMeta::VERSION`], bumped on any change of the layout.
+    pub version: u8,
+    /// 4 or 6, 0 if the packet is not IP and the flow fields are unset.
+    pub ip_version: u8,
+    pub proto: u8,
+    /// A [`Verdict`].
+    pub verdict: u8,
+    /// Interface the packet was received on.
+    pub ifindex: u32,
+    /// IPv4 addresses occupy the first 4 bytes, the rest being zero.
+    pub src_addr: [u8; 16],
+    pub dst_addr: [u8; 16],
+    pub src_port: u16,
+    pub dst_port: u16,
+    /// Kind of `tunnel_id`, see [`DatapathMeta::tunnel`].
+    pub tunnel_kind: u8,
+    /// Reason of the verdict, defined by the eBPF program.
+    pub reason: u8,
+    /// Tag control information of the outermost VLAN tag, 0 if untagged.
+    pub vlan_tci: u16,
+    pub tunnel_id: u32,
+    /// Mark set on the packet, e.g. `skb->mark`.
+    pub mark: u32,
+    pub pkt_len: u32,
+    pub _reserved: u32,
+    /// Time of the decision, from `bpf_ktime_get_ns`.
+    pub timestamp_ns: u64,
+}
+
+impl DatapathMeta {
+    pub const LEN: usize = mem::size_of::<DatapathMeta>();
+    pub const VERSION: u8 = 1;
+
+    /// Metadata with every field unset.
+    pub const fn new() -> Self {
+        Self {
+            version: Self::VERSION,
+            ip_version: 0,
+            proto: 0,
+            verdict: Verdict::Pass as u8,
+            ifindex: 0,
+            src_addr: [0; 16],
+            dst_addr: [0; 16],
+            src_port: 0,
+            dst_port: 0,
+            tunnel_kind: TUNNEL_NONE,
+            reason: 0,
+            vlan_tci: 0,
+            tunnel_id: 0,
+            mark: 0,
+            pkt_len: 0,
+            _reserved: 0,
+            timestamp_ns: 0,
+        }
+    }
+
+    #[inline]
+    pub fn verdict(&self) -> Option<Verdict> {
+        self.verdict.try_into().ok()
+    }
+
+    #[inline]
+    pub fn set_verdict(&mut self, verdict: Verdict) {
+        self.verdict = verdict as u8;
+    }
+
+    /// Returns the 5-tuple, if the packet is IP.
+    pub fn flow_key(&self) -> Option<FlowKey> {
+        Some(FlowKey {
+            src_addr: addr_from_bytes(self.ip_version, self.src_addr)?,
+            dst_addr: addr_from_bytes(self.ip_version, self.dst_addr)?,
+            proto: IpProto::from_u8(self.proto)?,
+            src_port: self.src_port,
+            dst_port: self.dst_port,
+        })
+    }
+
+    /// Stores the 5-tuple. The address family of `key` is expected to be
+    /// the same for the source and the destination.
+    pub fn set_flow_key(&mut self, key: &FlowKey) {
+        let (ip_version, src_addr) = addr_to_bytes(key.src_addr);
+        self.ip_version = ip_version;
+        self.src_addr = src_addr;
+        self.dst_addr = addr_to_bytes(key.dst_addr).1;
+        self.proto = key.proto as u8;
+        self.src_port = key.src_port;
+        self.dst_port = key.dst_port;
+    }
+
+    #[inline]
+    pub fn tunnel(&self) -> Option<TunnelId> {
+        DatapathTunnel::new(self.tunnel_kind, self.tunnel_id).into()
+    }
+
+    #[inline]
+    pub fn set_tunnel(&mut self, tunnel: Option<TunnelId>) {
+        let tunnel = DatapathTunnel::from(tunnel);
+        (self.tunnel_kind, self.tunnel_id) = (tunnel.kind, tunnel.id);
+    }
+}
+
+/// IP version and 16 bytes of `addr`, IPv4 addresses occupying the first
+/// 4 bytes.
+fn addr_to_bytes(addr: IpAddr) -> (u8, [u8; 16]) {
+    match addr {
+        IpAddr::V4(addr) => {
+            let mut bytes = [0; 16];
+            bytes[..4].copy_from_slice(&addr.octets());
+            (4, bytes)
+        }
+        IpAddr::V6(addr) => (6, addr.octets()),
+    }
+}
+
+fn addr_from_bytes(ip_version: u8, bytes: [u8; 16]) -> Option<IpAddr> {
+    match ip_version {
+        4 => {
+            let [a, b, c, d, ..] = bytes;
+            Some(IpAddr::V4(Ipv4Addr::new(a, b, c, d)))
+        }
+        6 => Some(IpAddr::V6(Ipv6Addr::from(bytes))),
+        _ => None,
+    }
+}
+
+impl Default for DatapathMeta {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl_header!(
+    DatapathMeta,
+    validate = |b: &[u8]| b[0] == DatapathMeta::VERSION
+);
+
+// Every field is naturally aligned, so that the layout is the same without
+// `packed` on the eBPF side.
+const _: () = assert!(DatapathMeta::LEN == 72);
+
+/// 5-tuple of a flow, as the key of an eBPF hash map.
+///
+/// Keys are hashed and compared as bytes by the kernel, so the padding is
+/// explicit and must stay zero, which the conversion from [`FlowKey`]
+/// ensures.
+/// ```text
+///  0               1               2               3
+/// +---------------------------------------------------------------+
+/// |                     Source address (16)                       |
+/// +---------------------------------------------------------------+
+/// |                   Destination address (16)                    |
+/// +-------------------------------+-------------------------------+
+/// |          Source port          |       Destination port        |
+/// +---------------+---------------+-------------------------------+
+/// |     Proto     |  IP version   |            Padding            |
+/// +---------------+---------------+-------------------------------+
+/// ```
+#[repr(C, packed)]
+#[derive(PartialEq, Eq, Hash, Debug, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct DatapathFlowKey {
+    /// IPv4 addresses occupy the first 4 bytes, the rest being zero.
+    pub src_addr: [u8; 16],
+    pub dst_addr: [u8; 16],
+    pub src_port: u16,
+    pub dst_port: u16,
+    pub proto: u8,
+    /// 4 or 6.
+    pub ip_version: u8,
+    pub _pad: [u8; 2],
+}
+
+impl DatapathFlowKey {
+    pub const LEN: usize = mem::size_of::<DatapathFlowKey>();
+}
+
+impl From<&FlowKey> for DatapathFlowKey {
+    fn from(key: &FlowKey) -> Self {
+        let (ip_version, src_addr) = addr_to_bytes(key.src_addr);
+        Self {
+            src_addr,
+            dst_addr: addr_to_bytes(key.dst_addr).1,
+            src_port: key.src_port,
+            dst_port: key.dst_port,
+            proto: key.proto as u8,
+            ip_version,
+            _pad: [0; 2],
+        }
+    }
+}
+
+impl From<FlowKey> for DatapathFlowKey {
+    #[inline]
+    fn from(key: FlowKey) -> Self {
+        Self::from(&key)
+    }
+}
+
+impl TryFrom<DatapathFlowKey> for FlowKey {
+    type Error = ();
+    fn try_from(key: DatapathFlowKey) -> Result<Self, Self::Error> {
+        Ok(FlowKey {
+            src_addr: addr_from_bytes(key.ip_version, key.src_addr).ok_or(())?,
+            dst_addr: addr_from_bytes(key.ip_version, key.dst_addr).ok_or(())?,
+            proto: IpProto::from_u8(key.proto).ok_or(())?,
+            src_port: key.src_port,
+            dst_port: key.dst_port,
+        })
+    }
+}
+
+/// Counters of a flow, as the value of an eBPF map.
+///
+/// With per-CPU maps, userspace reads one value per CPU and adds them up
+/// with [`DatapathFlowStats::merge`].
+/// ```text
+///  0               1               2               3
+/// +---------------------------------------------------------------+
+/// |                            Packets                            |
+/// |                                                               |
+/// +---------------------------------------------------------------+
+/// |                             Bytes                             |
+/// |                                                               |
+/// +---------------------------------------------------------------+
+/// |                     First timestamp (ns)                      |
+/// |                                                               |
+/// +---------------------------------------------------------------+
+/// |                      Last timestamp (ns)                      |
+/// |                                                               |
+/// +---------------+-----------------------------------------------+
+/// |   TCP flags   |                    Padding                    |
+/// +---------------+                                               |
+/// |                                                               |
+/// +---------------------------------------------------------------+
+/// ```
+#[repr(C, packed)]
+#[derive(PartialEq, Eq, Hash, Debug, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct DatapathFlowStats {
+    pub packets: u64,
+    pub bytes: u64,
+    /// Times of the first and last packets, from `bpf_ktime_get_ns`.
+    pub first_ns: u64,
+    pub last_ns: u64,
+    /// Union of the TCP flags of the packets.
+    pub tcp_flags: u8,
+    pub _pad: [u8; 7],
+}
+
+impl DatapathFlowStats {
+    pub const LEN: usize = mem::size_of::<DatapathFlowStats>();
+
+    /// Counts a packet of `len` bytes seen at `timestamp_ns`, with
+    /// `tcp_flags`, 0 if the flow is not TCP.
+    pub fn record(&mut self, len: u32, timestamp_ns: u64, tcp_flags: u8) {
+        if self.packets == 0 {
+            self.first_ns = timestamp_ns;
+        }
+        self.packets += 1;
+        self.bytes += len as u64;
+        self.last_ns = self.last_ns.max(timestamp_ns);
+        self.tcp_flags |= tcp_flags;
+    }
+
+    /// Adds the counters of `other`, e.g. the value of another CPU.
+    pub fn merge(&mut self, other: &DatapathFlowStats) {
+        if other.packets == 0 {
+            return;
+        }
+        self.first_ns = if self.packets == 0 {
+            other.first_ns
+        } else {
+            self.first_ns.min(other.first_ns)
+        };
+        self.packets += other.packets;
+        self.bytes += other.bytes;
+        self.last_ns = self.last_ns.max(other.last_ns);
+        self.tcp_flags |= other.tcp_flags;
+    }
+
+    /// Time between the first and last packets.
+    #[inline]
+    pub fn duration(&self) -> Duration {
+        Duration::from_nanos(self.last_ns.saturating_sub(self.first_ns))
+    }
+}
+
+/// Tunnel a flow was carried in, as part of an eBPF map key or value.
+/// ```text
+///  0               1               2               3
+/// +---------------+-----------------------------------------------+
+/// |     Kind      |                    Padding                    |
+/// +---------------+-----------------------------------------------+
+/// |                              ID                               |
+/// +---------------------------------------------------------------+
+/// ```
+#[repr(C, packed)]
+#[derive(PartialEq, Eq, Hash, Debug, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct DatapathTunnel {
+    /// Kind of `id`, 0 if the flow is not tunneled.
+    pub kind: u8,
+    pub _pad: [u8; 3],
+    pub id: u32,
+}
+
+impl DatapathTunnel {
+    pub const LEN: usize = mem::size_of::<DatapathTunnel>();
+
+    #[inline]
+    const fn new(kind: u8, id: u32) -> Self {
+        Self {
+            kind,
+            _pad: [0; 3],
+            id,
+        }
+    }
+}
+
+impl From<Option<TunnelId>> for DatapathTunnel {
+    fn from(tunnel: Option<TunnelId>) -> Self {
+        match tunnel {
+            Some(TunnelId::Vni(id)) => Self::new(TUNNEL_VNI, id),
+            Some(TunnelId::Teid(id)) => Self::new(TUNNEL_TEID, id),
+            Some(TunnelId::Spi(id)) => Self::new(TUNNEL_SPI, id),
+            Some(TunnelId::GreKey(id)) => Self::new(TUNNEL_GRE_KEY, id),
+            Some(TunnelId::MplsLabel(id)) => Self::new(TUNNEL_MPLS_LABEL, id),
+            None => Self::new(TUNNEL_NONE, 0),
+        }
+    }
+}
+
+impl From<DatapathTunnel> for Option<TunnelId> {
+    fn from(tunnel: DatapathTunnel) -> Self {
+        let id = tunnel.id;
+        match tunnel.kind {
+            TUNNEL_VNI => Some(TunnelId::Vni(id)),
+            TUNNEL_TEID => Some(TunnelId::Teid(id)),
+            TUNNEL_SPI => Some(TunnelId::Spi(id)),
+            TUNNEL_GRE_KEY => Some(TunnelId::GreKey(id)),
+            TUNNEL_MPLS_LABEL => Some(TunnelId::MplsLabel(id)),
+            _ => None,
+        }
+    }
+}
+
+impl_header!(DatapathFlowKey, DatapathFlowStats, DatapathTunnel);
+
+const _: () = assert!(DatapathFlowKey::LEN == 40);
+const _: () = assert!(DatapathFlowStats::LEN == 40);
+const _: () = assert!(DatapathTunnel::LEN == 8);
+
+#[cfg(test)]
+mod tests {
+    use core::{net::Ipv4Addr, time::Duration};
+
+    use super::{DatapathFlowKey, DatapathFlowStats, DatapathMeta, DatapathTunnel, Verdict};
+    use crate::{
+        flow::{FlowKey, TunnelId},
+        header::Header,
+        ip::IpProto,
+    };
+
+    #[test]
+    fn test_datapath_meta() {
+        let key = FlowKey {
+            src_addr: Ipv4Addr::new(10, 0, 0, 1).into(),
+            dst_addr: Ipv4Addr::new(10, 0, 0, 2).into(),
+            proto: IpProto::Udp,
+            src_port: 5000,
+            dst_port: 53,
+        };
+        let mut meta = DatapathMeta::new();
+        meta.set_flow_key(&key);
+        meta.set_tunnel(Some(TunnelId::Vni(42)));
+        meta.set_verdict(Verdict::Drop);
+        meta.ifindex = 3;
+
+        // As read out of a perf buffer, at an arbitrary alignment.
+        let mut buf = [0u8; DatapathMeta::LEN + 1];
+        buf[1..].copy_from_slice(meta.as_bytes());
+        let read = DatapathMeta::from_bytes(&buf[1..]).unwrap();
+        assert_eq!(read, &meta);
+        assert_eq!(read.flow_key(), Some(key));
+        assert_eq!(read.tunnel(), Some(TunnelId::Vni(42)));
+        assert_eq!(read.verdict(), Some(Verdict::Drop));
+
+        buf[1] = DatapathMeta::VERSION + 1;
+        assert!(DatapathMeta::from_bytes(&buf[1..]).is_none());
+
+        let map_key = DatapathFlowKey::from(&key);
+        assert_eq!(map_key.as_bytes()[36..], [17, 4, 0, 0]);
+        assert_eq!(FlowKey::try_from(map_key), Ok(key));
+        assert_eq!(FlowKey::try_from(DatapathFlowKey::default()), Err(()));
+        let tunnel = DatapathTunnel::from(Some(TunnelId::GreKey(7)));
+        assert_eq!(Option::<TunnelId>::from(tunnel), Some(TunnelId::GreKey(7)));
+
+        let (mut cpu0, mut cpu1) = (DatapathFlowStats::default(), DatapathFlowStats::default());
+        cpu0.record(100, 2_000, 0x10);
+        cpu1.record(60, 1_000, 0x02);
+        cpu1.record(60, 5_000, 0x10);
+        cpu0.merge(&cpu1);
+        cpu0.merge(&DatapathFlowStats::default());
+        assert_eq!({ cpu0.packets }, 3);
+        assert_eq!({ cpu0.bytes }, 220);
+        assert_eq!(cpu0.tcp_flags, 0x12);
+        assert_eq!(cpu0.duration(), Duration::from_nanos(4_000));
+    }
+}
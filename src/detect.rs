@@ -0,0 +1,230 @@
+//! Port-independent detection of application protocols.
+//!
+//! [`detect`] looks at the first payload bytes of a flow for signatures of
+//! well-known protocols: the TLS handshake record, HTTP request and status
+//! lines, the SSH banner, a sane DNS header and the RTP version bits. Each
+//! signature yields a [`Guess`] whose confidence reflects how likely other
+//! traffic is to match it by chance.
+
+use crate::ip::IpProto;
+
+/// Application protocols recognized by [`detect`].
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum AppProto {
+    Tls,
+    /// HTTP/1.x, or HTTP/2 with prior knowledge.
+    Http,
+    Ssh,
+    Dns,
+    Rtp,
+}
+
+/// A protocol guess, with a confidence from 0 to 100.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct Guess {
+    pub proto: AppProto,
+    pub confidence: u8,
+}
+
+impl Guess {
+    #[inline]
+    const fn new(proto: AppProto, confidence: u8) -> Self {
+        Self { proto, confidence }
+    }
+}
+
+const HTTP_METHODS: [&[u8]; 9] = [
+    b"GET ",
+    b"POST ",
+    b"HEAD ",
+    b"PUT ",
+    b"DELETE ",
+    b"OPTIONS ",
+    b"PATCH ",
+    b"CONNECT ",
+    b"TRACE ",
+];
+
+/// Guesses the protocol of a flow from the first payload bytes sent in one
+/// direction, carried over `proto`. Returns the most confident guess, or
+/// `None` if no signature matched.
+///
+/// ```
+/// use ether_packet::{detect::{detect, AppProto}, ip::IpProto};
+///
+/// let guess = detect(IpProto::Tcp, b"SSH-2.0-OpenSSH_9.6\r\n").unwrap();
+/// assert_eq!(guess.proto, AppProto::Ssh);
+/// assert!(guess.confidence >= 90);
+/// ```
+pub fn detect(proto: IpProto, payload: &[u8]) -> Option<Guess> {
+    let guesses: &[Option<Guess>] = match proto {
+        IpProto::Tcp => &[tls(payload), http(payload), ssh(payload), dns_tcp(payload)],
+        IpProto::Udp => &[dns(payload), rtp(payload)],
+        _ => &[],
+    };
+    guesses
+        .iter()
+        .flatten()
+        .copied()
+        .max_by_key(|g| g.confidence)
+}
+
+fn tls(payload: &[u8]) -> Option<Guess> {
+    // Handshake record of SSL 3.0 to TLS 1.3 (which still uses 0x0303).
+    let &[0x16, 0x03, minor, len_hi, len_lo, ..] = payload else {
+        return None;
+    };
+    let len = u16::from_be_bytes([len_hi, len_lo]);
+    if minor > 0x04 || len == 0 || len > 16384 + 2048 {
+        return None;
+    }
+    let confidence = match payload.get(5) {
+        // ClientHello and ServerHello.
+        Some(0x01) | Some(0x02) => 95,
+        Some(_) => 60,
+        None => 50,
+    };
+    Some(Guess::new(AppProto::Tls, confidence))
+}
+
+fn http(payload: &[u8]) -> Option<Guess> {
+    if payload.starts_with(b"PRI * HTTP/2.0\r\n") {
+        return Some(Guess::new(AppProto::Http, 100));
+    }
+    if payload.starts_with(b"HTTP/1.") {
+        return Some(Guess::new(AppProto::Http, 90));
+    }
+    HTTP_METHODS.iter().find(|m| payload.starts_with(m))?;
+    let line = payload.split(|&b| b == b'\n').next().unwrap_or(payload);
+    let confidence = if line.windows(7).any(|w| w == b" HTTP/1") {
+        95
+    } else {
+        75
+    };
+    Some(Guess::new(AppProto::Http, confidence))
+}
+
+fn ssh(payload: &[u8]) -> Option<Guess> {
+    let version = payload.strip_prefix(b"SSH-")?;
+    let confidence = if version.starts_with(b"2.0-") || version.starts_with(b"1.99-") {
+        95
+    } else {
+        70
+    };
+    Some(Guess::new(AppProto::Ssh, confidence))
+}
+
+/// Checks a DNS message over TCP, preceded by its 2-byte length.
+fn dns_tcp(payload: &[u8]) -> Option<Guess> {
+    let (len, msg) = payload.split_first_chunk::<2>()?;
+    if (u16::from_be_bytes(*len) as usize) < msg.len().max(12) {
+        return None;
+    }
+    dns(msg)
+}
+
+fn dns(msg: &[u8]) -> Option<Guess> {
+    let hdr = msg.get(..12)?;
+    let flags = u16::from_be_bytes([hdr[2], hdr[3]]);
+    let count = |i: usize| u16::from_be_bytes([hdr[i], hdr[i + 1]]);
+    let (qr, opcode, z) = (flags >> 15, (flags >> 11) & 0xf, (flags >> 6) & 0x1);
+    let (qdcount, ancount, nscount) = (count(4), count(6), count(8));
+    // Query, inverse query (obsolete), status, notify, update.
+    if !matches!(opcode, 0..=2 | 4 | 5) || z != 0 || qdcount > 1 {
+        return None;
+    }
+    if qr == 0 && (qdcount != 1 || ancount != 0) {
+        return None;
+    }
+
+    let mut confidence = 50;
+    if qdcount == 1 {
+        // Walk the question name, compression is not used in the question.
+        let mut pos = 12;
+        loop {
+            let len = *msg.get(pos)? as usize;
+            if len == 0 {
+                break;
+            }
+            if len > 63 {
+                return None;
+            }
+            pos += 1 + len;
+        }
+        let qclass = u16::from_be_bytes([*msg.get(pos + 3)?, *msg.get(pos + 4)?]);
+        if !matches!(qclass & 0x7fff, 1 | 3 | 4 | 254 | 255) {
+            return None;
+        }
+        confidence = 85;
+    }
+    if qr == 0 && nscount != 0 && opcode != 5 {
+        confidence -= 20;
+    }
+    Some(Guess::new(AppProto::Dns, confidence))
+}
+
+fn rtp(payload: &[u8]) -> Option<Guess> {
+    let &[b0, b1, ..] = payload else {
+        return None;
+    };
+    let (version, csrc_count, pt) = (b0 >> 6, (b0 & 0xf) as usize, b1 & 0x7f);
+    // Payload types 72-76 collide with RTCP packet types.
+    if version != 2 || payload.len() < 12 + 4 * csrc_count || (72..=76).contains(&pt) {
+        return None;
+    }
+    let confidence = match pt {
+        // Static audio and video payload types, and the dynamic range.
+        0..=34 | 96..=127 => 50,
+        _ => 30,
+    };
+    Some(Guess::new(AppProto::Rtp, confidence))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{detect, AppProto, Guess};
+    use crate::ip::IpProto;
+
+    #[test]
+    fn test_detect() {
+        let client_hello = [
+            0x16, 0x03, 0x01, 0x02, 0x00, 0x01, 0x00, 0x01, 0xfc, 0x03, 0x03,
+        ];
+        assert_eq!(
+            detect(IpProto::Tcp, &client_hello),
+            Some(Guess::new(AppProto::Tls, 95))
+        );
+        assert_eq!(
+            detect(IpProto::Tcp, b"GET / HTTP/1.1\r\nHost: a\r\n"),
+            Some(Guess::new(AppProto::Http, 95))
+        );
+
+        #[rustfmt::skip]
+        let query = [
+            0x12, 0x34, 0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0,
+            7, b'e', b'x', b'a', b'm', b'p', b'l', b'e', 3, b'c', b'o', b'm', 0, 0, 1, 0, 1,
+        ];
+        assert_eq!(
+            detect(IpProto::Udp, &query),
+            Some(Guess::new(AppProto::Dns, 85))
+        );
+        let mut framed = [0u8; 31];
+        framed[1] = 29;
+        framed[2..].copy_from_slice(&query);
+        assert_eq!(detect(IpProto::Tcp, &framed).unwrap().proto, AppProto::Dns);
+
+        // PCMU, sequence 1, timestamp 160, SSRC 0xdeadbeef.
+        let rtp = [0x80, 0x00, 0, 1, 0, 0, 0, 160, 0xde, 0xad, 0xbe, 0xef, 0xff];
+        assert_eq!(
+            detect(IpProto::Udp, &rtp),
+            Some(Guess::new(AppProto::Rtp, 50))
+        );
+        // RTCP sender report.
+        assert_eq!(
+            detect(IpProto::Udp, &[0x80, 200, 0, 6, 0, 0, 0, 0, 0, 0, 0, 0]),
+            None
+        );
+    }
+}
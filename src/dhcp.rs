@@ -0,0 +1,450 @@
+//! Dynamic Host Configuration Protocol for IPv4
+//! ([RFC 2131](https://datatracker.ietf.org/doc/html/rfc2131)), with options
+//! as defined in [RFC 2132](https://datatracker.ietf.org/doc/html/rfc2132).
+
+use core::{mem, net::Ipv4Addr};
+
+use crate::{
+    builder::{BuildError, Writer},
+    header::impl_header,
+    types::{U16, U32},
+};
+
+pub const DHCP_SERVER_PORT: u16 = 67;
+pub const DHCP_CLIENT_PORT: u16 = 68;
+/// Marks the start of the options, following the fixed header.
+pub const MAGIC_COOKIE: [u8; 4] = [99, 130, 83, 99];
+/// Minimum size of a BOOTP message, which relay agents and old clients
+/// may require.
+pub const MIN_MSG_LEN: usize = 300;
+
+pub const BOOTREQUEST: u8 = 1;
+pub const BOOTREPLY: u8 = 2;
+/// Hardware type of Ethernet.
+pub const HTYPE_ETHERNET: u8 = 1;
+
+pub const OPT_PAD: u8 = 0;
+pub const OPT_SUBNET_MASK: u8 = 1;
+pub const OPT_ROUTER: u8 = 3;
+pub const OPT_DNS_SERVER: u8 = 6;
+pub const OPT_HOST_NAME: u8 = 12;
+pub const OPT_DOMAIN_NAME: u8 = 15;
+pub const OPT_REQUESTED_IP: u8 = 50;
+pub const OPT_LEASE_TIME: u8 = 51;
+pub const OPT_MSG_TYPE: u8 = 53;
+pub const OPT_SERVER_ID: u8 = 54;
+pub const OPT_PARAM_REQUEST_LIST: u8 = 55;
+pub const OPT_CLIENT_ID: u8 = 61;
+pub const OPT_END: u8 = 255;
+
+/// DHCP message types, carried in option 53.
+#[repr(u8)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum DhcpMsgType {
+    Discover = 1,
+    Offer = 2,
+    Request = 3,
+    Decline = 4,
+    Ack = 5,
+    Nak = 6,
+    Release = 7,
+    Inform = 8,
+}
+
+impl DhcpMsgType {
+    /// BOOTP operation of messages of this type.
+    #[inline]
+    pub const fn op(&self) -> u8 {
+        match self {
+            DhcpMsgType::Offer | DhcpMsgType::Ack | DhcpMsgType::Nak => BOOTREPLY,
+            _ => BOOTREQUEST,
+        }
+    }
+}
+
+impl TryFrom<u8> for DhcpMsgType {
+    type Error = ();
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(DhcpMsgType::Discover),
+            2 => Ok(DhcpMsgType::Offer),
+            3 => Ok(DhcpMsgType::Request),
+            4 => Ok(DhcpMsgType::Decline),
+            5 => Ok(DhcpMsgType::Ack),
+            6 => Ok(DhcpMsgType::Nak),
+            7 => Ok(DhcpMsgType::Release),
+            8 => Ok(DhcpMsgType::Inform),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Fixed part of a DHCP message, followed by the magic cookie and the
+/// options.
+/// ```text
+/// 0                   1                   2                   3
+/// 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +---------------+---------------+---------------+---------------+
+/// |     op (1)    |   htype (1)   |   hlen (1)    |   hops (1)    |
+/// +---------------+---------------+---------------+---------------+
+/// |                            xid (4)                            |
+/// +-------------------------------+-------------------------------+
+/// |           secs (2)            |           flags (2)           |
+/// +-------------------------------+-------------------------------+
+/// |                          ciaddr  (4)                          |
+/// +---------------------------------------------------------------+
+/// |                          yiaddr  (4)                          |
+/// +---------------------------------------------------------------+
+/// |                          siaddr  (4)                          |
+/// +---------------------------------------------------------------+
+/// |                          giaddr  (4)                          |
+/// +---------------------------------------------------------------+
+/// |                          chaddr  (16)                         |
+/// +---------------------------------------------------------------+
+/// |                          sname   (64)                         |
+/// +---------------------------------------------------------------+
+/// |                          file    (128)                        |
+/// +---------------------------------------------------------------+
+/// ```
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct DhcpHdr {
+    /// [`BOOTREQUEST`] or [`BOOTREPLY`].
+    pub op: u8,
+    pub htype: u8,
+    pub hlen: u8,
+    pub hops: u8,
+    /// Transaction ID, chosen by the client.
+    pub xid: U32,
+    pub secs: U16,
+    pub flags: U16,
+    /// Client IP address, when already bound.
+    pub ciaddr: Ipv4Addr,
+    /// Address assigned to the client.
+    pub yiaddr: Ipv4Addr,
+    /// Address of the next server to use in bootstrap.
+    pub siaddr: Ipv4Addr,
+    /// Relay agent address.
+    pub giaddr: Ipv4Addr,
+    /// Client hardware address.
+    pub chaddr: [u8; 16],
+    pub sname: [u8; 64],
+    pub file: [u8; 128],
+}
+
+impl DhcpHdr {
+    pub const LEN: usize = mem::size_of::<DhcpHdr>();
+
+    #[inline]
+    pub const fn xid(&self) -> u32 {
+        self.xid.to_bits()
+    }
+
+    /// **Broadcast**: the client cannot receive unicast replies before
+    /// being configured.
+    #[inline]
+    pub const fn broadcast(&self) -> bool {
+        self.flags.to_bits() & 0x8000 != 0
+    }
+
+    /// Client hardware address, truncated to `hlen`.
+    #[inline]
+    pub fn chaddr(&self) -> &[u8] {
+        &self.chaddr[..(self.hlen as usize).min(16)]
+    }
+}
+
+impl_header!(DhcpHdr);
+
+/// A DHCP option.
+#[derive(Debug, Copy, Clone)]
+pub struct DhcpOption<'a> {
+    pub code: u8,
+    pub data: &'a [u8],
+}
+
+/// Iterator over the options of a DHCP message, stopping at the End option.
+/// Pad options are skipped.
+#[derive(Debug, Copy, Clone)]
+pub struct DhcpOptions<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> DhcpOptions<'a> {
+    /// Options of the DHCP message `msg`, which starts with the fixed
+    /// header. Returns `None` if the magic cookie is missing.
+    pub fn new(msg: &'a [u8]) -> Option<Self> {
+        match msg.get(DhcpHdr::LEN..)? {
+            [99, 130, 83, 99, data @ ..] => Some(Self { data }),
+            _ => None,
+        }
+    }
+
+    /// Type of the message, carried in option 53.
+    pub fn msg_type(&self) -> Option<DhcpMsgType> {
+        DhcpMsgType::try_from(*self.option(OPT_MSG_TYPE)?.first()?).ok()
+    }
+
+    /// Data of the first option with the given code.
+    pub fn option(&self, code: u8) -> Option<&'a [u8]> {
+        self.clone()
+            .find(|opt| opt.code == code)
+            .map(|opt| opt.data)
+    }
+}
+
+impl<'a> Iterator for DhcpOptions<'a> {
+    type Item = DhcpOption<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.data {
+                [OPT_PAD, rest @ ..] => self.data = rest,
+                [code, len, rest @ ..] if *code != OPT_END && *len as usize <= rest.len() => {
+                    let (data, rest) = rest.split_at(*len as usize);
+                    self.data = rest;
+                    return Some(DhcpOption { code: *code, data });
+                }
+                _ => {
+                    self.data = &[];
+                    return None;
+                }
+            }
+        }
+    }
+}
+
+/// Builds a DHCP message into a caller-provided buffer.
+///
+/// ```
+/// use core::net::Ipv4Addr;
+/// use ether_packet::dhcp::{DhcpBuilder, OPT_DNS_SERVER, OPT_ROUTER, OPT_SUBNET_MASK};
+///
+/// let mut buf = [0u8; 576];
+/// let mut discover = DhcpBuilder::discover(&mut buf, 0x1234_5678, [2, 0, 0, 0, 0, 1]).unwrap();
+/// discover
+///     .param_request_list(&[OPT_SUBNET_MASK, OPT_ROUTER, OPT_DNS_SERVER])
+///     .unwrap();
+/// assert_eq!(discover.finish().unwrap(), 300);
+/// ```
+pub struct DhcpBuilder<'a> {
+    w: Writer<'a>,
+}
+
+impl<'a> DhcpBuilder<'a> {
+    /// Starts a message of the given type, sent by or to the Ethernet client
+    /// `chaddr`. The op field is derived from `msg_type`.
+    pub fn new(
+        buf: &'a mut [u8],
+        msg_type: DhcpMsgType,
+        xid: u32,
+        chaddr: [u8; 6],
+    ) -> Result<Self, BuildError> {
+        let mut w = Writer::new(buf);
+        let hdr = w.reserve(DhcpHdr::LEN)?;
+        hdr[0] = msg_type.op();
+        hdr[1] = HTYPE_ETHERNET;
+        hdr[2] = chaddr.len() as u8;
+        hdr[4..8].copy_from_slice(&xid.to_be_bytes());
+        hdr[28..34].copy_from_slice(&chaddr);
+        w.put(&MAGIC_COOKIE)?;
+        let mut builder = Self { w };
+        builder.option(OPT_MSG_TYPE, &[msg_type as u8])?;
+        Ok(builder)
+    }
+
+    /// DHCPDISCOVER asking for broadcast replies.
+    pub fn discover(buf: &'a mut [u8], xid: u32, chaddr: [u8; 6]) -> Result<Self, BuildError> {
+        let mut builder = Self::new(buf, DhcpMsgType::Discover, xid, chaddr)?;
+        builder.broadcast(true);
+        Ok(builder)
+    }
+
+    /// DHCPOFFER of `yiaddr` by the server `server_id`.
+    pub fn offer(
+        buf: &'a mut [u8],
+        xid: u32,
+        chaddr: [u8; 6],
+        yiaddr: Ipv4Addr,
+        server_id: Ipv4Addr,
+    ) -> Result<Self, BuildError> {
+        let mut builder = Self::new(buf, DhcpMsgType::Offer, xid, chaddr)?;
+        builder.yiaddr(yiaddr).server_id(server_id)?;
+        Ok(builder)
+    }
+
+    /// DHCPREQUEST selecting the address `requested` offered by
+    /// `server_id`.
+    pub fn request(
+        buf: &'a mut [u8],
+        xid: u32,
+        chaddr: [u8; 6],
+        requested: Ipv4Addr,
+        server_id: Ipv4Addr,
+    ) -> Result<Self, BuildError> {
+        let mut builder = Self::new(buf, DhcpMsgType::Request, xid, chaddr)?;
+        builder
+            .broadcast(true)
+            .option(OPT_REQUESTED_IP, &requested.octets())?
+            .server_id(server_id)?;
+        Ok(builder)
+    }
+
+    /// DHCPACK of `yiaddr` by the server `server_id`.
+    pub fn ack(
+        buf: &'a mut [u8],
+        xid: u32,
+        chaddr: [u8; 6],
+        yiaddr: Ipv4Addr,
+        server_id: Ipv4Addr,
+    ) -> Result<Self, BuildError> {
+        let mut builder = Self::new(buf, DhcpMsgType::Ack, xid, chaddr)?;
+        builder.yiaddr(yiaddr).server_id(server_id)?;
+        Ok(builder)
+    }
+
+    fn hdr(&mut self) -> &mut [u8] {
+        &mut self.w.written_mut()[..DhcpHdr::LEN]
+    }
+
+    pub fn broadcast(&mut self, val: bool) -> &mut Self {
+        self.hdr()[10] = if val { 0x80 } else { 0 };
+        self
+    }
+
+    pub fn ciaddr(&mut self, addr: Ipv4Addr) -> &mut Self {
+        self.hdr()[12..16].copy_from_slice(&addr.octets());
+        self
+    }
+
+    pub fn yiaddr(&mut self, addr: Ipv4Addr) -> &mut Self {
+        self.hdr()[16..20].copy_from_slice(&addr.octets());
+        self
+    }
+
+    pub fn siaddr(&mut self, addr: Ipv4Addr) -> &mut Self {
+        self.hdr()[20..24].copy_from_slice(&addr.octets());
+        self
+    }
+
+    pub fn giaddr(&mut self, addr: Ipv4Addr) -> &mut Self {
+        self.hdr()[24..28].copy_from_slice(&addr.octets());
+        self
+    }
+
+    /// Appends an option. Pad and End options are written by the builder
+    /// and cannot be added.
+    pub fn option(&mut self, code: u8, data: &[u8]) -> Result<&mut Self, BuildError> {
+        let len = u8::try_from(data.len()).map_err(|_| BuildError::FieldOverflow)?;
+        if code == OPT_PAD || code == OPT_END {
+            return Err(BuildError::FieldOverflow);
+        }
+        let opt = self.w.reserve(2 + data.len())?;
+        opt[0] = code;
+        opt[1] = len;
+        opt[2..].copy_from_slice(data);
+        Ok(self)
+    }
+
+    pub fn server_id(&mut self, addr: Ipv4Addr) -> Result<&mut Self, BuildError> {
+        self.option(OPT_SERVER_ID, &addr.octets())
+    }
+
+    /// Lease time in seconds, `u32::MAX` meaning infinity.
+    pub fn lease_time(&mut self, secs: u32) -> Result<&mut Self, BuildError> {
+        self.option(OPT_LEASE_TIME, &secs.to_be_bytes())
+    }
+
+    pub fn subnet_mask(&mut self, mask: Ipv4Addr) -> Result<&mut Self, BuildError> {
+        self.option(OPT_SUBNET_MASK, &mask.octets())
+    }
+
+    /// Appends an option carrying a list of addresses, such as
+    /// [`OPT_ROUTER`] or [`OPT_DNS_SERVER`].
+    pub fn addr_list(&mut self, code: u8, addrs: &[Ipv4Addr]) -> Result<&mut Self, BuildError> {
+        let len = u8::try_from(addrs.len() * 4).map_err(|_| BuildError::FieldOverflow)?;
+        let opt = self.w.reserve(2 + len as usize)?;
+        opt[0] = code;
+        opt[1] = len;
+        for (dst, addr) in opt[2..].chunks_exact_mut(4).zip(addrs) {
+            dst.copy_from_slice(&addr.octets());
+        }
+        Ok(self)
+    }
+
+    pub fn param_request_list(&mut self, codes: &[u8]) -> Result<&mut Self, BuildError> {
+        self.option(OPT_PARAM_REQUEST_LIST, codes)
+    }
+
+    /// Terminates the options with the End option and pads the message to
+    /// [`MIN_MSG_LEN`], returning its length.
+    pub fn finish(mut self) -> Result<usize, BuildError> {
+        self.w.put_u8(OPT_END)?;
+        let pad = MIN_MSG_LEN.saturating_sub(self.w.pos());
+        self.w.reserve(pad)?;
+        Ok(self.w.pos())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::net::Ipv4Addr;
+
+    use super::{
+        DhcpBuilder, DhcpHdr, DhcpMsgType, DhcpOptions, BOOTREPLY, OPT_DNS_SERVER, OPT_LEASE_TIME,
+        OPT_ROUTER, OPT_SERVER_ID,
+    };
+    use crate::{builder::BuildError, header::Header};
+
+    #[test]
+    fn test_offer_roundtrip() {
+        let mac = [0x02, 0, 0, 0, 0, 1];
+        let server = Ipv4Addr::new(192, 168, 1, 1);
+        let mut buf = [0u8; 576];
+        let mut offer = DhcpBuilder::offer(
+            &mut buf,
+            0xdead_beef,
+            mac,
+            Ipv4Addr::new(192, 168, 1, 100),
+            server,
+        )
+        .unwrap();
+        offer
+            .lease_time(3600)
+            .unwrap()
+            .subnet_mask(Ipv4Addr::new(255, 255, 255, 0))
+            .unwrap()
+            .addr_list(OPT_ROUTER, &[server])
+            .unwrap()
+            .addr_list(
+                OPT_DNS_SERVER,
+                &[Ipv4Addr::new(1, 1, 1, 1), Ipv4Addr::new(8, 8, 8, 8)],
+            )
+            .unwrap();
+        let len = offer.finish().unwrap();
+        assert_eq!(len, 300);
+
+        let hdr = DhcpHdr::from_bytes(&buf).unwrap();
+        assert_eq!(hdr.op, BOOTREPLY);
+        assert_eq!(hdr.xid(), 0xdead_beef);
+        assert_eq!(hdr.chaddr(), &mac);
+        assert_eq!(hdr.yiaddr, Ipv4Addr::new(192, 168, 1, 100));
+        assert!(!hdr.broadcast());
+
+        let opts = DhcpOptions::new(&buf[..len]).unwrap();
+        assert_eq!(opts.msg_type(), Some(DhcpMsgType::Offer));
+        assert_eq!(opts.option(OPT_SERVER_ID), Some(&server.octets()[..]));
+        assert_eq!(
+            opts.option(OPT_LEASE_TIME),
+            Some(&3600u32.to_be_bytes()[..])
+        );
+        assert_eq!(opts.option(OPT_DNS_SERVER).map(<[u8]>::len), Some(8));
+        assert_eq!(opts.count(), 6);
+
+        let mut small = [0u8; 280];
+        let discover = DhcpBuilder::discover(&mut small, 1, mac).unwrap();
+        assert_eq!(discover.finish(), Err(BuildError::BufferTooSmall));
+    }
+}
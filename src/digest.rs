@@ -0,0 +1,135 @@
+//! Digest of the invariant header fields of a packet, identifying the
+//! copies of a packet captured on several taps or mirrored twice.
+//!
+//! The digest covers the IP and transport headers, leaving out what
+//! changes along the path or between capture points: MAC addresses, VLAN
+//! tags, TTL or hop limit, DSCP and ECN, and checksums. The payload is not
+//! covered, so that captures cut at different snapshot lengths still
+//! agree, and packets of a flow are told apart by the IPv4 identification
+//! and the TCP sequence numbers.
+
+use crate::{
+    flow::siphash,
+    packet::{NetworkHdr, Packet, TransportHdr},
+};
+
+/// Fixed key, so that digests are comparable across processes.
+const DIGEST_KEY: [u64; 2] = [0x6574686572, 0x646967657374];
+
+/// Maximum length of the IPv6 extension headers covered by the digest.
+const MAX_EXT_LEN: usize = 64;
+
+/// Invariant fields collected before being hashed.
+struct Fields {
+    buf: [u8; 192],
+    len: usize,
+}
+
+impl Fields {
+    #[inline]
+    fn put(&mut self, bytes: &[u8]) {
+        let end = (self.len + bytes.len()).min(self.buf.len());
+        self.buf[self.len..end].copy_from_slice(&bytes[..end - self.len]);
+        self.len = end;
+    }
+}
+
+/// Returns the digest of the invariant header fields of `packet`.
+///
+/// Frames which are not IP are digested over their EtherType and the data
+/// following it.
+pub fn digest(packet: &Packet<'_>) -> u64 {
+    let mut fields = Fields {
+        buf: [0; 192],
+        len: 0,
+    };
+    let data = packet.data();
+    let (Some(network), Some(l3)) = (packet.network(), packet.l3_offset()) else {
+        let ether_type = packet.ether_type().map_or(0, |t| t as u64);
+        let key = [DIGEST_KEY[0] ^ ether_type, DIGEST_KEY[1]];
+        return siphash(
+            &key,
+            data.get(packet.payload_offset()..).unwrap_or_default(),
+        );
+    };
+    match network {
+        NetworkHdr::Ipv4(hdr) => {
+            let ip = &data[l3..];
+            fields.put(&[4, hdr.proto as u8]);
+            fields.put(&ip[2..8]); // Total length, identification, fragment.
+            fields.put(&hdr.src_addr.octets());
+            fields.put(&hdr.dst_addr.octets());
+            fields.put(ip.get(20..hdr.hdrlen()).unwrap_or_default());
+        }
+        NetworkHdr::Ipv6(hdr) => {
+            fields.put(&[6, hdr.next_hdr as u8]);
+            fields.put(&hdr.payload_len.octets());
+            fields.put(&hdr.src_addr.octets());
+            fields.put(&hdr.dst_addr.octets());
+            let ext_start = l3 + 40;
+            let ext_end = packet.l4_offset().unwrap_or(packet.payload_offset());
+            let ext = data.get(ext_start..ext_end).unwrap_or_default();
+            fields.put(&ext[..ext.len().min(MAX_EXT_LEN)]);
+        }
+    }
+    match packet.transport() {
+        Some(TransportHdr::Tcp(hdr)) => {
+            let tcp = &data[packet.l4_offset().unwrap_or_default()..];
+            fields.put(&tcp[..16]); // Up to the checksum.
+            fields.put(&tcp[18..hdr.hdrlen()]);
+        }
+        Some(TransportHdr::Udp(hdr)) => {
+            fields.put(&hdr.source.octets());
+            fields.put(&hdr.dest.octets());
+            fields.put(&hdr.len.octets());
+        }
+        Some(TransportHdr::Icmp(hdr)) => {
+            fields.put(&[hdr.r#type, hdr.code]);
+            fields.put(&hdr.rest());
+        }
+        Some(TransportHdr::Icmpv6(hdr)) => {
+            fields.put(&[hdr.r#type, hdr.code]);
+            fields.put(&hdr.rest);
+        }
+        Some(TransportHdr::Sctp(hdr)) => {
+            fields.put(&hdr.src_port.octets());
+            fields.put(&hdr.dst_port.octets());
+            fields.put(&hdr.verification_tag.octets());
+        }
+        None => {}
+    }
+    siphash(&DIGEST_KEY, &fields.buf[..fields.len])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::digest;
+    use crate::packet::Packet;
+
+    #[test]
+    fn test_digest() {
+        #[rustfmt::skip]
+        let mut frame = [
+            0, 1, 2, 3, 4, 5, 0, 6, 7, 8, 9, 10, 0x08, 0x00,
+            0x45, 0, 0, 40, 0x12, 0x34, 0x40, 0, 64, 6, 0xaa, 0xbb, 10, 0, 0, 1, 10, 0, 0, 2,
+            0x04, 0xd2, 0x00, 0x50, 0, 0, 0, 1, 0, 0, 0, 0, 0x50, 0x02, 0xff, 0xff, 0xcc, 0xdd,
+            0, 0,
+        ];
+        let original = digest(&Packet::parse(&frame).unwrap());
+
+        // Another tap: other MACs, TTL, DSCP and checksums, plus a VLAN tag.
+        let mut tagged = [0u8; 58];
+        tagged[..12].copy_from_slice(&[9; 12]);
+        tagged[12..16].copy_from_slice(&[0x81, 0x00, 0x00, 0x64]);
+        tagged[16..].copy_from_slice(&frame[12..]);
+        tagged[19] = 0xb8;
+        tagged[26] = 63;
+        tagged[28..30].copy_from_slice(&[0x11, 0x22]);
+        tagged[54..56].copy_from_slice(&[0x33, 0x44]);
+        assert_eq!(digest(&Packet::parse(&tagged).unwrap()), original);
+
+        // The next segment of the flow.
+        frame[41] = 2;
+        assert_ne!(digest(&Packet::parse(&frame).unwrap()), original);
+    }
+}
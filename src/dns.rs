@@ -0,0 +1,542 @@
+//! Domain Name System
+//! ([RFC 1035](https://datatracker.ietf.org/doc/html/rfc1035)) messages.
+
+use core::{
+    mem,
+    net::{Ipv4Addr, Ipv6Addr},
+};
+
+use crate::{
+    builder::{BuildError, Writer},
+    header::{impl_header, ParseConfig, ParseError},
+    types::U16,
+};
+
+pub const DNS_PORT: u16 = 53;
+
+/// **QR**: the message is a response.
+pub const FLAG_QR: u16 = 0x8000;
+/// **AA**: authoritative answer.
+pub const FLAG_AA: u16 = 0x0400;
+/// **TC**: the message was truncated.
+pub const FLAG_TC: u16 = 0x0200;
+/// **RD**: recursion desired.
+pub const FLAG_RD: u16 = 0x0100;
+/// **RA**: recursion available.
+pub const FLAG_RA: u16 = 0x0080;
+
+/// Maximum length of an encoded domain name.
+pub const MAX_NAME_LEN: usize = 255;
+
+/// Resource record types.
+#[repr(u16)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum DnsType {
+    A = 1,
+    Ns = 2,
+    Cname = 5,
+    Soa = 6,
+    Ptr = 12,
+    Mx = 15,
+    Txt = 16,
+    Aaaa = 28,
+    Srv = 33,
+    Opt = 41,
+    Any = 255,
+}
+
+impl TryFrom<u16> for DnsType {
+    type Error = ();
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(DnsType::A),
+            2 => Ok(DnsType::Ns),
+            5 => Ok(DnsType::Cname),
+            6 => Ok(DnsType::Soa),
+            12 => Ok(DnsType::Ptr),
+            15 => Ok(DnsType::Mx),
+            16 => Ok(DnsType::Txt),
+            28 => Ok(DnsType::Aaaa),
+            33 => Ok(DnsType::Srv),
+            41 => Ok(DnsType::Opt),
+            255 => Ok(DnsType::Any),
+            _ => Err(()),
+        }
+    }
+}
+
+/// The Internet class, the only one in practical use.
+pub const CLASS_IN: u16 = 1;
+
+/// DNS message header.
+/// ```text
+///                                 1  1  1  1  1  1
+///   0  1  2  3  4  5  6  7  8  9  0  1  2  3  4  5
+/// +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+/// |                      ID                       |
+/// +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+/// |QR|   Opcode  |AA|TC|RD|RA|   Z    |   RCODE   |
+/// +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+/// |                    QDCOUNT                    |
+/// +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+/// |                    ANCOUNT                    |
+/// +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+/// |                    NSCOUNT                    |
+/// +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+/// |                    ARCOUNT                    |
+/// +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+/// ```
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct DnsHdr {
+    pub id: U16,
+    pub flags: U16,
+    pub qdcount: U16,
+    pub ancount: U16,
+    pub nscount: U16,
+    pub arcount: U16,
+}
+
+impl DnsHdr {
+    pub const LEN: usize = mem::size_of::<DnsHdr>();
+
+    #[inline]
+    pub const fn id(&self) -> u16 {
+        self.id.to_bits()
+    }
+
+    #[inline]
+    pub const fn flags(&self) -> u16 {
+        self.flags.to_bits()
+    }
+
+    /// **QR**: the message is a response.
+    #[inline]
+    pub const fn is_response(&self) -> bool {
+        self.flags() & FLAG_QR != 0
+    }
+
+    #[inline]
+    pub const fn opcode(&self) -> u8 {
+        ((self.flags() >> 11) & 0xf) as u8
+    }
+
+    #[inline]
+    pub const fn rcode(&self) -> u8 {
+        (self.flags() & 0xf) as u8
+    }
+
+    #[inline]
+    pub const fn qdcount(&self) -> u16 {
+        self.qdcount.to_bits()
+    }
+
+    #[inline]
+    pub const fn ancount(&self) -> u16 {
+        self.ancount.to_bits()
+    }
+}
+
+impl_header!(DnsHdr);
+
+/// A possibly compressed domain name stored in a DNS message.
+#[derive(Debug, Copy, Clone)]
+pub struct DnsName<'a> {
+    msg: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> DnsName<'a> {
+    /// Parses the name at `offset` in the DNS message `msg`, returning it and
+    /// the offset following its encoding.
+    #[inline]
+    pub fn parse(msg: &'a [u8], offset: usize) -> Option<(Self, usize)> {
+        Self::parse_with_config(msg, offset, &ParseConfig::DEFAULT).ok()
+    }
+
+    /// Parses the name like [`DnsName::parse`], following at most
+    /// [`ParseConfig::max_dns_pointers`] compression pointers to at most
+    /// [`ParseConfig::max_dns_labels`] labels.
+    pub fn parse_with_config(
+        msg: &'a [u8],
+        offset: usize,
+        config: &ParseConfig,
+    ) -> Result<(Self, usize), ParseError> {
+        let name = DnsName { msg, offset };
+        let mut end = None;
+        let (mut pos, mut len, mut labels, mut pointers) = (offset, 0, 0, 0);
+        loop {
+            let label = *msg.get(pos).ok_or(ParseError::Truncated)? as usize;
+            match label & 0xc0 {
+                0x00 if label == 0 => {
+                    return Ok((name, end.unwrap_or(pos + 1)));
+                }
+                0x00 => {
+                    len += label + 1;
+                    if len > MAX_NAME_LEN {
+                        return Err(ParseError::Malformed);
+                    }
+                    if labels == config.max_dns_labels {
+                        return Err(ParseError::LimitExceeded);
+                    }
+                    if pos + 1 + label > msg.len() {
+                        return Err(ParseError::Truncated);
+                    }
+                    labels += 1;
+                    pos += 1 + label;
+                }
+                0xc0 => {
+                    let ptr = *msg.get(pos + 1).ok_or(ParseError::Truncated)?;
+                    let ptr = ((label & 0x3f) << 8) | ptr as usize;
+                    end.get_or_insert(pos + 2);
+                    // Only pointers to prior occurrences are valid, which
+                    // rules out loops.
+                    if ptr >= pos {
+                        return Err(ParseError::Malformed);
+                    }
+                    if pointers == config.max_dns_pointers {
+                        return Err(ParseError::LimitExceeded);
+                    }
+                    pointers += 1;
+                    pos = ptr;
+                }
+                _ => return Err(ParseError::Malformed),
+            }
+        }
+    }
+
+    /// Iterator over the labels of the name, following compression pointers.
+    pub fn labels(&self) -> DnsLabels<'a> {
+        DnsLabels {
+            msg: self.msg,
+            pos: self.offset,
+        }
+    }
+
+    /// Compares the name with the dotted name `name`, ignoring ASCII case
+    /// and a trailing dot.
+    pub fn eq_str(&self, name: &str) -> bool {
+        let name = name.strip_suffix('.').unwrap_or(name);
+        let mut rest = (!name.is_empty()).then_some(name);
+        for label in self.labels() {
+            let Some(r) = rest else {
+                return false;
+            };
+            let (first, tail) = match r.split_once('.') {
+                Some((first, tail)) => (first, Some(tail)),
+                None => (r, None),
+            };
+            if !label.eq_ignore_ascii_case(first.as_bytes()) {
+                return false;
+            }
+            rest = tail;
+        }
+        rest.is_none()
+    }
+}
+
+/// Iterator over the labels of a [`DnsName`].
+#[derive(Debug, Clone)]
+pub struct DnsLabels<'a> {
+    msg: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Iterator for DnsLabels<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // The name was validated by `DnsName::parse`.
+        loop {
+            let label = *self.msg.get(self.pos)? as usize;
+            if label & 0xc0 == 0xc0 {
+                self.pos = ((label & 0x3f) << 8) | *self.msg.get(self.pos + 1)? as usize;
+                continue;
+            }
+            if label == 0 {
+                self.msg = &[];
+                return None;
+            }
+            let out = self.msg.get(self.pos + 1..self.pos + 1 + label)?;
+            self.pos += 1 + label;
+            return Some(out);
+        }
+    }
+}
+
+/// Builds a DNS message into a caller-provided buffer.
+///
+/// Questions must be added before answer records. Name compression is
+/// disabled by default.
+///
+/// ```
+/// use ether_packet::dns::{DnsBuilder, DnsType};
+///
+/// let mut buf = [0u8; 512];
+/// let query = DnsBuilder::query(&mut buf, 0x1234, "example.com", DnsType::Aaaa).unwrap();
+/// assert_eq!(query.finish(), 12 + 13 + 4);
+/// ```
+pub struct DnsBuilder<'a> {
+    w: Writer<'a>,
+    qdcount: u16,
+    ancount: u16,
+    compression: bool,
+    /// Offsets of the names written so far, candidates for compression.
+    names: [u16; 16],
+    num_names: usize,
+}
+
+impl<'a> DnsBuilder<'a> {
+    /// Starts a message with the given ID and flags (see `FLAG_*`).
+    pub fn new(buf: &'a mut [u8], id: u16, flags: u16) -> Result<Self, BuildError> {
+        let mut w = Writer::new(buf);
+        let hdr = w.reserve(DnsHdr::LEN)?;
+        hdr[0..2].copy_from_slice(&id.to_be_bytes());
+        hdr[2..4].copy_from_slice(&flags.to_be_bytes());
+        Ok(Self {
+            w,
+            qdcount: 0,
+            ancount: 0,
+            compression: false,
+            names: [0; 16],
+            num_names: 0,
+        })
+    }
+
+    /// Recursive query for `name` of type `qtype`, in class IN.
+    pub fn query(
+        buf: &'a mut [u8],
+        id: u16,
+        name: &str,
+        qtype: DnsType,
+    ) -> Result<Self, BuildError> {
+        let mut builder = Self::new(buf, id, FLAG_RD)?;
+        builder.question(name, qtype as u16, CLASS_IN)?;
+        Ok(builder)
+    }
+
+    /// Enables or disables the compression of names which were already
+    /// written to the message.
+    pub fn compression(&mut self, enabled: bool) -> &mut Self {
+        self.compression = enabled;
+        self
+    }
+
+    /// Appends a question.
+    ///
+    /// # Panics
+    ///
+    /// Panics if answer records were already added.
+    pub fn question(
+        &mut self,
+        name: &str,
+        qtype: u16,
+        qclass: u16,
+    ) -> Result<&mut Self, BuildError> {
+        assert!(self.ancount == 0, "DNS questions must precede answers");
+        let qdcount = self
+            .qdcount
+            .checked_add(1)
+            .ok_or(BuildError::FieldOverflow)?;
+        self.name(name)?;
+        let q = self.w.reserve(4)?;
+        q[0..2].copy_from_slice(&qtype.to_be_bytes());
+        q[2..4].copy_from_slice(&qclass.to_be_bytes());
+        self.qdcount = qdcount;
+        Ok(self)
+    }
+
+    /// Appends an answer record with raw record data.
+    pub fn answer(
+        &mut self,
+        name: &str,
+        rtype: u16,
+        class: u16,
+        ttl: u32,
+        rdata: &[u8],
+    ) -> Result<&mut Self, BuildError> {
+        let rdlength = self.record_hdr(name, rtype, class, ttl)?;
+        self.w.put(rdata)?;
+        self.finish_rdata(rdlength)
+    }
+
+    pub fn answer_a(
+        &mut self,
+        name: &str,
+        ttl: u32,
+        addr: Ipv4Addr,
+    ) -> Result<&mut Self, BuildError> {
+        self.answer(name, DnsType::A as u16, CLASS_IN, ttl, &addr.octets())
+    }
+
+    pub fn answer_aaaa(
+        &mut self,
+        name: &str,
+        ttl: u32,
+        addr: Ipv6Addr,
+    ) -> Result<&mut Self, BuildError> {
+        self.answer(name, DnsType::Aaaa as u16, CLASS_IN, ttl, &addr.octets())
+    }
+
+    /// Appends a PTR record pointing to `target`, which is subject to
+    /// compression as well.
+    pub fn answer_ptr(
+        &mut self,
+        name: &str,
+        ttl: u32,
+        target: &str,
+    ) -> Result<&mut Self, BuildError> {
+        let rdlength = self.record_hdr(name, DnsType::Ptr as u16, CLASS_IN, ttl)?;
+        self.name(target)?;
+        self.finish_rdata(rdlength)
+    }
+
+    /// Writes the fixed part of a record, returning the offset of its
+    /// RDLENGTH field.
+    fn record_hdr(
+        &mut self,
+        name: &str,
+        rtype: u16,
+        class: u16,
+        ttl: u32,
+    ) -> Result<usize, BuildError> {
+        self.name(name)?;
+        let hdr = self.w.reserve(10)?;
+        hdr[0..2].copy_from_slice(&rtype.to_be_bytes());
+        hdr[2..4].copy_from_slice(&class.to_be_bytes());
+        hdr[4..8].copy_from_slice(&ttl.to_be_bytes());
+        Ok(self.w.pos() - 2)
+    }
+
+    fn finish_rdata(&mut self, rdlength: usize) -> Result<&mut Self, BuildError> {
+        let len =
+            u16::try_from(self.w.pos() - rdlength - 2).map_err(|_| BuildError::FieldOverflow)?;
+        self.ancount = self
+            .ancount
+            .checked_add(1)
+            .ok_or(BuildError::FieldOverflow)?;
+        self.w.set_u16(rdlength, len);
+        Ok(self)
+    }
+
+    /// Encodes the dotted name `name`, splitting it into labels.
+    fn name(&mut self, name: &str) -> Result<(), BuildError> {
+        let name = name.strip_suffix('.').unwrap_or(name);
+        if name.len() + 2 > MAX_NAME_LEN {
+            return Err(BuildError::FieldOverflow);
+        }
+        let mut rest = name;
+        while !rest.is_empty() {
+            if let Some(ptr) = self.find_name(rest) {
+                self.w.put(&(0xc000 | ptr).to_be_bytes())?;
+                return Ok(());
+            }
+            let (label, tail) = rest.split_once('.').unwrap_or((rest, ""));
+            if label.is_empty() || label.len() > 63 {
+                return Err(BuildError::FieldOverflow);
+            }
+            let offset = self.w.pos();
+            self.w.put_u8(label.len() as u8)?;
+            self.w.put(label.as_bytes())?;
+            if self.compression && offset < 0x4000 && self.num_names < self.names.len() {
+                self.names[self.num_names] = offset as u16;
+                self.num_names += 1;
+            }
+            rest = tail;
+        }
+        self.w.put_u8(0)
+    }
+
+    /// Offset of a previously written name equal to `name`.
+    fn find_name(&self, name: &str) -> Option<u16> {
+        if !self.compression {
+            return None;
+        }
+        let msg = self.w.written();
+        self.names[..self.num_names].iter().copied().find(|offset| {
+            DnsName::parse(msg, *offset as usize).is_some_and(|(n, _)| n.eq_str(name))
+        })
+    }
+
+    /// Fills in the section counts, returning the length of the message.
+    pub fn finish(mut self) -> usize {
+        self.w.set_u16(4, self.qdcount);
+        self.w.set_u16(6, self.ancount);
+        self.w.pos()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::net::Ipv4Addr;
+
+    use super::{DnsBuilder, DnsHdr, DnsName, DnsType, CLASS_IN, FLAG_AA, FLAG_QR};
+    use crate::{
+        builder::BuildError,
+        header::{Header, ParseConfig, ParseError},
+    };
+
+    #[test]
+    fn test_response_compression() {
+        let mut buf = [0u8; 512];
+        let mut resp = DnsBuilder::new(&mut buf, 0xbeef, FLAG_QR | FLAG_AA).unwrap();
+        resp.compression(true)
+            .question("www.example.com.", DnsType::A as u16, CLASS_IN)
+            .unwrap()
+            .answer_a("www.example.com", 300, Ipv4Addr::new(192, 0, 2, 1))
+            .unwrap()
+            .answer_ptr("1.2.0.192.in-addr.arpa", 60, "mail.example.com")
+            .unwrap();
+        let len = resp.finish();
+
+        let hdr = DnsHdr::from_bytes(&buf).unwrap();
+        assert!(hdr.is_response());
+        assert_eq!((hdr.qdcount(), hdr.ancount()), (1, 2));
+
+        let (qname, end) = DnsName::parse(&buf, DnsHdr::LEN).unwrap();
+        assert!(qname.eq_str("WWW.example.com"));
+        assert_eq!(end, DnsHdr::LEN + 17);
+        // The answer name is a pointer to the question name.
+        let answer = end + 4;
+        assert_eq!(&buf[answer..answer + 2], &[0xc0, DnsHdr::LEN as u8]);
+        assert_eq!(&buf[answer + 12..answer + 16], &[192, 0, 2, 1]);
+
+        let ptr = answer + 16;
+        let (name, end) = DnsName::parse(&buf, ptr).unwrap();
+        assert!(name.eq_str("1.2.0.192.in-addr.arpa"));
+        // "mail" followed by a pointer to "example.com".
+        let rdata = end + 10;
+        assert_eq!(u16::from_be_bytes([buf[end + 8], buf[end + 9]]), 7);
+        let (target, target_end) = DnsName::parse(&buf, rdata).unwrap();
+        assert!(target.eq_str("mail.example.com"));
+        assert_eq!(target_end, len);
+        assert_eq!(target.labels().count(), 3);
+        let limits = ParseConfig {
+            max_dns_pointers: 0,
+            ..ParseConfig::DEFAULT
+        };
+        assert_eq!(
+            DnsName::parse_with_config(&buf, rdata, &limits).err(),
+            Some(ParseError::LimitExceeded)
+        );
+        // A chain of 20 pointers, each to the previous one.
+        let mut chain = [0u8; 41];
+        for i in 0..20 {
+            chain[1 + 2 * i..3 + 2 * i].copy_from_slice(&[0xc0, (2 * i) as u8 - (i > 0) as u8]);
+        }
+        assert!(DnsName::parse(&chain, 39).is_some());
+        assert_eq!(
+            DnsName::parse_with_config(&chain, 39, &ParseConfig::HARDENED).err(),
+            Some(ParseError::LimitExceeded)
+        );
+
+        let mut buf = [0u8; 64];
+        let long = core::str::from_utf8(&[b'a'; 64]).unwrap();
+        assert_eq!(
+            DnsBuilder::query(&mut buf, 1, long, DnsType::A).err(),
+            Some(BuildError::FieldOverflow)
+        );
+    }
+}
@@ -0,0 +1,202 @@
+//! Helpers for reading headers straight out of DPDK `rte_mbuf` chains, so
+//! DPDK applications can use the header types of this crate without first
+//! copying the packet into a contiguous slice.
+//!
+//! The crate does not link against DPDK. Instead, implement [`Mbuf`] on the
+//! `rte_mbuf` type of the bindings used by the application:
+//!
+//! ```rust,ignore
+//! unsafe impl ether_packet::dpdk::Mbuf for rte_mbuf {
+//!     fn buf_addr(&self) -> *const u8 {
+//!         self.buf_addr as *const u8
+//!     }
+//!     fn data_off(&self) -> u16 {
+//!         self.data_off
+//!     }
+//!     fn data_len(&self) -> u16 {
+//!         self.data_len
+//!     }
+//!     fn next(&self) -> Option<&Self> {
+//!         unsafe { self.next.as_ref() }
+//!     }
+//! }
+//! ```
+
+use core::{mem, slice};
+
+use crate::{
+    buffer::{Buffer, Chunk},
+    header::Header,
+    meta::PacketMeta,
+};
+
+/// Read-only view of one segment of a DPDK `rte_mbuf` chain.
+///
+/// # Safety
+///
+/// `buf_addr() + data_off()` must point to at least `data_len()` readable bytes
+/// which stay valid and unmodified for as long as the mbuf is borrowed.
+pub unsafe trait Mbuf {
+    /// Virtual address of the start of the segment buffer (`buf_addr`).
+    fn buf_addr(&self) -> *const u8;
+    /// Size of the headroom in front of the packet data (`data_off`).
+    fn data_off(&self) -> u16;
+    /// Amount of packet data in this segment (`data_len`).
+    fn data_len(&self) -> u16;
+    /// Next segment of a multi-segment packet (`next`).
+    fn next(&self) -> Option<&Self>;
+
+    /// RSS hash (`hash.rss`), if `RTE_MBUF_F_RX_RSS_HASH` is set in
+    /// `ol_flags`.
+    #[inline]
+    fn rss_hash(&self) -> Option<u32> {
+        None
+    }
+
+    /// VLAN TCI stripped by the NIC (`vlan_tci`), if
+    /// `RTE_MBUF_F_RX_VLAN_STRIPPED` is set in `ol_flags`.
+    #[inline]
+    fn vlan_tci(&self) -> Option<u16> {
+        None
+    }
+
+    /// Packet data stored in this segment, with the headroom skipped.
+    #[inline]
+    fn data(&self) -> &[u8] {
+        // SAFETY: guaranteed by the implementor of the trait.
+        unsafe {
+            slice::from_raw_parts(
+                self.buf_addr().add(self.data_off() as usize),
+                self.data_len() as usize,
+            )
+        }
+    }
+}
+
+/// A header read from an mbuf chain.
+///
+/// Headers which are fully contained in a single segment are borrowed from
+/// the mbuf; headers straddling a segment boundary are gathered into a copy.
+pub type MbufHeader<'a, H> = Chunk<'a, H>;
+
+/// Largest number of segments a header read by [`header`] may span.
+const MAX_HEADER_SEGMENTS: usize = 8;
+
+/// Reads the header of type `H` located `offset` bytes into the packet data
+/// of the mbuf chain starting at `mbuf`.
+///
+/// Only the bytes of the requested header are gathered when it spans several
+/// segments, the remaining segments are not touched. Returns `None` when the
+/// chain is too short, the header is not valid, or it spans more than 8
+/// segments.
+pub fn header<H: Header, M: Mbuf>(mbuf: &M, offset: usize) -> Option<MbufHeader<'_, H>> {
+    let len = mem::size_of::<H>();
+    let mut seg = mbuf;
+    let mut offset = offset;
+    while offset >= seg.data().len() {
+        offset -= seg.data().len();
+        seg = seg.next()?;
+    }
+
+    let data = seg.data();
+    if offset + len <= data.len() {
+        return H::from_bytes(&data[offset..]).map(MbufHeader::Borrowed);
+    }
+
+    let mut segments = [data; MAX_HEADER_SEGMENTS];
+    let mut count = 1;
+    let mut end = data.len();
+    while end < offset + len {
+        seg = seg.next()?;
+        *segments.get_mut(count)? = seg.data();
+        end += seg.data().len();
+        count += 1;
+    }
+    let hdr = Buffer::new(&segments[..count]).header::<H>(offset).ok()?;
+    Some(MbufHeader::Copied(*hdr))
+}
+
+/// Total amount of packet data in the mbuf chain starting at `mbuf`.
+#[inline]
+pub fn pkt_len<M: Mbuf>(mbuf: &M) -> usize {
+    let mut len = mbuf.data().len();
+    let mut seg = mbuf;
+    while let Some(next) = seg.next() {
+        len += next.data().len();
+        seg = next;
+    }
+    len
+}
+
+/// Capture metadata reported by the NIC in the first segment of the chain.
+///
+/// DPDK does not assign a timestamp to received packets unless the
+/// application registers the timestamp dynamic field, so it is left unset.
+#[inline]
+pub fn meta<M: Mbuf>(mbuf: &M) -> PacketMeta {
+    PacketMeta {
+        vlan_stripped: mbuf.vlan_tci(),
+        rss_hash: mbuf.rss_hash(),
+        ..PacketMeta::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{header, Mbuf, MbufHeader};
+    use crate::{eth::EthHdr, eth::EtherType, ip::Ipv4Hdr};
+
+    struct Seg<'a> {
+        buf: &'a [u8],
+        data_off: u16,
+        next: Option<&'a Seg<'a>>,
+    }
+
+    unsafe impl Mbuf for Seg<'_> {
+        fn buf_addr(&self) -> *const u8 {
+            self.buf.as_ptr()
+        }
+        fn data_off(&self) -> u16 {
+            self.data_off
+        }
+        fn data_len(&self) -> u16 {
+            self.buf.len() as u16 - self.data_off
+        }
+        fn next(&self) -> Option<&Self> {
+            self.next
+        }
+    }
+
+    #[test]
+    fn test_multi_segment() {
+        let frame = [
+            0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0, 0x11, 0x22, 0x33, 0x44, 0x55, 0x08, 0x00, 0x45,
+            0, 0, 20, 0, 0, 0, 0, 64, 17, 0, 0, 10, 0, 0, 1, 10, 0, 0, 2,
+        ];
+        // Two bytes of headroom, then the frame split in the middle of the
+        // IPv4 header.
+        let mut first = [0u8; 26];
+        first[2..].copy_from_slice(&frame[..24]);
+        let second = Seg {
+            buf: &frame[24..],
+            data_off: 0,
+            next: None,
+        };
+        let first = Seg {
+            buf: &first,
+            data_off: 2,
+            next: Some(&second),
+        };
+
+        let eth = header::<EthHdr, _>(&first, 0).unwrap();
+        assert!(matches!(eth, MbufHeader::Borrowed(_)));
+        assert_eq!(eth.ether_type(), Some(EtherType::Ipv4));
+
+        let ip = header::<Ipv4Hdr, _>(&first, EthHdr::LEN).unwrap();
+        assert!(matches!(ip, MbufHeader::Copied(_)));
+        assert_eq!(ip.dst_addr, core::net::Ipv4Addr::new(10, 0, 0, 2));
+
+        assert_eq!(super::pkt_len(&first), frame.len());
+        assert!(header::<Ipv4Hdr, _>(&first, 20).is_none());
+    }
+}
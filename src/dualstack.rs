@@ -0,0 +1,326 @@
+//! Correlation of the IPv4 and IPv6 connection attempts of dual-stack
+//! clients, e.g. to study their Happy Eyeballs
+//! ([RFC 8305](https://datatracker.ietf.org/doc/html/rfc8305)) behavior.
+//!
+//! A client racing both families to the same destination opens flows with
+//! nothing in common but the client and the destination port. The caller
+//! identifies the client, e.g. by its MAC address, and
+//! [`DualStackCorrelator`] identifies the destination from the addresses
+//! learned for a name, typically from DNS answers, or for an explicit pair
+//! of addresses. The attempts of a client to a destination falling within
+//! the correlation window form a [`DualStackGroup`].
+//!
+//! The `now` arguments are the capture times of the packets seen for the
+//! attempts, which the groups report as is: the Happy Eyeballs delay and
+//! the winning family compare times of packets of different flows, so they
+//! must all be stamped by the same clock. The window starts at the first
+//! attempt of a group, and [`DualStackCorrelator::expire`] closes it once
+//! `now` is past it.
+
+use alloc::{collections::BTreeMap, vec::Vec};
+use core::{
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    time::Duration,
+};
+
+use crate::flow::{siphash, FlowKey};
+
+/// Identifier of the destination named `name`, ignoring the case and a
+/// trailing dot.
+pub fn name_hash(name: &str) -> u64 {
+    let name = name.strip_suffix('.').unwrap_or(name);
+    let name: Vec<u8> = name.bytes().map(|b| b.to_ascii_lowercase()).collect();
+    siphash(&[0, 0], &name)
+}
+
+/// A connection attempt of a group.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub struct DualStackAttempt {
+    /// Key of the first flow of the family, from the client.
+    pub key: FlowKey,
+    pub start: Duration,
+    /// Time the first flow was established, e.g. when its SYN-ACK was seen.
+    pub established: Option<Duration>,
+}
+
+/// The attempts of a client to a destination within the correlation
+/// window.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub struct DualStackGroup {
+    /// Identifier of the client, given by the caller.
+    pub client: u64,
+    /// Identifier of the destination, see [`DualStackCorrelator::destination`].
+    pub destination: u64,
+    pub dst_port: u16,
+    pub ipv4: Option<DualStackAttempt>,
+    pub ipv6: Option<DualStackAttempt>,
+}
+
+impl DualStackGroup {
+    /// Time of the first attempt.
+    #[inline]
+    pub fn start(&self) -> Duration {
+        match (self.ipv4, self.ipv6) {
+            (Some(v4), Some(v6)) => v4.start.min(v6.start),
+            (Some(attempt), None) | (None, Some(attempt)) => attempt.start,
+            (None, None) => Duration::ZERO,
+        }
+    }
+
+    /// Whether both families were attempted.
+    #[inline]
+    pub fn is_dual_stack(&self) -> bool {
+        self.ipv4.is_some() && self.ipv6.is_some()
+    }
+
+    /// Whether IPv6 was attempted first, `None` unless both families were
+    /// attempted.
+    #[inline]
+    pub fn ipv6_first(&self) -> Option<bool> {
+        Some(self.ipv6?.start <= self.ipv4?.start)
+    }
+
+    /// Time between the first attempts of both families, the connection
+    /// attempt delay of Happy Eyeballs.
+    #[inline]
+    pub fn delay(&self) -> Option<Duration> {
+        let (v4, v6) = (self.ipv4?.start, self.ipv6?.start);
+        Some(v4.max(v6) - v4.min(v6))
+    }
+
+    /// Whether the established flow which came first is IPv6, `None` if no
+    /// flow was established.
+    pub fn ipv6_won(&self) -> Option<bool> {
+        let v4 = self.ipv4.and_then(|attempt| attempt.established);
+        let v6 = self.ipv6.and_then(|attempt| attempt.established);
+        match (v4, v6) {
+            (Some(v4), Some(v6)) => Some(v6 <= v4),
+            (Some(_), None) => Some(false),
+            (None, Some(_)) => Some(true),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Groups the connection attempts of clients, reporting each group to a
+/// callback once its window is over.
+///
+/// ```
+/// use core::time::Duration;
+/// use ether_packet::{dualstack::DualStackCorrelator, flow::FlowKey, ip::IpProto};
+///
+/// let mut groups = Vec::new();
+/// let mut correlator = DualStackCorrelator::new(Duration::from_secs(2), |group| groups.push(group));
+/// let (v4, v6) = ("192.0.2.1".parse().unwrap(), "2001:db8::1".parse().unwrap());
+/// correlator.learn_name("www.example.com", v4);
+/// correlator.learn_name("WWW.example.com.", v6);
+///
+/// let mut key = FlowKey {
+///     src_addr: "2001:db8::100".parse().unwrap(),
+///     dst_addr: v6,
+///     proto: IpProto::Tcp,
+///     src_port: 50000,
+///     dst_port: 443,
+/// };
+/// let client = 0x0200_0000_0001;
+/// correlator.attempt(client, &key, Duration::from_millis(0));
+/// (key.src_addr, key.dst_addr) = ("10.0.0.100".parse().unwrap(), v4);
+/// correlator.attempt(client, &key, Duration::from_millis(250));
+/// correlator.established(client, &key, Duration::from_millis(270));
+/// correlator.expire(Duration::from_secs(3));
+/// drop(correlator);
+///
+/// assert_eq!(groups[0].ipv6_first(), Some(true));
+/// assert_eq!(groups[0].delay(), Some(Duration::from_millis(250)));
+/// assert_eq!(groups[0].ipv6_won(), Some(false));
+/// ```
+pub struct DualStackCorrelator<F: FnMut(DualStackGroup)> {
+    window: Duration,
+    destinations: BTreeMap<IpAddr, u64>,
+    groups: BTreeMap<(u64, u64, u16), DualStackGroup>,
+    on_group: F,
+}
+
+impl<F: FnMut(DualStackGroup)> DualStackCorrelator<F> {
+    /// Creates a correlator grouping the attempts made less than `window`
+    /// after the first one of their group.
+    pub fn new(window: Duration, on_group: F) -> Self {
+        Self {
+            window,
+            destinations: BTreeMap::new(),
+            groups: BTreeMap::new(),
+            on_group,
+        }
+    }
+
+    /// Identifies `addr` by `name`, e.g. from the A or AAAA record of a
+    /// DNS answer.
+    #[inline]
+    pub fn learn_name(&mut self, name: &str, addr: IpAddr) {
+        self.destinations.insert(addr, name_hash(name));
+    }
+
+    /// Identifies `ipv4` and `ipv6` as the same destination.
+    pub fn learn_pair(&mut self, ipv4: Ipv4Addr, ipv6: Ipv6Addr) {
+        let mut pair = [0u8; 20];
+        pair[..4].copy_from_slice(&ipv4.octets());
+        pair[4..].copy_from_slice(&ipv6.octets());
+        let id = siphash(&[1, 1], &pair);
+        self.destinations.insert(ipv4.into(), id);
+        self.destinations.insert(ipv6.into(), id);
+    }
+
+    /// Forgets the destinations learned so far.
+    #[inline]
+    pub fn clear_destinations(&mut self) {
+        self.destinations.clear();
+    }
+
+    /// Identifier of the destination `addr`, which is specific to the
+    /// address if it was not learned.
+    pub fn destination(&self, addr: IpAddr) -> u64 {
+        self.destinations.get(&addr).copied().unwrap_or_else(|| {
+            let mut bytes = [0u8; 16];
+            match addr {
+                IpAddr::V4(addr) => bytes[..4].copy_from_slice(&addr.octets()),
+                IpAddr::V6(addr) => bytes.copy_from_slice(&addr.octets()),
+            }
+            siphash(&[2, addr.is_ipv6() as u64], &bytes)
+        })
+    }
+
+    /// Number of groups whose window is not over.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.groups.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// Records the connection attempt of `client` with flow `key`, e.g. on
+    /// its SYN.
+    ///
+    /// Further attempts of the same family within the window are part of
+    /// the group but only the first one is recorded.
+    pub fn attempt(&mut self, client: u64, key: &FlowKey, now: Duration) {
+        let id = (client, self.destination(key.dst_addr), key.dst_port);
+        let attempt = DualStackAttempt {
+            key: *key,
+            start: now,
+            established: None,
+        };
+        let group = match self.groups.get_mut(&id) {
+            Some(group) if now.saturating_sub(group.start()) < self.window => group,
+            _ => {
+                let new = DualStackGroup {
+                    client,
+                    destination: id.1,
+                    dst_port: key.dst_port,
+                    ipv4: None,
+                    ipv6: None,
+                };
+                if let Some(old) = self.groups.insert(id, new) {
+                    (self.on_group)(old);
+                }
+                self.groups.get_mut(&id).unwrap()
+            }
+        };
+        let slot = if key.dst_addr.is_ipv4() {
+            &mut group.ipv4
+        } else {
+            &mut group.ipv6
+        };
+        slot.get_or_insert(attempt);
+    }
+
+    /// Records that the flow `key` of `client` was established, e.g. on its
+    /// SYN-ACK. Only the first recorded attempt of each family is
+    /// followed.
+    pub fn established(&mut self, client: u64, key: &FlowKey, now: Duration) {
+        let id = (client, self.destination(key.dst_addr), key.dst_port);
+        let Some(group) = self.groups.get_mut(&id) else {
+            return;
+        };
+        let slot = if key.dst_addr.is_ipv4() {
+            &mut group.ipv4
+        } else {
+            &mut group.ipv6
+        };
+        if let Some(attempt) = slot.as_mut().filter(|attempt| attempt.key == *key) {
+            attempt.established.get_or_insert(now);
+        }
+    }
+
+    /// Reports and removes the groups whose window is over at time `now`.
+    pub fn expire(&mut self, now: Duration) {
+        let window = self.window;
+        let on_group = &mut self.on_group;
+        self.groups.retain(|_, group| {
+            if now.saturating_sub(group.start()) < window {
+                return true;
+            }
+            on_group(*group);
+            false
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use core::{net::IpAddr, time::Duration};
+
+    use super::{name_hash, DualStackCorrelator};
+    use crate::{flow::FlowKey, ip::IpProto};
+
+    #[test]
+    fn test_correlator() {
+        assert_eq!(name_hash("Example.COM."), name_hash("example.com"));
+        assert_ne!(name_hash("example.com"), name_hash("example.net"));
+
+        let ms = Duration::from_millis;
+        let mut groups = Vec::new();
+        let mut correlator = DualStackCorrelator::new(ms(1000), |group| groups.push(group));
+        let v4 = "198.51.100.7".parse().unwrap();
+        let v6 = "2001:db8::7".parse().unwrap();
+        correlator.learn_pair(v4, v6);
+        let key = |src: &str, dst: IpAddr| FlowKey {
+            src_addr: src.parse().unwrap(),
+            dst_addr: dst,
+            proto: IpProto::Tcp,
+            src_port: 40000,
+            dst_port: 443,
+        };
+        let key4 = key("10.0.0.1", v4.into());
+        let key6 = key("2001:db8::1", v6.into());
+        assert_eq!(
+            correlator.destination(v4.into()),
+            correlator.destination(v6.into())
+        );
+
+        // IPv4 only, the IPv6 attempt coming after the window.
+        correlator.attempt(1, &key4, ms(0));
+        correlator.attempt(1, &key6, ms(1500));
+        // Another client, racing both families.
+        correlator.attempt(2, &key6, ms(1600));
+        correlator.attempt(2, &key4, ms(1650));
+        correlator.established(2, &key6, ms(1700));
+        // Unrelated destination.
+        correlator.attempt(2, &key("10.0.0.1", "192.0.2.9".parse().unwrap()), ms(1700));
+        assert_eq!(correlator.len(), 3);
+        correlator.expire(ms(5000));
+        assert!(correlator.is_empty());
+        drop(correlator);
+
+        assert_eq!(groups.len(), 4);
+        assert!(!groups[0].is_dual_stack() && groups[0].ipv4.is_some());
+        let racing = groups.iter().find(|group| group.client == 2).unwrap();
+        assert_eq!(racing.ipv6_first(), Some(true));
+        assert_eq!(racing.delay(), Some(ms(50)));
+        assert_eq!(racing.ipv6_won(), Some(true));
+        assert_eq!(racing.start(), ms(1600));
+    }
+}
@@ -0,0 +1,107 @@
+//! Explicit Congestion Notification
+//! ([RFC 3168](https://datatracker.ietf.org/doc/html/rfc3168)) helpers for
+//! active queue management, working on IPv4 and IPv6 packets alike.
+
+use crate::checksum;
+
+/// ECN codepoint, the two least significant bits of the IPv4 Type of
+/// Service or IPv6 Traffic Class field.
+#[repr(u8)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum Ecn {
+    /// Not ECN-Capable Transport.
+    NotEct = 0,
+    /// ECN-Capable Transport (1), which identifies L4S traffic
+    /// ([RFC 9331](https://datatracker.ietf.org/doc/html/rfc9331)).
+    Ect1 = 1,
+    /// ECN-Capable Transport (0).
+    Ect0 = 2,
+    /// Congestion Experienced.
+    Ce = 3,
+}
+
+impl Ecn {
+    #[inline]
+    pub const fn from_bits(bits: u8) -> Self {
+        match bits & 0x3 {
+            0 => Ecn::NotEct,
+            1 => Ecn::Ect1,
+            2 => Ecn::Ect0,
+            _ => Ecn::Ce,
+        }
+    }
+}
+
+/// Returns the ECN codepoint of the IPv4 or IPv6 packet stored in `packet`.
+#[inline]
+pub fn ecn(packet: &[u8]) -> Option<Ecn> {
+    match packet {
+        [b, tos, ..] if b >> 4 == 4 => Some(Ecn::from_bits(*tos)),
+        [b, tc, ..] if b >> 4 == 6 => Some(Ecn::from_bits(tc >> 4)),
+        _ => None,
+    }
+}
+
+/// Whether the transport of the packet is ECN-capable, i.e. its codepoint
+/// is ECT(0), ECT(1) or CE.
+#[inline]
+pub fn is_ect(packet: &[u8]) -> bool {
+    !matches!(ecn(packet), None | Some(Ecn::NotEct))
+}
+
+/// Marks the IPv4 or IPv6 packet stored in `packet` as having experienced
+/// congestion, updating the IPv4 header checksum.
+///
+/// Returns `false` if the packet is not ECN-capable, in which case it is
+/// left untouched and should be dropped instead.
+pub fn mark_ce(packet: &mut [u8]) -> bool {
+    if !is_ect(packet) {
+        return false;
+    }
+    match packet[0] >> 4 {
+        4 => {
+            let Some(check) = packet.get(10..12) else {
+                return false;
+            };
+            let check = u16::from_be_bytes([check[0], check[1]]);
+            let old = u16::from_be_bytes([packet[0], packet[1]]);
+            packet[1] |= Ecn::Ce as u8;
+            let new = u16::from_be_bytes([packet[0], packet[1]]);
+            let check = checksum::update(check, old, new);
+            packet[10..12].copy_from_slice(&check.to_be_bytes());
+        }
+        _ => packet[1] |= (Ecn::Ce as u8) << 4,
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ecn, is_ect, mark_ce, Ecn};
+    use crate::checksum;
+
+    #[test]
+    fn test_mark_ce() {
+        let mut v4 = [
+            0x45, 0x02, 0, 20, 0, 0, 0x40, 0, 64, 17, 0, 0, 10, 0, 0, 1, 10, 0, 0, 2,
+        ];
+        let check = checksum::checksum(&v4);
+        v4[10..12].copy_from_slice(&check.to_be_bytes());
+        assert_eq!(ecn(&v4), Some(Ecn::Ect0));
+        assert!(mark_ce(&mut v4));
+        assert_eq!(ecn(&v4), Some(Ecn::Ce));
+        assert_eq!(v4[1], 0x03);
+        assert_eq!(checksum::checksum(&v4), 0);
+
+        let mut v6 = [0u8; 40];
+        v6[..2].copy_from_slice(&[0x6b, 0x90]); // DSCP 46, ECT(1)
+        assert!(is_ect(&v6));
+        assert!(mark_ce(&mut v6));
+        assert_eq!(&v6[..2], &[0x6b, 0xb0]);
+
+        v6[1] = 0x80;
+        assert!(!is_ect(&v6));
+        assert!(!mark_ce(&mut v6));
+    }
+}
@@ -0,0 +1,646 @@
+//! Transactional editing of Ethernet frames.
+//!
+//! Rewriting a field usually requires fixing up lengths and checksums
+//! elsewhere in the frame, and ad-hoc edits easily leave a frame in an
+//! inconsistent state when one of them fails halfway. [`PacketEditor`]
+//! stages the mutations instead, validates all of them against the frame,
+//! and only then applies them in one pass, recomputing every affected
+//! length and checksum.
+//!
+//! With the `alloc` feature, [`trim_payload`] shortens captured frames to
+//! their headers, e.g. before storing them.
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::{fmt, net::IpAddr};
+
+use crate::{
+    checksum,
+    eth::{self, EthHdr, EtherType, FrameSize},
+    ip::IpProto,
+    sctp::{self, SctpHdr},
+};
+
+/// Error returned by [`PacketEditor::apply`], in which case the frame is
+/// left untouched.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum EditError {
+    /// The frame is truncated or its headers are inconsistent.
+    Malformed,
+    /// An IP edit was staged on a frame which is not IPv4 or IPv6.
+    NotIp,
+    /// A staged address does not belong to the address family of the packet.
+    AddressFamily,
+    /// The payload cannot be edited in a non-first fragment.
+    Fragment,
+    /// Decrementing the TTL would make it reach 0.
+    TtlExpired,
+    /// The buffer has no room for the inserted VLAN tag.
+    BufferTooSmall,
+    /// The edited frame would exceed the configured [`FrameSize`].
+    FrameTooLarge,
+}
+
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EditError::Malformed => f.write_str("malformed frame"),
+            EditError::NotIp => f.write_str("not an IP packet"),
+            EditError::AddressFamily => f.write_str("address family mismatch"),
+            EditError::Fragment => f.write_str("payload edit on a fragment"),
+            EditError::TtlExpired => f.write_str("TTL expired"),
+            EditError::BufferTooSmall => f.write_str("buffer too small"),
+            EditError::FrameTooLarge => f.write_str("frame too large"),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+enum TtlEdit {
+    Set(u8),
+    Decrement(u8),
+}
+
+/// Layout of the IP packet of a frame.
+struct Layout {
+    /// Offset of the IP header.
+    l3: usize,
+    /// Offset of the upper-layer header.
+    l4: usize,
+    /// End of the IP packet, excluding the Ethernet padding.
+    end: usize,
+    v6: bool,
+    proto: u8,
+    /// Whether the upper-layer header is present, i.e. the packet is not a
+    /// non-first fragment.
+    has_l4: bool,
+    /// Whether the packet is a fragment, in which case the upper-layer
+    /// checksum covers data of other fragments.
+    fragment: bool,
+}
+
+/// Staged mutations of an Ethernet frame.
+///
+/// ```
+/// use core::net::Ipv4Addr;
+///
+/// use ether_packet::edit::PacketEditor;
+///
+/// #[rustfmt::skip]
+/// let mut buf = [
+///     // Ethernet.
+///     0, 1, 2, 3, 4, 5, 0, 6, 7, 8, 9, 10, 0x08, 0x00,
+///     // IPv4, UDP.
+///     0x45, 0, 0, 32, 0, 0, 0, 0, 64, 17, 0, 0, 10, 0, 0, 1, 10, 0, 0, 2,
+///     0x30, 0x39, 0, 53, 0, 12, 0, 0, 1, 2, 3, 4,
+///     // Room for the VLAN tag.
+///     0, 0, 0, 0,
+/// ];
+/// let len = PacketEditor::new(&mut buf, 46)
+///     .push_vlan(100)
+///     .set_dst_addr(Ipv4Addr::new(192, 0, 2, 1).into())
+///     .decrement_ttl(1)
+///     .truncate_payload(2)
+///     .apply()
+///     .unwrap();
+/// assert_eq!(len, 48);
+/// assert_eq!(&buf[12..16], &[0x81, 0x00, 0x00, 100]);
+/// assert_eq!(&buf[20..22], &[0, 30]); // IPv4 total length.
+/// assert_eq!(buf[26], 63); // TTL.
+/// assert_eq!(&buf[42..44], &[0, 10]); // UDP length.
+/// ```
+#[derive(Debug)]
+pub struct PacketEditor<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+    vlan: Option<(u16, u16)>,
+    src_addr: Option<IpAddr>,
+    dst_addr: Option<IpAddr>,
+    ttl: Option<TtlEdit>,
+    payload_len: Option<usize>,
+    frame_size: Option<FrameSize>,
+}
+
+impl<'a> PacketEditor<'a> {
+    /// Edits the frame of `len` bytes at the start of `buf`, the rest of
+    /// `buf` being available for growing it.
+    pub fn new(buf: &'a mut [u8], len: usize) -> Self {
+        Self {
+            len: len.min(buf.len()),
+            buf,
+            vlan: None,
+            src_addr: None,
+            dst_addr: None,
+            ttl: None,
+            payload_len: None,
+            frame_size: None,
+        }
+    }
+
+    /// Inserts an 802.1Q tag with the given tag control information as the
+    /// outermost tag.
+    pub fn push_vlan(&mut self, tci: u16) -> &mut Self {
+        self.vlan = Some((EtherType::VLAN as u16, tci));
+        self
+    }
+
+    /// Inserts an 802.1ad service tag with the given tag control information
+    /// as the outermost tag.
+    pub fn push_svlan(&mut self, tci: u16) -> &mut Self {
+        self.vlan = Some((EtherType::QinQ as u16, tci));
+        self
+    }
+
+    pub fn set_src_addr(&mut self, addr: IpAddr) -> &mut Self {
+        self.src_addr = Some(addr);
+        self
+    }
+
+    pub fn set_dst_addr(&mut self, addr: IpAddr) -> &mut Self {
+        self.dst_addr = Some(addr);
+        self
+    }
+
+    /// Sets the IPv4 TTL or IPv6 hop limit.
+    pub fn set_ttl(&mut self, ttl: u8) -> &mut Self {
+        self.ttl = Some(TtlEdit::Set(ttl));
+        self
+    }
+
+    /// Decrements the IPv4 TTL or IPv6 hop limit, as a router does.
+    pub fn decrement_ttl(&mut self, by: u8) -> &mut Self {
+        self.ttl = Some(TtlEdit::Decrement(by));
+        self
+    }
+
+    /// Truncates the payload following the TCP, UDP, ICMP, ICMPv6 or SCTP
+    /// header, or the IP header for other protocols, to `len` bytes.
+    pub fn truncate_payload(&mut self, len: usize) -> &mut Self {
+        self.payload_len = Some(len);
+        self
+    }
+
+    /// Rejects edits which would make the frame exceed `size`, e.g. a VLAN
+    /// tag pushed onto a full-sized frame when baby giants are not allowed.
+    pub fn frame_size(&mut self, size: FrameSize) -> &mut Self {
+        self.frame_size = Some(size);
+        self
+    }
+
+    fn ip_edits(&self) -> bool {
+        self.src_addr.is_some()
+            || self.dst_addr.is_some()
+            || self.ttl.is_some()
+            || self.payload_len.is_some()
+    }
+
+    /// Finds the IP packet of the frame, skipping VLAN tags.
+    fn layout(&self) -> Result<Option<Layout>, EditError> {
+        let frame = &self.buf[..self.len];
+        let mut pos = EthHdr::LEN - 2;
+        let ether_type = loop {
+            let ether_type = frame.get(pos..pos + 2).ok_or(EditError::Malformed)?;
+            let ether_type = u16::from_be_bytes([ether_type[0], ether_type[1]]);
+            if ether_type != EtherType::VLAN as u16 && ether_type != EtherType::QinQ as u16 {
+                break ether_type;
+            }
+            pos += 4;
+        };
+        let l3 = pos + 2;
+        let ip = &frame[l3..];
+        let malformed = EditError::Malformed;
+
+        if ether_type == EtherType::Ipv4 as u16 {
+            let ihl = (*ip.first().ok_or(malformed)? & 0x0f) as usize * 4;
+            if ip.len() < 20 || ihl < 20 {
+                return Err(malformed);
+            }
+            let tot_len = u16::from_be_bytes([ip[2], ip[3]]) as usize;
+            if tot_len < ihl || tot_len > ip.len() {
+                return Err(malformed);
+            }
+            let frag_off = u16::from_be_bytes([ip[6], ip[7]]);
+            Ok(Some(Layout {
+                l3,
+                l4: l3 + ihl,
+                end: l3 + tot_len,
+                v6: false,
+                proto: ip[9],
+                has_l4: frag_off & 0x1fff == 0,
+                fragment: frag_off & 0x3fff != 0,
+            }))
+        } else if ether_type == EtherType::Ipv6 as u16 {
+            if ip.len() < 40 {
+                return Err(malformed);
+            }
+            let end = 40 + u16::from_be_bytes([ip[4], ip[5]]) as usize;
+            if end > ip.len() {
+                return Err(malformed);
+            }
+            let (mut next_hdr, mut pos) = (ip[6], 40);
+            let (mut has_l4, mut fragment) = (true, false);
+            loop {
+                let len = match IpProto::from_u8(next_hdr) {
+                    Some(IpProto::HopOpt | IpProto::Ipv6Route | IpProto::Ipv6Opts) => {
+                        (*ip.get(pos + 1).ok_or(malformed)? as usize + 1) * 8
+                    }
+                    Some(IpProto::Ipv6Frag) => {
+                        let frag = ip.get(pos..pos + 4).ok_or(malformed)?;
+                        let frag_off = u16::from_be_bytes([frag[2], frag[3]]);
+                        has_l4 = frag_off & 0xfff8 == 0;
+                        fragment = true;
+                        8
+                    }
+                    _ => break,
+                };
+                next_hdr = *ip.get(pos).ok_or(malformed)?;
+                pos += len;
+                if pos > end {
+                    return Err(malformed);
+                }
+            }
+            Ok(Some(Layout {
+                l3,
+                l4: l3 + pos,
+                end: l3 + end,
+                v6: true,
+                proto: next_hdr,
+                has_l4,
+                fragment,
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Applies the staged edits, returning the new length of the frame.
+    ///
+    /// The edits are validated first, the frame being left untouched if
+    /// any of them cannot be applied.
+    pub fn apply(&mut self) -> Result<usize, EditError> {
+        let layout = self.layout()?;
+        if self.vlan.is_some() && self.len + 4 > self.buf.len() {
+            return Err(EditError::BufferTooSmall);
+        }
+        let grow = if self.vlan.is_some() { 4 } else { 0 };
+        let max_len = match self.frame_size {
+            Some(size) => {
+                let tags = eth::vlan_tags(&self.buf[..self.len]) + grow / 4;
+                size.limit(tags).saturating_sub(grow)
+            }
+            None => usize::MAX,
+        };
+        let mut len = self.len;
+        if let Some(layout) = layout {
+            len = self.apply_ip(&layout, max_len)?;
+        } else if self.ip_edits() {
+            return Err(EditError::NotIp);
+        } else if len > max_len {
+            return Err(EditError::FrameTooLarge);
+        }
+
+        if let Some((tpid, tci)) = self.vlan {
+            self.buf.copy_within(EthHdr::LEN - 2..len, EthHdr::LEN + 2);
+            self.buf[12..14].copy_from_slice(&tpid.to_be_bytes());
+            self.buf[14..16].copy_from_slice(&tci.to_be_bytes());
+            len += 4;
+        }
+        self.len = len;
+        Ok(len)
+    }
+
+    /// Validates and applies the IP edits, returning the new length of the
+    /// frame, which may not exceed `max_len`.
+    fn apply_ip(&mut self, layout: &Layout, max_len: usize) -> Result<usize, EditError> {
+        let (ttl_at, src_at, addr_len) = if layout.v6 { (7, 8, 16) } else { (8, 12, 4) };
+        for addr in [self.src_addr, self.dst_addr].into_iter().flatten() {
+            if addr.is_ipv6() != layout.v6 {
+                return Err(EditError::AddressFamily);
+            }
+        }
+        let old_ttl = self.buf[layout.l3 + ttl_at];
+        let ttl = match self.ttl {
+            Some(TtlEdit::Set(ttl)) => ttl,
+            Some(TtlEdit::Decrement(by)) => match old_ttl.checked_sub(by) {
+                Some(ttl) if ttl > 0 => ttl,
+                _ => return Err(EditError::TtlExpired),
+            },
+            None => old_ttl,
+        };
+
+        let proto = IpProto::from_u8(layout.proto);
+        let l4_hdr_len = match proto {
+            _ if !layout.has_l4 => None,
+            Some(IpProto::Tcp) => {
+                let doff =
+                    (*self.buf.get(layout.l4 + 12).ok_or(EditError::Malformed)? >> 4) as usize;
+                Some(doff * 4).filter(|len| *len >= 20 && layout.l4 + len <= layout.end)
+            }
+            Some(IpProto::Udp | IpProto::Icmp | IpProto::Ipv6Icmp) => {
+                Some(8).filter(|len| layout.l4 + len <= layout.end)
+            }
+            Some(IpProto::Sctp) => Some(SctpHdr::LEN).filter(|len| layout.l4 + len <= layout.end),
+            _ => Some(0),
+        };
+        let addr_changed = self.src_addr.is_some() || self.dst_addr.is_some();
+        let end = match (self.payload_len, l4_hdr_len) {
+            (Some(_), _) if layout.fragment => return Err(EditError::Fragment),
+            (Some(_), None) => return Err(EditError::Malformed),
+            (Some(len), Some(hdr_len)) => layout.end.min(layout.l4 + hdr_len + len),
+            (None, _) => layout.end,
+        };
+        // The checksums of ICMP and SCTP do not cover the addresses.
+        let fix_l4 = match proto {
+            Some(IpProto::Tcp | IpProto::Udp | IpProto::Ipv6Icmp) => {
+                addr_changed || end != layout.end
+            }
+            Some(IpProto::Icmp | IpProto::Sctp) => end != layout.end,
+            _ => false,
+        } && l4_hdr_len.is_some();
+        if fix_l4 && layout.fragment {
+            // The checksum covers the payload of the other fragments.
+            return Err(EditError::Fragment);
+        }
+        if end > max_len {
+            return Err(EditError::FrameTooLarge);
+        }
+
+        // Everything was validated, the frame can now be modified.
+        let ip = &mut self.buf[layout.l3..end];
+        ip[ttl_at] = ttl;
+        if let Some(addr) = self.src_addr {
+            ip[src_at..src_at + addr_len].copy_from_slice(&octets(addr)[..addr_len]);
+        }
+        if let Some(addr) = self.dst_addr {
+            let dst_at = src_at + addr_len;
+            ip[dst_at..dst_at + addr_len].copy_from_slice(&octets(addr)[..addr_len]);
+        }
+        let ip_len = end - layout.l3;
+        if layout.v6 {
+            ip[4..6].copy_from_slice(&((ip_len - 40) as u16).to_be_bytes());
+        } else {
+            ip[2..4].copy_from_slice(&(ip_len as u16).to_be_bytes());
+            let hdr = &mut ip[..layout.l4 - layout.l3];
+            hdr[10..12].fill(0);
+            let check = checksum::checksum(hdr);
+            hdr[10..12].copy_from_slice(&check.to_be_bytes());
+        }
+
+        if fix_l4 {
+            let (ip, l4) = ip.split_at_mut(layout.l4 - layout.l3);
+            match proto {
+                Some(IpProto::Icmp) => {
+                    l4[2..4].fill(0);
+                    let check = checksum::checksum(l4);
+                    l4[2..4].copy_from_slice(&check.to_be_bytes());
+                }
+                Some(IpProto::Sctp) => {
+                    // The header was checked to be present.
+                    if let Some(check) = sctp::checksum(l4) {
+                        l4[8..12].copy_from_slice(&check);
+                    }
+                }
+                _ => fix_pseudo_header_checksum(ip, l4, src_at, addr_len, layout),
+            }
+        }
+        Ok(end)
+    }
+}
+
+/// Payload kept by [`trim_payload`].
+#[cfg(feature = "alloc")]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum TrimPolicy {
+    /// Keeps the headers only.
+    HeadersOnly,
+    /// Keeps up to the given number of payload bytes.
+    Payload(usize),
+}
+
+/// Shortens the Ethernet frame `packet` to its headers followed by the
+/// payload allowed by `keep`, the TCP, UDP, ICMP, ICMPv6 or SCTP header
+/// being kept and the payload of other protocols starting after the IP
+/// header. The Ethernet padding is removed. Frames which are not IP are
+/// left untouched.
+///
+/// The IP and UDP lengths and the checksums of the IPv4 header and of the
+/// upper-layer protocol are fixed so that the trimmed frame is still a
+/// valid packet. Fails as [`PacketEditor::apply`] does, e.g. for fragments
+/// whose upper-layer checksum cannot be fixed, in which case `packet` is
+/// left untouched.
+///
+/// ```
+/// use ether_packet::{
+///     edit::{trim_payload, TrimPolicy},
+///     packet::Packet,
+/// };
+///
+/// #[rustfmt::skip]
+/// let mut frame = vec![
+///     0, 1, 2, 3, 4, 5, 0, 6, 7, 8, 9, 10, 0x08, 0x00,
+///     0x45, 0, 0, 32, 0, 0, 0, 0, 64, 17, 0, 0, 10, 0, 0, 1, 10, 0, 0, 2,
+///     0x30, 0x39, 0, 53, 0, 12, 0, 0, 1, 2, 3, 4,
+///     // Ethernet padding.
+///     0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+/// ];
+/// trim_payload(&mut frame, TrimPolicy::HeadersOnly).unwrap();
+/// assert_eq!(frame.len(), 42);
+/// let packet = Packet::parse(&frame).unwrap();
+/// assert!(packet.ipv4().unwrap().verify_checksum());
+/// assert_eq!(packet.payload(), &[0x30, 0x39, 0, 53, 0, 8, 0, 0]);
+/// ```
+#[cfg(feature = "alloc")]
+pub fn trim_payload(packet: &mut Vec<u8>, keep: TrimPolicy) -> Result<(), EditError> {
+    let payload_len = match keep {
+        TrimPolicy::HeadersOnly => 0,
+        TrimPolicy::Payload(len) => len,
+    };
+    let len = packet.len();
+    match PacketEditor::new(packet, len)
+        .truncate_payload(payload_len)
+        .apply()
+    {
+        Ok(len) => packet.truncate(len),
+        Err(EditError::NotIp) => {}
+        Err(err) => return Err(err),
+    }
+    Ok(())
+}
+
+/// Recomputes the length of UDP datagrams and the checksum of the TCP, UDP
+/// or ICMPv6 message `l4`, covering the pseudo-header of the IP header `ip`.
+fn fix_pseudo_header_checksum(
+    ip: &[u8],
+    l4: &mut [u8],
+    src_at: usize,
+    addr_len: usize,
+    layout: &Layout,
+) {
+    let proto = IpProto::from_u8(layout.proto);
+    let check_at = match proto {
+        Some(IpProto::Tcp) => 16,
+        Some(IpProto::Udp) => 6,
+        _ => 2,
+    };
+    if proto == Some(IpProto::Udp) {
+        let udp_len = l4.len() as u16;
+        l4[4..6].copy_from_slice(&udp_len.to_be_bytes());
+    }
+    // A zero UDP checksum over IPv4 means no checksum.
+    if !layout.v6 && proto == Some(IpProto::Udp) && l4[6..8] == [0, 0] {
+        return;
+    }
+    l4[check_at..check_at + 2].fill(0);
+    let pseudo = checksum::sum(&ip[src_at..src_at + 2 * addr_len], 0);
+    let pseudo = checksum::sum(&(l4.len() as u32).to_be_bytes(), pseudo);
+    let pseudo = checksum::sum(&[0, layout.proto], pseudo);
+    let mut check = checksum::fold(checksum::sum(l4, pseudo));
+    if check == 0 && proto == Some(IpProto::Udp) {
+        check = 0xffff;
+    }
+    l4[check_at..check_at + 2].copy_from_slice(&check.to_be_bytes());
+}
+
+fn octets(addr: IpAddr) -> [u8; 16] {
+    match addr {
+        IpAddr::V4(addr) => {
+            let mut octets = [0; 16];
+            octets[..4].copy_from_slice(&addr.octets());
+            octets
+        }
+        IpAddr::V6(addr) => addr.octets(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::net::Ipv6Addr;
+
+    use super::{EditError, PacketEditor};
+    use crate::{checksum, ip::IpProto};
+
+    #[test]
+    fn test_packet_editor() {
+        #[rustfmt::skip]
+        let mut buf = [
+            0, 1, 2, 3, 4, 5, 0, 6, 7, 8, 9, 10, 0x86, 0xdd,
+            // IPv6, TCP.
+            0x60, 0, 0, 0, 0, 24, 6, 64,
+            0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1,
+            0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2,
+            0x30, 0x39, 0, 80, 0, 0, 0, 1, 0, 0, 0, 0, 0x50, 0x18, 0xff, 0xff, 0, 0, 0, 0,
+            b'a', b'b', b'c', b'd',
+        ];
+        let len = buf.len();
+        let orig = buf;
+
+        // The hop limit would expire, nothing is modified.
+        let err = PacketEditor::new(&mut buf, len)
+            .set_dst_addr(Ipv6Addr::LOCALHOST.into())
+            .decrement_ttl(64)
+            .apply();
+        assert_eq!(err, Err(EditError::TtlExpired));
+        assert_eq!(buf, orig);
+        let err = PacketEditor::new(&mut buf, len).push_vlan(1).apply();
+        assert_eq!(err, Err(EditError::BufferTooSmall));
+
+        let dst = Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 3);
+        let len = PacketEditor::new(&mut buf, len)
+            .set_dst_addr(dst.into())
+            .truncate_payload(1)
+            .apply()
+            .unwrap();
+        assert_eq!(len, 14 + 40 + 21);
+        assert_eq!(&buf[18..20], &[0, 21]);
+        assert_eq!(&buf[38..54], &dst.octets());
+        let pseudo = checksum::pseudo_header_v6(
+            Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1),
+            dst,
+            IpProto::Tcp,
+            21,
+        );
+        assert_eq!(checksum::fold(checksum::sum(&buf[54..len], pseudo)), 0);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_trim_payload() {
+        use super::{trim_payload, TrimPolicy};
+        use crate::{
+            ip::Ipv4Hdr,
+            packet::{Packet, TransportHdr},
+            sctp,
+        };
+        use alloc::vec::Vec;
+
+        let eth = |ether_type: [u8; 2]| {
+            let mut frame = Vec::from([0, 1, 2, 3, 4, 5, 0, 6, 7, 8, 9, 10]);
+            frame.extend_from_slice(&ether_type);
+            frame
+        };
+
+        // ICMP echo request with 4 bytes of data.
+        let mut icmp = eth([0x08, 0x00]);
+        #[rustfmt::skip]
+        icmp.extend_from_slice(&[
+            0x45, 0, 0, 32, 0, 0, 0, 0, 64, 1, 0, 0, 10, 0, 0, 1, 10, 0, 0, 2,
+            8, 0, 0, 0, 0, 1, 0, 1, b'p', b'i', b'n', b'g',
+        ]);
+        trim_payload(&mut icmp, TrimPolicy::HeadersOnly).unwrap();
+        assert_eq!(icmp.len(), 42);
+        let packet = Packet::parse(&icmp).unwrap();
+        assert!(matches!(packet.transport(), Some(TransportHdr::Icmp(_))));
+        assert!(packet.ipv4().is_some_and(Ipv4Hdr::verify_checksum));
+        assert_eq!(checksum::checksum(&icmp[34..]), 0);
+
+        // ICMPv6 echo request, its checksum covering the pseudo-header.
+        let (src, dst) = (
+            Ipv6Addr::LOCALHOST,
+            Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1),
+        );
+        let mut icmpv6 = eth([0x86, 0xdd]);
+        icmpv6.extend_from_slice(&[0x60, 0, 0, 0, 0, 12, 58, 64]);
+        icmpv6.extend_from_slice(&src.octets());
+        icmpv6.extend_from_slice(&dst.octets());
+        icmpv6.extend_from_slice(&[128, 0, 0, 0, 0, 1, 0, 1, b'p', b'i', b'n', b'g']);
+        trim_payload(&mut icmpv6, TrimPolicy::Payload(2)).unwrap();
+        assert_eq!(
+            (icmpv6.len(), &icmpv6[18..20]),
+            (14 + 40 + 10, &[0, 10][..])
+        );
+        assert!(checksum::verify_l4_v6(
+            src,
+            dst,
+            IpProto::Ipv6Icmp,
+            &icmpv6[54..]
+        ));
+
+        // SCTP with a DATA chunk, its CRC32c covering the whole packet.
+        let mut sctp = eth([0x08, 0x00]);
+        #[rustfmt::skip]
+        sctp.extend_from_slice(&[
+            0x45, 0, 0, 48, 0, 0, 0, 0, 64, 132, 0, 0, 10, 0, 0, 1, 10, 0, 0, 2,
+            0x0b, 0x59, 0x0b, 0x59, 0, 0, 0, 1, 0, 0, 0, 0,
+            0, 3, 0, 16, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0,
+        ]);
+        trim_payload(&mut sctp, TrimPolicy::Payload(4)).unwrap();
+        assert_eq!(sctp.len(), 14 + 20 + 12 + 4);
+        assert!(matches!(
+            Packet::parse(&sctp).unwrap().transport(),
+            Some(TransportHdr::Sctp(_))
+        ));
+        assert_eq!(
+            sctp::checksum(&sctp[34..]),
+            Some(sctp[42..46].try_into().unwrap())
+        );
+
+        // ARP is not trimmed.
+        let mut arp = eth([0x08, 0x06]);
+        arp.extend_from_slice(&[0, 1, 8, 0, 6, 4, 0, 1]);
+        arp.resize(60, 0);
+        let orig = arp.clone();
+        trim_payload(&mut arp, TrimPolicy::HeadersOnly).unwrap();
+        assert_eq!(arp, orig);
+    }
+}
@@ -1,15 +1,16 @@
 use core::mem;
 
-use crate::{bitfield::BitfieldUnit, types::U16};
+use crate::{bitfield::BitfieldUnit, builder::BuildError, header::impl_header, types::U16};
 
 /// Protocol which is encapsulated in the payload of the Ethernet frame.
 ///
 /// According [EtherType](https://en.wikipedia.org/wiki/EtherType)
 #[repr(u16)]
-#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Default)]
 #[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 pub enum EtherType {
     Loop = 0x0060,
+    #[default]
     Ipv4 = 0x0800,
     Arp = 0x0806,
     /// wake on lan
@@ -42,56 +43,98 @@ pub enum EtherType {
     Ipv6 = 0x86DD,
     MPLSUnicast = 0x8847,
     MPLSMulticast = 0x8848,
+    /// Ethernet flow control, i.e. PAUSE and PFC frames (IEEE 802.3x)
+    FlowControl = 0x8808,
     /// Ethernet Slow Protocols such as the Link Aggregation Control Protocol (LACP)
     LACP = 0x8809,
+    /// EAP over LAN (IEEE 802.1X)
+    EAPOL = 0x888E,
+    /// EtherCAT
+    EtherCAT = 0x88A4,
     /// Service VLAN tag identifier (S-Tag) on Q-in-Q tunnel
     QinQ = 0x88A8,
+    /// Generic Object Oriented Substation Event (IEC 61850-8-1)
+    GOOSE = 0x88B8,
+    /// Generic Substation Events management (IEC 61850-8-1)
+    GSE = 0x88B9,
+    /// Sampled Values (IEC 61850-9-2)
+    SV = 0x88BA,
     /// Link Layer Discovery Protocol
     LLDP = 0x88CC,
+    /// Media Redundancy Protocol (IEC 62439-2)
+    MRP = 0x88E3,
+    /// MAC security (IEEE 802.1AE)
+    MACsec = 0x88E5,
+    /// Multiple VLAN Registration Protocol (IEEE 802.1Q)
+    MVRP = 0x88F5,
+    /// Precision Time Protocol (IEEE 1588) over Ethernet
+    PTP = 0x88F7,
+    /// Connectivity Fault Management (IEEE 802.1ag) and ITU-T Y.1731 OAM
+    CFM = 0x8902,
     FibreChannel = 0x8906,
     /// RDMA over Converged Ethernet (RoCE)
     RoCE = 0x8915,
     LoopbackIeee8023 = 0x9000,
+    /// OPC UA PubSub UADP network messages (OPC 10000-14)
+    UADP = 0xB62C,
 }
 
-impl TryFrom<u16> for EtherType {
-    type Error = ();
-    fn try_from(value: u16) -> Result<Self, Self::Error> {
+impl EtherType {
+    /// Converts a raw EtherType value, returning `None` for unknown values.
+    #[inline]
+    pub const fn from_u16(value: u16) -> Option<EtherType> {
         match value {
-            0x0060 => Ok(EtherType::Loop),
-            0x0800 => Ok(EtherType::Ipv4),
-            0x0806 => Ok(EtherType::Arp),
-            0x0842 => Ok(EtherType::WakeOnLan),
-            0x2000 => Ok(EtherType::CDP),
-            0x22EA => Ok(EtherType::SRP),
-            0x22F0 => Ok(EtherType::AVTP),
-            0x22F3 => Ok(EtherType::TRILL),
-            0x6002 => Ok(EtherType::MOP),
-            0x6003 => Ok(EtherType::DECnet),
-            0x6004 => Ok(EtherType::DECLAT),
-            0x8035 => Ok(EtherType::RARP),
-            0x809B => Ok(EtherType::AppleTalk),
-            0x80F3 => Ok(EtherType::AARP),
-            0x8100 => Ok(EtherType::VLAN),
-            0x8102 => Ok(EtherType::SLPP),
-            0x8103 => Ok(EtherType::VLACP),
-            0x86DD => Ok(EtherType::Ipv6),
-            0x8847 => Ok(EtherType::MPLSUnicast),
-            0x8848 => Ok(EtherType::MPLSMulticast),
-            0x8809 => Ok(EtherType::LACP),
-            0x88A8 => Ok(EtherType::QinQ),
-            0x88CC => Ok(EtherType::LLDP),
-            0x8906 => Ok(EtherType::FibreChannel),
-            0x8915 => Ok(EtherType::RoCE),
-            0x9000 => Ok(EtherType::LoopbackIeee8023),
-            _ => Err(()),
+            0x0060 => Some(EtherType::Loop),
+            0x0800 => Some(EtherType::Ipv4),
+            0x0806 => Some(EtherType::Arp),
+            0x0842 => Some(EtherType::WakeOnLan),
+            0x2000 => Some(EtherType::CDP),
+            0x22EA => Some(EtherType::SRP),
+            0x22F0 => Some(EtherType::AVTP),
+            0x22F3 => Some(EtherType::TRILL),
+            0x6002 => Some(EtherType::MOP),
+            0x6003 => Some(EtherType::DECnet),
+            0x6004 => Some(EtherType::DECLAT),
+            0x8035 => Some(EtherType::RARP),
+            0x809B => Some(EtherType::AppleTalk),
+            0x80F3 => Some(EtherType::AARP),
+            0x8100 => Some(EtherType::VLAN),
+            0x8102 => Some(EtherType::SLPP),
+            0x8103 => Some(EtherType::VLACP),
+            0x86DD => Some(EtherType::Ipv6),
+            0x8847 => Some(EtherType::MPLSUnicast),
+            0x8848 => Some(EtherType::MPLSMulticast),
+            0x8808 => Some(EtherType::FlowControl),
+            0x8809 => Some(EtherType::LACP),
+            0x888E => Some(EtherType::EAPOL),
+            0x88A4 => Some(EtherType::EtherCAT),
+            0x88A8 => Some(EtherType::QinQ),
+            0x88B8 => Some(EtherType::GOOSE),
+            0x88B9 => Some(EtherType::GSE),
+            0x88BA => Some(EtherType::SV),
+            0x88CC => Some(EtherType::LLDP),
+            0x88E3 => Some(EtherType::MRP),
+            0x88E5 => Some(EtherType::MACsec),
+            0x88F5 => Some(EtherType::MVRP),
+            0x88F7 => Some(EtherType::PTP),
+            0x8902 => Some(EtherType::CFM),
+            0x8906 => Some(EtherType::FibreChannel),
+            0x8915 => Some(EtherType::RoCE),
+            0x9000 => Some(EtherType::LoopbackIeee8023),
+            0xB62C => Some(EtherType::UADP),
+            _ => None,
         }
     }
+
+    pub const fn is_vlan(&self) -> bool {
+        matches!(self, EtherType::VLAN | EtherType::QinQ)
+    }
 }
 
-impl Default for EtherType {
-    fn default() -> Self {
-        EtherType::Ipv4
+impl TryFrom<u16> for EtherType {
+    type Error = ();
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        Self::from_u16(value).ok_or(())
     }
 }
 
@@ -102,12 +145,6 @@ impl TryFrom<U16> for EtherType {
     }
 }
 
-impl EtherType {
-    pub fn is_vlan(&self) -> bool {
-        self == &EtherType::VLAN || self == &EtherType::QinQ
-    }
-}
-
 /// Ethernet header, which is present at the beginning of every Ethernet frame.
 #[repr(C, packed)]
 #[derive(Debug, Copy, Clone, Default)]
@@ -124,9 +161,16 @@ pub struct EthHdr {
 impl EthHdr {
     pub const LEN: usize = mem::size_of::<EthHdr>();
 
+    /// Parses a header from its wire representation in const contexts.
+    #[inline]
+    pub const fn parse_const(bytes: &[u8; Self::LEN]) -> Self {
+        // SAFETY: `EthHdr` is packed and every bit pattern is valid.
+        unsafe { mem::transmute::<[u8; Self::LEN], Self>(*bytes) }
+    }
+
     #[inline(always)]
-    pub fn ether_type(&self) -> Option<EtherType> {
-        self.ether_type.try_into().ok()
+    pub const fn ether_type(&self) -> Option<EtherType> {
+        EtherType::from_u16(self.ether_type.to_bits())
     }
 }
 
@@ -149,9 +193,16 @@ pub struct QinQHdr {
 
 impl QinQHdr {
     pub const LEN: usize = mem::size_of::<QinQHdr>();
+
+    /// Parses a header from its wire representation in const contexts.
+    #[inline]
+    pub const fn parse_const(bytes: &[u8; Self::LEN]) -> Self {
+        // SAFETY: `QinQHdr` is packed and every bit pattern is valid.
+        unsafe { mem::transmute::<[u8; Self::LEN], Self>(*bytes) }
+    }
     #[inline(always)]
-    pub fn ether_type(&self) -> Option<EtherType> {
-        self.ether_type.try_into().ok()
+    pub const fn ether_type(&self) -> Option<EtherType> {
+        EtherType::from_u16(self.ether_type.to_bits())
     }
 }
 
@@ -173,21 +224,21 @@ pub struct VlanHdr {
     pub tpid: U16,
     /// tag control information: A 16-bit field containing the following sub-fields:
     /// - **Priority code point (PCP)**: A 3-bit field which refers to the IEEE 802.1p
-    /// class of service (CoS) and maps to the frame priority level. Different PCP values
-    /// can be used to prioritize different classes of traffic.
+    ///   class of service (CoS) and maps to the frame priority level. Different PCP values
+    ///   can be used to prioritize different classes of traffic.
     /// - **Drop eligible indicator (DEI)**: A 1-bit field. (formerly CFI) May be used
-    /// separately or in conjunction with PCP to indicate frames eligible to be dropped
-    /// in the presence of congestion.
+    ///   separately or in conjunction with PCP to indicate frames eligible to be dropped
+    ///   in the presence of congestion.
     /// - **VLAN identifier (VID)**: A 12-bit field specifying the VLAN to which the
-    /// frame belongs. The values of 0 and 4095 (0x000 and 0xFFF in hexadecimal) are
-    /// reserved. All other values may be used as VLAN identifiers, allowing up to
-    /// 4,094 VLANs. The reserved value 0x000 indicates that the frame does not
-    /// carry a VLAN ID; in this case, the 802.1Q tag specifies only a priority
-    /// (in PCP and DEI fields) and is referred to as a priority tag. On bridges,
-    /// VID 0x001 (the default VLAN ID) is often reserved for a network management
-    /// VLAN; this is vendor-specific. The VID value 0xFFF is reserved for implementation
-    /// use; it must not be configured or transmitted. 0xFFF can be used to indicate
-    /// a wildcard match in management operations or filtering database entries.
+    ///   frame belongs. The values of 0 and 4095 (0x000 and 0xFFF in hexadecimal) are
+    ///   reserved. All other values may be used as VLAN identifiers, allowing up to
+    ///   4,094 VLANs. The reserved value 0x000 indicates that the frame does not
+    ///   carry a VLAN ID; in this case, the 802.1Q tag specifies only a priority
+    ///   (in PCP and DEI fields) and is referred to as a priority tag. On bridges,
+    ///   VID 0x001 (the default VLAN ID) is often reserved for a network management
+    ///   VLAN; this is vendor-specific. The VID value 0xFFF is reserved for implementation
+    ///   use; it must not be configured or transmitted. 0xFFF can be used to indicate
+    ///   a wildcard match in management operations or filtering database entries.
     pub tci: BitfieldUnit<[u8; 2usize]>,
     /// Protocol which is encapsulated in the payload of the frame.
     pub ether_type: U16,
@@ -195,29 +246,48 @@ pub struct VlanHdr {
 
 impl VlanHdr {
     pub const LEN: usize = mem::size_of::<VlanHdr>();
+
+    /// Parses a header from its wire representation in const contexts.
+    #[inline]
+    pub const fn parse_const(bytes: &[u8; Self::LEN]) -> Self {
+        // SAFETY: `VlanHdr` is packed and every bit pattern is valid.
+        unsafe { mem::transmute::<[u8; Self::LEN], Self>(*bytes) }
+    }
+    /// Tag control information, in host byte order.
+    #[inline]
+    const fn tci_bits(&self) -> u16 {
+        ((self.tci.get(0, 8) << 8) | self.tci.get(8, 8)) as u16
+    }
+
+    #[inline]
+    const fn set_tci_bits(&mut self, val: u16) {
+        self.tci.set(0, 8, (val >> 8) as u64);
+        self.tci.set(8, 8, (val & 0xff) as u64);
+    }
+
     /// VLAN ID (VID), indicating the VLAN to which a frame belongs.
     ///
     /// 12bits
     ///
     /// The VLAN ID is in the range from 0 to 4095. The values 0 and 4095 are reserved, and therefore available VLAN IDs are in the range from 1 to 4094.
     #[inline]
-    pub fn vid(&self) -> u16 {
-        self.tci.get(0usize, 12u8) as u16
+    pub const fn vid(&self) -> u16 {
+        self.tci_bits() & 0xfff
     }
 
     #[inline]
-    pub fn set_vid(&mut self, val: u16) {
-        self.tci.set(0usize, 12u8, val as u64)
+    pub const fn set_vid(&mut self, val: u16) {
+        self.set_tci_bits((self.tci_bits() & !0xfff) | (val & 0xfff))
     }
 
     #[inline]
-    pub fn dei(&self) -> bool {
-        self.tci.get_bit(12)
+    pub const fn dei(&self) -> bool {
+        self.tci_bits() & 0x1000 != 0
     }
 
     #[inline]
-    pub fn set_cfi(&mut self, val: bool) {
-        self.tci.set(12usize, 1u8, if val { 1 } else { 0 } as u64)
+    pub const fn set_cfi(&mut self, val: bool) {
+        self.set_tci_bits((self.tci_bits() & !0x1000) | if val { 0x1000 } else { 0 })
     }
 
     /// Priority code point (PCP), indicating the 802.1p priority of a frame.
@@ -227,27 +297,226 @@ impl VlanHdr {
     /// The value is in the range from 0 to 7. A larger value indicates a higher priority.
     /// If congestion occurs, the switch sends packets with the highest priority first.
     #[inline]
-    pub fn pcp(&self) -> u8 {
-        self.tci.get(13usize, 3u8) as u8
+    pub const fn pcp(&self) -> u8 {
+        (self.tci_bits() >> 13) as u8
+    }
+
+    #[inline]
+    pub const fn set_pcp(&mut self, val: u8) {
+        self.set_tci_bits((self.tci_bits() & 0x1fff) | ((val as u16 & 0x7) << 13))
     }
 
+    /// Whether the tag carries a VLAN ID or only a priority.
     #[inline]
-    pub fn set_pcp(&mut self, val: u8) {
-        self.tci.set(13usize, 3u8, val as u64)
+    pub const fn kind(&self) -> VlanTagKind {
+        VlanTagKind::from_vid(self.vid())
     }
 
     #[inline(always)]
-    pub fn ether_type(&self) -> Option<EtherType> {
-        self.ether_type.try_into().ok()
+    pub const fn ether_type(&self) -> Option<EtherType> {
+        EtherType::from_u16(self.ether_type.to_bits())
+    }
+}
+
+impl_header!(EthHdr, QinQHdr, VlanHdr);
+
+/// Kind of an 802.1Q tag, telling VLAN tags from priority tags.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum VlanTagKind {
+    /// The tag assigns the frame to a VLAN.
+    Vlan,
+    /// Priority tag, with a VID of 0: the tag only carries the PCP and the
+    /// DEI, and the frame belongs to the VLAN of an untagged frame. Common
+    /// with TSN and AVB endpoints.
+    PriorityOnly,
+}
+
+impl VlanTagKind {
+    #[inline]
+    pub const fn from_vid(vid: u16) -> Self {
+        match vid {
+            0 => VlanTagKind::PriorityOnly,
+            _ => VlanTagKind::Vlan,
+        }
     }
 }
 
+/// Largest Ethernet frame allowed on a link, excluding the FCS.
+///
+/// A standard frame carries up to 1500 bytes of payload, i.e. 1514 bytes
+/// with the Ethernet header. Jumbo frames raise this limit up to
+/// [`FrameSize::MAX_JUMBO_LEN`]. With baby giants, each 802.1Q or 802.1ad
+/// tag may extend a frame by another 4 bytes, as for the 1518-byte tagged
+/// frame of 802.1Q, so that tagging does not reduce the payload size.
+///
+/// ```
+/// use ether_packet::eth::FrameSize;
+///
+/// let size = FrameSize::jumbo(9000).unwrap().with_baby_giants(true);
+/// assert_eq!(size.mtu(), 9000 - 14);
+/// assert_eq!(size.limit(2), 9008);
+/// assert!(FrameSize::jumbo(9217).is_none());
+/// ```
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct FrameSize {
+    max_len: usize,
+    baby_giants: bool,
+}
+
+impl FrameSize {
+    /// Largest untagged standard frame.
+    pub const STANDARD_LEN: usize = 1514;
+    /// Largest jumbo frame supported.
+    pub const MAX_JUMBO_LEN: usize = 9216;
+    /// Standard frames, without baby giants.
+    pub const STANDARD: Self = Self {
+        max_len: Self::STANDARD_LEN,
+        baby_giants: false,
+    };
+
+    /// Frames of up to `max_len` bytes, which must lie between
+    /// [`FrameSize::STANDARD_LEN`] and [`FrameSize::MAX_JUMBO_LEN`].
+    #[inline]
+    pub const fn jumbo(max_len: usize) -> Option<Self> {
+        if max_len < Self::STANDARD_LEN || max_len > Self::MAX_JUMBO_LEN {
+            return None;
+        }
+        Some(Self {
+            max_len,
+            baby_giants: false,
+        })
+    }
+
+    /// Allows VLAN tags to extend frames past the maximum length.
+    #[inline]
+    pub const fn with_baby_giants(mut self, allow: bool) -> Self {
+        self.baby_giants = allow;
+        self
+    }
+
+    /// Largest untagged frame.
+    #[inline]
+    pub const fn max_len(&self) -> usize {
+        self.max_len
+    }
+
+    #[inline]
+    pub const fn baby_giants(&self) -> bool {
+        self.baby_giants
+    }
+
+    /// Largest payload of an untagged frame, i.e. the MTU of the link.
+    #[inline]
+    pub const fn mtu(&self) -> usize {
+        self.max_len - EthHdr::LEN
+    }
+
+    /// Largest frame carrying `tags` VLAN tags.
+    #[inline]
+    pub const fn limit(&self, tags: usize) -> usize {
+        if self.baby_giants {
+            self.max_len + 4 * tags
+        } else {
+            self.max_len
+        }
+    }
+
+    /// Checks that `frame` does not exceed the limit for its VLAN tags.
+    pub fn check(&self, frame: &[u8]) -> Result<(), BuildError> {
+        if frame.len() > self.limit(vlan_tags(frame)) {
+            return Err(BuildError::FrameTooLarge);
+        }
+        Ok(())
+    }
+}
+
+impl Default for FrameSize {
+    fn default() -> Self {
+        Self::STANDARD
+    }
+}
+
+/// Length of the frame check sequence ending Ethernet frames on the wire.
+pub const FCS_LEN: usize = 4;
+
+/// Table of the reflected CRC-32 polynomial of IEEE 802.3.
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// CRC-32 of IEEE 802.3, as used for the FCS of Ethernet frames.
+#[inline]
+pub fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |crc, &b| {
+        (crc >> 8) ^ CRC32_TABLE[((crc ^ b as u32) & 0xff) as usize]
+    })
+}
+
+/// Returns the FCS of `frame`, in the order it follows the frame on the
+/// wire.
+///
+/// ```
+/// use ether_packet::eth::{fcs, verify_fcs};
+///
+/// let mut frame = [0u8; 64];
+/// frame[..12].copy_from_slice(&[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 2, 0, 0, 0, 0, 1]);
+/// let check = fcs(&frame[..60]);
+/// frame[60..].copy_from_slice(&check);
+/// assert!(verify_fcs(&frame));
+/// ```
+#[inline]
+pub fn fcs(frame: &[u8]) -> [u8; FCS_LEN] {
+    crc32(frame).to_le_bytes()
+}
+
+/// Whether `frame`, including its trailing FCS, is free of errors.
+#[inline]
+pub fn verify_fcs(frame: &[u8]) -> bool {
+    match frame.len().checked_sub(FCS_LEN) {
+        Some(len) => fcs(&frame[..len]) == frame[len..],
+        None => false,
+    }
+}
+
+/// Counts the 802.1Q and 802.1ad tags following the MAC addresses of `frame`.
+pub(crate) fn vlan_tags(frame: &[u8]) -> usize {
+    let mut pos = EthHdr::LEN - 2;
+    while let Some(&[hi, lo]) = frame.get(pos..pos + 2) {
+        let tpid = u16::from_be_bytes([hi, lo]);
+        if tpid != EtherType::VLAN as u16 && tpid != EtherType::QinQ as u16 {
+            break;
+        }
+        pos += 4;
+    }
+    (pos - (EthHdr::LEN - 2)) / 4
+}
+
 #[cfg(test)]
 mod test {
     use core::mem;
 
     use super::EthHdr;
     use super::EtherType;
+    use super::FrameSize;
+    use crate::builder::BuildError;
 
     #[test]
     fn validate_etherheader() {
@@ -258,11 +527,56 @@ mod test {
             0x08, 0x00, // 协议类型 (IPv4, 大端字节序)
         ];
 
-        let ethhdr: EthHdr =
-            unsafe { mem::transmute::<[u8; EthHdr::LEN], _>(data_stream.try_into().unwrap()) };
+        let ethhdr: EthHdr = unsafe { mem::transmute::<[u8; EthHdr::LEN], _>(data_stream) };
 
         assert_eq!(ethhdr.ether_type.to_bits(), EtherType::Ipv4 as u16);
         assert_eq!(ethhdr.dst_addr, [0xFF_u8, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]);
         assert_eq!(ethhdr.src_addr, [0x00, 0x11, 0x22, 0x33, 0x44, 0x55]);
+
+        for ether_type in [
+            EtherType::FlowControl,
+            EtherType::EAPOL,
+            EtherType::EtherCAT,
+            EtherType::GOOSE,
+            EtherType::GSE,
+            EtherType::SV,
+            EtherType::MRP,
+            EtherType::MACsec,
+            EtherType::PTP,
+            EtherType::UADP,
+        ] {
+            assert_eq!(EtherType::try_from(ether_type as u16), Ok(ether_type));
+            assert!(!ether_type.is_vlan());
+        }
+    }
+
+    #[test]
+    fn test_fcs() {
+        assert_eq!(super::crc32(b"123456789"), 0xcbf4_3926);
+        let mut frame = [0u8; 64];
+        frame[..6].fill(0xff);
+        frame[12..14].copy_from_slice(&[0x08, 0x06]);
+        let check = super::fcs(&frame[..60]);
+        frame[60..].copy_from_slice(&check);
+        assert!(super::verify_fcs(&frame));
+        frame[20] ^= 1;
+        assert!(!super::verify_fcs(&frame));
+        assert!(!super::verify_fcs(&frame[..3]));
+    }
+
+    #[test]
+    fn test_frame_size() {
+        let mut frame = [0u8; 1518];
+        frame[12..14].copy_from_slice(&[0x81, 0x00]);
+        frame[16..18].copy_from_slice(&[0x08, 0x00]);
+        assert_eq!(
+            FrameSize::STANDARD.check(&frame),
+            Err(BuildError::FrameTooLarge)
+        );
+        let size = FrameSize::STANDARD.with_baby_giants(true);
+        assert_eq!(size.check(&frame), Ok(()));
+        assert_eq!(size.check(&[0u8; 1515]), Err(BuildError::FrameTooLarge));
+        assert_eq!(FrameSize::jumbo(9216).unwrap().check(&[0u8; 9216]), Ok(()));
+        assert!(FrameSize::jumbo(1500).is_none());
     }
 }
@@ -0,0 +1,588 @@
+//! Flow keys identifying the conversation a packet belongs to.
+//!
+//! [`FlowKey`] is the classic 5-tuple of an IP packet. [`OverlayFlowKey`]
+//! additionally walks into the overlay tunnels recognized by [`Tunnel`],
+//! GTP-U and ESP tunnels, keying on the outer 5-tuple, the tunnel identifier
+//! and the 5-tuple of the encapsulated packet.
+
+use core::net::{IpAddr, Ipv6Addr};
+
+use crate::{
+    eth::{EthHdr, EtherType},
+    header::{Header, ParseConfig, ParseError},
+    ip::{IpProto, Ipv4Hdr, Ipv6Hdr},
+    tunnel::{Inner, Tunnel},
+    udp::UdpHdr,
+};
+
+/// UDP port of the GTP user plane protocol (GTP-U).
+pub const GTPU_PORT: u16 = 2152;
+
+/// Ports below it are well-known ports, assigned to services.
+const WELL_KNOWN_PORTS: u16 = 1024;
+
+/// TCP SYN flag.
+const TCP_SYN: u8 = 0x02;
+/// TCP ACK flag.
+const TCP_ACK: u8 = 0x10;
+
+/// Direction of a packet within its connection.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum Direction {
+    ClientToServer,
+    ServerToClient,
+}
+
+impl Direction {
+    /// Direction of the packets flowing the other way.
+    #[inline]
+    pub const fn reversed(self) -> Self {
+        match self {
+            Direction::ClientToServer => Direction::ServerToClient,
+            Direction::ServerToClient => Direction::ClientToServer,
+        }
+    }
+}
+
+/// 5-tuple of an IP packet.
+///
+/// Ports are 0 for protocols without ports and for non-first fragments.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct FlowKey {
+    pub src_addr: IpAddr,
+    pub dst_addr: IpAddr,
+    pub proto: IpProto,
+    pub src_port: u16,
+    pub dst_port: u16,
+}
+
+impl FlowKey {
+    /// Extracts the 5-tuple of the IPv4 or IPv6 packet stored in `bytes`.
+    #[inline]
+    pub fn from_ip(bytes: &[u8]) -> Option<Self> {
+        parse_ip(bytes, &ParseConfig::DEFAULT)
+            .ok()
+            .map(|(key, _)| key)
+    }
+
+    /// Key of the packets flowing in the opposite direction.
+    #[inline]
+    pub fn reversed(&self) -> Self {
+        Self {
+            src_addr: self.dst_addr,
+            dst_addr: self.src_addr,
+            proto: self.proto,
+            src_port: self.dst_port,
+            dst_port: self.src_port,
+        }
+    }
+
+    /// Direction of the packets of this key, guessed from the ports: a
+    /// well-known port facing a port which is not is the port of the
+    /// server, and otherwise the lower port is.
+    ///
+    /// Keys with equal ports, including those without ports, are ordered
+    /// by their addresses, so that both directions of a flow always get
+    /// opposite directions.
+    pub fn direction(&self) -> Direction {
+        let (src, dst) = (self.src_port, self.dst_port);
+        let to_server = match (src < WELL_KNOWN_PORTS, dst < WELL_KNOWN_PORTS) {
+            (false, true) => true,
+            (true, false) => false,
+            _ if src != dst => dst < src,
+            _ => *self <= self.reversed(),
+        };
+        if to_server {
+            Direction::ClientToServer
+        } else {
+            Direction::ServerToClient
+        }
+    }
+
+    /// Key of the flow in the client-to-server direction, and the direction
+    /// of this key, see [`FlowKey::direction`].
+    ///
+    /// ```
+    /// use ether_packet::{
+    ///     flow::{Direction, FlowKey},
+    ///     ip::IpProto,
+    /// };
+    ///
+    /// let reply = FlowKey {
+    ///     src_addr: "192.0.2.80".parse().unwrap(),
+    ///     dst_addr: "198.51.100.1".parse().unwrap(),
+    ///     proto: IpProto::Tcp,
+    ///     src_port: 443,
+    ///     dst_port: 51000,
+    /// };
+    /// let (key, direction) = reply.canonicalize();
+    /// assert_eq!(direction, Direction::ServerToClient);
+    /// assert_eq!((key.src_port, key.dst_port), (51000, 443));
+    /// assert_eq!(key.canonicalize(), (key, Direction::ClientToServer));
+    /// ```
+    #[inline]
+    pub fn canonicalize(&self) -> (Self, Direction) {
+        self.canonicalize_as(self.direction())
+    }
+
+    /// Same as [`FlowKey::canonicalize`] for a TCP segment carrying the
+    /// `flags` of its header: a SYN is sent by the client and a SYN-ACK
+    /// by the server, whatever their ports. Other segments fall back to
+    /// the ports.
+    #[inline]
+    pub fn canonicalize_tcp(&self, flags: u8) -> (Self, Direction) {
+        let direction = match flags & (TCP_SYN | TCP_ACK) {
+            TCP_SYN => Direction::ClientToServer,
+            f if f == TCP_SYN | TCP_ACK => Direction::ServerToClient,
+            _ => self.direction(),
+        };
+        self.canonicalize_as(direction)
+    }
+
+    #[inline]
+    fn canonicalize_as(&self, direction: Direction) -> (Self, Direction) {
+        match direction {
+            Direction::ClientToServer => (*self, direction),
+            Direction::ServerToClient => (self.reversed(), direction),
+        }
+    }
+
+    /// SipHash-2-4 of the key, keyed with `key`, e.g. to spread flows over
+    /// sampling ranges or paths in a way outsiders cannot predict.
+    pub fn keyed_hash(&self, key: &[u64; 2]) -> u64 {
+        let mut msg = [0u8; 38];
+        let (src, dst) = match (self.src_addr, self.dst_addr) {
+            (IpAddr::V4(src), IpAddr::V4(dst)) => (src.to_ipv6_mapped(), dst.to_ipv6_mapped()),
+            (IpAddr::V6(src), IpAddr::V6(dst)) => {
+                msg[0] = 1;
+                (src, dst)
+            }
+            (src, dst) => (to_ipv6(src), to_ipv6(dst)),
+        };
+        msg[1] = self.proto as u8;
+        msg[2..4].copy_from_slice(&self.src_port.to_be_bytes());
+        msg[4..6].copy_from_slice(&self.dst_port.to_be_bytes());
+        msg[6..22].copy_from_slice(&src.octets());
+        msg[22..38].copy_from_slice(&dst.octets());
+        siphash(key, &msg)
+    }
+}
+
+#[inline]
+fn to_ipv6(addr: IpAddr) -> Ipv6Addr {
+    match addr {
+        IpAddr::V4(addr) => addr.to_ipv6_mapped(),
+        IpAddr::V6(addr) => addr,
+    }
+}
+
+/// SipHash-2-4 of `data`.
+pub(crate) fn siphash(key: &[u64; 2], data: &[u8]) -> u64 {
+    let mut v = [
+        key[0] ^ 0x736f6d6570736575,
+        key[1] ^ 0x646f72616e646f6d,
+        key[0] ^ 0x6c7967656e657261,
+        key[1] ^ 0x7465646279746573,
+    ];
+    let mut chunks = data.chunks_exact(8);
+    for chunk in &mut chunks {
+        let mut word = [0u8; 8];
+        word.copy_from_slice(chunk);
+        compress(&mut v, u64::from_le_bytes(word));
+    }
+    let mut last = [0u8; 8];
+    last[..chunks.remainder().len()].copy_from_slice(chunks.remainder());
+    last[7] = data.len() as u8;
+    compress(&mut v, u64::from_le_bytes(last));
+    v[2] ^= 0xff;
+    (0..4).for_each(|_| sipround(&mut v));
+    v[0] ^ v[1] ^ v[2] ^ v[3]
+}
+
+#[inline]
+fn compress(v: &mut [u64; 4], m: u64) {
+    v[3] ^= m;
+    sipround(v);
+    sipround(v);
+    v[0] ^= m;
+}
+
+#[inline]
+fn sipround(v: &mut [u64; 4]) {
+    v[0] = v[0].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(13) ^ v[0];
+    v[0] = v[0].rotate_left(32);
+    v[2] = v[2].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(16) ^ v[2];
+    v[0] = v[0].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(21) ^ v[0];
+    v[2] = v[2].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(17) ^ v[2];
+    v[2] = v[2].rotate_left(32);
+}
+
+/// Returns the 5-tuple of the IP packet in `bytes` and its transport header,
+/// unless it is a non-first fragment.
+fn parse_ip<'a>(
+    bytes: &'a [u8],
+    config: &ParseConfig,
+) -> Result<(FlowKey, Option<&'a [u8]>), ParseError> {
+    const MALFORMED: ParseError = ParseError::Malformed;
+    let (src_addr, dst_addr, proto, l4) = match bytes.first().ok_or(MALFORMED)? >> 4 {
+        4 => {
+            let hdr = Ipv4Hdr::from_bytes(bytes).ok_or(MALFORMED)?;
+            let l4 = bytes.get(hdr.hdrlen()..).ok_or(MALFORMED)?;
+            let l4 = hdr.has_l4_header().then_some(l4);
+            (hdr.src_addr.into(), hdr.dst_addr.into(), hdr.proto, l4)
+        }
+        6 => {
+            let hdr = Ipv6Hdr::from_bytes(bytes).ok_or(MALFORMED)?;
+            let (proto, l4) = skip_ipv6_ext_hdrs(hdr.next_hdr, &bytes[Ipv6Hdr::LEN..], config)?;
+            (hdr.src_addr.into(), hdr.dst_addr.into(), proto, l4)
+        }
+        _ => return Err(MALFORMED),
+    };
+    let (src_port, dst_port) = match (proto, l4) {
+        (IpProto::Tcp | IpProto::Udp | IpProto::Sctp | IpProto::UdpLite, Some(l4)) => {
+            let [s0, s1, d0, d1, ..] = *l4 else {
+                return Err(MALFORMED);
+            };
+            (u16::from_be_bytes([s0, s1]), u16::from_be_bytes([d0, d1]))
+        }
+        _ => (0, 0),
+    };
+    let key = FlowKey {
+        src_addr,
+        dst_addr,
+        proto,
+        src_port,
+        dst_port,
+    };
+    Ok((key, l4))
+}
+
+/// Skips the IPv6 extension headers preceding the upper-layer header.
+fn skip_ipv6_ext_hdrs<'a>(
+    mut next_hdr: IpProto,
+    mut data: &'a [u8],
+    config: &ParseConfig,
+) -> Result<(IpProto, Option<&'a [u8]>), ParseError> {
+    let next = |data: &[u8]| {
+        data.first()
+            .and_then(|&proto| IpProto::from_u8(proto))
+            .ok_or(ParseError::Malformed)
+    };
+    for _ in 0..=config.max_ipv6_ext_hdrs {
+        let len = match next_hdr {
+            IpProto::HopOpt | IpProto::Ipv6Route | IpProto::Ipv6Opts => {
+                let len = (*data.get(1).ok_or(ParseError::Malformed)? as usize + 1) * 8;
+                if next_hdr != IpProto::Ipv6Route && len > config.max_options_len {
+                    return Err(ParseError::LimitExceeded);
+                }
+                len
+            }
+            IpProto::Ipv6Frag => {
+                let [_, _, o0, o1, ..] = *data else {
+                    return Err(ParseError::Malformed);
+                };
+                if u16::from_be_bytes([o0, o1]) >> 3 != 0 {
+                    return Ok((next(data)?, None));
+                }
+                8
+            }
+            _ => return Ok((next_hdr, Some(data))),
+        };
+        next_hdr = next(data)?;
+        data = data.get(len..).ok_or(ParseError::Malformed)?;
+    }
+    Err(ParseError::LimitExceeded)
+}
+
+/// Identifier of a tunnel, carried in the outer packet.
+#[derive(PartialEq, Eq, Hash, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum TunnelId {
+    /// VXLAN or Geneve network identifier.
+    Vni(u32),
+    /// GTP-U tunnel endpoint identifier.
+    Teid(u32),
+    /// IPsec ESP security parameters index.
+    Spi(u32),
+    /// GRE key, GRE packets without key leaving the tunnel unset.
+    GreKey(u32),
+    /// Top label of an MPLS-in-UDP packet.
+    MplsLabel(u32),
+}
+
+/// Flow key of a possibly tunneled packet.
+///
+/// For packets which are not tunneled, only `outer` is set. The inner
+/// 5-tuple of ESP tunnels is encrypted and thus never available.
+#[derive(PartialEq, Eq, Hash, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct OverlayFlowKey {
+    pub outer: FlowKey,
+    pub tunnel: Option<TunnelId>,
+    pub inner: Option<FlowKey>,
+}
+
+impl OverlayFlowKey {
+    /// Extracts the flow key of the IPv4 or IPv6 packet stored in `bytes`,
+    /// looking into VXLAN (UDP port 4789), Geneve (UDP port 6081), GTP-U
+    /// (UDP port 2152), GRE, GRE-in-UDP (UDP port 4754), MPLS-in-UDP (UDP
+    /// port 6635) and ESP tunnels.
+    ///
+    /// Returns `None` if the outer packet is malformed. A malformed tunnel
+    /// header or inner packet only leaves the inner key unset.
+    #[inline]
+    pub fn from_ip(bytes: &[u8]) -> Option<Self> {
+        Self::from_ip_with_config(bytes, &ParseConfig::DEFAULT).ok()
+    }
+
+    /// Extracts the flow key like [`OverlayFlowKey::from_ip`], within the
+    /// limits of `config`.
+    ///
+    /// Fails with [`ParseError::LimitExceeded`] if the outer or the inner
+    /// packet goes beyond a limit, including a tunnel when
+    /// [`ParseConfig::tunnels`] is unset.
+    pub fn from_ip_with_config(bytes: &[u8], config: &ParseConfig) -> Result<Self, ParseError> {
+        let (outer, l4) = parse_ip(bytes, config)?;
+        let (tunnel, inner) = match l4 {
+            Some(l4) => walk_tunnel(&outer, l4, config)?,
+            None => (None, None),
+        };
+        Ok(Self {
+            outer,
+            tunnel,
+            inner,
+        })
+    }
+}
+
+fn walk_tunnel(
+    outer: &FlowKey,
+    l4: &[u8],
+    config: &ParseConfig,
+) -> Result<(Option<TunnelId>, Option<FlowKey>), ParseError> {
+    let (tunnel, inner) = match outer.proto {
+        IpProto::Esp => {
+            let spi = l4
+                .get(..4)
+                .map(|spi| u32::from_be_bytes([spi[0], spi[1], spi[2], spi[3]]));
+            (spi.map(TunnelId::Spi), None)
+        }
+        IpProto::Gre => walk_overlay(Tunnel::from_ip(IpProto::Gre, l4), config)?,
+        IpProto::Udp => {
+            let payload = l4.get(UdpHdr::LEN..).unwrap_or_default();
+            match outer.dst_port {
+                GTPU_PORT => match parse_gtpu(payload) {
+                    Some((teid, inner)) => (Some(TunnelId::Teid(teid)), inner.map(Inner::Ip)),
+                    None => (None, None),
+                },
+                port => walk_overlay(Tunnel::from_udp(port, payload), config)?,
+            }
+        }
+        _ => (None, None),
+    };
+    if tunnel.is_some() && !config.tunnels {
+        return Err(ParseError::LimitExceeded);
+    }
+    let inner = match inner {
+        Some(Inner::Ethernet(frame)) => parse_eth(frame, config),
+        Some(Inner::Ip(packet)) => parse_ip(packet, config).map(|(key, _)| key),
+        None => return Ok((tunnel, None)),
+    };
+    match inner {
+        Ok(inner) => Ok((tunnel, Some(inner))),
+        Err(ParseError::LimitExceeded) => Err(ParseError::LimitExceeded),
+        Err(_) => Ok((tunnel, None)),
+    }
+}
+
+/// Returns the identifier of an overlay tunnel and the packet it carries.
+fn walk_overlay<'a>(
+    tunnel: Option<(Tunnel<'a>, Inner<'a>)>,
+    config: &ParseConfig,
+) -> Result<(Option<TunnelId>, Option<Inner<'a>>), ParseError> {
+    let Some((tunnel, inner)) = tunnel else {
+        return Ok((None, None));
+    };
+    let id = match tunnel {
+        Tunnel::Vxlan(hdr) => Some(TunnelId::Vni(hdr.vni())),
+        Tunnel::Geneve(hdr) => {
+            if hdr.options_len() > config.max_options_len {
+                return Err(ParseError::LimitExceeded);
+            }
+            Some(TunnelId::Vni(hdr.vni()))
+        }
+        Tunnel::Gre(gre) => gre.key().map(TunnelId::GreKey),
+        Tunnel::Mpls(mut stack) => {
+            if stack.clone().count() > config.max_mpls_labels {
+                return Err(ParseError::LimitExceeded);
+            }
+            stack.next().map(|hdr| TunnelId::MplsLabel(hdr.label()))
+        }
+    };
+    Ok((id, Some(inner)))
+}
+
+/// Flow key of the IP packet carried in an Ethernet frame.
+fn parse_eth(frame: &[u8], config: &ParseConfig) -> Result<FlowKey, ParseError> {
+    let eth = EthHdr::from_bytes(frame).ok_or(ParseError::Malformed)?;
+    match eth.ether_type() {
+        Some(EtherType::Ipv4 | EtherType::Ipv6) => {
+            parse_ip(&frame[EthHdr::LEN..], config).map(|(key, _)| key)
+        }
+        _ => Err(ParseError::Malformed),
+    }
+}
+
+/// Returns the TEID of a GTPv1-U G-PDU and the user packet.
+fn parse_gtpu(bytes: &[u8]) -> Option<(u32, Option<&[u8]>)> {
+    let [flags, msg_type, _, _, t0, t1, t2, t3, ..] = *bytes else {
+        return None;
+    };
+    // Version 1, protocol type GTP, G-PDU.
+    if flags & 0xf0 != 0x30 || msg_type != 0xff {
+        return None;
+    }
+    let teid = u32::from_be_bytes([t0, t1, t2, t3]);
+
+    let mut offset = 8;
+    // The E, S and PN flags are followed by the optional fields, whose last
+    // octet is the type of the first extension header.
+    if flags & 0x07 != 0 {
+        let mut next_ext = *bytes.get(offset + 3)?;
+        offset += 4;
+        while next_ext != 0 {
+            let len = *bytes.get(offset)? as usize * 4;
+            if len == 0 {
+                return Some((teid, None));
+            }
+            next_ext = *bytes.get(offset + len - 1)?;
+            offset += len;
+        }
+    }
+    Some((teid, bytes.get(offset..)))
+}
+
+#[cfg(test)]
+mod tests {
+    use core::net::{IpAddr, Ipv4Addr};
+
+    use super::{siphash, Direction, FlowKey, OverlayFlowKey, TunnelId};
+    use crate::{
+        header::{ParseConfig, ParseError},
+        ip::IpProto,
+        mpls::{write_mpls_in_udp, MplsHdr},
+    };
+
+    #[test]
+    fn test_direction() {
+        let key = |src_port, dst_port| FlowKey {
+            src_addr: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+            dst_addr: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)),
+            proto: IpProto::Udp,
+            src_port,
+            dst_port,
+        };
+        for (src_port, dst_port, direction) in [
+            // A well-known port beats a lower registered one.
+            (8080, 53, Direction::ClientToServer),
+            (53, 1000, Direction::ServerToClient),
+            (5060, 40000, Direction::ServerToClient),
+            (5353, 5353, Direction::ClientToServer),
+        ] {
+            let key = key(src_port, dst_port);
+            assert_eq!(key.direction(), direction);
+            assert_eq!(key.reversed().direction(), direction.reversed());
+            assert_eq!(key.canonicalize().0, key.reversed().canonicalize().0);
+        }
+
+        // The SYN of an active FTP data connection comes from port 20.
+        let syn = key(20, 50000);
+        assert_eq!(syn.canonicalize_tcp(0x02), (syn, Direction::ClientToServer));
+        assert_eq!(
+            syn.reversed().canonicalize_tcp(0x12),
+            (syn, Direction::ServerToClient)
+        );
+        assert_eq!(syn.canonicalize_tcp(0x10).1, Direction::ServerToClient);
+    }
+
+    #[test]
+    fn test_overlay_vxlan() {
+        #[rustfmt::skip]
+        let packet = [
+            // Outer IPv4 and UDP headers.
+            0x45, 0, 0, 86, 0, 0, 0x40, 0, 64, 17, 0, 0, 192, 168, 0, 1, 192, 168, 0, 2,
+            0xc0, 0x01, 0x12, 0xb5, 0, 66, 0, 0,
+            // VXLAN header, VNI 100.
+            0x08, 0, 0, 0, 0, 0, 100, 0,
+            // Inner Ethernet, IPv4 and TCP headers.
+            0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 1, 0x08, 0x00,
+            0x45, 0, 0, 40, 0, 0, 0x40, 0, 64, 6, 0, 0, 10, 0, 0, 1, 10, 0, 0, 2,
+            0x04, 0xd2, 0x00, 0x50,
+        ];
+        let key = OverlayFlowKey::from_ip(&packet).unwrap();
+        assert_eq!(key.outer.proto, IpProto::Udp);
+        assert_eq!((key.outer.src_port, key.outer.dst_port), (49153, 4789));
+        assert_eq!(key.tunnel, Some(TunnelId::Vni(100)));
+        let inner = key.inner.unwrap();
+        assert_eq!(
+            inner,
+            FlowKey {
+                src_addr: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+                dst_addr: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)),
+                proto: IpProto::Tcp,
+                src_port: 1234,
+                dst_port: 80,
+            }
+        );
+        assert_eq!(inner.reversed().reversed(), inner);
+        let no_tunnels = ParseConfig {
+            tunnels: false,
+            ..ParseConfig::DEFAULT
+        };
+        assert_eq!(
+            OverlayFlowKey::from_ip_with_config(&packet, &no_tunnels),
+            Err(ParseError::LimitExceeded)
+        );
+
+        let key = OverlayFlowKey::from_ip(&packet[50..]).unwrap();
+        assert_eq!(key.tunnel, None);
+        assert_eq!(key.outer, inner);
+
+        // The same inner packet in MPLS-in-UDP, with two labels.
+        let mut mpls = [0u8; 72];
+        mpls[..20].copy_from_slice(&packet[..20]);
+        let labels = [
+            MplsHdr::new(100, 0, false, 64),
+            MplsHdr::new(200, 0, true, 64),
+        ];
+        let len = 20 + write_mpls_in_udp(&mut mpls[20..], 49153, &labels, &packet[50..]).unwrap();
+        mpls[2..4].copy_from_slice(&(len as u16).to_be_bytes());
+        let key = OverlayFlowKey::from_ip(&mpls[..len]).unwrap();
+        assert_eq!(key.outer.dst_port, 6635);
+        assert_eq!(key.tunnel, Some(TunnelId::MplsLabel(100)));
+        assert_eq!(key.inner, Some(inner));
+        let one_label = ParseConfig {
+            max_mpls_labels: 1,
+            ..ParseConfig::DEFAULT
+        };
+        assert_eq!(
+            OverlayFlowKey::from_ip_with_config(&mpls[..len], &one_label),
+            Err(ParseError::LimitExceeded)
+        );
+
+        // Reference vector of the SipHash paper, on 15 bytes.
+        let sip_key = [0x0706050403020100, 0x0f0e0d0c0b0a0908];
+        let data: [u8; 15] = core::array::from_fn(|i| i as u8);
+        assert_eq!(siphash(&sip_key, &data), 0xa129ca6149be45e5);
+        assert_ne!(
+            inner.keyed_hash(&sip_key),
+            inner.reversed().keyed_hash(&sip_key)
+        );
+    }
+}
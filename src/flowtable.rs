@@ -0,0 +1,532 @@
+//! Flow table of a flow probe and periodic export of its records.
+//!
+//! [`FlowTable`] aggregates packets into unidirectional flow records keyed
+//! by their 5-tuple and ends them the way NetFlow and IPFIX
+//! ([RFC 5102](https://datatracker.ietf.org/doc/html/rfc5102#section-5.11.3))
+//! meters do: after an idle or active timeout, at the end of a TCP
+//! connection, or to make room for a new flow.
+//!
+//! [`FlowExporter`] cuts time into epochs of a fixed length and, at the end
+//! of each one, hands a [`FlowSink`] the records which ended and a snapshot
+//! of the active ones, so that the sink can write IPFIX messages, JSON
+//! lines or a time series.
+//!
+//! The `now` arguments are the capture times of the packets, which the
+//! records keep as their first and last times, e.g. the time since the UNIX
+//! epoch for an exporter writing absolute IPFIX timestamps. Timeouts and
+//! epochs only require the times passed to a table or an exporter to come
+//! from the same clock, a time earlier than a record's packets never timing
+//! it out.
+
+use alloc::collections::{BTreeMap, BTreeSet};
+use core::time::Duration;
+
+use crate::{
+    flow::FlowKey,
+    header::Header,
+    packet::{Packet, TransportHdr},
+};
+
+/// TCP FIN flag.
+const TCP_FIN: u8 = 0x01;
+/// TCP RST flag.
+const TCP_RST: u8 = 0x04;
+
+/// Why a flow record ended, with the values of the IPFIX `flowEndReason`
+/// information element.
+#[repr(u8)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum FlowEndReason {
+    /// No packet was seen for the idle timeout.
+    IdleTimeout = 1,
+    /// The flow lasted for the active timeout, and goes on in a new record.
+    ActiveTimeout = 2,
+    /// A TCP FIN or RST was seen.
+    EndOfFlow = 3,
+    /// The table was flushed, e.g. when the probe stops.
+    ForcedEnd = 4,
+    /// The table was full.
+    LackOfResources = 5,
+}
+
+impl TryFrom<u8> for FlowEndReason {
+    type Error = ();
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(FlowEndReason::IdleTimeout),
+            2 => Ok(FlowEndReason::ActiveTimeout),
+            3 => Ok(FlowEndReason::EndOfFlow),
+            4 => Ok(FlowEndReason::ForcedEnd),
+            5 => Ok(FlowEndReason::LackOfResources),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Counters of a flow.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct FlowRecord {
+    pub key: FlowKey,
+    pub packets: u64,
+    pub bytes: u64,
+    /// Times of the first and last packets of the record.
+    pub first: Duration,
+    pub last: Duration,
+    /// Union of the TCP flags of the packets.
+    pub tcp_flags: u8,
+}
+
+impl FlowRecord {
+    fn new(key: FlowKey, now: Duration) -> Self {
+        Self {
+            key,
+            packets: 0,
+            bytes: 0,
+            first: now,
+            last: now,
+            tcp_flags: 0,
+        }
+    }
+}
+
+/// Flow records of the packets seen, ending after the timeouts.
+///
+/// ```
+/// use core::time::Duration;
+/// use ether_packet::{flow::FlowKey, flowtable::{FlowEndReason, FlowTable}, ip::IpProto};
+///
+/// let secs = Duration::from_secs;
+/// let mut table = FlowTable::new(secs(15), secs(60), 1024);
+/// let key = FlowKey {
+///     src_addr: "10.0.0.1".parse().unwrap(),
+///     dst_addr: "10.0.0.2".parse().unwrap(),
+///     proto: IpProto::Udp,
+///     src_port: 5000,
+///     dst_port: 53,
+/// };
+/// table.record(&key, 80, 0, secs(1));
+/// table.record(&key, 120, 0, secs(2));
+/// assert_eq!(table.get(&key).unwrap().bytes, 200);
+///
+/// let mut ended = Vec::new();
+/// table.expire(secs(20), |record, reason| ended.push((record, reason)));
+/// assert_eq!(ended[0].0.packets, 2);
+/// assert_eq!(ended[0].1, FlowEndReason::IdleTimeout);
+/// ```
+#[derive(Debug, Clone)]
+pub struct FlowTable {
+    flows: BTreeMap<FlowKey, FlowRecord>,
+    /// Keys of the flows, by time of their last packet.
+    lru: BTreeSet<(Duration, FlowKey)>,
+    idle_timeout: Duration,
+    active_timeout: Duration,
+    max_flows: usize,
+}
+
+impl FlowTable {
+    /// Creates a table of at most `max_flows` records, ending the records
+    /// without packets for `idle_timeout` and those lasting for
+    /// `active_timeout`.
+    pub fn new(idle_timeout: Duration, active_timeout: Duration, max_flows: usize) -> Self {
+        Self {
+            flows: BTreeMap::new(),
+            lru: BTreeSet::new(),
+            idle_timeout,
+            active_timeout,
+            max_flows,
+        }
+    }
+
+    #[inline]
+    pub fn get(&self, key: &FlowKey) -> Option<&FlowRecord> {
+        self.flows.get(key)
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.flows.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.flows.is_empty()
+    }
+
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = &FlowRecord> {
+        self.flows.values()
+    }
+
+    /// Counts a packet of flow `key`, `len` bytes long, with the TCP flags
+    /// `tcp_flags`.
+    ///
+    /// If the table is full, the record of the least recently seen flow is
+    /// removed to make room and returned.
+    pub fn record(
+        &mut self,
+        key: &FlowKey,
+        len: u64,
+        tcp_flags: u8,
+        now: Duration,
+    ) -> Option<FlowRecord> {
+        let mut evicted = None;
+        if !self.flows.contains_key(key) && self.flows.len() >= self.max_flows {
+            if let Some((_, oldest)) = self.lru.pop_first() {
+                evicted = self.flows.remove(&oldest);
+            }
+        }
+        if self.max_flows > 0 {
+            let record = self
+                .flows
+                .entry(*key)
+                .or_insert_with(|| FlowRecord::new(*key, now));
+            self.lru.remove(&(record.last, *key));
+            record.packets += 1;
+            record.bytes += len;
+            record.last = record.last.max(now);
+            record.tcp_flags |= tcp_flags;
+            self.lru.insert((record.last, *key));
+        }
+        evicted
+    }
+
+    /// Counts an IP packet, see [`FlowTable::record`]. Other packets are
+    /// ignored.
+    pub fn packet(&mut self, packet: &Packet<'_>, now: Duration) -> Option<FlowRecord> {
+        let key = FlowKey::from_ip(&packet.data()[packet.l3_offset()?..])?;
+        let tcp_flags = match packet.transport() {
+            Some(TransportHdr::Tcp(tcp)) => tcp.as_bytes()[13],
+            _ => 0,
+        };
+        self.record(&key, packet.data().len() as u64, tcp_flags, now)
+    }
+
+    /// Removes the records which ended at time `now`, passing them to
+    /// `on_end` with the reason.
+    ///
+    /// Flows which lasted for the active timeout go on in a new record
+    /// from their next packet.
+    pub fn expire(&mut self, now: Duration, mut on_end: impl FnMut(FlowRecord, FlowEndReason)) {
+        let (idle_timeout, active_timeout) = (self.idle_timeout, self.active_timeout);
+        let lru = &mut self.lru;
+        self.flows.retain(|key, record| {
+            let reason = if now.saturating_sub(record.last) >= idle_timeout {
+                FlowEndReason::IdleTimeout
+            } else if record.tcp_flags & (TCP_FIN | TCP_RST) != 0 {
+                FlowEndReason::EndOfFlow
+            } else if now.saturating_sub(record.first) >= active_timeout {
+                FlowEndReason::ActiveTimeout
+            } else {
+                return true;
+            };
+            lru.remove(&(record.last, *key));
+            on_end(*record, reason);
+            false
+        });
+    }
+
+    /// Removes every record, passing them to `on_end`.
+    pub fn flush(&mut self, mut on_end: impl FnMut(FlowRecord, FlowEndReason)) {
+        self.lru.clear();
+        for (_, record) in core::mem::take(&mut self.flows) {
+            on_end(record, FlowEndReason::ForcedEnd);
+        }
+    }
+}
+
+/// Export period of a [`FlowExporter`].
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct Epoch {
+    /// Number of the epoch since time 0.
+    pub index: u64,
+    pub start: Duration,
+    pub end: Duration,
+}
+
+impl Epoch {
+    /// Epoch of length `interval` containing `now`. The index and the end
+    /// saturate, which no clock reaches but with nanosecond epochs more than
+    /// 584 years after time 0.
+    fn at(now: Duration, interval: Duration) -> Self {
+        let interval_ns = interval.as_nanos().max(1);
+        let index = u64::try_from(now.as_nanos() / interval_ns).unwrap_or(u64::MAX);
+        // The remainder is shorter than `interval`, so its seconds fit.
+        let rem = now.as_nanos() % interval_ns;
+        let start = now - Duration::new((rem / 1_000_000_000) as u64, (rem % 1_000_000_000) as u32);
+        Self {
+            index,
+            start,
+            end: start.saturating_add(interval),
+        }
+    }
+}
+
+/// Destination of the records exported by a [`FlowExporter`].
+///
+/// Closures taking the arguments of [`FlowSink::flow`] are sinks.
+pub trait FlowSink {
+    /// Starts the export of `epoch`.
+    fn begin(&mut self, _epoch: &Epoch) {}
+
+    /// Exports `record`, which ended for `reason`, or is still active if
+    /// `reason` is `None`.
+    fn flow(&mut self, epoch: &Epoch, record: &FlowRecord, reason: Option<FlowEndReason>);
+
+    /// Ends the export of `epoch`.
+    fn end(&mut self, _epoch: &Epoch) {}
+}
+
+impl<F> FlowSink for F
+where
+    F: FnMut(&Epoch, &FlowRecord, Option<FlowEndReason>),
+{
+    #[inline]
+    fn flow(&mut self, epoch: &Epoch, record: &FlowRecord, reason: Option<FlowEndReason>) {
+        self(epoch, record, reason)
+    }
+}
+
+/// Feeds a [`FlowTable`] and exports it to a [`FlowSink`] at the end of
+/// every epoch.
+///
+/// An export passes the records which ended during the epoch, then a
+/// snapshot of the active ones. Records evicted from a full table are
+/// exported at once, in the current epoch. Epochs end on the first packet
+/// or call to [`FlowExporter::tick`] after them, and the epochs without
+/// either are skipped.
+///
+/// ```
+/// use core::time::Duration;
+/// use ether_packet::{
+///     flow::FlowKey,
+///     flowtable::{FlowExporter, FlowTable},
+///     ip::IpProto,
+/// };
+///
+/// let secs = Duration::from_secs;
+/// let mut exported = Vec::new();
+/// let table = FlowTable::new(secs(15), secs(60), 1024);
+/// let mut exporter = FlowExporter::new(table, secs(10), |epoch: &_, record: &_, reason| {
+///     exported.push((*epoch, *record, reason))
+/// });
+/// let key = FlowKey {
+///     src_addr: "10.0.0.1".parse().unwrap(),
+///     dst_addr: "10.0.0.2".parse().unwrap(),
+///     proto: IpProto::Udp,
+///     src_port: 5000,
+///     dst_port: 53,
+/// };
+/// exporter.record(&key, 100, 0, secs(3));
+/// exporter.record(&key, 100, 0, secs(12));
+/// exporter.finish(secs(14));
+///
+/// // A snapshot at the end of the first epoch, then the forced end.
+/// assert_eq!(exported.len(), 2);
+/// assert_eq!((exported[0].0.index, exported[0].1.packets, exported[0].2), (0, 1, None));
+/// assert_eq!((exported[1].0.index, exported[1].1.packets), (1, 2));
+/// ```
+pub struct FlowExporter<S: FlowSink> {
+    table: FlowTable,
+    interval: Duration,
+    epoch: Option<Epoch>,
+    sink: S,
+}
+
+impl<S: FlowSink> FlowExporter<S> {
+    /// Creates an exporter of `table` to `sink` every `interval`.
+    pub fn new(table: FlowTable, interval: Duration, sink: S) -> Self {
+        Self {
+            table,
+            interval,
+            epoch: None,
+            sink,
+        }
+    }
+
+    #[inline]
+    pub fn table(&self) -> &FlowTable {
+        &self.table
+    }
+
+    /// The current epoch, `None` before the first packet.
+    #[inline]
+    pub fn epoch(&self) -> Option<Epoch> {
+        self.epoch
+    }
+
+    /// Counts a packet, see [`FlowTable::record`], after exporting the
+    /// epochs which ended before `now`.
+    pub fn record(&mut self, key: &FlowKey, len: u64, tcp_flags: u8, now: Duration) {
+        self.tick(now);
+        if let Some(evicted) = self.table.record(key, len, tcp_flags, now) {
+            self.export_evicted(&evicted);
+        }
+    }
+
+    /// Counts an IP packet, see [`FlowTable::packet`].
+    pub fn packet(&mut self, packet: &Packet<'_>, now: Duration) {
+        self.tick(now);
+        if let Some(evicted) = self.table.packet(packet, now) {
+            self.export_evicted(&evicted);
+        }
+    }
+
+    fn export_evicted(&mut self, record: &FlowRecord) {
+        let epoch = self.epoch.expect("epoch started by tick");
+        let reason = Some(FlowEndReason::LackOfResources);
+        self.sink.flow(&epoch, record, reason);
+    }
+
+    /// Exports the current epoch if it ended before `now`, and moves to the
+    /// epoch containing `now`.
+    pub fn tick(&mut self, now: Duration) {
+        match self.epoch {
+            Some(epoch) if now >= epoch.end => {
+                self.export(&epoch);
+                self.epoch = Some(Epoch::at(now, self.interval));
+            }
+            Some(_) => {}
+            None => self.epoch = Some(Epoch::at(now, self.interval)),
+        }
+    }
+
+    fn export(&mut self, epoch: &Epoch) {
+        let sink = &mut self.sink;
+        sink.begin(epoch);
+        self.table.expire(epoch.end, |record, reason| {
+            sink.flow(epoch, &record, Some(reason))
+        });
+        for record in self.table.iter() {
+            sink.flow(epoch, record, None);
+        }
+        sink.end(epoch);
+    }
+
+    /// Exports the current epoch, ending every record at `now`, and
+    /// returns the sink.
+    pub fn finish(mut self, now: Duration) -> S {
+        self.tick(now);
+        if let Some(mut epoch) = self.epoch {
+            epoch.end = epoch.end.min(now);
+            let sink = &mut self.sink;
+            sink.begin(&epoch);
+            self.table
+                .flush(|record, reason| sink.flow(&epoch, &record, Some(reason)));
+            sink.end(&epoch);
+        }
+        self.sink
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use core::time::Duration;
+
+    use super::{Epoch, FlowEndReason, FlowExporter, FlowRecord, FlowSink, FlowTable};
+    use crate::{flow::FlowKey, ip::IpProto};
+
+    #[derive(Default)]
+    struct Sink {
+        epochs: Vec<u64>,
+        flows: Vec<(u64, FlowRecord, Option<FlowEndReason>)>,
+    }
+
+    impl FlowSink for Sink {
+        fn begin(&mut self, epoch: &Epoch) {
+            self.epochs.push(epoch.index);
+        }
+
+        fn flow(&mut self, epoch: &Epoch, record: &FlowRecord, reason: Option<FlowEndReason>) {
+            self.flows.push((epoch.index, *record, reason));
+        }
+    }
+
+    #[test]
+    fn test_flow_eviction() {
+        let secs = Duration::from_secs;
+        let key = |port| FlowKey {
+            src_addr: "192.0.2.1".parse().unwrap(),
+            dst_addr: "192.0.2.2".parse().unwrap(),
+            proto: IpProto::Udp,
+            src_port: port,
+            dst_port: 53,
+        };
+        let mut table = FlowTable::new(secs(10), secs(60), 2);
+        assert_eq!(table.record(&key(1), 60, 0, secs(1)), None);
+        assert_eq!(table.record(&key(2), 60, 0, secs(2)), None);
+        // Flow 1 is seen again, leaving flow 2 the least recently seen.
+        assert_eq!(table.record(&key(1), 60, 0, secs(3)), None);
+        let evicted = table.record(&key(3), 60, 0, secs(4)).unwrap();
+        assert_eq!((evicted.key, evicted.last), (key(2), secs(2)));
+
+        let mut ended = Vec::new();
+        table.expire(secs(13), |record, _| ended.push(record.key));
+        assert_eq!(ended, [key(1)]);
+        assert_eq!(table.record(&key(4), 60, 0, secs(13)), None);
+        let evicted = table.record(&key(5), 60, 0, secs(14)).unwrap();
+        assert_eq!(evicted.key, key(3));
+
+        table.flush(|_, _| {});
+        assert!(table.is_empty());
+        assert_eq!(table.record(&key(6), 60, 0, secs(15)), None);
+        assert_eq!(table.record(&key(7), 60, 0, secs(16)), None);
+        assert_eq!(table.record(&key(8), 60, 0, secs(17)).unwrap().key, key(6));
+    }
+
+    #[test]
+    fn test_flow_export() {
+        let secs = Duration::from_secs;
+        let key = |port| FlowKey {
+            src_addr: "192.0.2.1".parse().unwrap(),
+            dst_addr: "192.0.2.2".parse().unwrap(),
+            proto: IpProto::Tcp,
+            src_port: port,
+            dst_port: 80,
+        };
+        let table = FlowTable::new(secs(30), secs(25), 2);
+        let mut exporter = FlowExporter::new(table, secs(10), Sink::default());
+        // A long-lived flow 1, a connection 2 closed with FIN, and flow 4
+        // evicting flow 3, least recently seen, from the full table.
+        for t in 0..40 {
+            exporter.record(&key(1), 100, 0x10, secs(t));
+            match t {
+                5 => exporter.record(&key(2), 60, 0x02, secs(t)),
+                6 => exporter.record(&key(2), 60, 0x11, secs(t)),
+                12 => exporter.record(&key(3), 60, 0, secs(t)),
+                13 => exporter.record(&key(4), 60, 0, secs(t)),
+                _ => {}
+            }
+        }
+        assert_eq!(exporter.epoch().map(|epoch| epoch.index), Some(3));
+        let sink = exporter.finish(secs(41));
+
+        assert_eq!(sink.epochs, [0, 1, 2, 3, 4]);
+        let ended = |reason| {
+            sink.flows
+                .iter()
+                .filter(|(_, _, r)| *r == Some(reason))
+                .map(|(epoch, record, _)| (*epoch, record.key.src_port, record.packets))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ended(FlowEndReason::EndOfFlow), [(0, 2, 2)]);
+        assert_eq!(ended(FlowEndReason::LackOfResources), [(1, 3, 1)]);
+        assert_eq!(ended(FlowEndReason::ActiveTimeout), [(2, 1, 30), (3, 4, 1)]);
+        assert_eq!(ended(FlowEndReason::IdleTimeout), []);
+        assert_eq!(ended(FlowEndReason::ForcedEnd), [(4, 1, 10)]);
+        assert!(sink
+            .flows
+            .iter()
+            .any(|(epoch, _, r)| *epoch == 0 && r.is_none()));
+
+        // Epochs far from time 0 are not truncated.
+        let epoch = Epoch::at(Duration::new(u64::MAX - 1, 0), secs(10));
+        assert_eq!(epoch.index, (u64::MAX - 1) / 10);
+        assert_eq!(epoch.start, Duration::new(epoch.index * 10, 0));
+        let epoch = Epoch::at(Duration::MAX, Duration::from_nanos(1));
+        assert_eq!((epoch.index, epoch.start), (u64::MAX, Duration::MAX));
+    }
+}
@@ -0,0 +1,382 @@
+//! Generic Network Virtualization Encapsulation
+//! ([RFC 8926](https://datatracker.ietf.org/doc/html/rfc8926)).
+//!
+//! Geneve options are identified by a class, assigned by IANA to a vendor or
+//! project, and a type within that class. Options of well-known classes are
+//! decoded into [`KnownOption`], and [`OptionRegistry`] lets users plug in
+//! decoders for their own classes.
+
+use core::mem;
+
+use crate::{
+    eth::EtherType,
+    header::{impl_header, Limited, ParseConfig, ParseError},
+    types::U16,
+};
+
+/// IANA-assigned UDP destination port of Geneve.
+pub const GENEVE_PORT: u16 = 6081;
+
+/// Geneve header, followed by the variable-length options.
+/// ```text
+/// +---+-----------+-+-+-----------+-------------------------------+
+/// |Ver| Opt Len   |O|C|   Rsvd.   |         Protocol Type         |
+/// +---+-----------+-+-+-----------+---------------+---------------+
+/// |     Virtual Network Identifier (VNI)          |   Reserved    |
+/// +-----------------------------------------------+---------------+
+/// |                    Variable-Length Options                    |
+/// +---------------------------------------------------------------+
+/// ```
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct GeneveHdr {
+    pub ver_opt_len: u8,
+    pub flags: u8,
+    /// An [`EtherType`], Transparent Ethernet Bridging (0x6558) for
+    /// Ethernet payloads.
+    pub protocol_type: U16,
+    pub vni: [u8; 3],
+    pub _reserved: u8,
+}
+
+impl GeneveHdr {
+    pub const LEN: usize = mem::size_of::<GeneveHdr>();
+
+    #[inline]
+    pub const fn version(&self) -> u8 {
+        self.ver_opt_len >> 6
+    }
+
+    /// Length of the options in bytes.
+    #[inline]
+    pub const fn options_len(&self) -> usize {
+        (self.ver_opt_len & 0x3f) as usize * 4
+    }
+
+    /// **O**: the packet carries a control message.
+    #[inline]
+    pub const fn oam(&self) -> bool {
+        self.flags & 0x80 != 0
+    }
+
+    /// **C**: critical options are present.
+    #[inline]
+    pub const fn critical(&self) -> bool {
+        self.flags & 0x40 != 0
+    }
+
+    #[inline]
+    pub const fn vni(&self) -> u32 {
+        u32::from_be_bytes([0, self.vni[0], self.vni[1], self.vni[2]])
+    }
+
+    #[inline]
+    pub const fn set_vni(&mut self, vni: u32) {
+        let [_, a, b, c] = vni.to_be_bytes();
+        self.vni = [a, b, c];
+    }
+
+    #[inline]
+    pub const fn protocol_type(&self) -> Option<EtherType> {
+        EtherType::from_u16(self.protocol_type.to_bits())
+    }
+}
+
+impl_header!(GeneveHdr, validate = |b: &[u8]| b[0] >> 6 == 0);
+
+/// Geneve option classes registered with IANA.
+#[repr(u16)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum OptionClass {
+    Linux = 0x0100,
+    OpenVSwitch = 0x0101,
+    /// Open Virtual Network.
+    Ovn = 0x0102,
+    /// In-band Network Telemetry.
+    Int = 0x0103,
+    VMware = 0x0104,
+    Amazon = 0x0105,
+    Cisco = 0x0106,
+    Oracle = 0x0107,
+    /// AWS Gateway Load Balancer.
+    AwsGwlb = 0x0108,
+    Experimental = 0xffff,
+}
+
+impl TryFrom<u16> for OptionClass {
+    type Error = ();
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        match value {
+            0x0100 => Ok(OptionClass::Linux),
+            0x0101 => Ok(OptionClass::OpenVSwitch),
+            0x0102 => Ok(OptionClass::Ovn),
+            0x0103 => Ok(OptionClass::Int),
+            0x0104 => Ok(OptionClass::VMware),
+            0x0105 => Ok(OptionClass::Amazon),
+            0x0106 => Ok(OptionClass::Cisco),
+            0x0107 => Ok(OptionClass::Oracle),
+            0x0108 => Ok(OptionClass::AwsGwlb),
+            0xffff => Ok(OptionClass::Experimental),
+            _ => Err(()),
+        }
+    }
+}
+
+/// A Geneve option.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub struct GeneveOption<'a> {
+    pub class: u16,
+    pub option_type: u8,
+    pub data: &'a [u8],
+}
+
+impl GeneveOption<'_> {
+    #[inline]
+    pub fn class(&self) -> Option<OptionClass> {
+        self.class.try_into().ok()
+    }
+
+    /// Whether the receiver must drop the packet if it does not understand
+    /// the option, i.e. the most significant bit of the type is set.
+    #[inline]
+    pub fn is_critical(&self) -> bool {
+        self.option_type & 0x80 != 0
+    }
+}
+
+/// Iterator over the options following a Geneve header.
+#[derive(Debug, Copy, Clone)]
+pub struct GeneveOptions<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> GeneveOptions<'a> {
+    /// `data` are the [`GeneveHdr::options_len`] bytes following the header.
+    #[inline]
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    /// Bounds the walk to [`ParseConfig::max_options`] options.
+    ///
+    /// Fails with [`ParseError::LimitExceeded`] if the options are longer
+    /// than [`ParseConfig::max_options_len`].
+    pub fn limit(self, config: &ParseConfig) -> Result<Limited<Self>, ParseError> {
+        let len = self.data.len();
+        config.limit_options(self, len)
+    }
+}
+
+impl<'a> Iterator for GeneveOptions<'a> {
+    type Item = GeneveOption<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // The length is in units of 4 octets, excluding the option header.
+        let len = match self.data {
+            [_, _, _, len, ..] if 4 + (*len & 0x1f) as usize * 4 <= self.data.len() => {
+                4 + (*len & 0x1f) as usize * 4
+            }
+            _ => {
+                self.data = &[];
+                return None;
+            }
+        };
+        let option = GeneveOption {
+            class: u16::from_be_bytes([self.data[0], self.data[1]]),
+            option_type: self.data[2],
+            data: &self.data[4..len],
+        };
+        self.data = &self.data[len..];
+        Some(option)
+    }
+}
+
+/// Options of well-known classes.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum KnownOption {
+    /// ID of the Gateway Load Balancer endpoint the packet went through.
+    GwlbEndpointId(u64),
+    /// ID of the Gateway Load Balancer attachment.
+    GwlbAttachmentId(u64),
+    /// Cookie identifying the flow at the Gateway Load Balancer.
+    GwlbFlowCookie(u32),
+    /// Logical ports of the OVN pipeline.
+    OvnPorts { ingress: u16, egress: u16 },
+}
+
+impl KnownOption {
+    pub fn decode(option: &GeneveOption<'_>) -> Option<Self> {
+        match (option.class()?, option.option_type, option.data) {
+            (OptionClass::AwsGwlb, 1, data) => Some(KnownOption::GwlbEndpointId(
+                u64::from_be_bytes(data.try_into().ok()?),
+            )),
+            (OptionClass::AwsGwlb, 2, data) => Some(KnownOption::GwlbAttachmentId(
+                u64::from_be_bytes(data.try_into().ok()?),
+            )),
+            (OptionClass::AwsGwlb, 3, data) => Some(KnownOption::GwlbFlowCookie(
+                u32::from_be_bytes(data.try_into().ok()?),
+            )),
+            (OptionClass::Ovn, 0x80, &[a, b, c, d]) => Some(KnownOption::OvnPorts {
+                ingress: u16::from_be_bytes([a, b]) & 0x7fff,
+                egress: u16::from_be_bytes([c, d]),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Decoder of the options of a class, taking the option type and data.
+pub type OptionDecoder<T> = fn(option_type: u8, data: &[u8]) -> Option<T>;
+
+/// An option decoded by an [`OptionRegistry`].
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum DecodedOption<'a, T> {
+    Known(KnownOption),
+    /// Decoded by a registered decoder.
+    Custom(T),
+    /// No decoder understood the option.
+    Unknown(GeneveOption<'a>),
+}
+
+/// Decoders of up to `N` option classes registered at runtime, on top of
+/// the built-in [`KnownOption`] decoding.
+///
+/// ```
+/// use ether_packet::geneve::{DecodedOption, GeneveOption, OptionRegistry};
+///
+/// let mut registry = OptionRegistry::<u16>::new();
+/// registry.register(0xff01, |ty, data| match (ty, data) {
+///     (1, &[a, b, _, _]) => Some(u16::from_be_bytes([a, b])),
+///     _ => None,
+/// });
+/// let option = GeneveOption { class: 0xff01, option_type: 1, data: &[0, 42, 0, 0] };
+/// assert_eq!(registry.decode(&option), DecodedOption::Custom(42));
+/// ```
+#[derive(Debug, Clone)]
+pub struct OptionRegistry<T, const N: usize = 8> {
+    decoders: [Option<(u16, OptionDecoder<T>)>; N],
+}
+
+impl<T, const N: usize> OptionRegistry<T, N> {
+    pub fn new() -> Self {
+        Self {
+            decoders: [None; N],
+        }
+    }
+
+    /// Registers the decoder of `class`, replacing the previous one and
+    /// taking precedence over the built-in decoding. Returns `false` if `N`
+    /// classes are already registered.
+    pub fn register(&mut self, class: u16, decoder: OptionDecoder<T>) -> bool {
+        let slot = self
+            .decoders
+            .iter()
+            .position(|d| d.is_some_and(|(c, _)| c == class))
+            .or_else(|| self.decoders.iter().position(Option::is_none));
+        match slot {
+            Some(slot) => {
+                self.decoders[slot] = Some((class, decoder));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Removes the decoder of `class`.
+    pub fn unregister(&mut self, class: u16) {
+        for d in &mut self.decoders {
+            if d.is_some_and(|(c, _)| c == class) {
+                *d = None;
+            }
+        }
+    }
+
+    pub fn decode<'a>(&self, option: &GeneveOption<'a>) -> DecodedOption<'a, T> {
+        let decoder = self
+            .decoders
+            .iter()
+            .flatten()
+            .find(|(c, _)| *c == option.class);
+        if let Some((_, decoder)) = decoder {
+            if let Some(val) = decoder(option.option_type, option.data) {
+                return DecodedOption::Custom(val);
+            }
+        }
+        match KnownOption::decode(option) {
+            Some(known) => DecodedOption::Known(known),
+            None => DecodedOption::Unknown(*option),
+        }
+    }
+}
+
+impl<T, const N: usize> Default for OptionRegistry<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DecodedOption, GeneveHdr, GeneveOptions, KnownOption, OptionRegistry};
+    use crate::header::{Header, ParseConfig, ParseError};
+
+    #[test]
+    fn test_geneve_options() {
+        #[rustfmt::skip]
+        let packet = [
+            0x06, 0x00, 0x65, 0x58, 0x00, 0x12, 0x34, 0x00,
+            // AWS GWLB endpoint ID.
+            0x01, 0x08, 0x01, 0x02, 0, 0, 0, 0, 0, 0, 0, 0x2a,
+            // OVN logical ports.
+            0x01, 0x02, 0x80, 0x01, 0x00, 0x03, 0x00, 0x04,
+            // Experimental, critical.
+            0xff, 0xff, 0x81, 0x00,
+        ];
+        let hdr = GeneveHdr::from_bytes(&packet).unwrap();
+        assert_eq!(hdr.vni(), 0x1234);
+        assert_eq!(hdr.protocol_type.to_bits(), 0x6558);
+        assert_eq!(hdr.options_len(), 24);
+
+        let registry = OptionRegistry::<(), 1>::new();
+        let mut options = GeneveOptions::new(&packet[GeneveHdr::LEN..]);
+        assert_eq!(
+            registry.decode(&options.next().unwrap()),
+            DecodedOption::Known(KnownOption::GwlbEndpointId(42))
+        );
+        assert_eq!(
+            registry.decode(&options.next().unwrap()),
+            DecodedOption::Known(KnownOption::OvnPorts {
+                ingress: 3,
+                egress: 4
+            })
+        );
+        let experimental = options.next().unwrap();
+        assert!(experimental.is_critical());
+        assert_eq!(
+            registry.decode(&experimental),
+            DecodedOption::Unknown(experimental)
+        );
+        assert_eq!(options.next(), None);
+
+        let options = GeneveOptions::new(&packet[GeneveHdr::LEN..]);
+        let two = ParseConfig {
+            max_options: 2,
+            ..ParseConfig::DEFAULT
+        };
+        let mut limited = options.limit(&two).unwrap();
+        assert!(limited.nth(1).unwrap().is_ok());
+        assert_eq!(limited.next(), Some(Err(ParseError::LimitExceeded)));
+        assert_eq!(limited.next(), None);
+        let short = ParseConfig {
+            max_options_len: 20,
+            ..ParseConfig::DEFAULT
+        };
+        assert_eq!(
+            options.limit(&short).unwrap_err(),
+            ParseError::LimitExceeded
+        );
+    }
+}
@@ -0,0 +1,199 @@
+//! Generic Routing Encapsulation
+//! ([RFC 2784](https://datatracker.ietf.org/doc/html/rfc2784), with the key
+//! and sequence number extensions of
+//! [RFC 2890](https://datatracker.ietf.org/doc/html/rfc2890)), carried
+//! directly over IP or in UDP
+//! ([RFC 8086](https://datatracker.ietf.org/doc/html/rfc8086)).
+
+use core::mem;
+
+use crate::{
+    builder::{BuildError, Writer},
+    eth::EtherType,
+    header::{impl_header, Header},
+    types::U16,
+    udp::{write_udp, UdpHdr},
+};
+
+/// IANA-assigned UDP destination port of GRE-in-UDP.
+pub const GRE_IN_UDP_PORT: u16 = 4754;
+
+/// Protocol type of Ethernet frames carried by GRE, also known as
+/// Transparent Ethernet Bridging.
+pub const PROTO_TEB: u16 = 0x6558;
+
+const FLAG_CHECKSUM: u16 = 0x8000;
+const FLAG_KEY: u16 = 0x2000;
+const FLAG_SEQ: u16 = 0x1000;
+
+/// GRE header, followed by the optional fields whose presence is given by
+/// the C, K and S flags.
+/// ```text
+/// +-+-+-+-+-------------------+-----+-------------------------------+
+/// |C| |K|S|     Reserved0     | Ver |         Protocol Type         |
+/// +-+-+-+-+-------------------+-----+-------------------------------+
+/// |      Checksum (optional)        |       Reserved1 (optional)    |
+/// +---------------------------------+-------------------------------+
+/// |                         Key (optional)                          |
+/// +-----------------------------------------------------------------+
+/// |                   Sequence Number (optional)                    |
+/// +-----------------------------------------------------------------+
+/// ```
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct GreHdr {
+    pub flags_version: U16,
+    /// An [`EtherType`], or [`PROTO_TEB`] for Ethernet frames.
+    pub protocol_type: U16,
+}
+
+impl GreHdr {
+    pub const LEN: usize = mem::size_of::<GreHdr>();
+
+    #[inline]
+    pub const fn version(&self) -> u8 {
+        (self.flags_version.to_bits() & 0x7) as u8
+    }
+
+    /// **C**: the checksum field is present.
+    #[inline]
+    pub const fn checksum_present(&self) -> bool {
+        self.flags_version.to_bits() & FLAG_CHECKSUM != 0
+    }
+
+    /// **K**: the key field is present.
+    #[inline]
+    pub const fn key_present(&self) -> bool {
+        self.flags_version.to_bits() & FLAG_KEY != 0
+    }
+
+    /// **S**: the sequence number field is present.
+    #[inline]
+    pub const fn seq_present(&self) -> bool {
+        self.flags_version.to_bits() & FLAG_SEQ != 0
+    }
+
+    /// Length of the header including the optional fields, in bytes.
+    #[inline]
+    pub const fn hdrlen(&self) -> usize {
+        Self::LEN
+            + 4 * (self.checksum_present() as usize
+                + self.key_present() as usize
+                + self.seq_present() as usize)
+    }
+
+    #[inline]
+    pub const fn protocol_type(&self) -> Option<EtherType> {
+        EtherType::from_u16(self.protocol_type.to_bits())
+    }
+}
+
+impl_header!(GreHdr, validate = |b: &[u8]| b[1] & 0x7 == 0);
+
+/// A GRE packet, with its optional fields decoded.
+#[derive(Debug, Copy, Clone)]
+pub struct Gre<'a> {
+    hdr: &'a GreHdr,
+    key: Option<u32>,
+    seq: Option<u32>,
+    payload: &'a [u8],
+}
+
+impl<'a> Gre<'a> {
+    /// Parses the GRE packet stored in `bytes`, returning `None` if it is
+    /// truncated or its version is not 0.
+    pub fn parse(bytes: &'a [u8]) -> Option<Self> {
+        let hdr = GreHdr::from_bytes(bytes)?;
+        let payload = bytes.get(hdr.hdrlen()..)?;
+        let mut fields = bytes[GreHdr::LEN..hdr.hdrlen()]
+            .chunks_exact(4)
+            .map(|f| u32::from_be_bytes([f[0], f[1], f[2], f[3]]))
+            .skip(hdr.checksum_present() as usize);
+        let key = hdr.key_present().then(|| fields.next()).flatten();
+        let seq = hdr.seq_present().then(|| fields.next()).flatten();
+        Some(Self {
+            hdr,
+            key,
+            seq,
+            payload,
+        })
+    }
+
+    #[inline]
+    pub fn hdr(&self) -> &'a GreHdr {
+        self.hdr
+    }
+
+    #[inline]
+    pub fn key(&self) -> Option<u32> {
+        self.key
+    }
+
+    #[inline]
+    pub fn seq(&self) -> Option<u32> {
+        self.seq
+    }
+
+    /// The encapsulated packet.
+    #[inline]
+    pub fn payload(&self) -> &'a [u8] {
+        self.payload
+    }
+}
+
+/// Writes a GRE-in-UDP packet carrying `payload` to `buf`, starting with the
+/// UDP header, and returns its length.
+///
+/// `src_port` should be derived from a hash of the inner flow so that the
+/// tunnel traffic is spread over ECMP paths. The UDP checksum is left at 0,
+/// which RFC 8086 allows over IPv4 and, under the conditions of
+/// [RFC 6935](https://datatracker.ietf.org/doc/html/rfc6935), over IPv6.
+pub fn write_gre_in_udp(
+    buf: &mut [u8],
+    src_port: u16,
+    protocol_type: u16,
+    key: Option<u32>,
+    payload: &[u8],
+) -> Result<usize, BuildError> {
+    let gre_len = GreHdr::LEN + if key.is_some() { 4 } else { 0 };
+    let udp_len = UdpHdr::LEN + gre_len + payload.len();
+    let udp_len = u16::try_from(udp_len).map_err(|_| BuildError::FieldOverflow)?;
+    let mut w = Writer::new(buf);
+    write_udp(&mut w, src_port, GRE_IN_UDP_PORT, udp_len)?;
+    let flags = if key.is_some() { FLAG_KEY } else { 0 };
+    w.put(&flags.to_be_bytes())?;
+    w.put(&protocol_type.to_be_bytes())?;
+    if let Some(key) = key {
+        w.put(&key.to_be_bytes())?;
+    }
+    w.put(payload)?;
+    Ok(w.pos())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{write_gre_in_udp, Gre, GRE_IN_UDP_PORT};
+    use crate::{builder::BuildError, eth::EtherType, header::Header, udp::UdpHdr};
+
+    #[test]
+    fn test_gre_in_udp() {
+        let inner = [0x45, 0, 0, 20];
+        let mut buf = [0u8; 32];
+        let len = write_gre_in_udp(&mut buf, 49152, 0x0800, Some(42), &inner).unwrap();
+        assert_eq!(len, UdpHdr::LEN + 8 + inner.len());
+        let udp = UdpHdr::from_bytes(&buf).unwrap();
+        assert_eq!(udp.dest.to_bits(), GRE_IN_UDP_PORT);
+        assert_eq!(udp.len.to_bits() as usize, len);
+
+        let gre = Gre::parse(&buf[UdpHdr::LEN..len]).unwrap();
+        assert_eq!(gre.hdr().protocol_type(), Some(EtherType::Ipv4));
+        assert_eq!((gre.key(), gre.seq()), (Some(42), None));
+        assert_eq!(gre.payload(), &inner);
+
+        assert_eq!(
+            write_gre_in_udp(&mut buf[..19], 49152, 0x0800, Some(42), &inner),
+            Err(BuildError::BufferTooSmall)
+        );
+    }
+}
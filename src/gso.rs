@@ -0,0 +1,249 @@
+//! Software UDP segmentation, the userspace counterpart of the kernel's UDP
+//! GSO (`UDP_SEGMENT`): a large payload is split into datagrams of a fixed
+//! size which all share the same headers.
+//!
+//! Each datagram gets its own IPv4 identification, lengths and checksums,
+//! so the segments can be handed to a raw socket or a NIC queue as is.
+
+use core::slice::Chunks;
+
+use crate::{
+    builder::BuildError,
+    checksum,
+    eth::{self, FrameSize},
+    header::Header,
+    ip::{IpProto, Ipv4Hdr, Ipv6Hdr},
+    udp::UdpHdr,
+};
+
+/// Splits UDP payloads into datagrams built from a header template.
+///
+/// ```
+/// use ether_packet::gso::UdpGso;
+///
+/// let template = [
+///     0x45, 0, 0, 0, 0x12, 0x34, 0x40, 0, 64, 17, 0, 0, 10, 0, 0, 1, 10, 0, 0, 2,
+///     0x30, 0x39, 0x01, 0xbb, 0, 0, 0, 0,
+/// ];
+/// let gso = UdpGso::new(&template, 0, 1200).unwrap();
+/// let mut segments = gso.segments(&[0u8; 3000]);
+/// assert_eq!(segments.len(), 3);
+///
+/// let mut buf = [0u8; 1500];
+/// while let Some(len) = segments.next_into(&mut buf) {
+///     assert!(len.unwrap() <= 28 + 1200);
+/// }
+/// ```
+#[derive(Debug, Copy, Clone)]
+pub struct UdpGso<'a> {
+    template: &'a [u8],
+    l3_offset: usize,
+    ipv6: bool,
+    gso_size: usize,
+    frame_size: Option<FrameSize>,
+}
+
+impl<'a> UdpGso<'a> {
+    /// Creates a segmenter from `template`, which holds the headers of every
+    /// datagram up to and including the UDP header. The IP header starts at
+    /// `l3_offset`, anything before it (e.g. an Ethernet header) is copied
+    /// verbatim.
+    ///
+    /// Returns `None` if the template does not end with an IPv4 or IPv6
+    /// header directly followed by a UDP header, or if `gso_size` is 0.
+    pub fn new(template: &'a [u8], l3_offset: usize, gso_size: usize) -> Option<Self> {
+        let ip = template.get(l3_offset..)?;
+        let (ipv6, hdrlen) = match ip.first()? >> 4 {
+            4 => {
+                let hdr = Ipv4Hdr::from_bytes(ip)?;
+                if !matches!(hdr.proto, IpProto::Udp) || hdr.hdrlen() < Ipv4Hdr::LEN {
+                    return None;
+                }
+                (false, hdr.hdrlen())
+            }
+            6 => {
+                let hdr = Ipv6Hdr::from_bytes(ip)?;
+                if !matches!(hdr.next_hdr, IpProto::Udp) {
+                    return None;
+                }
+                (true, Ipv6Hdr::LEN)
+            }
+            _ => return None,
+        };
+        if ip.len() != hdrlen + UdpHdr::LEN || gso_size == 0 {
+            return None;
+        }
+        Some(Self {
+            template,
+            l3_offset,
+            ipv6,
+            gso_size,
+            frame_size: None,
+        })
+    }
+
+    /// Rejects datagrams whose frame exceeds `size` with
+    /// [`BuildError::FrameTooLarge`]. The template has to start with the
+    /// Ethernet header, `gso_size` being typically derived from
+    /// [`FrameSize::mtu`] for jumbo frames.
+    #[inline]
+    pub fn frame_size(mut self, size: FrameSize) -> Self {
+        self.frame_size = Some(size);
+        self
+    }
+
+    /// Size of the payload carried by every datagram but the last one.
+    #[inline]
+    pub fn gso_size(&self) -> usize {
+        self.gso_size
+    }
+
+    /// Returns the datagrams carrying `payload`, in order. An empty payload
+    /// yields no datagram.
+    pub fn segments<'p>(&self, payload: &'p [u8]) -> UdpSegments<'_, 'p> {
+        UdpSegments {
+            gso: self,
+            chunks: payload.chunks(self.gso_size),
+            index: 0,
+        }
+    }
+
+    /// Writes the datagram number `index` carrying `chunk` into `buf`,
+    /// returning its length.
+    fn build(&self, index: u16, chunk: &[u8], buf: &mut [u8]) -> Result<usize, BuildError> {
+        let len = self.template.len() + chunk.len();
+        if let Some(size) = self.frame_size {
+            if len > size.limit(eth::vlan_tags(self.template)) {
+                return Err(BuildError::FrameTooLarge);
+            }
+        }
+        let out = buf.get_mut(..len).ok_or(BuildError::BufferTooSmall)?;
+        let (hdrs, data) = out.split_at_mut(self.template.len());
+        hdrs.copy_from_slice(self.template);
+        data.copy_from_slice(chunk);
+
+        let ip = &mut out[self.l3_offset..];
+        let udp_offset = ip.len() - chunk.len() - UdpHdr::LEN;
+        let udp_len =
+            u16::try_from(UdpHdr::LEN + chunk.len()).map_err(|_| BuildError::FieldOverflow)?;
+        let pseudo = if self.ipv6 {
+            let hdr = Ipv6Hdr::from_bytes_mut(ip).ok_or(BuildError::BufferTooSmall)?;
+            hdr.payload_len = udp_len.into();
+            checksum::pseudo_header_v6(hdr.src_addr, hdr.dst_addr, IpProto::Udp, udp_len as u32)
+        } else {
+            let tot_len = u16::try_from(ip.len()).map_err(|_| BuildError::FieldOverflow)?;
+            let hdr = Ipv4Hdr::from_bytes_mut(ip).ok_or(BuildError::BufferTooSmall)?;
+            hdr.tot_len = tot_len.into();
+            hdr.id = hdr.id.to_bits().wrapping_add(index).into();
+            hdr.check = 0.into();
+            let pseudo =
+                checksum::pseudo_header_v4(hdr.src_addr, hdr.dst_addr, IpProto::Udp, udp_len);
+            let check = checksum::checksum(&ip[..udp_offset]);
+            ip[10..12].copy_from_slice(&check.to_be_bytes());
+            pseudo
+        };
+
+        let udp = &mut ip[udp_offset..];
+        udp[4..6].copy_from_slice(&udp_len.to_be_bytes());
+        udp[6..8].fill(0);
+        // A computed checksum of zero is transmitted as all ones.
+        let check = match checksum::fold(checksum::sum(udp, pseudo)) {
+            0 => 0xffff,
+            check => check,
+        };
+        udp[6..8].copy_from_slice(&check.to_be_bytes());
+        Ok(len)
+    }
+}
+
+/// Datagrams produced by [`UdpGso::segments`].
+#[derive(Debug, Clone)]
+pub struct UdpSegments<'g, 'p> {
+    gso: &'g UdpGso<'g>,
+    chunks: Chunks<'p, u8>,
+    index: u16,
+}
+
+impl UdpSegments<'_, '_> {
+    /// Number of datagrams left.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.chunks.len() == 0
+    }
+
+    /// Writes the next datagram into `buf`, returning its length, or `None`
+    /// once the whole payload has been written. A datagram which does not
+    /// fit into `buf` is skipped.
+    pub fn next_into(&mut self, buf: &mut [u8]) -> Option<Result<usize, BuildError>> {
+        let chunk = self.chunks.next()?;
+        let index = self.index;
+        self.index = self.index.wrapping_add(1);
+        Some(self.gso.build(index, chunk, buf))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UdpGso;
+    use crate::{builder::BuildError, checksum, eth::FrameSize};
+
+    #[test]
+    fn test_segments_v4() {
+        #[rustfmt::skip]
+        let template = [
+            0x45, 0, 0, 0, 0xff, 0xff, 0x40, 0, 64, 17, 0, 0, 10, 0, 0, 1, 10, 0, 0, 2,
+            0x30, 0x39, 0x01, 0xbb, 0, 0, 0, 0,
+        ];
+        let payload: [u8; 25] = core::array::from_fn(|i| i as u8);
+        let gso = UdpGso::new(&template, 0, 10).unwrap();
+        let mut segments = gso.segments(&payload);
+        assert_eq!(segments.len(), 3);
+
+        let mut buf = [0u8; 64];
+        let mut ids = [0u16; 3];
+        for (i, id) in ids.iter_mut().enumerate() {
+            let len = segments.next_into(&mut buf).unwrap().unwrap();
+            let chunk_len = if i == 2 { 5 } else { 10 };
+            assert_eq!(len, 28 + chunk_len);
+            assert_eq!(u16::from_be_bytes([buf[2], buf[3]]) as usize, len);
+            assert_eq!(
+                u16::from_be_bytes([buf[24], buf[25]]) as usize,
+                8 + chunk_len
+            );
+            assert_eq!(&buf[28..len], &payload[i * 10..i * 10 + chunk_len]);
+            assert_eq!(checksum::checksum(&buf[..20]), 0);
+            let pseudo = checksum::sum(&[10, 0, 0, 1, 10, 0, 0, 2, 0, 17], 0);
+            let pseudo = checksum::sum(&buf[24..26], pseudo);
+            assert_eq!(checksum::fold(checksum::sum(&buf[20..len], pseudo)), 0);
+            *id = u16::from_be_bytes([buf[4], buf[5]]);
+        }
+        assert_eq!(ids, [0xffff, 0, 1]);
+        assert!(segments.next_into(&mut buf).is_none());
+
+        let mut segments = gso.segments(&payload);
+        assert_eq!(
+            segments.next_into(&mut buf[..30]),
+            Some(Err(BuildError::BufferTooSmall))
+        );
+        assert!(UdpGso::new(&template[..27], 0, 10).is_none());
+
+        // Jumbo datagrams, whose lengths still fit into 16 bits.
+        let jumbo = [0u8; 9000];
+        let mut buf = [0u8; 9100];
+        let gso = UdpGso::new(&template, 0, 8972).unwrap();
+        let len = gso.segments(&jumbo).next_into(&mut buf).unwrap().unwrap();
+        assert_eq!(len, 9000);
+        assert_eq!(u16::from_be_bytes([buf[2], buf[3]]), 9000);
+        let gso = gso.frame_size(FrameSize::STANDARD);
+        assert_eq!(
+            gso.segments(&jumbo).next_into(&mut buf),
+            Some(Err(BuildError::FrameTooLarge))
+        );
+        assert!(UdpGso::new(&template, 0, 0).is_none());
+    }
+}
@@ -0,0 +1,242 @@
+//! Zero-copy access to fixed-size headers stored in byte buffers.
+
+use core::{fmt, mem, slice};
+
+/// Why a header could not be parsed.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum ParseError {
+    /// The header extends past the captured bytes, e.g. because the capture
+    /// was cut at the snapshot length. The packet itself may be well-formed.
+    Truncated,
+    /// The header is invalid, or extends past the end of the packet.
+    Malformed,
+    /// A chain of headers, options or labels goes beyond a limit of the
+    /// [`ParseConfig`].
+    LimitExceeded,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Truncated => f.write_str("truncated"),
+            ParseError::Malformed => f.write_str("malformed"),
+            ParseError::LimitExceeded => f.write_str("limit exceeded"),
+        }
+    }
+}
+
+/// Limits of the parsers walking chains whose length is chosen by the
+/// sender, such as IPv6 extension headers or compressed DNS names.
+///
+/// Parsers given a configuration fail with [`ParseError::LimitExceeded`]
+/// as soon as a limit is reached, bounding the work spent on a hostile
+/// packet below what its length alone allows. The other parsers use
+/// [`ParseConfig::DEFAULT`], which only rejects what they always rejected,
+/// while [`ParseConfig::HARDENED`] suits fuzzing and exposed sensors.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub struct ParseConfig {
+    /// Largest number of IPv6 extension headers before the upper-layer
+    /// header.
+    pub max_ipv6_ext_hdrs: usize,
+    /// Largest length, in bytes, of an IPv6 Hop-by-Hop or Destination
+    /// Options header, of the options of a Geneve header or Neighbor
+    /// Discovery message, or of the TLVs of a CFM PDU.
+    pub max_options_len: usize,
+    /// Largest number of options, TLVs or objects walked by the `limit`
+    /// methods of their iterators.
+    pub max_options: usize,
+    /// Largest number of entries of an MPLS label stack.
+    pub max_mpls_labels: usize,
+    /// Whether to walk into tunnels, the parsers going through a single
+    /// one.
+    pub tunnels: bool,
+    /// Largest number of labels of a DNS name, the root excluded.
+    pub max_dns_labels: usize,
+    /// Largest number of compression pointers followed in a DNS name.
+    pub max_dns_pointers: usize,
+}
+
+impl ParseConfig {
+    /// Limits implied by the formats, e.g. a DNS name of 255 bytes has at
+    /// most 127 labels.
+    pub const DEFAULT: Self = Self {
+        max_ipv6_ext_hdrs: usize::MAX,
+        max_options_len: usize::MAX,
+        max_options: usize::MAX,
+        max_mpls_labels: usize::MAX,
+        tunnels: true,
+        max_dns_labels: 127,
+        max_dns_pointers: 255,
+    };
+
+    /// Limits above what legitimate traffic uses.
+    pub const HARDENED: Self = Self {
+        max_ipv6_ext_hdrs: 8,
+        max_options_len: 256,
+        max_options: 256,
+        max_mpls_labels: 16,
+        tunnels: true,
+        max_dns_labels: 64,
+        max_dns_pointers: 8,
+    };
+
+    /// Bounds `iter`, walking an option area of `len` bytes.
+    pub(crate) fn limit_options<I>(&self, iter: I, len: usize) -> Result<Limited<I>, ParseError> {
+        if len > self.max_options_len {
+            return Err(ParseError::LimitExceeded);
+        }
+        Ok(Limited::new(iter, self.max_options))
+    }
+}
+
+impl Default for ParseConfig {
+    #[inline]
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Iterator over the items of another, up to a limit of a [`ParseConfig`].
+///
+/// Past the limit, it yields [`ParseError::LimitExceeded`] in place of the
+/// next item, then ends.
+#[derive(Debug, Clone)]
+pub struct Limited<I> {
+    iter: I,
+    /// Items left within the limit, `None` once it was exceeded.
+    left: Option<usize>,
+}
+
+impl<I> Limited<I> {
+    #[inline]
+    pub(crate) fn new(iter: I, max: usize) -> Self {
+        Self {
+            iter,
+            left: Some(max),
+        }
+    }
+}
+
+impl<I: Iterator> Iterator for Limited<I> {
+    type Item = Result<I::Item, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let left = self.left?;
+        let item = self.iter.next()?;
+        if left == 0 {
+            self.left = None;
+            return Some(Err(ParseError::LimitExceeded));
+        }
+        self.left = Some(left - 1);
+        Some(Ok(item))
+    }
+}
+
+/// A fixed-size protocol header which can be viewed in place inside a byte
+/// buffer, without copying and without the caller writing any `unsafe` code.
+///
+/// # Safety
+///
+/// Implementors must be `#[repr(C, packed)]` (alignment of 1, no padding) and
+/// every byte pattern accepted by [`Header::validate`] must be a valid value of
+/// `Self`.
+pub unsafe trait Header: Copy {
+    /// Checks the bytes of a header before they are reinterpreted as `Self`.
+    ///
+    /// `bytes` is always at least `size_of::<Self>()` long. Headers containing
+    /// enums (e.g. [`IpProto`]) reject discriminants which are not declared.
+    #[inline]
+    fn validate(_bytes: &[u8]) -> bool {
+        true
+    }
+
+    /// Returns a reference to the header at the start of `bytes`, or `None`
+    /// when `bytes` is too short or does not hold a valid header.
+    #[inline]
+    fn from_bytes(bytes: &[u8]) -> Option<&Self> {
+        if bytes.len() < mem::size_of::<Self>() || !Self::validate(bytes) {
+            return None;
+        }
+        // SAFETY: the length was checked above, `Self` has an alignment of 1
+        // and the contents were validated.
+        Some(unsafe { &*(bytes.as_ptr() as *const Self) })
+    }
+
+    /// Like [`Header::from_bytes`], but tells a buffer too short to hold the
+    /// header, reported as [`ParseError::Truncated`], from invalid contents.
+    #[inline]
+    fn parse(bytes: &[u8]) -> Result<&Self, ParseError> {
+        if bytes.len() < mem::size_of::<Self>() {
+            return Err(ParseError::Truncated);
+        }
+        Self::from_bytes(bytes).ok_or(ParseError::Malformed)
+    }
+
+    /// Mutable variant of [`Header::from_bytes`].
+    #[inline]
+    fn from_bytes_mut(bytes: &mut [u8]) -> Option<&mut Self> {
+        if bytes.len() < mem::size_of::<Self>() || !Self::validate(bytes) {
+            return None;
+        }
+        // SAFETY: see `from_bytes`.
+        Some(unsafe { &mut *(bytes.as_mut_ptr() as *mut Self) })
+    }
+
+    /// Returns a copy of the header at the start of `bytes`.
+    #[inline]
+    fn read(bytes: &[u8]) -> Option<Self> {
+        Self::from_bytes(bytes).copied()
+    }
+
+    /// Returns the wire representation of the header.
+    #[inline]
+    fn as_bytes(&self) -> &[u8] {
+        // SAFETY: `Self` has no padding, so all of its bytes are initialized.
+        unsafe { slice::from_raw_parts(self as *const Self as *const u8, mem::size_of::<Self>()) }
+    }
+}
+
+/// Implements [`Header`] for packed structs, checking their alignment at
+/// compile time. An optional `validate` closure rejects invalid contents.
+macro_rules! impl_header {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            const _: () = assert!(::core::mem::align_of::<$ty>() == 1);
+            unsafe impl $crate::header::Header for $ty {}
+        )*
+    };
+    ($ty:ty, validate = $validate:expr) => {
+        const _: () = assert!(::core::mem::align_of::<$ty>() == 1);
+        unsafe impl $crate::header::Header for $ty {
+            #[inline]
+            fn validate(bytes: &[u8]) -> bool {
+                $validate(bytes)
+            }
+        }
+    };
+}
+pub(crate) use impl_header;
+
+#[cfg(test)]
+mod tests {
+    use core::net::Ipv4Addr;
+
+    use super::Header;
+    use crate::{eth::EthHdr, ip::Ipv4Hdr};
+
+    #[test]
+    fn test_from_bytes() {
+        let mut bytes = [
+            0x45, 0, 0, 20, 0, 0, 0, 0, 64, 17, 0, 0, 10, 0, 0, 1, 10, 0, 0, 2,
+        ];
+        let hdr = Ipv4Hdr::from_bytes(&bytes).unwrap();
+        assert_eq!(hdr.ihl(), 5);
+        assert_eq!(hdr.src_addr, Ipv4Addr::new(10, 0, 0, 1));
+        assert_eq!(hdr.as_bytes(), &bytes);
+        assert!(EthHdr::from_bytes(&bytes[..13]).is_none());
+
+        // 150 is not an assigned protocol number.
+        bytes[9] = 150;
+        assert!(Ipv4Hdr::from_bytes(&bytes).is_none());
+    }
+}
@@ -1,45 +1,193 @@
-use core::mem;
+//! Internet Control Message Protocol
+//! ([RFC 792](https://datatracker.ietf.org/doc/html/rfc792)).
+
+use core::{fmt, mem};
+
+use crate::{
+    header::impl_header,
+    types::{U16, U32},
+};
 
 pub const ICMP_HDR_LEN: usize = mem::size_of::<IcmpHdr>();
 
-//todo: https://datatracker.ietf.org/doc/html/rfc792
+/// ICMP message types.
+#[repr(u8)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum IcmpType {
+    EchoReply = 0,
+    DestUnreachable = 3,
+    SourceQuench = 4,
+    Redirect = 5,
+    EchoRequest = 8,
+    RouterAdvert = 9,
+    RouterSolicit = 10,
+    TimeExceeded = 11,
+    ParameterProblem = 12,
+    Timestamp = 13,
+    TimestampReply = 14,
+}
+
+impl TryFrom<u8> for IcmpType {
+    type Error = ();
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(IcmpType::EchoReply),
+            3 => Ok(IcmpType::DestUnreachable),
+            4 => Ok(IcmpType::SourceQuench),
+            5 => Ok(IcmpType::Redirect),
+            8 => Ok(IcmpType::EchoRequest),
+            9 => Ok(IcmpType::RouterAdvert),
+            10 => Ok(IcmpType::RouterSolicit),
+            11 => Ok(IcmpType::TimeExceeded),
+            12 => Ok(IcmpType::ParameterProblem),
+            13 => Ok(IcmpType::Timestamp),
+            14 => Ok(IcmpType::TimestampReply),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Codes of [`IcmpType::DestUnreachable`] messages.
+#[repr(u8)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum DestUnreachableCode {
+    NetUnreachable = 0,
+    HostUnreachable = 1,
+    ProtocolUnreachable = 2,
+    PortUnreachable = 3,
+    /// The packet needs to be fragmented but DF is set, the next-hop MTU
+    /// being given ([RFC 1191](https://datatracker.ietf.org/doc/html/rfc1191)).
+    FragmentationNeeded = 4,
+    SourceRouteFailed = 5,
+    NetUnknown = 6,
+    HostUnknown = 7,
+    NetProhibited = 9,
+    HostProhibited = 10,
+    /// Communication administratively prohibited
+    /// ([RFC 1812](https://datatracker.ietf.org/doc/html/rfc1812)).
+    AdminProhibited = 13,
+}
+
+impl TryFrom<u8> for DestUnreachableCode {
+    type Error = ();
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(DestUnreachableCode::NetUnreachable),
+            1 => Ok(DestUnreachableCode::HostUnreachable),
+            2 => Ok(DestUnreachableCode::ProtocolUnreachable),
+            3 => Ok(DestUnreachableCode::PortUnreachable),
+            4 => Ok(DestUnreachableCode::FragmentationNeeded),
+            5 => Ok(DestUnreachableCode::SourceRouteFailed),
+            6 => Ok(DestUnreachableCode::NetUnknown),
+            7 => Ok(DestUnreachableCode::HostUnknown),
+            9 => Ok(DestUnreachableCode::NetProhibited),
+            10 => Ok(DestUnreachableCode::HostProhibited),
+            13 => Ok(DestUnreachableCode::AdminProhibited),
+            _ => Err(()),
+        }
+    }
+}
 
-#[repr(C)]
+/// ICMP header, the meaning of the last 4 bytes depending on the type.
+/// ```text
+/// +---------------+---------------+-------------------------------+
+/// |     Type      |     Code      |           Checksum            |
+/// +---------------+---------------+-------------------------------+
+/// |                         Rest of header                        |
+/// +---------------------------------------------------------------+
+/// ```
+#[repr(C, packed)]
 #[derive(Copy, Clone)]
 #[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 pub struct IcmpHdr {
     pub r#type: u8,
     pub code: u8,
-    pub checksum: u16,
+    pub checksum: U16,
     pub un: IcmpHdrUn,
 }
 
 impl IcmpHdr {
     pub const LEN: usize = mem::size_of::<IcmpHdr>();
+
+    #[inline]
+    pub fn icmp_type(&self) -> Option<IcmpType> {
+        self.r#type.try_into().ok()
+    }
+
+    /// Code of a [`IcmpType::DestUnreachable`] message.
+    #[inline]
+    pub fn dest_unreachable_code(&self) -> Option<DestUnreachableCode> {
+        if self.icmp_type() != Some(IcmpType::DestUnreachable) {
+            return None;
+        }
+        self.code.try_into().ok()
+    }
+
+    /// The last 4 bytes of the header.
+    #[inline]
+    pub const fn rest(&self) -> [u8; 4] {
+        // SAFETY: all the variants of the union are plain bytes.
+        unsafe { self.un.reserved }
+    }
+
+    /// Identifier of echo requests and replies.
+    #[inline]
+    pub const fn echo_id(&self) -> u16 {
+        let [a, b, _, _] = self.rest();
+        u16::from_be_bytes([a, b])
+    }
+
+    /// Sequence number of echo requests and replies.
+    #[inline]
+    pub const fn echo_sequence(&self) -> u16 {
+        let [_, _, a, b] = self.rest();
+        u16::from_be_bytes([a, b])
+    }
+
+    /// Next-hop MTU of "fragmentation needed" messages.
+    #[inline]
+    pub const fn next_hop_mtu(&self) -> u16 {
+        self.echo_sequence()
+    }
+}
+
+impl_header!(IcmpHdr);
+
+impl fmt::Debug for IcmpHdr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IcmpHdr")
+            .field("type", &self.r#type)
+            .field("code", &self.code)
+            .field("checksum", &{ self.checksum })
+            .field("rest", &self.rest())
+            .finish()
+    }
 }
 
-#[repr(C)]
+#[repr(C, packed)]
 #[derive(Copy, Clone)]
 #[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 pub union IcmpHdrUn {
     pub echo: IcmpHdrEcho,
-    pub gateway: u32,
+    pub gateway: U32,
     pub frag: IcmpHdrFrag,
     pub reserved: [u8; 4usize],
 }
 
-#[repr(C)]
+#[repr(C, packed)]
 #[derive(Debug, Copy, Clone)]
 #[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 pub struct IcmpHdrEcho {
-    pub id: u16,
-    pub sequence: u16,
+    pub id: U16,
+    pub sequence: U16,
 }
 
-#[repr(C)]
+#[repr(C, packed)]
 #[derive(Debug, Copy, Clone)]
 #[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 pub struct IcmpHdrFrag {
-    pub __unused: u16,
-    pub mtu: u16,
+    pub __unused: U16,
+    pub mtu: U16,
 }
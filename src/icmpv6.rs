@@ -0,0 +1,150 @@
+//! Internet Control Message Protocol for IPv6
+//! ([RFC 4443](https://datatracker.ietf.org/doc/html/rfc4443)).
+//!
+//! Neighbor Discovery messages are described in [`ndp`](crate::ndp).
+
+use core::mem;
+
+use crate::{header::impl_header, types::U16};
+
+/// ICMPv6 message types.
+#[repr(u8)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum Icmpv6Type {
+    DestUnreachable = 1,
+    PacketTooBig = 2,
+    TimeExceeded = 3,
+    ParameterProblem = 4,
+    EchoRequest = 128,
+    EchoReply = 129,
+    MulticastListenerQuery = 130,
+    MulticastListenerReport = 131,
+    MulticastListenerDone = 132,
+    RouterSolicit = 133,
+    RouterAdvert = 134,
+    NeighborSolicit = 135,
+    NeighborAdvert = 136,
+    Redirect = 137,
+    MulticastListenerReportV2 = 143,
+}
+
+impl TryFrom<u8> for Icmpv6Type {
+    type Error = ();
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(Icmpv6Type::DestUnreachable),
+            2 => Ok(Icmpv6Type::PacketTooBig),
+            3 => Ok(Icmpv6Type::TimeExceeded),
+            4 => Ok(Icmpv6Type::ParameterProblem),
+            128 => Ok(Icmpv6Type::EchoRequest),
+            129 => Ok(Icmpv6Type::EchoReply),
+            130 => Ok(Icmpv6Type::MulticastListenerQuery),
+            131 => Ok(Icmpv6Type::MulticastListenerReport),
+            132 => Ok(Icmpv6Type::MulticastListenerDone),
+            133 => Ok(Icmpv6Type::RouterSolicit),
+            134 => Ok(Icmpv6Type::RouterAdvert),
+            135 => Ok(Icmpv6Type::NeighborSolicit),
+            136 => Ok(Icmpv6Type::NeighborAdvert),
+            137 => Ok(Icmpv6Type::Redirect),
+            143 => Ok(Icmpv6Type::MulticastListenerReportV2),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Icmpv6Type {
+    /// Whether the message reports an error, i.e. the type is below 128.
+    #[inline]
+    pub const fn is_error(&self) -> bool {
+        (*self as u8) < 128
+    }
+}
+
+/// Codes of [`Icmpv6Type::DestUnreachable`] messages.
+#[repr(u8)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum DestUnreachableCode {
+    NoRoute = 0,
+    AdminProhibited = 1,
+    BeyondScope = 2,
+    AddrUnreachable = 3,
+    PortUnreachable = 4,
+    /// Source address failed ingress/egress policy.
+    SourcePolicy = 5,
+    RejectRoute = 6,
+}
+
+impl TryFrom<u8> for DestUnreachableCode {
+    type Error = ();
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(DestUnreachableCode::NoRoute),
+            1 => Ok(DestUnreachableCode::AdminProhibited),
+            2 => Ok(DestUnreachableCode::BeyondScope),
+            3 => Ok(DestUnreachableCode::AddrUnreachable),
+            4 => Ok(DestUnreachableCode::PortUnreachable),
+            5 => Ok(DestUnreachableCode::SourcePolicy),
+            6 => Ok(DestUnreachableCode::RejectRoute),
+            _ => Err(()),
+        }
+    }
+}
+
+/// ICMPv6 header, the meaning of the last 4 bytes depending on the type.
+/// ```text
+/// +---------------+---------------+-------------------------------+
+/// |     Type      |     Code      |           Checksum            |
+/// +---------------+---------------+-------------------------------+
+/// |                         Rest of header                        |
+/// +---------------------------------------------------------------+
+/// ```
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct Icmpv6Hdr {
+    pub r#type: u8,
+    pub code: u8,
+    pub checksum: U16,
+    pub rest: [u8; 4],
+}
+
+impl Icmpv6Hdr {
+    pub const LEN: usize = mem::size_of::<Icmpv6Hdr>();
+
+    #[inline]
+    pub fn icmp_type(&self) -> Option<Icmpv6Type> {
+        self.r#type.try_into().ok()
+    }
+
+    /// Code of a [`Icmpv6Type::DestUnreachable`] message.
+    #[inline]
+    pub fn dest_unreachable_code(&self) -> Option<DestUnreachableCode> {
+        if self.icmp_type() != Some(Icmpv6Type::DestUnreachable) {
+            return None;
+        }
+        self.code.try_into().ok()
+    }
+
+    /// Identifier of echo requests and replies.
+    #[inline]
+    pub const fn echo_id(&self) -> u16 {
+        u16::from_be_bytes([self.rest[0], self.rest[1]])
+    }
+
+    /// Sequence number of echo requests and replies.
+    #[inline]
+    pub const fn echo_sequence(&self) -> u16 {
+        u16::from_be_bytes([self.rest[2], self.rest[3]])
+    }
+
+    /// MTU of [`Icmpv6Type::PacketTooBig`] messages, or pointer of
+    /// [`Icmpv6Type::ParameterProblem`] messages.
+    #[inline]
+    pub const fn mtu(&self) -> u32 {
+        u32::from_be_bytes(self.rest)
+    }
+}
+
+impl_header!(Icmpv6Hdr);
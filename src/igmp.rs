@@ -0,0 +1,579 @@
+use core::{mem, net::Ipv4Addr};
+
+use crate::{
+    builder::{BuildError, Writer},
+    checksum,
+    header::{impl_header, Header},
+    ip::{IpProto, Ipv4Hdr},
+    types::U16,
+};
+
+/// IGMP message types.
+///
+/// <https://www.iana.org/assignments/igmp-type-numbers/igmp-type-numbers.xhtml>
+#[repr(u8)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum IgmpType {
+    MembershipQuery = 0x11,
+    V1MembershipReport = 0x12,
+    V2MembershipReport = 0x16,
+    LeaveGroup = 0x17,
+    V3MembershipReport = 0x22,
+}
+
+impl TryFrom<u8> for IgmpType {
+    type Error = ();
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x11 => Ok(IgmpType::MembershipQuery),
+            0x12 => Ok(IgmpType::V1MembershipReport),
+            0x16 => Ok(IgmpType::V2MembershipReport),
+            0x17 => Ok(IgmpType::LeaveGroup),
+            0x22 => Ok(IgmpType::V3MembershipReport),
+            _ => Err(()),
+        }
+    }
+}
+
+/// IGMPv1/v2 message, also the common prefix of IGMPv3 queries.
+///
+/// [RFC 2236](https://datatracker.ietf.org/doc/html/rfc2236)
+/// ```text
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |      Type     | Max Resp Time |           Checksum            |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                         Group Address                         |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct IgmpHdr {
+    /// See [`IgmpType`].
+    pub r#type: u8,
+    /// Maximum time allowed before sending a responding report, in units of
+    /// 1/10 second. Only meaningful in queries.
+    pub max_resp_time: u8,
+    pub checksum: U16,
+    pub group_addr: Ipv4Addr,
+}
+
+impl IgmpHdr {
+    pub const LEN: usize = mem::size_of::<IgmpHdr>();
+
+    #[inline]
+    pub fn igmp_type(&self) -> Option<IgmpType> {
+        self.r#type.try_into().ok()
+    }
+}
+
+/// Fixed part of an IGMPv3 Membership Report, followed by the group records.
+///
+/// [RFC 3376 4.2](https://datatracker.ietf.org/doc/html/rfc3376#section-4.2)
+/// ```text
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |  Type = 0x22  |    Reserved   |           Checksum            |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |           Reserved            |  Number of Group Records (M)  |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct IgmpV3ReportHdr {
+    pub r#type: u8,
+    pub _reserved_1: u8,
+    pub checksum: U16,
+    pub _reserved_2: U16,
+    pub num_records: U16,
+}
+
+impl IgmpV3ReportHdr {
+    pub const LEN: usize = mem::size_of::<IgmpV3ReportHdr>();
+}
+
+/// Type of an IGMPv3 group record.
+#[repr(u8)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum GroupRecordType {
+    /// Current-State Record: the interface is in INCLUDE mode for the sources.
+    ModeIsInclude = 1,
+    /// Current-State Record: the interface is in EXCLUDE mode for the sources.
+    ModeIsExclude = 2,
+    /// Filter-Mode-Change Record to INCLUDE.
+    ChangeToIncludeMode = 3,
+    /// Filter-Mode-Change Record to EXCLUDE.
+    ChangeToExcludeMode = 4,
+    /// Source-List-Change Record adding sources.
+    AllowNewSources = 5,
+    /// Source-List-Change Record removing sources.
+    BlockOldSources = 6,
+}
+
+impl TryFrom<u8> for GroupRecordType {
+    type Error = ();
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(GroupRecordType::ModeIsInclude),
+            2 => Ok(GroupRecordType::ModeIsExclude),
+            3 => Ok(GroupRecordType::ChangeToIncludeMode),
+            4 => Ok(GroupRecordType::ChangeToExcludeMode),
+            5 => Ok(GroupRecordType::AllowNewSources),
+            6 => Ok(GroupRecordType::BlockOldSources),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Fixed part of an IGMPv3 group record, followed by the source addresses
+/// and the auxiliary data.
+/// ```text
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |  Record Type  |  Aux Data Len  |     Number of Sources (N)    |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                       Multicast Address                       |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct IgmpV3GroupRecordHdr {
+    /// See [`GroupRecordType`].
+    pub record_type: u8,
+    /// Length of the auxiliary data, in units of 32-bit words.
+    pub aux_data_len: u8,
+    pub num_sources: U16,
+    pub group_addr: Ipv4Addr,
+}
+
+impl IgmpV3GroupRecordHdr {
+    pub const LEN: usize = mem::size_of::<IgmpV3GroupRecordHdr>();
+
+    #[inline]
+    pub fn record_type(&self) -> Option<GroupRecordType> {
+        self.record_type.try_into().ok()
+    }
+}
+
+impl_header!(IgmpHdr, IgmpV3ReportHdr, IgmpV3GroupRecordHdr);
+
+/// A group record of an IGMPv3 Membership Report.
+#[derive(Debug, Copy, Clone)]
+pub struct GroupRecord<'a> {
+    pub hdr: &'a IgmpV3GroupRecordHdr,
+    sources: &'a [u8],
+    /// Auxiliary data of the record.
+    pub aux_data: &'a [u8],
+}
+
+impl<'a> GroupRecord<'a> {
+    /// Source addresses of the record.
+    pub fn sources(&self) -> impl Iterator<Item = Ipv4Addr> + 'a {
+        self.sources
+            .chunks_exact(4)
+            .map(|a| Ipv4Addr::new(a[0], a[1], a[2], a[3]))
+    }
+}
+
+/// Iterator over the group records of an IGMPv3 Membership Report.
+#[derive(Debug, Clone)]
+pub struct GroupRecords<'a> {
+    data: &'a [u8],
+    remaining: u16,
+}
+
+impl<'a> GroupRecords<'a> {
+    /// Iterates over the records of the report stored in `msg`, which starts
+    /// with the [`IgmpV3ReportHdr`].
+    pub fn new(msg: &'a [u8]) -> Option<Self> {
+        let hdr = IgmpV3ReportHdr::from_bytes(msg)?;
+        Some(Self {
+            data: &msg[IgmpV3ReportHdr::LEN..],
+            remaining: hdr.num_records.to_bits(),
+        })
+    }
+}
+
+impl<'a> Iterator for GroupRecords<'a> {
+    type Item = GroupRecord<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let hdr = IgmpV3GroupRecordHdr::from_bytes(self.data)?;
+        let sources_len = hdr.num_sources.to_bits() as usize * 4;
+        let aux_len = hdr.aux_data_len as usize * 4;
+        let end = IgmpV3GroupRecordHdr::LEN + sources_len + aux_len;
+        if self.data.len() < end {
+            self.remaining = 0;
+            return None;
+        }
+        let body = &self.data[IgmpV3GroupRecordHdr::LEN..end];
+        self.data = &self.data[end..];
+        self.remaining -= 1;
+        Some(GroupRecord {
+            hdr,
+            sources: &body[..sources_len],
+            aux_data: &body[sources_len..],
+        })
+    }
+}
+
+/// Builds an IGMPv3 Membership Report into a caller-provided buffer.
+///
+/// ```
+/// use core::net::Ipv4Addr;
+/// use ether_packet::igmp::{GroupRecordType, IgmpV3ReportBuilder};
+///
+/// let mut buf = [0u8; 64];
+/// let mut report = IgmpV3ReportBuilder::new(&mut buf).unwrap();
+/// report
+///     .group_record(
+///         GroupRecordType::ChangeToIncludeMode,
+///         Ipv4Addr::new(232, 1, 1, 1),
+///         &[Ipv4Addr::new(10, 0, 0, 1)],
+///     )
+///     .unwrap();
+/// let len = report.finish();
+/// assert_eq!(len, 8 + 8 + 4);
+/// ```
+pub struct IgmpV3ReportBuilder<'a> {
+    w: Writer<'a>,
+    num_records: u16,
+}
+
+impl<'a> IgmpV3ReportBuilder<'a> {
+    pub fn new(buf: &'a mut [u8]) -> Result<Self, BuildError> {
+        let mut w = Writer::new(buf);
+        w.put_u8(IgmpType::V3MembershipReport as u8)?;
+        w.reserve(IgmpV3ReportHdr::LEN - 1)?;
+        Ok(Self { w, num_records: 0 })
+    }
+
+    /// Appends a group record without auxiliary data.
+    pub fn group_record(
+        &mut self,
+        record_type: GroupRecordType,
+        group: Ipv4Addr,
+        sources: &[Ipv4Addr],
+    ) -> Result<&mut Self, BuildError> {
+        self.group_record_with_aux(record_type, group, sources, &[])
+    }
+
+    /// Appends a group record. `aux_data` is padded with zeros to a multiple
+    /// of 32 bits.
+    pub fn group_record_with_aux(
+        &mut self,
+        record_type: GroupRecordType,
+        group: Ipv4Addr,
+        sources: &[Ipv4Addr],
+        aux_data: &[u8],
+    ) -> Result<&mut Self, BuildError> {
+        let num_sources = u16::try_from(sources.len()).map_err(|_| BuildError::FieldOverflow)?;
+        let aux_words =
+            u8::try_from(aux_data.len().div_ceil(4)).map_err(|_| BuildError::FieldOverflow)?;
+        let num_records = self
+            .num_records
+            .checked_add(1)
+            .ok_or(BuildError::FieldOverflow)?;
+
+        let sources_len = sources.len() * 4;
+        let rec = self
+            .w
+            .reserve(IgmpV3GroupRecordHdr::LEN + sources_len + aux_words as usize * 4)?;
+        rec[0] = record_type as u8;
+        rec[1] = aux_words;
+        rec[2..4].copy_from_slice(&num_sources.to_be_bytes());
+        rec[4..8].copy_from_slice(&group.octets());
+        let (src, aux) = rec[IgmpV3GroupRecordHdr::LEN..].split_at_mut(sources_len);
+        for (dst, source) in src.chunks_exact_mut(4).zip(sources) {
+            dst.copy_from_slice(&source.octets());
+        }
+        aux[..aux_data.len()].copy_from_slice(aux_data);
+        self.num_records = num_records;
+        Ok(self)
+    }
+
+    /// Fills in the number of records and the checksum, returning the length
+    /// of the report.
+    pub fn finish(mut self) -> usize {
+        self.w.set_u16(6, self.num_records);
+        let check = checksum::checksum(self.w.written());
+        self.w.set_u16(2, check);
+        self.w.pos()
+    }
+}
+
+/// Destination of General Queries, the all-systems group.
+pub const ALL_SYSTEMS: Ipv4Addr = Ipv4Addr::new(224, 0, 0, 1);
+
+/// Encodes a maximum response time, in units of 1/10 second, as the Max
+/// Resp Code of IGMPv3 queries
+/// ([RFC 3376 4.1.1](https://datatracker.ietf.org/doc/html/rfc3376#section-4.1.1)).
+/// Times from 128 on use the floating-point format, rounded down, and are
+/// capped at 31744.
+pub const fn max_resp_code(tenths: u32) -> u8 {
+    if tenths < 128 {
+        return tenths as u8;
+    }
+    let mut exp = 0;
+    while exp < 7 && tenths >> (exp + 3) > 0x1f {
+        exp += 1;
+    }
+    let mant = tenths >> (exp + 3);
+    if mant > 0x1f {
+        return 0xff;
+    }
+    0x80 | (exp as u8) << 4 | (mant as u8 & 0x0f)
+}
+
+/// Decodes the Max Resp Code of IGMPv3 queries, in units of 1/10 second.
+pub const fn max_resp_time(code: u8) -> u32 {
+    if code < 128 {
+        return code as u32;
+    }
+    ((code as u32 & 0x0f) | 0x10) << (((code >> 4) & 0x7) + 3)
+}
+
+/// Writes the IPv4 header of an IGMP message, with a TTL of 1 and the
+/// Router Alert option.
+fn put_ipv4_router_alert(
+    w: &mut Writer<'_>,
+    src: Ipv4Addr,
+    dst: Ipv4Addr,
+) -> Result<(), BuildError> {
+    let hdr = w.reserve(IPV4_RA_HDR_LEN)?;
+    hdr[0] = 0x46;
+    // Internetwork control, as sent by multicast routers.
+    hdr[1] = 0xc0;
+    hdr[6] = 0x40;
+    hdr[8] = 1;
+    hdr[9] = IpProto::Igmp as u8;
+    hdr[12..16].copy_from_slice(&src.octets());
+    hdr[16..20].copy_from_slice(&dst.octets());
+    hdr[20..24].copy_from_slice(&[0x94, 0x04, 0, 0]);
+    Ok(())
+}
+
+/// Length of an IPv4 header carrying the Router Alert option.
+const IPV4_RA_HDR_LEN: usize = Ipv4Hdr::LEN + 4;
+
+/// Fills in the lengths and checksums of an IGMP message following an
+/// IPv4 header of [`IPV4_RA_HDR_LEN`] bytes.
+fn finish_ipv4(w: &mut Writer<'_>) -> usize {
+    let len = w.pos();
+    w.set_u16(2, len as u16);
+    let check = checksum::checksum(&w.written()[..IPV4_RA_HDR_LEN]);
+    w.set_u16(10, check);
+    let check = checksum::checksum(&w.written()[IPV4_RA_HDR_LEN..]);
+    w.set_u16(IPV4_RA_HDR_LEN + 2, check);
+    len
+}
+
+/// Writes an IGMPv2 Membership Query, with its IPv4 header, into `buf` and
+/// returns its length. The query is a General Query if `group` is
+/// unspecified, and is then sent to [`ALL_SYSTEMS`], otherwise a
+/// Group-Specific Query sent to the group.
+pub fn write_igmpv2_query(
+    buf: &mut [u8],
+    src: Ipv4Addr,
+    group: Ipv4Addr,
+    max_resp_time: u8,
+) -> Result<usize, BuildError> {
+    let mut w = Writer::new(buf);
+    put_ipv4_router_alert(&mut w, src, query_dst(group))?;
+    let msg = w.reserve(IgmpHdr::LEN)?;
+    msg[0] = IgmpType::MembershipQuery as u8;
+    msg[1] = max_resp_time;
+    msg[4..8].copy_from_slice(&group.octets());
+    Ok(finish_ipv4(&mut w))
+}
+
+#[inline]
+fn query_dst(group: Ipv4Addr) -> Ipv4Addr {
+    if group.is_unspecified() {
+        ALL_SYSTEMS
+    } else {
+        group
+    }
+}
+
+/// Builds an IGMPv3 Membership Query, with its IPv4 header, into a
+/// caller-provided buffer.
+///
+/// ```
+/// use core::net::Ipv4Addr;
+/// use ether_packet::igmp::IgmpV3QueryBuilder;
+///
+/// let mut buf = [0u8; 64];
+/// let mut query =
+///     IgmpV3QueryBuilder::new(&mut buf, Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::UNSPECIFIED, 100)
+///         .unwrap();
+/// query.robustness(2).query_interval(125);
+/// assert_eq!(query.finish(), 24 + 12);
+/// ```
+pub struct IgmpV3QueryBuilder<'a> {
+    w: Writer<'a>,
+    num_sources: u16,
+}
+
+impl<'a> IgmpV3QueryBuilder<'a> {
+    /// Starts a General Query if `group` is unspecified, a Group-Specific
+    /// Query otherwise, `max_resp_time` being in units of 1/10 second.
+    pub fn new(
+        buf: &'a mut [u8],
+        src: Ipv4Addr,
+        group: Ipv4Addr,
+        max_resp_time: u32,
+    ) -> Result<Self, BuildError> {
+        let mut w = Writer::new(buf);
+        put_ipv4_router_alert(&mut w, src, query_dst(group))?;
+        let msg = w.reserve(IgmpHdr::LEN + 4)?;
+        msg[0] = IgmpType::MembershipQuery as u8;
+        msg[1] = max_resp_code(max_resp_time);
+        msg[4..8].copy_from_slice(&group.octets());
+        let mut query = Self { w, num_sources: 0 };
+        query.robustness(2).query_interval(125);
+        Ok(query)
+    }
+
+    #[inline]
+    fn msg_mut(&mut self) -> &mut [u8] {
+        &mut self.w.written_mut()[IPV4_RA_HDR_LEN..]
+    }
+
+    /// Sets the **S** flag, telling routers to suppress their timer updates.
+    pub fn suppress_router_processing(&mut self, val: bool) -> &mut Self {
+        let msg = self.msg_mut();
+        msg[8] = (msg[8] & !0x08) | (val as u8) << 3;
+        self
+    }
+
+    /// Sets the Querier's Robustness Variable, 0 if it exceeds 7.
+    pub fn robustness(&mut self, qrv: u8) -> &mut Self {
+        let qrv = if qrv > 7 { 0 } else { qrv };
+        let msg = self.msg_mut();
+        msg[8] = (msg[8] & !0x07) | qrv;
+        self
+    }
+
+    /// Sets the Querier's Query Interval, in seconds, encoded like the Max
+    /// Resp Code.
+    pub fn query_interval(&mut self, secs: u32) -> &mut Self {
+        self.msg_mut()[9] = max_resp_code(secs);
+        self
+    }
+
+    /// Appends a source, making the query Group-and-Source-Specific.
+    pub fn source(&mut self, addr: Ipv4Addr) -> Result<&mut Self, BuildError> {
+        let num_sources = self
+            .num_sources
+            .checked_add(1)
+            .ok_or(BuildError::FieldOverflow)?;
+        if self.w.pos() + 4 > u16::MAX as usize {
+            return Err(BuildError::FieldOverflow);
+        }
+        self.w.put(&addr.octets())?;
+        self.num_sources = num_sources;
+        Ok(self)
+    }
+
+    /// Fills in the number of sources, the lengths and the checksums,
+    /// returning the length of the packet.
+    pub fn finish(mut self) -> usize {
+        self.w.set_u16(IPV4_RA_HDR_LEN + 10, self.num_sources);
+        finish_ipv4(&mut self.w)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::net::Ipv4Addr;
+
+    use super::{
+        max_resp_code, max_resp_time, write_igmpv2_query, GroupRecordType, GroupRecords,
+        IgmpV3QueryBuilder, IgmpV3ReportBuilder, ALL_SYSTEMS,
+    };
+    use crate::{builder::BuildError, checksum, header::Header, ip::Ipv4Hdr};
+
+    #[test]
+    fn test_report_roundtrip() {
+        let mut buf = [0u8; 64];
+        let mut report = IgmpV3ReportBuilder::new(&mut buf).unwrap();
+        report
+            .group_record(
+                GroupRecordType::ModeIsInclude,
+                Ipv4Addr::new(232, 1, 1, 1),
+                &[Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::new(10, 0, 0, 2)],
+            )
+            .unwrap()
+            .group_record_with_aux(
+                GroupRecordType::ModeIsExclude,
+                Ipv4Addr::new(239, 1, 1, 1),
+                &[],
+                &[0xaa, 0xbb],
+            )
+            .unwrap();
+        let len = report.finish();
+        assert_eq!(len, 8 + (8 + 8) + (8 + 4));
+        assert_eq!(checksum::checksum(&buf[..len]), 0);
+
+        let mut records = GroupRecords::new(&buf[..len]).unwrap();
+        let first = records.next().unwrap();
+        assert_eq!(
+            first.hdr.record_type(),
+            Some(GroupRecordType::ModeIsInclude)
+        );
+        assert!(first
+            .sources()
+            .eq([Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::new(10, 0, 0, 2)]));
+        let second = records.next().unwrap();
+        assert_eq!(second.hdr.group_addr, Ipv4Addr::new(239, 1, 1, 1));
+        assert_eq!(second.aux_data, &[0xaa, 0xbb, 0, 0]);
+        assert!(records.next().is_none());
+
+        let mut small = [0u8; 12];
+        let mut report = IgmpV3ReportBuilder::new(&mut small).unwrap();
+        assert_eq!(
+            report
+                .group_record(
+                    GroupRecordType::AllowNewSources,
+                    Ipv4Addr::new(232, 0, 0, 1),
+                    &[]
+                )
+                .err(),
+            Some(BuildError::BufferTooSmall)
+        );
+    }
+
+    #[test]
+    fn test_query_builder() {
+        let mut buf = [0u8; 64];
+        assert_eq!(max_resp_time(max_resp_code(1000)), 992);
+        assert_eq!(max_resp_code(u32::MAX), 0xff);
+        let src = Ipv4Addr::new(10, 0, 0, 1);
+        let group = Ipv4Addr::new(239, 1, 1, 1);
+        let mut query = IgmpV3QueryBuilder::new(&mut buf, src, group, 100).unwrap();
+        query.source(Ipv4Addr::new(10, 0, 0, 2)).unwrap();
+        let len = query.finish();
+        assert_eq!(len, 24 + 12 + 4);
+        let ip = Ipv4Hdr::from_bytes(&buf).unwrap();
+        assert_eq!((ip.hdrlen(), ip.ttl, ip.dst_addr), (24, 1, group));
+        assert_eq!(checksum::checksum(&buf[..24]), 0);
+        assert_eq!(&buf[20..24], &[0x94, 0x04, 0, 0]);
+        assert_eq!(checksum::checksum(&buf[24..len]), 0);
+        assert_eq!(&buf[32..36], &[0x02, 125, 0, 1]);
+
+        let len = write_igmpv2_query(&mut buf, src, Ipv4Addr::UNSPECIFIED, 100).unwrap();
+        assert_eq!(len, 32);
+        assert_eq!(Ipv4Hdr::from_bytes(&buf).unwrap().dst_addr, ALL_SYSTEMS);
+        assert_eq!(checksum::checksum(&buf[24..len]), 0);
+    }
+}
@@ -0,0 +1,426 @@
+//! In-band Network Telemetry
+//! ([INT v2.1](https://p4.org/p4-spec/docs/INT_v2_1.pdf)).
+//!
+//! With INT-MD, the source of a telemetry domain inserts an INT header
+//! into the packets, and every transit hop pushes its metadata onto a
+//! stack, as selected by the instruction bitmap. The header follows a shim
+//! after the UDP, TCP or GRE header, or is carried in a Geneve option of
+//! class [`OptionClass::Int`].
+//!
+//! [`OptionClass::Int`]: crate::geneve::OptionClass::Int
+
+use core::mem;
+
+use crate::{
+    geneve::{GeneveOption, OptionClass},
+    header::{impl_header, Header},
+    types::U16,
+};
+
+/// Type of the Geneve option carrying INT-MD.
+pub const GENEVE_OPTION_MD: u8 = 0x03;
+
+/// Kind of INT header following a shim.
+#[repr(u8)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum IntType {
+    /// Embedded data: the hops push their metadata onto a stack.
+    Md = 1,
+    /// Destination header, only processed by the sink.
+    Destination = 2,
+    /// eMbedded instructions: the hops report their metadata out of band.
+    Mx = 3,
+}
+
+impl TryFrom<u8> for IntType {
+    type Error = ();
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(IntType::Md),
+            2 => Ok(IntType::Destination),
+            3 => Ok(IntType::Mx),
+            _ => Err(()),
+        }
+    }
+}
+
+/// INT shim header, following the TCP, UDP or GRE header.
+/// ```text
+/// over TCP and UDP:
+/// +-------+---+---+---------------+-------------------------------+
+/// | Type  |NPT| R |    Length     |      Next protocol field      |
+/// +-------+---+---+---------------+-------------------------------+
+/// over GRE:
+/// +-------+-+-----+---------------+-------------------------------+
+/// | Type  |G|  R  |    Length     |         Next protocol         |
+/// +-------+-+-----+---------------+-------------------------------+
+/// ```
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct IntShimHdr {
+    pub type_flags: u8,
+    /// Length of the shim and the INT header, in 4-byte words.
+    pub len: u8,
+    /// Over TCP and UDP, its meaning depends on [`IntShimHdr::npt`]. Over
+    /// GRE, the EtherType of the payload.
+    pub next: U16,
+}
+
+impl IntShimHdr {
+    pub const LEN: usize = mem::size_of::<IntShimHdr>();
+
+    #[inline]
+    pub const fn int_type(&self) -> Option<IntType> {
+        match self.type_flags >> 4 {
+            1 => Some(IntType::Md),
+            2 => Some(IntType::Destination),
+            3 => Some(IntType::Mx),
+            _ => None,
+        }
+    }
+
+    /// **Next Protocol Type**, over TCP and UDP: 0 if the original payload
+    /// follows, 1 if the next protocol field holds the original UDP
+    /// destination port, 2 if it holds the IP protocol of the header
+    /// following the INT header.
+    #[inline]
+    pub const fn npt(&self) -> u8 {
+        (self.type_flags >> 2) & 0x3
+    }
+
+    /// **G**, over GRE: the original GRE header is kept after the INT
+    /// header.
+    #[inline]
+    pub const fn gre(&self) -> bool {
+        self.type_flags & 0x08 != 0
+    }
+
+    /// Length of the shim and the INT header in bytes.
+    #[inline]
+    pub const fn total_len(&self) -> usize {
+        self.len as usize * 4
+    }
+}
+
+impl_header!(IntShimHdr);
+
+/// Instruction bitmap of an INT header, bit 0 being the most significant.
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct Instructions(pub u16);
+
+impl Instructions {
+    /// Bit 0: switch ID.
+    pub const NODE_ID: u16 = 0x8000;
+    /// Bit 1: level 1 ingress and egress interface IDs, 16 bits each.
+    pub const L1_INTERFACES: u16 = 0x4000;
+    /// Bit 2: hop latency.
+    pub const HOP_LATENCY: u16 = 0x2000;
+    /// Bit 3: queue ID and occupancy.
+    pub const QUEUE: u16 = 0x1000;
+    /// Bit 4: ingress timestamp, 8 bytes.
+    pub const INGRESS_TIMESTAMP: u16 = 0x0800;
+    /// Bit 5: egress timestamp, 8 bytes.
+    pub const EGRESS_TIMESTAMP: u16 = 0x0400;
+    /// Bit 6: level 2 ingress and egress interface IDs, 32 bits each.
+    pub const L2_INTERFACES: u16 = 0x0200;
+    /// Bit 7: egress interface Tx utilization.
+    pub const TX_UTILIZATION: u16 = 0x0100;
+    /// Bit 8: buffer ID and occupancy.
+    pub const BUFFER: u16 = 0x0080;
+    /// Bit 15: checksum complement.
+    pub const CHECKSUM_COMPLEMENT: u16 = 0x0001;
+
+    #[inline]
+    pub const fn contains(&self, bits: u16) -> bool {
+        self.0 & bits == bits
+    }
+
+    /// Length of the metadata pushed by every hop, in 4-byte words.
+    /// Reserved instructions account for one word each.
+    #[inline]
+    pub const fn hop_ml(&self) -> usize {
+        let wide = Self::INGRESS_TIMESTAMP | Self::EGRESS_TIMESTAMP | Self::L2_INTERFACES;
+        (self.0.count_ones() + (self.0 & wide).count_ones()) as usize
+    }
+}
+
+/// INT-MD metadata header, followed by the metadata stack.
+/// ```text
+/// +-------+-+-+-+-----------------------+---------+---------------+
+/// |  Ver  |D|E|M|       Reserved        | Hop ML  | Remaining Hops|
+/// +-------+-+-+-+-----------------------+---------+---------------+
+/// |      Instruction Bitmap       |      Domain Specific ID       |
+/// +-------------------------------+-------------------------------+
+/// |   Domain Specific Instruction |      Domain Specific Flags    |
+/// +-------------------------------+-------------------------------+
+/// ```
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct IntMdHdr {
+    pub ver_flags: u8,
+    pub _reserved: u8,
+    /// Hop ML in the 5 least significant bits.
+    pub hop_ml: u8,
+    /// Number of hops still allowed to push their metadata.
+    pub remaining_hops: u8,
+    pub instructions: U16,
+    pub domain_id: U16,
+    pub ds_instructions: U16,
+    pub ds_flags: U16,
+}
+
+impl IntMdHdr {
+    pub const LEN: usize = mem::size_of::<IntMdHdr>();
+    pub const VERSION: u8 = 2;
+
+    #[inline]
+    pub const fn version(&self) -> u8 {
+        self.ver_flags >> 4
+    }
+
+    /// **D**: the sink drops the packet after extracting the telemetry.
+    #[inline]
+    pub const fn discard(&self) -> bool {
+        self.ver_flags & 0x08 != 0
+    }
+
+    /// **E**: a hop could not push its metadata, no hops remaining.
+    #[inline]
+    pub const fn hop_count_exceeded(&self) -> bool {
+        self.ver_flags & 0x04 != 0
+    }
+
+    /// **M**: a hop could not push its metadata, which would have exceeded
+    /// the MTU.
+    #[inline]
+    pub const fn mtu_exceeded(&self) -> bool {
+        self.ver_flags & 0x02 != 0
+    }
+
+    /// Length of the metadata pushed by every hop, in 4-byte words,
+    /// including domain-specific metadata.
+    #[inline]
+    pub const fn hop_ml(&self) -> usize {
+        (self.hop_ml & 0x1f) as usize
+    }
+
+    #[inline]
+    pub const fn instructions(&self) -> Instructions {
+        Instructions(self.instructions.to_bits())
+    }
+}
+
+impl_header!(
+    IntMdHdr,
+    validate = |b: &[u8]| b[0] >> 4 == IntMdHdr::VERSION
+);
+
+/// Metadata pushed by a hop, the fields not requested by the instruction
+/// bitmap being `None`.
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct HopMetadata {
+    pub node_id: Option<u32>,
+    /// Level 1 ingress and egress interface IDs.
+    pub l1_interfaces: Option<(u16, u16)>,
+    pub hop_latency: Option<u32>,
+    /// Queue ID and occupancy, 24 bits.
+    pub queue: Option<(u8, u32)>,
+    pub ingress_timestamp: Option<u64>,
+    pub egress_timestamp: Option<u64>,
+    /// Level 2 ingress and egress interface IDs.
+    pub l2_interfaces: Option<(u32, u32)>,
+    pub tx_utilization: Option<u32>,
+    /// Buffer ID and occupancy, 24 bits.
+    pub buffer: Option<(u8, u32)>,
+    pub checksum_complement: Option<u32>,
+}
+
+impl HopMetadata {
+    /// Decodes the metadata of a hop, `None` if it is shorter than required
+    /// by `instructions`.
+    pub fn decode(instructions: Instructions, data: &[u8]) -> Option<Self> {
+        let mut meta = HopMetadata::default();
+        let mut pos = 0;
+        for bit in (0..16).rev() {
+            let instruction = 1 << bit;
+            if !instructions.contains(instruction) {
+                continue;
+            }
+            let wide = Instructions(instruction).hop_ml() == 2;
+            let len = if wide { 8 } else { 4 };
+            let bytes = data.get(pos..pos + len)?;
+            pos += len;
+            let word = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+            let low = word & 0xff_ffff;
+            match instruction {
+                Instructions::NODE_ID => meta.node_id = Some(word),
+                Instructions::L1_INTERFACES => {
+                    meta.l1_interfaces = Some(((word >> 16) as u16, word as u16))
+                }
+                Instructions::HOP_LATENCY => meta.hop_latency = Some(word),
+                Instructions::QUEUE => meta.queue = Some(((word >> 24) as u8, low)),
+                Instructions::INGRESS_TIMESTAMP => {
+                    meta.ingress_timestamp = Some(u64::from_be_bytes(bytes.try_into().ok()?))
+                }
+                Instructions::EGRESS_TIMESTAMP => {
+                    meta.egress_timestamp = Some(u64::from_be_bytes(bytes.try_into().ok()?))
+                }
+                Instructions::L2_INTERFACES => {
+                    let egress = u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+                    meta.l2_interfaces = Some((word, egress))
+                }
+                Instructions::TX_UTILIZATION => meta.tx_utilization = Some(word),
+                Instructions::BUFFER => meta.buffer = Some(((word >> 24) as u8, low)),
+                Instructions::CHECKSUM_COMPLEMENT => meta.checksum_complement = Some(word),
+                _ => {}
+            }
+        }
+        Some(meta)
+    }
+}
+
+/// An INT-MD header and its metadata stack.
+///
+/// ```
+/// use ether_packet::int::{IntMd, Instructions};
+///
+/// #[rustfmt::skip]
+/// let data = [
+///     // Shim over UDP: INT-MD, 6 words.
+///     0x10, 6, 0, 0,
+///     // Version 2, hop ML 1, 5 hops remaining, node ID.
+///     0x20, 0, 1, 5, 0x80, 0, 0, 0, 0, 0, 0, 0,
+///     // The stack, most recent hop first.
+///     0, 0, 0, 2, 0, 0, 0, 1,
+/// ];
+/// let (md, rest) = IntMd::from_shim(&data).unwrap();
+/// assert!(rest.is_empty());
+/// assert!(md.hdr().instructions().contains(Instructions::NODE_ID));
+/// let nodes = md.hops().map(|hop| hop.node_id.unwrap());
+/// assert!(nodes.eq([2, 1]));
+/// ```
+#[derive(Debug, Copy, Clone)]
+pub struct IntMd<'a> {
+    hdr: &'a IntMdHdr,
+    stack: &'a [u8],
+}
+
+impl<'a> IntMd<'a> {
+    /// Parses an INT-MD header followed by the metadata stack, which spans
+    /// the rest of `data`.
+    pub fn parse(data: &'a [u8]) -> Option<Self> {
+        let hdr = IntMdHdr::from_bytes(data)?;
+        Some(Self {
+            hdr,
+            stack: &data[IntMdHdr::LEN..],
+        })
+    }
+
+    /// Parses the shim following a TCP, UDP or GRE header, then the INT-MD
+    /// header, returning the data following them.
+    pub fn from_shim(data: &'a [u8]) -> Option<(Self, &'a [u8])> {
+        let shim = IntShimHdr::from_bytes(data)?;
+        if shim.int_type() != Some(IntType::Md) || shim.total_len() < IntShimHdr::LEN {
+            return None;
+        }
+        let (int, rest) = data.split_at_checked(shim.total_len())?;
+        Some((Self::parse(&int[IntShimHdr::LEN..])?, rest))
+    }
+
+    /// Parses the INT-MD header carried by a Geneve option.
+    pub fn from_geneve(option: &GeneveOption<'a>) -> Option<Self> {
+        if option.class != OptionClass::Int as u16 || option.option_type & 0x7f != GENEVE_OPTION_MD
+        {
+            return None;
+        }
+        Self::parse(option.data)
+    }
+
+    #[inline]
+    pub fn hdr(&self) -> &'a IntMdHdr {
+        self.hdr
+    }
+
+    /// Raw metadata stack.
+    #[inline]
+    pub fn stack(&self) -> &'a [u8] {
+        self.stack
+    }
+
+    /// Metadata of the hops, the most recent one first.
+    #[inline]
+    pub fn hops(&self) -> HopStack<'a> {
+        HopStack {
+            instructions: self.hdr.instructions(),
+            hop_len: self.hdr.hop_ml() * 4,
+            data: self.stack,
+        }
+    }
+}
+
+/// Iterator over the metadata stack of an INT-MD header.
+#[derive(Debug, Clone)]
+pub struct HopStack<'a> {
+    instructions: Instructions,
+    hop_len: usize,
+    data: &'a [u8],
+}
+
+impl Iterator for HopStack<'_> {
+    type Item = HopMetadata;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.hop_len == 0 {
+            return None;
+        }
+        let (hop, rest) = self.data.split_at_checked(self.hop_len)?;
+        self.data = rest;
+        HopMetadata::decode(self.instructions, hop)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Instructions, IntMd, GENEVE_OPTION_MD};
+    use crate::geneve::{GeneveOption, OptionClass};
+
+    #[test]
+    fn test_int_md() {
+        let instructions = Instructions(
+            Instructions::NODE_ID | Instructions::QUEUE | Instructions::INGRESS_TIMESTAMP,
+        );
+        assert_eq!(instructions.hop_ml(), 4);
+
+        #[rustfmt::skip]
+        let data = [
+            0x20, 0, 4, 6, 0x98, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 7, 3, 0, 0x10, 0, 0, 0, 0, 0, 0, 0, 0x30, 0x39,
+            0, 0, 0, 8, 1, 0, 0, 0x20, 0, 0, 0, 0, 0, 0, 0x30, 0x38,
+        ];
+        let option = GeneveOption {
+            class: OptionClass::Int as u16,
+            option_type: GENEVE_OPTION_MD,
+            data: &data,
+        };
+        let md = IntMd::from_geneve(&option).unwrap();
+        assert_eq!(md.hdr().instructions(), instructions);
+        assert_eq!(md.hdr().remaining_hops, 6);
+
+        let mut hops = md.hops();
+        let hop = hops.next().unwrap();
+        assert_eq!(hop.node_id, Some(7));
+        assert_eq!(hop.queue, Some((3, 0x1000)));
+        assert_eq!(hop.ingress_timestamp, Some(12345));
+        assert_eq!(hop.egress_timestamp, None);
+        assert_eq!(hops.next().unwrap().queue, Some((1, 0x20)));
+        assert_eq!(hops.next(), None);
+
+        // Truncated stack.
+        assert_eq!(IntMd::parse(&data[..20]).unwrap().hops().count(), 0);
+    }
+}
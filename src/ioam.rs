@@ -0,0 +1,372 @@
+//! In-situ OAM trace options
+//! ([RFC 9197](https://datatracker.ietf.org/doc/html/rfc9197)), carried in
+//! the IPv6 Hop-by-Hop options header
+//! ([RFC 9486](https://datatracker.ietf.org/doc/html/rfc9486)).
+//!
+//! Every IOAM node on the path records the data selected by the trace type
+//! into the node data list, the most recent node first. With the
+//! pre-allocated trace, the encapsulating node reserves room for the whole
+//! path and the nodes fill it from the end; with the incremental trace,
+//! every node inserts its data after the trace header.
+
+use core::mem;
+
+use crate::{
+    header::{impl_header, Header},
+    ip::v6::Ipv6Option,
+    types::U16,
+};
+
+/// Type of the Hop-by-Hop option carrying IOAM.
+pub const IOAM_OPTION: u8 = 0x31;
+
+/// IOAM option types.
+#[repr(u8)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum IoamOptionType {
+    PreallocatedTrace = 0,
+    IncrementalTrace = 1,
+    ProofOfTransit = 2,
+    EdgeToEdge = 3,
+    /// Direct export ([RFC 9326](https://datatracker.ietf.org/doc/html/rfc9326)).
+    DirectExport = 4,
+}
+
+impl TryFrom<u8> for IoamOptionType {
+    type Error = ();
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(IoamOptionType::PreallocatedTrace),
+            1 => Ok(IoamOptionType::IncrementalTrace),
+            2 => Ok(IoamOptionType::ProofOfTransit),
+            3 => Ok(IoamOptionType::EdgeToEdge),
+            4 => Ok(IoamOptionType::DirectExport),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Data fields recorded by every node, bit 0 being the most significant
+/// of the 24 bits.
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct TraceType(pub u32);
+
+impl TraceType {
+    /// Bit 0: hop limit and 24-bit node ID.
+    pub const HOP_LIM_NODE_ID: u32 = 1 << 23;
+    /// Bit 1: 16-bit ingress and egress interface IDs.
+    pub const INTERFACES: u32 = 1 << 22;
+    /// Bit 2: timestamp seconds.
+    pub const TIMESTAMP_SECS: u32 = 1 << 21;
+    /// Bit 3: timestamp fraction.
+    pub const TIMESTAMP_FRAC: u32 = 1 << 20;
+    /// Bit 4: transit delay.
+    pub const TRANSIT_DELAY: u32 = 1 << 19;
+    /// Bit 5: namespace-specific data.
+    pub const NAMESPACE_DATA: u32 = 1 << 18;
+    /// Bit 6: queue depth.
+    pub const QUEUE_DEPTH: u32 = 1 << 17;
+    /// Bit 7: checksum complement.
+    pub const CHECKSUM_COMPLEMENT: u32 = 1 << 16;
+    /// Bit 8: hop limit and 56-bit node ID.
+    pub const HOP_LIM_NODE_ID_WIDE: u32 = 1 << 15;
+    /// Bit 9: 32-bit ingress and egress interface IDs.
+    pub const INTERFACES_WIDE: u32 = 1 << 14;
+    /// Bit 10: wide namespace-specific data.
+    pub const NAMESPACE_DATA_WIDE: u32 = 1 << 13;
+    /// Bit 11: buffer occupancy.
+    pub const BUFFER_OCCUPANCY: u32 = 1 << 12;
+    /// Bit 22: variable-length opaque state snapshot, following the other
+    /// fields.
+    pub const OPAQUE_STATE: u32 = 1 << 1;
+
+    #[inline]
+    pub const fn contains(&self, bits: u32) -> bool {
+        self.0 & bits == bits
+    }
+
+    /// Length of the data recorded by every node, in 4-byte words,
+    /// excluding the opaque state snapshot. Undefined bits account for one
+    /// word each.
+    #[inline]
+    pub const fn node_len(&self) -> usize {
+        let fixed = self.0 & 0xff_fffc;
+        let wide = Self::HOP_LIM_NODE_ID_WIDE | Self::INTERFACES_WIDE | Self::NAMESPACE_DATA_WIDE;
+        (fixed.count_ones() + (fixed & wide).count_ones()) as usize
+    }
+}
+
+/// Header of the pre-allocated and incremental trace options, followed by
+/// the node data list.
+/// ```text
+/// +-------------------------------+---------+-------+-------------+
+/// |         Namespace-ID          | NodeLen | Flags | RemainingLen|
+/// +-------------------------------+---------+-------+-------------+
+/// |                IOAM-Trace-Type                |   Reserved    |
+/// +-----------------------------------------------+---------------+
+/// ```
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct IoamTraceHdr {
+    pub namespace_id: U16,
+    pub node_len_flags: U16,
+    pub trace_type: [u8; 3],
+    pub _reserved: u8,
+}
+
+impl IoamTraceHdr {
+    pub const LEN: usize = mem::size_of::<IoamTraceHdr>();
+
+    /// Length of the data recorded by every node, in 4-byte words,
+    /// excluding the opaque state snapshot.
+    #[inline]
+    pub const fn node_len(&self) -> usize {
+        (self.node_len_flags.to_bits() >> 11) as usize
+    }
+
+    /// **O**: a node could not record its data, the list being full.
+    #[inline]
+    pub const fn overflow(&self) -> bool {
+        self.node_len_flags.to_bits() & 0x0400 != 0
+    }
+
+    /// Room left in the node data list, in 4-byte words.
+    #[inline]
+    pub const fn remaining_len(&self) -> usize {
+        (self.node_len_flags.to_bits() & 0x7f) as usize
+    }
+
+    #[inline]
+    pub const fn trace_type(&self) -> TraceType {
+        let [a, b, c] = self.trace_type;
+        TraceType(u32::from_be_bytes([0, a, b, c]))
+    }
+}
+
+impl_header!(IoamTraceHdr);
+
+/// Data recorded by a node, the fields not selected by the trace type
+/// being `None`.
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct NodeData<'a> {
+    pub hop_limit: Option<u8>,
+    /// Short or wide node ID, the wide one taking precedence.
+    pub node_id: Option<u64>,
+    /// Short or wide ingress interface ID, the wide one taking precedence.
+    pub ingress_if: Option<u32>,
+    pub egress_if: Option<u32>,
+    pub timestamp_secs: Option<u32>,
+    pub timestamp_frac: Option<u32>,
+    /// Time spent in the node, in nanoseconds.
+    pub transit_delay: Option<u32>,
+    pub namespace_data: Option<u32>,
+    pub queue_depth: Option<u32>,
+    pub checksum_complement: Option<u32>,
+    pub namespace_data_wide: Option<u64>,
+    pub buffer_occupancy: Option<u32>,
+    /// Opaque state snapshot: schema ID and data.
+    pub opaque_state: Option<(u32, &'a [u8])>,
+}
+
+impl<'a> NodeData<'a> {
+    /// Decodes the data recorded by a node at the start of `data`,
+    /// returning it along with its length.
+    pub fn decode(trace_type: TraceType, data: &'a [u8]) -> Option<(Self, usize)> {
+        let mut node = NodeData::default();
+        let mut pos = 0;
+        // In the order of the bits, so that the wide fields come last.
+        for bit in (2..24).rev() {
+            let field = 1 << bit;
+            if !trace_type.contains(field) {
+                continue;
+            }
+            let len = TraceType(field).node_len() * 4;
+            let bytes = data.get(pos..pos + len)?;
+            pos += len;
+            let word = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+            match field {
+                TraceType::HOP_LIM_NODE_ID => {
+                    node.hop_limit = Some(bytes[0]);
+                    node.node_id = Some((word & 0xff_ffff) as u64);
+                }
+                TraceType::INTERFACES => {
+                    node.ingress_if = Some(word >> 16);
+                    node.egress_if = Some(word & 0xffff);
+                }
+                TraceType::TIMESTAMP_SECS => node.timestamp_secs = Some(word),
+                TraceType::TIMESTAMP_FRAC => node.timestamp_frac = Some(word),
+                TraceType::TRANSIT_DELAY => node.transit_delay = Some(word),
+                TraceType::NAMESPACE_DATA => node.namespace_data = Some(word),
+                TraceType::QUEUE_DEPTH => node.queue_depth = Some(word),
+                TraceType::CHECKSUM_COMPLEMENT => node.checksum_complement = Some(word),
+                TraceType::HOP_LIM_NODE_ID_WIDE => {
+                    let wide = u64::from_be_bytes(bytes.try_into().ok()?);
+                    node.hop_limit = Some(bytes[0]);
+                    node.node_id = Some(wide & 0xff_ffff_ffff_ffff);
+                }
+                TraceType::INTERFACES_WIDE => {
+                    node.ingress_if = Some(word);
+                    node.egress_if = Some(u32::from_be_bytes(bytes[4..].try_into().ok()?));
+                }
+                TraceType::NAMESPACE_DATA_WIDE => {
+                    node.namespace_data_wide = Some(u64::from_be_bytes(bytes.try_into().ok()?))
+                }
+                TraceType::BUFFER_OCCUPANCY => node.buffer_occupancy = Some(word),
+                _ => {}
+            }
+        }
+        if trace_type.contains(TraceType::OPAQUE_STATE) {
+            let hdr = data.get(pos..pos + 4)?;
+            let len = hdr[0] as usize * 4;
+            let schema_id = u32::from_be_bytes([0, hdr[1], hdr[2], hdr[3]]);
+            let state = data.get(pos + 4..pos + 4 + len)?;
+            node.opaque_state = Some((schema_id, state));
+            pos += 4 + len;
+        }
+        Some((node, pos))
+    }
+}
+
+/// A pre-allocated or incremental trace option.
+///
+/// ```
+/// use ether_packet::{
+///     ioam::{IoamOptionType, IoamTrace},
+///     ip::v6::Ipv6Options,
+/// };
+///
+/// #[rustfmt::skip]
+/// let options = [
+///     // IOAM incremental trace, namespace 1, hop limit and node ID.
+///     0x31, 18, 0, 1, 0, 1, 0x08, 0x05, 0x80, 0, 0, 0,
+///     // Two nodes.
+///     62, 0, 0, 2, 63, 0, 0, 1,
+/// ];
+/// let option = Ipv6Options::new(&options).next().unwrap();
+/// let trace = IoamTrace::parse(&option).unwrap();
+/// assert_eq!(trace.option_type(), IoamOptionType::IncrementalTrace);
+/// let nodes = trace.nodes().map(|node| (node.hop_limit.unwrap(), node.node_id.unwrap()));
+/// assert!(nodes.eq([(62, 2), (63, 1)]));
+/// ```
+#[derive(Debug, Copy, Clone)]
+pub struct IoamTrace<'a> {
+    option_type: IoamOptionType,
+    hdr: &'a IoamTraceHdr,
+    data: &'a [u8],
+}
+
+impl<'a> IoamTrace<'a> {
+    /// Parses a Hop-by-Hop option, `None` if it is not an IOAM trace
+    /// option.
+    pub fn parse(option: &Ipv6Option<'a>) -> Option<Self> {
+        if option.option_type != IOAM_OPTION {
+            return None;
+        }
+        let option_type = IoamOptionType::try_from(*option.data.get(1)?).ok()?;
+        if !matches!(
+            option_type,
+            IoamOptionType::PreallocatedTrace | IoamOptionType::IncrementalTrace
+        ) {
+            return None;
+        }
+        let data = &option.data[2..];
+        let hdr = IoamTraceHdr::from_bytes(data)?;
+        Some(Self {
+            option_type,
+            hdr,
+            data: &data[IoamTraceHdr::LEN..],
+        })
+    }
+
+    #[inline]
+    pub fn option_type(&self) -> IoamOptionType {
+        self.option_type
+    }
+
+    #[inline]
+    pub fn hdr(&self) -> &'a IoamTraceHdr {
+        self.hdr
+    }
+
+    /// The recorded data of the nodes, the most recent one first.
+    pub fn nodes(&self) -> TraceNodes<'a> {
+        let data = match self.option_type {
+            // The nodes fill the list from its end.
+            IoamOptionType::PreallocatedTrace => {
+                self.data.get(self.hdr.remaining_len() * 4..).unwrap_or(&[])
+            }
+            _ => self.data,
+        };
+        TraceNodes {
+            trace_type: self.hdr.trace_type(),
+            data,
+        }
+    }
+}
+
+/// Iterator over the node data list of a trace option.
+#[derive(Debug, Clone)]
+pub struct TraceNodes<'a> {
+    trace_type: TraceType,
+    data: &'a [u8],
+}
+
+impl<'a> Iterator for TraceNodes<'a> {
+    type Item = NodeData<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match NodeData::decode(self.trace_type, self.data) {
+            Some((node, len)) if len > 0 => {
+                self.data = &self.data[len..];
+                Some(node)
+            }
+            _ => {
+                self.data = &[];
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IoamOptionType, IoamTrace, TraceType};
+    use crate::ip::v6::Ipv6Options;
+
+    #[test]
+    fn test_preallocated_trace() {
+        let trace_type = TraceType(
+            TraceType::HOP_LIM_NODE_ID_WIDE | TraceType::TIMESTAMP_SECS | TraceType::OPAQUE_STATE,
+        );
+        assert_eq!(trace_type.node_len(), 3);
+
+        #[rustfmt::skip]
+        let options = [
+            // PadN.
+            1, 2, 0, 0,
+            // Room for one node left, of 4 words without the opaque state.
+            0x31, 42, 0, 0, 0, 7, 0x18, 0x04, 0x20, 0x80, 0x02, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0x65, 0x00, 0x00, 0x01, 63, 0, 0, 0, 0, 0, 0, 9, 0, 0, 0, 42,
+        ];
+        let mut options = Ipv6Options::new(&options);
+        let trace = IoamTrace::parse(&options.next().unwrap()).unwrap();
+        assert_eq!(options.next(), None);
+        assert_eq!(trace.option_type(), IoamOptionType::PreallocatedTrace);
+        assert_eq!(trace.hdr().namespace_id.to_bits(), 7);
+        assert_eq!(trace.hdr().node_len(), 3);
+        assert_eq!(trace.hdr().trace_type(), trace_type);
+
+        let mut nodes = trace.nodes();
+        let node = nodes.next().unwrap();
+        assert_eq!(node.hop_limit, Some(63));
+        assert_eq!(node.node_id, Some(9));
+        assert_eq!(node.timestamp_secs, Some(0x6500_0001));
+        assert_eq!(node.opaque_state, Some((42, &[][..])));
+        assert_eq!(nodes.next(), None);
+    }
+}
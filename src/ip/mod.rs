@@ -1,5 +1,5 @@
-use v4::Ipv4Hdr;
-use v6::Ipv6Hdr;
+pub use v4::Ipv4Hdr;
+pub use v6::Ipv6Hdr;
 
 pub mod v4;
 pub mod v6;
@@ -11,12 +11,11 @@ pub enum IpHdr {
     V6(Ipv6Hdr),
 }
 
-
 /// Protocol which is encapsulated in the IPv4 packet.
 /// <https://www.iana.org/assignments/protocol-numbers/protocol-numbers.xhtml>
 #[repr(u8)]
-#[derive(PartialEq, Eq, Debug, Copy, Clone)]
-#[cfg_attr(features = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 pub enum IpProto {
     /// IPv6 Hop-by-Hop Option
     HopOpt = 0,
@@ -314,4 +313,25 @@ pub enum IpProto {
     Test2 = 254,
     /// Reserved
     Reserved = 255,
-}
\ No newline at end of file
+}
+
+impl IpProto {
+    /// Converts a raw protocol number into an [`IpProto`], returning `None`
+    /// for the unassigned range 145-252.
+    #[inline]
+    pub const fn from_u8(value: u8) -> Option<IpProto> {
+        match value {
+            // SAFETY: `IpProto` is `repr(u8)` and every value in these ranges
+            // is a declared discriminant.
+            0..=144 | 253..=255 => Some(unsafe { core::mem::transmute::<u8, IpProto>(value) }),
+            _ => None,
+        }
+    }
+}
+
+impl TryFrom<u8> for IpProto {
+    type Error = ();
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Self::from_u8(value).ok_or(())
+    }
+}
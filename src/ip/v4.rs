@@ -1,6 +1,11 @@
 use core::{mem, net::Ipv4Addr};
 
-use crate::{bitfield::BitfieldUnit, types::U16};
+use crate::{
+    bitfield::BitfieldUnit,
+    checksum,
+    header::{impl_header, Header},
+    types::U16,
+};
 
 use super::IpProto;
 
@@ -95,8 +100,8 @@ pub struct Ipv4Hdr {
     /// IP Flags: These 3 bits are used for fragmentation:
     /// - The first bit is always set to 0.
     /// - The second bit is called the DF (Don’t Fragment) bit and indicates that this packet should not be fragmented.
-    /// 当DF位被设置为1时，表示路由器不能对数据包进行分段处理。如果数据包由于不能被分段而未能被转发，那么路由器将丢弃该数据包并向源点发送错误消息。
-    /// 这一功能可以在网络上用于测试MTU值。可以使用Ping工具可以对DF位进行设置测试。
+    ///   当DF位被设置为1时，表示路由器不能对数据包进行分段处理。如果数据包由于不能被分段而未能被转发，那么路由器将丢弃该数据包并向源点发送错误消息。
+    ///   这一功能可以在网络上用于测试MTU值。可以使用Ping工具可以对DF位进行设置测试。
     /// - The third bit is called the MF (More Fragments) bit and is set on all fragmented packets except the last one.
     ///
     /// Fragment Offset: this 13 bit field specifies the position of the fragment in the original fragmented IP packet.
@@ -130,13 +135,43 @@ pub struct Ipv4Hdr {
 impl Ipv4Hdr {
     pub const LEN: usize = mem::size_of::<Ipv4Hdr>();
 
+    /// Parses a header from its wire representation, usable in const
+    /// contexts so that header templates and test vectors are checked at
+    /// build time:
+    ///
+    /// ```
+    /// use ether_packet::ip::{IpProto, Ipv4Hdr};
+    ///
+    /// const HDR: Ipv4Hdr = Ipv4Hdr::parse_const(&[
+    ///     0x45, 0, 0, 20, 0, 0, 0x40, 0, 64, 17, 0, 0, 10, 0, 0, 1, 10, 0, 0, 2,
+    /// ]);
+    /// assert_eq!(HDR.hdrlen(), 20);
+    /// assert!(matches!(HDR.proto, IpProto::Udp));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the version is not 4, the IHL is smaller than 5 or the
+    /// protocol number is unassigned.
+    pub const fn parse_const(bytes: &[u8; Self::LEN]) -> Self {
+        assert!(bytes[0] >> 4 == 4, "IPv4 header: version is not 4");
+        assert!(bytes[0] & 0x0f >= 5, "IPv4 header: IHL is smaller than 5");
+        assert!(
+            IpProto::from_u8(bytes[9]).is_some(),
+            "IPv4 header: unassigned protocol number"
+        );
+        // SAFETY: `Ipv4Hdr` is packed and the only field with invalid bit
+        // patterns (`proto`) was validated above.
+        unsafe { mem::transmute::<[u8; Self::LEN], Self>(*bytes) }
+    }
+
     #[inline]
-    pub fn ihl(&self) -> u8 {
+    pub const fn ihl(&self) -> u8 {
         self._bitfield_1.get(0usize, 4u8) as u8
     }
 
     #[inline]
-    pub fn set_ihl(&mut self, val: u8) {
+    pub const fn set_ihl(&mut self, val: u8) {
         self._bitfield_1.set(0usize, 4u8, val as u64)
     }
 
@@ -144,37 +179,43 @@ impl Ipv4Hdr {
     /// - 0100表示IP版本4（IPv4）
     /// - 0110表示IP版本6（IPv6）
     #[inline]
-    pub fn version(&self) -> u8 {
+    pub const fn version(&self) -> u8 {
         self._bitfield_1.get(4usize, 4u8) as u8
     }
 
     #[inline]
-    pub fn set_version(&mut self, val: u8) {
+    pub const fn set_version(&mut self, val: u8) {
         self._bitfield_1.set(4usize, 4u8, val as u64)
     }
 
     #[inline]
-    pub fn new_bitfield_1(ihl: u8, version: u8) -> BitfieldUnit<[u8; 1usize]> {
-        let mut bitfield_unit: BitfieldUnit<[u8; 1usize]> = Default::default();
+    pub const fn new_bitfield_1(ihl: u8, version: u8) -> BitfieldUnit<[u8; 1usize]> {
+        let mut bitfield_unit = BitfieldUnit::new([0u8; 1usize]);
         bitfield_unit.set(0usize, 4u8, ihl as u64);
         bitfield_unit.set(4usize, 4u8, version as u64);
         bitfield_unit
     }
 
     #[inline]
-    pub fn hdrlen(&self) -> usize {
+    pub const fn hdrlen(&self) -> usize {
         self.ihl() as usize * 4
     }
 
+    /// Flags and fragment offset, in host byte order.
+    #[inline]
+    const fn frag_off_bits(&self) -> u16 {
+        ((self.frag_off.get(0, 8) << 8) | self.frag_off.get(8, 8)) as u16
+    }
+
     /// is **DONT_FRAGMENT** flag setted
     #[inline]
-    pub fn dont_fragment(&self) -> bool {
-        self.frag_off.get_bit(15)
+    pub const fn dont_fragment(&self) -> bool {
+        self.frag_off_bits() & 0x4000 != 0
     }
     /// is **MORE_FRAGMENTS** flag setted
     #[inline]
-    pub fn more_fragments(&self) -> bool {
-        self.frag_off.get_bit(14)
+    pub const fn more_fragments(&self) -> bool {
+        self.frag_off_bits() & 0x2000 != 0
     }
 
     /// The frag_off portion of the header consists of:
@@ -186,25 +227,45 @@ impl Ipv4Hdr {
     ///  If "More fragments" or the offset is nonzero, then this is an IP
     ///  fragment (RFC791).
     #[inline]
-    pub fn is_fragment(&self) -> bool {
-        // 0x3FFF
-        self.frag_off.get(0, 14) > 0
+    pub const fn is_fragment(&self) -> bool {
+        self.frag_off_bits() & 0x3fff != 0
     }
 
     #[inline]
-    pub fn is_not_first_fragment(&self) -> bool {
+    pub const fn is_not_first_fragment(&self) -> bool {
         /* Ignore "More fragments" bit to catch all fragments but the first */
-        // 0x1FFF
-        self.frag_off.get(0, 13) > 0
+        self.frag_off_bits() & 0x1fff != 0
     }
 
     #[inline]
-    pub fn has_l4_header(&self) -> bool {
+    pub const fn has_l4_header(&self) -> bool {
         /* Simply a reverse of ipv4_is_not_first_fragment to avoid double negative. */
         !self.is_not_first_fragment()
     }
+
+    /// Computes the checksum of the header, ignoring the current value of
+    /// `check`. Options are not part of `Ipv4Hdr`: the checksum of headers
+    /// with options is computed with [`checksum::checksum`] over the
+    /// `hdrlen()` bytes.
+    #[inline]
+    pub fn compute_checksum(&self) -> u16 {
+        let mut hdr = *self;
+        hdr.check = U16::from_bits(0);
+        checksum::checksum(hdr.as_bytes())
+    }
+
+    /// Whether `check` is the checksum of the header, options excluded.
+    #[inline]
+    pub fn verify_checksum(&self) -> bool {
+        checksum::checksum(self.as_bytes()) == 0
+    }
 }
 
+impl_header!(
+    Ipv4Hdr,
+    validate = |b: &[u8]| IpProto::from_u8(b[9]).is_some()
+);
+
 /// The option-type octet is viewed as having 3 fields:
 /// ```text
 ///    1 bit   copied flag,
@@ -218,7 +279,7 @@ pub struct Ipv4HdrOptionType {
 }
 
 impl Ipv4HdrOptionType {
-    pub fn new(value: u8) -> Ipv4HdrOptionType {
+    pub const fn new(value: u8) -> Ipv4HdrOptionType {
         Ipv4HdrOptionType {
             octets: BitfieldUnit::new([value]),
         }
@@ -228,7 +289,7 @@ impl Ipv4HdrOptionType {
     /// fragments on fragmentation.
     /// - 0 = not copied
     /// - 1 = copied
-    pub fn copied_flag(&self) -> bool {
+    pub const fn copied_flag(&self) -> bool {
         self.octets.get_bit(7)
     }
 
@@ -240,7 +301,7 @@ impl Ipv4HdrOptionType {
     ///     3 = reserved for future use
     /// ```
     #[inline]
-    pub fn option_class(&self) -> u8 {
+    pub const fn option_class(&self) -> u8 {
         self.octets.get(5, 2) as u8
     }
 
@@ -269,40 +330,40 @@ impl Ipv4HdrOptionType {
     ///    2     4     var.  Internet Timestamp.
     /// ```
     #[inline]
-    pub fn option_number(&self) -> u8 {
+    pub const fn option_number(&self) -> u8 {
         self.octets.get(0, 5) as u8
     }
 
     #[inline]
-    pub fn is_end_of_option_list(&self) -> bool {
+    pub const fn is_end_of_option_list(&self) -> bool {
         self.option_number() == 0 && self.option_class() == 0
     }
     #[inline]
-    pub fn is_no_operation(&self) -> bool {
+    pub const fn is_no_operation(&self) -> bool {
         self.option_number() == 1 && self.option_class() == 0
     }
     #[inline]
-    pub fn is_security(&self) -> bool {
+    pub const fn is_security(&self) -> bool {
         self.option_number() == 2 && self.option_class() == 0
     }
     #[inline]
-    pub fn is_loose_source_routing(&self) -> bool {
+    pub const fn is_loose_source_routing(&self) -> bool {
         self.option_number() == 3 && self.option_class() == 0
     }
     #[inline]
-    pub fn is_strict_source_routing(&self) -> bool {
+    pub const fn is_strict_source_routing(&self) -> bool {
         self.option_number() == 9 && self.option_class() == 0
     }
     #[inline]
-    pub fn is_record_route(&self) -> bool {
+    pub const fn is_record_route(&self) -> bool {
         self.option_number() == 7 && self.option_class() == 0
     }
     #[inline]
-    pub fn is_stream_id(&self) -> bool {
+    pub const fn is_stream_id(&self) -> bool {
         self.option_number() == 8 && self.option_class() == 0
     }
     #[inline]
-    pub fn is_internet_timestamp(&self) -> bool {
+    pub const fn is_internet_timestamp(&self) -> bool {
         self.option_number() == 4 && self.option_class() == 2
     }
 }
@@ -321,9 +382,8 @@ mod tests {
             0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 127, 0, 0, 1, 127, 0, 0, 2,
         ];
 
-        let ipv4_header: Ipv4Hdr = unsafe {
-            mem::transmute::<[u8; Ipv4Hdr::LEN], _>(expected_header_bytes.try_into().unwrap())
-        };
+        let ipv4_header: Ipv4Hdr =
+            unsafe { mem::transmute::<[u8; Ipv4Hdr::LEN], _>(expected_header_bytes) };
         assert_eq!(ipv4_header.src_addr, Ipv4Addr::new(127, 0, 0, 1));
         assert_eq!(ipv4_header.dst_addr, Ipv4Addr::new(127, 0, 0, 2));
     }
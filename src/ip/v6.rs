@@ -1,6 +1,11 @@
 use core::{mem, net::Ipv6Addr};
 
-use crate::{bitfield::BitfieldUnit, types::{U16, U32}};
+use crate::{
+    bitfield::BitfieldUnit,
+    flow::FlowKey,
+    header::impl_header,
+    types::{U16, U32},
+};
 
 use super::IpProto;
 
@@ -28,8 +33,8 @@ use super::IpProto;
 ///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
 /// ```
 #[repr(C, packed)]
-#[derive(Copy, Clone)]
-#[cfg_attr(features = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 pub struct Ipv6Hdr {
     /// **Version** 4-bit Internet Protocol version number = 6.
     ///
@@ -72,42 +77,140 @@ pub struct Ipv6Hdr {
 impl Ipv6Hdr {
     pub const LEN: usize = mem::size_of::<Ipv6Hdr>();
 
+    /// Parses a header from its wire representation in const contexts, see
+    /// [`Ipv4Hdr::parse_const`](super::Ipv4Hdr::parse_const).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the version is not 6 or the next header is unassigned.
+    pub const fn parse_const(bytes: &[u8; Self::LEN]) -> Self {
+        assert!(bytes[0] >> 4 == 6, "IPv6 header: version is not 6");
+        assert!(
+            IpProto::from_u8(bytes[6]).is_some(),
+            "IPv6 header: unassigned next header"
+        );
+        // SAFETY: `Ipv6Hdr` is packed and the only field with invalid bit
+        // patterns (`next_hdr`) was validated above.
+        unsafe { mem::transmute::<[u8; Self::LEN], Self>(*bytes) }
+    }
+
+    /// First 32-bit word of the header, in host byte order.
+    ///
+    /// The fields of the word are not byte-aligned, so they are extracted
+    /// from whole bytes, which read the same on every target.
+    #[inline]
+    const fn ver_tc_flow_label_word(&self) -> u32 {
+        let b = &self.ver_tc_flow_label;
+        ((b.get(0, 8) as u32) << 24)
+            | ((b.get(8, 8) as u32) << 16)
+            | ((b.get(16, 8) as u32) << 8)
+            | b.get(24, 8) as u32
+    }
+
+    #[inline]
+    const fn set_ver_tc_flow_label_word(&mut self, word: u32) {
+        let b = &mut self.ver_tc_flow_label;
+        b.set(0, 8, (word >> 24) as u64 & 0xff);
+        b.set(8, 8, (word >> 16) as u64 & 0xff);
+        b.set(16, 8, (word >> 8) as u64 & 0xff);
+        b.set(24, 8, word as u64 & 0xff);
+    }
+
     #[inline]
-    pub fn version(&self) -> u8 {
-        self.ver_tc_flow_label.get(28usize, 4u8) as u8
+    pub const fn version(&self) -> u8 {
+        (self.ver_tc_flow_label_word() >> 28) as u8
     }
 
     #[inline]
-    pub fn set_version(&mut self, val: u8) {
-        self.ver_tc_flow_label.set(28usize, 4u8, val as u64)
+    pub const fn set_version(&mut self, val: u8) {
+        let word = self.ver_tc_flow_label_word() & 0x0fff_ffff;
+        self.set_ver_tc_flow_label_word(word | ((val as u32 & 0xf) << 28))
     }
 
     #[inline]
-    pub fn tc(&self) -> u8 {
-        self.ver_tc_flow_label.get(20, 8) as u8
+    pub const fn tc(&self) -> u8 {
+        (self.ver_tc_flow_label_word() >> 20) as u8
     }
 
     #[inline]
-    pub fn set_tc(&mut self, val: u8) {
-        self.ver_tc_flow_label.set(20, 8, val as u64)
+    pub const fn set_tc(&mut self, val: u8) {
+        let word = self.ver_tc_flow_label_word() & 0xf00f_ffff;
+        self.set_ver_tc_flow_label_word(word | ((val as u32) << 20))
     }
 
-    /// **caution**: value returned is big endian
+    /// 20-bit flow label, in host byte order.
     #[inline]
-    pub fn flow_label(&self) -> u32 {
-        self.ver_tc_flow_label.get(0, 20) as u32
+    pub const fn flow_label(&self) -> u32 {
+        self.ver_tc_flow_label_word() & FLOW_LABEL_MASK
     }
 
-    /// **caution**: value should be big endian
+    /// Sets the 20-bit flow label, given in host byte order. Upper bits of
+    /// `val` are ignored.
     #[inline]
-    pub fn set_flow_table(&mut self, val: u32) {
-        self.ver_tc_flow_label.set(0, 20, val as u64)
+    pub const fn set_flow_label(&mut self, val: u32) {
+        let word = self.ver_tc_flow_label_word() & !FLOW_LABEL_MASK;
+        self.set_ver_tc_flow_label_word(word | (val & FLOW_LABEL_MASK))
+    }
+
+    /// Sets the flow label of the packets of `flow`, see [`flow_label_for`].
+    #[inline]
+    pub fn set_flow_label_from_flow(&mut self, flow: &FlowKey) {
+        self.set_flow_label(flow_label_for(flow))
+    }
+}
+
+/// Mask of the flow label in the first word of [`Ipv6Hdr`].
+pub const FLOW_LABEL_MASK: u32 = 0x000f_ffff;
+
+/// Flow label of the packets of `flow`, as recommended by
+/// [RFC 6437](https://datatracker.ietf.org/doc/html/rfc6437#section-3):
+/// a stateless hash of the 5-tuple, uniformly distributed over the 20 bits
+/// and never 0, which marks unlabeled packets.
+///
+/// The label is the same across hosts and restarts. Senders wanting labels
+/// outsiders cannot predict use [`keyed_flow_label_for`] with a secret key.
+///
+/// ```
+/// use ether_packet::{
+///     flow::FlowKey,
+///     header::Header,
+///     ip::{v6::flow_label_for, IpProto, Ipv6Hdr},
+/// };
+///
+/// let key = FlowKey {
+///     src_addr: "2001:db8::1".parse().unwrap(),
+///     dst_addr: "2001:db8::2".parse().unwrap(),
+///     proto: IpProto::Tcp,
+///     src_port: 40000,
+///     dst_port: 443,
+/// };
+/// let mut bytes = [0x60, 0, 0, 0, 0, 0, 6, 64].repeat(5);
+/// let hdr = Ipv6Hdr::from_bytes_mut(&mut bytes).unwrap();
+/// hdr.set_flow_label_from_flow(&key);
+/// assert_eq!(hdr.flow_label(), flow_label_for(&key));
+/// assert_ne!(hdr.flow_label(), 0);
+/// assert_eq!(hdr.version(), 6);
+/// ```
+#[inline]
+pub fn flow_label_for(flow: &FlowKey) -> u32 {
+    keyed_flow_label_for(flow, &[0, 0])
+}
+
+/// Flow label of the packets of `flow` derived with the secret `key`, see
+/// [`flow_label_for`].
+pub fn keyed_flow_label_for(flow: &FlowKey, key: &[u64; 2]) -> u32 {
+    let hash = flow.keyed_hash(key);
+    let label = (hash ^ (hash >> 20) ^ (hash >> 40)) as u32 & FLOW_LABEL_MASK;
+    if label == 0 {
+        1
+    } else {
+        label
     }
 }
 
 #[repr(C, packed)]
 #[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, PartialOrd, Ord)]
-#[cfg_attr(features = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 pub struct Ipv6OptionHdr {
     /// 8-bit selector.  Identifies the type of header
     /// immediately following this Options
@@ -121,7 +224,7 @@ pub struct Ipv6OptionHdr {
 
 #[repr(C, packed)]
 #[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, PartialOrd, Ord)]
-#[cfg_attr(features = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 pub struct Ipv6OptionRoutingHdr {
     pub header: Ipv6OptionHdr,
     /// 8-bit identifier of a particular Routing header variant.
@@ -133,7 +236,7 @@ pub struct Ipv6OptionRoutingHdr {
 
 #[repr(C, packed)]
 #[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, PartialOrd, Ord)]
-#[cfg_attr(features = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 pub struct Ipv6OptionFragmentHdr {
     /// 8-bit selector.  Identifies the type of header
     /// immediately following this Options
@@ -153,6 +256,60 @@ pub struct Ipv6OptionFragmentHdr {
     pub identification: U32,
 }
 
+impl_header!(
+    Ipv6Hdr,
+    validate = |b: &[u8]| IpProto::from_u8(b[6]).is_some()
+);
+impl_header!(Ipv6OptionHdr, Ipv6OptionRoutingHdr, Ipv6OptionFragmentHdr);
+
+/// An option of a Hop-by-Hop or Destination Options header.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub struct Ipv6Option<'a> {
+    pub option_type: u8,
+    pub data: &'a [u8],
+}
+
+/// Iterator over the options of a Hop-by-Hop or Destination Options
+/// header, skipping the Pad1 and PadN options.
+#[derive(Debug, Clone)]
+pub struct Ipv6Options<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Ipv6Options<'a> {
+    /// `data` is the extension header following its [`Ipv6OptionHdr`].
+    #[inline]
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+}
+
+impl<'a> Iterator for Ipv6Options<'a> {
+    type Item = Ipv6Option<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (&option_type, rest) = self.data.split_first()?;
+            if option_type == 0 {
+                self.data = rest;
+                continue;
+            }
+            let Some((&len, rest)) = rest.split_first() else {
+                self.data = &[];
+                return None;
+            };
+            let Some((data, rest)) = rest.split_at_checked(len as usize) else {
+                self.data = &[];
+                return None;
+            };
+            self.data = rest;
+            if option_type != 1 {
+                return Some(Ipv6Option { option_type, data });
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
 
@@ -168,9 +325,8 @@ mod test {
             0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1,
         ];
 
-        let ipv6_header: Ipv6Hdr = unsafe {
-            mem::transmute::<[u8; Ipv6Hdr::LEN], _>(expected_header_bytes.try_into().unwrap())
-        };
+        let ipv6_header: Ipv6Hdr =
+            unsafe { mem::transmute::<[u8; Ipv6Hdr::LEN], _>(expected_header_bytes) };
         assert_eq!(ipv6_header.src_addr, Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 0));
         assert_eq!(ipv6_header.dst_addr, Ipv6Addr::new(2, 0, 0, 0, 0, 0, 0, 1));
 
@@ -181,11 +337,23 @@ mod test {
             (*ipv6_header).dst_addr = Ipv6Addr::new(2, 0, 0, 0, 0, 0, 0, 1);
         }
 
-        let ipv6_header: Ipv6Hdr =
-            unsafe { mem::transmute::<[u8; Ipv6Hdr::LEN], _>(header_bytes.try_into().unwrap()) };
+        let ipv6_header: Ipv6Hdr = unsafe { mem::transmute::<[u8; Ipv6Hdr::LEN], _>(header_bytes) };
         assert_eq!(ipv6_header.src_addr, Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 0));
         assert_eq!(ipv6_header.dst_addr, Ipv6Addr::new(2, 0, 0, 0, 0, 0, 0, 1));
 
         assert_eq!(expected_header_bytes, header_bytes);
+
+        let mut hdr: Ipv6Hdr = unsafe { mem::transmute::<[u8; Ipv6Hdr::LEN], _>(header_bytes) };
+        hdr.set_version(6);
+        hdr.set_tc(0xb8);
+        hdr.set_flow_label(0x12345);
+        assert_eq!(
+            (hdr.version(), hdr.tc(), hdr.flow_label()),
+            (6, 0xb8, 0x12345)
+        );
+        let bytes: [u8; Ipv6Hdr::LEN] = unsafe { mem::transmute(hdr) };
+        assert_eq!(bytes[..4], [0x6b, 0x81, 0x23, 0x45]);
+        hdr.set_flow_label(0xfff00000);
+        assert_eq!((hdr.tc(), hdr.flow_label()), (0xb8, 0));
     }
 }
@@ -0,0 +1,468 @@
+//! Label Distribution Protocol ([RFC 5036](https://datatracker.ietf.org/doc/html/rfc5036)),
+//! carried over UDP (discovery) and TCP (sessions) port 646.
+
+use core::{
+    mem,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+};
+
+use crate::{
+    header::{impl_header, Header, Limited, ParseConfig},
+    types::{U16, U32},
+};
+
+pub const LDP_PORT: u16 = 646;
+
+/// Address family numbers used by the FEC and Address List TLVs.
+pub const ADDRESS_FAMILY_IPV4: u16 = 1;
+pub const ADDRESS_FAMILY_IPV6: u16 = 2;
+
+/// LDP PDU header, followed by one or more LDP messages.
+/// ```text
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |  Version                      |         PDU Length            |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                         LDP Identifier                        |
+/// +                               +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                               |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct LdpPduHdr {
+    pub version: U16,
+    /// Length of the PDU, excluding the version and length fields.
+    pub pdu_length: U16,
+    /// LSR Id, the first part of the LDP Identifier.
+    pub lsr_id: Ipv4Addr,
+    /// Label space, the second part of the LDP Identifier.
+    pub label_space: U16,
+}
+
+impl LdpPduHdr {
+    pub const LEN: usize = mem::size_of::<LdpPduHdr>();
+
+    /// Parses the LDP PDU stored in `pdu`, returning its header and an
+    /// iterator over its messages.
+    pub fn parse(pdu: &[u8]) -> Option<(&LdpPduHdr, LdpMessages<'_>)> {
+        let hdr = LdpPduHdr::from_bytes(pdu)?;
+        let body = pdu.get(Self::LEN..4 + hdr.pdu_length.to_bits() as usize)?;
+        Some((hdr, LdpMessages { data: body }))
+    }
+}
+
+/// LDP message types.
+#[repr(u16)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum LdpMsgType {
+    Notification = 0x0001,
+    Hello = 0x0100,
+    Initialization = 0x0200,
+    KeepAlive = 0x0201,
+    Address = 0x0300,
+    AddressWithdraw = 0x0301,
+    LabelMapping = 0x0400,
+    LabelRequest = 0x0401,
+    LabelWithdraw = 0x0402,
+    LabelRelease = 0x0403,
+    LabelAbortRequest = 0x0404,
+}
+
+impl TryFrom<u16> for LdpMsgType {
+    type Error = ();
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        match value {
+            0x0001 => Ok(LdpMsgType::Notification),
+            0x0100 => Ok(LdpMsgType::Hello),
+            0x0200 => Ok(LdpMsgType::Initialization),
+            0x0201 => Ok(LdpMsgType::KeepAlive),
+            0x0300 => Ok(LdpMsgType::Address),
+            0x0301 => Ok(LdpMsgType::AddressWithdraw),
+            0x0400 => Ok(LdpMsgType::LabelMapping),
+            0x0401 => Ok(LdpMsgType::LabelRequest),
+            0x0402 => Ok(LdpMsgType::LabelWithdraw),
+            0x0403 => Ok(LdpMsgType::LabelRelease),
+            0x0404 => Ok(LdpMsgType::LabelAbortRequest),
+            _ => Err(()),
+        }
+    }
+}
+
+/// LDP message header, followed by the mandatory and optional parameters
+/// encoded as TLVs.
+/// ```text
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |U|   Message Type              |      Message Length           |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                     Message ID                                |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct LdpMsgHdr {
+    /// The U bit followed by the 15-bit message type.
+    pub u_type: U16,
+    /// Length of the message, excluding the type and length fields.
+    pub length: U16,
+    pub msg_id: U32,
+}
+
+impl LdpMsgHdr {
+    pub const LEN: usize = mem::size_of::<LdpMsgHdr>();
+
+    /// **Unknown message bit**: unknown messages with this bit set are
+    /// silently ignored instead of being answered with a notification.
+    #[inline]
+    pub const fn unknown(&self) -> bool {
+        self.u_type.to_bits() & 0x8000 != 0
+    }
+
+    #[inline]
+    pub fn msg_type(&self) -> Option<LdpMsgType> {
+        (self.u_type.to_bits() & 0x7fff).try_into().ok()
+    }
+
+    #[inline]
+    pub const fn msg_id(&self) -> u32 {
+        self.msg_id.to_bits()
+    }
+}
+
+/// LDP TLV types.
+#[repr(u16)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum LdpTlvType {
+    Fec = 0x0100,
+    AddressList = 0x0101,
+    HopCount = 0x0103,
+    PathVector = 0x0104,
+    GenericLabel = 0x0200,
+    AtmLabel = 0x0201,
+    FrameRelayLabel = 0x0202,
+    Status = 0x0300,
+    ExtendedStatus = 0x0301,
+    ReturnedPdu = 0x0302,
+    ReturnedMessage = 0x0303,
+    CommonHelloParams = 0x0400,
+    Ipv4TransportAddress = 0x0401,
+    ConfigurationSequenceNumber = 0x0402,
+    Ipv6TransportAddress = 0x0403,
+    CommonSessionParams = 0x0500,
+    AtmSessionParams = 0x0501,
+    FrameRelaySessionParams = 0x0502,
+    LabelRequestMessageId = 0x0600,
+}
+
+impl TryFrom<u16> for LdpTlvType {
+    type Error = ();
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        match value {
+            0x0100 => Ok(LdpTlvType::Fec),
+            0x0101 => Ok(LdpTlvType::AddressList),
+            0x0103 => Ok(LdpTlvType::HopCount),
+            0x0104 => Ok(LdpTlvType::PathVector),
+            0x0200 => Ok(LdpTlvType::GenericLabel),
+            0x0201 => Ok(LdpTlvType::AtmLabel),
+            0x0202 => Ok(LdpTlvType::FrameRelayLabel),
+            0x0300 => Ok(LdpTlvType::Status),
+            0x0301 => Ok(LdpTlvType::ExtendedStatus),
+            0x0302 => Ok(LdpTlvType::ReturnedPdu),
+            0x0303 => Ok(LdpTlvType::ReturnedMessage),
+            0x0400 => Ok(LdpTlvType::CommonHelloParams),
+            0x0401 => Ok(LdpTlvType::Ipv4TransportAddress),
+            0x0402 => Ok(LdpTlvType::ConfigurationSequenceNumber),
+            0x0403 => Ok(LdpTlvType::Ipv6TransportAddress),
+            0x0500 => Ok(LdpTlvType::CommonSessionParams),
+            0x0501 => Ok(LdpTlvType::AtmSessionParams),
+            0x0502 => Ok(LdpTlvType::FrameRelaySessionParams),
+            0x0600 => Ok(LdpTlvType::LabelRequestMessageId),
+            _ => Err(()),
+        }
+    }
+}
+
+/// LDP TLV header.
+/// ```text
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |U|F|        Type               |            Length             |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct LdpTlvHdr {
+    /// The U and F bits followed by the 14-bit TLV type.
+    pub uf_type: U16,
+    /// Length of the value, in octets.
+    pub length: U16,
+}
+
+impl LdpTlvHdr {
+    pub const LEN: usize = mem::size_of::<LdpTlvHdr>();
+
+    /// **Unknown TLV bit**: unknown TLVs with this bit set are silently
+    /// ignored.
+    #[inline]
+    pub const fn unknown(&self) -> bool {
+        self.uf_type.to_bits() & 0x8000 != 0
+    }
+
+    /// **Forward unknown TLV bit**: unknown TLVs with this bit set are
+    /// forwarded along with the containing message.
+    #[inline]
+    pub const fn forward(&self) -> bool {
+        self.uf_type.to_bits() & 0x4000 != 0
+    }
+
+    #[inline]
+    pub const fn raw_type(&self) -> u16 {
+        self.uf_type.to_bits() & 0x3fff
+    }
+
+    #[inline]
+    pub fn tlv_type(&self) -> Option<LdpTlvType> {
+        self.raw_type().try_into().ok()
+    }
+}
+
+impl_header!(LdpPduHdr, LdpMsgHdr, LdpTlvHdr);
+
+/// An LDP message.
+#[derive(Debug, Copy, Clone)]
+pub struct LdpMessage<'a> {
+    pub hdr: &'a LdpMsgHdr,
+    /// Parameters of the message, following the message ID.
+    pub params: &'a [u8],
+}
+
+impl<'a> LdpMessage<'a> {
+    /// Iterator over the TLVs encoding the message parameters.
+    #[inline]
+    pub fn tlvs(&self) -> LdpTlvs<'a> {
+        LdpTlvs { data: self.params }
+    }
+}
+
+/// Iterator over the messages of an LDP PDU.
+#[derive(Debug, Clone)]
+pub struct LdpMessages<'a> {
+    data: &'a [u8],
+}
+
+impl LdpMessages<'_> {
+    /// Bounds the walk to [`ParseConfig::max_options`] messages.
+    #[inline]
+    pub fn limit(self, config: &ParseConfig) -> Limited<Self> {
+        Limited::new(self, config.max_options)
+    }
+}
+
+impl<'a> Iterator for LdpMessages<'a> {
+    type Item = LdpMessage<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let hdr = LdpMsgHdr::from_bytes(self.data)?;
+        let Some(params) = self
+            .data
+            .get(LdpMsgHdr::LEN..4 + hdr.length.to_bits() as usize)
+        else {
+            self.data = &[];
+            return None;
+        };
+        self.data = &self.data[LdpMsgHdr::LEN + params.len()..];
+        Some(LdpMessage { hdr, params })
+    }
+}
+
+/// An LDP TLV.
+#[derive(Debug, Copy, Clone)]
+pub struct LdpTlv<'a> {
+    pub hdr: &'a LdpTlvHdr,
+    pub value: &'a [u8],
+}
+
+impl<'a> LdpTlv<'a> {
+    /// Elements of a FEC TLV.
+    pub fn fec(&self) -> Option<FecElements<'a>> {
+        self.value_of(LdpTlvType::Fec)
+            .map(|data| FecElements { data })
+    }
+
+    /// Label carried by a Generic Label TLV.
+    pub fn generic_label(&self) -> Option<u32> {
+        let value = self.value_of(LdpTlvType::GenericLabel)?;
+        let label: [u8; 4] = value.get(..4)?.try_into().ok()?;
+        Some(u32::from_be_bytes(label) & 0xfffff)
+    }
+
+    /// Addresses of an Address List TLV.
+    pub fn address_list(&self) -> Option<AddressList<'a>> {
+        let value = self.value_of(LdpTlvType::AddressList)?;
+        let family: [u8; 2] = value.get(..2)?.try_into().ok()?;
+        Some(AddressList {
+            family: u16::from_be_bytes(family),
+            data: &value[2..],
+        })
+    }
+
+    fn value_of(&self, kind: LdpTlvType) -> Option<&'a [u8]> {
+        (self.hdr.raw_type() == kind as u16).then_some(self.value)
+    }
+}
+
+/// Iterator over a sequence of LDP TLVs.
+#[derive(Debug, Clone)]
+pub struct LdpTlvs<'a> {
+    data: &'a [u8],
+}
+
+impl LdpTlvs<'_> {
+    /// Bounds the walk to [`ParseConfig::max_options`] TLVs.
+    #[inline]
+    pub fn limit(self, config: &ParseConfig) -> Limited<Self> {
+        Limited::new(self, config.max_options)
+    }
+}
+
+impl<'a> Iterator for LdpTlvs<'a> {
+    type Item = LdpTlv<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let hdr = LdpTlvHdr::from_bytes(self.data)?;
+        let end = LdpTlvHdr::LEN + hdr.length.to_bits() as usize;
+        let Some(value) = self.data.get(LdpTlvHdr::LEN..end) else {
+            self.data = &[];
+            return None;
+        };
+        self.data = &self.data[end..];
+        Some(LdpTlv { hdr, value })
+    }
+}
+
+/// An element of a FEC TLV.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum FecElement {
+    /// Applies to all FECs bound to the label (or of the session).
+    Wildcard,
+    /// An address prefix and its length in bits.
+    Prefix(IpAddr, u8),
+}
+
+/// Iterator over the elements of a FEC TLV.
+///
+/// Iteration stops at the first element of an unknown type or address
+/// family, as its length cannot be determined.
+#[derive(Debug, Clone)]
+pub struct FecElements<'a> {
+    data: &'a [u8],
+}
+
+impl Iterator for FecElements<'_> {
+    type Item = FecElement;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (&kind, rest) = self.data.split_first()?;
+        let (elem, len) = match kind {
+            0x01 => (FecElement::Wildcard, 1),
+            0x02 => {
+                let family = u16::from_be_bytes(rest.get(..2)?.try_into().ok()?);
+                let prefix_len = *rest.get(2)?;
+                let octets = rest.get(3..3 + (prefix_len as usize).div_ceil(8))?;
+                (
+                    FecElement::Prefix(decode_address(family, octets)?, prefix_len),
+                    4 + octets.len(),
+                )
+            }
+            _ => None?,
+        };
+        self.data = &self.data[len..];
+        Some(elem)
+    }
+}
+
+/// Iterator over the addresses of an Address List TLV.
+#[derive(Debug, Clone)]
+pub struct AddressList<'a> {
+    pub family: u16,
+    data: &'a [u8],
+}
+
+impl Iterator for AddressList<'_> {
+    type Item = IpAddr;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let len = match self.family {
+            ADDRESS_FAMILY_IPV4 => 4,
+            ADDRESS_FAMILY_IPV6 => 16,
+            _ => return None,
+        };
+        let addr = decode_address(self.family, self.data.get(..len)?)?;
+        self.data = &self.data[len..];
+        Some(addr)
+    }
+}
+
+/// Decodes a possibly truncated address of the given address family, the
+/// missing trailing octets being zero.
+fn decode_address(family: u16, octets: &[u8]) -> Option<IpAddr> {
+    match family {
+        ADDRESS_FAMILY_IPV4 => {
+            let mut a = [0u8; 4];
+            a.get_mut(..octets.len())?.copy_from_slice(octets);
+            Some(IpAddr::V4(Ipv4Addr::from(a)))
+        }
+        ADDRESS_FAMILY_IPV6 => {
+            let mut a = [0u8; 16];
+            a.get_mut(..octets.len())?.copy_from_slice(octets);
+            Some(IpAddr::V6(Ipv6Addr::from(a)))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::net::{IpAddr, Ipv4Addr};
+
+    use super::{FecElement, LdpMsgType, LdpPduHdr};
+
+    #[test]
+    fn test_label_mapping() {
+        let pdu = [
+            0, 1, 0, 34, 10, 0, 0, 1, 0, 0, // PDU header, LSR 10.0.0.1:0
+            0x04, 0x00, 0, 24, 0, 0, 0, 7, // Label Mapping, id 7
+            0x01, 0x00, 0, 8, 2, 0, 1, 24, 192, 0, 2, 1, // FEC 192.0.2.0/24, wildcard
+            0x02, 0x00, 0, 4, 0, 0x01, 0xe2, 0x40, // Generic Label 123456
+        ];
+        let (hdr, mut msgs) = LdpPduHdr::parse(&pdu).unwrap();
+        assert_eq!(hdr.lsr_id, Ipv4Addr::new(10, 0, 0, 1));
+
+        let msg = msgs.next().unwrap();
+        assert_eq!(msg.hdr.msg_type(), Some(LdpMsgType::LabelMapping));
+        assert_eq!(msg.hdr.msg_id(), 7);
+        let mut tlvs = msg.tlvs();
+        let mut fec = tlvs.next().unwrap().fec().unwrap();
+        assert_eq!(
+            fec.next(),
+            Some(FecElement::Prefix(
+                IpAddr::V4(Ipv4Addr::new(192, 0, 2, 0)),
+                24
+            ))
+        );
+        assert_eq!(fec.next(), Some(FecElement::Wildcard));
+        assert_eq!(fec.next(), None);
+        assert_eq!(tlvs.next().unwrap().generic_label(), Some(123456));
+        assert!(tlvs.next().is_none());
+        assert!(msgs.next().is_none());
+    }
+}
@@ -9,7 +9,7 @@
 //! An example of an [XDP program](https://aya-rs.dev/book/start/) logging
 //! information about addresses and ports for incoming packets:
 //!
-//! ```rust
+//! ```rust,ignore
 //! use core::mem;
 //!
 //! use aya_bpf::{bindings::xdp_action, macros::xdp, programs::XdpContext};
@@ -57,12 +57,12 @@
 //!         IpProto::Tcp => {
 //!             let tcphdr: *const TcpHdr =
 //!                 unsafe { ptr_at(&ctx, EthHdr::LEN + Ipv4Hdr::LEN) }?;
-//!             u16::from_be(unsafe { *tcphdr }.source)
+//!             unsafe { *tcphdr }.source.to_bits()
 //!         }
 //!         IpProto::Udp => {
 //!             let udphdr: *const UdpHdr =
 //!                 unsafe { ptr_at(&ctx, EthHdr::LEN + Ipv4Hdr::LEN) }?;
-//!             u16::from_be(unsafe { *udphdr }.source)
+//!             unsafe { *udphdr }.source.to_bits()
 //!         }
 //!         _ => return Err(()),
 //!     };
@@ -96,15 +96,130 @@
 //! infrastructure.
 //!
 //! Note that `no_std` support is lost when enabling Serde.
+//!
+//! The `dpdk` feature enables the [`dpdk`] module, which reads headers
+//! directly out of DPDK `rte_mbuf` chains.
+//!
+//! The `alloc` feature, implied by `std`, enables the stateful helpers
+//! which need heap allocation, such as the neighbor cache, the TCP
+//! connection tracker, the flow table, the MPLS label forwarding table and
+//! the [`pcapng`] parser. The `futures-io` feature adds an asynchronous pcapng reader on
+//! top of it.
+//!
+//! The `std` feature enables the [`tshark`] module, exporting parsed
+//! packets in the JSON format of Wireshark, and the [`pipeline`] module,
+//! spreading captured packets over worker threads.
+//!
+//! The `live` feature enables the [`live`] module, yielding the packets of
+//! a live capture, such as one of the [`pcap`](https://docs.rs/pcap) crate,
+//! as parsed packets.
+//!
+//! The `tpacket` feature enables the [`tpacket`] module on Linux, capturing
+//! packets from a memory-mapped `AF_PACKET` ring.
+//!
+//! The `nom` feature enables the [`nom`](mod@nom) module, exposing the
+//! headers as [nom](https://docs.rs/nom) parsers.
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+pub mod anomaly;
+pub mod arp;
+pub mod avtp;
+pub mod babel;
+pub mod bfd;
 pub mod bitfield;
+pub mod bmp;
+pub mod bpdu;
+pub mod buffer;
+pub mod builder;
+pub mod capture;
+pub mod cfm;
+pub mod checksum;
+#[cfg(feature = "alloc")]
+pub mod classifier;
+pub mod datapath;
+pub mod detect;
+pub mod dhcp;
+pub mod digest;
+pub mod dns;
+#[cfg(feature = "dpdk")]
+pub mod dpdk;
+#[cfg(feature = "alloc")]
+pub mod dualstack;
+pub mod ecn;
+pub mod edit;
 pub mod eth;
+pub mod flow;
+#[cfg(feature = "alloc")]
+pub mod flowtable;
+pub mod geneve;
+pub mod gre;
+pub mod gso;
+pub mod header;
 pub mod icmp;
+pub mod icmpv6;
+pub mod igmp;
+pub mod int;
+pub mod ioam;
 pub mod ip;
+pub mod ldp;
+#[cfg(feature = "live")]
+pub mod live;
+pub mod lowpan;
+#[cfg(feature = "alloc")]
+pub mod maglev;
+pub mod meta;
+pub mod metrics;
+pub mod mld;
+pub mod mndp;
+pub mod mpls;
+pub mod mrp;
+pub mod msdp;
+pub mod multicast;
+pub mod mvrp;
+pub mod nbds;
+pub mod ndp;
+pub mod ne;
+#[cfg(feature = "alloc")]
+pub mod neighbor;
+#[cfg(feature = "nom")]
+pub mod nom;
+pub mod nptv6;
+pub mod packet;
+#[cfg(feature = "alloc")]
+pub mod pcapng;
+#[cfg(feature = "std")]
+pub mod pipeline;
+pub mod policer;
+pub mod ptp;
+pub mod qos;
+pub mod rohc;
+pub mod rsvp;
+pub mod sampling;
+pub mod scrub;
+pub mod sctp;
+pub mod shim6;
+pub mod sll;
+pub mod slow;
+pub mod ssdp;
+pub mod sv;
+pub mod syncookie;
 pub mod tcp;
+#[cfg(feature = "alloc")]
+pub mod tcptrack;
+pub mod template;
+pub mod testing;
+#[cfg(all(feature = "tpacket", target_os = "linux"))]
+pub mod tpacket;
+#[cfg(feature = "std")]
+pub mod tshark;
+pub mod tunnel;
+pub mod types;
+pub mod uadp;
 pub mod udp;
+pub mod vlan;
+pub mod vrrp;
 pub mod vxlan;
-pub mod ne;
-pub mod types;
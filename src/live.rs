@@ -0,0 +1,235 @@
+//! Parsing of live captures, such as those of the
+//! [`pcap`](https://docs.rs/pcap) crate.
+//!
+//! [`LiveCapture`] wraps a [`CaptureSource`], maps its `DLT_*` datalink type
+//! to a [`LinkType`] and yields every captured packet as a parsed
+//! [`Packet`], along with its [`PacketMeta`]. The direction and interface
+//! recorded in Linux cooked headers are filled in.
+//!
+//! A `pcap::Capture` is a source once its packets are mapped to a
+//! [`RawPacket`]:
+//!
+//! ```rust,ignore
+//! use std::time::Duration;
+//!
+//! use ether_packet::live::{CaptureSource, LiveCapture, RawPacket};
+//!
+//! struct Pcap(pcap::Capture<pcap::Active>);
+//!
+//! impl CaptureSource for Pcap {
+//!     type Error = pcap::Error;
+//!
+//!     fn datalink(&self) -> i32 {
+//!         self.0.get_datalink().0
+//!     }
+//!
+//!     fn next_packet(&mut self) -> Result<Option<RawPacket<'_>>, pcap::Error> {
+//!         match self.0.next_packet() {
+//!             Ok(packet) => Ok(Some(RawPacket {
+//!                 data: packet.data,
+//!                 original_len: packet.header.len as usize,
+//!                 timestamp: Duration::new(
+//!                     packet.header.ts.tv_sec as u64,
+//!                     packet.header.ts.tv_usec as u32 * 1000,
+//!                 ),
+//!             })),
+//!             Err(pcap::Error::NoMorePackets) => Ok(None),
+//!             Err(err) => Err(err),
+//!         }
+//!     }
+//! }
+//!
+//! let capture = pcap::Capture::from_device("any")?.immediate_mode(true).open()?;
+//! let mut capture = LiveCapture::new(Pcap(capture))?;
+//! while let Some((packet, meta)) = capture.next_packet()? {
+//!     println!("{:?} {:?}", meta.direction, packet.network().map(|ip| ip.src_addr()));
+//! }
+//! ```
+
+use core::{fmt, time::Duration};
+
+use crate::{
+    capture::Captured,
+    header::{Header, ParseConfig, ParseError},
+    meta::PacketMeta,
+    packet::{LinkType, Packet},
+    sll::{Sll2Hdr, SllHdr},
+};
+
+/// A packet handed out by a [`CaptureSource`].
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub struct RawPacket<'a> {
+    /// Captured bytes, starting with the link-layer header.
+    pub data: &'a [u8],
+    /// Length of the packet on the wire.
+    pub original_len: usize,
+    /// Capture time, as a duration since the UNIX epoch.
+    pub timestamp: Duration,
+}
+
+/// Source of the packets of a [`LiveCapture`].
+pub trait CaptureSource {
+    type Error;
+
+    /// `DLT_*` datalink type of the capture, as returned by
+    /// `pcap_datalink`.
+    fn datalink(&self) -> i32;
+
+    /// Waits for the next packet, returning `None` once the capture ends.
+    fn next_packet(&mut self) -> Result<Option<RawPacket<'_>>, Self::Error>;
+}
+
+/// Error of a [`LiveCapture`].
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum LiveError<E> {
+    /// The datalink type of the source has no [`LinkType`].
+    UnsupportedDatalink(i32),
+    /// The source failed.
+    Source(E),
+    /// A captured packet is malformed. Later packets can still be read.
+    Parse(ParseError),
+}
+
+impl<E: fmt::Display> fmt::Display for LiveError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LiveError::UnsupportedDatalink(dlt) => write!(f, "unsupported datalink type {dlt}"),
+            LiveError::Source(err) => err.fmt(f),
+            LiveError::Parse(err) => err.fmt(f),
+        }
+    }
+}
+
+/// Parser of the packets of a [`CaptureSource`].
+#[derive(Debug)]
+pub struct LiveCapture<S> {
+    source: S,
+    link_type: LinkType,
+}
+
+impl<S: CaptureSource> LiveCapture<S> {
+    /// Fails with [`LiveError::UnsupportedDatalink`] if the datalink type
+    /// of `source` is not one of [`LinkType::from_dlt`].
+    pub fn new(source: S) -> Result<Self, LiveError<S::Error>> {
+        let dlt = source.datalink();
+        let link_type = LinkType::from_dlt(dlt).ok_or(LiveError::UnsupportedDatalink(dlt))?;
+        Ok(Self { source, link_type })
+    }
+
+    #[inline]
+    pub fn link_type(&self) -> LinkType {
+        self.link_type
+    }
+
+    #[inline]
+    pub fn source(&self) -> &S {
+        &self.source
+    }
+
+    #[inline]
+    pub fn source_mut(&mut self) -> &mut S {
+        &mut self.source
+    }
+
+    #[inline]
+    pub fn into_source(self) -> S {
+        self.source
+    }
+
+    /// Waits for the next packet and parses it, returning `None` once the
+    /// capture ends.
+    pub fn next_packet(&mut self) -> Result<Option<(Packet<'_>, PacketMeta)>, LiveError<S::Error>> {
+        let link_type = self.link_type;
+        let Some(raw) = self.source.next_packet().map_err(LiveError::Source)? else {
+            return Ok(None);
+        };
+        let mut meta = PacketMeta::new(raw.timestamp);
+        match link_type {
+            LinkType::LinuxSll => {
+                if let Some(sll) = SllHdr::from_bytes(raw.data) {
+                    meta.update_from_sll(sll);
+                }
+            }
+            LinkType::LinuxSll2 => {
+                if let Some(sll) = Sll2Hdr::from_bytes(raw.data) {
+                    meta.update_from_sll2(sll);
+                }
+            }
+            _ => {}
+        }
+        let captured = Captured::new(raw.data, raw.original_len);
+        let packet = Packet::parse_with_config(captured, link_type, &ParseConfig::DEFAULT)
+            .map_err(LiveError::Parse)?;
+        Ok(Some((packet, meta)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use super::{CaptureSource, LiveCapture, LiveError, RawPacket};
+    use crate::{header::ParseError, ip::IpProto, meta::Direction, packet::LinkType};
+
+    #[derive(Debug)]
+    struct Replay<'a> {
+        dlt: i32,
+        packets: &'a [&'a [u8]],
+    }
+
+    impl CaptureSource for Replay<'_> {
+        type Error = ();
+
+        fn datalink(&self) -> i32 {
+            self.dlt
+        }
+
+        fn next_packet(&mut self) -> Result<Option<RawPacket<'_>>, ()> {
+            let Some((data, rest)) = self.packets.split_first() else {
+                return Ok(None);
+            };
+            self.packets = rest;
+            Ok(Some(RawPacket {
+                data,
+                original_len: data.len(),
+                timestamp: Duration::from_secs(rest.len() as u64),
+            }))
+        }
+    }
+
+    #[test]
+    fn test_live_capture() {
+        let empty = Replay {
+            dlt: 147,
+            packets: &[],
+        };
+        assert_eq!(
+            LiveCapture::new(empty).unwrap_err(),
+            LiveError::UnsupportedDatalink(147)
+        );
+
+        #[rustfmt::skip]
+        let sll = [
+            // Outgoing, ARPHRD_ETHER, 6-byte address, IPv4.
+            0, 4, 0, 1, 0, 6, 0, 2, 3, 4, 5, 6, 0, 0, 0x08, 0x00,
+            0x45, 0, 0, 28, 0, 1, 0x40, 0, 64, 17, 0, 0, 10, 0, 0, 1, 10, 0, 0, 2,
+            0x30, 0x39, 0, 53, 0, 8, 0, 0,
+        ];
+        let packets = [&sll[..], &sll[..20]];
+        let source = Replay {
+            dlt: 113,
+            packets: &packets,
+        };
+        let mut capture = LiveCapture::new(source).unwrap();
+        assert_eq!(capture.link_type(), LinkType::LinuxSll);
+        let (packet, meta) = capture.next_packet().unwrap().unwrap();
+        assert_eq!(packet.proto(), Some(IpProto::Udp));
+        assert_eq!(meta.direction, Direction::Outbound);
+        assert_eq!(meta.timestamp, Some(Duration::from_secs(1)));
+        assert_eq!(
+            capture.next_packet().unwrap_err(),
+            LiveError::Parse(ParseError::Malformed)
+        );
+        assert!(capture.next_packet().unwrap().is_none());
+    }
+}
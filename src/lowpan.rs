@@ -0,0 +1,535 @@
+//! 6LoWPAN header compression
+//! ([RFC 6282](https://datatracker.ietf.org/doc/html/rfc6282)), converting
+//! between the IPHC/NHC form carried in IEEE 802.15.4 frames and full IPv6
+//! and UDP headers, which can then be read with [`Ipv6Hdr`].
+//!
+//! Addresses are elided against the link-layer addresses of the frame and
+//! against shared contexts, each one being a 64-bit prefix indexed by its
+//! context identifier. Only the UDP next header compression is supported;
+//! fragmentation and mesh headers are left to the caller.
+
+use core::{fmt, net::Ipv6Addr};
+
+use crate::{
+    builder::{BuildError, Writer},
+    checksum,
+    header::Header,
+    ip::{IpProto, Ipv6Hdr},
+};
+
+/// Dispatch of an uncompressed IPv6 header.
+pub const DISPATCH_IPV6: u8 = 0x41;
+/// Dispatch of an IPHC header, in its three most significant bits.
+pub const DISPATCH_IPHC: u8 = 0x60;
+/// Dispatch of a UDP NHC header, in its five most significant bits.
+pub const NHC_UDP: u8 = 0xf0;
+
+const LINK_LOCAL_PREFIX: [u8; 8] = [0xfe, 0x80, 0, 0, 0, 0, 0, 0];
+
+/// IEEE 802.15.4 address of the sender or receiver of a frame.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum LinkAddr {
+    Short(u16),
+    Extended([u8; 8]),
+}
+
+impl LinkAddr {
+    /// Interface identifier derived from the address (RFC 4944 section 6
+    /// and RFC 6282 section 3.2.2).
+    pub fn iid(&self) -> [u8; 8] {
+        match *self {
+            LinkAddr::Short(addr) => {
+                let [hi, lo] = addr.to_be_bytes();
+                [0, 0, 0, 0xff, 0xfe, 0, hi, lo]
+            }
+            LinkAddr::Extended(mut addr) => {
+                addr[0] ^= 0x02;
+                addr
+            }
+        }
+    }
+}
+
+/// Error returned by [`compress`] and [`decompress`].
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum LowpanError {
+    /// The packet or frame is truncated or inconsistent.
+    Malformed,
+    /// The frame uses a dispatch or an encoding which is not supported.
+    Unsupported,
+    /// The frame refers to a context which is not known.
+    UnknownContext,
+    /// The output buffer is too small.
+    BufferTooSmall,
+}
+
+impl fmt::Display for LowpanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LowpanError::Malformed => f.write_str("malformed packet"),
+            LowpanError::Unsupported => f.write_str("unsupported dispatch or encoding"),
+            LowpanError::UnknownContext => f.write_str("unknown context"),
+            LowpanError::BufferTooSmall => f.write_str("buffer too small"),
+        }
+    }
+}
+
+impl From<BuildError> for LowpanError {
+    fn from(err: BuildError) -> Self {
+        match err {
+            BuildError::BufferTooSmall => LowpanError::BufferTooSmall,
+            BuildError::FieldOverflow | BuildError::FrameTooLarge | BuildError::MisplacedHeader => {
+                LowpanError::Malformed
+            }
+        }
+    }
+}
+
+/// Interface identifier of the form `0000:00ff:fe00:XXXX`, compressible to
+/// 16 bits.
+#[inline]
+fn is_short_iid(iid: &[u8]) -> bool {
+    iid[..6] == [0, 0, 0, 0xff, 0xfe, 0]
+}
+
+/// Chooses the encoding of a unicast address, returning the context used
+/// (if any), the address mode and the inline bytes.
+fn compress_unicast<'a>(
+    addr: &'a [u8; 16],
+    ll: &LinkAddr,
+    contexts: &[[u8; 8]],
+) -> (Option<usize>, u8, &'a [u8]) {
+    let ctx = if addr[..8] == LINK_LOCAL_PREFIX {
+        None
+    } else {
+        match contexts
+            .iter()
+            .take(16)
+            .position(|prefix| addr[..8] == *prefix)
+        {
+            Some(ctx) => Some(ctx),
+            None => return (None, 0b00, &addr[..]),
+        }
+    };
+    let iid = &addr[8..];
+    if iid == ll.iid() {
+        (ctx, 0b11, &[])
+    } else if is_short_iid(iid) {
+        (ctx, 0b10, &addr[14..])
+    } else {
+        (ctx, 0b01, iid)
+    }
+}
+
+/// Chooses the encoding of a multicast address, returning the address mode
+/// and the inline bytes.
+fn compress_multicast(addr: &[u8; 16], inline: &mut [u8; 6]) -> (u8, usize) {
+    if addr[..2] == [0xff, 0x02] && addr[2..15].iter().all(|b| *b == 0) {
+        inline[0] = addr[15];
+        (0b11, 1)
+    } else if addr[2..13].iter().all(|b| *b == 0) {
+        inline[0] = addr[1];
+        inline[1..4].copy_from_slice(&addr[13..]);
+        (0b10, 4)
+    } else if addr[2..11].iter().all(|b| *b == 0) {
+        inline[0] = addr[1];
+        inline[1..6].copy_from_slice(&addr[11..]);
+        (0b01, 6)
+    } else {
+        (0b00, 0)
+    }
+}
+
+/// Compresses the IPv6 packet stored in `packet` into `out`, eliding what
+/// can be derived from the link-layer addresses `src_ll` and `dst_ll` or
+/// from `contexts`. A UDP header directly following the IPv6 header is
+/// compressed too, keeping its checksum. Returns the length of the frame
+/// payload written to `out`.
+pub fn compress(
+    packet: &[u8],
+    src_ll: LinkAddr,
+    dst_ll: LinkAddr,
+    contexts: &[[u8; 8]],
+    out: &mut [u8],
+) -> Result<usize, LowpanError> {
+    let hdr = Ipv6Hdr::from_bytes(packet).ok_or(LowpanError::Malformed)?;
+    let len = Ipv6Hdr::LEN + hdr.payload_len.to_bits() as usize;
+    let packet = packet.get(..len).ok_or(LowpanError::Malformed)?;
+    let (src, dst) = (hdr.src_addr.octets(), hdr.dst_addr.octets());
+    let tc = (packet[0] << 4) | (packet[1] >> 4);
+    let flow_label = u32::from_be_bytes([0, packet[1] & 0xf, packet[2], packet[3]]);
+    let (next_hdr, hop_limit) = (packet[6], packet[7]);
+    let mut payload = &packet[Ipv6Hdr::LEN..];
+    let udp = next_hdr == IpProto::Udp as u8 && payload.len() >= 8;
+
+    let mut w = Writer::new(out);
+    let mut iphc = [DISPATCH_IPHC, 0];
+    let (ecn, dscp) = (tc & 0x3, tc >> 2);
+    let tf = match (tc, dscp, flow_label) {
+        (0, _, 0) => 0b11,
+        (_, _, 0) => 0b10,
+        (_, 0, _) => 0b01,
+        _ => 0b00,
+    };
+    iphc[0] |= tf << 3;
+    if udp {
+        iphc[0] |= 0x04;
+    }
+    iphc[0] |= match hop_limit {
+        1 => 0b01,
+        64 => 0b10,
+        255 => 0b11,
+        _ => 0b00,
+    };
+
+    let (sci, sam, src_inline) = if hdr.src_addr.is_unspecified() {
+        (Some(0), 0b00, &[][..])
+    } else {
+        compress_unicast(&src, &src_ll, contexts)
+    };
+    let mut dst_mcast = [0u8; 6];
+    let (dci, dam, dst_inline) = if hdr.dst_addr.is_multicast() {
+        iphc[1] |= 0x08;
+        match compress_multicast(&dst, &mut dst_mcast) {
+            (0b00, _) => (None, 0b00, &dst[..]),
+            (dam, len) => (None, dam, &dst_mcast[..len]),
+        }
+    } else {
+        compress_unicast(&dst, &dst_ll, contexts)
+    };
+    let cid = (sci.unwrap_or(0) << 4 | dci.unwrap_or(0)) as u8;
+    if cid != 0 {
+        iphc[1] |= 0x80;
+    }
+    iphc[1] |= (sci.is_some() as u8) << 6 | sam << 4 | (dci.is_some() as u8) << 2 | dam;
+    w.put(&iphc)?;
+    if cid != 0 {
+        w.put_u8(cid)?;
+    }
+
+    match tf {
+        0b00 => {
+            w.put(&[ecn << 6 | dscp])?;
+            w.put(&flow_label.to_be_bytes()[1..])?;
+        }
+        0b01 => {
+            let fl = flow_label.to_be_bytes();
+            w.put(&[ecn << 6 | fl[1], fl[2], fl[3]])?;
+        }
+        0b10 => w.put_u8(ecn << 6 | dscp)?,
+        _ => {}
+    }
+    if !udp {
+        w.put_u8(next_hdr)?;
+    }
+    if iphc[0] & 0x3 == 0 {
+        w.put_u8(hop_limit)?;
+    }
+    w.put(src_inline)?;
+    w.put(dst_inline)?;
+
+    if udp {
+        let src_port = u16::from_be_bytes([payload[0], payload[1]]);
+        let dst_port = u16::from_be_bytes([payload[2], payload[3]]);
+        match (src_port, dst_port) {
+            (0xf0b0..=0xf0bf, 0xf0b0..=0xf0bf) => {
+                w.put(&[
+                    NHC_UDP | 0b11,
+                    (src_port as u8) << 4 | (dst_port as u8 & 0xf),
+                ])?;
+            }
+            (_, 0xf000..=0xf0ff) => {
+                w.put_u8(NHC_UDP | 0b01)?;
+                w.put(&payload[..2])?;
+                w.put_u8(dst_port as u8)?;
+            }
+            (0xf000..=0xf0ff, _) => {
+                w.put(&[NHC_UDP | 0b10, src_port as u8])?;
+                w.put(&payload[2..4])?;
+            }
+            _ => {
+                w.put_u8(NHC_UDP)?;
+                w.put(&payload[..4])?;
+            }
+        }
+        w.put(&payload[6..8])?;
+        payload = &payload[8..];
+    }
+    w.put(payload)?;
+    Ok(w.pos())
+}
+
+/// Big-endian cursor over a received frame.
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], LowpanError> {
+        let out = self
+            .data
+            .get(self.pos..self.pos + len)
+            .ok_or(LowpanError::Malformed)?;
+        self.pos += len;
+        Ok(out)
+    }
+
+    fn u8(&mut self) -> Result<u8, LowpanError> {
+        Ok(self.take(1)?[0])
+    }
+}
+
+/// Decodes a unicast address, `ctx` being the prefix of the context used
+/// or `None` for link-local addresses.
+fn decompress_unicast(
+    r: &mut Reader<'_>,
+    ctx: Option<&[u8; 8]>,
+    mode: u8,
+    ll: &LinkAddr,
+) -> Result<[u8; 16], LowpanError> {
+    let mut addr = [0u8; 16];
+    if ctx.is_none() && mode == 0b00 {
+        addr.copy_from_slice(r.take(16)?);
+        return Ok(addr);
+    }
+    addr[..8].copy_from_slice(ctx.unwrap_or(&LINK_LOCAL_PREFIX));
+    match mode {
+        0b01 => addr[8..].copy_from_slice(r.take(8)?),
+        0b10 => {
+            addr[8..14].copy_from_slice(&[0, 0, 0, 0xff, 0xfe, 0]);
+            addr[14..].copy_from_slice(r.take(2)?);
+        }
+        _ => addr[8..].copy_from_slice(&ll.iid()),
+    }
+    Ok(addr)
+}
+
+/// Decodes a multicast address, `ctx` being the prefix of the context used
+/// for unicast-prefix-based addresses.
+fn decompress_multicast(
+    r: &mut Reader<'_>,
+    ctx: Option<&[u8; 8]>,
+    mode: u8,
+) -> Result<[u8; 16], LowpanError> {
+    let mut addr = [0u8; 16];
+    addr[0] = 0xff;
+    match (ctx, mode) {
+        (None, 0b00) => addr.copy_from_slice(r.take(16)?),
+        (None, 0b01) => {
+            addr[1] = r.u8()?;
+            addr[11..].copy_from_slice(r.take(5)?);
+        }
+        (None, 0b10) => {
+            addr[1] = r.u8()?;
+            addr[13..].copy_from_slice(r.take(3)?);
+        }
+        (None, _) => {
+            addr[1] = 0x02;
+            addr[15] = r.u8()?;
+        }
+        // Unicast-prefix-based address (RFC 3306), with a 64-bit prefix.
+        (Some(prefix), 0b00) => {
+            addr[1..3].copy_from_slice(r.take(2)?);
+            addr[3] = 64;
+            addr[4..12].copy_from_slice(prefix);
+            addr[12..].copy_from_slice(r.take(4)?);
+        }
+        (Some(_), _) => return Err(LowpanError::Unsupported),
+    }
+    Ok(addr)
+}
+
+/// Decompresses the 6LoWPAN frame payload `frame` into a full IPv6 packet
+/// written to `out`, returning its length. `src_ll` and `dst_ll` are the
+/// link-layer addresses of the frame, and `contexts` the prefixes shared
+/// with the compressor.
+pub fn decompress(
+    frame: &[u8],
+    src_ll: LinkAddr,
+    dst_ll: LinkAddr,
+    contexts: &[[u8; 8]],
+    out: &mut [u8],
+) -> Result<usize, LowpanError> {
+    let mut w = Writer::new(out);
+    match frame.first() {
+        Some(&DISPATCH_IPV6) => {
+            w.put(&frame[1..])?;
+            return Ok(w.pos());
+        }
+        Some(b) if b & 0xe0 == DISPATCH_IPHC => {}
+        Some(_) => return Err(LowpanError::Unsupported),
+        None => return Err(LowpanError::Malformed),
+    }
+    let mut r = Reader {
+        data: frame,
+        pos: 0,
+    };
+    let iphc = r.take(2)?;
+    let (sci, dci) = match iphc[1] & 0x80 {
+        0 => (0, 0),
+        _ => {
+            let cid = r.u8()?;
+            ((cid >> 4) as usize, (cid & 0xf) as usize)
+        }
+    };
+
+    // The traffic class is carried as ECN followed by DSCP.
+    let (tc, flow_label) = match (iphc[0] >> 3) & 0x3 {
+        0b00 => {
+            let b = r.take(4)?;
+            let fl = u32::from_be_bytes([0, b[1] & 0xf, b[2], b[3]]);
+            (b[0].rotate_left(2), fl)
+        }
+        0b01 => {
+            let b = r.take(3)?;
+            (b[0] >> 6, u32::from_be_bytes([0, b[0] & 0xf, b[1], b[2]]))
+        }
+        0b10 => {
+            let b = r.u8()?;
+            (b.rotate_left(2), 0)
+        }
+        _ => (0, 0),
+    };
+    let nhc = iphc[0] & 0x04 != 0;
+    let next_hdr = if nhc { IpProto::Udp as u8 } else { r.u8()? };
+    let hop_limit = match iphc[0] & 0x3 {
+        0b00 => r.u8()?,
+        0b01 => 1,
+        0b10 => 64,
+        _ => 255,
+    };
+
+    let context = |cid: usize| contexts.get(cid).ok_or(LowpanError::UnknownContext);
+    let sam = (iphc[1] >> 4) & 0x3;
+    let src = match iphc[1] & 0x40 {
+        0 => decompress_unicast(&mut r, None, sam, &src_ll)?,
+        _ if sam == 0b00 => [0; 16],
+        _ => decompress_unicast(&mut r, Some(context(sci)?), sam, &src_ll)?,
+    };
+    let dam = iphc[1] & 0x3;
+    let dac = iphc[1] & 0x04 != 0;
+    let dst = match (iphc[1] & 0x08 != 0, dac) {
+        (false, false) => decompress_unicast(&mut r, None, dam, &dst_ll)?,
+        (false, true) if dam == 0b00 => return Err(LowpanError::Unsupported),
+        (false, true) => decompress_unicast(&mut r, Some(context(dci)?), dam, &dst_ll)?,
+        (true, false) => decompress_multicast(&mut r, None, dam)?,
+        (true, true) => decompress_multicast(&mut r, Some(context(dci)?), dam)?,
+    };
+
+    w.put(&[
+        0x60 | tc >> 4,
+        (tc << 4) | (flow_label >> 16) as u8,
+        (flow_label >> 8) as u8,
+        flow_label as u8,
+        0,
+        0,
+        next_hdr,
+        hop_limit,
+    ])?;
+    w.put(&src)?;
+    w.put(&dst)?;
+
+    let mut elided_check = false;
+    if nhc {
+        let dispatch = r.u8()?;
+        if dispatch & 0xf8 != NHC_UDP {
+            return Err(LowpanError::Unsupported);
+        }
+        let (src_port, dst_port) = match dispatch & 0x3 {
+            0b00 => {
+                let b = r.take(4)?;
+                (
+                    u16::from_be_bytes([b[0], b[1]]),
+                    u16::from_be_bytes([b[2], b[3]]),
+                )
+            }
+            0b01 => {
+                let b = r.take(3)?;
+                (u16::from_be_bytes([b[0], b[1]]), 0xf000 | b[2] as u16)
+            }
+            0b10 => {
+                let b = r.take(3)?;
+                (0xf000 | b[0] as u16, u16::from_be_bytes([b[1], b[2]]))
+            }
+            _ => {
+                let b = r.u8()?;
+                (0xf0b0 | (b >> 4) as u16, 0xf0b0 | (b & 0xf) as u16)
+            }
+        };
+        let check = match dispatch & 0x4 {
+            0 => r.take(2)?,
+            _ => {
+                elided_check = true;
+                &[0, 0]
+            }
+        };
+        let udp_len = 8 + frame.len() - r.pos;
+        let udp_len = u16::try_from(udp_len).map_err(|_| LowpanError::Malformed)?;
+        w.put(&src_port.to_be_bytes())?;
+        w.put(&dst_port.to_be_bytes())?;
+        w.put(&udp_len.to_be_bytes())?;
+        w.put(check)?;
+    }
+    w.put(&frame[r.pos..])?;
+
+    let payload_len = w.pos() - Ipv6Hdr::LEN;
+    let payload_len = u16::try_from(payload_len).map_err(|_| LowpanError::Malformed)?;
+    w.set_u16(4, payload_len);
+    if elided_check {
+        let pseudo = checksum::pseudo_header_v6(
+            Ipv6Addr::from(src),
+            Ipv6Addr::from(dst),
+            IpProto::Udp,
+            payload_len as u32,
+        );
+        let check = match checksum::fold(checksum::sum(&w.written()[Ipv6Hdr::LEN..], pseudo)) {
+            0 => 0xffff,
+            check => check,
+        };
+        w.set_u16(Ipv6Hdr::LEN + 6, check);
+    }
+    Ok(w.pos())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compress, decompress, LinkAddr};
+
+    #[test]
+    fn test_iphc_udp() {
+        let src_ll = LinkAddr::Extended([0x00, 0x12, 0x4b, 0, 0, 0, 0, 1]);
+        let dst_ll = LinkAddr::Short(0x0002);
+        let contexts = [[0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0]];
+
+        // 2001:db8::212:4b00:0:1 -> fe80::ff:fe00:2, UDP 0xf0b1 -> 0xf0b2.
+        #[rustfmt::skip]
+        let packet = [
+            0x60, 0, 0, 0, 0, 12, 17, 64,
+            0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0x02, 0x12, 0x4b, 0, 0, 0, 0, 1,
+            0xfe, 0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xff, 0xfe, 0, 0, 2,
+            0xf0, 0xb1, 0xf0, 0xb2, 0, 12, 0x12, 0x34,
+            b'p', b'i', b'n', b'g',
+        ];
+        let mut frame = [0u8; 64];
+        let len = compress(&packet, src_ll, dst_ll, &contexts, &mut frame).unwrap();
+        // IPHC, NHC with compressed ports, checksum and payload.
+        assert_eq!(
+            &frame[..len],
+            &[0x7e, 0x73, 0xf3, 0x12, 0x12, 0x34, b'p', b'i', b'n', b'g']
+        );
+
+        let mut out = [0u8; 64];
+        let len = decompress(&frame[..len], src_ll, dst_ll, &contexts, &mut out).unwrap();
+        assert_eq!(&out[..len], &packet);
+
+        // Multicast destination, traffic class and flow label inline.
+        let mut packet = packet;
+        packet[..4].copy_from_slice(&[0x6b, 0x81, 0x23, 0x45]);
+        packet[24..40].copy_from_slice(&[0xff, 0x02, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x1a]);
+        let len = compress(&packet, src_ll, dst_ll, &contexts, &mut frame).unwrap();
+        let len = decompress(&frame[..len], src_ll, dst_ll, &contexts, &mut out).unwrap();
+        assert_eq!(&out[..len], &packet);
+    }
+}
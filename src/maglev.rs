@@ -0,0 +1,177 @@
+//! Maglev consistent hashing
+//! ([NSDI '16](https://research.google/pubs/maglev-a-fast-and-reliable-software-network-load-balancer/)),
+//! selecting the backend of a flow in a load balancer.
+//!
+//! [`Maglev`] fills a lookup table with the indices of the backends so
+//! that each backend owns about the same number of entries and changing
+//! the backend list moves few entries. A flow is mapped to the entry of
+//! its [`FlowKey::keyed_hash`], so that every load balancer sharing the
+//! backend list and the hash key makes the same decision, without state.
+
+use alloc::{vec, vec::Vec};
+
+use crate::flow::{siphash, FlowKey};
+
+/// Size of the lookup table recommended for up to a few hundred backends.
+pub const DEFAULT_TABLE_SIZE: usize = 65537;
+
+/// Whether `n` is prime.
+const fn is_prime(n: usize) -> bool {
+    if n < 2 {
+        return false;
+    }
+    let mut i = 2;
+    while i * i <= n {
+        if n.is_multiple_of(i) {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Maglev lookup table.
+///
+/// ```
+/// use ether_packet::{flow::FlowKey, ip::IpProto, maglev::{Maglev, DEFAULT_TABLE_SIZE}};
+///
+/// let backends = ["10.1.0.1", "10.1.0.2", "10.1.0.3"];
+/// let maglev = Maglev::new(&backends, DEFAULT_TABLE_SIZE, [7, 7]);
+/// let key = FlowKey {
+///     src_addr: "192.0.2.1".parse().unwrap(),
+///     dst_addr: "198.51.100.80".parse().unwrap(),
+///     proto: IpProto::Tcp,
+///     src_port: 40000,
+///     dst_port: 80,
+/// };
+/// let backend = maglev.select(&key).unwrap();
+/// assert!(backend < backends.len());
+/// assert_eq!(maglev.select(&key), Some(backend));
+/// ```
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Maglev {
+    table: Vec<u32>,
+    backends: usize,
+    key: [u64; 2],
+}
+
+impl Maglev {
+    /// Builds the table of `size` entries for `backends`, identified by
+    /// their name, e.g. an address or a host name, and hashing flows with
+    /// `key`.
+    ///
+    /// The entries of a backend only depend on its name, not on its
+    /// position in the list.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is not a prime number or is smaller than the
+    /// number of backends.
+    pub fn new<B: AsRef<[u8]>>(backends: &[B], size: usize, key: [u64; 2]) -> Self {
+        assert!(is_prime(size), "Maglev table size must be prime");
+        assert!(backends.len() <= size, "more backends than table entries");
+        if backends.is_empty() {
+            return Self {
+                table: Vec::new(),
+                backends: 0,
+                key,
+            };
+        }
+        let mut table = vec![u32::MAX; size];
+        // Permutation of each backend, as its current position and skip.
+        let mut permutations: Vec<(usize, usize)> = backends
+            .iter()
+            .map(|name| {
+                let offset = siphash(&[0, 0], name.as_ref()) as usize % size;
+                let skip = siphash(&[1, 1], name.as_ref()) as usize % (size - 1) + 1;
+                (offset, skip)
+            })
+            .collect();
+        let mut filled = 0;
+        'fill: loop {
+            for (index, (next, skip)) in permutations.iter_mut().enumerate() {
+                while table[*next] != u32::MAX {
+                    *next = (*next + *skip) % size;
+                }
+                table[*next] = index as u32;
+                *next = (*next + *skip) % size;
+                filled += 1;
+                if filled == size {
+                    break 'fill;
+                }
+            }
+        }
+        Self {
+            table,
+            backends: backends.len(),
+            key,
+        }
+    }
+
+    /// Number of backends.
+    #[inline]
+    pub fn backends(&self) -> usize {
+        self.backends
+    }
+
+    /// Number of entries of the table, 0 without backends.
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.table.len()
+    }
+
+    /// Index of the backend owning the entry of `hash`, `None` without
+    /// backends.
+    #[inline]
+    pub fn lookup(&self, hash: u64) -> Option<usize> {
+        let entry = hash.checked_rem(self.table.len() as u64)?;
+        Some(self.table[entry as usize] as usize)
+    }
+
+    /// Index of the backend of the flow `key`.
+    #[inline]
+    pub fn select(&self, key: &FlowKey) -> Option<usize> {
+        self.lookup(key.keyed_hash(&self.key))
+    }
+
+    /// Number of entries owned by each backend.
+    pub fn shares(&self) -> Vec<usize> {
+        let mut shares = vec![0; self.backends];
+        for &index in &self.table {
+            shares[index as usize] += 1;
+        }
+        shares
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{format, string::String, vec::Vec};
+
+    use super::{is_prime, Maglev};
+
+    #[test]
+    fn test_maglev() {
+        assert!(is_prime(65537) && is_prime(251) && !is_prime(65535) && !is_prime(1));
+        let empty: [&str; 0] = [];
+        assert_eq!(Maglev::new(&empty, 13, [0, 0]).lookup(42), None);
+
+        let names: Vec<String> = (0..10).map(|i| format!("backend-{i}")).collect();
+        let maglev = Maglev::new(&names, 65537, [0, 0]);
+        assert_eq!((maglev.backends(), maglev.size()), (10, 65537));
+        let shares = maglev.shares();
+        assert!(shares.iter().all(|&share| (6400..=6700).contains(&share)));
+
+        // Removing a backend mostly moves its own entries.
+        let mut fewer = names.clone();
+        fewer.remove(3);
+        let other = Maglev::new(&fewer, 65537, [0, 0]);
+        let moved = (0..65537u64)
+            .filter(|&hash| {
+                let before = &names[maglev.lookup(hash).unwrap()];
+                before != "backend-3" && *before != fewer[other.lookup(hash).unwrap()]
+            })
+            .count();
+        assert!(moved < 65537 / 50, "{moved} entries moved");
+    }
+}
@@ -0,0 +1,114 @@
+//! Capture context carried alongside the headers of a packet.
+//!
+//! The headers of this crate are zero-copy views of the packet bytes, which
+//! do not record when and where a packet was seen. [`PacketMeta`] holds that
+//! information, as reported by the capture source (pcap, `AF_PACKET`, DPDK,
+//! ...), so that it can be passed along with the packet data.
+
+use core::time::Duration;
+
+use crate::sll::{Sll2Hdr, SllHdr, SllPacketType};
+
+/// Direction of a packet relative to the capturing host.
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum Direction {
+    /// Not reported by the capture source.
+    #[default]
+    Unknown,
+    /// Received by the host, including packets for other hosts seen in
+    /// promiscuous mode.
+    Inbound,
+    /// Sent by the host.
+    Outbound,
+}
+
+impl From<SllPacketType> for Direction {
+    fn from(value: SllPacketType) -> Self {
+        match value {
+            SllPacketType::Outgoing => Direction::Outbound,
+            _ => Direction::Inbound,
+        }
+    }
+}
+
+/// Metadata of a captured packet.
+///
+/// Every field is optional since capture sources report different subsets,
+/// fields which are not known are left to their default value.
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct PacketMeta {
+    /// Capture time, as a duration since the UNIX epoch.
+    pub timestamp: Option<Duration>,
+    /// Index of the interface the packet was captured on.
+    pub ifindex: Option<u32>,
+    pub direction: Direction,
+    /// VLAN tag control information removed from the frame by the NIC or
+    /// the kernel before capture.
+    pub vlan_stripped: Option<u16>,
+    /// Receive side scaling hash computed by the NIC.
+    pub rss_hash: Option<u32>,
+}
+
+impl PacketMeta {
+    /// Metadata of a packet captured at `timestamp`.
+    #[inline]
+    pub const fn new(timestamp: Duration) -> Self {
+        Self {
+            timestamp: Some(timestamp),
+            ifindex: None,
+            direction: Direction::Unknown,
+            vlan_stripped: None,
+            rss_hash: None,
+        }
+    }
+
+    /// VLAN identifier of the stripped tag, if any.
+    #[inline]
+    pub const fn stripped_vid(&self) -> Option<u16> {
+        match self.vlan_stripped {
+            Some(tci) => Some(tci & 0x0fff),
+            None => None,
+        }
+    }
+
+    /// Fills in the direction recorded in a Linux cooked capture header.
+    pub fn update_from_sll(&mut self, sll: &SllHdr) {
+        if let Some(packet_type) = sll.packet_type() {
+            self.direction = packet_type.into();
+        }
+    }
+
+    /// Fills in the direction and the interface index recorded in a Linux
+    /// cooked capture header, version 2.
+    pub fn update_from_sll2(&mut self, sll: &Sll2Hdr) {
+        if let Some(packet_type) = sll.packet_type() {
+            self.direction = packet_type.into();
+        }
+        self.ifindex = Some(sll.if_index());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use super::{Direction, PacketMeta};
+    use crate::{header::Header, sll::Sll2Hdr};
+
+    #[test]
+    fn test_meta_from_sll2() {
+        let bytes = [
+            0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x07, 0x00, 0x01, 0x04, 0x06, 0x00, 0x11,
+            0x22, 0x33, 0x44, 0x55, 0x00, 0x00,
+        ];
+        let mut meta = PacketMeta::new(Duration::from_micros(1_700_000_000_000_001));
+        meta.vlan_stripped = Some(0x2064);
+        meta.update_from_sll2(Sll2Hdr::from_bytes(&bytes).unwrap());
+        assert_eq!(meta.ifindex, Some(7));
+        assert_eq!(meta.direction, Direction::Outbound);
+        assert_eq!(meta.stripped_vid(), Some(100));
+        assert_eq!(PacketMeta::default().direction, Direction::Unknown);
+    }
+}
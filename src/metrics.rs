@@ -0,0 +1,347 @@
+//! Histograms of packet sizes and inter-arrival times, with percentile
+//! queries.
+//!
+//! [`Histogram`] counts values in fixed buckets, either powers of two or
+//! the IMIX size classes, and [`TrafficStats`] feeds a size and an
+//! inter-arrival histogram from the packets of a capture loop.
+
+use core::time::Duration;
+
+use crate::meta::PacketMeta;
+
+/// Upper bounds of the IMIX size classes, in bytes, the last bucket
+/// counting the jumbo frames.
+pub const IMIX_BOUNDS: [u64; 6] = [64, 127, 255, 511, 1023, 1518];
+
+/// Bucket layout of a [`Histogram`].
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum Buckets {
+    /// Bucket 0 counts zeros and bucket `i` the values from `2^(i-1)` to
+    /// `2^i - 1`, covering the whole `u64` range.
+    Log2,
+    /// Increasing inclusive upper bounds, at most 64, followed by a bucket
+    /// for the larger values.
+    Bounds(&'static [u64]),
+}
+
+impl Buckets {
+    /// Number of buckets.
+    #[inline]
+    pub const fn count(&self) -> usize {
+        match self {
+            Buckets::Log2 => 65,
+            Buckets::Bounds(bounds) => bounds.len() + 1,
+        }
+    }
+
+    /// Index of the bucket counting `value`.
+    #[inline]
+    fn index(&self, value: u64) -> usize {
+        match self {
+            Buckets::Log2 => (u64::BITS - value.leading_zeros()) as usize,
+            Buckets::Bounds(bounds) => bounds.partition_point(|&bound| bound < value),
+        }
+    }
+
+    /// Largest value counted by bucket `index`.
+    #[inline]
+    fn upper_bound(&self, index: usize) -> u64 {
+        match self {
+            Buckets::Log2 => match index {
+                0 => 0,
+                64 => u64::MAX,
+                index => (1 << index) - 1,
+            },
+            Buckets::Bounds(bounds) => bounds.get(index).copied().unwrap_or(u64::MAX),
+        }
+    }
+}
+
+/// Histogram of `u64` values in fixed buckets, tracking their count, sum,
+/// minimum and maximum.
+///
+/// ```
+/// use ether_packet::metrics::Histogram;
+///
+/// let mut sizes = Histogram::imix();
+/// for len in [64, 64, 576, 1500] {
+///     sizes.record(len);
+/// }
+/// assert_eq!(sizes.count(), 4);
+/// assert_eq!(sizes.percentile(50.0), Some(64));
+/// // The 75th percentile falls in the 512 to 1023 bytes class.
+/// assert_eq!(sizes.percentile(75.0), Some(1023));
+/// assert_eq!(sizes.percentile(100.0), Some(1500));
+/// ```
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Histogram {
+    buckets: Buckets,
+    counts: [u64; 65],
+    count: u64,
+    sum: u64,
+    min: u64,
+    max: u64,
+}
+
+impl Histogram {
+    /// Creates an empty histogram.
+    ///
+    /// # Panics
+    ///
+    /// Panics if more than 64 bounds are given.
+    pub const fn new(buckets: Buckets) -> Self {
+        assert!(buckets.count() <= 65, "too many histogram buckets");
+        Self {
+            buckets,
+            counts: [0; 65],
+            count: 0,
+            sum: 0,
+            min: u64::MAX,
+            max: 0,
+        }
+    }
+
+    /// Histogram with power-of-two buckets.
+    #[inline]
+    pub const fn log2() -> Self {
+        Self::new(Buckets::Log2)
+    }
+
+    /// Histogram of packet sizes in the IMIX classes.
+    #[inline]
+    pub const fn imix() -> Self {
+        Self::new(Buckets::Bounds(&IMIX_BOUNDS))
+    }
+
+    #[inline]
+    pub const fn buckets(&self) -> Buckets {
+        self.buckets
+    }
+
+    #[inline]
+    pub fn record(&mut self, value: u64) {
+        self.record_n(value, 1);
+    }
+
+    /// Records `n` occurrences of `value`.
+    pub fn record_n(&mut self, value: u64, n: u64) {
+        if n == 0 {
+            return;
+        }
+        self.counts[self.buckets.index(value)] += n;
+        self.count += n;
+        self.sum = self.sum.saturating_add(value.saturating_mul(n));
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+    }
+
+    /// Adds the values of `other`, which must have the same buckets.
+    pub fn merge(&mut self, other: &Histogram) {
+        debug_assert_eq!(self.buckets, other.buckets);
+        for (count, other) in self.counts.iter_mut().zip(other.counts) {
+            *count += other;
+        }
+        self.count += other.count;
+        self.sum = self.sum.saturating_add(other.sum);
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new(self.buckets);
+    }
+
+    /// Number of recorded values.
+    #[inline]
+    pub const fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Sum of the recorded values, saturating at `u64::MAX`.
+    #[inline]
+    pub const fn sum(&self) -> u64 {
+        self.sum
+    }
+
+    #[inline]
+    pub const fn min(&self) -> Option<u64> {
+        if self.count == 0 {
+            None
+        } else {
+            Some(self.min)
+        }
+    }
+
+    #[inline]
+    pub const fn max(&self) -> Option<u64> {
+        if self.count == 0 {
+            None
+        } else {
+            Some(self.max)
+        }
+    }
+
+    #[inline]
+    pub fn mean(&self) -> Option<f64> {
+        (self.count != 0).then(|| self.sum as f64 / self.count as f64)
+    }
+
+    /// Upper bound and count of each bucket.
+    pub fn iter(&self) -> impl Iterator<Item = (u64, u64)> + '_ {
+        (0..self.buckets.count()).map(|i| (self.buckets.upper_bound(i), self.counts[i]))
+    }
+
+    /// Estimates the `p`-th percentile, `p` going from 0 to 100, as the
+    /// upper bound of the bucket holding it, capped to the maximum.
+    ///
+    /// Returns `None` if the histogram is empty.
+    pub fn percentile(&self, p: f64) -> Option<u64> {
+        if self.count == 0 {
+            return None;
+        }
+        // Rank of the percentile, rounded up without `f64::ceil`, missing
+        // from `core`.
+        let exact = p.clamp(0.0, 100.0) / 100.0 * self.count as f64;
+        let rank = (exact as u64 + ((exact as u64 as f64) < exact) as u64).max(1);
+        let mut seen = 0;
+        let (index, _) = self.counts.iter().enumerate().find(|(_, &count)| {
+            seen += count;
+            seen >= rank
+        })?;
+        Some(self.buckets.upper_bound(index).clamp(self.min, self.max))
+    }
+}
+
+impl Default for Histogram {
+    #[inline]
+    fn default() -> Self {
+        Self::log2()
+    }
+}
+
+/// Packet size and inter-arrival time histograms of a packet stream,
+/// with its volume.
+///
+/// Inter-arrival times are recorded in nanoseconds, between packets with
+/// a timestamp in their [`PacketMeta`].
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct TrafficStats {
+    /// Wire lengths of the packets, in bytes.
+    pub sizes: Histogram,
+    /// Time between consecutive packets, in nanoseconds.
+    pub inter_arrival: Histogram,
+    bytes: u64,
+    first: Option<Duration>,
+    last: Option<Duration>,
+}
+
+impl TrafficStats {
+    /// Stats with power-of-two size buckets.
+    #[inline]
+    pub const fn new() -> Self {
+        Self::with_sizes(Histogram::log2())
+    }
+
+    /// Stats using `sizes` as the size histogram, e.g.
+    /// [`Histogram::imix`].
+    #[inline]
+    pub const fn with_sizes(sizes: Histogram) -> Self {
+        Self {
+            sizes,
+            inter_arrival: Histogram::log2(),
+            bytes: 0,
+            first: None,
+            last: None,
+        }
+    }
+
+    /// Records a packet of `wire_len` bytes, the length before any
+    /// truncation by the capture.
+    ///
+    /// Packets going back in time are not counted in the inter-arrival
+    /// histogram.
+    pub fn record(&mut self, wire_len: usize, meta: &PacketMeta) {
+        self.sizes.record(wire_len as u64);
+        self.bytes += wire_len as u64;
+        let Some(timestamp) = meta.timestamp else {
+            return;
+        };
+        if let Some(gap) = self.last.and_then(|last| timestamp.checked_sub(last)) {
+            self.inter_arrival.record(gap.as_nanos() as u64);
+        }
+        self.first.get_or_insert(timestamp);
+        self.last = Some(self.last.map_or(timestamp, |last| last.max(timestamp)));
+    }
+
+    #[inline]
+    pub const fn packets(&self) -> u64 {
+        self.sizes.count()
+    }
+
+    #[inline]
+    pub const fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    /// Time between the first and the last timestamped packets.
+    #[inline]
+    pub fn duration(&self) -> Option<Duration> {
+        self.last?.checked_sub(self.first?)
+    }
+
+    /// Average bitrate over [`duration`](Self::duration), in bits per
+    /// second, `None` before two packets are timestamped apart.
+    pub fn bitrate(&self) -> Option<f64> {
+        let secs = self.duration()?.as_secs_f64();
+        (secs > 0.0).then(|| self.bytes as f64 * 8.0 / secs)
+    }
+}
+
+impl Default for TrafficStats {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use super::{Histogram, TrafficStats};
+    use crate::meta::PacketMeta;
+
+    #[test]
+    fn test_histograms() {
+        let mut h = Histogram::log2();
+        assert_eq!(h.percentile(50.0), None);
+        for value in 1..=100 {
+            h.record(value);
+        }
+        assert_eq!((h.min(), h.max(), h.sum()), (Some(1), Some(100), 5050));
+        assert_eq!(h.percentile(0.0), Some(1));
+        assert_eq!(h.percentile(50.0), Some(63));
+        assert_eq!(h.percentile(99.0), Some(100));
+        let buckets: [(u64, u64); 3] = core::array::from_fn(|i| h.iter().nth(i).unwrap());
+        assert_eq!(buckets, [(0, 0), (1, 1), (3, 2)]);
+
+        let mut other = Histogram::log2();
+        other.record_n(0, 100);
+        h.merge(&other);
+        assert_eq!((h.count(), h.min()), (200, Some(0)));
+        assert_eq!(h.percentile(50.0), Some(0));
+
+        let mut stats = TrafficStats::with_sizes(Histogram::imix());
+        for (i, len) in [64, 1518, 64, 9000].into_iter().enumerate() {
+            let meta = PacketMeta::new(Duration::from_micros(10 * i as u64));
+            stats.record(len, &meta);
+        }
+        stats.record(64, &PacketMeta::default());
+        assert_eq!((stats.packets(), stats.bytes()), (5, 10710));
+        assert_eq!(stats.sizes.iter().last(), Some((u64::MAX, 1)));
+        assert_eq!(stats.inter_arrival.count(), 3);
+        assert_eq!(stats.inter_arrival.max(), Some(10_000));
+        assert_eq!(stats.duration(), Some(Duration::from_micros(30)));
+        assert_eq!(stats.bitrate(), Some(10710.0 * 8.0 / 30e-6));
+    }
+}
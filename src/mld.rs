@@ -0,0 +1,247 @@
+//! Multicast Listener Discovery queries, MLDv1
+//! ([RFC 2710](https://datatracker.ietf.org/doc/html/rfc2710)) and MLDv2
+//! ([RFC 3810](https://datatracker.ietf.org/doc/html/rfc3810)), the IPv6
+//! counterpart of the IGMP queries of [`igmp`](crate::igmp).
+
+use core::net::Ipv6Addr;
+
+use crate::{
+    builder::{BuildError, Writer},
+    checksum,
+    icmpv6::Icmpv6Type,
+    igmp,
+    ip::{IpProto, Ipv6Hdr},
+    ndp::ALL_NODES,
+};
+
+/// Length of the MLDv1 message, also the fixed part of MLDv2 queries.
+const MLD_LEN: usize = 24;
+
+/// Length of the Hop-by-Hop Options header carrying the Router Alert option.
+const HOP_BY_HOP_LEN: usize = 8;
+
+/// Offset of the MLD message, following the IPv6 and Hop-by-Hop headers.
+const MLD_OFFSET: usize = Ipv6Hdr::LEN + HOP_BY_HOP_LEN;
+
+/// Encodes a maximum response delay, in milliseconds, as the Maximum
+/// Response Code of MLDv2 queries
+/// ([RFC 3810 5.1.3](https://datatracker.ietf.org/doc/html/rfc3810#section-5.1.3)).
+/// Delays from 32768 on use the floating-point format, rounded down, and
+/// are capped at 8387584.
+pub const fn max_resp_code(millis: u32) -> u16 {
+    if millis < 0x8000 {
+        return millis as u16;
+    }
+    let mut exp = 0;
+    while exp < 7 && millis >> (exp + 3) > 0x1fff {
+        exp += 1;
+    }
+    let mant = millis >> (exp + 3);
+    if mant > 0x1fff {
+        return 0xffff;
+    }
+    0x8000 | (exp as u16) << 12 | (mant as u16 & 0x0fff)
+}
+
+/// Decodes the Maximum Response Code of MLDv2 queries, in milliseconds.
+pub const fn max_resp_delay(code: u16) -> u32 {
+    if code < 0x8000 {
+        return code as u32;
+    }
+    ((code as u32 & 0x0fff) | 0x1000) << (((code >> 12) & 0x7) + 3)
+}
+
+/// Writes the IPv6 header, with a hop limit of 1, and the Hop-by-Hop
+/// Options header carrying the Router Alert option, then the fixed part of
+/// the query.
+fn put_query(
+    w: &mut Writer<'_>,
+    src: Ipv6Addr,
+    group: Ipv6Addr,
+    max_resp_code: u16,
+) -> Result<(), BuildError> {
+    let dst = if group.is_unspecified() {
+        ALL_NODES
+    } else {
+        group
+    };
+    let hdr = w.reserve(Ipv6Hdr::LEN)?;
+    hdr[0] = 0x60;
+    hdr[6] = IpProto::HopOpt as u8;
+    hdr[7] = 1;
+    hdr[8..24].copy_from_slice(&src.octets());
+    hdr[24..40].copy_from_slice(&dst.octets());
+    // Router Alert with the MLD value 0, then a 2-byte PadN.
+    w.put(&[IpProto::Ipv6Icmp as u8, 0, 0x05, 0x02, 0, 0, 0x01, 0x00])?;
+    let msg = w.reserve(MLD_LEN)?;
+    msg[0] = Icmpv6Type::MulticastListenerQuery as u8;
+    msg[4..6].copy_from_slice(&max_resp_code.to_be_bytes());
+    msg[8..24].copy_from_slice(&group.octets());
+    Ok(())
+}
+
+/// Fills in the payload length and the ICMPv6 checksum, returning the
+/// length of the packet.
+fn finish(w: &mut Writer<'_>, src: Ipv6Addr, dst: Ipv6Addr) -> usize {
+    let len = w.pos();
+    w.set_u16(4, (len - Ipv6Hdr::LEN) as u16);
+    let msg = &w.written()[MLD_OFFSET..];
+    let check = checksum::l4_checksum_v6(src, dst, IpProto::Ipv6Icmp, msg);
+    w.set_u16(MLD_OFFSET + 2, check);
+    len
+}
+
+#[inline]
+fn dst_addr(w: &Writer<'_>) -> Ipv6Addr {
+    let dst: [u8; 16] = w.written()[24..40].try_into().unwrap_or_default();
+    dst.into()
+}
+
+/// Writes an MLDv1 Multicast Listener Query, with its IPv6 headers, into
+/// `buf` and returns its length. `src` must be a link-local address. The
+/// query is a General Query, sent to all nodes, if `group` is unspecified,
+/// otherwise a Multicast-Address-Specific Query sent to the group.
+pub fn write_mldv1_query(
+    buf: &mut [u8],
+    src: Ipv6Addr,
+    group: Ipv6Addr,
+    max_resp_delay: u16,
+) -> Result<usize, BuildError> {
+    let mut w = Writer::new(buf);
+    put_query(&mut w, src, group, max_resp_delay)?;
+    let dst = dst_addr(&w);
+    Ok(finish(&mut w, src, dst))
+}
+
+/// Builds an MLDv2 Multicast Listener Query, with its IPv6 headers, into a
+/// caller-provided buffer.
+///
+/// ```
+/// use core::net::Ipv6Addr;
+/// use ether_packet::mld::MldV2QueryBuilder;
+///
+/// let mut buf = [0u8; 128];
+/// let src = "fe80::1".parse().unwrap();
+/// let mut query = MldV2QueryBuilder::new(&mut buf, src, Ipv6Addr::UNSPECIFIED, 10000).unwrap();
+/// query.robustness(2);
+/// assert_eq!(query.finish(), 40 + 8 + 28);
+/// ```
+pub struct MldV2QueryBuilder<'a> {
+    w: Writer<'a>,
+    src: Ipv6Addr,
+    num_sources: u16,
+}
+
+impl<'a> MldV2QueryBuilder<'a> {
+    /// Starts a General Query if `group` is unspecified, a
+    /// Multicast-Address-Specific Query otherwise, `max_resp_delay` being in
+    /// milliseconds. `src` must be a link-local address.
+    pub fn new(
+        buf: &'a mut [u8],
+        src: Ipv6Addr,
+        group: Ipv6Addr,
+        max_resp_delay: u32,
+    ) -> Result<Self, BuildError> {
+        let mut w = Writer::new(buf);
+        put_query(&mut w, src, group, max_resp_code(max_resp_delay))?;
+        w.reserve(4)?;
+        let mut query = Self {
+            w,
+            src,
+            num_sources: 0,
+        };
+        query.robustness(2).query_interval(125);
+        Ok(query)
+    }
+
+    #[inline]
+    fn msg_mut(&mut self) -> &mut [u8] {
+        &mut self.w.written_mut()[MLD_OFFSET..]
+    }
+
+    /// Sets the **S** flag, telling routers to suppress their timer updates.
+    pub fn suppress_router_processing(&mut self, val: bool) -> &mut Self {
+        let msg = self.msg_mut();
+        msg[24] = (msg[24] & !0x08) | (val as u8) << 3;
+        self
+    }
+
+    /// Sets the Querier's Robustness Variable, 0 if it exceeds 7.
+    pub fn robustness(&mut self, qrv: u8) -> &mut Self {
+        let qrv = if qrv > 7 { 0 } else { qrv };
+        let msg = self.msg_mut();
+        msg[24] = (msg[24] & !0x07) | qrv;
+        self
+    }
+
+    /// Sets the Querier's Query Interval, in seconds, encoded like the IGMPv3
+    /// Max Resp Code.
+    pub fn query_interval(&mut self, secs: u32) -> &mut Self {
+        self.msg_mut()[25] = igmp::max_resp_code(secs);
+        self
+    }
+
+    /// Appends a source, making the query
+    /// Multicast-Address-and-Source-Specific.
+    pub fn source(&mut self, addr: Ipv6Addr) -> Result<&mut Self, BuildError> {
+        let num_sources = self
+            .num_sources
+            .checked_add(1)
+            .ok_or(BuildError::FieldOverflow)?;
+        if self.w.pos() + 16 - Ipv6Hdr::LEN > u16::MAX as usize {
+            return Err(BuildError::FieldOverflow);
+        }
+        self.w.put(&addr.octets())?;
+        self.num_sources = num_sources;
+        Ok(self)
+    }
+
+    /// Fills in the number of sources, the payload length and the checksum,
+    /// returning the length of the packet.
+    pub fn finish(mut self) -> usize {
+        self.w.set_u16(MLD_OFFSET + 26, self.num_sources);
+        let dst = dst_addr(&self.w);
+        finish(&mut self.w, self.src, dst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::net::Ipv6Addr;
+
+    use super::{max_resp_code, max_resp_delay, write_mldv1_query, MldV2QueryBuilder};
+    use crate::{checksum, ip::IpProto, packet::Packet};
+
+    #[test]
+    fn test_mld_queries() {
+        assert_eq!(max_resp_code(10000), 10000);
+        assert_eq!(max_resp_delay(max_resp_code(60001)), 60000);
+        assert_eq!(max_resp_code(u32::MAX), 0xffff);
+        assert_eq!(max_resp_delay(0xffff), 8387584);
+
+        let src: Ipv6Addr = "fe80::1".parse().unwrap();
+        let group: Ipv6Addr = "ff15::1".parse().unwrap();
+        let mut buf = [0u8; 128];
+        let mut query = MldV2QueryBuilder::new(&mut buf, src, group, 1000).unwrap();
+        query.suppress_router_processing(true).source(src).unwrap();
+        let len = query.finish();
+        assert_eq!(len, 48 + 28 + 16);
+        assert_eq!(&buf[48 + 24..48 + 28], &[0x0a, 125, 0, 1]);
+        assert!(checksum::verify_l4_v6(
+            src,
+            group,
+            IpProto::Ipv6Icmp,
+            &buf[48..len]
+        ));
+
+        let len = write_mldv1_query(&mut buf, src, Ipv6Addr::UNSPECIFIED, 1000).unwrap();
+        assert_eq!(len, 48 + 24);
+        let mut frame = [0u8; 14 + 72];
+        frame[12..14].copy_from_slice(&[0x86, 0xdd]);
+        frame[14..].copy_from_slice(&buf[..len]);
+        let packet = Packet::parse(&frame).unwrap();
+        assert_eq!(packet.proto(), Some(IpProto::Ipv6Icmp));
+        assert_eq!(packet.ipv6().unwrap().dst_addr, super::ALL_NODES);
+        assert_eq!(packet.ipv6().unwrap().hop_limit, 1);
+    }
+}
@@ -0,0 +1,215 @@
+//! MikroTik Neighbor Discovery Protocol, carried over UDP port 5678.
+//!
+//! MNDP has no published specification; the layout follows what RouterOS
+//! devices send and what the Wireshark dissector decodes.
+
+use core::{
+    mem,
+    net::{Ipv4Addr, Ipv6Addr},
+    str,
+};
+
+use crate::{
+    header::{impl_header, Header},
+    types::U16,
+};
+
+pub const MNDP_PORT: u16 = 5678;
+
+/// MNDP packet header, followed by TLVs.
+/// ```text
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |            Header             |        Sequence Number        |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct MndpHdr {
+    pub header: U16,
+    pub seq_no: U16,
+}
+
+impl MndpHdr {
+    pub const LEN: usize = mem::size_of::<MndpHdr>();
+
+    /// Parses the MNDP packet stored in `packet`, returning its header and an
+    /// iterator over its TLVs.
+    pub fn parse(packet: &[u8]) -> Option<(&MndpHdr, MndpTlvs<'_>)> {
+        let hdr = MndpHdr::from_bytes(packet)?;
+        Some((
+            hdr,
+            MndpTlvs {
+                data: &packet[Self::LEN..],
+            },
+        ))
+    }
+}
+
+/// MNDP TLV types.
+#[repr(u16)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum MndpTlvType {
+    MacAddress = 1,
+    Identity = 5,
+    Version = 7,
+    Platform = 8,
+    Uptime = 10,
+    SoftwareId = 11,
+    Board = 12,
+    Unpack = 14,
+    Ipv6Address = 15,
+    InterfaceName = 16,
+    Ipv4Address = 17,
+}
+
+impl TryFrom<u16> for MndpTlvType {
+    type Error = ();
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(MndpTlvType::MacAddress),
+            5 => Ok(MndpTlvType::Identity),
+            7 => Ok(MndpTlvType::Version),
+            8 => Ok(MndpTlvType::Platform),
+            10 => Ok(MndpTlvType::Uptime),
+            11 => Ok(MndpTlvType::SoftwareId),
+            12 => Ok(MndpTlvType::Board),
+            14 => Ok(MndpTlvType::Unpack),
+            15 => Ok(MndpTlvType::Ipv6Address),
+            16 => Ok(MndpTlvType::InterfaceName),
+            17 => Ok(MndpTlvType::Ipv4Address),
+            _ => Err(()),
+        }
+    }
+}
+
+/// MNDP TLV header.
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct MndpTlvHdr {
+    pub tlv_type: U16,
+    /// Length of the value, in octets.
+    pub length: U16,
+}
+
+impl MndpTlvHdr {
+    pub const LEN: usize = mem::size_of::<MndpTlvHdr>();
+
+    #[inline]
+    pub fn tlv_type(&self) -> Option<MndpTlvType> {
+        self.tlv_type.to_bits().try_into().ok()
+    }
+}
+
+impl_header!(MndpHdr, MndpTlvHdr);
+
+/// An MNDP TLV.
+#[derive(Debug, Copy, Clone)]
+pub struct MndpTlv<'a> {
+    pub hdr: &'a MndpTlvHdr,
+    pub value: &'a [u8],
+}
+
+impl<'a> MndpTlv<'a> {
+    /// MAC address of the announcing interface.
+    pub fn mac_address(&self) -> Option<[u8; 6]> {
+        self.value_of(MndpTlvType::MacAddress)?.try_into().ok()
+    }
+
+    /// System identity (host name) of the device.
+    pub fn identity(&self) -> Option<&'a str> {
+        self.str_of(MndpTlvType::Identity)
+    }
+
+    /// RouterOS version.
+    pub fn version(&self) -> Option<&'a str> {
+        self.str_of(MndpTlvType::Version)
+    }
+
+    /// Platform name, e.g. `MikroTik`.
+    pub fn platform(&self) -> Option<&'a str> {
+        self.str_of(MndpTlvType::Platform)
+    }
+
+    /// Name of the announcing interface.
+    pub fn interface_name(&self) -> Option<&'a str> {
+        self.str_of(MndpTlvType::InterfaceName)
+    }
+
+    /// Uptime of the device, in seconds.
+    pub fn uptime(&self) -> Option<u32> {
+        // Unlike the TLV header, the uptime is sent little-endian.
+        let value = self.value_of(MndpTlvType::Uptime)?;
+        Some(u32::from_le_bytes(value.try_into().ok()?))
+    }
+
+    pub fn ipv4_address(&self) -> Option<Ipv4Addr> {
+        let value: [u8; 4] = self.value_of(MndpTlvType::Ipv4Address)?.try_into().ok()?;
+        Some(Ipv4Addr::from(value))
+    }
+
+    pub fn ipv6_address(&self) -> Option<Ipv6Addr> {
+        let value: [u8; 16] = self.value_of(MndpTlvType::Ipv6Address)?.try_into().ok()?;
+        Some(Ipv6Addr::from(value))
+    }
+
+    fn str_of(&self, kind: MndpTlvType) -> Option<&'a str> {
+        str::from_utf8(self.value_of(kind)?).ok()
+    }
+
+    fn value_of(&self, kind: MndpTlvType) -> Option<&'a [u8]> {
+        (self.hdr.tlv_type.to_bits() == kind as u16).then_some(self.value)
+    }
+}
+
+/// Iterator over the TLVs of an MNDP packet.
+#[derive(Debug, Clone)]
+pub struct MndpTlvs<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Iterator for MndpTlvs<'a> {
+    type Item = MndpTlv<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let hdr = MndpTlvHdr::from_bytes(self.data)?;
+        let end = MndpTlvHdr::LEN + hdr.length.to_bits() as usize;
+        let Some(value) = self.data.get(MndpTlvHdr::LEN..end) else {
+            self.data = &[];
+            return None;
+        };
+        self.data = &self.data[end..];
+        Some(MndpTlv { hdr, value })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MndpHdr, MndpTlvType};
+
+    #[test]
+    fn test_mndp_tlvs() {
+        let packet = [
+            0, 0, 0, 3, // header, seq 3
+            0, 1, 0, 6, 0x4c, 0x5e, 0x0c, 1, 2, 3, // MAC
+            0, 5, 0, 4, b'c', b'o', b'r', b'e', // identity
+            0, 10, 0, 4, 0x10, 0x0e, 0, 0, // uptime 3600s
+            0, 16, 0, 5, b'e', b't', b'h', b'e', // truncated interface name
+        ];
+        let (hdr, mut tlvs) = MndpHdr::parse(&packet).unwrap();
+        assert_eq!(hdr.seq_no.to_bits(), 3);
+        assert_eq!(
+            tlvs.next().unwrap().mac_address(),
+            Some([0x4c, 0x5e, 0x0c, 1, 2, 3])
+        );
+        let identity = tlvs.next().unwrap();
+        assert_eq!(identity.hdr.tlv_type(), Some(MndpTlvType::Identity));
+        assert_eq!(identity.identity(), Some("core"));
+        assert_eq!(tlvs.next().unwrap().uptime(), Some(3600));
+        assert!(tlvs.next().is_none());
+    }
+}
@@ -0,0 +1,519 @@
+//! Multiprotocol Label Switching
+//! ([RFC 3032](https://datatracker.ietf.org/doc/html/rfc3032)) label stack
+//! entries, with helpers pushing, swapping and popping labels of a frame in
+//! place.
+//!
+//! With the `alloc` feature, [`LabelFib`] maps incoming labels to
+//! forwarding actions applied with these helpers, as done by an LSR.
+
+#[cfg(feature = "alloc")]
+use alloc::{collections::BTreeMap, vec::Vec};
+use core::{fmt, mem};
+
+use crate::{
+    builder::{BuildError, Writer},
+    eth::EtherType,
+    header::{impl_header, Header, Limited, ParseConfig},
+    types::U32,
+    udp::{write_udp, UdpHdr},
+};
+
+/// Largest label value, labels being 20 bits.
+pub const MAX_LABEL: u32 = 0xfffff;
+
+/// IANA-assigned UDP destination port of MPLS-in-UDP
+/// ([RFC 7510](https://datatracker.ietf.org/doc/html/rfc7510)).
+pub const MPLS_IN_UDP_PORT: u16 = 6635;
+
+/// Special-purpose labels, in the reserved range 0 to 15
+/// ([RFC 7274](https://datatracker.ietf.org/doc/html/rfc7274)).
+#[repr(u32)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum SpecialLabel {
+    /// The label stack is popped and the packet forwarded as IPv4.
+    Ipv4ExplicitNull = 0,
+    /// The packet is delivered to the local software for processing.
+    RouterAlert = 1,
+    /// The label stack is popped and the packet forwarded as IPv6.
+    Ipv6ExplicitNull = 2,
+    /// Only signaled, never carried in a packet.
+    ImplicitNull = 3,
+    /// **Entropy Label Indicator**: the next entry is an entropy label
+    /// ([RFC 6790](https://datatracker.ietf.org/doc/html/rfc6790)).
+    EntropyLabelIndicator = 7,
+    /// **Generic Associated Channel Label**.
+    Gal = 13,
+    OamAlert = 14,
+    /// The next entry is an extended special-purpose label.
+    Extension = 15,
+}
+
+impl TryFrom<u32> for SpecialLabel {
+    type Error = ();
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(SpecialLabel::Ipv4ExplicitNull),
+            1 => Ok(SpecialLabel::RouterAlert),
+            2 => Ok(SpecialLabel::Ipv6ExplicitNull),
+            3 => Ok(SpecialLabel::ImplicitNull),
+            7 => Ok(SpecialLabel::EntropyLabelIndicator),
+            13 => Ok(SpecialLabel::Gal),
+            14 => Ok(SpecialLabel::OamAlert),
+            15 => Ok(SpecialLabel::Extension),
+            _ => Err(()),
+        }
+    }
+}
+
+/// MPLS label stack entry.
+/// ```text
+/// +----------------------------------------+------+---+---------------+
+/// |              Label (20)                | TC(3)| S |    TTL (8)    |
+/// +----------------------------------------+------+---+---------------+
+/// ```
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct MplsHdr {
+    pub entry: U32,
+}
+
+impl MplsHdr {
+    pub const LEN: usize = mem::size_of::<MplsHdr>();
+
+    #[inline]
+    pub const fn new(label: u32, tc: u8, bos: bool, ttl: u8) -> Self {
+        let entry = (label & MAX_LABEL) << 12 | (tc as u32 & 0x7) << 9 | (bos as u32) << 8;
+        Self {
+            entry: U32::from_bits(entry | ttl as u32),
+        }
+    }
+
+    #[inline]
+    pub const fn label(&self) -> u32 {
+        self.entry.to_bits() >> 12
+    }
+
+    #[inline]
+    pub const fn set_label(&mut self, label: u32) {
+        let entry = self.entry.to_bits() & 0xfff | (label & MAX_LABEL) << 12;
+        self.entry = U32::from_bits(entry);
+    }
+
+    /// **Traffic Class**, formerly EXP.
+    #[inline]
+    pub const fn tc(&self) -> u8 {
+        (self.entry.to_bits() >> 9) as u8 & 0x7
+    }
+
+    #[inline]
+    pub const fn set_tc(&mut self, tc: u8) {
+        let entry = self.entry.to_bits() & !0xe00 | (tc as u32 & 0x7) << 9;
+        self.entry = U32::from_bits(entry);
+    }
+
+    /// **Bottom of Stack**: the entry is the last one of the stack.
+    #[inline]
+    pub const fn bos(&self) -> bool {
+        self.entry.to_bits() & 0x100 != 0
+    }
+
+    #[inline]
+    pub const fn set_bos(&mut self, bos: bool) {
+        let entry = self.entry.to_bits() & !0x100 | (bos as u32) << 8;
+        self.entry = U32::from_bits(entry);
+    }
+
+    #[inline]
+    pub const fn ttl(&self) -> u8 {
+        self.entry.to_bits() as u8
+    }
+
+    #[inline]
+    pub const fn set_ttl(&mut self, ttl: u8) {
+        let entry = self.entry.to_bits() & !0xff | ttl as u32;
+        self.entry = U32::from_bits(entry);
+    }
+
+    /// Whether the label is in the reserved range 0 to 15.
+    #[inline]
+    pub const fn is_reserved(&self) -> bool {
+        self.label() < 16
+    }
+
+    /// Returns the special-purpose label, if the label is an assigned one.
+    #[inline]
+    pub fn special(&self) -> Option<SpecialLabel> {
+        if !self.is_reserved() {
+            return None;
+        }
+        self.label().try_into().ok()
+    }
+}
+
+impl_header!(MplsHdr);
+
+/// Iterator over the entries of a label stack, up to the bottom of stack.
+#[derive(Debug, Clone)]
+pub struct LabelStack<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> LabelStack<'a> {
+    #[inline]
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    /// Bounds the walk to [`ParseConfig::max_mpls_labels`] entries.
+    #[inline]
+    pub fn limit(self, config: &ParseConfig) -> Limited<Self> {
+        Limited::new(self, config.max_mpls_labels)
+    }
+
+    /// Returns the value of the first entropy label of the stack, i.e. of
+    /// the entry following an Entropy Label Indicator.
+    ///
+    /// ```
+    /// use ether_packet::mpls::{LabelStack, MplsHdr};
+    ///
+    /// let mut stack = [0u8; 12];
+    /// stack[..4].copy_from_slice(&MplsHdr::new(16000, 0, false, 64).entry.octets());
+    /// stack[4..8].copy_from_slice(&MplsHdr::new(7, 0, false, 0).entry.octets());
+    /// stack[8..].copy_from_slice(&MplsHdr::new(0x5a5a5, 0, true, 0).entry.octets());
+    /// assert_eq!(LabelStack::new(&stack).entropy_label(), Some(0x5a5a5));
+    /// ```
+    pub fn entropy_label(self) -> Option<u32> {
+        let mut stack =
+            self.skip_while(|hdr| hdr.special() != Some(SpecialLabel::EntropyLabelIndicator));
+        stack.next()?;
+        stack.next().map(|hdr| hdr.label())
+    }
+
+    /// Whether the stack carries the Router Alert label.
+    pub fn has_router_alert(mut self) -> bool {
+        self.any(|hdr| hdr.special() == Some(SpecialLabel::RouterAlert))
+    }
+}
+
+impl Iterator for LabelStack<'_> {
+    type Item = MplsHdr;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (entry, rest) = self.data.split_first_chunk::<{ MplsHdr::LEN }>()?;
+        let hdr = MplsHdr {
+            entry: U32::from_bits(u32::from_be_bytes(*entry)),
+        };
+        self.data = if hdr.bos() { &[] } else { rest };
+        Some(hdr)
+    }
+}
+
+/// Error returned by the label rewrite helpers.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum MplsError {
+    /// The frame does not carry a label stack, or it is truncated.
+    NotMpls,
+    /// The TTL of the top entry expired.
+    TtlExpired,
+    /// No entry exists for the top label.
+    UnknownLabel(u32),
+    /// The buffer is too small to push the labels.
+    BufferTooSmall,
+}
+
+impl fmt::Display for MplsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MplsError::NotMpls => f.write_str("not an MPLS frame"),
+            MplsError::TtlExpired => f.write_str("TTL expired"),
+            MplsError::UnknownLabel(label) => write!(f, "unknown label {label}"),
+            MplsError::BufferTooSmall => f.write_str("buffer too small"),
+        }
+    }
+}
+
+#[inline]
+fn ether_type(frame: &[u8], offset: usize) -> Option<u16> {
+    let ty = frame.get(offset.checked_sub(2)?..offset)?;
+    Some(u16::from_be_bytes([ty[0], ty[1]]))
+}
+
+#[inline]
+fn is_mpls(ether_type: u16) -> bool {
+    ether_type == EtherType::MPLSUnicast as u16 || ether_type == EtherType::MPLSMulticast as u16
+}
+
+/// Returns the top entry of the label stack of `frame`, found at `offset`
+/// right after the EtherType.
+pub fn top(frame: &[u8], offset: usize) -> Result<MplsHdr, MplsError> {
+    if !ether_type(frame, offset).is_some_and(is_mpls) {
+        return Err(MplsError::NotMpls);
+    }
+    let hdr = frame
+        .get(offset..)
+        .and_then(MplsHdr::from_bytes)
+        .ok_or(MplsError::NotMpls)?;
+    Ok(*hdr)
+}
+
+/// Replaces the top label of the frame and decrements its TTL.
+pub fn swap(frame: &mut [u8], offset: usize, label: u32) -> Result<(), MplsError> {
+    let mut hdr = top(frame, offset)?;
+    if hdr.ttl() <= 1 {
+        return Err(MplsError::TtlExpired);
+    }
+    hdr.set_label(label);
+    hdr.set_ttl(hdr.ttl() - 1);
+    frame[offset..offset + MplsHdr::LEN].copy_from_slice(&hdr.entry.octets());
+    Ok(())
+}
+
+/// Pushes an entry onto the label stack of the frame stored in
+/// `buf[..len]`, returning the new length. The bottom of stack flag is set
+/// if the frame was not labeled yet, in which case the EtherType becomes
+/// MPLS unicast.
+pub fn push(
+    buf: &mut [u8],
+    len: usize,
+    offset: usize,
+    mut hdr: MplsHdr,
+) -> Result<usize, MplsError> {
+    let ether_type = ether_type(&buf[..len], offset).ok_or(MplsError::NotMpls)?;
+    if len + MplsHdr::LEN > buf.len() {
+        return Err(MplsError::BufferTooSmall);
+    }
+    hdr.set_bos(!is_mpls(ether_type));
+    buf.copy_within(offset..len, offset + MplsHdr::LEN);
+    buf[offset..offset + MplsHdr::LEN].copy_from_slice(&hdr.entry.octets());
+    if !is_mpls(ether_type) {
+        let mpls = EtherType::MPLSUnicast as u16;
+        buf[offset - 2..offset].copy_from_slice(&mpls.to_be_bytes());
+    }
+    Ok(len + MplsHdr::LEN)
+}
+
+/// Pops the top entry of the label stack of the frame stored in
+/// `buf[..len]`, returning the new length and the popped entry. When the
+/// bottom of stack is popped, the EtherType is set to IPv4 or IPv6 from the
+/// version of the payload.
+pub fn pop(buf: &mut [u8], len: usize, offset: usize) -> Result<(usize, MplsHdr), MplsError> {
+    let hdr = top(&buf[..len], offset)?;
+    buf.copy_within(offset + MplsHdr::LEN..len, offset);
+    let len = len - MplsHdr::LEN;
+    if hdr.bos() {
+        let ether_type = match buf.get(offset).map(|b| b >> 4) {
+            Some(4) => Some(EtherType::Ipv4),
+            Some(6) => Some(EtherType::Ipv6),
+            _ => None,
+        };
+        if let Some(ether_type) = ether_type {
+            buf[offset - 2..offset].copy_from_slice(&(ether_type as u16).to_be_bytes());
+        }
+    }
+    Ok((len, hdr))
+}
+
+/// Writes an MPLS-in-UDP packet to `buf`, starting with the UDP header, and
+/// returns its length. `labels` are pushed in order, the first one being
+/// the outermost, and the bottom of stack flag is set on the last one.
+///
+/// As with [`write_gre_in_udp`](crate::gre::write_gre_in_udp), `src_port`
+/// should be derived from the inner flow and the UDP checksum is left at 0.
+pub fn write_mpls_in_udp(
+    buf: &mut [u8],
+    src_port: u16,
+    labels: &[MplsHdr],
+    payload: &[u8],
+) -> Result<usize, BuildError> {
+    let udp_len = UdpHdr::LEN + labels.len() * MplsHdr::LEN + payload.len();
+    let udp_len = u16::try_from(udp_len).map_err(|_| BuildError::FieldOverflow)?;
+    let mut w = Writer::new(buf);
+    write_udp(&mut w, src_port, MPLS_IN_UDP_PORT, udp_len)?;
+    for (i, hdr) in labels.iter().enumerate() {
+        let mut hdr = *hdr;
+        hdr.set_bos(i == labels.len() - 1);
+        w.put(&hdr.entry.octets())?;
+    }
+    w.put(payload)?;
+    Ok(w.pos())
+}
+
+/// Forwarding action of a [`LabelFib`] entry.
+#[cfg(feature = "alloc")]
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum LabelAction {
+    /// Removes the top label.
+    Pop,
+    /// Replaces the top label.
+    Swap(u32),
+    /// Replaces the top label, then pushes the given labels on top of it,
+    /// the first one being the outermost.
+    SwapPush(u32, Vec<u32>),
+    /// Keeps the top label and pushes the given labels on top of it.
+    Push(Vec<u32>),
+}
+
+/// Label forwarding table, mapping incoming labels to an action and to
+/// forwarding metadata `T`, such as an egress port and next hop.
+///
+/// TTLs follow the pipe model: the TTL of the top entry is decremented and
+/// copied into the pushed entries, but not into the IP header when the
+/// bottom of stack is popped.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone)]
+pub struct LabelFib<T> {
+    entries: BTreeMap<u32, (LabelAction, T)>,
+}
+
+#[cfg(feature = "alloc")]
+impl<T> LabelFib<T> {
+    pub fn new() -> Self {
+        Self {
+            entries: BTreeMap::new(),
+        }
+    }
+
+    /// Adds or replaces the entry of `label`.
+    pub fn insert(&mut self, label: u32, action: LabelAction, meta: T) -> Option<(LabelAction, T)> {
+        self.entries.insert(label, (action, meta))
+    }
+
+    pub fn remove(&mut self, label: u32) -> Option<(LabelAction, T)> {
+        self.entries.remove(&label)
+    }
+
+    #[inline]
+    pub fn get(&self, label: u32) -> Option<&(LabelAction, T)> {
+        self.entries.get(&label)
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Looks up the top label of the frame stored in `buf[..len]`, whose
+    /// label stack is at `offset`, and applies the action of its entry.
+    /// Returns the new length of the frame and the forwarding metadata.
+    pub fn forward(
+        &self,
+        buf: &mut [u8],
+        len: usize,
+        offset: usize,
+    ) -> Result<(usize, &T), MplsError> {
+        let hdr = top(&buf[..len], offset)?;
+        let (action, meta) = self
+            .get(hdr.label())
+            .ok_or(MplsError::UnknownLabel(hdr.label()))?;
+        if hdr.ttl() <= 1 {
+            return Err(MplsError::TtlExpired);
+        }
+        let ttl = hdr.ttl() - 1;
+        let (outer, labels) = match action {
+            LabelAction::Pop => return pop(buf, len, offset).map(|(len, _)| (len, meta)),
+            LabelAction::Swap(label) => (Some(*label), &[][..]),
+            LabelAction::SwapPush(label, labels) => (Some(*label), labels.as_slice()),
+            LabelAction::Push(labels) => (None, labels.as_slice()),
+        };
+        let needed = len + labels.len() * MplsHdr::LEN;
+        if needed > buf.len() {
+            return Err(MplsError::BufferTooSmall);
+        }
+        let mut entry = hdr;
+        entry.set_label(outer.unwrap_or(hdr.label()));
+        entry.set_ttl(ttl);
+        buf[offset..offset + MplsHdr::LEN].copy_from_slice(&entry.entry.octets());
+        let mut len = len;
+        for &label in labels.iter().rev() {
+            len = push(buf, len, offset, MplsHdr::new(label, hdr.tc(), false, ttl))?;
+        }
+        Ok((len, meta))
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> Default for LabelFib<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{pop, push, swap, LabelStack, MplsHdr, SpecialLabel};
+    use crate::header::{ParseConfig, ParseError};
+
+    #[test]
+    fn test_label_rewrite() {
+        let mut buf = [0u8; 64];
+        #[rustfmt::skip]
+        buf[..34].copy_from_slice(&[
+            0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 1, 0x08, 0x00,
+            0x45, 0, 0, 20, 0, 0, 0, 0, 64, 17, 0, 0, 10, 0, 0, 1, 10, 0, 0, 2,
+        ]);
+        let len = push(&mut buf, 34, 14, MplsHdr::new(100, 5, false, 64)).unwrap();
+        let len = push(&mut buf, len, 14, MplsHdr::new(200, 0, false, 64)).unwrap();
+        assert_eq!(len, 42);
+        assert_eq!(&buf[12..14], &[0x88, 0x47]);
+        swap(&mut buf, 14, 300).unwrap();
+
+        let labels = LabelStack::new(&buf[14..len]).map(|hdr| (hdr.label(), hdr.ttl(), hdr.bos()));
+        assert!(labels.eq([(300, 63, false), (100, 64, true)]));
+        assert_eq!(LabelStack::new(&buf[14..len]).entropy_label(), None);
+        let one = ParseConfig {
+            max_mpls_labels: 1,
+            ..ParseConfig::HARDENED
+        };
+        let mut limited = LabelStack::new(&buf[14..len]).limit(&one);
+        assert_eq!(limited.next().unwrap().unwrap().label(), 300);
+        assert_eq!(
+            limited.next().unwrap().unwrap_err(),
+            ParseError::LimitExceeded
+        );
+        assert!(limited.next().is_none());
+        assert_eq!(
+            MplsHdr::new(2, 0, true, 1).special(),
+            Some(SpecialLabel::Ipv6ExplicitNull)
+        );
+        assert_eq!(MplsHdr::new(5, 0, true, 1).special(), None);
+
+        let (len, top) = pop(&mut buf, len, 14).unwrap();
+        assert_eq!(top.label(), 300);
+        let (len, bottom) = pop(&mut buf, len, 14).unwrap();
+        assert_eq!(bottom.tc(), 5);
+        assert_eq!(len, 34);
+        assert_eq!(&buf[12..15], &[0x08, 0x00, 0x45]);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_label_fib() {
+        use super::{LabelAction, LabelFib, MplsError};
+        use alloc::vec;
+
+        let mut fib = LabelFib::new();
+        fib.insert(16, LabelAction::SwapPush(17, vec![1000, 2000]), "eth1");
+        fib.insert(17, LabelAction::Pop, "eth2");
+
+        let mut buf = [0u8; 64];
+        buf[12..14].copy_from_slice(&[0x88, 0x47]);
+        buf[14..18].copy_from_slice(&MplsHdr::new(16, 0, true, 10).entry.octets());
+        buf[18] = 0x45;
+        let (len, port) = fib.forward(&mut buf, 38, 14).unwrap();
+        assert_eq!((len, *port), (46, "eth1"));
+        let labels = LabelStack::new(&buf[14..len]).map(|hdr| (hdr.label(), hdr.ttl()));
+        assert!(labels.eq([(1000, 9), (2000, 9), (17, 9)]));
+
+        assert_eq!(
+            fib.forward(&mut buf, len, 14),
+            Err(MplsError::UnknownLabel(1000))
+        );
+    }
+}
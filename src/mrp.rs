@@ -0,0 +1,316 @@
+//! Media Redundancy Protocol (IEC 62439-2), carried with
+//! [`EtherType::MRP`](crate::eth::EtherType::MRP).
+//!
+//! The media redundancy manager of a ring sends test frames out of both of
+//! its ring ports and blocks its secondary port while they come back,
+//! reporting the ring state in each test frame. Clients report the state
+//! of their ring ports with link change frames, and the manager announces
+//! reconfigurations with topology change frames.
+
+use core::mem;
+
+use crate::{
+    header::{impl_header, Header},
+    types::{U16, U32},
+};
+
+/// Destination of the MRP test frames.
+pub const MRP_TEST_ADDR: [u8; 6] = [0x01, 0x15, 0x4e, 0x00, 0x00, 0x01];
+/// Destination of the MRP topology change and link change frames.
+pub const MRP_CONTROL_ADDR: [u8; 6] = [0x01, 0x15, 0x4e, 0x00, 0x00, 0x02];
+
+/// Types of the MRP TLVs.
+#[repr(u8)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum MrpTlvType {
+    End = 0x00,
+    Common = 0x01,
+    Test = 0x02,
+    TopologyChange = 0x03,
+    LinkDown = 0x04,
+    LinkUp = 0x05,
+    InTest = 0x06,
+    InTopologyChange = 0x07,
+    InLinkDown = 0x08,
+    InLinkUp = 0x09,
+    InLinkStatusPoll = 0x0a,
+    Option = 0x7f,
+}
+
+impl TryFrom<u8> for MrpTlvType {
+    type Error = ();
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x00 => Ok(MrpTlvType::End),
+            0x01 => Ok(MrpTlvType::Common),
+            0x02 => Ok(MrpTlvType::Test),
+            0x03 => Ok(MrpTlvType::TopologyChange),
+            0x04 => Ok(MrpTlvType::LinkDown),
+            0x05 => Ok(MrpTlvType::LinkUp),
+            0x06 => Ok(MrpTlvType::InTest),
+            0x07 => Ok(MrpTlvType::InTopologyChange),
+            0x08 => Ok(MrpTlvType::InLinkDown),
+            0x09 => Ok(MrpTlvType::InLinkUp),
+            0x0a => Ok(MrpTlvType::InLinkStatusPoll),
+            0x7f => Ok(MrpTlvType::Option),
+            _ => Err(()),
+        }
+    }
+}
+
+/// State of the ring, as seen by the manager.
+#[repr(u16)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum MrpRingState {
+    /// The ring is broken, the manager forwards on both ports.
+    Open = 0,
+    /// The ring is intact, the manager blocks its secondary port.
+    Closed = 1,
+}
+
+/// Role of the port sending a frame.
+#[repr(u16)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum MrpPortRole {
+    Primary = 0,
+    Secondary = 1,
+    /// Port of an interconnection between rings.
+    Interconnection = 2,
+}
+
+impl TryFrom<u16> for MrpPortRole {
+    type Error = ();
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(MrpPortRole::Primary),
+            1 => Ok(MrpPortRole::Secondary),
+            2 => Ok(MrpPortRole::Interconnection),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Value of the MRP_Common TLV, ending the frames.
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct MrpCommon {
+    pub sequence_id: U16,
+    /// UUID of the MRP domain of the ring.
+    pub domain_uuid: [u8; 16],
+}
+
+impl MrpCommon {
+    pub const LEN: usize = mem::size_of::<MrpCommon>();
+}
+
+/// Value of the MRP_Test TLV.
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct MrpTest {
+    /// Priority of the manager, the lowest value winning.
+    pub prio: U16,
+    /// MAC address of the manager.
+    pub sa: [u8; 6],
+    pub port_role: U16,
+    pub ring_state: U16,
+    /// Number of transitions from the open to the closed state.
+    pub transition: U16,
+    /// Time of transmission by the manager, in milliseconds.
+    pub timestamp: U32,
+}
+
+impl MrpTest {
+    pub const LEN: usize = mem::size_of::<MrpTest>();
+
+    #[inline]
+    pub fn port_role(&self) -> Option<MrpPortRole> {
+        self.port_role.to_bits().try_into().ok()
+    }
+
+    #[inline]
+    pub const fn ring_state(&self) -> Option<MrpRingState> {
+        match self.ring_state.to_bits() {
+            0 => Some(MrpRingState::Open),
+            1 => Some(MrpRingState::Closed),
+            _ => None,
+        }
+    }
+}
+
+/// Value of the MRP_TopologyChange TLV.
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct MrpTopologyChange {
+    pub prio: U16,
+    pub sa: [u8; 6],
+    /// Time until the forwarding databases are flushed, in milliseconds.
+    pub interval: U16,
+}
+
+impl MrpTopologyChange {
+    pub const LEN: usize = mem::size_of::<MrpTopologyChange>();
+}
+
+/// Value of the MRP_LinkDown and MRP_LinkUp TLVs.
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct MrpLinkChange {
+    /// MAC address of the client.
+    pub sa: [u8; 6],
+    pub port_role: U16,
+    /// Time until the link change is repeated, in milliseconds.
+    pub interval: U16,
+    /// 1 if the client blocks the port while the link is up.
+    pub blocked: U16,
+}
+
+impl MrpLinkChange {
+    pub const LEN: usize = mem::size_of::<MrpLinkChange>();
+
+    #[inline]
+    pub fn port_role(&self) -> Option<MrpPortRole> {
+        self.port_role.to_bits().try_into().ok()
+    }
+
+    #[inline]
+    pub const fn blocked(&self) -> bool {
+        self.blocked.to_bits() == 1
+    }
+}
+
+impl_header!(MrpCommon, MrpTest, MrpTopologyChange, MrpLinkChange);
+
+/// Decoded MRP TLV.
+#[derive(Debug, Copy, Clone)]
+pub enum MrpMessage<'a> {
+    Common(&'a MrpCommon),
+    Test(&'a MrpTest),
+    TopologyChange(&'a MrpTopologyChange),
+    LinkDown(&'a MrpLinkChange),
+    LinkUp(&'a MrpLinkChange),
+    /// Any other TLV, with its value.
+    Other(MrpTlv<'a>),
+}
+
+/// An MRP TLV.
+#[derive(Debug, Copy, Clone)]
+pub struct MrpTlv<'a> {
+    pub tlv_type: u8,
+    pub value: &'a [u8],
+}
+
+impl<'a> MrpTlv<'a> {
+    #[inline]
+    pub fn tlv_type(&self) -> Option<MrpTlvType> {
+        self.tlv_type.try_into().ok()
+    }
+
+    /// Decodes the value of the TLV, returning `None` if it is too short.
+    pub fn message(&self) -> Option<MrpMessage<'a>> {
+        let value = self.value;
+        Some(match self.tlv_type() {
+            Some(MrpTlvType::Common) => MrpMessage::Common(MrpCommon::from_bytes(value)?),
+            Some(MrpTlvType::Test) => MrpMessage::Test(MrpTest::from_bytes(value)?),
+            Some(MrpTlvType::TopologyChange) => {
+                MrpMessage::TopologyChange(MrpTopologyChange::from_bytes(value)?)
+            }
+            Some(MrpTlvType::LinkDown) => MrpMessage::LinkDown(MrpLinkChange::from_bytes(value)?),
+            Some(MrpTlvType::LinkUp) => MrpMessage::LinkUp(MrpLinkChange::from_bytes(value)?),
+            _ => MrpMessage::Other(*self),
+        })
+    }
+}
+
+/// Iterator over the TLVs of an MRP frame, stopping at the End TLV.
+#[derive(Debug, Clone)]
+pub struct MrpTlvs<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Iterator for MrpTlvs<'a> {
+    type Item = MrpTlv<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let [tlv_type, len, ref rest @ ..] = *self.data else {
+            return None;
+        };
+        let value = rest.get(..len as usize).filter(|_| tlv_type != 0);
+        self.data = value.map_or(&[], |value| &rest[value.len()..]);
+        Some(MrpTlv {
+            tlv_type,
+            value: value?,
+        })
+    }
+}
+
+/// Parses the MRP PDU stored in `pdu`, returning its version, 1, and an
+/// iterator over its TLVs.
+///
+/// ```
+/// use ether_packet::mrp::{self, MrpMessage};
+///
+/// #[rustfmt::skip]
+/// let pdu = [
+///     0, 1,
+///     // MRP_LinkDown of the secondary port.
+///     4, 12, 0, 1, 2, 3, 4, 5, 0, 1, 0, 20, 0, 1,
+///     0, 0,
+/// ];
+/// let (version, mut tlvs) = mrp::parse(&pdu).unwrap();
+/// assert_eq!(version, 1);
+/// let Some(MrpMessage::LinkDown(link)) = tlvs.next().unwrap().message() else {
+///     panic!("expected a link down TLV");
+/// };
+/// assert!(link.blocked());
+/// ```
+pub fn parse(pdu: &[u8]) -> Option<(u16, MrpTlvs<'_>)> {
+    let (version, data) = pdu.split_first_chunk::<2>()?;
+    Some((u16::from_be_bytes(*version), MrpTlvs { data }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, MrpMessage, MrpPortRole, MrpRingState, MrpTlvType};
+
+    #[test]
+    fn test_mrp_test_frame() {
+        #[rustfmt::skip]
+        let pdu = [
+            0, 1,
+            // MRP_Test from the primary port of a closed ring.
+            2, 18, 0x80, 0, 0, 0x0e, 0xcf, 1, 2, 3, 0, 0, 0, 1, 0, 4, 0, 1, 0x86, 0xa0,
+            // MRP_Common
+            1, 18, 0, 9, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+            0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+            0, 0,
+        ];
+        let (_, mut tlvs) = parse(&pdu).unwrap();
+        let tlv = tlvs.next().unwrap();
+        assert_eq!(tlv.tlv_type(), Some(MrpTlvType::Test));
+        let Some(MrpMessage::Test(test)) = tlv.message() else {
+            panic!("expected a test TLV");
+        };
+        assert_eq!(test.port_role(), Some(MrpPortRole::Primary));
+        assert_eq!(test.ring_state(), Some(MrpRingState::Closed));
+        assert_eq!(
+            (test.transition.to_bits(), test.timestamp.to_bits()),
+            (4, 100_000)
+        );
+        let Some(MrpMessage::Common(common)) = tlvs.next().unwrap().message() else {
+            panic!("expected a common TLV");
+        };
+        assert_eq!(common.sequence_id.to_bits(), 9);
+        assert!(tlvs.next().is_none());
+
+        // A test TLV too short for its value.
+        let (_, mut tlvs) = parse(&pdu[..10]).unwrap();
+        assert!(tlvs.next().is_none());
+    }
+}
@@ -0,0 +1,217 @@
+//! Multicast Source Discovery Protocol ([RFC 3618](https://datatracker.ietf.org/doc/html/rfc3618)),
+//! carried over TCP port 639.
+
+use core::{mem, net::Ipv4Addr};
+
+use crate::{
+    header::{impl_header, Header},
+    types::U16,
+};
+
+pub const MSDP_PORT: u16 = 639;
+
+/// MSDP message types.
+#[repr(u8)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum MsdpMsgType {
+    SourceActive = 1,
+    SaRequest = 2,
+    SaResponse = 3,
+    Keepalive = 4,
+}
+
+impl TryFrom<u8> for MsdpMsgType {
+    type Error = ();
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(MsdpMsgType::SourceActive),
+            2 => Ok(MsdpMsgType::SaRequest),
+            3 => Ok(MsdpMsgType::SaResponse),
+            4 => Ok(MsdpMsgType::Keepalive),
+            _ => Err(()),
+        }
+    }
+}
+
+/// MSDP TLV header, present at the beginning of every message.
+/// ```text
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |     Type      |           Length              |  Value ....  |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct MsdpHdr {
+    /// See [`MsdpMsgType`].
+    pub r#type: u8,
+    /// Length of the whole message, including this header.
+    pub length: U16,
+}
+
+impl MsdpHdr {
+    pub const LEN: usize = mem::size_of::<MsdpHdr>();
+
+    #[inline]
+    pub fn msg_type(&self) -> Option<MsdpMsgType> {
+        self.r#type.try_into().ok()
+    }
+}
+
+/// Fixed part of an IPv4 Source-Active message value, followed by
+/// `entry_count` [`MsdpSaEntry`] and optionally an encapsulated data packet.
+/// ```text
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |       1       |           x + y               |  Entry Count  |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                          RP Address                           |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct MsdpSaHdr {
+    pub entry_count: u8,
+    /// Address of the RP which originated the message.
+    pub rp_addr: Ipv4Addr,
+}
+
+impl MsdpSaHdr {
+    pub const LEN: usize = mem::size_of::<MsdpSaHdr>();
+}
+
+/// (S,G) entry of a Source-Active message.
+/// ```text
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                   Reserved                    |  Sprefix Len  |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                         Group Address                         |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                         Source Address                        |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct MsdpSaEntry {
+    pub _reserved: [u8; 3],
+    /// Route prefix length of the source, always 32.
+    pub sprefix_len: u8,
+    pub group_addr: Ipv4Addr,
+    pub src_addr: Ipv4Addr,
+}
+
+impl MsdpSaEntry {
+    pub const LEN: usize = mem::size_of::<MsdpSaEntry>();
+}
+
+impl_header!(MsdpHdr, MsdpSaHdr, MsdpSaEntry);
+
+/// An MSDP message.
+#[derive(Debug, Copy, Clone)]
+pub struct MsdpMessage<'a> {
+    pub hdr: &'a MsdpHdr,
+    /// Value of the message, without the TLV header.
+    pub value: &'a [u8],
+}
+
+impl<'a> MsdpMessage<'a> {
+    /// Decodes the value of a Source-Active message.
+    pub fn source_active(&self) -> Option<SourceActive<'a>> {
+        if self.hdr.msg_type()? != MsdpMsgType::SourceActive {
+            return None;
+        }
+        let hdr = MsdpSaHdr::from_bytes(self.value)?;
+        let entries_len = hdr.entry_count as usize * MsdpSaEntry::LEN;
+        let rest = &self.value[MsdpSaHdr::LEN..];
+        Some(SourceActive {
+            hdr,
+            entries: rest.get(..entries_len)?,
+            data: &rest[entries_len..],
+        })
+    }
+}
+
+/// A decoded Source-Active message.
+#[derive(Debug, Copy, Clone)]
+pub struct SourceActive<'a> {
+    pub hdr: &'a MsdpSaHdr,
+    entries: &'a [u8],
+    /// Encapsulated multicast data packet, empty when absent.
+    pub data: &'a [u8],
+}
+
+impl<'a> SourceActive<'a> {
+    /// The (S,G) entries of the message.
+    pub fn entries(&self) -> impl Iterator<Item = &'a MsdpSaEntry> {
+        self.entries
+            .chunks_exact(MsdpSaEntry::LEN)
+            .filter_map(MsdpSaEntry::from_bytes)
+    }
+}
+
+/// Iterator over the MSDP messages stored back to back in a TCP stream
+/// segment. Iteration stops at the first incomplete message.
+#[derive(Debug, Clone)]
+pub struct MsdpMessages<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> MsdpMessages<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    /// Bytes which have not been consumed yet, e.g. the start of a message
+    /// continued in the next segment.
+    pub fn remainder(&self) -> &'a [u8] {
+        self.data
+    }
+}
+
+impl<'a> Iterator for MsdpMessages<'a> {
+    type Item = MsdpMessage<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let hdr = MsdpHdr::from_bytes(self.data)?;
+        let len = hdr.length.to_bits() as usize;
+        if len < MsdpHdr::LEN || len > self.data.len() {
+            return None;
+        }
+        let value = &self.data[MsdpHdr::LEN..len];
+        self.data = &self.data[len..];
+        Some(MsdpMessage { hdr, value })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::net::Ipv4Addr;
+
+    use super::{MsdpMessages, MsdpMsgType};
+
+    #[test]
+    fn test_source_active() {
+        let stream = [
+            1, 0, 20, 1, 192, 0, 2, 1, // SA, 1 entry, RP 192.0.2.1
+            0, 0, 0, 32, 239, 1, 1, 1, 10, 0, 0, 1, // (10.0.0.1, 239.1.1.1)
+            4, 0, 3, // keepalive
+            1, 0, // truncated
+        ];
+        let mut msgs = MsdpMessages::new(&stream);
+        let sa = msgs.next().unwrap().source_active().unwrap();
+        assert_eq!(sa.hdr.rp_addr, Ipv4Addr::new(192, 0, 2, 1));
+        let entry = sa.entries().next().unwrap();
+        assert_eq!(entry.group_addr, Ipv4Addr::new(239, 1, 1, 1));
+        assert_eq!(entry.src_addr, Ipv4Addr::new(10, 0, 0, 1));
+        assert!(sa.data.is_empty());
+
+        let keepalive = msgs.next().unwrap();
+        assert_eq!(keepalive.hdr.msg_type(), Some(MsdpMsgType::Keepalive));
+        assert!(msgs.next().is_none());
+        assert_eq!(msgs.remainder(), &[1, 0]);
+    }
+}
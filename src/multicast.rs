@@ -0,0 +1,260 @@
+//! Names of the well-known multicast groups, at the MAC layer and for IPv4
+//! and IPv6, such as the spanning tree bridge group, the all-routers
+//! groups or the mDNS groups.
+//!
+//! [`WellKnownGroup`] classifies an address, and [`Named`] displays an
+//! address along with its name.
+
+use core::{
+    fmt,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+};
+
+/// A well-known multicast group, or the broadcast address.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum WellKnownGroup {
+    Broadcast,
+    /// All hosts or all nodes on the link.
+    AllHosts,
+    AllRouters,
+    /// Solicited-node group of IPv6 neighbor discovery.
+    SolicitedNode,
+    /// IGMPv3 or MLDv2 reports.
+    MembershipReports,
+    OspfAllRouters,
+    OspfDesignatedRouters,
+    Rip,
+    Pim,
+    Vrrp,
+    Mdns,
+    Llmnr,
+    Ssdp,
+    /// All DHCPv6 relay agents and servers.
+    DhcpAgents,
+    /// Bridge group address of the spanning tree protocols.
+    SpanningTree,
+    /// PAUSE frames of IEEE 802.3x.
+    MacControl,
+    /// LACP and the other slow protocols.
+    SlowProtocols,
+    /// Nearest non-TPMR bridge, used by IEEE 802.1X.
+    NearestNonTpmrBridge,
+    /// Nearest bridge, used by LLDP and gPTP.
+    NearestBridge,
+    Mvrp,
+    /// CDP, VTP and the other Cisco protocols.
+    Cdp,
+    /// Cisco Per-VLAN Spanning Tree Plus.
+    Pvst,
+    /// PTP over Ethernet, except the peer delay messages.
+    Ptp,
+}
+
+impl WellKnownGroup {
+    /// Short name of the group.
+    pub const fn name(&self) -> &'static str {
+        match self {
+            WellKnownGroup::Broadcast => "broadcast",
+            WellKnownGroup::AllHosts => "all-hosts",
+            WellKnownGroup::AllRouters => "all-routers",
+            WellKnownGroup::SolicitedNode => "solicited-node",
+            WellKnownGroup::MembershipReports => "membership-reports",
+            WellKnownGroup::OspfAllRouters => "ospf-all-routers",
+            WellKnownGroup::OspfDesignatedRouters => "ospf-designated-routers",
+            WellKnownGroup::Rip => "rip-routers",
+            WellKnownGroup::Pim => "pim-routers",
+            WellKnownGroup::Vrrp => "vrrp",
+            WellKnownGroup::Mdns => "mdns",
+            WellKnownGroup::Llmnr => "llmnr",
+            WellKnownGroup::Ssdp => "ssdp",
+            WellKnownGroup::DhcpAgents => "dhcp-agents",
+            WellKnownGroup::SpanningTree => "spanning-tree",
+            WellKnownGroup::MacControl => "mac-control",
+            WellKnownGroup::SlowProtocols => "slow-protocols",
+            WellKnownGroup::NearestNonTpmrBridge => "nearest-non-tpmr-bridge",
+            WellKnownGroup::NearestBridge => "lldp-nearest-bridge",
+            WellKnownGroup::Mvrp => "mvrp",
+            WellKnownGroup::Cdp => "cdp",
+            WellKnownGroup::Pvst => "pvst",
+            WellKnownGroup::Ptp => "ptp",
+        }
+    }
+
+    /// Group of a MAC address, including the addresses IPv4 and IPv6
+    /// groups map to.
+    pub fn from_mac(addr: &[u8; 6]) -> Option<Self> {
+        match *addr {
+            [0xff, 0xff, 0xff, 0xff, 0xff, 0xff] => Some(WellKnownGroup::Broadcast),
+            [0x01, 0x80, 0xc2, 0x00, 0x00, last] => match last {
+                0x00 => Some(WellKnownGroup::SpanningTree),
+                0x01 => Some(WellKnownGroup::MacControl),
+                0x02 => Some(WellKnownGroup::SlowProtocols),
+                0x03 => Some(WellKnownGroup::NearestNonTpmrBridge),
+                0x0e => Some(WellKnownGroup::NearestBridge),
+                0x21 => Some(WellKnownGroup::Mvrp),
+                _ => None,
+            },
+            [0x01, 0x00, 0x0c, 0xcc, 0xcc, 0xcc] => Some(WellKnownGroup::Cdp),
+            [0x01, 0x00, 0x0c, 0xcc, 0xcc, 0xcd] => Some(WellKnownGroup::Pvst),
+            [0x01, 0x1b, 0x19, 0x00, 0x00, 0x00] => Some(WellKnownGroup::Ptp),
+            // Only the groups of 224.0.0.0/24 and 239.255.255.250 are
+            // named, the MAC address leaving out 5 bits of the group.
+            [0x01, 0x00, 0x5e, 0x00, 0x00, last] => Self::from_ipv4(Ipv4Addr::new(224, 0, 0, last)),
+            [0x01, 0x00, 0x5e, 0x7f, 0xff, 0xfa] => Some(WellKnownGroup::Ssdp),
+            [0x33, 0x33, 0xff, ..] => Some(WellKnownGroup::SolicitedNode),
+            [0x33, 0x33, a, b, c, d] => {
+                let [x, y] = [u16::from_be_bytes([a, b]), u16::from_be_bytes([c, d])];
+                Self::from_ipv6(Ipv6Addr::new(0xff02, 0, 0, 0, 0, 0, x, y))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn from_ipv4(addr: Ipv4Addr) -> Option<Self> {
+        match addr.octets() {
+            [255, 255, 255, 255] => Some(WellKnownGroup::Broadcast),
+            [224, 0, 0, last] => match last {
+                1 => Some(WellKnownGroup::AllHosts),
+                2 => Some(WellKnownGroup::AllRouters),
+                5 => Some(WellKnownGroup::OspfAllRouters),
+                6 => Some(WellKnownGroup::OspfDesignatedRouters),
+                9 => Some(WellKnownGroup::Rip),
+                13 => Some(WellKnownGroup::Pim),
+                18 => Some(WellKnownGroup::Vrrp),
+                22 => Some(WellKnownGroup::MembershipReports),
+                251 => Some(WellKnownGroup::Mdns),
+                252 => Some(WellKnownGroup::Llmnr),
+                _ => None,
+            },
+            [239, 255, 255, 250] => Some(WellKnownGroup::Ssdp),
+            _ => None,
+        }
+    }
+
+    pub fn from_ipv6(addr: Ipv6Addr) -> Option<Self> {
+        let segments = addr.segments();
+        if segments[..6] == [0xff02, 0, 0, 0, 0, 1] && segments[6] >> 8 == 0xff {
+            return Some(WellKnownGroup::SolicitedNode);
+        }
+        match segments {
+            [0xff02, 0, 0, 0, 0, 0, 0, last] => match last {
+                0x1 => Some(WellKnownGroup::AllHosts),
+                0x2 => Some(WellKnownGroup::AllRouters),
+                0x5 => Some(WellKnownGroup::OspfAllRouters),
+                0x6 => Some(WellKnownGroup::OspfDesignatedRouters),
+                0x9 => Some(WellKnownGroup::Rip),
+                0xd => Some(WellKnownGroup::Pim),
+                0x12 => Some(WellKnownGroup::Vrrp),
+                0x16 => Some(WellKnownGroup::MembershipReports),
+                0xfb => Some(WellKnownGroup::Mdns),
+                0xc => Some(WellKnownGroup::Ssdp),
+                _ => None,
+            },
+            [0xff02, 0, 0, 0, 0, 0, 1, 0x2] => Some(WellKnownGroup::DhcpAgents),
+            [0xff02, 0, 0, 0, 0, 0, 1, 0x3] => Some(WellKnownGroup::Llmnr),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn from_ip(addr: IpAddr) -> Option<Self> {
+        match addr {
+            IpAddr::V4(addr) => Self::from_ipv4(addr),
+            IpAddr::V6(addr) => Self::from_ipv6(addr),
+        }
+    }
+}
+
+impl fmt::Display for WellKnownGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Displays a MAC or IP address followed by the name of its well-known
+/// group, if any.
+///
+/// ```
+/// use core::net::Ipv6Addr;
+/// use ether_packet::multicast::Named;
+///
+/// let lldp = [0x01, 0x80, 0xc2, 0x00, 0x00, 0x0e];
+/// assert_eq!(Named(lldp).to_string(), "01:80:c2:00:00:0e (lldp-nearest-bridge)");
+/// assert_eq!(Named(Ipv6Addr::new(0xff02, 0, 0, 0, 0, 0, 0, 0xfb)).to_string(), "ff02::fb (mdns)");
+/// assert_eq!(Named([2, 0, 0, 0, 0, 1]).to_string(), "02:00:00:00:00:01");
+/// ```
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub struct Named<A>(pub A);
+
+impl<A> Named<A> {
+    #[inline]
+    fn suffix(f: &mut fmt::Formatter<'_>, group: Option<WellKnownGroup>) -> fmt::Result {
+        match group {
+            Some(group) => write!(f, " ({group})"),
+            None => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for Named<[u8; 6]> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [a, b, c, d, e, g] = self.0;
+        write!(f, "{a:02x}:{b:02x}:{c:02x}:{d:02x}:{e:02x}:{g:02x}")?;
+        Self::suffix(f, WellKnownGroup::from_mac(&self.0))
+    }
+}
+
+impl fmt::Display for Named<Ipv4Addr> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)?;
+        Self::suffix(f, WellKnownGroup::from_ipv4(self.0))
+    }
+}
+
+impl fmt::Display for Named<Ipv6Addr> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)?;
+        Self::suffix(f, WellKnownGroup::from_ipv6(self.0))
+    }
+}
+
+impl fmt::Display for Named<IpAddr> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)?;
+        Self::suffix(f, WellKnownGroup::from_ip(self.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::net::{Ipv4Addr, Ipv6Addr};
+
+    use super::WellKnownGroup;
+
+    #[test]
+    fn test_well_known_groups() {
+        let mdns = Some(WellKnownGroup::Mdns);
+        assert_eq!(
+            WellKnownGroup::from_ipv4(Ipv4Addr::new(224, 0, 0, 251)),
+            mdns
+        );
+        assert_eq!(WellKnownGroup::from_mac(&[1, 0, 0x5e, 0, 0, 0xfb]), mdns);
+        assert_eq!(WellKnownGroup::from_mac(&[0x33, 0x33, 0, 0, 0, 0xfb]), mdns);
+        assert_eq!(
+            WellKnownGroup::from_mac(&[0x01, 0x80, 0xc2, 0, 0, 0]),
+            Some(WellKnownGroup::SpanningTree)
+        );
+        let solicited = Ipv6Addr::new(0xff02, 0, 0, 0, 0, 1, 0xff12, 0x3456);
+        assert_eq!(
+            WellKnownGroup::from_ipv6(solicited),
+            Some(WellKnownGroup::SolicitedNode)
+        );
+        assert_eq!(
+            WellKnownGroup::from_mac(&[0x33, 0x33, 0, 1, 0, 3]),
+            Some(WellKnownGroup::Llmnr)
+        );
+        assert_eq!(WellKnownGroup::from_ipv4(Ipv4Addr::new(224, 0, 1, 1)), None);
+        assert_eq!(WellKnownGroup::from_mac(&[2, 0, 0, 0, 0, 1]), None);
+    }
+}
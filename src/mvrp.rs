@@ -0,0 +1,379 @@
+//! VLAN registration protocols: the Multiple VLAN Registration Protocol
+//! (IEEE 802.1Q clause 11) carried with [`EtherType::MVRP`](crate::eth::EtherType::MVRP),
+//! and its predecessor GVRP (IEEE 802.1D clause 12), carried over 802.2 LLC.
+
+use core::mem;
+
+use crate::{
+    header::{impl_header, Header},
+    types::U16,
+};
+
+/// Destination MAC address of MVRP PDUs.
+pub const MVRP_MAC: [u8; 6] = [0x01, 0x80, 0xc2, 0x00, 0x00, 0x21];
+/// Destination MAC address of GVRP PDUs, shared with MVRP.
+pub const GVRP_MAC: [u8; 6] = MVRP_MAC;
+/// LLC service access point of the GARP applications.
+pub const GARP_SAP: u8 = 0x42;
+
+/// MVRP attribute type of VLAN identifiers.
+pub const MVRP_ATTR_VID: u8 = 1;
+/// GVRP attribute type of VLAN identifiers.
+pub const GVRP_ATTR_VID: u8 = 1;
+
+/// Attribute events of MRP, packed three per octet in vector attributes.
+#[repr(u8)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum MrpEvent {
+    New = 0,
+    JoinIn = 1,
+    In = 2,
+    JoinMt = 3,
+    Mt = 4,
+    Lv = 5,
+}
+
+impl TryFrom<u8> for MrpEvent {
+    type Error = ();
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(MrpEvent::New),
+            1 => Ok(MrpEvent::JoinIn),
+            2 => Ok(MrpEvent::In),
+            3 => Ok(MrpEvent::JoinMt),
+            4 => Ok(MrpEvent::Mt),
+            5 => Ok(MrpEvent::Lv),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Header of an MRP vector attribute, followed by the first value and the
+/// packed events.
+/// ```text
+///  0                   1
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |LvA|       NumberOfValues      |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct MrpVectorHdr {
+    /// LeaveAllEvent (3 bits) and NumberOfValues (13 bits).
+    pub vector_hdr: U16,
+}
+
+impl MrpVectorHdr {
+    pub const LEN: usize = mem::size_of::<MrpVectorHdr>();
+
+    /// The sender requests all registrations to be re-declared.
+    #[inline]
+    pub const fn leave_all(&self) -> bool {
+        self.vector_hdr.to_bits() >> 13 == 1
+    }
+
+    #[inline]
+    pub const fn num_values(&self) -> u16 {
+        self.vector_hdr.to_bits() & 0x1fff
+    }
+}
+
+impl_header!(MrpVectorHdr);
+
+/// Parses the MRPDU stored in `pdu`, the payload of an MVRP frame,
+/// returning its protocol version and an iterator over its messages.
+pub fn parse_mrpdu(pdu: &[u8]) -> Option<(u8, MrpMessages<'_>)> {
+    let (&version, rest) = pdu.split_first()?;
+    Some((version, MrpMessages { data: rest }))
+}
+
+/// An MRP message: the vector attributes of a single attribute type.
+#[derive(Debug, Copy, Clone)]
+pub struct MrpMessage<'a> {
+    pub attr_type: u8,
+    /// Length of the first value of each vector attribute.
+    pub attr_len: u8,
+    attrs: &'a [u8],
+}
+
+impl<'a> MrpMessage<'a> {
+    #[inline]
+    pub fn vector_attributes(&self) -> MrpVectorAttributes<'a> {
+        MrpVectorAttributes {
+            attr_len: self.attr_len as usize,
+            data: self.attrs,
+        }
+    }
+}
+
+/// Iterator over the messages of an MRPDU.
+#[derive(Debug, Clone)]
+pub struct MrpMessages<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Iterator for MrpMessages<'a> {
+    type Item = MrpMessage<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let [attr_type, attr_len, ref rest @ ..] = *self.data else {
+            return None;
+        };
+        // An attribute type of 0 is the end mark of the PDU.
+        if attr_type == 0 {
+            self.data = &[];
+            return None;
+        }
+        let mut iter = MrpVectorAttributes {
+            attr_len: attr_len as usize,
+            data: rest,
+        };
+        while iter.next().is_some() {}
+        let len = rest.len() - iter.data.len();
+        // Skip the end mark of the attribute list.
+        self.data = rest.get(len + 2..).unwrap_or(&[]);
+        Some(MrpMessage {
+            attr_type,
+            attr_len,
+            attrs: &rest[..len],
+        })
+    }
+}
+
+/// An MRP vector attribute: a run of consecutive attribute values starting
+/// at `first_value`, with one event each.
+#[derive(Debug, Copy, Clone)]
+pub struct MrpVectorAttribute<'a> {
+    pub hdr: &'a MrpVectorHdr,
+    pub first_value: &'a [u8],
+    vectors: &'a [u8],
+}
+
+impl<'a> MrpVectorAttribute<'a> {
+    /// Events of the successive values, in order.
+    pub fn events(&self) -> impl Iterator<Item = MrpEvent> + 'a {
+        self.vectors
+            .iter()
+            .flat_map(|&packed| [packed / 36, packed / 6 % 6, packed % 6])
+            .take(self.hdr.num_values() as usize)
+            .map_while(|event| event.try_into().ok())
+    }
+
+    /// VLAN identifiers and events of an MVRP VID vector attribute.
+    pub fn vids(&self) -> Option<impl Iterator<Item = (u16, MrpEvent)> + 'a> {
+        let first: [u8; 2] = self.first_value.try_into().ok()?;
+        let first = u16::from_be_bytes(first);
+        Some(
+            self.events()
+                .enumerate()
+                .map(move |(i, event)| (first.wrapping_add(i as u16) & 0xfff, event)),
+        )
+    }
+}
+
+/// Iterator over the vector attributes of an MRP message.
+#[derive(Debug, Clone)]
+pub struct MrpVectorAttributes<'a> {
+    attr_len: usize,
+    data: &'a [u8],
+}
+
+impl<'a> Iterator for MrpVectorAttributes<'a> {
+    type Item = MrpVectorAttribute<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let hdr = MrpVectorHdr::from_bytes(self.data)?;
+        if hdr.vector_hdr.to_bits() == 0 {
+            return None;
+        }
+        let vectors_len = (hdr.num_values() as usize).div_ceil(3);
+        let rest = &self.data[MrpVectorHdr::LEN..];
+        let Some(first_value) = rest.get(..self.attr_len) else {
+            self.data = &[];
+            return None;
+        };
+        let Some(vectors) = rest.get(self.attr_len..self.attr_len + vectors_len) else {
+            self.data = &[];
+            return None;
+        };
+        self.data = &rest[self.attr_len + vectors_len..];
+        Some(MrpVectorAttribute {
+            hdr,
+            first_value,
+            vectors,
+        })
+    }
+}
+
+/// GARP attribute events.
+#[repr(u8)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum GarpEvent {
+    LeaveAll = 0,
+    JoinEmpty = 1,
+    JoinIn = 2,
+    LeaveEmpty = 3,
+    LeaveIn = 4,
+    Empty = 5,
+}
+
+impl TryFrom<u8> for GarpEvent {
+    type Error = ();
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(GarpEvent::LeaveAll),
+            1 => Ok(GarpEvent::JoinEmpty),
+            2 => Ok(GarpEvent::JoinIn),
+            3 => Ok(GarpEvent::LeaveEmpty),
+            4 => Ok(GarpEvent::LeaveIn),
+            5 => Ok(GarpEvent::Empty),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Parses the GARP PDU stored in `llc`, an 802.3 payload starting with the
+/// LLC header, returning an iterator over its messages.
+pub fn parse_garp(llc: &[u8]) -> Option<GarpMessages<'_>> {
+    match llc {
+        [GARP_SAP, GARP_SAP, 0x03, 0x00, 0x01, rest @ ..] => Some(GarpMessages { data: rest }),
+        _ => None,
+    }
+}
+
+/// A GARP message: the attributes of a single attribute type.
+#[derive(Debug, Copy, Clone)]
+pub struct GarpMessage<'a> {
+    pub attr_type: u8,
+    attrs: &'a [u8],
+}
+
+impl<'a> GarpMessage<'a> {
+    #[inline]
+    pub fn attributes(&self) -> GarpAttributes<'a> {
+        GarpAttributes { data: self.attrs }
+    }
+}
+
+/// Iterator over the messages of a GARP PDU.
+#[derive(Debug, Clone)]
+pub struct GarpMessages<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Iterator for GarpMessages<'a> {
+    type Item = GarpMessage<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (&attr_type, rest) = self.data.split_first()?;
+        if attr_type == 0 {
+            self.data = &[];
+            return None;
+        }
+        let mut iter = GarpAttributes { data: rest };
+        while iter.next().is_some() {}
+        let len = rest.len() - iter.data.len();
+        self.data = rest.get(len + 1..).unwrap_or(&[]);
+        Some(GarpMessage {
+            attr_type,
+            attrs: &rest[..len],
+        })
+    }
+}
+
+/// A GARP attribute.
+#[derive(Debug, Copy, Clone)]
+pub struct GarpAttribute<'a> {
+    pub event: u8,
+    /// Attribute value, empty for LeaveAll events.
+    pub value: &'a [u8],
+}
+
+impl GarpAttribute<'_> {
+    #[inline]
+    pub fn event(&self) -> Option<GarpEvent> {
+        self.event.try_into().ok()
+    }
+
+    /// VLAN identifier of a GVRP attribute.
+    #[inline]
+    pub fn vid(&self) -> Option<u16> {
+        Some(u16::from_be_bytes(self.value.try_into().ok()?) & 0xfff)
+    }
+}
+
+/// Iterator over the attributes of a GARP message.
+#[derive(Debug, Clone)]
+pub struct GarpAttributes<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Iterator for GarpAttributes<'a> {
+    type Item = GarpAttribute<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // The attribute length includes the length and event octets.
+        let [len, event, ref rest @ ..] = *self.data else {
+            return None;
+        };
+        let value = (len as usize).checked_sub(2).and_then(|n| rest.get(..n))?;
+        self.data = &rest[value.len()..];
+        Some(GarpAttribute { event, value })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_garp, parse_mrpdu, GarpEvent, MrpEvent, MVRP_ATTR_VID};
+
+    #[test]
+    fn test_mvrp() {
+        #[rustfmt::skip]
+        let pdu = [
+            0, // protocol version
+            MVRP_ATTR_VID, 2, // attribute type and length
+            0x20, 4, 0, 100, // LeaveAll, VIDs 100..=103
+            36 + 6 + 2, 5 * 36, // JoinIn, JoinIn, In, Lv
+            0, 0, // end of attribute list
+            0, 0, // end of PDU
+        ];
+        let (version, mut msgs) = parse_mrpdu(&pdu).unwrap();
+        assert_eq!(version, 0);
+        let msg = msgs.next().unwrap();
+        assert_eq!(msg.attr_type, MVRP_ATTR_VID);
+        let mut attrs = msg.vector_attributes();
+        let attr = attrs.next().unwrap();
+        assert!(attr.hdr.leave_all());
+        let mut vids = attr.vids().unwrap();
+        assert_eq!(vids.next(), Some((100, MrpEvent::JoinIn)));
+        assert_eq!(vids.next(), Some((101, MrpEvent::JoinIn)));
+        assert_eq!(vids.next(), Some((102, MrpEvent::In)));
+        assert_eq!(vids.next(), Some((103, MrpEvent::Lv)));
+        assert_eq!(vids.next(), None);
+        assert!(attrs.next().is_none());
+        assert!(msgs.next().is_none());
+    }
+
+    #[test]
+    fn test_gvrp() {
+        let llc = [
+            0x42, 0x42, 0x03, 0, 1, // LLC, protocol id
+            1, // VID attributes
+            2, 0, // LeaveAll
+            4, 2, 0, 10, // JoinIn VID 10
+            0, 0, // end marks
+        ];
+        let mut msgs = parse_garp(&llc).unwrap();
+        let mut attrs = msgs.next().unwrap().attributes();
+        assert_eq!(attrs.next().unwrap().event(), Some(GarpEvent::LeaveAll));
+        let join = attrs.next().unwrap();
+        assert_eq!(join.event(), Some(GarpEvent::JoinIn));
+        assert_eq!(join.vid(), Some(10));
+        assert!(attrs.next().is_none());
+        assert!(msgs.next().is_none());
+    }
+}
@@ -0,0 +1,251 @@
+//! NetBIOS Datagram Service ([RFC 1002](https://datatracker.ietf.org/doc/html/rfc1002#section-4.4)),
+//! carried over UDP port 138.
+
+use core::{mem, net::Ipv4Addr, str};
+
+use crate::{
+    header::{impl_header, Header},
+    types::U16,
+};
+
+pub const NBDS_PORT: u16 = 138;
+
+/// NetBIOS datagram message types.
+#[repr(u8)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum NbdsMsgType {
+    DirectUnique = 0x10,
+    DirectGroup = 0x11,
+    Broadcast = 0x12,
+    Error = 0x13,
+    QueryRequest = 0x14,
+    PositiveQueryResponse = 0x15,
+    NegativeQueryResponse = 0x16,
+}
+
+impl TryFrom<u8> for NbdsMsgType {
+    type Error = ();
+    fn try_from(value: u8) -> Result<Self, ()> {
+        match value {
+            0x10 => Ok(NbdsMsgType::DirectUnique),
+            0x11 => Ok(NbdsMsgType::DirectGroup),
+            0x12 => Ok(NbdsMsgType::Broadcast),
+            0x13 => Ok(NbdsMsgType::Error),
+            0x14 => Ok(NbdsMsgType::QueryRequest),
+            0x15 => Ok(NbdsMsgType::PositiveQueryResponse),
+            0x16 => Ok(NbdsMsgType::NegativeQueryResponse),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Header common to all NetBIOS datagram messages.
+/// ```text
+///                      1 1 1 1 1 1 1 1 1 1 2 2 2 2 2 2 2 2 2 2 3 3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |   MSG_TYPE    |     FLAGS     |           DGM_ID              |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                           SOURCE_IP                           |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |          SOURCE_PORT          |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct NbdsHdr {
+    /// See [`NbdsMsgType`].
+    pub msg_type: u8,
+    /// Source end-node type (2 bits), followed by the F and M bits.
+    pub flags: u8,
+    pub dgm_id: U16,
+    pub src_ip: Ipv4Addr,
+    pub src_port: U16,
+}
+
+impl NbdsHdr {
+    pub const LEN: usize = mem::size_of::<NbdsHdr>();
+
+    #[inline]
+    pub fn msg_type(&self) -> Option<NbdsMsgType> {
+        self.msg_type.try_into().ok()
+    }
+
+    /// Source end-node type: 0 for B, 1 for P, 2 for M nodes and 3 for the
+    /// NetBIOS datagram distribution server.
+    #[inline]
+    pub const fn node_type(&self) -> u8 {
+        (self.flags >> 2) & 0x3
+    }
+
+    /// **F**: the datagram is the first fragment.
+    #[inline]
+    pub const fn first(&self) -> bool {
+        self.flags & 0x02 != 0
+    }
+
+    /// **M**: more fragments follow.
+    #[inline]
+    pub const fn more(&self) -> bool {
+        self.flags & 0x01 != 0
+    }
+
+    #[inline]
+    pub const fn src_port(&self) -> u16 {
+        self.src_port.to_bits()
+    }
+
+    /// Parses the datagram message stored in `msg`, returning its header and
+    /// the type-specific part.
+    pub fn parse(msg: &[u8]) -> Option<(&NbdsHdr, NbdsBody<'_>)> {
+        let hdr = NbdsHdr::from_bytes(msg)?;
+        let rest = &msg[Self::LEN..];
+        let body = match hdr.msg_type()? {
+            NbdsMsgType::DirectUnique | NbdsMsgType::DirectGroup | NbdsMsgType::Broadcast => {
+                let data = NbdsDataHdr::from_bytes(rest)?;
+                let rest = &rest[NbdsDataHdr::LEN..];
+                let rest = rest.get(..data.dgm_length.to_bits() as usize)?;
+                let (src_name, len) = NetbiosName::decode(rest)?;
+                let rest = &rest[len..];
+                let (dst_name, len) = NetbiosName::decode(rest)?;
+                NbdsBody::Data {
+                    hdr: data,
+                    src_name,
+                    dst_name,
+                    user_data: &rest[len..],
+                }
+            }
+            NbdsMsgType::Error => NbdsBody::Error(*rest.first()?),
+            _ => NbdsBody::Query(NetbiosName::decode(rest)?.0),
+        };
+        Some((hdr, body))
+    }
+}
+
+/// Fields following the common header in direct unique, direct group and
+/// broadcast datagrams.
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct NbdsDataHdr {
+    /// Length of the source name, destination name and user data.
+    pub dgm_length: U16,
+    /// Offset of the user data of this fragment in the whole datagram.
+    pub packet_offset: U16,
+}
+
+impl NbdsDataHdr {
+    pub const LEN: usize = mem::size_of::<NbdsDataHdr>();
+}
+
+impl_header!(NbdsHdr, NbdsDataHdr);
+
+/// Type-specific part of a NetBIOS datagram message.
+#[derive(Debug, Copy, Clone)]
+pub enum NbdsBody<'a> {
+    /// Direct unique, direct group or broadcast datagram.
+    Data {
+        hdr: &'a NbdsDataHdr,
+        src_name: NetbiosName,
+        dst_name: NetbiosName,
+        user_data: &'a [u8],
+    },
+    /// Error code of an error response.
+    Error(u8),
+    /// Destination name of a query request or response.
+    Query(NetbiosName),
+}
+
+/// A decoded NetBIOS name: 15 characters padded with spaces and a suffix
+/// identifying the service.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub struct NetbiosName {
+    pub bytes: [u8; 16],
+}
+
+impl NetbiosName {
+    /// Decodes the first-level encoded name at the start of `bytes`,
+    /// returning it with the length of the encoded name, scope included.
+    pub fn decode(bytes: &[u8]) -> Option<(NetbiosName, usize)> {
+        let encoded = bytes.get(1..33).filter(|_| bytes[0] == 32)?;
+        let mut name = [0u8; 16];
+        for (out, pair) in name.iter_mut().zip(encoded.chunks_exact(2)) {
+            let hi = pair[0].wrapping_sub(b'A');
+            let lo = pair[1].wrapping_sub(b'A');
+            if hi > 0xf || lo > 0xf {
+                return None;
+            }
+            *out = hi << 4 | lo;
+        }
+        // Skip the NetBIOS scope labels up to the terminating zero length.
+        let mut pos = 33;
+        loop {
+            let len = *bytes.get(pos)? as usize;
+            pos += 1 + len;
+            if len == 0 {
+                break;
+            }
+        }
+        Some((NetbiosName { bytes: name }, pos))
+    }
+
+    /// The name without the suffix and its padding.
+    pub fn name(&self) -> Option<&str> {
+        str::from_utf8(&self.bytes[..15])
+            .ok()
+            .map(|name| name.trim_end_matches(' '))
+    }
+
+    /// The suffix, e.g. 0x00 for workstations and 0x1d for master browsers.
+    #[inline]
+    pub const fn suffix(&self) -> u8 {
+        self.bytes[15]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::net::Ipv4Addr;
+
+    use super::{NbdsBody, NbdsHdr, NbdsMsgType, NetbiosName};
+
+    fn encode(name: &[u8; 16], out: &mut [u8]) {
+        out[0] = 32;
+        for (i, b) in name.iter().enumerate() {
+            out[1 + 2 * i] = b'A' + (b >> 4);
+            out[2 + 2 * i] = b'A' + (b & 0xf);
+        }
+        out[33] = 0;
+    }
+
+    #[test]
+    fn test_broadcast_datagram() {
+        let mut msg = [0u8; 14 + 34 * 2 + 3];
+        msg[..14].copy_from_slice(&[0x12, 0x02, 0, 9, 192, 168, 1, 7, 0, 138, 0, 71, 0, 0]);
+        encode(b"HOST           \x00", &mut msg[14..]);
+        encode(b"WORKGROUP      \x1d", &mut msg[48..]);
+        msg[82..].copy_from_slice(b"smb");
+
+        let (hdr, body) = NbdsHdr::parse(&msg).unwrap();
+        assert_eq!(hdr.msg_type(), Some(NbdsMsgType::Broadcast));
+        assert!(hdr.first() && !hdr.more());
+        assert_eq!(hdr.src_ip, Ipv4Addr::new(192, 168, 1, 7));
+        match body {
+            NbdsBody::Data {
+                src_name,
+                dst_name,
+                user_data,
+                ..
+            } => {
+                assert_eq!(src_name.name(), Some("HOST"));
+                assert_eq!(dst_name.name(), Some("WORKGROUP"));
+                assert_eq!(dst_name.suffix(), 0x1d);
+                assert_eq!(user_data, b"smb");
+            }
+            _ => panic!("expected a datagram"),
+        }
+        assert!(NetbiosName::decode(&msg[15..]).is_none());
+    }
+}
@@ -0,0 +1,632 @@
+//! IPv6 Neighbor Discovery
+//! ([RFC 4861](https://datatracker.ietf.org/doc/html/rfc4861)) messages,
+//! carried over ICMPv6.
+
+use core::{mem, net::Ipv6Addr};
+
+use crate::{
+    builder::{BuildError, Writer},
+    checksum,
+    header::{impl_header, Limited, ParseConfig, ParseError},
+    ip::IpProto,
+    types::{U16, U32},
+};
+
+/// All-nodes link-local multicast address, destination of unsolicited
+/// Router Advertisements.
+pub const ALL_NODES: Ipv6Addr = Ipv6Addr::new(0xff02, 0, 0, 0, 0, 0, 0, 1);
+/// All-routers link-local multicast address, destination of Router
+/// Solicitations.
+pub const ALL_ROUTERS: Ipv6Addr = Ipv6Addr::new(0xff02, 0, 0, 0, 0, 0, 0, 2);
+
+/// ICMPv6 types of Neighbor Discovery messages.
+#[repr(u8)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum NdMsgType {
+    RouterSolicit = 133,
+    RouterAdvert = 134,
+    NeighborSolicit = 135,
+    NeighborAdvert = 136,
+    Redirect = 137,
+}
+
+impl TryFrom<u8> for NdMsgType {
+    type Error = ();
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            133 => Ok(NdMsgType::RouterSolicit),
+            134 => Ok(NdMsgType::RouterAdvert),
+            135 => Ok(NdMsgType::NeighborSolicit),
+            136 => Ok(NdMsgType::NeighborAdvert),
+            137 => Ok(NdMsgType::Redirect),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Neighbor Discovery option types.
+#[repr(u8)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum NdOptionType {
+    SourceLinkAddr = 1,
+    TargetLinkAddr = 2,
+    PrefixInfo = 3,
+    RedirectedHdr = 4,
+    Mtu = 5,
+    /// Recursive DNS Server ([RFC 8106](https://datatracker.ietf.org/doc/html/rfc8106)).
+    Rdnss = 25,
+}
+
+impl TryFrom<u8> for NdOptionType {
+    type Error = ();
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(NdOptionType::SourceLinkAddr),
+            2 => Ok(NdOptionType::TargetLinkAddr),
+            3 => Ok(NdOptionType::PrefixInfo),
+            4 => Ok(NdOptionType::RedirectedHdr),
+            5 => Ok(NdOptionType::Mtu),
+            25 => Ok(NdOptionType::Rdnss),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Default router preference of a Router Advertisement
+/// ([RFC 4191](https://datatracker.ietf.org/doc/html/rfc4191)).
+#[repr(u8)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum RouterPreference {
+    Medium = 0,
+    High = 1,
+    /// Reserved value, treated as medium by receivers.
+    Reserved = 2,
+    Low = 3,
+}
+
+/// Router Solicitation message.
+/// ```text
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |     Type      |     Code      |          Checksum             |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                            Reserved                           |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |   Options ...
+/// +-+-+-+-+-+-+-+-+-+-+-+-
+/// ```
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct RouterSolicitHdr {
+    pub r#type: u8,
+    pub code: u8,
+    pub checksum: U16,
+    pub _reserved: U32,
+}
+
+impl RouterSolicitHdr {
+    pub const LEN: usize = mem::size_of::<RouterSolicitHdr>();
+}
+
+/// Router Advertisement message.
+/// ```text
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |     Type      |     Code      |          Checksum             |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// | Cur Hop Limit |M|O|H|Prf|  R  |       Router Lifetime         |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                         Reachable Time                        |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                          Retrans Timer                        |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |   Options ...
+/// +-+-+-+-+-+-+-+-+-+-+-+-
+/// ```
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct RouterAdvertHdr {
+    pub r#type: u8,
+    pub code: u8,
+    pub checksum: U16,
+    pub cur_hop_limit: u8,
+    pub flags: u8,
+    /// Lifetime of the default router in seconds, 0 if the router is not a
+    /// default router.
+    pub router_lifetime: U16,
+    /// Milliseconds.
+    pub reachable_time: U32,
+    /// Milliseconds.
+    pub retrans_timer: U32,
+}
+
+impl RouterAdvertHdr {
+    pub const LEN: usize = mem::size_of::<RouterAdvertHdr>();
+
+    /// **Managed address configuration**: addresses are available via DHCPv6.
+    #[inline]
+    pub const fn managed(&self) -> bool {
+        self.flags & 0x80 != 0
+    }
+
+    /// **Other configuration**: other configuration is available via DHCPv6.
+    #[inline]
+    pub const fn other(&self) -> bool {
+        self.flags & 0x40 != 0
+    }
+
+    /// **Default router preference**
+    #[inline]
+    pub const fn preference(&self) -> RouterPreference {
+        match (self.flags >> 3) & 0x3 {
+            0 => RouterPreference::Medium,
+            1 => RouterPreference::High,
+            2 => RouterPreference::Reserved,
+            _ => RouterPreference::Low,
+        }
+    }
+
+    #[inline]
+    pub const fn router_lifetime(&self) -> u16 {
+        self.router_lifetime.to_bits()
+    }
+
+    #[inline]
+    pub const fn reachable_time(&self) -> u32 {
+        self.reachable_time.to_bits()
+    }
+
+    #[inline]
+    pub const fn retrans_timer(&self) -> u32 {
+        self.retrans_timer.to_bits()
+    }
+}
+
+/// Neighbor Solicitation message, followed by options.
+/// ```text
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |     Type      |     Code      |          Checksum             |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                           Reserved                            |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                                                               |
+/// +                       Target Address                          +
+/// |                                                               |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct NeighborSolicitHdr {
+    pub r#type: u8,
+    pub code: u8,
+    pub checksum: U16,
+    pub _reserved: U32,
+    pub target: Ipv6Addr,
+}
+
+impl NeighborSolicitHdr {
+    pub const LEN: usize = mem::size_of::<NeighborSolicitHdr>();
+}
+
+/// Neighbor Advertisement message, followed by options.
+/// ```text
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |     Type      |     Code      |          Checksum             |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |R|S|O|                     Reserved                            |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                                                               |
+/// +                       Target Address                          +
+/// |                                                               |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct NeighborAdvertHdr {
+    pub r#type: u8,
+    pub code: u8,
+    pub checksum: U16,
+    pub flags: u8,
+    pub _reserved: [u8; 3],
+    pub target: Ipv6Addr,
+}
+
+impl NeighborAdvertHdr {
+    pub const LEN: usize = mem::size_of::<NeighborAdvertHdr>();
+
+    /// **Router**: the sender is a router.
+    #[inline]
+    pub const fn router(&self) -> bool {
+        self.flags & 0x80 != 0
+    }
+
+    /// **Solicited**: the advertisement answers a Neighbor Solicitation.
+    #[inline]
+    pub const fn solicited(&self) -> bool {
+        self.flags & 0x40 != 0
+    }
+
+    /// **Override**: the advertisement should replace a cached link-layer
+    /// address.
+    #[inline]
+    pub const fn override_flag(&self) -> bool {
+        self.flags & 0x20 != 0
+    }
+}
+
+impl_header!(
+    RouterSolicitHdr,
+    RouterAdvertHdr,
+    NeighborSolicitHdr,
+    NeighborAdvertHdr
+);
+
+/// A Neighbor Discovery option.
+#[derive(Debug, Copy, Clone)]
+pub struct NdOption<'a> {
+    pub option_type: u8,
+    /// Option data, following the type and length octets.
+    pub data: &'a [u8],
+}
+
+impl NdOption<'_> {
+    #[inline]
+    pub fn option_type(&self) -> Option<NdOptionType> {
+        self.option_type.try_into().ok()
+    }
+
+    /// Link-layer address of a Source/Target Link-Layer Address option on
+    /// Ethernet.
+    pub fn link_addr(&self) -> Option<[u8; 6]> {
+        match self.option_type()? {
+            NdOptionType::SourceLinkAddr | NdOptionType::TargetLinkAddr => {
+                self.data.get(..6)?.try_into().ok()
+            }
+            _ => None,
+        }
+    }
+
+    /// Value of an MTU option.
+    pub fn mtu(&self) -> Option<u32> {
+        match (self.option_type()?, self.data) {
+            (NdOptionType::Mtu, [_, _, a, b, c, d, ..]) => {
+                Some(u32::from_be_bytes([*a, *b, *c, *d]))
+            }
+            _ => None,
+        }
+    }
+
+    /// Contents of a Prefix Information option.
+    pub fn prefix_info(&self) -> Option<PrefixInfo> {
+        if self.option_type()? != NdOptionType::PrefixInfo {
+            return None;
+        }
+        let data: &[u8; 30] = self.data.get(..30)?.try_into().ok()?;
+        let prefix: [u8; 16] = data[14..30].try_into().ok()?;
+        Some(PrefixInfo {
+            prefix_len: data[0],
+            on_link: data[1] & 0x80 != 0,
+            autonomous: data[1] & 0x40 != 0,
+            valid_lifetime: u32::from_be_bytes([data[2], data[3], data[4], data[5]]),
+            preferred_lifetime: u32::from_be_bytes([data[6], data[7], data[8], data[9]]),
+            prefix: prefix.into(),
+        })
+    }
+}
+
+/// Iterator over the options following a Neighbor Discovery message.
+#[derive(Debug, Copy, Clone)]
+pub struct NdOptions<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> NdOptions<'a> {
+    #[inline]
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    /// Bounds the walk to [`ParseConfig::max_options`] options.
+    ///
+    /// Fails with [`ParseError::LimitExceeded`] if the options are longer
+    /// than [`ParseConfig::max_options_len`].
+    pub fn limit(self, config: &ParseConfig) -> Result<Limited<Self>, ParseError> {
+        let len = self.data.len();
+        config.limit_options(self, len)
+    }
+}
+
+impl<'a> Iterator for NdOptions<'a> {
+    type Item = NdOption<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // The length is in units of 8 octets and includes the type and
+        // length octets; 0 is invalid.
+        let len = match self.data {
+            [_, len, ..] if *len != 0 && *len as usize * 8 <= self.data.len() => *len as usize * 8,
+            _ => {
+                self.data = &[];
+                return None;
+            }
+        };
+        let option = NdOption {
+            option_type: self.data[0],
+            data: &self.data[2..len],
+        };
+        self.data = &self.data[len..];
+        Some(option)
+    }
+}
+
+/// Prefix Information option of a Router Advertisement.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub struct PrefixInfo {
+    pub prefix_len: u8,
+    /// **L**: the prefix can be used for on-link determination.
+    pub on_link: bool,
+    /// **A**: the prefix can be used for stateless address autoconfiguration.
+    pub autonomous: bool,
+    /// Seconds, `u32::MAX` meaning infinity.
+    pub valid_lifetime: u32,
+    /// Seconds, `u32::MAX` meaning infinity.
+    pub preferred_lifetime: u32,
+    pub prefix: Ipv6Addr,
+}
+
+/// Appends Neighbor Discovery options to a message.
+struct NdOptionWriter<'a> {
+    w: Writer<'a>,
+}
+
+impl NdOptionWriter<'_> {
+    /// Reserves an option of `len` octets, type and length included, which
+    /// must be a multiple of 8.
+    fn option(&mut self, option_type: NdOptionType, len: usize) -> Result<&mut [u8], BuildError> {
+        let units = u8::try_from(len / 8).map_err(|_| BuildError::FieldOverflow)?;
+        let opt = self.w.reserve(len)?;
+        opt[0] = option_type as u8;
+        opt[1] = units;
+        Ok(&mut opt[2..])
+    }
+
+    fn link_addr(&mut self, option_type: NdOptionType, addr: [u8; 6]) -> Result<(), BuildError> {
+        self.option(option_type, 8)?.copy_from_slice(&addr);
+        Ok(())
+    }
+
+    /// Fills in the ICMPv6 checksum, returning the length of the message.
+    fn finish(mut self, src: Ipv6Addr, dst: Ipv6Addr) -> usize {
+        let len = self.w.pos();
+        let pseudo = checksum::pseudo_header_v6(src, dst, IpProto::Ipv6Icmp, len as u32);
+        let check = checksum::fold(checksum::sum(self.w.written(), pseudo));
+        self.w.set_u16(2, check);
+        len
+    }
+}
+
+/// Builds a Router Solicitation into a caller-provided buffer.
+pub struct RouterSolicitBuilder<'a> {
+    opts: NdOptionWriter<'a>,
+}
+
+impl<'a> RouterSolicitBuilder<'a> {
+    pub fn new(buf: &'a mut [u8]) -> Result<Self, BuildError> {
+        let mut w = Writer::new(buf);
+        w.reserve(RouterSolicitHdr::LEN)?[0] = NdMsgType::RouterSolicit as u8;
+        Ok(Self {
+            opts: NdOptionWriter { w },
+        })
+    }
+
+    /// Appends a Source Link-Layer Address option, which must be omitted
+    /// when soliciting from the unspecified address.
+    pub fn source_link_addr(&mut self, mac: [u8; 6]) -> Result<&mut Self, BuildError> {
+        self.opts.link_addr(NdOptionType::SourceLinkAddr, mac)?;
+        Ok(self)
+    }
+
+    /// Fills in the checksum for the given IPv6 source and destination
+    /// addresses, returning the length of the message.
+    pub fn finish(self, src: Ipv6Addr, dst: Ipv6Addr) -> usize {
+        self.opts.finish(src, dst)
+    }
+}
+
+/// Builds a Router Advertisement into a caller-provided buffer.
+///
+/// ```
+/// use core::net::Ipv6Addr;
+/// use ether_packet::ndp::{RouterAdvertBuilder, ALL_NODES};
+///
+/// let mut buf = [0u8; 128];
+/// let mut ra = RouterAdvertBuilder::new(&mut buf, 64, 1800).unwrap();
+/// ra.source_link_addr([0x02, 0, 0, 0, 0, 1])
+///     .unwrap()
+///     .mtu(1500)
+///     .unwrap()
+///     .prefix_info("2001:db8::".parse().unwrap(), 64, true, true, 86400, 14400)
+///     .unwrap();
+/// let len = ra.finish("fe80::1".parse().unwrap(), ALL_NODES);
+/// assert_eq!(len, 16 + 8 + 8 + 32);
+/// ```
+pub struct RouterAdvertBuilder<'a> {
+    opts: NdOptionWriter<'a>,
+}
+
+impl<'a> RouterAdvertBuilder<'a> {
+    /// Starts a Router Advertisement with the given current hop limit and
+    /// router lifetime in seconds. The flags and timers are zero.
+    pub fn new(
+        buf: &'a mut [u8],
+        cur_hop_limit: u8,
+        router_lifetime: u16,
+    ) -> Result<Self, BuildError> {
+        let mut w = Writer::new(buf);
+        let hdr = w.reserve(RouterAdvertHdr::LEN)?;
+        hdr[0] = NdMsgType::RouterAdvert as u8;
+        hdr[4] = cur_hop_limit;
+        hdr[6..8].copy_from_slice(&router_lifetime.to_be_bytes());
+        Ok(Self {
+            opts: NdOptionWriter { w },
+        })
+    }
+
+    /// Sets the **M** and **O** flags.
+    pub fn flags(&mut self, managed: bool, other: bool) -> &mut Self {
+        let flags = (managed as u8) << 7 | (other as u8) << 6;
+        self.update_flags(0xc0, flags);
+        self
+    }
+
+    pub fn preference(&mut self, preference: RouterPreference) -> &mut Self {
+        self.update_flags(0x18, (preference as u8) << 3);
+        self
+    }
+
+    fn update_flags(&mut self, mask: u8, flags: u8) {
+        let hdr = &mut self.opts.w.written_mut()[..RouterAdvertHdr::LEN];
+        hdr[5] = (hdr[5] & !mask) | flags;
+    }
+
+    /// Sets the reachable time and retransmission timer, in milliseconds.
+    pub fn timers(&mut self, reachable_time: u32, retrans_timer: u32) -> &mut Self {
+        let hdr = &mut self.opts.w.written_mut()[..RouterAdvertHdr::LEN];
+        hdr[8..12].copy_from_slice(&reachable_time.to_be_bytes());
+        hdr[12..16].copy_from_slice(&retrans_timer.to_be_bytes());
+        self
+    }
+
+    pub fn source_link_addr(&mut self, mac: [u8; 6]) -> Result<&mut Self, BuildError> {
+        self.opts.link_addr(NdOptionType::SourceLinkAddr, mac)?;
+        Ok(self)
+    }
+
+    pub fn mtu(&mut self, mtu: u32) -> Result<&mut Self, BuildError> {
+        self.opts.option(NdOptionType::Mtu, 8)?[2..].copy_from_slice(&mtu.to_be_bytes());
+        Ok(self)
+    }
+
+    /// Appends a Prefix Information option. Lifetimes are in seconds,
+    /// `u32::MAX` meaning infinity.
+    pub fn prefix_info(
+        &mut self,
+        prefix: Ipv6Addr,
+        prefix_len: u8,
+        on_link: bool,
+        autonomous: bool,
+        valid_lifetime: u32,
+        preferred_lifetime: u32,
+    ) -> Result<&mut Self, BuildError> {
+        if prefix_len > 128 {
+            return Err(BuildError::FieldOverflow);
+        }
+        let opt = self.opts.option(NdOptionType::PrefixInfo, 32)?;
+        opt[0] = prefix_len;
+        opt[1] = (on_link as u8) << 7 | (autonomous as u8) << 6;
+        opt[2..6].copy_from_slice(&valid_lifetime.to_be_bytes());
+        opt[6..10].copy_from_slice(&preferred_lifetime.to_be_bytes());
+        opt[14..30].copy_from_slice(&prefix.octets());
+        Ok(self)
+    }
+
+    /// Appends a Recursive DNS Server option, `lifetime` being in seconds.
+    pub fn rdnss(&mut self, lifetime: u32, servers: &[Ipv6Addr]) -> Result<&mut Self, BuildError> {
+        if servers.is_empty() {
+            return Err(BuildError::FieldOverflow);
+        }
+        let opt = self
+            .opts
+            .option(NdOptionType::Rdnss, 8 + servers.len() * 16)?;
+        opt[2..6].copy_from_slice(&lifetime.to_be_bytes());
+        for (dst, server) in opt[6..].chunks_exact_mut(16).zip(servers) {
+            dst.copy_from_slice(&server.octets());
+        }
+        Ok(self)
+    }
+
+    /// Fills in the checksum for the given IPv6 source and destination
+    /// addresses, returning the length of the message.
+    pub fn finish(self, src: Ipv6Addr, dst: Ipv6Addr) -> usize {
+        self.opts.finish(src, dst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::net::Ipv6Addr;
+
+    use super::{
+        NdOptionType, NdOptions, PrefixInfo, RouterAdvertBuilder, RouterAdvertHdr,
+        RouterPreference, RouterSolicitBuilder, ALL_NODES, ALL_ROUTERS,
+    };
+    use crate::{checksum, header::Header, ip::IpProto};
+
+    #[test]
+    fn test_router_advert_roundtrip() {
+        let src: Ipv6Addr = "fe80::1".parse().unwrap();
+        let prefix: Ipv6Addr = "2001:db8:1::".parse().unwrap();
+        let mut buf = [0u8; 128];
+        let mut ra = RouterAdvertBuilder::new(&mut buf, 64, 1800).unwrap();
+        ra.flags(false, true)
+            .preference(RouterPreference::High)
+            .timers(30000, 1000)
+            .source_link_addr([0x02, 0, 0, 0, 0, 1])
+            .unwrap()
+            .prefix_info(prefix, 64, true, true, 86400, 14400)
+            .unwrap()
+            .rdnss(600, &[src])
+            .unwrap();
+        let len = ra.finish(src, ALL_NODES);
+        assert_eq!(len, 16 + 8 + 32 + 24);
+
+        let pseudo = checksum::pseudo_header_v6(src, ALL_NODES, IpProto::Ipv6Icmp, len as u32);
+        assert_eq!(checksum::fold(checksum::sum(&buf[..len], pseudo)), 0);
+
+        let hdr = RouterAdvertHdr::from_bytes(&buf).unwrap();
+        assert!(!hdr.managed() && hdr.other());
+        assert_eq!(hdr.preference(), RouterPreference::High);
+        assert_eq!(hdr.router_lifetime(), 1800);
+        assert_eq!(hdr.reachable_time(), 30000);
+
+        let mut opts = NdOptions::new(&buf[RouterAdvertHdr::LEN..len]);
+        assert_eq!(
+            opts.next().unwrap().link_addr(),
+            Some([0x02, 0, 0, 0, 0, 1])
+        );
+        assert_eq!(
+            opts.next().unwrap().prefix_info(),
+            Some(PrefixInfo {
+                prefix_len: 64,
+                on_link: true,
+                autonomous: true,
+                valid_lifetime: 86400,
+                preferred_lifetime: 14400,
+                prefix,
+            })
+        );
+        assert_eq!(
+            opts.next().unwrap().option_type(),
+            Some(NdOptionType::Rdnss)
+        );
+        assert!(opts.next().is_none());
+
+        let mut rs = RouterSolicitBuilder::new(&mut buf).unwrap();
+        rs.source_link_addr([0x02, 0, 0, 0, 0, 2]).unwrap();
+        let len = rs.finish(src, ALL_ROUTERS);
+        assert_eq!(len, 16);
+        assert_eq!(buf[0], 133);
+    }
+}
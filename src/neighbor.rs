@@ -0,0 +1,278 @@
+//! Neighbor cache mapping IP addresses to Ethernet addresses, learned from
+//! ARP packets and IPv6 Neighbor Advertisements.
+//!
+//! The cache follows the reachability model of
+//! [RFC 4861](https://datatracker.ietf.org/doc/html/rfc4861#section-7.3):
+//! confirmed entries are `Reachable`, and become `Stale` once no
+//! confirmation was seen for the reachable time. Stale entries are removed
+//! after the expiry time.
+//!
+//! The `now` arguments must all come from the same monotonic clock, such as
+//! the time elapsed since a fixed `Instant`; capture timestamps, which
+//! follow the wall clock, may jump when it is adjusted. Entry ages saturate
+//! at zero, so a `now` earlier than an entry's last update never ages it.
+
+use alloc::collections::BTreeMap;
+use core::{net::IpAddr, time::Duration};
+
+use crate::{
+    arp::{ArpHdr, ArpOp},
+    ndp::{NdOptionType, NdOptions, NeighborAdvertHdr},
+};
+
+/// Reachability state of a cache entry.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum NeighborState {
+    /// The mapping was recently confirmed.
+    Reachable,
+    /// The mapping was learned without confirmation, or its confirmation is
+    /// older than the reachable time.
+    Stale,
+}
+
+/// A cached mapping.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct NeighborEntry {
+    pub mac: [u8; 6],
+    pub state: NeighborState,
+    /// Time of the last packet which created, changed or confirmed the
+    /// mapping.
+    pub last_seen: Duration,
+}
+
+/// Change of the cache, reported to the callback given to
+/// [`NeighborCache::new`].
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum NeighborEvent {
+    /// A mapping was learned for a new address.
+    Added(IpAddr, [u8; 6]),
+    /// The Ethernet address of a known address changed, which also happens
+    /// on ARP or ND spoofing.
+    MacChanged {
+        ip: IpAddr,
+        old: [u8; 6],
+        new: [u8; 6],
+    },
+    /// A reachable mapping was not confirmed in time.
+    Stale(IpAddr),
+    /// A stale mapping was removed.
+    Expired(IpAddr),
+}
+
+/// Neighbor cache, reporting its changes to a callback.
+///
+/// ```
+/// use core::time::Duration;
+/// use ether_packet::neighbor::{NeighborCache, NeighborEvent, NeighborState};
+///
+/// let mut events = Vec::new();
+/// let mut cache = NeighborCache::new(
+///     Duration::from_secs(30),
+///     Duration::from_secs(300),
+///     |event| events.push(event),
+/// );
+/// let ip = "10.0.0.1".parse().unwrap();
+/// cache.update(ip, [2, 0, 0, 0, 0, 1], true, Duration::from_secs(1));
+/// cache.expire(Duration::from_secs(40));
+/// assert_eq!(cache.get(ip).unwrap().state, NeighborState::Stale);
+/// drop(cache);
+/// assert_eq!(events.len(), 2);
+/// ```
+pub struct NeighborCache<F: FnMut(NeighborEvent)> {
+    entries: BTreeMap<IpAddr, NeighborEntry>,
+    reachable_time: Duration,
+    expiry_time: Duration,
+    on_event: F,
+}
+
+impl<F: FnMut(NeighborEvent)> NeighborCache<F> {
+    /// Creates an empty cache. Reachable entries become stale
+    /// `reachable_time` after their last confirmation, and stale entries are
+    /// removed `expiry_time` after they were last seen.
+    pub fn new(reachable_time: Duration, expiry_time: Duration, on_event: F) -> Self {
+        Self {
+            entries: BTreeMap::new(),
+            reachable_time,
+            expiry_time,
+            on_event,
+        }
+    }
+
+    #[inline]
+    pub fn get(&self, ip: IpAddr) -> Option<&NeighborEntry> {
+        self.entries.get(&ip)
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&IpAddr, &NeighborEntry)> {
+        self.entries.iter()
+    }
+
+    /// Removes the mapping of `ip`, without reporting an event.
+    pub fn remove(&mut self, ip: IpAddr) -> Option<NeighborEntry> {
+        self.entries.remove(&ip)
+    }
+
+    /// Records that `ip` uses `mac` at time `now`. A `confirmed` mapping
+    /// becomes reachable, otherwise it is stale.
+    pub fn update(&mut self, ip: IpAddr, mac: [u8; 6], confirmed: bool, now: Duration) {
+        let state = if confirmed {
+            NeighborState::Reachable
+        } else {
+            NeighborState::Stale
+        };
+        let new = NeighborEntry {
+            mac,
+            state,
+            last_seen: now,
+        };
+        match self.entries.insert(ip, new) {
+            None => (self.on_event)(NeighborEvent::Added(ip, mac)),
+            Some(old) if old.mac != mac => (self.on_event)(NeighborEvent::MacChanged {
+                ip,
+                old: old.mac,
+                new: mac,
+            }),
+            // An unconfirmed sighting of the same address does not turn a
+            // reachable entry stale.
+            Some(old) if !confirmed => {
+                self.entries.insert(ip, old);
+            }
+            Some(_) => {}
+        }
+    }
+
+    /// Learns the sender mapping of an ARP packet. Replies confirm the
+    /// mapping, requests and gratuitous announcements do not.
+    pub fn handle_arp(&mut self, arp: &ArpHdr, now: Duration) {
+        if arp.spa.is_unspecified() {
+            // ARP probe, the sender does not own the address yet.
+            return;
+        }
+        let confirmed = arp.op() == Some(ArpOp::Reply) && !arp.is_gratuitous();
+        self.update(IpAddr::V4(arp.spa), arp.sha, confirmed, now);
+    }
+
+    /// Learns the target mapping of a Neighbor Advertisement, `opts` being
+    /// the options following it. Follows the rules of RFC 4861 section
+    /// 7.2.5: without the Override flag, a different link-layer address
+    /// does not replace the cached one, the entry only turns stale.
+    pub fn handle_na(&mut self, na: &NeighborAdvertHdr, opts: NdOptions<'_>, now: Duration) {
+        let ip = IpAddr::V6(na.target);
+        let mac = opts
+            .filter(|opt| opt.option_type() == Some(NdOptionType::TargetLinkAddr))
+            .find_map(|opt| opt.link_addr());
+        let entry = self.entries.get_mut(&ip);
+        let Some(mac) = mac.or(entry.as_ref().map(|entry| entry.mac)) else {
+            return;
+        };
+        match entry {
+            Some(entry) if entry.mac != mac && !na.override_flag() => {
+                if entry.state == NeighborState::Reachable {
+                    entry.state = NeighborState::Stale;
+                    (self.on_event)(NeighborEvent::Stale(ip));
+                }
+            }
+            Some(entry) if entry.mac == mac && !na.solicited() => entry.last_seen = now,
+            _ => self.update(ip, mac, na.solicited(), now),
+        }
+    }
+
+    /// Ages the entries at time `now`: reachable entries which were not
+    /// confirmed for the reachable time become stale, and stale entries not
+    /// seen for the expiry time are removed.
+    pub fn expire(&mut self, now: Duration) {
+        let (reachable_time, expiry_time) = (self.reachable_time, self.expiry_time);
+        let on_event = &mut self.on_event;
+        self.entries.retain(|ip, entry| {
+            let age = now.saturating_sub(entry.last_seen);
+            if entry.state == NeighborState::Reachable && age >= reachable_time {
+                entry.state = NeighborState::Stale;
+                on_event(NeighborEvent::Stale(*ip));
+            }
+            if entry.state == NeighborState::Stale && age >= expiry_time {
+                on_event(NeighborEvent::Expired(*ip));
+                return false;
+            }
+            true
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use core::{net::IpAddr, time::Duration};
+
+    use super::{NeighborCache, NeighborEvent, NeighborState};
+    use crate::{
+        header::Header,
+        ndp::{NdOptions, NeighborAdvertHdr},
+    };
+
+    #[test]
+    fn test_neighbor_advert() {
+        let mut events = Vec::new();
+        let mut cache =
+            NeighborCache::new(Duration::from_secs(30), Duration::from_secs(60), |event| {
+                events.push(event)
+            });
+        let mac1 = [2, 0, 0, 0, 0, 1];
+        let mac2 = [2, 0, 0, 0, 0, 2];
+        let mut na = [0u8; NeighborAdvertHdr::LEN + 8];
+        na[0] = 136;
+        na[4] = 0x60; // solicited, override
+        na[8..24].copy_from_slice(&[0xfe, 0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+        na[24..].copy_from_slice(&[2, 1, 2, 0, 0, 0, 0, 1]);
+        let ip: IpAddr = "fe80::1".parse().unwrap();
+
+        let hdr = NeighborAdvertHdr::from_bytes(&na).unwrap();
+        let opts = NdOptions::new(&na[NeighborAdvertHdr::LEN..]);
+        cache.handle_na(hdr, opts, Duration::from_secs(0));
+        assert_eq!(cache.get(ip).unwrap().state, NeighborState::Reachable);
+
+        // Unsolicited, without override and with another address.
+        na[4] = 0;
+        na[31] = 2;
+        let hdr = NeighborAdvertHdr::from_bytes(&na).unwrap();
+        let opts = NdOptions::new(&na[NeighborAdvertHdr::LEN..]);
+        cache.handle_na(hdr, opts, Duration::from_secs(1));
+        assert_eq!(cache.get(ip).unwrap().mac, mac1);
+        assert_eq!(cache.get(ip).unwrap().state, NeighborState::Stale);
+
+        // With override, the new address is taken.
+        na[4] = 0x20;
+        let hdr = NeighborAdvertHdr::from_bytes(&na).unwrap();
+        let opts = NdOptions::new(&na[NeighborAdvertHdr::LEN..]);
+        cache.handle_na(hdr, opts, Duration::from_secs(2));
+        assert_eq!(cache.get(ip).unwrap().mac, mac2);
+
+        cache.expire(Duration::from_secs(100));
+        assert!(cache.is_empty());
+        drop(cache);
+        assert_eq!(
+            events,
+            [
+                NeighborEvent::Added(ip, mac1),
+                NeighborEvent::Stale(ip),
+                NeighborEvent::MacChanged {
+                    ip,
+                    old: mac1,
+                    new: mac2
+                },
+                NeighborEvent::Expired(ip),
+            ]
+        );
+    }
+}
@@ -0,0 +1,136 @@
+//! [nom](https://docs.rs/nom) parsers for the headers of this crate, so they
+//! can be embedded in larger nom grammars.
+//!
+//! Each parser returns a reference to the header, without copying, along
+//! with the input following it. Truncated input fails with
+//! [`ErrorKind::Eof`], and headers rejected by [`Header::validate`] with
+//! [`ErrorKind::Verify`].
+//!
+//! ```
+//! use ether_packet::nom::{eth_hdr, ipv4_hdr};
+//! use nom::{error::Error, Parser};
+//!
+//! let frame = [
+//!     0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0, 0x11, 0x22, 0x33, 0x44, 0x55, 0x08, 0x00,
+//!     0x45, 0, 0, 20, 0, 0, 0, 0, 64, 17, 0, 0, 10, 0, 0, 1, 10, 0, 0, 2,
+//! ];
+//! let (rest, (eth, ip)) = (eth_hdr, ipv4_hdr::<Error<_>>).parse(&frame[..]).unwrap();
+//! assert_eq!(eth.ether_type.to_bits(), 0x0800);
+//! assert_eq!(ip.ttl, 64);
+//! assert!(rest.is_empty());
+//! ```
+
+use core::mem;
+
+use ::nom::{
+    error::{ErrorKind, ParseError},
+    Err, IResult,
+};
+
+use crate::{
+    arp::ArpHdr,
+    dhcp::DhcpHdr,
+    dns::DnsHdr,
+    eth::{EthHdr, QinQHdr, VlanHdr},
+    geneve::GeneveHdr,
+    header::Header,
+    ip::{Ipv4Hdr, Ipv6Hdr},
+    mpls::MplsHdr,
+    sll::{Sll2Hdr, SllHdr},
+    vxlan::VxlanHdr,
+};
+
+/// Parses any fixed-size header.
+#[inline]
+pub fn header<'a, T: Header, E: ParseError<&'a [u8]>>(
+    input: &'a [u8],
+) -> IResult<&'a [u8], &'a T, E> {
+    if input.len() < mem::size_of::<T>() {
+        return Err(Err::Error(E::from_error_kind(input, ErrorKind::Eof)));
+    }
+    match T::from_bytes(input) {
+        Some(hdr) => Ok((&input[mem::size_of::<T>()..], hdr)),
+        None => Err(Err::Error(E::from_error_kind(input, ErrorKind::Verify))),
+    }
+}
+
+macro_rules! header_parsers {
+    ($($name:ident => $ty:ty),* $(,)?) => {
+        $(
+            #[doc = concat!("Parses a [`", stringify!($ty), "`].")]
+            #[inline]
+            pub fn $name<'a, E: ParseError<&'a [u8]>>(
+                input: &'a [u8],
+            ) -> IResult<&'a [u8], &'a $ty, E> {
+                header(input)
+            }
+        )*
+    };
+}
+
+header_parsers! {
+    arp_hdr => ArpHdr,
+    dhcp_hdr => DhcpHdr,
+    dns_hdr => DnsHdr,
+    eth_hdr => EthHdr,
+    geneve_hdr => GeneveHdr,
+    ipv6_hdr => Ipv6Hdr,
+    mpls_hdr => MplsHdr,
+    qinq_hdr => QinQHdr,
+    sll_hdr => SllHdr,
+    sll2_hdr => Sll2Hdr,
+    vlan_hdr => VlanHdr,
+    vxlan_hdr => VxlanHdr,
+}
+
+/// Parses an [`Ipv4Hdr`], also consuming its options.
+pub fn ipv4_hdr<'a, E: ParseError<&'a [u8]>>(input: &'a [u8]) -> IResult<&'a [u8], &'a Ipv4Hdr, E> {
+    let (_, hdr) = header::<Ipv4Hdr, E>(input)?;
+    let len = hdr.hdrlen();
+    if len < Ipv4Hdr::LEN {
+        return Err(Err::Error(E::from_error_kind(input, ErrorKind::Verify)));
+    }
+    match input.get(len..) {
+        Some(rest) => Ok((rest, hdr)),
+        None => Err(Err::Error(E::from_error_kind(input, ErrorKind::Eof))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::nom::{
+        error::{Error, ErrorKind},
+        Err,
+    };
+
+    use super::ipv4_hdr;
+
+    #[test]
+    fn test_ipv4_hdr() {
+        let mut bytes = [
+            0x46, 0, 0, 28, 0, 0, 0, 0, 64, 17, 0, 0, 10, 0, 0, 1, 10, 0, 0, 2, 1, 1, 1, 0, 0xaa,
+        ];
+        let (rest, hdr) = ipv4_hdr::<Error<_>>(&bytes).unwrap();
+        assert_eq!(hdr.ihl(), 6);
+        assert_eq!(rest, &[0xaa]);
+
+        let err = ipv4_hdr::<Error<_>>(&bytes[..22]).unwrap_err();
+        assert!(matches!(
+            err,
+            Err::Error(Error {
+                code: ErrorKind::Eof,
+                ..
+            })
+        ));
+
+        bytes[0] = 0x44;
+        let err = ipv4_hdr::<Error<_>>(&bytes).unwrap_err();
+        assert!(matches!(
+            err,
+            Err::Error(Error {
+                code: ErrorKind::Verify,
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,195 @@
+//! IPv6-to-IPv6 network prefix translation (NPTv6,
+//! [RFC 6296](https://datatracker.ietf.org/doc/html/rfc6296)).
+//!
+//! [`Nptv6`] swaps an internal prefix for an external one of the same
+//! length and compensates the change in one 16-bit word of the address, so
+//! that the ones' complement sum of the address is unchanged. The
+//! checksums of TCP, UDP and ICMPv6, which cover the addresses through the
+//! pseudo-header, stay valid and a gateway only rewrites the addresses.
+
+use core::net::Ipv6Addr;
+
+use crate::{checksum, ip::Ipv6Hdr};
+
+/// Mask of the first `len` bits of an address.
+#[inline]
+const fn mask(len: u8) -> u128 {
+    match len {
+        0 => 0,
+        len => u128::MAX << (128 - len as u32),
+    }
+}
+
+/// Ones' complement sum of the words of the first 64 bits of `prefix`.
+#[inline]
+fn prefix_sum(prefix: u128) -> u16 {
+    !checksum::fold(checksum::sum(&prefix.to_be_bytes()[..8], 0))
+}
+
+/// Ones' complement addition of `a` and `b`.
+#[inline]
+const fn add(a: u16, b: u16) -> u16 {
+    let sum = a as u32 + b as u32;
+    (sum as u16).wrapping_add((sum >> 16) as u16)
+}
+
+/// Checksum-neutral mapping between an internal and an external prefix.
+///
+/// ```
+/// use ether_packet::nptv6::Nptv6;
+///
+/// // Example of RFC 6296, section 3.1.
+/// let npt = Nptv6::new("fd01:203:405::".parse().unwrap(), "2001:db8:1::".parse().unwrap(), 48)
+///     .unwrap();
+/// let inside = "fd01:203:405:1::1234".parse().unwrap();
+/// let outside = npt.outbound(inside).unwrap();
+/// assert_eq!(outside, "2001:db8:1:d550::1234".parse::<core::net::Ipv6Addr>().unwrap());
+/// assert_eq!(npt.inbound(outside), Some(inside));
+/// ```
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub struct Nptv6 {
+    internal: u128,
+    external: u128,
+    prefix_len: u8,
+    /// Added to the adjusted word of outbound addresses, and subtracted
+    /// from the one of inbound addresses.
+    adjustment: u16,
+}
+
+impl Nptv6 {
+    /// Maps the `internal` prefix to the `external` one, both of
+    /// `prefix_len` bits, ignoring the bits of the addresses beyond it.
+    ///
+    /// Returns `None` if `prefix_len` is larger than 64, as the word
+    /// holding the adjustment must be outside of the prefix.
+    pub fn new(internal: Ipv6Addr, external: Ipv6Addr, prefix_len: u8) -> Option<Self> {
+        if prefix_len > 64 {
+            return None;
+        }
+        let internal = internal.to_bits() & mask(prefix_len);
+        let external = external.to_bits() & mask(prefix_len);
+        Some(Self {
+            internal,
+            external,
+            prefix_len,
+            adjustment: add(prefix_sum(internal), !prefix_sum(external)),
+        })
+    }
+
+    #[inline]
+    pub fn internal(&self) -> Ipv6Addr {
+        Ipv6Addr::from_bits(self.internal)
+    }
+
+    #[inline]
+    pub fn external(&self) -> Ipv6Addr {
+        Ipv6Addr::from_bits(self.external)
+    }
+
+    #[inline]
+    pub const fn prefix_len(&self) -> u8 {
+        self.prefix_len
+    }
+
+    /// Translates an internal address, e.g. the source address of an
+    /// outgoing packet.
+    ///
+    /// Returns `None` if `addr` is not in the internal prefix or cannot be
+    /// translated, the adjusted word being 0xffff.
+    #[inline]
+    pub fn outbound(&self, addr: Ipv6Addr) -> Option<Ipv6Addr> {
+        self.map(addr, self.internal, self.external, self.adjustment)
+    }
+
+    /// Translates an external address, e.g. the destination address of an
+    /// incoming packet, see [`outbound`](Self::outbound).
+    #[inline]
+    pub fn inbound(&self, addr: Ipv6Addr) -> Option<Ipv6Addr> {
+        self.map(addr, self.external, self.internal, !self.adjustment)
+    }
+
+    /// Translates the source address of the outgoing packet `hdr`, returning
+    /// whether it was translated.
+    pub fn translate_source(&self, hdr: &mut Ipv6Hdr) -> bool {
+        let Some(src_addr) = self.outbound(hdr.src_addr) else {
+            return false;
+        };
+        hdr.src_addr = src_addr;
+        true
+    }
+
+    /// Translates the destination address of the incoming packet `hdr`,
+    /// returning whether it was translated.
+    pub fn translate_destination(&self, hdr: &mut Ipv6Hdr) -> bool {
+        let Some(dst_addr) = self.inbound(hdr.dst_addr) else {
+            return false;
+        };
+        hdr.dst_addr = dst_addr;
+        true
+    }
+
+    fn map(&self, addr: Ipv6Addr, from: u128, to: u128, adjustment: u16) -> Option<Ipv6Addr> {
+        let mask = mask(self.prefix_len);
+        let bits = addr.to_bits();
+        if bits & mask != from {
+            return None;
+        }
+        let mut words = Ipv6Addr::from_bits(to | (bits & !mask)).segments();
+        // Bits 48 to 63 for prefixes up to /48, else the first word of the
+        // interface identifier which is not 0xffff (RFC 6296, section 3.4).
+        let index = if self.prefix_len <= 48 {
+            3
+        } else {
+            (4..8).find(|&i| words[i] != 0xffff)?
+        };
+        if words[index] == 0xffff {
+            return None;
+        }
+        words[index] = match add(words[index], adjustment) {
+            0xffff => 0,
+            word => word,
+        };
+        Some(Ipv6Addr::from(words))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::net::Ipv6Addr;
+
+    use super::Nptv6;
+    use crate::checksum;
+
+    #[test]
+    fn test_nptv6() {
+        assert_eq!(
+            Nptv6::new(Ipv6Addr::UNSPECIFIED, Ipv6Addr::UNSPECIFIED, 65),
+            None
+        );
+        let sum = |addr: Ipv6Addr| checksum::fold(checksum::sum(&addr.octets(), 0));
+
+        let npt = Nptv6::new(
+            "fd00:aaaa:bbbb:cc00::".parse().unwrap(),
+            "2001:db8:1234:5600::".parse().unwrap(),
+            56,
+        )
+        .unwrap();
+        let inside: Ipv6Addr = "fd00:aaaa:bbbb:cc07:ffff:1:2:3".parse().unwrap();
+        let outside = npt.outbound(inside).unwrap();
+        assert_eq!(sum(outside), sum(inside));
+        assert_eq!(outside.segments()[..4], [0x2001, 0xdb8, 0x1234, 0x5607]);
+        // The first word of the interface identifier is skipped.
+        assert_eq!(outside.segments()[4], 0xffff);
+        assert_eq!(npt.inbound(outside), Some(inside));
+
+        assert_eq!(
+            npt.outbound("fd00:aaaa:bbbb:cd00::1".parse().unwrap()),
+            None
+        );
+        assert_eq!(
+            npt.outbound("fd00:aaaa:bbbb:cc00:ffff:ffff:ffff:ffff".parse().unwrap()),
+            None
+        );
+        assert_eq!(npt.inbound(inside), None);
+    }
+}
@@ -0,0 +1,912 @@
+//! Safe parsing of the header chain of an Ethernet frame.
+//!
+//! [`Packet::parse`] walks a frame from the Ethernet header through the
+//! 802.1Q and 802.1ad tags, the IPv4 header and its options or the IPv6
+//! header and its extension headers, checking every length field against
+//! the data. The layers are returned as references into the frame, so
+//! nothing is copied and the caller writes no `unsafe` code.
+
+use core::{net::IpAddr, ops::Range};
+
+use crate::{
+    capture::{Captured, Field},
+    eth::{self, EthHdr, EtherType, VlanTagKind, FCS_LEN},
+    header::{Header, ParseConfig, ParseError},
+    icmp::IcmpHdr,
+    icmpv6::Icmpv6Hdr,
+    ip::{IpProto, Ipv4Hdr, Ipv6Hdr},
+    sctp::SctpHdr,
+    sll::{Sll2Hdr, SllHdr},
+    tcp::TcpHdr,
+    udp::UdpHdr,
+};
+
+/// Largest number of VLAN tags of a frame accepted by [`Packet::parse`].
+pub const MAX_VLAN_TAGS: usize = 2;
+
+/// An 802.1Q or 802.1ad tag.
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct VlanTag {
+    /// [`EtherType::VLAN`] or [`EtherType::QinQ`].
+    pub tpid: u16,
+    /// Tag control information.
+    pub tci: u16,
+}
+
+impl VlanTag {
+    #[inline]
+    pub const fn vid(&self) -> u16 {
+        self.tci & 0xfff
+    }
+
+    #[inline]
+    pub const fn dei(&self) -> bool {
+        self.tci & 0x1000 != 0
+    }
+
+    #[inline]
+    pub const fn pcp(&self) -> u8 {
+        (self.tci >> 13) as u8
+    }
+
+    #[inline]
+    pub const fn kind(&self) -> VlanTagKind {
+        VlanTagKind::from_vid(self.vid())
+    }
+}
+
+/// Link-layer header type of a capture, i.e. its `LINKTYPE_*` value, as
+/// found in pcap and pcapng files.
+///
+/// [Link-layer header types](https://www.tcpdump.org/linktypes.html)
+#[repr(u16)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum LinkType {
+    /// BSD loopback, a 4-byte address family in the byte order of the
+    /// capturing host.
+    Null = 0,
+    #[default]
+    Ethernet = 1,
+    /// IPv4 or IPv6 without link-layer header, as captured on tun
+    /// interfaces.
+    Raw = 101,
+    /// OpenBSD loopback, a 4-byte address family in network byte order.
+    Loop = 108,
+    /// Linux cooked capture, as taken with `tcpdump -i any`, see
+    /// [`SllHdr`].
+    LinuxSll = 113,
+    Ipv4 = 228,
+    Ipv6 = 229,
+    /// Linux cooked capture, version 2, see [`Sll2Hdr`].
+    LinuxSll2 = 276,
+}
+
+impl TryFrom<u16> for LinkType {
+    type Error = ();
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(LinkType::Null),
+            1 => Ok(LinkType::Ethernet),
+            101 => Ok(LinkType::Raw),
+            108 => Ok(LinkType::Loop),
+            113 => Ok(LinkType::LinuxSll),
+            228 => Ok(LinkType::Ipv4),
+            229 => Ok(LinkType::Ipv6),
+            276 => Ok(LinkType::LinuxSll2),
+            _ => Err(()),
+        }
+    }
+}
+
+impl LinkType {
+    /// Link type of the `DLT_*` datalink type of a live capture, as
+    /// returned by `pcap_datalink`. Most of them have the value of their
+    /// `LINKTYPE_*`, but `DLT_RAW` and `DLT_LOOP` vary between platforms.
+    pub fn from_dlt(dlt: i32) -> Option<Self> {
+        match dlt {
+            12 if cfg!(target_os = "openbsd") => Some(LinkType::Loop),
+            14 if cfg!(target_os = "openbsd") => Some(LinkType::Raw),
+            12 => Some(LinkType::Raw),
+            dlt => LinkType::try_from(u16::try_from(dlt).ok()?).ok(),
+        }
+    }
+
+    /// EtherType of the packet following the link-layer header `data` of a
+    /// [`LinkType::Null`], [`LinkType::Loop`] or Linux cooked capture, or
+    /// of the [`LinkType::Raw`] packet `data`.
+    fn ether_type(self, data: &[u8]) -> u16 {
+        let family = match (self, data) {
+            (LinkType::LinuxSll, _) => {
+                return SllHdr::from_bytes(data).map_or(0, |hdr| hdr.protocol.to_bits())
+            }
+            (LinkType::LinuxSll2, _) => {
+                return Sll2Hdr::from_bytes(data).map_or(0, |hdr| hdr.protocol.to_bits())
+            }
+            (LinkType::Null | LinkType::Loop, [0, 0, hi, lo, ..]) => u16::from_be_bytes([*hi, *lo]),
+            (LinkType::Null, [lo, hi, 0, 0, ..]) => u16::from_le_bytes([*lo, *hi]),
+            (LinkType::Raw, [b, ..]) if b >> 4 == 4 => return EtherType::Ipv4 as u16,
+            (LinkType::Raw, [b, ..]) if b >> 4 == 6 => return EtherType::Ipv6 as u16,
+            (LinkType::Ipv4, _) => return EtherType::Ipv4 as u16,
+            (LinkType::Ipv6, _) => return EtherType::Ipv6 as u16,
+            _ => return 0,
+        };
+        // AF_INET6 differs between the BSDs, Linux and macOS.
+        match family {
+            2 => EtherType::Ipv4 as u16,
+            10 | 24 | 28 | 30 => EtherType::Ipv6 as u16,
+            _ => 0,
+        }
+    }
+
+    /// Length of the link-layer header, which is followed by a VLAN tag or
+    /// the network layer.
+    #[inline]
+    const fn header_len(self) -> usize {
+        match self {
+            LinkType::Null | LinkType::Loop => 4,
+            LinkType::Ethernet => EthHdr::LEN,
+            LinkType::LinuxSll => SllHdr::LEN,
+            LinkType::LinuxSll2 => Sll2Hdr::LEN,
+            LinkType::Raw | LinkType::Ipv4 | LinkType::Ipv6 => 0,
+        }
+    }
+}
+
+/// Network layer header of a [`Packet`].
+#[derive(Debug, Copy, Clone)]
+pub enum NetworkHdr<'a> {
+    Ipv4(&'a Ipv4Hdr),
+    Ipv6(&'a Ipv6Hdr),
+}
+
+impl NetworkHdr<'_> {
+    #[inline]
+    pub fn src_addr(&self) -> IpAddr {
+        match self {
+            NetworkHdr::Ipv4(hdr) => hdr.src_addr.into(),
+            NetworkHdr::Ipv6(hdr) => hdr.src_addr.into(),
+        }
+    }
+
+    #[inline]
+    pub fn dst_addr(&self) -> IpAddr {
+        match self {
+            NetworkHdr::Ipv4(hdr) => hdr.dst_addr.into(),
+            NetworkHdr::Ipv6(hdr) => hdr.dst_addr.into(),
+        }
+    }
+}
+
+/// Transport layer header of a [`Packet`].
+#[derive(Debug, Copy, Clone)]
+pub enum TransportHdr<'a> {
+    Tcp(&'a TcpHdr),
+    Udp(&'a UdpHdr),
+    Icmp(&'a IcmpHdr),
+    Icmpv6(&'a Icmpv6Hdr),
+    Sctp(&'a SctpHdr),
+}
+
+impl TransportHdr<'_> {
+    /// Source port of TCP, UDP and SCTP headers.
+    #[inline]
+    pub fn src_port(&self) -> Option<u16> {
+        match self {
+            TransportHdr::Tcp(hdr) => Some(hdr.source.to_bits()),
+            TransportHdr::Udp(hdr) => Some(hdr.source.to_bits()),
+            TransportHdr::Sctp(hdr) => Some(hdr.src_port.to_bits()),
+            TransportHdr::Icmp(_) | TransportHdr::Icmpv6(_) => None,
+        }
+    }
+
+    /// Destination port of TCP, UDP and SCTP headers.
+    #[inline]
+    pub fn dst_port(&self) -> Option<u16> {
+        match self {
+            TransportHdr::Tcp(hdr) => Some(hdr.dest.to_bits()),
+            TransportHdr::Udp(hdr) => Some(hdr.dest.to_bits()),
+            TransportHdr::Sctp(hdr) => Some(hdr.dst_port.to_bits()),
+            TransportHdr::Icmp(_) | TransportHdr::Icmpv6(_) => None,
+        }
+    }
+
+    /// Length of the header, with the options of TCP headers.
+    #[inline]
+    pub fn hdrlen(&self) -> usize {
+        match self {
+            TransportHdr::Tcp(hdr) => hdr.hdrlen(),
+            TransportHdr::Udp(_) => UdpHdr::LEN,
+            TransportHdr::Icmp(_) => IcmpHdr::LEN,
+            TransportHdr::Icmpv6(_) => Icmpv6Hdr::LEN,
+            TransportHdr::Sctp(_) => SctpHdr::LEN,
+        }
+    }
+}
+
+/// Largest number of layers recorded by [`Packet::layers`]. Extension
+/// headers of IPv6 beyond it share the span of the last one.
+pub const MAX_LAYERS: usize = 8;
+
+/// Kind of a layer of a [`Packet`].
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum Layer {
+    /// Link-layer header, e.g. the Ethernet header.
+    Link,
+    Vlan,
+    Ipv4,
+    Ipv6,
+    /// IPv6 extension header, of the given type.
+    Ipv6Ext(IpProto),
+    Tcp,
+    Udp,
+    Icmp,
+    Icmpv6,
+    Sctp,
+}
+
+/// Bytes of the frame holding the header of a layer, options included.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct LayerSpan {
+    pub layer: Layer,
+    /// Offset of the header in [`Packet::data`].
+    pub offset: usize,
+    pub len: usize,
+}
+
+impl LayerSpan {
+    const EMPTY: LayerSpan = LayerSpan {
+        layer: Layer::Link,
+        offset: 0,
+        len: 0,
+    };
+
+    /// Range of the header in [`Packet::data`].
+    #[inline]
+    pub const fn range(&self) -> Range<usize> {
+        self.offset..self.offset + self.len
+    }
+}
+
+/// The layers of an Ethernet frame.
+///
+/// ```
+/// use ether_packet::{eth::EtherType, ip::IpProto, packet::Packet};
+///
+/// #[rustfmt::skip]
+/// let frame = [
+///     0, 1, 2, 3, 4, 5, 0, 6, 7, 8, 9, 10,
+///     // 802.1Q tag, VLAN 100.
+///     0x81, 0x00, 0x00, 100, 0x08, 0x00,
+///     // IPv4 with 4 bytes of options, UDP.
+///     0x46, 0, 0, 32, 0, 0, 0, 0, 64, 17, 0, 0, 10, 0, 0, 1, 10, 0, 0, 2, 1, 1, 1, 0,
+///     0x30, 0x39, 0, 53, 0, 8, 0, 0,
+///     // Ethernet padding.
+///     0, 0,
+/// ];
+/// let packet = Packet::parse(&frame).unwrap();
+/// assert_eq!(packet.vlan_tags()[0].vid(), 100);
+/// assert_eq!(packet.ether_type(), Some(EtherType::Ipv4));
+/// assert_eq!(packet.proto(), Some(IpProto::Udp));
+/// assert_eq!(packet.l4_offset(), Some(42));
+/// assert_eq!(packet.payload().len(), 8);
+/// ```
+#[derive(Debug, Copy, Clone)]
+pub struct Packet<'a> {
+    data: &'a [u8],
+    link_type: LinkType,
+    eth: Option<&'a EthHdr>,
+    vlan_tags: [VlanTag; MAX_VLAN_TAGS],
+    vlan_count: usize,
+    layers: [LayerSpan; MAX_LAYERS],
+    layer_count: usize,
+    ether_type: u16,
+    network: Option<NetworkHdr<'a>>,
+    l3_offset: Option<usize>,
+    l4_offset: Option<usize>,
+    proto: Option<IpProto>,
+    fragment: bool,
+    payload_offset: usize,
+    payload: &'a [u8],
+    truncated: bool,
+    fcs: Option<[u8; FCS_LEN]>,
+}
+
+impl<'a> Packet<'a> {
+    /// Parses a complete frame.
+    ///
+    /// Fails with [`ParseError::Malformed`] if a header is invalid, does
+    /// not fit into the frame, or if the frame carries more than
+    /// [`MAX_VLAN_TAGS`] tags.
+    #[inline]
+    pub fn parse(data: &'a [u8]) -> Result<Self, ParseError> {
+        Self::parse_captured(Captured::complete(data))
+    }
+
+    /// Parses a complete frame ending with its FCS, which is left out of
+    /// [`Packet::data`] and of the payload. See [`Packet::fcs_valid`].
+    #[inline]
+    pub fn parse_with_fcs(data: &'a [u8]) -> Result<Self, ParseError> {
+        Self::parse_captured(Captured::complete(data).with_fcs())
+    }
+
+    /// Parses a frame cut at the snapshot length of a capture. The layers
+    /// which were not captured are left unset and the packet is marked as
+    /// truncated, see [`Packet::is_truncated`].
+    #[inline]
+    pub fn parse_captured(captured: Captured<'a>) -> Result<Self, ParseError> {
+        Self::parse_with_config(captured, LinkType::Ethernet, &ParseConfig::DEFAULT)
+    }
+
+    /// Parses a complete packet starting with the link-layer header of
+    /// `link_type`, e.g. from a loopback or tun interface, or from a
+    /// `tcpdump -i any` capture.
+    ///
+    /// ```
+    /// use ether_packet::{
+    ///     eth::EtherType,
+    ///     ip::IpProto,
+    ///     packet::{LinkType, Packet},
+    /// };
+    ///
+    /// #[rustfmt::skip]
+    /// let data = [
+    ///     // AF_INET, little-endian.
+    ///     2, 0, 0, 0,
+    ///     0x45, 0, 0, 28, 0, 0, 0, 0, 64, 17, 0, 0, 127, 0, 0, 1, 127, 0, 0, 1,
+    ///     0x30, 0x39, 0, 53, 0, 8, 0, 0,
+    /// ];
+    /// let packet = Packet::parse_with_linktype(&data, LinkType::Null).unwrap();
+    /// assert!(packet.eth().is_none());
+    /// assert_eq!(packet.ether_type(), Some(EtherType::Ipv4));
+    /// assert_eq!(packet.l4_offset(), Some(24));
+    ///
+    /// let packet = Packet::parse_with_linktype(&data[4..], LinkType::Raw).unwrap();
+    /// assert_eq!(packet.proto(), Some(IpProto::Udp));
+    /// ```
+    #[inline]
+    pub fn parse_with_linktype(data: &'a [u8], link_type: LinkType) -> Result<Self, ParseError> {
+        Self::parse_with_config(Captured::complete(data), link_type, &ParseConfig::DEFAULT)
+    }
+
+    /// Parses a captured packet starting with the link-layer header of
+    /// `link_type`, within the limits of `config`.
+    ///
+    /// ```
+    /// use ether_packet::{
+    ///     capture::Captured,
+    ///     header::{ParseConfig, ParseError},
+    ///     packet::{LinkType, Packet},
+    /// };
+    ///
+    /// // IPv6 packet with 9 empty Destination Options headers.
+    /// let mut data = vec![0x60, 0, 0, 0, 0, 72, 60, 64];
+    /// data.extend_from_slice(&[0; 32]);
+    /// for i in 0..9 {
+    ///     data.extend_from_slice(&[if i < 8 { 60 } else { 59 }, 0, 1, 4, 0, 0, 0, 0]);
+    /// }
+    /// let captured = Captured::complete(&data);
+    /// assert!(Packet::parse_with_config(captured, LinkType::Raw, &ParseConfig::DEFAULT).is_ok());
+    /// assert_eq!(
+    ///     Packet::parse_with_config(captured, LinkType::Raw, &ParseConfig::HARDENED).err(),
+    ///     Some(ParseError::LimitExceeded)
+    /// );
+    /// ```
+    pub fn parse_with_config(
+        captured: Captured<'a>,
+        link_type: LinkType,
+        config: &ParseConfig,
+    ) -> Result<Self, ParseError> {
+        let data = captured.data();
+        let (eth, ether_type) = match link_type {
+            LinkType::Ethernet => match captured.header::<EthHdr>(0)? {
+                Field::Present(eth) => (Some(eth), eth.ether_type.to_bits()),
+                Field::Truncated => return Err(ParseError::Truncated),
+            },
+            _ => match captured.bytes(0, link_type.header_len().max(1))? {
+                Field::Present(hdr) => (None, link_type.ether_type(hdr)),
+                Field::Truncated => return Err(ParseError::Truncated),
+            },
+        };
+        let offset = link_type.header_len();
+        let mut packet = Packet {
+            data,
+            link_type,
+            eth,
+            vlan_tags: [VlanTag::default(); MAX_VLAN_TAGS],
+            vlan_count: 0,
+            layers: [LayerSpan::EMPTY; MAX_LAYERS],
+            layer_count: 0,
+            ether_type,
+            network: None,
+            l3_offset: None,
+            l4_offset: None,
+            proto: None,
+            fragment: false,
+            payload_offset: offset,
+            payload: &data[offset..],
+            truncated: captured.is_truncated(),
+            fcs: captured.fcs(),
+        };
+
+        if offset > 0 {
+            packet.push_layer(Layer::Link, 0, offset);
+        }
+        let mut offset = offset;
+        while packet.ether_type == EtherType::VLAN as u16
+            || packet.ether_type == EtherType::QinQ as u16
+        {
+            if packet.vlan_count == MAX_VLAN_TAGS {
+                return Err(ParseError::Malformed);
+            }
+            let Field::Present(tag) = captured.bytes(offset, 4)? else {
+                return Ok(packet.cut(offset));
+            };
+            packet.vlan_tags[packet.vlan_count] = VlanTag {
+                tpid: packet.ether_type,
+                tci: u16::from_be_bytes([tag[0], tag[1]]),
+            };
+            packet.vlan_count += 1;
+            packet.push_layer(Layer::Vlan, offset, 4);
+            packet.ether_type = u16::from_be_bytes([tag[2], tag[3]]);
+            offset += 4;
+        }
+        packet.payload_offset = offset;
+        packet.payload = captured.partial(offset, captured.original_len() - offset)?;
+
+        if packet.ether_type == EtherType::Ipv4 as u16 {
+            packet.parse_ipv4(&captured, offset)
+        } else if packet.ether_type == EtherType::Ipv6 as u16 {
+            packet.parse_ipv6(&captured, offset, config)
+        } else {
+            Ok(packet)
+        }
+    }
+
+    /// Marks the packet as truncated at `offset`.
+    fn cut(mut self, offset: usize) -> Self {
+        self.payload_offset = offset;
+        self.payload = &[];
+        self.truncated = true;
+        self
+    }
+
+    /// Records the header of `layer`, of `len` bytes at `offset`.
+    #[inline]
+    fn push_layer(&mut self, layer: Layer, offset: usize, len: usize) {
+        self.layers[self.layer_count] = LayerSpan { layer, offset, len };
+        self.layer_count += 1;
+    }
+
+    /// Sets the upper-layer header found at `offset`, the IP packet ending
+    /// at `end`.
+    fn set_l4(
+        mut self,
+        captured: &Captured<'a>,
+        proto: IpProto,
+        has_l4: bool,
+        offset: usize,
+        end: usize,
+    ) -> Result<Self, ParseError> {
+        self.proto = Some(proto);
+        self.l4_offset = has_l4.then_some(offset);
+        self.payload_offset = offset;
+        self.payload = captured.partial(offset, end - offset)?;
+        if let Some(hdr) = self.transport() {
+            let layer = match hdr {
+                TransportHdr::Tcp(_) => Layer::Tcp,
+                TransportHdr::Udp(_) => Layer::Udp,
+                TransportHdr::Icmp(_) => Layer::Icmp,
+                TransportHdr::Icmpv6(_) => Layer::Icmpv6,
+                TransportHdr::Sctp(_) => Layer::Sctp,
+            };
+            self.push_layer(layer, offset, hdr.hdrlen());
+        }
+        Ok(self)
+    }
+
+    fn parse_ipv4(mut self, captured: &Captured<'a>, l3: usize) -> Result<Self, ParseError> {
+        let hdr = match captured.header::<Ipv4Hdr>(l3)? {
+            Field::Present(hdr) => hdr,
+            Field::Truncated => return Ok(self.cut(l3)),
+        };
+        let (hdrlen, tot_len) = (hdr.hdrlen(), hdr.tot_len.to_bits() as usize);
+        if hdr.version() != 4 || hdrlen < Ipv4Hdr::LEN || tot_len < hdrlen {
+            return Err(ParseError::Malformed);
+        }
+        // Checks the total length and the options against the frame.
+        captured.partial(l3, tot_len)?;
+        if captured.bytes(l3, hdrlen)?.is_truncated() {
+            return Ok(self.cut(l3));
+        }
+        self.network = Some(NetworkHdr::Ipv4(hdr));
+        self.l3_offset = Some(l3);
+        self.push_layer(Layer::Ipv4, l3, hdrlen);
+        self.fragment = hdr.is_fragment();
+        self.set_l4(
+            captured,
+            hdr.proto,
+            hdr.has_l4_header(),
+            l3 + hdrlen,
+            l3 + tot_len,
+        )
+    }
+
+    fn parse_ipv6(
+        mut self,
+        captured: &Captured<'a>,
+        l3: usize,
+        config: &ParseConfig,
+    ) -> Result<Self, ParseError> {
+        let hdr = match captured.header::<Ipv6Hdr>(l3)? {
+            Field::Present(hdr) => hdr,
+            Field::Truncated => return Ok(self.cut(l3)),
+        };
+        if hdr.version() != 6 {
+            return Err(ParseError::Malformed);
+        }
+        let end = l3 + Ipv6Hdr::LEN + hdr.payload_len.to_bits() as usize;
+        captured.partial(l3, end - l3)?;
+        self.network = Some(NetworkHdr::Ipv6(hdr));
+        self.l3_offset = Some(l3);
+        self.push_layer(Layer::Ipv6, l3, Ipv6Hdr::LEN);
+
+        let (mut next_hdr, mut offset, mut has_l4) = (hdr.next_hdr, l3 + Ipv6Hdr::LEN, true);
+        let mut ext_hdrs = 0;
+        while matches!(
+            next_hdr,
+            IpProto::HopOpt | IpProto::Ipv6Route | IpProto::Ipv6Opts | IpProto::Ipv6Frag
+        ) {
+            if ext_hdrs == config.max_ipv6_ext_hdrs {
+                return Err(ParseError::LimitExceeded);
+            }
+            ext_hdrs += 1;
+            let Field::Present(ext) = captured.bytes(offset, 4)? else {
+                return Ok(self.cut(offset));
+            };
+            let len = match next_hdr {
+                IpProto::Ipv6Frag => {
+                    let frag_off = u16::from_be_bytes([ext[2], ext[3]]);
+                    has_l4 = frag_off & 0xfff8 == 0;
+                    self.fragment = true;
+                    8
+                }
+                IpProto::Ipv6Route => (ext[1] as usize + 1) * 8,
+                _ => {
+                    let len = (ext[1] as usize + 1) * 8;
+                    if len > config.max_options_len {
+                        return Err(ParseError::LimitExceeded);
+                    }
+                    len
+                }
+            };
+            let ext_hdr = next_hdr;
+            next_hdr = IpProto::from_u8(ext[0]).ok_or(ParseError::Malformed)?;
+            if offset + len > end {
+                return Err(ParseError::Malformed);
+            }
+            // Leaves a span to the transport header.
+            if self.layer_count < MAX_LAYERS - 1 {
+                self.push_layer(Layer::Ipv6Ext(ext_hdr), offset, len);
+            } else {
+                let last = &mut self.layers[self.layer_count - 1];
+                last.len = offset + len - last.offset;
+            }
+            offset += len;
+        }
+        self.set_l4(captured, next_hdr, has_l4, offset, end)
+    }
+
+    /// The whole frame, without its FCS.
+    #[inline]
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    #[inline]
+    pub fn link_type(&self) -> LinkType {
+        self.link_type
+    }
+
+    /// Ethernet header of the frame, unless the packet was captured with
+    /// another link type.
+    #[inline]
+    pub fn eth(&self) -> Option<&'a EthHdr> {
+        self.eth
+    }
+
+    /// VLAN tags of the frame, the outermost first.
+    #[inline]
+    pub fn vlan_tags(&self) -> &[VlanTag] {
+        &self.vlan_tags[..self.vlan_count]
+    }
+
+    /// Spans of the headers found in the frame, the outermost first. A
+    /// header cut by the snapshot length of a capture has no span.
+    ///
+    /// ```
+    /// use ether_packet::packet::{Layer, Packet};
+    ///
+    /// #[rustfmt::skip]
+    /// let frame = [
+    ///     0, 1, 2, 3, 4, 5, 0, 6, 7, 8, 9, 10, 0x08, 0x00,
+    ///     0x45, 0, 0, 28, 0, 1, 0x40, 0, 64, 17, 0, 0, 10, 0, 0, 1, 10, 0, 0, 2,
+    ///     0x30, 0x39, 0, 53, 0, 8, 0, 0,
+    /// ];
+    /// let packet = Packet::parse(&frame).unwrap();
+    /// let layers: Vec<_> = packet.layers().iter().map(|span| span.layer).collect();
+    /// assert_eq!(layers, [Layer::Link, Layer::Ipv4, Layer::Udp]);
+    /// // Strips the link-layer header.
+    /// assert_eq!(&frame[packet.layers()[1].offset..], &frame[14..]);
+    /// assert_eq!(packet.layers()[2].range(), 34..42);
+    /// ```
+    #[inline]
+    pub fn layers(&self) -> &[LayerSpan] {
+        &self.layers[..self.layer_count]
+    }
+
+    /// VLAN the frame belongs to, given by the outermost tag which is not a
+    /// priority tag. `None` for untagged and priority-tagged frames.
+    #[inline]
+    pub fn vid(&self) -> Option<u16> {
+        self.vlan_tags()
+            .iter()
+            .find(|tag| tag.kind() == VlanTagKind::Vlan)
+            .map(VlanTag::vid)
+    }
+
+    /// PCP of the outermost tag, priority tags included.
+    #[inline]
+    pub fn pcp(&self) -> Option<u8> {
+        self.vlan_tags().first().map(VlanTag::pcp)
+    }
+
+    /// EtherType following the VLAN tags.
+    #[inline]
+    pub fn ether_type(&self) -> Option<EtherType> {
+        EtherType::from_u16(self.ether_type)
+    }
+
+    #[inline]
+    pub fn network(&self) -> Option<NetworkHdr<'a>> {
+        self.network
+    }
+
+    #[inline]
+    pub fn ipv4(&self) -> Option<&'a Ipv4Hdr> {
+        match self.network {
+            Some(NetworkHdr::Ipv4(hdr)) => Some(hdr),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn ipv6(&self) -> Option<&'a Ipv6Hdr> {
+        match self.network {
+            Some(NetworkHdr::Ipv6(hdr)) => Some(hdr),
+            _ => None,
+        }
+    }
+
+    /// Offset of the IP header.
+    #[inline]
+    pub fn l3_offset(&self) -> Option<usize> {
+        self.l3_offset
+    }
+
+    /// Upper-layer protocol, following the IPv6 extension headers.
+    #[inline]
+    pub fn proto(&self) -> Option<IpProto> {
+        self.proto
+    }
+
+    /// Offset of the upper-layer header, unset for non-first fragments.
+    #[inline]
+    pub fn l4_offset(&self) -> Option<usize> {
+        self.l4_offset
+    }
+
+    /// Header found at [`Packet::l4_offset`], if its protocol is known and
+    /// it was captured entirely.
+    pub fn transport(&self) -> Option<TransportHdr<'a>> {
+        self.l4_offset?;
+        let l4 = self.payload;
+        let hdr = match self.proto? {
+            IpProto::Tcp => {
+                let hdr = TcpHdr::from_bytes(l4)?;
+                if hdr.hdrlen() > l4.len() {
+                    return None;
+                }
+                TransportHdr::Tcp(hdr)
+            }
+            IpProto::Udp => TransportHdr::Udp(UdpHdr::from_bytes(l4)?),
+            IpProto::Icmp => TransportHdr::Icmp(IcmpHdr::from_bytes(l4)?),
+            IpProto::Ipv6Icmp => TransportHdr::Icmpv6(Icmpv6Hdr::from_bytes(l4)?),
+            IpProto::Sctp => TransportHdr::Sctp(SctpHdr::from_bytes(l4)?),
+            _ => return None,
+        };
+        Some(hdr)
+    }
+
+    #[inline]
+    pub fn is_fragment(&self) -> bool {
+        self.fragment
+    }
+
+    /// Offset of [`Packet::payload`] in the frame.
+    #[inline]
+    pub fn payload_offset(&self) -> usize {
+        self.payload_offset
+    }
+
+    /// The captured data following the last parsed header, excluding the
+    /// Ethernet padding of IP packets.
+    #[inline]
+    pub fn payload(&self) -> &'a [u8] {
+        self.payload
+    }
+
+    /// Whether the frame was cut by the snapshot length of a capture.
+    #[inline]
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// FCS of the frame, if it was parsed with one, see
+    /// [`Captured::with_fcs`].
+    #[inline]
+    pub fn fcs(&self) -> Option<[u8; FCS_LEN]> {
+        self.fcs
+    }
+
+    /// Whether the FCS of the frame matches its data, or `None` if there is
+    /// no FCS or the frame was truncated.
+    pub fn fcs_valid(&self) -> Option<bool> {
+        let fcs = self.fcs.filter(|_| !self.truncated)?;
+        Some(eth::fcs(self.data) == fcs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Layer, LinkType, Packet, TransportHdr, MAX_LAYERS};
+    use crate::{capture::Captured, eth::VlanTagKind, header::ParseError, ip::IpProto};
+
+    #[test]
+    fn test_parse_ipv6() {
+        #[rustfmt::skip]
+        let frame = [
+            0, 1, 2, 3, 4, 5, 0, 6, 7, 8, 9, 10,
+            // 802.1ad and 802.1Q tags.
+            0x88, 0xa8, 0x00, 10, 0x81, 0x00, 0x20, 20, 0x86, 0xdd,
+            // IPv6, Hop-by-Hop options, TCP.
+            0x60, 0, 0, 0, 0, 28, 0, 64,
+            0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1,
+            0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2,
+            6, 0, 1, 4, 0, 0, 0, 0,
+            0x30, 0x39, 0, 80, 0, 0, 0, 1, 0, 0, 0, 0, 0x50, 0x02, 0xff, 0xff, 0, 0, 0, 0,
+        ];
+        let packet = Packet::parse(&frame).unwrap();
+        let tags = packet.vlan_tags();
+        assert_eq!((tags[0].vid(), tags[1].vid(), tags[1].pcp()), (10, 20, 1));
+        assert!(packet.ipv6().is_some());
+        assert_eq!(packet.proto(), Some(IpProto::Tcp));
+        assert_eq!(packet.l4_offset(), Some(22 + 40 + 8));
+        assert_eq!(packet.payload().len(), 20);
+        assert!(!packet.is_truncated());
+        assert_eq!((packet.vid(), packet.pcp()), (Some(10), Some(0)));
+        let spans = packet.layers();
+        assert_eq!(spans.len(), 6);
+        assert_eq!((spans[1].layer, spans[1].range()), (Layer::Vlan, 14..18));
+        assert_eq!((spans[3].layer, spans[3].range()), (Layer::Ipv6, 22..62));
+        assert_eq!(
+            (spans[4].layer, spans[4].range()),
+            (Layer::Ipv6Ext(IpProto::HopOpt), 62..70)
+        );
+        assert_eq!((spans[5].layer, spans[5].range()), (Layer::Tcp, 70..90));
+        match packet.transport() {
+            Some(TransportHdr::Tcp(tcp)) => {
+                assert_eq!((tcp.source.to_bits(), tcp.dest.to_bits()), (12345, 80));
+                assert_eq!((tcp.hdrlen(), tcp.syn(), tcp.ack()), (20, 1, 0));
+            }
+            hdr => panic!("unexpected transport header {hdr:?}"),
+        }
+
+        // Priority-tagged S-tag, PCP 5: the C-tag gives the VLAN.
+        let mut tagged = frame;
+        tagged[14..16].copy_from_slice(&[0xa0, 0x00]);
+        let packet = Packet::parse(&tagged).unwrap();
+        assert_eq!(packet.vlan_tags()[0].kind(), VlanTagKind::PriorityOnly);
+        assert_eq!((packet.vid(), packet.pcp()), (Some(20), Some(5)));
+
+        // Cut in the extension header.
+        let packet = Packet::parse_captured(Captured::new(&frame[..64], frame.len())).unwrap();
+        assert!(packet.is_truncated());
+        assert!(packet.ipv6().is_some());
+        assert_eq!(packet.proto(), None);
+        assert!(packet.transport().is_none());
+        assert_eq!(packet.layers().last().unwrap().layer, Layer::Ipv6);
+        // But not on the wire, the payload length being larger than the frame.
+        assert_eq!(
+            Packet::parse(&frame[..64]).unwrap_err(),
+            ParseError::Malformed
+        );
+
+        // The same IPv6 packet on an OpenBSD loopback, AF_INET6 being 24.
+        let mut looped = [0u8; 72];
+        looped[..4].copy_from_slice(&[0, 0, 0, 24]);
+        looped[4..].copy_from_slice(&frame[22..]);
+        let packet = Packet::parse_with_linktype(&looped, LinkType::Loop).unwrap();
+        assert!(packet.eth().is_none() && packet.vlan_tags().is_empty());
+        assert_eq!(packet.l4_offset(), Some(4 + 40 + 8));
+        assert_eq!(LinkType::try_from(108), Ok(LinkType::Loop));
+
+        // Extension headers beyond the spans share the last one.
+        let mut chained = [0u8; 40 + 8 * 8 + 8];
+        chained[..40].copy_from_slice(&frame[22..62]);
+        chained[4..6].copy_from_slice(&(8 * 8 + 8u16).to_be_bytes());
+        chained[6] = 60;
+        for i in 0..8 {
+            chained[40 + 8 * i] = if i < 7 { 60 } else { 17 };
+        }
+        chained[104..106].copy_from_slice(&[0, 53]);
+        chained[108] = 8;
+        let packet = Packet::parse_with_linktype(&chained, LinkType::Raw).unwrap();
+        let spans = packet.layers();
+        assert_eq!(spans.len(), MAX_LAYERS);
+        assert_eq!(spans[MAX_LAYERS - 2].range(), 40 + 8 * 5..104);
+        assert_eq!(
+            (spans[MAX_LAYERS - 1].layer, spans[MAX_LAYERS - 1].offset),
+            (Layer::Udp, 104)
+        );
+        assert_eq!(
+            Packet::parse_with_linktype(&[], LinkType::Raw).unwrap_err(),
+            ParseError::Malformed
+        );
+    }
+
+    #[test]
+    fn test_parse_sll() {
+        #[rustfmt::skip]
+        let ip = [
+            0x45, 0, 0, 32, 0, 1, 0x40, 0, 64, 17, 0, 0, 10, 0, 0, 1, 10, 0, 0, 2,
+            0x30, 0x39, 0, 53, 0, 12, 0, 0, b'a', b'b', b'c', b'd',
+        ];
+        // Outgoing, ARPHRD_ETHER, 6-byte address, IPv4.
+        let mut sll = [0u8; 16 + 32];
+        sll[..16].copy_from_slice(&[0, 4, 0, 1, 0, 6, 0, 2, 3, 4, 5, 6, 0, 0, 0x08, 0x00]);
+        sll[16..].copy_from_slice(&ip);
+        // IPv4, interface 3, ARPHRD_ETHER, sent to us.
+        let mut sll2 = [0u8; 20 + 32];
+        sll2[..20].copy_from_slice(&[
+            0x08, 0x00, 0, 0, 0, 0, 0, 3, 0, 1, 0, 6, 0, 2, 3, 4, 5, 6, 0, 0,
+        ]);
+        sll2[20..].copy_from_slice(&ip);
+
+        for (data, link_type, l3) in [
+            (&sll[..], LinkType::LinuxSll, 16),
+            (&sll2[..], LinkType::LinuxSll2, 20),
+        ] {
+            assert_eq!(LinkType::try_from(link_type as u16), Ok(link_type));
+            let packet = Packet::parse_with_linktype(data, link_type).unwrap();
+            assert!(packet.eth().is_none());
+            assert_eq!(packet.l3_offset(), Some(l3));
+            assert_eq!(packet.proto(), Some(IpProto::Udp));
+            assert_eq!(packet.layers()[0].range(), 0..l3);
+            match packet.transport() {
+                Some(TransportHdr::Udp(udp)) => assert_eq!(udp.dest.to_bits(), 53),
+                hdr => panic!("unexpected transport header {hdr:?}"),
+            }
+            assert_eq!(&packet.payload()[8..], b"abcd");
+        }
+
+        // Cut in the cooked header.
+        assert_eq!(
+            Packet::parse_with_linktype(&sll[..10], LinkType::LinuxSll).unwrap_err(),
+            ParseError::Malformed
+        );
+    }
+}
@@ -0,0 +1,683 @@
+//! Reading of [pcapng](https://www.ietf.org/archive/id/draft-ietf-opsawg-pcapng-03.html)
+//! capture files.
+//!
+//! [`Parser`] decodes blocks out of byte slices without doing any I/O, and
+//! keeps track of the byte order and the interfaces of the current section.
+//! With the `futures-io` feature, [`AsyncReader`] drives it from any
+//! [`AsyncRead`](futures_io::AsyncRead) source, such as a pipe fed by
+//! `tcpdump -w -` or a file still being written. Tokio sources can be used
+//! through the `compat` adapters of `tokio-util`. The packets they return
+//! are parsed with [`Packet::parse`], according to the link type of their
+//! interface.
+
+use alloc::vec::Vec;
+use core::{fmt, iter, time::Duration};
+
+use crate::{
+    capture::Captured,
+    header::{ParseConfig, ParseError},
+    meta::{Direction, PacketMeta},
+    packet::{self, LinkType},
+};
+
+const SECTION_HEADER: u32 = 0x0a0d0d0a;
+const INTERFACE_DESCRIPTION: u32 = 0x00000001;
+const SIMPLE_PACKET: u32 = 0x00000003;
+const ENHANCED_PACKET: u32 = 0x00000006;
+
+const BYTE_ORDER_MAGIC: u32 = 0x1a2b3c4d;
+
+const IF_TSRESOL: u16 = 9;
+const IF_TSOFFSET: u16 = 14;
+const EPB_FLAGS: u16 = 2;
+
+/// Largest block accepted by default by a [`Parser`].
+pub const DEFAULT_MAX_BLOCK_LEN: usize = 16 * 1024 * 1024;
+
+/// Error returned when reading a pcapng stream.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum PcapngError {
+    /// A block was found before the first section header block.
+    NoSection,
+    /// The byte-order magic of a section header block is invalid.
+    InvalidMagic(u32),
+    /// The major version of a section is not 1.
+    UnsupportedVersion(u16),
+    /// A block of the given type is truncated or has inconsistent lengths.
+    InvalidBlock(u32),
+    /// A packet refers to an interface which was not described.
+    UnknownInterface(u32),
+    /// A block is larger than the limit of the parser.
+    BlockTooLarge(usize),
+    /// The underlying reader failed.
+    #[cfg(feature = "std")]
+    Io(std::io::ErrorKind),
+}
+
+impl fmt::Display for PcapngError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PcapngError::NoSection => f.write_str("block outside of a section"),
+            PcapngError::InvalidMagic(magic) => write!(f, "invalid byte-order magic {magic:#x}"),
+            PcapngError::UnsupportedVersion(major) => write!(f, "unsupported version {major}"),
+            PcapngError::InvalidBlock(ty) => write!(f, "invalid block of type {ty:#x}"),
+            PcapngError::UnknownInterface(id) => write!(f, "unknown interface {id}"),
+            PcapngError::BlockTooLarge(len) => write!(f, "block of {len} bytes is too large"),
+            #[cfg(feature = "std")]
+            PcapngError::Io(kind) => write!(f, "I/O error: {kind}"),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+enum ByteOrder {
+    Big,
+    Little,
+}
+
+impl ByteOrder {
+    #[inline]
+    fn u16(self, b: &[u8], at: usize) -> u16 {
+        let bytes = [b[at], b[at + 1]];
+        match self {
+            ByteOrder::Big => u16::from_be_bytes(bytes),
+            ByteOrder::Little => u16::from_le_bytes(bytes),
+        }
+    }
+
+    #[inline]
+    fn u32(self, b: &[u8], at: usize) -> u32 {
+        let bytes = [b[at], b[at + 1], b[at + 2], b[at + 3]];
+        match self {
+            ByteOrder::Big => u32::from_be_bytes(bytes),
+            ByteOrder::Little => u32::from_le_bytes(bytes),
+        }
+    }
+}
+
+/// Iterates over the options of a block, stopping at `opt_endofopt`.
+fn options(order: ByteOrder, mut data: &[u8]) -> impl Iterator<Item = (u16, &[u8])> {
+    iter::from_fn(move || {
+        if data.len() < 4 {
+            return None;
+        }
+        let (code, len) = (order.u16(data, 0), order.u16(data, 2) as usize);
+        let value = match data.get(4..4 + len) {
+            Some(value) if code != 0 => value,
+            _ => {
+                data = &[];
+                return None;
+            }
+        };
+        data = data.get(4 + len.next_multiple_of(4)..).unwrap_or(&[]);
+        Some((code, value))
+    })
+}
+
+/// An interface described in the current section.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub struct Interface {
+    /// `LINKTYPE_*` value of the packets captured on the interface.
+    pub link_type: u16,
+    /// Maximum number of bytes captured from each packet, 0 if unlimited.
+    pub snaplen: u32,
+    /// Number of timestamp units per second, from `if_tsresol`.
+    pub ts_units_per_sec: u64,
+    /// Offset in seconds added to the timestamps, from `if_tsoffset`.
+    pub ts_offset: i64,
+}
+
+impl Interface {
+    /// Converts a timestamp of a packet captured on the interface to a
+    /// duration since the UNIX epoch.
+    pub fn timestamp(&self, ts: u64) -> Duration {
+        let units = self.ts_units_per_sec;
+        let nanos = (ts % units) as u128 * 1_000_000_000 / units as u128;
+        let ts = Duration::new(ts / units, nanos as u32);
+        let offset = Duration::from_secs(self.ts_offset.unsigned_abs());
+        if self.ts_offset < 0 {
+            ts.saturating_sub(offset)
+        } else {
+            ts.saturating_add(offset)
+        }
+    }
+}
+
+/// A packet read from a pcapng stream.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub struct Packet<'a> {
+    /// The timestamp, the direction from `epb_flags` and, as `ifindex`, the
+    /// pcapng interface ID.
+    pub meta: PacketMeta,
+    pub link_type: u16,
+    /// Length of the packet on the wire, `data` being truncated to the
+    /// snapshot length.
+    pub orig_len: u32,
+    pub data: &'a [u8],
+}
+
+impl<'a> Packet<'a> {
+    /// The packet data, along with its length on the wire.
+    #[inline]
+    pub fn captured(&self) -> Captured<'a> {
+        Captured::new(self.data, self.orig_len as usize)
+    }
+
+    /// Parses the packet, starting with the link-layer header of the link
+    /// type of its interface.
+    ///
+    /// Fails with [`ParseError::Malformed`] if the link type is not one of
+    /// [`LinkType`].
+    pub fn parse(&self) -> Result<packet::Packet<'a>, ParseError> {
+        let link_type = LinkType::try_from(self.link_type).map_err(|_| ParseError::Malformed)?;
+        packet::Packet::parse_with_config(self.captured(), link_type, &ParseConfig::DEFAULT)
+    }
+}
+
+/// Parser of pcapng blocks, not tied to any I/O source.
+///
+/// Callers first find the length of the next block with
+/// [`block_len`](Parser::block_len), then hand the whole block to
+/// [`parse_block`](Parser::parse_block).
+///
+/// ```
+/// use ether_packet::pcapng::Parser;
+///
+/// #[rustfmt::skip]
+/// let file = [
+///     // Section header block, little-endian.
+///     0x0a, 0x0d, 0x0d, 0x0a, 28, 0, 0, 0, 0x4d, 0x3c, 0x2b, 0x1a, 1, 0, 0, 0,
+///     0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 28, 0, 0, 0,
+///     // Interface description block, Ethernet.
+///     1, 0, 0, 0, 20, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 20, 0, 0, 0,
+///     // Simple packet block.
+///     3, 0, 0, 0, 20, 0, 0, 0, 4, 0, 0, 0, 0xde, 0xad, 0xbe, 0xef, 20, 0, 0, 0,
+/// ];
+/// let mut parser = Parser::new();
+/// let mut data = &file[..];
+/// let mut packets = 0;
+/// while let Some(len) = parser.block_len(data).unwrap() {
+///     if let Some(packet) = parser.parse_block(&data[..len]).unwrap() {
+///         assert_eq!(packet.data, &[0xde, 0xad, 0xbe, 0xef]);
+///         packets += 1;
+///     }
+///     data = &data[len..];
+/// }
+/// assert_eq!(packets, 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Parser {
+    order: Option<ByteOrder>,
+    interfaces: Vec<Interface>,
+    max_block_len: usize,
+}
+
+impl Parser {
+    pub fn new() -> Self {
+        Self {
+            order: None,
+            interfaces: Vec::new(),
+            max_block_len: DEFAULT_MAX_BLOCK_LEN,
+        }
+    }
+
+    /// Sets the largest block accepted, [`DEFAULT_MAX_BLOCK_LEN`] by default.
+    pub fn max_block_len(&mut self, len: usize) -> &mut Self {
+        self.max_block_len = len;
+        self
+    }
+
+    /// Interfaces described so far in the current section, indexed by
+    /// interface ID.
+    #[inline]
+    pub fn interfaces(&self) -> &[Interface] {
+        &self.interfaces
+    }
+
+    /// Returns the total length of the block starting at `data`, or `None`
+    /// if `data` is too short to tell. The block itself may extend past the
+    /// end of `data`.
+    pub fn block_len(&self, data: &[u8]) -> Result<Option<usize>, PcapngError> {
+        if data.len() < 8 {
+            return Ok(None);
+        }
+        let order = if data[..4] == SECTION_HEADER.to_be_bytes() {
+            match data.get(8..12) {
+                Some(&[0x1a, 0x2b, 0x3c, 0x4d]) => ByteOrder::Big,
+                Some(&[0x4d, 0x3c, 0x2b, 0x1a]) => ByteOrder::Little,
+                Some(_) => return Err(PcapngError::InvalidMagic(ByteOrder::Big.u32(data, 8))),
+                None => return Ok(None),
+            }
+        } else {
+            self.order.ok_or(PcapngError::NoSection)?
+        };
+        let len = order.u32(data, 4) as usize;
+        if len < 12 || !len.is_multiple_of(4) {
+            return Err(PcapngError::InvalidBlock(order.u32(data, 0)));
+        }
+        if len > self.max_block_len {
+            return Err(PcapngError::BlockTooLarge(len));
+        }
+        Ok(Some(len))
+    }
+
+    /// Parses a whole block, returning the packet it holds, if any. Section
+    /// header and interface description blocks update the state of the
+    /// parser, and blocks of other types are skipped.
+    pub fn parse_block<'a>(&mut self, block: &'a [u8]) -> Result<Option<Packet<'a>>, PcapngError> {
+        let len = self.block_len(block)?;
+        let order = match block.get(8..12) {
+            Some(magic) if block[..4] == SECTION_HEADER.to_be_bytes() => {
+                if magic == BYTE_ORDER_MAGIC.to_be_bytes() {
+                    ByteOrder::Big
+                } else {
+                    ByteOrder::Little
+                }
+            }
+            _ => self.order.ok_or(PcapngError::NoSection)?,
+        };
+        let block_type = order.u32(block, 0);
+        if len != Some(block.len()) || order.u32(block, block.len() - 4) as usize != block.len() {
+            return Err(PcapngError::InvalidBlock(block_type));
+        }
+        let body = &block[8..block.len() - 4];
+        let invalid = PcapngError::InvalidBlock(block_type);
+
+        match block_type {
+            SECTION_HEADER => {
+                if body.len() < 16 {
+                    return Err(invalid);
+                }
+                let major = order.u16(body, 4);
+                if major != 1 {
+                    return Err(PcapngError::UnsupportedVersion(major));
+                }
+                self.order = Some(order);
+                self.interfaces.clear();
+                Ok(None)
+            }
+            INTERFACE_DESCRIPTION => {
+                if body.len() < 8 {
+                    return Err(invalid);
+                }
+                let mut interface = Interface {
+                    link_type: order.u16(body, 0),
+                    snaplen: order.u32(body, 4),
+                    ts_units_per_sec: 1_000_000,
+                    ts_offset: 0,
+                };
+                for (code, value) in options(order, &body[8..]) {
+                    match (code, value) {
+                        (IF_TSRESOL, &[resol]) => {
+                            let units = if resol & 0x80 != 0 {
+                                1u64.checked_shl((resol & 0x7f) as u32)
+                            } else {
+                                10u64.checked_pow(resol as u32)
+                            };
+                            interface.ts_units_per_sec = units.ok_or(invalid)?;
+                        }
+                        (IF_TSOFFSET, &[a, b, c, d, e, f, g, h]) => {
+                            let bytes = [a, b, c, d, e, f, g, h];
+                            interface.ts_offset = match order {
+                                ByteOrder::Big => i64::from_be_bytes(bytes),
+                                ByteOrder::Little => i64::from_le_bytes(bytes),
+                            };
+                        }
+                        _ => {}
+                    }
+                }
+                self.interfaces.push(interface);
+                Ok(None)
+            }
+            ENHANCED_PACKET => {
+                if body.len() < 20 {
+                    return Err(invalid);
+                }
+                let id = order.u32(body, 0);
+                let interface = self.interface(id)?;
+                let ts = (order.u32(body, 4) as u64) << 32 | order.u32(body, 8) as u64;
+                let cap_len = order.u32(body, 12) as usize;
+                let data = body.get(20..20 + cap_len).ok_or(invalid)?;
+                let mut meta = PacketMeta::new(interface.timestamp(ts));
+                meta.ifindex = Some(id);
+                let opts = body.get(20 + cap_len.next_multiple_of(4)..).unwrap_or(&[]);
+                for (code, value) in options(order, opts) {
+                    if code == EPB_FLAGS && value.len() == 4 {
+                        meta.direction = match order.u32(value, 0) & 0x3 {
+                            1 => Direction::Inbound,
+                            2 => Direction::Outbound,
+                            _ => Direction::Unknown,
+                        };
+                    }
+                }
+                Ok(Some(Packet {
+                    meta,
+                    link_type: interface.link_type,
+                    orig_len: order.u32(body, 16),
+                    data,
+                }))
+            }
+            SIMPLE_PACKET => {
+                if body.len() < 4 {
+                    return Err(invalid);
+                }
+                let interface = self.interface(0)?;
+                let orig_len = order.u32(body, 0);
+                let mut cap_len = (orig_len as usize).min(body.len() - 4);
+                if interface.snaplen != 0 {
+                    cap_len = cap_len.min(interface.snaplen as usize);
+                }
+                Ok(Some(Packet {
+                    meta: PacketMeta {
+                        ifindex: Some(0),
+                        ..PacketMeta::default()
+                    },
+                    link_type: interface.link_type,
+                    orig_len,
+                    data: &body[4..4 + cap_len],
+                }))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Whether the block starting at `data` holds a packet.
+    #[cfg(feature = "futures-io")]
+    #[inline]
+    fn is_packet(&self, data: &[u8]) -> bool {
+        self.order
+            .is_some_and(|o| matches!(o.u32(data, 0), ENHANCED_PACKET | SIMPLE_PACKET))
+    }
+
+    #[inline]
+    fn interface(&self, id: u32) -> Result<Interface, PcapngError> {
+        self.interfaces
+            .get(id as usize)
+            .copied()
+            .ok_or(PcapngError::UnknownInterface(id))
+    }
+}
+
+impl Default for Parser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Asynchronous reader of the packets of a pcapng stream.
+///
+/// Blocks split across reads are buffered until complete. When the source
+/// reaches its end in the middle of a block, [`next_packet`] returns
+/// `Ok(None)` and keeps the partial block, so that a file which is still
+/// being written can be followed by calling it again later.
+///
+/// [`next_packet`]: AsyncReader::next_packet
+#[cfg(feature = "futures-io")]
+#[derive(Debug)]
+pub struct AsyncReader<R> {
+    inner: R,
+    parser: Parser,
+    buf: Vec<u8>,
+    start: usize,
+    end: usize,
+}
+
+#[cfg(feature = "futures-io")]
+impl<R: futures_io::AsyncRead + Unpin> AsyncReader<R> {
+    const READ_LEN: usize = 64 * 1024;
+
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            parser: Parser::new(),
+            buf: Vec::new(),
+            start: 0,
+            end: 0,
+        }
+    }
+
+    #[inline]
+    pub fn parser(&self) -> &Parser {
+        &self.parser
+    }
+
+    #[inline]
+    pub fn parser_mut(&mut self) -> &mut Parser {
+        &mut self.parser
+    }
+
+    /// Number of bytes read but not parsed yet, i.e. of the partial block
+    /// left when the source reached its end.
+    #[inline]
+    pub fn pending(&self) -> usize {
+        self.end - self.start
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Reads the next packet, or returns `Ok(None)` once the source reached
+    /// its end. See [`Packet::parse`] to parse it.
+    pub async fn next_packet(&mut self) -> Result<Option<Packet<'_>>, PcapngError> {
+        let block = loop {
+            let data = &self.buf[self.start..self.end];
+            let needed = match self.parser.block_len(data)? {
+                Some(len) if len <= data.len() => {
+                    let block = self.start..self.start + len;
+                    self.start += len;
+                    if self.parser.is_packet(data) {
+                        break block;
+                    }
+                    self.parser.parse_block(&self.buf[block])?;
+                    continue;
+                }
+                Some(len) => len,
+                None => 12,
+            };
+            if !self.fill(needed).await? {
+                return Ok(None);
+            }
+        };
+        self.parser.parse_block(&self.buf[block])
+    }
+
+    /// Reads until at least `needed` bytes are buffered, returning `false`
+    /// if the source reached its end first.
+    async fn fill(&mut self, needed: usize) -> Result<bool, PcapngError> {
+        if self.start > 0 {
+            self.buf.copy_within(self.start..self.end, 0);
+            self.end -= self.start;
+            self.start = 0;
+        }
+        let len = needed.max(self.end + Self::READ_LEN);
+        if self.buf.len() < len {
+            self.buf.resize(len, 0);
+        }
+        while self.end < needed {
+            let read = core::future::poll_fn(|cx| {
+                core::pin::Pin::new(&mut self.inner).poll_read(cx, &mut self.buf[self.end..])
+            })
+            .await;
+            match read {
+                Ok(0) => return Ok(false),
+                Ok(n) => self.end += n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(PcapngError::Io(e.kind())),
+            }
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_parser() {
+        use alloc::vec::Vec;
+
+        use super::{Parser, PcapngError};
+        use crate::{
+            header::ParseError,
+            ip::IpProto,
+            meta::Direction,
+            packet::{LinkType, TransportHdr},
+        };
+
+        #[rustfmt::skip]
+        let frame = [
+            0, 1, 2, 3, 4, 5, 0, 6, 7, 8, 9, 10, 0x08, 0x00,
+            0x45, 0, 0, 28, 0, 1, 0x40, 0, 64, 17, 0, 0, 10, 0, 0, 1, 10, 0, 0, 2,
+            0x30, 0x39, 0, 53, 0, 8, 0, 0,
+        ];
+        let block = |block_type: u32, body: &[u8]| {
+            let len = (12 + body.len()) as u32;
+            let mut block = Vec::new();
+            block.extend_from_slice(&block_type.to_le_bytes());
+            block.extend_from_slice(&len.to_le_bytes());
+            block.extend_from_slice(body);
+            block.extend_from_slice(&len.to_le_bytes());
+            block
+        };
+        let epb = |interface: u32, data: &[u8]| {
+            let mut body = Vec::new();
+            body.extend_from_slice(&interface.to_le_bytes());
+            // 1 second since the epoch, in microseconds.
+            body.extend_from_slice(&[0, 0, 0, 0, 0x40, 0x42, 0x0f, 0]);
+            body.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            body.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            body.extend_from_slice(data);
+            body.resize(body.len().next_multiple_of(4), 0);
+            // epb_flags: inbound.
+            body.extend_from_slice(&[2, 0, 4, 0, 1, 0, 0, 0, 0, 0, 0, 0]);
+            block(6, &body)
+        };
+
+        let mut file = block(
+            0x0a0d0d0a,
+            &[
+                0x4d, 0x3c, 0x2b, 0x1a, 1, 0, 0, 0, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+            ],
+        );
+        // Ethernet and LINKTYPE_FRELAY interfaces.
+        file.extend(block(1, &[1, 0, 0, 0, 0, 0, 0, 0]));
+        file.extend(block(1, &[107, 0, 0, 0, 0, 0, 0, 0]));
+        file.extend(epb(0, &frame));
+        file.extend(epb(1, &frame));
+        file.extend(epb(2, &frame));
+
+        let mut parser = Parser::new();
+        let mut data = &file[..];
+        let mut packets = Vec::new();
+        while let Some(len) = parser.block_len(data).unwrap() {
+            match parser.parse_block(&data[..len]) {
+                Ok(Some(packet)) => packets.push(packet),
+                Ok(None) => {}
+                Err(err) => assert_eq!(err, PcapngError::UnknownInterface(2)),
+            }
+            data = &data[len..];
+        }
+        assert_eq!(parser.interfaces().len(), 2);
+        assert_eq!(packets.len(), 2);
+
+        let packet = packets[0].parse().unwrap();
+        assert_eq!(packet.link_type(), LinkType::Ethernet);
+        assert_eq!(packet.proto(), Some(IpProto::Udp));
+        assert!(matches!(packet.transport(), Some(TransportHdr::Udp(_))));
+        assert_eq!(packets[0].meta.direction, Direction::Inbound);
+        assert_eq!(
+            packets[0].meta.timestamp,
+            Some(core::time::Duration::from_secs(1))
+        );
+        assert_eq!(packets[1].parse().unwrap_err(), ParseError::Malformed);
+    }
+
+    #[cfg(feature = "futures-io")]
+    #[test]
+    fn test_async_reader() {
+        use core::{
+            future::Future,
+            pin::{pin, Pin},
+            task::{Context, Poll, Waker},
+            time::Duration,
+        };
+        use std::io;
+
+        use super::AsyncReader;
+        use crate::meta::Direction;
+
+        /// A file being written, returning a few bytes per read.
+        struct Growing<'a> {
+            data: &'a [u8],
+            written: usize,
+            pos: usize,
+        }
+
+        impl futures_io::AsyncRead for Growing<'_> {
+            fn poll_read(
+                mut self: Pin<&mut Self>,
+                _: &mut Context<'_>,
+                buf: &mut [u8],
+            ) -> Poll<io::Result<usize>> {
+                let n = (self.written - self.pos).min(buf.len()).min(7);
+                buf[..n].copy_from_slice(&self.data[self.pos..self.pos + n]);
+                self.pos += n;
+                Poll::Ready(Ok(n))
+            }
+        }
+
+        fn block_on<F: Future>(f: F) -> F::Output {
+            let mut f = pin!(f);
+            loop {
+                if let Poll::Ready(out) = f.as_mut().poll(&mut Context::from_waker(Waker::noop())) {
+                    return out;
+                }
+            }
+        }
+
+        #[rustfmt::skip]
+        let file = [
+            // Section header block, big-endian.
+            0x0a, 0x0d, 0x0d, 0x0a, 0, 0, 0, 28, 0x1a, 0x2b, 0x3c, 0x4d, 0, 1, 0, 0,
+            0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0, 0, 0, 28,
+            // Interface description block, nanosecond timestamps.
+            0, 0, 0, 1, 0, 0, 0, 32, 0, 1, 0, 0, 0, 0, 0xff, 0xff,
+            0, 9, 0, 1, 9, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 32,
+            // Enhanced packet block, outbound.
+            0, 0, 0, 6, 0, 0, 0, 52, 0, 0, 0, 0, 0x17, 0x97, 0x9c, 0xfe,
+            0x3d, 0x85, 0xcd, 0x15, 0, 0, 0, 5, 0, 0, 0, 60,
+            1, 2, 3, 4, 5, 0, 0, 0,
+            0, 2, 0, 4, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 52,
+        ];
+        let mut reader = AsyncReader::new(Growing {
+            data: &file,
+            written: 70,
+            pos: 0,
+        });
+        assert_eq!(block_on(reader.next_packet()), Ok(None));
+        assert_eq!(reader.pending(), 10);
+        assert_eq!(
+            reader.parser().interfaces()[0].ts_units_per_sec,
+            1_000_000_000
+        );
+
+        reader.get_mut().written = file.len();
+        let packet = block_on(reader.next_packet()).unwrap().unwrap();
+        assert_eq!(packet.data, &[1, 2, 3, 4, 5]);
+        assert_eq!(packet.orig_len, 60);
+        assert_eq!(packet.link_type, 1);
+        assert_eq!(packet.meta.direction, Direction::Outbound);
+        assert_eq!(
+            packet.meta.timestamp,
+            Some(Duration::new(1_700_000_000, 123_456_789))
+        );
+        assert_eq!(block_on(reader.next_packet()), Ok(None));
+        assert_eq!(reader.pending(), 0);
+    }
+}
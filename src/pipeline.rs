@@ -0,0 +1,253 @@
+//! Fan-out of captured packets to worker threads.
+//!
+//! A [`Pipeline`] is fed by a single capture loop and hands each packet to
+//! one of its workers, chosen by a hash of the flow the packet belongs to.
+//! Both directions of a flow go to the same worker, so that every worker
+//! sees the packets of its flows in capture order and can keep per-flow
+//! state, such as a [`TcpTracker`](crate::tcptrack::TcpTracker), without
+//! locking. Each worker has its own bounded channel, so a slow worker
+//! blocks the capture loop rather than growing a queue without limit.
+
+use std::{
+    panic,
+    sync::mpsc::{self, SyncSender},
+    thread::{self, JoinHandle},
+    vec::Vec,
+};
+
+use crate::{
+    flow::FlowKey,
+    header::ParseError,
+    ip::IpProto,
+    meta::PacketMeta,
+    packet::{LinkType, Packet},
+};
+
+/// A packet queued to a worker.
+struct Job {
+    data: Vec<u8>,
+    meta: PacketMeta,
+}
+
+/// Handler of the packets of a worker thread.
+///
+/// Closures are workers, and so are types keeping per-flow state, which
+/// [`Pipeline::finish`] gives back once the capture is over.
+pub trait Worker: Send + 'static {
+    fn packet(&mut self, packet: &Packet<'_>, meta: &PacketMeta);
+}
+
+impl<F> Worker for F
+where
+    F: FnMut(&Packet<'_>, &PacketMeta) + Send + 'static,
+{
+    #[inline]
+    fn packet(&mut self, packet: &Packet<'_>, meta: &PacketMeta) {
+        self(packet, meta)
+    }
+}
+
+/// Worker threads processing the packets of a capture, each receiving the
+/// packets of a share of the flows.
+///
+/// ```
+/// use ether_packet::{
+///     meta::PacketMeta,
+///     packet::{LinkType, Packet},
+///     pipeline::{Pipeline, Worker},
+/// };
+///
+/// struct Bytes(usize);
+///
+/// impl Worker for Bytes {
+///     fn packet(&mut self, packet: &Packet<'_>, _meta: &PacketMeta) {
+///         self.0 += packet.data().len();
+///     }
+/// }
+///
+/// let pipeline = Pipeline::new(4, 1024, LinkType::Ethernet, |_index| Bytes(0));
+/// #[rustfmt::skip]
+/// let frame = [
+///     0, 1, 2, 3, 4, 5, 0, 6, 7, 8, 9, 10, 0x08, 0x00,
+///     0x45, 0, 0, 28, 0, 1, 0x40, 0, 64, 17, 0, 0, 10, 0, 0, 1, 10, 0, 0, 2,
+///     0x30, 0x39, 0, 53, 0, 8, 0, 0,
+/// ];
+/// for _ in 0..10 {
+///     pipeline.send(&frame, PacketMeta::default()).unwrap();
+/// }
+/// let workers = pipeline.finish();
+/// assert_eq!(workers.iter().map(|worker| worker.0).sum::<usize>(), 420);
+/// ```
+pub struct Pipeline<H> {
+    link_type: LinkType,
+    senders: Vec<SyncSender<Job>>,
+    workers: Vec<JoinHandle<H>>,
+}
+
+impl<H: Worker> Pipeline<H> {
+    /// Spawns `workers` threads, each running the worker returned by
+    /// `worker` for its index and receiving the packets through a channel
+    /// holding up to `capacity` packets.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `workers` is 0.
+    pub fn new<F>(workers: usize, capacity: usize, link_type: LinkType, mut worker: F) -> Self
+    where
+        F: FnMut(usize) -> H,
+    {
+        assert!(workers > 0, "pipeline without workers");
+        let (senders, workers) = (0..workers)
+            .map(|index| {
+                let (sender, receiver) = mpsc::sync_channel::<Job>(capacity);
+                let mut worker = worker(index);
+                let thread = thread::spawn(move || {
+                    for job in receiver {
+                        // Packets were parsed before being queued.
+                        if let Ok(packet) = Packet::parse_with_linktype(&job.data, link_type) {
+                            worker.packet(&packet, &job.meta);
+                        }
+                    }
+                    worker
+                });
+                (sender, thread)
+            })
+            .unzip();
+        Self {
+            link_type,
+            senders,
+            workers,
+        }
+    }
+
+    /// Number of worker threads.
+    #[inline]
+    pub fn workers(&self) -> usize {
+        self.senders.len()
+    }
+
+    /// Index of the worker receiving `packet`.
+    ///
+    /// IP packets are spread by their 5-tuple, regardless of their
+    /// direction. Fragments, which do not all carry the ports, are spread
+    /// by their addresses and protocol only, and other packets go to the
+    /// first worker.
+    pub fn worker_for(&self, packet: &Packet<'_>) -> usize {
+        let Some(network) = packet.network() else {
+            return 0;
+        };
+        let transport = packet.transport().filter(|_| !packet.is_fragment());
+        let key = FlowKey {
+            src_addr: network.src_addr(),
+            dst_addr: network.dst_addr(),
+            proto: packet.proto().unwrap_or(IpProto::Reserved),
+            src_port: transport.and_then(|t| t.src_port()).unwrap_or(0),
+            dst_port: transport.and_then(|t| t.dst_port()).unwrap_or(0),
+        };
+        let key = key.min(key.reversed());
+        (key.keyed_hash(&[0, 0]) % self.senders.len() as u64) as usize
+    }
+
+    /// Queues the packet `data` to its worker, blocking while the channel
+    /// of the worker is full, and returns the index of the worker.
+    ///
+    /// Packets which cannot be parsed are not queued.
+    pub fn send(&self, data: &[u8], meta: PacketMeta) -> Result<usize, ParseError> {
+        let packet = Packet::parse_with_linktype(data, self.link_type)?;
+        let worker = self.worker_for(&packet);
+        let job = Job {
+            data: data.to_vec(),
+            meta,
+        };
+        // A worker only stops when it panics, which `finish` reports.
+        let _ = self.senders[worker].send(job);
+        Ok(worker)
+    }
+
+    /// Waits for the workers to process the queued packets and returns
+    /// them, in the order of their index.
+    ///
+    /// # Panics
+    ///
+    /// Resumes the panic of a worker which panicked.
+    pub fn finish(self) -> Vec<H> {
+        drop(self.senders);
+        self.workers
+            .into_iter()
+            .map(|worker| {
+                worker
+                    .join()
+                    .unwrap_or_else(|err| panic::resume_unwind(err))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, vec::Vec};
+
+    use super::{Pipeline, Worker};
+    use crate::{
+        builder::EthFrameBuilder,
+        flow::FlowKey,
+        ip::IpProto,
+        meta::PacketMeta,
+        packet::{LinkType, Packet},
+    };
+
+    struct Seen(Vec<(FlowKey, u32)>);
+
+    impl Worker for Seen {
+        fn packet(&mut self, packet: &Packet<'_>, meta: &PacketMeta) {
+            let key = FlowKey::from_ip(&packet.data()[packet.l3_offset().unwrap()..]).unwrap();
+            self.0
+                .push((key.min(key.reversed()), meta.ifindex.unwrap()));
+        }
+    }
+
+    #[test]
+    fn test_pipeline() {
+        let pipeline = Pipeline::new(3, 4, LinkType::Ethernet, |_| Seen(Vec::new()));
+        assert_eq!(pipeline.workers(), 3);
+        let mut buf = [0u8; 64];
+        for i in 0..200u32 {
+            let port = 1000 + (i / 2 % 10) as u16;
+            let (src, dst, sport, dport) = if i % 2 == 0 {
+                ([10, 0, 0, 1], [10, 0, 0, 2], port, 53)
+            } else {
+                ([10, 0, 0, 2], [10, 0, 0, 1], 53, port)
+            };
+            let mut frame = EthFrameBuilder::new(&mut buf, [2; 6], [4; 6]).unwrap();
+            frame
+                .ipv4(src.into(), dst.into(), IpProto::Udp)
+                .unwrap()
+                .udp(sport, dport)
+                .unwrap();
+            let len = frame.finish().unwrap();
+            let meta = PacketMeta {
+                ifindex: Some(i),
+                ..Default::default()
+            };
+            pipeline.send(&buf[..len], meta).unwrap();
+        }
+        assert!(pipeline.send(&buf[..10], PacketMeta::default()).is_err());
+
+        // Both directions of each flow go to a single worker, in order.
+        let mut owners = BTreeMap::new();
+        let workers = pipeline.finish();
+        for (index, worker) in workers.iter().enumerate() {
+            let mut last = BTreeMap::new();
+            for &(key, i) in &worker.0 {
+                assert_eq!(*owners.entry(key).or_insert(index), index);
+                assert!(last.insert(key, i).is_none_or(|last| last < i));
+            }
+        }
+        assert_eq!(owners.len(), 10);
+        assert_eq!(
+            workers.iter().map(|worker| worker.0.len()).sum::<usize>(),
+            200
+        );
+        assert!(workers.iter().all(|worker| !worker.0.is_empty()));
+    }
+}
@@ -0,0 +1,216 @@
+//! Rate estimation and token-bucket policing, driven by packet lengths and
+//! timestamps.
+//!
+//! The `now` arguments are points in time on any clock, as long as an
+//! estimator or policer is always given the same one; capture timestamps
+//! do. A time earlier than the latest one seen closes no estimator interval
+//! and adds no tokens to a policer bucket, which keeps the latest time and
+//! only refills again once time moves past it, so a clock stepped back
+//! throttles the policer rather than crediting it twice. Per-flow state is
+//! obtained by keeping one estimator or policer per
+//! [`FlowKey`](crate::flow::FlowKey).
+
+use core::time::Duration;
+
+const NANOS_PER_SEC: u128 = 1_000_000_000;
+
+/// Fractional bits of the estimated rate.
+const RATE_SHIFT: u32 = 16;
+
+/// Idle intervals after which the estimate is considered to be 0.
+const MAX_IDLE_INTERVALS: u128 = 64;
+
+/// Exponentially weighted moving average of a byte rate, like the Linux
+/// `gen_estimator`.
+///
+/// The bytes are counted over fixed intervals, and at the end of each
+/// interval the estimate moves towards the measured rate by 2^-`ewma_log`
+/// of the difference.
+#[derive(Debug, Clone)]
+pub struct RateEstimator {
+    interval: Duration,
+    ewma_log: u8,
+    start: Option<Duration>,
+    bytes: u64,
+    /// Bytes per second, with `RATE_SHIFT` fractional bits.
+    rate: u64,
+}
+
+impl RateEstimator {
+    /// Creates an estimator measuring over `interval`, averaging with a
+    /// weight of 2^-`ewma_log`, capped at 2^-31.
+    #[inline]
+    pub const fn new(interval: Duration, ewma_log: u8) -> Self {
+        Self {
+            interval,
+            ewma_log: if ewma_log > 31 { 31 } else { ewma_log },
+            start: None,
+            bytes: 0,
+            rate: 0,
+        }
+    }
+
+    /// Accounts for a packet of `len` bytes seen at `now`.
+    pub fn update(&mut self, now: Duration, len: usize) {
+        self.advance(now);
+        self.bytes = self.bytes.saturating_add(len as u64);
+    }
+
+    /// Closes the intervals which ended before `now`, so that the estimate
+    /// also decays while no packets are seen.
+    pub fn advance(&mut self, now: Duration) {
+        let Some(start) = self.start else {
+            self.start = Some(now);
+            return;
+        };
+        let interval = self.interval.as_nanos();
+        let elapsed = now.saturating_sub(start).as_nanos();
+        if interval == 0 || elapsed < interval {
+            return;
+        }
+        let intervals = elapsed / interval;
+        let sample = ((self.bytes as u128 * NANOS_PER_SEC / interval) << RATE_SHIFT) as u64;
+        self.ewma(sample);
+        if intervals > MAX_IDLE_INTERVALS {
+            self.rate = 0;
+        } else {
+            (1..intervals).for_each(|_| self.ewma(0));
+        }
+        self.bytes = 0;
+        self.start = Some(start + Duration::from_nanos((intervals * interval) as u64));
+    }
+
+    #[inline]
+    fn ewma(&mut self, sample: u64) {
+        if sample >= self.rate {
+            self.rate += (sample - self.rate) >> self.ewma_log;
+        } else {
+            self.rate -= (self.rate - sample) >> self.ewma_log;
+        }
+    }
+
+    /// Estimated rate, in bytes per second.
+    #[inline]
+    pub const fn bytes_per_sec(&self) -> u64 {
+        self.rate >> RATE_SHIFT
+    }
+}
+
+/// Decision of a [`Policer`].
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum Conformance {
+    /// The packet is within the rate and burst, and consumed tokens.
+    Conform,
+    /// The packet exceeds the rate, and left the bucket unchanged.
+    Exceed,
+}
+
+/// Single-rate token bucket.
+///
+/// The bucket fills at the committed rate up to the burst size, and a
+/// packet conforms if the bucket holds at least as many tokens as its
+/// length.
+///
+/// ```
+/// use core::time::Duration;
+/// use ether_packet::policer::{Conformance, Policer};
+///
+/// let mut policer = Policer::new(1000, 1500);
+/// let t0 = Duration::ZERO;
+/// assert_eq!(policer.police(t0, 1500), Conformance::Conform);
+/// assert_eq!(policer.police(t0, 64), Conformance::Exceed);
+/// assert_eq!(policer.police(Duration::from_millis(64), 64), Conformance::Conform);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Policer {
+    rate: u64,
+    burst: u64,
+    /// Tokens, in bytes multiplied by 10^9 so that they are accumulated
+    /// every nanosecond.
+    tokens: u128,
+    last: Option<Duration>,
+}
+
+impl Policer {
+    /// Creates a policer with a committed rate in bytes per second and a
+    /// burst size in bytes. The bucket starts full.
+    #[inline]
+    pub const fn new(rate: u64, burst: u64) -> Self {
+        Self {
+            rate,
+            burst,
+            tokens: burst as u128 * NANOS_PER_SEC,
+            last: None,
+        }
+    }
+
+    /// Polices a packet of `len` bytes seen at `now`.
+    pub fn police(&mut self, now: Duration, len: usize) -> Conformance {
+        self.refill(now);
+        let needed = len as u128 * NANOS_PER_SEC;
+        if needed > self.tokens {
+            return Conformance::Exceed;
+        }
+        self.tokens -= needed;
+        Conformance::Conform
+    }
+
+    /// Tokens in the bucket at the last update, in bytes.
+    #[inline]
+    pub const fn tokens(&self) -> u64 {
+        (self.tokens / NANOS_PER_SEC) as u64
+    }
+
+    fn refill(&mut self, now: Duration) {
+        let elapsed = match self.last {
+            // Timestamps going backwards add no tokens.
+            Some(last) => now.saturating_sub(last).as_nanos(),
+            None => 0,
+        };
+        self.last = Some(match self.last {
+            Some(last) if last > now => last,
+            _ => now,
+        });
+        let full = self.burst as u128 * NANOS_PER_SEC;
+        let added = elapsed.saturating_mul(self.rate as u128);
+        self.tokens = self.tokens.saturating_add(added).min(full);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use super::{Conformance, Policer, RateEstimator};
+
+    #[test]
+    fn test_rate_and_policer() {
+        let mut est = RateEstimator::new(Duration::from_millis(100), 1);
+        // 1000 bytes every 10 ms, i.e. 100 kB/s.
+        for ms in 0..1000 {
+            if ms % 10 == 0 {
+                est.update(Duration::from_millis(ms), 1000);
+            }
+        }
+        est.advance(Duration::from_millis(1000));
+        assert!((99_000..=100_000).contains(&est.bytes_per_sec()));
+        est.advance(Duration::from_millis(1200));
+        assert!((24_000..=25_000).contains(&est.bytes_per_sec()));
+        est.advance(Duration::from_secs(60));
+        assert_eq!(est.bytes_per_sec(), 0);
+
+        // 10 kB/s: 10 bytes per millisecond, after a 100-byte burst.
+        let mut policer = Policer::new(10_000, 100);
+        let conforming = (0..100)
+            .filter(|&ms| policer.police(Duration::from_millis(ms), 50) == Conformance::Conform)
+            .count();
+        assert_eq!(conforming, 2 + 99 / 5);
+        // 40 bytes of tokens left, and no refill when going back in time.
+        assert_eq!(policer.tokens(), 40);
+        assert_eq!(
+            policer.police(Duration::from_millis(10), 41),
+            Conformance::Exceed
+        );
+    }
+}
@@ -0,0 +1,427 @@
+//! Precision Time Protocol (IEEE 1588-2019), carried with
+//! [`EtherType::PTP`](crate::eth::EtherType::PTP) or over UDP, and the
+//! generalized PTP profile of IEEE 802.1AS (gPTP) used by AVB and TSN
+//! networks.
+//!
+//! gPTP always runs two-step over Ethernet: the precise time of a Sync is
+//! sent in its Follow_Up, which carries the Follow_Up information TLV, and
+//! the delay of each link is measured with the Pdelay_Req, Pdelay_Resp and
+//! Pdelay_Resp_Follow_Up exchange, see [`mean_link_delay`].
+
+use core::mem;
+
+use crate::{
+    bitfield::BitfieldUnit,
+    header::{impl_header, Header},
+    types::{U16, U32, U64},
+};
+
+/// UDP port of the PTP event messages, which are timestamped.
+pub const PTP_EVENT_PORT: u16 = 319;
+/// UDP port of the PTP general messages.
+pub const PTP_GENERAL_PORT: u16 = 320;
+/// Destination of gPTP frames, which are not forwarded by bridges.
+pub const GPTP_MULTICAST_ADDR: [u8; 6] = [0x01, 0x80, 0xc2, 0x00, 0x00, 0x0e];
+/// majorSdoId of gPTP messages.
+pub const GPTP_MAJOR_SDO_ID: u8 = 1;
+
+/// PTP message types.
+#[repr(u8)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum PtpMessageType {
+    Sync = 0x0,
+    DelayReq = 0x1,
+    PdelayReq = 0x2,
+    PdelayResp = 0x3,
+    FollowUp = 0x8,
+    DelayResp = 0x9,
+    PdelayRespFollowUp = 0xa,
+    Announce = 0xb,
+    Signaling = 0xc,
+    Management = 0xd,
+}
+
+impl TryFrom<u8> for PtpMessageType {
+    type Error = ();
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x0 => Ok(PtpMessageType::Sync),
+            0x1 => Ok(PtpMessageType::DelayReq),
+            0x2 => Ok(PtpMessageType::PdelayReq),
+            0x3 => Ok(PtpMessageType::PdelayResp),
+            0x8 => Ok(PtpMessageType::FollowUp),
+            0x9 => Ok(PtpMessageType::DelayResp),
+            0xa => Ok(PtpMessageType::PdelayRespFollowUp),
+            0xb => Ok(PtpMessageType::Announce),
+            0xc => Ok(PtpMessageType::Signaling),
+            0xd => Ok(PtpMessageType::Management),
+            _ => Err(()),
+        }
+    }
+}
+
+impl PtpMessageType {
+    /// Whether the message is timestamped on transmission and reception.
+    #[inline]
+    pub const fn is_event(&self) -> bool {
+        (*self as u8) < 0x8
+    }
+}
+
+/// PTP timestamp, in seconds on 48 bits and nanoseconds.
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct PtpTimestamp {
+    pub secs_hi: U16,
+    pub secs_lo: U32,
+    pub nanos: U32,
+}
+
+impl PtpTimestamp {
+    pub const LEN: usize = mem::size_of::<PtpTimestamp>();
+
+    #[inline]
+    pub const fn secs(&self) -> u64 {
+        (self.secs_hi.to_bits() as u64) << 32 | self.secs_lo.to_bits() as u64
+    }
+
+    /// The timestamp in nanoseconds.
+    #[inline]
+    pub const fn as_nanos(&self) -> u64 {
+        self.secs() * 1_000_000_000 + self.nanos.to_bits() as u64
+    }
+}
+
+/// Identity of a PTP port: the clock identity, usually derived from a MAC
+/// address, and the port number.
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct PortIdentity {
+    pub clock_identity: [u8; 8],
+    pub port_number: U16,
+}
+
+/// PTP common header.
+/// ```text
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |SdoId  |MsgType| Minor | Vers. |         Message Length        |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |  Domain Number| Minor SdoId   |             Flags             |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                                                               |
+/// +                       Correction Field                        +
+/// |                                                               |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                    Message Type Specific                      |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                                                               |
+/// +                                                               +
+/// |                    Source Port Identity                       |
+/// +                               +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                               |          Sequence Id          |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// | Control Field | Log Msg Intvl |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct PtpHdr {
+    /// **majorSdoId** (4 bits) and **messageType** (4 bits).
+    pub sdo_msg_type: BitfieldUnit<[u8; 1usize]>,
+    /// **minorVersionPTP** (4 bits) and **versionPTP** (4 bits).
+    pub version: BitfieldUnit<[u8; 1usize]>,
+    /// Length of the whole message, TLVs included.
+    pub message_length: U16,
+    pub domain_number: u8,
+    pub minor_sdo_id: u8,
+    pub flags: U16,
+    /// Signed, in nanoseconds multiplied by 2^16.
+    pub correction_field: U64,
+    pub message_type_specific: U32,
+    pub source_port_identity: PortIdentity,
+    pub sequence_id: U16,
+    /// Obsolete message type of PTPv1.
+    pub control_field: u8,
+    /// Log2 of the mean interval between messages, in seconds.
+    pub log_message_interval: i8,
+}
+
+impl PtpHdr {
+    pub const LEN: usize = mem::size_of::<PtpHdr>();
+
+    /// **majorSdoId**, formerly transportSpecific, 1 for gPTP.
+    #[inline]
+    pub const fn major_sdo_id(&self) -> u8 {
+        self.sdo_msg_type.get(4, 4) as u8
+    }
+
+    #[inline]
+    pub const fn set_major_sdo_id(&mut self, val: u8) {
+        self.sdo_msg_type.set(4, 4, val as u64)
+    }
+
+    #[inline]
+    pub fn message_type(&self) -> Option<PtpMessageType> {
+        (self.sdo_msg_type.get(0, 4) as u8).try_into().ok()
+    }
+
+    #[inline]
+    pub const fn set_message_type(&mut self, val: PtpMessageType) {
+        self.sdo_msg_type.set(0, 4, val as u64)
+    }
+
+    /// **versionPTP**, 2 for IEEE 1588-2008 and later.
+    #[inline]
+    pub const fn version(&self) -> u8 {
+        self.version.get(0, 4) as u8
+    }
+
+    #[inline]
+    pub const fn minor_version(&self) -> u8 {
+        self.version.get(4, 4) as u8
+    }
+
+    /// Whether the message belongs to the gPTP profile.
+    #[inline]
+    pub const fn is_gptp(&self) -> bool {
+        self.major_sdo_id() == GPTP_MAJOR_SDO_ID
+    }
+
+    /// **twoStepFlag**: the precise origin timestamp follows in a
+    /// Follow_Up or Pdelay_Resp_Follow_Up.
+    #[inline]
+    pub const fn two_step(&self) -> bool {
+        self.flags.to_bits() & 0x0200 != 0
+    }
+
+    /// Correction field, rounded down to nanoseconds.
+    #[inline]
+    pub const fn correction_nanos(&self) -> i64 {
+        self.correction_field.to_bits() as i64 >> 16
+    }
+
+    /// Parses the PTP message stored in `pdu`, returning its header, decoded
+    /// body and an iterator over its TLVs.
+    pub fn parse(pdu: &[u8]) -> Option<(&PtpHdr, PtpMessage<'_>, PtpTlvs<'_>)> {
+        let hdr = PtpHdr::from_bytes(pdu)?;
+        let body = pdu.get(Self::LEN..hdr.message_length.to_bits() as usize)?;
+        let (msg, len) = match hdr.message_type() {
+            Some(PtpMessageType::Sync | PtpMessageType::DelayReq) => (
+                PtpMessage::Sync(PtpTimestamp::from_bytes(body)?),
+                PtpTimestamp::LEN,
+            ),
+            Some(PtpMessageType::FollowUp) => (
+                PtpMessage::FollowUp(PtpTimestamp::from_bytes(body)?),
+                PtpTimestamp::LEN,
+            ),
+            Some(PtpMessageType::PdelayReq) => (
+                PtpMessage::PdelayReq(PtpTimestamp::from_bytes(body)?),
+                PdelayBody::LEN,
+            ),
+            Some(PtpMessageType::PdelayResp) => (
+                PtpMessage::PdelayResp(PdelayBody::from_bytes(body)?),
+                PdelayBody::LEN,
+            ),
+            Some(PtpMessageType::PdelayRespFollowUp) => (
+                PtpMessage::PdelayRespFollowUp(PdelayBody::from_bytes(body)?),
+                PdelayBody::LEN,
+            ),
+            _ => (PtpMessage::Other(body), body.len()),
+        };
+        let tlvs = body.get(len..)?;
+        Some((hdr, msg, PtpTlvs { data: tlvs }))
+    }
+}
+
+/// Body of the Pdelay_Resp and Pdelay_Resp_Follow_Up messages. The
+/// Pdelay_Req body has the same size, its origin timestamp being followed
+/// by 10 reserved bytes.
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct PdelayBody {
+    /// Reception time of the Pdelay_Req (t2) in a Pdelay_Resp, or
+    /// transmission time of the Pdelay_Resp (t3) in a Pdelay_Resp_Follow_Up.
+    pub timestamp: PtpTimestamp,
+    pub requesting_port_identity: PortIdentity,
+}
+
+impl PdelayBody {
+    pub const LEN: usize = mem::size_of::<PdelayBody>();
+}
+
+/// Follow_Up information TLV of gPTP, the value of an organization
+/// extension TLV of the IEEE 802.1 OUI.
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct FollowUpInfo {
+    /// 00-80-C2.
+    pub organization_id: [u8; 3],
+    /// 1.
+    pub organization_sub_type: [u8; 3],
+    /// Signed, (rateRatio - 1) multiplied by 2^41.
+    pub cumulative_scaled_rate_offset: U32,
+    /// Changed by the grandmaster on each phase or frequency change.
+    pub gm_time_base_indicator: U16,
+    /// Signed, in nanoseconds multiplied by 2^16.
+    pub last_gm_phase_change: [u8; 12],
+    /// Signed, fractional frequency offset multiplied by 2^41.
+    pub scaled_last_gm_freq_change: U32,
+}
+
+impl FollowUpInfo {
+    pub const LEN: usize = mem::size_of::<FollowUpInfo>();
+    /// Organization of the TLV.
+    pub const ORGANIZATION_ID: [u8; 3] = [0x00, 0x80, 0xc2];
+    pub const ORGANIZATION_SUB_TYPE: [u8; 3] = [0, 0, 1];
+
+    /// Ratio of the grandmaster frequency to the local clock frequency of
+    /// the sender.
+    #[inline]
+    pub fn rate_ratio(&self) -> f64 {
+        1.0 + self.cumulative_scaled_rate_offset.to_bits() as i32 as f64 / (1u64 << 41) as f64
+    }
+
+    #[inline]
+    pub const fn gm_time_base_indicator(&self) -> u16 {
+        self.gm_time_base_indicator.to_bits()
+    }
+
+    /// Last phase change of the grandmaster, rounded down to nanoseconds.
+    #[inline]
+    pub const fn last_gm_phase_change_nanos(&self) -> i64 {
+        let [_, _, a, b, c, d, e, f, g, h, _, _] = self.last_gm_phase_change;
+        i64::from_be_bytes([a, b, c, d, e, f, g, h])
+    }
+}
+
+impl_header!(PtpHdr, PtpTimestamp, PdelayBody, FollowUpInfo);
+
+/// Decoded body of a PTP message.
+#[derive(Debug, Copy, Clone)]
+pub enum PtpMessage<'a> {
+    /// Sync or Delay_Req, with the origin timestamp.
+    Sync(&'a PtpTimestamp),
+    /// Follow_Up, with the precise origin timestamp of the Sync.
+    FollowUp(&'a PtpTimestamp),
+    /// Pdelay_Req, with the origin timestamp, zero in gPTP.
+    PdelayReq(&'a PtpTimestamp),
+    PdelayResp(&'a PdelayBody),
+    PdelayRespFollowUp(&'a PdelayBody),
+    /// Any other message type, with the bytes following the common header.
+    Other(&'a [u8]),
+}
+
+/// Type of the organization extension TLVs.
+pub const TLV_ORGANIZATION_EXTENSION: u16 = 0x0003;
+
+/// A PTP TLV.
+#[derive(Debug, Copy, Clone)]
+pub struct PtpTlv<'a> {
+    pub tlv_type: u16,
+    pub value: &'a [u8],
+}
+
+impl<'a> PtpTlv<'a> {
+    /// The value of the TLV, if it is the Follow_Up information TLV.
+    pub fn follow_up_info(&self) -> Option<&'a FollowUpInfo> {
+        if self.tlv_type != TLV_ORGANIZATION_EXTENSION {
+            return None;
+        }
+        FollowUpInfo::from_bytes(self.value).filter(|info| {
+            info.organization_id == FollowUpInfo::ORGANIZATION_ID
+                && info.organization_sub_type == FollowUpInfo::ORGANIZATION_SUB_TYPE
+        })
+    }
+}
+
+/// Iterator over the TLVs of a PTP message.
+#[derive(Debug, Clone)]
+pub struct PtpTlvs<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Iterator for PtpTlvs<'a> {
+    type Item = PtpTlv<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let [t0, t1, l0, l1, ref rest @ ..] = *self.data else {
+            return None;
+        };
+        let len = u16::from_be_bytes([l0, l1]) as usize;
+        let value = rest.get(..len);
+        self.data = value.map_or(&[], |value| &rest[value.len()..]);
+        Some(PtpTlv {
+            tlv_type: u16::from_be_bytes([t0, t1]),
+            value: value?,
+        })
+    }
+}
+
+/// Mean delay of a link measured by the peer delay mechanism, in
+/// nanoseconds: `t1` and `t4` are the transmission time of the Pdelay_Req
+/// and the reception time of the Pdelay_Resp, `t2` and `t3` the times
+/// reported by the responder, and `neighbor_rate_ratio` the ratio of the
+/// responder frequency to the local frequency.
+///
+/// ```
+/// use ether_packet::ptp::mean_link_delay;
+///
+/// // 500 ns each way, the responder taking 10 µs.
+/// assert_eq!(mean_link_delay(1_000, 2_000, 12_000, 12_000, 1.0), 500.0);
+/// ```
+#[inline]
+pub fn mean_link_delay(t1: u64, t2: u64, t3: u64, t4: u64, neighbor_rate_ratio: f64) -> f64 {
+    let round_trip = t4.wrapping_sub(t1) as i64 as f64;
+    let turnaround = t3.wrapping_sub(t2) as i64 as f64;
+    (neighbor_rate_ratio * round_trip - turnaround) / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PtpHdr, PtpMessage, PtpMessageType};
+
+    #[test]
+    fn test_gptp_follow_up() {
+        let mut pdu = [0u8; 34 + 10 + 32];
+        pdu[..4].copy_from_slice(&[0x18, 0x02, 0, 76]);
+        pdu[8..16].copy_from_slice(&[0, 0, 0, 0, 0, 0x2a, 0x80, 0]); // 42.5 ns
+        pdu[20..28].copy_from_slice(&[0, 0x1b, 0x21, 0xff, 0xfe, 0, 0, 1]);
+        pdu[28..34].copy_from_slice(&[0, 1, 0, 7, 2, 0xfd]);
+        pdu[38..44].copy_from_slice(&[0, 5, 0x1d, 0xcd, 0x65, 0]); // 5.5 s
+        pdu[44..48].copy_from_slice(&[0, 3, 0, 28]);
+        pdu[48..54].copy_from_slice(&[0, 0x80, 0xc2, 0, 0, 1]);
+        pdu[54..58].copy_from_slice(&0x0000_0800u32.to_be_bytes()); // 2^-30
+        pdu[58..60].copy_from_slice(&[0, 3]);
+
+        let (hdr, msg, mut tlvs) = PtpHdr::parse(&pdu).unwrap();
+        assert!(hdr.is_gptp());
+        assert_eq!(hdr.message_type(), Some(PtpMessageType::FollowUp));
+        assert_eq!((hdr.version(), hdr.correction_nanos()), (2, 42));
+        assert_eq!(hdr.sequence_id.to_bits(), 7);
+        assert_eq!(hdr.log_message_interval, -3);
+        let PtpMessage::FollowUp(ts) = msg else {
+            panic!("expected a Follow_Up");
+        };
+        assert_eq!(ts.as_nanos(), 5_500_000_000);
+        let info = tlvs.next().unwrap().follow_up_info().unwrap();
+        assert_eq!(info.rate_ratio(), 1.0 + 1.0 / (1u64 << 30) as f64);
+        assert_eq!(info.gm_time_base_indicator(), 3);
+        assert!(tlvs.next().is_none());
+
+        // Pdelay_Resp, cut before the requesting port identity.
+        let mut resp = [0u8; 34 + 20];
+        resp[..4].copy_from_slice(&[0x13, 0x02, 0, 54]);
+        let (_, msg, _) = PtpHdr::parse(&resp).unwrap();
+        assert!(matches!(msg, PtpMessage::PdelayResp(_)));
+        resp[3] = 50;
+        assert!(PtpHdr::parse(&resp).is_none());
+    }
+}
@@ -0,0 +1,188 @@
+//! Translation between the DSCP of IP packets and the 802.1p priority (PCP)
+//! of VLAN tags, as done by QoS gateways between routed and bridged
+//! domains.
+
+use crate::{checksum, eth::EtherType};
+
+/// Returns the DSCP of the IPv4 or IPv6 packet stored in `packet`.
+#[inline]
+pub fn dscp(packet: &[u8]) -> Option<u8> {
+    match packet {
+        [b, tos, ..] if b >> 4 == 4 => Some(tos >> 2),
+        [b, tc, ..] if b >> 4 == 6 => Some((b & 0x0f) << 2 | tc >> 6),
+        _ => None,
+    }
+}
+
+/// Sets the DSCP of the IPv4 or IPv6 packet stored in `packet`, keeping the
+/// ECN codepoint and updating the IPv4 header checksum.
+///
+/// Returns `false` if the packet is neither IPv4 nor IPv6.
+pub fn set_dscp(packet: &mut [u8], dscp: u8) -> bool {
+    let dscp = dscp & 0x3f;
+    match packet {
+        [b, tos, _, _, _, _, _, _, _, _, hi, lo, ..] if *b >> 4 == 4 => {
+            let old = u16::from_be_bytes([*b, *tos]);
+            *tos = dscp << 2 | (*tos & 0x3);
+            let new = u16::from_be_bytes([*b, *tos]);
+            let check = checksum::update(u16::from_be_bytes([*hi, *lo]), old, new);
+            [*hi, *lo] = check.to_be_bytes();
+        }
+        [b, tc, ..] if *b >> 4 == 6 => {
+            *b = (*b & 0xf0) | dscp >> 2;
+            *tc = (dscp & 0x3) << 6 | (*tc & 0x3f);
+        }
+        _ => return false,
+    }
+    true
+}
+
+/// Mapping tables between DSCP and PCP.
+///
+/// The default tables map each DSCP to the PCP of its class selector, i.e.
+/// its 3 most significant bits, and each PCP to the class selector
+/// codepoint CS0 to CS7.
+///
+/// ```
+/// use ether_packet::qos::QosMap;
+///
+/// let mut map = QosMap::new();
+/// map.map_pcp(5, 46); // EF
+/// assert_eq!(map.pcp_of(46), 5);
+/// assert_eq!(map.dscp_of(5), 46);
+/// ```
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct QosMap {
+    to_pcp: [u8; 64],
+    to_dscp: [u8; 8],
+}
+
+impl Default for QosMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl QosMap {
+    /// Creates the class selector mapping.
+    pub const fn new() -> Self {
+        let mut to_pcp = [0; 64];
+        let mut dscp = 0;
+        while dscp < 64 {
+            to_pcp[dscp] = dscp as u8 >> 3;
+            dscp += 1;
+        }
+        Self {
+            to_pcp,
+            to_dscp: [0, 8, 16, 24, 32, 40, 48, 56],
+        }
+    }
+
+    /// Maps `dscp` to `pcp`.
+    pub fn map_dscp(&mut self, dscp: u8, pcp: u8) -> &mut Self {
+        self.to_pcp[(dscp & 0x3f) as usize] = pcp & 0x7;
+        self
+    }
+
+    /// Maps `pcp` to `dscp`.
+    pub fn map_pcp(&mut self, pcp: u8, dscp: u8) -> &mut Self {
+        self.to_dscp[(pcp & 0x7) as usize] = dscp & 0x3f;
+        self
+    }
+
+    /// PCP `dscp` maps to.
+    #[inline]
+    pub const fn pcp_of(&self, dscp: u8) -> u8 {
+        self.to_pcp[(dscp & 0x3f) as usize]
+    }
+
+    /// DSCP `pcp` maps to.
+    #[inline]
+    pub const fn dscp_of(&self, pcp: u8) -> u8 {
+        self.to_dscp[(pcp & 0x7) as usize]
+    }
+
+    /// Sets the PCP of the outermost VLAN tag of the Ethernet frame `frame`
+    /// from the DSCP of its IP packet, returning the new PCP.
+    ///
+    /// Returns `None`, leaving the frame untouched, if it is untagged or does
+    /// not carry IPv4 or IPv6.
+    pub fn dscp_to_pcp(&self, frame: &mut [u8]) -> Option<u8> {
+        let ip = ip_offset(frame)?;
+        if ip == 14 {
+            return None;
+        }
+        let pcp = self.pcp_of(dscp(&frame[ip..])?);
+        frame[14] = (frame[14] & 0x1f) | pcp << 5;
+        Some(pcp)
+    }
+
+    /// Sets the DSCP of the IP packet of the Ethernet frame `frame` from the
+    /// PCP of its outermost VLAN tag, returning the new DSCP.
+    ///
+    /// Returns `None`, leaving the frame untouched, if it is untagged or does
+    /// not carry IPv4 or IPv6.
+    pub fn pcp_to_dscp(&self, frame: &mut [u8]) -> Option<u8> {
+        let ip = ip_offset(frame)?;
+        if ip == 14 {
+            return None;
+        }
+        let dscp = self.dscp_of(frame[14] >> 5);
+        set_dscp(&mut frame[ip..], dscp).then_some(dscp)
+    }
+}
+
+/// Offset of the IPv4 or IPv6 packet of an Ethernet frame, after its VLAN
+/// tags.
+fn ip_offset(frame: &[u8]) -> Option<usize> {
+    let mut offset = 12;
+    loop {
+        let ether_type = u16::from_be_bytes([*frame.get(offset)?, *frame.get(offset + 1)?]);
+        match EtherType::from_u16(ether_type) {
+            Some(t) if t.is_vlan() => offset += 4,
+            Some(EtherType::Ipv4 | EtherType::Ipv6) => {
+                return (frame.len() >= offset + 2 + 12).then_some(offset + 2);
+            }
+            _ => return None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{dscp, QosMap};
+    use crate::checksum;
+
+    #[test]
+    fn test_qos_map() {
+        #[rustfmt::skip]
+        let mut frame = [
+            0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 1,
+            // S-tag, C-tag with PCP 3.
+            0x88, 0xa8, 0x00, 0x64, 0x81, 0x00, 0x60, 0x0a, 0x08, 0x00,
+            // IPv4, DSCP 46 and ECT(0).
+            0x45, 0xba, 0, 20, 0, 0, 0x40, 0, 64, 17, 0, 0, 10, 0, 0, 1, 10, 0, 0, 2,
+        ];
+        let check = checksum::checksum(&frame[22..]);
+        frame[32..34].copy_from_slice(&check.to_be_bytes());
+
+        let mut map = QosMap::new();
+        assert_eq!(map.dscp_to_pcp(&mut frame), Some(5));
+        assert_eq!(&frame[14..16], &[0xa0, 0x64]);
+        map.map_pcp(5, 34);
+        assert_eq!(map.pcp_to_dscp(&mut frame), Some(34));
+        assert_eq!(frame[23], 34 << 2 | 0x2);
+        assert_eq!(checksum::checksum(&frame[22..]), 0);
+
+        let mut v6 = [0u8; 40];
+        v6[..2].copy_from_slice(&[0x6b, 0x90]); // DSCP 46, ECT(1)
+        assert_eq!(dscp(&v6), Some(46));
+        assert!(super::set_dscp(&mut v6, 10));
+        assert_eq!(&v6[..2], &[0x62, 0x90]);
+
+        // Untagged frames have no PCP.
+        let mut untagged = [0u8; 34];
+        untagged[12..15].copy_from_slice(&[0x08, 0x00, 0x45]);
+        assert_eq!(map.dscp_to_pcp(&mut untagged), None);
+    }
+}
@@ -0,0 +1,780 @@
+//! Header compression for IPv4/UDP and IPv4/UDP/RTP flows over constrained
+//! links, modeled on the unidirectional mode of RObust Header Compression
+//! ([RFC 3095](https://datatracker.ietf.org/doc/html/rfc3095)) profiles 1
+//! (RTP) and 2 (UDP).
+//!
+//! A context is established with IR packets carrying the static chain
+//! (addresses, ports, SSRC) and the dynamic chain (TOS, TTL, IP-ID, sequence
+//! number, timestamp...). Once established, packets are sent as UO-0 or
+//! UOR-2, carrying only the least significant bits of the sequence number
+//! and of the scaled RTP timestamp, from which the IP-ID and the timestamp
+//! are inferred. Any other change is sent as an IR-DYN packet.
+//!
+//! ```text
+//! IR:     [Add-CID] | 1111 1101 | Profile | CRC-8 | Static | Dynamic | Payload
+//! IR-DYN: [Add-CID] | 1111 1000 | Profile | CRC-8 | Dynamic | Payload
+//! UO-0:   [Add-CID] | 0 SN(4) CRC-3 | [UDP Checksum] | Payload
+//! UOR-2:  [Add-CID] | 110 TS(5) | M SN(7) | CRC-8 | [UDP Checksum] | Payload
+//! ```
+//!
+//! This is a subset: the packet types and CRCs follow RFC 3095, but the
+//! chains are simplified, so both ends must use this module. Only IPv4
+//! without options or fragmentation, and RTP without CSRCs or header
+//! extension, are compressed.
+
+use core::{fmt, net::Ipv4Addr};
+
+use crate::{
+    builder::{BuildError, Writer},
+    checksum,
+    ip::IpProto,
+};
+
+/// Number of IR packets sent when a context is established, so that it
+/// survives the loss of some of them.
+pub const IR_COUNT: u8 = 3;
+
+/// Largest number of contexts, as small CIDs range from 0 to 15.
+pub const MAX_CONTEXTS: usize = 16;
+
+const IR: u8 = 0xfd;
+const IR_DYN: u8 = 0xf8;
+const ADD_CID: u8 = 0xe0;
+
+/// ROHC profiles.
+#[repr(u8)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum Profile {
+    /// IPv4/UDP/RTP.
+    Rtp = 1,
+    /// IPv4/UDP, with a sequence number generated by the compressor.
+    Udp = 2,
+}
+
+impl TryFrom<u8> for Profile {
+    type Error = ();
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(Profile::Rtp),
+            2 => Ok(Profile::Udp),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Error returned by [`Compressor::compress`] and
+/// [`Decompressor::decompress`].
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum RohcError {
+    /// The packet does not match the profile, e.g. it has IP options.
+    Unsupported,
+    /// The output buffer is too small.
+    BufferTooSmall,
+    /// The packet is truncated or inconsistent.
+    Malformed,
+    /// A compressed packet refers to a context which was not established.
+    NoContext,
+    /// The CRC of a packet does not match, the context is left untouched.
+    BadCrc,
+}
+
+impl fmt::Display for RohcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RohcError::Unsupported => f.write_str("packet not supported by the profile"),
+            RohcError::BufferTooSmall => f.write_str("buffer too small"),
+            RohcError::Malformed => f.write_str("malformed packet"),
+            RohcError::NoContext => f.write_str("no context for CID"),
+            RohcError::BadCrc => f.write_str("CRC mismatch"),
+        }
+    }
+}
+
+impl From<BuildError> for RohcError {
+    fn from(err: BuildError) -> Self {
+        match err {
+            BuildError::BufferTooSmall => RohcError::BufferTooSmall,
+            BuildError::FieldOverflow | BuildError::FrameTooLarge | BuildError::MisplacedHeader => {
+                RohcError::Malformed
+            }
+        }
+    }
+}
+
+/// Reflected CRC with all bits initially set, as in RFC 3095 section 5.9.
+fn crc(data: &[u8], init: u8, poly: u8) -> u8 {
+    let mut crc = init;
+    for &b in data {
+        crc ^= b;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ poly
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    crc
+}
+
+/// CRC-3, polynomial `1 + x + x^3`.
+#[inline]
+fn crc3(data: &[u8]) -> u8 {
+    crc(data, 0x7, 0x6)
+}
+
+/// CRC-8, polynomial `1 + x + x^2 + x^8`.
+#[inline]
+fn crc8(data: &[u8]) -> u8 {
+    crc(data, 0xff, 0xe0)
+}
+
+/// Decodes the `k` least significant bits `lsb` of a value, taking the one
+/// in the interpretation interval `[v_ref - p, v_ref - p + 2^k - 1]`
+/// (RFC 3095 section 4.5.1).
+#[inline]
+fn decode_lsb(v_ref: u32, lsb: u32, k: u32, p: i32) -> u32 {
+    let low = v_ref.wrapping_sub(p as u32);
+    low.wrapping_add(lsb.wrapping_sub(low) & ((1 << k) - 1))
+}
+
+/// Big-endian cursor over a received packet.
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], RohcError> {
+        let out = self
+            .data
+            .get(self.pos..self.pos + len)
+            .ok_or(RohcError::Malformed)?;
+        self.pos += len;
+        Ok(out)
+    }
+
+    fn u8(&mut self) -> Result<u8, RohcError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, RohcError> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, RohcError> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn rest(&self) -> &'a [u8] {
+        &self.data[self.pos..]
+    }
+}
+
+/// Header fields of a packet of a flow.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+struct Fields {
+    profile: Profile,
+    src: Ipv4Addr,
+    dst: Ipv4Addr,
+    src_port: u16,
+    dst_port: u16,
+    ssrc: u32,
+    tos: u8,
+    ttl: u8,
+    df: bool,
+    ip_id: u16,
+    udp_check: u16,
+    padding: bool,
+    marker: bool,
+    payload_type: u8,
+    sn: u16,
+    ts: u32,
+}
+
+impl Fields {
+    /// Parses the headers of `packet`, returning them with the payload.
+    fn parse(profile: Profile, packet: &[u8]) -> Result<(Self, &[u8]), RohcError> {
+        let be16 = |i: usize| u16::from_be_bytes([packet[i], packet[i + 1]]);
+        if packet.len() < 28 {
+            return Err(RohcError::Malformed);
+        }
+        if packet[0] != 0x45 || packet[9] != IpProto::Udp as u8 || be16(6) & !0x4000 != 0 {
+            return Err(RohcError::Unsupported);
+        }
+        let tot_len = be16(2) as usize;
+        if tot_len < 28 || tot_len > packet.len() || be16(24) as usize != tot_len - 20 {
+            return Err(RohcError::Malformed);
+        }
+        let mut fields = Fields {
+            profile,
+            src: Ipv4Addr::new(packet[12], packet[13], packet[14], packet[15]),
+            dst: Ipv4Addr::new(packet[16], packet[17], packet[18], packet[19]),
+            src_port: be16(20),
+            dst_port: be16(22),
+            ssrc: 0,
+            tos: packet[1],
+            ttl: packet[8],
+            df: be16(6) != 0,
+            ip_id: be16(4),
+            udp_check: be16(26),
+            padding: false,
+            marker: false,
+            payload_type: 0,
+            sn: 0,
+            ts: 0,
+        };
+        if profile == Profile::Udp {
+            return Ok((fields, &packet[28..tot_len]));
+        }
+        let rtp = packet.get(28..40).ok_or(RohcError::Malformed)?;
+        if rtp[0] & !0x20 != 0x80 {
+            return Err(RohcError::Unsupported);
+        }
+        fields.padding = rtp[0] & 0x20 != 0;
+        fields.marker = rtp[1] & 0x80 != 0;
+        fields.payload_type = rtp[1] & 0x7f;
+        fields.sn = be16(30);
+        fields.ts = u32::from_be_bytes(rtp[4..8].try_into().unwrap());
+        fields.ssrc = u32::from_be_bytes(rtp[8..12].try_into().unwrap());
+        Ok((fields, &packet[40..tot_len]))
+    }
+
+    #[inline]
+    fn same_flow(&self, other: &Fields) -> bool {
+        (
+            self.profile,
+            self.src,
+            self.dst,
+            self.src_port,
+            self.dst_port,
+            self.ssrc,
+        ) == (
+            other.profile,
+            other.src,
+            other.dst,
+            other.src_port,
+            other.dst_port,
+            other.ssrc,
+        )
+    }
+
+    /// Writes the uncompressed headers for a payload of `payload_len` bytes.
+    fn write_header(&self, payload_len: usize, w: &mut Writer<'_>) -> Result<(), RohcError> {
+        let hdr_len = match self.profile {
+            Profile::Rtp => 40,
+            Profile::Udp => 28,
+        };
+        let tot_len = u16::try_from(hdr_len + payload_len).map_err(|_| RohcError::Malformed)?;
+        let start = w.pos();
+        w.put(&[0x45, self.tos])?;
+        w.put(&tot_len.to_be_bytes())?;
+        w.put(&self.ip_id.to_be_bytes())?;
+        w.put(&[
+            if self.df { 0x40 } else { 0 },
+            0,
+            self.ttl,
+            IpProto::Udp as u8,
+            0,
+            0,
+        ])?;
+        w.put(&self.src.octets())?;
+        w.put(&self.dst.octets())?;
+        let check = checksum::checksum(&w.written()[start..]);
+        w.set_u16(start + 10, check);
+        w.put(&self.src_port.to_be_bytes())?;
+        w.put(&self.dst_port.to_be_bytes())?;
+        w.put(&(tot_len - 20).to_be_bytes())?;
+        w.put(&self.udp_check.to_be_bytes())?;
+        if self.profile == Profile::Rtp {
+            w.put(&[
+                0x80 | (self.padding as u8) << 5,
+                (self.marker as u8) << 7 | self.payload_type,
+            ])?;
+            w.put(&self.sn.to_be_bytes())?;
+            w.put(&self.ts.to_be_bytes())?;
+            w.put(&self.ssrc.to_be_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// CRC-3 or CRC-8 of the uncompressed headers, which the decompressor
+    /// checks against the headers it reconstructed.
+    fn header_crc(&self, payload_len: usize, crc: fn(&[u8]) -> u8) -> Result<u8, RohcError> {
+        let mut hdr = [0u8; 40];
+        let mut w = Writer::new(&mut hdr);
+        self.write_header(payload_len, &mut w)?;
+        Ok(crc(w.written()))
+    }
+}
+
+/// State shared by both ends of a flow.
+#[derive(Debug, Copy, Clone)]
+struct Context {
+    /// Fields of the last packet.
+    fields: Fields,
+    /// RTP timestamp increment per sequence number, 0 if unknown.
+    ts_stride: u32,
+    /// Whether the IP-ID is constant rather than following the sequence
+    /// number.
+    static_ip_id: bool,
+}
+
+impl Context {
+    /// Fields of the packet with sequence number `sn`, inferred from the
+    /// context. `ts_bits` are the 5 least significant bits of the scaled
+    /// timestamp, without them the timestamp follows the sequence number.
+    fn infer(&self, sn: u16, ts_bits: Option<u8>, marker: bool, udp_check: u16) -> Fields {
+        let mut fields = self.fields;
+        let delta = sn.wrapping_sub(fields.sn);
+        if !self.static_ip_id {
+            fields.ip_id = fields.ip_id.wrapping_add(delta);
+        }
+        let stride = self.ts_stride;
+        fields.ts = match ts_bits {
+            None => fields.ts.wrapping_add(stride.wrapping_mul(delta as u32)),
+            Some(_) if stride == 0 => fields.ts,
+            Some(bits) => {
+                let scaled = decode_lsb(fields.ts / stride, bits as u32, 5, 0);
+                scaled.wrapping_mul(stride).wrapping_add(fields.ts % stride)
+            }
+        };
+        fields.sn = sn;
+        fields.marker = marker;
+        fields.udp_check = udp_check;
+        fields
+    }
+
+    fn write_static(&self, w: &mut Writer<'_>) -> Result<(), BuildError> {
+        let f = &self.fields;
+        w.put(&f.src.octets())?;
+        w.put(&f.dst.octets())?;
+        w.put(&f.src_port.to_be_bytes())?;
+        w.put(&f.dst_port.to_be_bytes())?;
+        if f.profile == Profile::Rtp {
+            w.put(&f.ssrc.to_be_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn read_static(profile: Profile, r: &mut Reader<'_>) -> Result<Self, RohcError> {
+        let src = Ipv4Addr::from(r.u32()?);
+        let dst = Ipv4Addr::from(r.u32()?);
+        let src_port = r.u16()?;
+        let dst_port = r.u16()?;
+        let ssrc = match profile {
+            Profile::Rtp => r.u32()?,
+            Profile::Udp => 0,
+        };
+        Ok(Context {
+            fields: Fields {
+                profile,
+                src,
+                dst,
+                src_port,
+                dst_port,
+                ssrc,
+                tos: 0,
+                ttl: 0,
+                df: false,
+                ip_id: 0,
+                udp_check: 0,
+                padding: false,
+                marker: false,
+                payload_type: 0,
+                sn: 0,
+                ts: 0,
+            },
+            ts_stride: 0,
+            static_ip_id: false,
+        })
+    }
+
+    fn write_dynamic(&self, w: &mut Writer<'_>) -> Result<(), BuildError> {
+        let f = &self.fields;
+        let flags = f.df as u8
+            | (self.static_ip_id as u8) << 1
+            | (f.padding as u8) << 2
+            | (f.marker as u8) << 3;
+        w.put(&[f.tos, f.ttl, flags])?;
+        w.put(&f.ip_id.to_be_bytes())?;
+        w.put(&f.udp_check.to_be_bytes())?;
+        w.put(&f.sn.to_be_bytes())?;
+        if f.profile == Profile::Rtp {
+            w.put_u8(f.payload_type)?;
+            w.put(&f.ts.to_be_bytes())?;
+            w.put(&self.ts_stride.to_be_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn read_dynamic(&mut self, r: &mut Reader<'_>) -> Result<(), RohcError> {
+        let f = &mut self.fields;
+        f.tos = r.u8()?;
+        f.ttl = r.u8()?;
+        let flags = r.u8()?;
+        f.df = flags & 0x1 != 0;
+        self.static_ip_id = flags & 0x2 != 0;
+        f.padding = flags & 0x4 != 0;
+        f.marker = flags & 0x8 != 0;
+        f.ip_id = r.u16()?;
+        f.udp_check = r.u16()?;
+        f.sn = r.u16()?;
+        if f.profile == Profile::Rtp {
+            f.payload_type = r.u8()? & 0x7f;
+            f.ts = r.u32()?;
+            self.ts_stride = r.u32()?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+struct CompressorContext {
+    ctx: Context,
+    /// IR packets still to be sent.
+    ir_left: u8,
+}
+
+/// Compressor of up to `N` flows, each one identified by a small CID.
+/// When all contexts are in use, the oldest one is reused.
+///
+/// ```
+/// use ether_packet::rohc::{Compressor, Decompressor, Profile};
+///
+/// let packet = [
+///     0x45, 0, 0, 30, 0, 1, 0x40, 0, 64, 17, 0x26, 0xcc, 10, 0, 0, 1, 10, 0, 0, 2,
+///     0x30, 0x39, 0x13, 0x88, 0, 10, 0, 0, b'h', b'i',
+/// ];
+/// let mut compressor: Compressor = Compressor::new();
+/// let mut decompressor: Decompressor = Decompressor::new();
+/// let (mut rohc, mut out) = ([0u8; 64], [0u8; 64]);
+///
+/// let len = compressor.compress(Profile::Udp, &packet, &mut rohc).unwrap();
+/// let len = decompressor.decompress(&rohc[..len], &mut out).unwrap();
+/// assert_eq!(&out[..len], &packet);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Compressor<const N: usize = MAX_CONTEXTS> {
+    contexts: [Option<CompressorContext>; N],
+    next_cid: usize,
+}
+
+impl<const N: usize> Compressor<N> {
+    /// Creates a compressor without contexts.
+    ///
+    /// Fails to compile unless `N` is between 1 and [`MAX_CONTEXTS`].
+    pub fn new() -> Self {
+        const { assert!(0 < N && N <= MAX_CONTEXTS, "1 to 16 contexts") };
+        Self {
+            contexts: [None; N],
+            next_cid: 0,
+        }
+    }
+
+    /// Compresses the IPv4 packet `packet` according to `profile`, writing
+    /// the ROHC packet to `out` and returning its length.
+    pub fn compress(
+        &mut self,
+        profile: Profile,
+        packet: &[u8],
+        out: &mut [u8],
+    ) -> Result<usize, RohcError> {
+        let (mut fields, payload) = Fields::parse(profile, packet)?;
+        let found = self
+            .contexts
+            .iter()
+            .position(|c| c.is_some_and(|c| c.ctx.fields.same_flow(&fields)));
+        let cid = match found {
+            Some(cid) => cid,
+            None => {
+                let cid = self.next_cid;
+                self.next_cid = (cid + 1) % N;
+                self.contexts[cid] = None;
+                cid
+            }
+        };
+
+        let mut w = Writer::new(out);
+        if cid != 0 {
+            w.put_u8(ADD_CID | cid as u8)?;
+        }
+        let comp = match self.contexts[cid] {
+            Some(comp) => {
+                if profile == Profile::Udp {
+                    fields.sn = comp.ctx.fields.sn.wrapping_add(1);
+                }
+                compress_fields(&comp, fields, payload.len(), &mut w)?
+            }
+            None => {
+                let ctx = Context {
+                    fields,
+                    ts_stride: 0,
+                    static_ip_id: fields.ip_id == 0,
+                };
+                write_ir(&ctx, IR, &mut w)?;
+                CompressorContext {
+                    ctx,
+                    ir_left: IR_COUNT - 1,
+                }
+            }
+        };
+        w.put(payload)?;
+        self.contexts[cid] = Some(comp);
+        Ok(w.pos())
+    }
+}
+
+impl<const N: usize> Default for Compressor<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Writes an IR or IR-DYN header, whose CRC-8 covers the header but the
+/// Add-CID octet and the CRC itself.
+fn write_ir(ctx: &Context, packet_type: u8, w: &mut Writer<'_>) -> Result<(), RohcError> {
+    let start = w.pos();
+    w.put(&[packet_type, ctx.fields.profile as u8, 0])?;
+    if packet_type == IR {
+        ctx.write_static(w)?;
+    }
+    ctx.write_dynamic(w)?;
+    let hdr = &mut w.written_mut()[start..];
+    hdr[2] = crc(&hdr[3..], crc8(&hdr[..2]), 0xe0);
+    Ok(())
+}
+
+/// Writes the smallest header allowing the decompressor to rebuild
+/// `fields` from the context, returning the updated context.
+fn compress_fields(
+    comp: &CompressorContext,
+    fields: Fields,
+    payload_len: usize,
+    w: &mut Writer<'_>,
+) -> Result<CompressorContext, RohcError> {
+    let ctx = &comp.ctx;
+    let delta = fields.sn.wrapping_sub(ctx.fields.sn);
+    let same_check = (fields.udp_check != 0) == (ctx.fields.udp_check != 0);
+    if comp.ir_left == 0 && same_check {
+        // The least significant bits of the sequence number decode to
+        // `fields.sn` as long as its delta is within the interpretation
+        // interval, so only the inferred fields have to be checked.
+        let ts_bits = match ctx.ts_stride {
+            0 => 0,
+            stride => (fields.ts / stride) as u8 & 0x1f,
+        };
+        let mut header = [0u8; 3];
+        let len = if (1..=16).contains(&delta)
+            && ctx.infer(fields.sn, None, false, fields.udp_check) == fields
+        {
+            header[0] = ((fields.sn & 0xf) as u8) << 3 | fields.header_crc(payload_len, crc3)?;
+            1
+        } else if (1..=128).contains(&delta)
+            && ctx.infer(fields.sn, Some(ts_bits), fields.marker, fields.udp_check) == fields
+        {
+            header = [
+                0xc0 | ts_bits,
+                (fields.marker as u8) << 7 | (fields.sn & 0x7f) as u8,
+                fields.header_crc(payload_len, crc8)?,
+            ];
+            3
+        } else {
+            0
+        };
+        if len > 0 {
+            w.put(&header[..len])?;
+            if fields.udp_check != 0 {
+                w.put(&fields.udp_check.to_be_bytes())?;
+            }
+            return Ok(CompressorContext {
+                ctx: Context { fields, ..*ctx },
+                ir_left: 0,
+            });
+        }
+    }
+
+    let mut next = Context {
+        fields,
+        ts_stride: ctx.ts_stride,
+        static_ip_id: fields.ip_id == ctx.fields.ip_id,
+    };
+    let ts_delta = fields.ts.wrapping_sub(ctx.fields.ts);
+    if delta != 0 && ts_delta != 0 && ts_delta.is_multiple_of(delta as u32) {
+        next.ts_stride = ts_delta / delta as u32;
+    }
+    let packet_type = if comp.ir_left > 0 { IR } else { IR_DYN };
+    write_ir(&next, packet_type, w)?;
+    Ok(CompressorContext {
+        ctx: next,
+        ir_left: comp.ir_left.saturating_sub(1),
+    })
+}
+
+/// Decompressor of up to `N` flows, the counterpart of [`Compressor`].
+#[derive(Debug, Clone)]
+pub struct Decompressor<const N: usize = MAX_CONTEXTS> {
+    contexts: [Option<Context>; N],
+}
+
+impl<const N: usize> Decompressor<N> {
+    /// Creates a decompressor without contexts.
+    ///
+    /// Fails to compile unless `N` is between 1 and [`MAX_CONTEXTS`].
+    pub fn new() -> Self {
+        const { assert!(0 < N && N <= MAX_CONTEXTS, "1 to 16 contexts") };
+        Self {
+            contexts: [None; N],
+        }
+    }
+
+    /// Decompresses the ROHC packet `data`, writing the IPv4 packet to `out`
+    /// and returning its length. The context is only updated if the packet
+    /// passes its CRC check.
+    pub fn decompress(&mut self, data: &[u8], out: &mut [u8]) -> Result<usize, RohcError> {
+        let (cid, data) = match data {
+            [b, rest @ ..] if b & 0xf0 == ADD_CID => ((b & 0xf) as usize, rest),
+            _ => (0, data),
+        };
+        let slot = self.contexts.get_mut(cid).ok_or(RohcError::NoContext)?;
+        let mut r = Reader { data, pos: 0 };
+        let packet_type = r.u8()?;
+
+        let ctx = if packet_type == IR || packet_type == IR_DYN {
+            let profile = Profile::try_from(r.u8()?).map_err(|_| RohcError::Unsupported)?;
+            let crc = r.u8()?;
+            let mut ctx = match *slot {
+                _ if packet_type == IR => Context::read_static(profile, &mut r)?,
+                Some(ctx) if ctx.fields.profile == profile => ctx,
+                Some(_) => return Err(RohcError::Malformed),
+                None => return Err(RohcError::NoContext),
+            };
+            ctx.read_dynamic(&mut r)?;
+            if self::crc(&data[3..r.pos], crc8(&data[..2]), 0xe0) != crc {
+                return Err(RohcError::BadCrc);
+            }
+            ctx
+        } else {
+            let ctx = slot.ok_or(RohcError::NoContext)?;
+            let (fields, crc, expected) = if packet_type & 0x80 == 0 {
+                let sn = decode_lsb(ctx.fields.sn as u32, (packet_type >> 3) as u32, 4, -1);
+                let udp_check = read_udp_check(&ctx, &mut r)?;
+                let fields = ctx.infer(sn as u16, None, false, udp_check);
+                let payload_len = r.rest().len();
+                (
+                    fields,
+                    fields.header_crc(payload_len, crc3)?,
+                    packet_type & 0x7,
+                )
+            } else if packet_type & 0xe0 == 0xc0 {
+                let b = r.u8()?;
+                let crc = r.u8()?;
+                let sn = decode_lsb(ctx.fields.sn as u32, (b & 0x7f) as u32, 7, -1);
+                let udp_check = read_udp_check(&ctx, &mut r)?;
+                let fields = ctx.infer(
+                    sn as u16,
+                    Some(packet_type & 0x1f),
+                    b & 0x80 != 0,
+                    udp_check,
+                );
+                let payload_len = r.rest().len();
+                (fields, fields.header_crc(payload_len, crc8)?, crc)
+            } else {
+                return Err(RohcError::Malformed);
+            };
+            if crc != expected {
+                return Err(RohcError::BadCrc);
+            }
+            Context { fields, ..ctx }
+        };
+
+        let payload = r.rest();
+        let mut w = Writer::new(out);
+        ctx.fields.write_header(payload.len(), &mut w)?;
+        w.put(payload)?;
+        *slot = Some(ctx);
+        Ok(w.pos())
+    }
+}
+
+impl<const N: usize> Default for Decompressor<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Reads the UDP checksum following a compressed header, only present if
+/// the flow uses checksums.
+#[inline]
+fn read_udp_check(ctx: &Context, r: &mut Reader<'_>) -> Result<u16, RohcError> {
+    match ctx.fields.udp_check {
+        0 => Ok(0),
+        _ => r.u16(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{crc3, crc8, Compressor, Decompressor, Profile, RohcError};
+    use crate::checksum;
+
+    fn rtp_packet(sn: u16, ts: u32, marker: bool, buf: &mut [u8; 50]) {
+        #[rustfmt::skip]
+        buf[..40].copy_from_slice(&[
+            0x45, 0, 0, 50, 0, 0, 0, 0, 64, 17, 0, 0, 10, 0, 0, 1, 10, 0, 0, 2,
+            0x13, 0x88, 0x13, 0x8a, 0, 30, 0, 0,
+            0x80, 8, 0, 0, 0, 0, 0, 0, 0xde, 0xad, 0xbe, 0xef,
+        ]);
+        buf[4..6].copy_from_slice(&sn.wrapping_add(100).to_be_bytes());
+        let check = checksum::checksum(&buf[..20]);
+        buf[10..12].copy_from_slice(&check.to_be_bytes());
+        buf[29] |= (marker as u8) << 7;
+        buf[30..32].copy_from_slice(&sn.to_be_bytes());
+        buf[32..36].copy_from_slice(&ts.to_be_bytes());
+        buf[40..].fill(sn as u8);
+    }
+
+    #[test]
+    fn test_rtp() {
+        assert_eq!(crc8(b"123456789"), 0xd0);
+        assert_eq!(crc3(b"123456789"), 0x6);
+
+        let mut compressor: Compressor = Compressor::new();
+        let mut decompressor: Decompressor = Decompressor::new();
+        let (mut packet, mut rohc, mut out) = ([0u8; 50], [0u8; 64], [0u8; 64]);
+        // Three IRs, then the timestamp follows the sequence number, then the
+        // marker is set and the timestamp jumps after a silence.
+        let packets = [
+            (1, 160, false),
+            (2, 320, false),
+            (3, 480, false),
+            (4, 640, false),
+        ];
+        let packets = packets
+            .into_iter()
+            .chain([(5, 800, true), (6, 2400, false)]);
+        let mut lens = [0; 6];
+        for (i, (sn, ts, marker)) in packets.enumerate() {
+            rtp_packet(sn, ts, marker, &mut packet);
+            let len = compressor
+                .compress(Profile::Rtp, &packet, &mut rohc)
+                .unwrap();
+            lens[i] = len - 10;
+            let len = decompressor.decompress(&rohc[..len], &mut out).unwrap();
+            assert_eq!(&out[..len], &packet);
+        }
+        assert_eq!(lens, [37, 37, 37, 1, 3, 3]);
+
+        // A lost context update is detected by the CRC.
+        rtp_packet(8, 2720, false, &mut packet);
+        let len = compressor
+            .compress(Profile::Rtp, &packet, &mut rohc)
+            .unwrap();
+        rohc[0] ^= 0x1;
+        assert_eq!(
+            decompressor.decompress(&rohc[..len], &mut out),
+            Err(RohcError::BadCrc)
+        );
+    }
+}
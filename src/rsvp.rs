@@ -0,0 +1,494 @@
+//! Resource ReSerVation Protocol ([RFC 2205](https://datatracker.ietf.org/doc/html/rfc2205)),
+//! carried directly over IP with [`IpProto::Rsvp`](crate::ip::IpProto::Rsvp), including the
+//! RSVP-TE LSP tunnel objects of [RFC 3209](https://datatracker.ietf.org/doc/html/rfc3209).
+
+use core::{
+    mem,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+};
+
+use crate::{
+    bitfield::BitfieldUnit,
+    checksum,
+    header::{impl_header, Header, Limited, ParseConfig},
+    types::U16,
+};
+
+/// RSVP message types.
+#[repr(u8)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum RsvpMsgType {
+    Path = 1,
+    Resv = 2,
+    PathErr = 3,
+    ResvErr = 4,
+    PathTear = 5,
+    ResvTear = 6,
+    ResvConf = 7,
+    Bundle = 12,
+    Ack = 13,
+    Srefresh = 15,
+    Hello = 20,
+}
+
+impl TryFrom<u8> for RsvpMsgType {
+    type Error = ();
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(RsvpMsgType::Path),
+            2 => Ok(RsvpMsgType::Resv),
+            3 => Ok(RsvpMsgType::PathErr),
+            4 => Ok(RsvpMsgType::ResvErr),
+            5 => Ok(RsvpMsgType::PathTear),
+            6 => Ok(RsvpMsgType::ResvTear),
+            7 => Ok(RsvpMsgType::ResvConf),
+            12 => Ok(RsvpMsgType::Bundle),
+            13 => Ok(RsvpMsgType::Ack),
+            15 => Ok(RsvpMsgType::Srefresh),
+            20 => Ok(RsvpMsgType::Hello),
+            _ => Err(()),
+        }
+    }
+}
+
+/// RSVP common header, followed by a sequence of objects.
+/// ```text
+///  0             1              2             3
+/// +-------------+-------------+-------------+-------------+
+/// | Vers | Flags|  Msg Type   |       RSVP Checksum       |
+/// +-------------+-------------+-------------+-------------+
+/// |  Send_TTL   | (Reserved)  |        RSVP Length        |
+/// +-------------+-------------+-------------+-------------+
+/// ```
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct RsvpHdr {
+    /// **Vers** (4 bits) and **Flags** (4 bits).
+    pub vers_flags: BitfieldUnit<[u8; 1usize]>,
+    /// See [`RsvpMsgType`].
+    pub msg_type: u8,
+    pub checksum: U16,
+    /// IP TTL with which the message was sent.
+    pub send_ttl: u8,
+    pub _reserved: u8,
+    /// Length of the whole message, including this header.
+    pub length: U16,
+}
+
+impl RsvpHdr {
+    pub const LEN: usize = mem::size_of::<RsvpHdr>();
+
+    #[inline]
+    pub const fn version(&self) -> u8 {
+        self.vers_flags.get(4, 4) as u8
+    }
+
+    #[inline]
+    pub const fn flags(&self) -> u8 {
+        self.vers_flags.get(0, 4) as u8
+    }
+
+    #[inline]
+    pub fn msg_type(&self) -> Option<RsvpMsgType> {
+        self.msg_type.try_into().ok()
+    }
+
+    #[inline]
+    pub const fn checksum(&self) -> u16 {
+        self.checksum.to_bits()
+    }
+
+    /// Parses the RSVP message stored in `msg`, returning its header and an
+    /// iterator over its objects.
+    pub fn parse(msg: &[u8]) -> Option<(&RsvpHdr, RsvpObjects<'_>)> {
+        let hdr = RsvpHdr::from_bytes(msg)?;
+        let objects = msg.get(Self::LEN..hdr.length.to_bits() as usize)?;
+        Some((hdr, RsvpObjects { data: objects }))
+    }
+}
+
+/// Verifies the checksum of the RSVP message at the start of `msg`. A zero
+/// checksum means none was transmitted and is always accepted.
+pub fn verify_checksum(msg: &[u8]) -> bool {
+    let Some(hdr) = RsvpHdr::from_bytes(msg) else {
+        return false;
+    };
+    match msg.get(..hdr.length.to_bits() as usize) {
+        Some(msg) => hdr.checksum() == 0 || checksum::checksum(msg) == 0,
+        None => false,
+    }
+}
+
+/// RSVP object classes.
+#[repr(u8)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum RsvpClass {
+    Session = 1,
+    RsvpHop = 3,
+    Integrity = 4,
+    TimeValues = 5,
+    ErrorSpec = 6,
+    Scope = 7,
+    Style = 8,
+    FlowSpec = 9,
+    FilterSpec = 10,
+    SenderTemplate = 11,
+    SenderTspec = 12,
+    Adspec = 13,
+    PolicyData = 14,
+    ResvConfirm = 15,
+    Label = 16,
+    LabelRequest = 19,
+    ExplicitRoute = 20,
+    RecordRoute = 21,
+    Hello = 22,
+    SessionAttribute = 207,
+}
+
+impl TryFrom<u8> for RsvpClass {
+    type Error = ();
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(RsvpClass::Session),
+            3 => Ok(RsvpClass::RsvpHop),
+            4 => Ok(RsvpClass::Integrity),
+            5 => Ok(RsvpClass::TimeValues),
+            6 => Ok(RsvpClass::ErrorSpec),
+            7 => Ok(RsvpClass::Scope),
+            8 => Ok(RsvpClass::Style),
+            9 => Ok(RsvpClass::FlowSpec),
+            10 => Ok(RsvpClass::FilterSpec),
+            11 => Ok(RsvpClass::SenderTemplate),
+            12 => Ok(RsvpClass::SenderTspec),
+            13 => Ok(RsvpClass::Adspec),
+            14 => Ok(RsvpClass::PolicyData),
+            15 => Ok(RsvpClass::ResvConfirm),
+            16 => Ok(RsvpClass::Label),
+            19 => Ok(RsvpClass::LabelRequest),
+            20 => Ok(RsvpClass::ExplicitRoute),
+            21 => Ok(RsvpClass::RecordRoute),
+            22 => Ok(RsvpClass::Hello),
+            207 => Ok(RsvpClass::SessionAttribute),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Session and Sender Template C-Types.
+pub const CTYPE_IPV4: u8 = 1;
+pub const CTYPE_IPV6: u8 = 2;
+pub const CTYPE_LSP_TUNNEL_IPV4: u8 = 7;
+pub const CTYPE_LSP_TUNNEL_IPV6: u8 = 8;
+
+/// RSVP object header.
+/// ```text
+///  0             1              2             3
+/// +-------------+-------------+-------------+-------------+
+/// |       Length (bytes)      |  Class-Num  |   C-Type    |
+/// +-------------+-------------+-------------+-------------+
+/// ```
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct RsvpObjHdr {
+    /// Length of the whole object, including this header.
+    pub length: U16,
+    /// See [`RsvpClass`].
+    pub class_num: u8,
+    pub c_type: u8,
+}
+
+impl RsvpObjHdr {
+    pub const LEN: usize = mem::size_of::<RsvpObjHdr>();
+
+    #[inline]
+    pub fn class(&self) -> Option<RsvpClass> {
+        self.class_num.try_into().ok()
+    }
+}
+
+impl_header!(RsvpHdr, RsvpObjHdr);
+
+/// An RSVP object.
+#[derive(Debug, Copy, Clone)]
+pub struct RsvpObject<'a> {
+    pub hdr: &'a RsvpObjHdr,
+    /// Object contents, without the object header.
+    pub contents: &'a [u8],
+}
+
+impl<'a> RsvpObject<'a> {
+    /// Decodes a SESSION object.
+    pub fn session(&self) -> Option<RsvpSession> {
+        let c = self.contents_of(RsvpClass::Session)?;
+        match self.hdr.c_type {
+            CTYPE_IPV4 | CTYPE_IPV6 => {
+                let (dst, rest) = address(c, self.hdr.c_type == CTYPE_IPV6)?;
+                let rest = rest.get(..4)?;
+                Some(RsvpSession::Ip {
+                    dst,
+                    protocol: rest[0],
+                    flags: rest[1],
+                    dst_port: u16::from_be_bytes([rest[2], rest[3]]),
+                })
+            }
+            CTYPE_LSP_TUNNEL_IPV4 | CTYPE_LSP_TUNNEL_IPV6 => {
+                let v6 = self.hdr.c_type == CTYPE_LSP_TUNNEL_IPV6;
+                let (end_point, rest) = address(c, v6)?;
+                let tunnel_id = u16::from_be_bytes(rest.get(2..4)?.try_into().ok()?);
+                let (ext_tunnel_id, _) = address(&rest[4..], v6)?;
+                Some(RsvpSession::LspTunnel {
+                    end_point,
+                    tunnel_id,
+                    ext_tunnel_id,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Decodes a SENDER_TEMPLATE (or FILTER_SPEC) object.
+    pub fn sender_template(&self) -> Option<RsvpSenderTemplate> {
+        if !matches!(
+            self.hdr.class(),
+            Some(RsvpClass::SenderTemplate | RsvpClass::FilterSpec)
+        ) {
+            return None;
+        }
+        let v6 = match self.hdr.c_type {
+            CTYPE_IPV4 | CTYPE_LSP_TUNNEL_IPV4 => false,
+            CTYPE_IPV6 | CTYPE_LSP_TUNNEL_IPV6 => true,
+            _ => return None,
+        };
+        let (src, rest) = address(self.contents, v6)?;
+        let port = u16::from_be_bytes(rest.get(2..4)?.try_into().ok()?);
+        match self.hdr.c_type {
+            CTYPE_IPV4 | CTYPE_IPV6 => Some(RsvpSenderTemplate::Ip {
+                src,
+                src_port: port,
+            }),
+            _ => Some(RsvpSenderTemplate::LspTunnel {
+                sender: src,
+                lsp_id: port,
+            }),
+        }
+    }
+
+    /// Decodes an Integrated Services FLOWSPEC (or SENDER_TSPEC) object.
+    pub fn flow_spec(&self) -> Option<RsvpFlowSpec> {
+        if !matches!(
+            self.hdr.class(),
+            Some(RsvpClass::FlowSpec | RsvpClass::SenderTspec)
+        ) || self.hdr.c_type != 2
+        {
+            return None;
+        }
+        // Message header (version and overall length), then the service
+        // header (service number and service data length).
+        let c = self.contents;
+        let service = *c.get(4)?;
+        let data_len = u16::from_be_bytes(c.get(6..8)?.try_into().ok()?) as usize * 4;
+        let mut params = c.get(8..8 + data_len)?;
+
+        let mut spec = RsvpFlowSpec {
+            service,
+            token_bucket: None,
+            guaranteed: None,
+        };
+        while params.len() >= 4 {
+            let id = params[0];
+            let len = 4 + u16::from_be_bytes([params[2], params[3]]) as usize * 4;
+            let value = params.get(4..len)?;
+            match id {
+                127 if value.len() >= 20 => {
+                    spec.token_bucket = Some(TokenBucket {
+                        rate: f32_at(value, 0),
+                        bucket_size: f32_at(value, 4),
+                        peak_rate: f32_at(value, 8),
+                        min_policed_unit: u32_at(value, 12),
+                        max_packet_size: u32_at(value, 16),
+                    })
+                }
+                130 if value.len() >= 8 => {
+                    spec.guaranteed = Some(GuaranteedParams {
+                        rate: f32_at(value, 0),
+                        slack: u32_at(value, 4),
+                    })
+                }
+                _ => {}
+            }
+            params = &params[len..];
+        }
+        Some(spec)
+    }
+
+    fn contents_of(&self, class: RsvpClass) -> Option<&'a [u8]> {
+        (self.hdr.class_num == class as u8).then_some(self.contents)
+    }
+}
+
+/// A decoded SESSION object.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum RsvpSession {
+    /// IPv4 or IPv6 session (C-Type 1 or 2).
+    Ip {
+        dst: IpAddr,
+        protocol: u8,
+        flags: u8,
+        dst_port: u16,
+    },
+    /// RSVP-TE LSP tunnel session (C-Type 7 or 8).
+    LspTunnel {
+        end_point: IpAddr,
+        tunnel_id: u16,
+        ext_tunnel_id: IpAddr,
+    },
+}
+
+/// A decoded SENDER_TEMPLATE or FILTER_SPEC object.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum RsvpSenderTemplate {
+    /// IPv4 or IPv6 sender (C-Type 1 or 2).
+    Ip { src: IpAddr, src_port: u16 },
+    /// RSVP-TE LSP tunnel sender (C-Type 7 or 8).
+    LspTunnel { sender: IpAddr, lsp_id: u16 },
+}
+
+/// A decoded Integrated Services FLOWSPEC or SENDER_TSPEC object
+/// ([RFC 2210](https://datatracker.ietf.org/doc/html/rfc2210)).
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct RsvpFlowSpec {
+    /// Service number: 1 for the default/general parameters (TSPEC),
+    /// 2 for Guaranteed and 5 for Controlled-Load.
+    pub service: u8,
+    pub token_bucket: Option<TokenBucket>,
+    pub guaranteed: Option<GuaranteedParams>,
+}
+
+/// Token bucket TSpec parameters.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct TokenBucket {
+    /// Token bucket rate, in bytes per second.
+    pub rate: f32,
+    /// Token bucket size, in bytes.
+    pub bucket_size: f32,
+    /// Peak data rate, in bytes per second.
+    pub peak_rate: f32,
+    pub min_policed_unit: u32,
+    pub max_packet_size: u32,
+}
+
+/// Guaranteed service RSpec parameters.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct GuaranteedParams {
+    /// Reserved rate, in bytes per second.
+    pub rate: f32,
+    /// Slack term, in microseconds.
+    pub slack: u32,
+}
+
+/// Iterator over the objects of an RSVP message.
+#[derive(Debug, Clone)]
+pub struct RsvpObjects<'a> {
+    data: &'a [u8],
+}
+
+impl RsvpObjects<'_> {
+    /// Bounds the walk to [`ParseConfig::max_options`] objects.
+    #[inline]
+    pub fn limit(self, config: &ParseConfig) -> Limited<Self> {
+        Limited::new(self, config.max_options)
+    }
+}
+
+impl<'a> Iterator for RsvpObjects<'a> {
+    type Item = RsvpObject<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let hdr = RsvpObjHdr::from_bytes(self.data)?;
+        let len = hdr.length.to_bits() as usize;
+        let Some(contents) = self.data.get(RsvpObjHdr::LEN..len) else {
+            self.data = &[];
+            return None;
+        };
+        self.data = &self.data[len..];
+        Some(RsvpObject { hdr, contents })
+    }
+}
+
+/// Reads the IPv4 or IPv6 address at the start of `bytes`.
+fn address(bytes: &[u8], v6: bool) -> Option<(IpAddr, &[u8])> {
+    if v6 {
+        let a: [u8; 16] = bytes.get(..16)?.try_into().ok()?;
+        Some((IpAddr::V6(Ipv6Addr::from(a)), &bytes[16..]))
+    } else {
+        let a: [u8; 4] = bytes.get(..4)?.try_into().ok()?;
+        Some((IpAddr::V4(Ipv4Addr::from(a)), &bytes[4..]))
+    }
+}
+
+#[inline]
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+#[inline]
+fn f32_at(bytes: &[u8], offset: usize) -> f32 {
+    f32::from_bits(u32_at(bytes, offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use core::net::{IpAddr, Ipv4Addr};
+
+    use super::{verify_checksum, RsvpHdr, RsvpMsgType, RsvpSenderTemplate, RsvpSession};
+    use crate::checksum;
+
+    #[test]
+    fn test_path_message() {
+        let mut msg = [
+            0x10, 1, 0, 0, 64, 0, 0, 84, // Path
+            0, 16, 1, 7, 192, 0, 2, 9, 0, 0, 0, 1, 10, 0, 0, 1, // Session
+            0, 12, 11, 7, 10, 0, 0, 1, 0, 0, 0, 5, // Sender Template
+            0, 36, 12, 2, 0, 0, 0, 7, 1, 0, 0, 6, // Sender TSpec
+            127, 0, 0, 5, 0x49, 0x74, 0x24, 0, 0x45, 0x7a, 0, 0, 0x7f, 0x80, 0, 0, 0, 0, 0, 0, 0,
+            0, 5, 0xdc, // Unknown object
+            0, 4, 99, 1,
+        ];
+        let len = msg.len() as u8;
+        msg[7] = len;
+        let check = checksum::checksum(&msg);
+        msg[2..4].copy_from_slice(&check.to_be_bytes());
+        assert!(verify_checksum(&msg));
+
+        let (hdr, mut objects) = RsvpHdr::parse(&msg).unwrap();
+        assert_eq!(hdr.version(), 1);
+        assert_eq!(hdr.msg_type(), Some(RsvpMsgType::Path));
+
+        assert_eq!(
+            objects.next().unwrap().session(),
+            Some(RsvpSession::LspTunnel {
+                end_point: IpAddr::V4(Ipv4Addr::new(192, 0, 2, 9)),
+                tunnel_id: 1,
+                ext_tunnel_id: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+            })
+        );
+        assert_eq!(
+            objects.next().unwrap().sender_template(),
+            Some(RsvpSenderTemplate::LspTunnel {
+                sender: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+                lsp_id: 5,
+            })
+        );
+        let tspec = objects.next().unwrap().flow_spec().unwrap();
+        let bucket = tspec.token_bucket.unwrap();
+        assert_eq!(bucket.rate, 1_000_000.0);
+        assert_eq!(bucket.bucket_size, 4000.0);
+        assert_eq!(bucket.max_packet_size, 1500);
+        assert_eq!(objects.next().unwrap().hdr.class(), None);
+        assert!(objects.next().is_none());
+    }
+}
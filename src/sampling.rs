@@ -0,0 +1,175 @@
+//! Packet samplers, selecting a reproducible subset of the packets.
+//!
+//! [`CountSampler`] selects every N-th packet, [`RandomSampler`] selects
+//! packets with probability 1/N from a seeded generator, and
+//! [`FlowSampler`] selects all the packets of 1/N of the flows, according
+//! to the keyed hash of their [`FlowKey`].
+
+use crate::flow::FlowKey;
+
+/// Decides whether packets are sampled.
+pub trait Sampler {
+    /// Whether the packet of flow `key` is selected.
+    fn sample(&mut self, key: &FlowKey) -> bool;
+}
+
+/// Deterministic 1-in-N sampler, selecting the first packet then every
+/// N-th one.
+#[derive(Debug, Clone)]
+pub struct CountSampler {
+    rate: u32,
+    skip: u32,
+}
+
+impl CountSampler {
+    /// Selects 1 packet in `rate`, none if `rate` is 0.
+    #[inline]
+    pub const fn new(rate: u32) -> Self {
+        Self { rate, skip: 0 }
+    }
+}
+
+impl Sampler for CountSampler {
+    #[inline]
+    fn sample(&mut self, _key: &FlowKey) -> bool {
+        if self.rate == 0 {
+            return false;
+        }
+        if self.skip > 0 {
+            self.skip -= 1;
+            return false;
+        }
+        self.skip = self.rate - 1;
+        true
+    }
+}
+
+/// Probabilistic sampler, selecting each packet with probability 1/N.
+///
+/// The random numbers come from a xorshift64* generator, so that two
+/// samplers with the same seed select the same packets.
+#[derive(Debug, Clone)]
+pub struct RandomSampler {
+    threshold: u32,
+    state: u64,
+}
+
+impl RandomSampler {
+    /// Selects packets with probability 1/`rate`, none if `rate` is 0.
+    #[inline]
+    pub const fn new(rate: u32, seed: u64) -> Self {
+        Self {
+            threshold: threshold(rate),
+            // xorshift never leaves the all-zero state.
+            state: if seed == 0 { 0x9e3779b97f4a7c15 } else { seed },
+        }
+    }
+
+    #[inline]
+    fn next_u32(&mut self) -> u32 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        (self.state.wrapping_mul(0x2545f4914f6cdd1d) >> 32) as u32
+    }
+}
+
+impl Sampler for RandomSampler {
+    #[inline]
+    fn sample(&mut self, _key: &FlowKey) -> bool {
+        self.threshold != 0 && self.next_u32() <= self.threshold
+    }
+}
+
+/// Flow-consistent sampler, selecting all or none of the packets of a flow.
+///
+/// A flow is selected if the top 32 bits of its keyed hash fall in the
+/// lowest 1/N of the range, so that samplers sharing the key, e.g. on
+/// several capture points, select the same flows. The two directions of a
+/// connection are generally not selected together.
+#[derive(Debug, Clone)]
+pub struct FlowSampler {
+    threshold: u32,
+    key: [u64; 2],
+}
+
+impl FlowSampler {
+    /// Selects 1 flow in `rate`, none if `rate` is 0.
+    #[inline]
+    pub const fn new(rate: u32, key: [u64; 2]) -> Self {
+        Self {
+            threshold: threshold(rate),
+            key,
+        }
+    }
+
+    /// Whether the packets of flow `key` are selected.
+    #[inline]
+    pub fn selects(&self, key: &FlowKey) -> bool {
+        self.threshold != 0 && (key.keyed_hash(&self.key) >> 32) as u32 <= self.threshold
+    }
+}
+
+impl Sampler for FlowSampler {
+    #[inline]
+    fn sample(&mut self, key: &FlowKey) -> bool {
+        self.selects(key)
+    }
+}
+
+/// Largest 32-bit value selected with a probability of 1/`rate`, 0 meaning
+/// that nothing is selected.
+#[inline]
+const fn threshold(rate: u32) -> u32 {
+    match rate {
+        0 => 0,
+        1 => u32::MAX,
+        rate => u32::MAX / rate,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::net::Ipv4Addr;
+
+    use super::{CountSampler, FlowSampler, RandomSampler, Sampler};
+    use crate::{flow::FlowKey, ip::IpProto};
+
+    #[test]
+    fn test_samplers() {
+        let mut key = FlowKey {
+            src_addr: Ipv4Addr::new(10, 0, 0, 1).into(),
+            dst_addr: Ipv4Addr::new(10, 0, 0, 2).into(),
+            proto: IpProto::Udp,
+            src_port: 0,
+            dst_port: 53,
+        };
+
+        let mut count = CountSampler::new(3);
+        let selected: [bool; 6] = core::array::from_fn(|_| count.sample(&key));
+        assert_eq!(selected, [true, false, false, true, false, false]);
+        assert!(!CountSampler::new(0).sample(&key));
+
+        let mut a = RandomSampler::new(4, 42);
+        let mut b = RandomSampler::new(4, 42);
+        let hits = (0..4000)
+            .filter(|_| {
+                let hit = a.sample(&key);
+                assert_eq!(hit, b.sample(&key));
+                hit
+            })
+            .count();
+        assert!((800..1200).contains(&hits), "{hits}");
+
+        let mut flows = FlowSampler::new(8, [1, 2]);
+        let mut hits = 0;
+        for port in 0..4000 {
+            key.src_port = port;
+            let hit = flows.sample(&key);
+            assert_eq!(hit, flows.sample(&key));
+            hits += hit as usize;
+        }
+        assert!((350..650).contains(&hits), "{hits}");
+        assert!(FlowSampler::new(1, [1, 2]).selects(&key));
+    }
+}
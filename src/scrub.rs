@@ -0,0 +1,715 @@
+//! Packet normalization in the spirit of pf's `scrub`, removing the
+//! ambiguities an attacker could use to evade an IDS: reserved bits, invalid
+//! TCP flag combinations, low TTLs, spurious ECN codepoints and overlapping
+//! fragments.
+//!
+//! [`Scrubber::scrub`] works in place on IPv4 and IPv6 packets, fixing up
+//! the checksums of what it modifies.
+
+use core::net::IpAddr;
+
+use crate::{
+    checksum,
+    header::Header,
+    ip::{IpProto, Ipv4Hdr, Ipv6Hdr},
+};
+
+const TCP_FIN: u8 = 0x01;
+const TCP_SYN: u8 = 0x02;
+const TCP_RST: u8 = 0x04;
+const TCP_PSH: u8 = 0x08;
+const TCP_ACK: u8 = 0x10;
+const TCP_URG: u8 = 0x20;
+
+/// Maximum number of disjoint byte ranges tracked per datagram, contiguous
+/// fragments being merged into a single range.
+const MAX_FRAGMENTS: usize = 16;
+
+/// Handling of a fragment overlapping data already received for its
+/// datagram.
+///
+/// The scrubber forwards fragments without buffering them, so only
+/// [`OverlapPolicy::Drop`] guarantees that the receiver cannot reassemble
+/// the datagram differently from an IDS watching the same traffic.
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum OverlapPolicy {
+    /// Drops the fragment and every later fragment of the datagram, so that
+    /// it is never reassembled.
+    #[default]
+    Drop,
+    /// Drops the fragment, the data received first standing.
+    FirstWins,
+    /// Passes the fragment, whose data supersedes the overlapped ranges.
+    LastWins,
+}
+
+/// Normalizations applied by a [`Scrubber`].
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub struct ScrubConfig {
+    /// TTL or hop limit below which packets are raised to it, 0 to disable.
+    pub min_ttl: u8,
+    /// Clears the IPv4 Don't Fragment flag.
+    pub clear_df: bool,
+    /// Drops or fixes invalid TCP flag combinations and clears the TCP
+    /// reserved bits and unused urgent pointers.
+    pub tcp_flags: bool,
+    /// Resets the ECN codepoint of TCP segments which must not be
+    /// ECN-capable, i.e. SYN, RST and pure ACK segments
+    /// ([RFC 3168, section 6.1](https://datatracker.ietf.org/doc/html/rfc3168#section-6.1)).
+    pub ecn: bool,
+    /// Drops fragments overlapping a previous fragment of the same datagram,
+    /// as well as TCP fragments splitting or overwriting the TCP header
+    /// ([RFC 1858](https://datatracker.ietf.org/doc/html/rfc1858)).
+    pub fragments: bool,
+    /// Handling of overlapping fragments, when `fragments` is set.
+    pub overlap: OverlapPolicy,
+    /// Drops IPv6 atomic fragments, i.e. with an offset of 0 and the M flag
+    /// clear, which are otherwise processed as unfragmented packets
+    /// ([RFC 6946](https://datatracker.ietf.org/doc/html/rfc6946)).
+    pub drop_atomic_fragments: bool,
+}
+
+impl Default for ScrubConfig {
+    fn default() -> Self {
+        Self {
+            min_ttl: 0,
+            clear_df: false,
+            tcp_flags: true,
+            ecn: true,
+            fragments: true,
+            overlap: OverlapPolicy::Drop,
+            drop_atomic_fragments: false,
+        }
+    }
+}
+
+/// Why a packet was dropped.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum DropReason {
+    /// The packet is truncated or its headers are inconsistent.
+    Malformed,
+    /// Invalid TCP flag combination, such as SYN+RST or no flag at all.
+    TcpFlags,
+    /// The fragment overlaps data of a previous fragment.
+    OverlappingFragment,
+    /// The first fragment of a TCP segment does not hold the whole TCP
+    /// header.
+    TinyFragment,
+    /// The datagram has more holes than can be tracked.
+    TooManyFragments,
+    /// IPv6 atomic fragment, with [`ScrubConfig::drop_atomic_fragments`].
+    AtomicFragment,
+}
+
+/// Fragment statistics of a [`Scrubber`].
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct ScrubCounters {
+    /// IPv6 atomic fragments.
+    pub atomic_fragments: u64,
+    /// Fragments overlapping data already received for their datagram.
+    pub overlapping_fragments: u64,
+    /// Datagrams whose remaining fragments are dropped, because of an
+    /// overlap under [`OverlapPolicy::Drop`], of inconsistent lengths or of
+    /// too many holes.
+    pub dropped_datagrams: u64,
+}
+
+/// Outcome of [`Scrubber::scrub`].
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum Verdict {
+    Pass,
+    /// The packet was normalized in place.
+    Modified,
+    Drop(DropReason),
+}
+
+/// Identifies the datagram a fragment belongs to.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+struct FragKey {
+    src: IpAddr,
+    dst: IpAddr,
+    id: u32,
+    proto: u8,
+}
+
+/// Byte ranges received so far for a fragmented datagram.
+#[derive(Debug, Copy, Clone)]
+struct FragEntry {
+    key: FragKey,
+    /// Sorted ranges, neither overlapping nor touching each other.
+    ranges: [(u32, u32); MAX_FRAGMENTS],
+    count: usize,
+    received: u32,
+    /// Length of the datagram, known once the last fragment was seen.
+    total: Option<u32>,
+    /// The datagram was dropped for this reason, and so are its remaining
+    /// fragments until the entry is evicted.
+    dropped: Option<DropReason>,
+}
+
+impl FragEntry {
+    /// Adds the range `start..end`, merging it with the ranges it overlaps
+    /// or touches so that only holes take up slots. Returns `false` if it
+    /// needs a slot beyond [`MAX_FRAGMENTS`].
+    fn insert(&mut self, start: u32, end: u32) -> bool {
+        let ranges = &self.ranges[..self.count];
+        // `ranges[first..last]` are merged with the new range.
+        let first = ranges.partition_point(|&(_, e)| e < start);
+        let last = ranges.partition_point(|&(s, _)| s <= end);
+        if first == last {
+            if self.count == MAX_FRAGMENTS {
+                return false;
+            }
+            self.ranges.copy_within(first..self.count, first + 1);
+            self.ranges[first] = (start, end);
+            self.count += 1;
+        } else {
+            let merged = (
+                start.min(self.ranges[first].0),
+                end.max(self.ranges[last - 1].1),
+            );
+            self.ranges.copy_within(last..self.count, first + 1);
+            self.ranges[first] = merged;
+            self.count -= last - first - 1;
+        }
+        true
+    }
+}
+
+/// Position of a fragment within its datagram.
+struct Fragment {
+    key: FragKey,
+    offset: u32,
+    len: u32,
+    more: bool,
+}
+
+/// Stateful packet normalizer, tracking up to `N` fragmented datagrams at
+/// a time. When the table is full, the oldest datagram is forgotten.
+#[derive(Debug, Clone)]
+pub struct Scrubber<const N: usize = 64> {
+    config: ScrubConfig,
+    frags: [Option<FragEntry>; N],
+    next_slot: usize,
+    counters: ScrubCounters,
+}
+
+impl<const N: usize> Scrubber<N> {
+    pub fn new(config: ScrubConfig) -> Self {
+        Self {
+            config,
+            frags: [None; N],
+            next_slot: 0,
+            counters: ScrubCounters::default(),
+        }
+    }
+
+    #[inline]
+    pub fn config(&self) -> &ScrubConfig {
+        &self.config
+    }
+
+    #[inline]
+    pub fn counters(&self) -> &ScrubCounters {
+        &self.counters
+    }
+
+    #[inline]
+    pub fn reset_counters(&mut self) {
+        self.counters = ScrubCounters::default();
+    }
+
+    /// Normalizes the IPv4 or IPv6 packet stored in `packet`.
+    pub fn scrub(&mut self, packet: &mut [u8]) -> Verdict {
+        let res = match packet.first().map(|b| b >> 4) {
+            Some(4) => self.scrub_v4(packet),
+            Some(6) => self.scrub_v6(packet),
+            _ => Err(DropReason::Malformed),
+        };
+        match res {
+            Ok(true) => Verdict::Modified,
+            Ok(false) => Verdict::Pass,
+            Err(reason) => Verdict::Drop(reason),
+        }
+    }
+
+    fn scrub_v4(&mut self, packet: &mut [u8]) -> Result<bool, DropReason> {
+        let hdr = Ipv4Hdr::from_bytes(packet).ok_or(DropReason::Malformed)?;
+        let (src, dst, id) = (hdr.src_addr, hdr.dst_addr, hdr.id.to_bits());
+        let hdrlen = hdr.hdrlen();
+        let tot_len = hdr.tot_len.to_bits() as usize;
+        if hdrlen < Ipv4Hdr::LEN || tot_len < hdrlen || tot_len > packet.len() {
+            return Err(DropReason::Malformed);
+        }
+        let packet = &mut packet[..tot_len];
+        let frag_off = u16::from_be_bytes([packet[6], packet[7]]);
+        let proto = packet[9];
+        let offset = (frag_off & 0x1fff) as u32 * 8;
+        let more = frag_off & 0x2000 != 0;
+
+        let mut modified = false;
+        let mut new_frag_off = frag_off & !0x8000; // Reserved flag.
+        if self.config.clear_df {
+            new_frag_off &= !0x4000;
+        }
+        if new_frag_off != frag_off {
+            packet[6..8].copy_from_slice(&new_frag_off.to_be_bytes());
+            modified = true;
+        }
+        if packet[8] < self.config.min_ttl {
+            packet[8] = self.config.min_ttl;
+            modified = true;
+        }
+
+        if offset != 0 || more {
+            let frag = Fragment {
+                key: FragKey {
+                    src: src.into(),
+                    dst: dst.into(),
+                    id: id as u32,
+                    proto,
+                },
+                offset,
+                len: (tot_len - hdrlen) as u32,
+                more,
+            };
+            self.check_fragment(frag)?;
+        }
+
+        let (ip, l4) = packet.split_at_mut(hdrlen);
+        if offset == 0 && proto == IpProto::Tcp as u8 {
+            let (tcp_modified, not_ect) = self.scrub_tcp(l4, more)?;
+            modified |= tcp_modified;
+            if not_ect && ip[1] & 0x3 != 0 {
+                ip[1] &= !0x3;
+                modified = true;
+            }
+        }
+
+        if modified {
+            ip[10..12].fill(0);
+            let check = checksum::checksum(ip);
+            ip[10..12].copy_from_slice(&check.to_be_bytes());
+        }
+        Ok(modified)
+    }
+
+    fn scrub_v6(&mut self, packet: &mut [u8]) -> Result<bool, DropReason> {
+        let hdr = Ipv6Hdr::from_bytes(packet).ok_or(DropReason::Malformed)?;
+        let len = Ipv6Hdr::LEN + hdr.payload_len.to_bits() as usize;
+        if len > packet.len() {
+            return Err(DropReason::Malformed);
+        }
+        let (src, dst) = (hdr.src_addr, hdr.dst_addr);
+        let packet = &mut packet[..len];
+
+        let mut modified = false;
+        if packet[7] < self.config.min_ttl {
+            packet[7] = self.config.min_ttl;
+            modified = true;
+        }
+
+        // Walk the extension headers up to the upper-layer header.
+        let mut next_hdr = packet[6];
+        let mut pos = Ipv6Hdr::LEN;
+        let mut first_fragment = true;
+        let mut fragmented = false;
+        loop {
+            let is_ext = matches!(
+                IpProto::from_u8(next_hdr),
+                Some(IpProto::HopOpt | IpProto::Ipv6Route | IpProto::Ipv6Opts | IpProto::Ipv6Frag)
+            );
+            if !is_ext {
+                break;
+            }
+            let ext = packet.get(pos..pos + 8).ok_or(DropReason::Malformed)?;
+            let ext_len = match IpProto::from_u8(next_hdr) {
+                Some(IpProto::Ipv6Frag) => {
+                    let frag_off = u16::from_be_bytes([ext[2], ext[3]]);
+                    let offset = (frag_off & 0xfff8) as u32;
+                    let more = frag_off & 0x1 != 0;
+                    first_fragment = offset == 0;
+                    fragmented = more;
+                    if offset == 0 && !more {
+                        // Atomic fragments are never reassembled with other
+                        // fragments of the same identification.
+                        self.counters.atomic_fragments += 1;
+                        if self.config.drop_atomic_fragments {
+                            return Err(DropReason::AtomicFragment);
+                        }
+                    } else {
+                        let frag = Fragment {
+                            key: FragKey {
+                                src: src.into(),
+                                dst: dst.into(),
+                                id: u32::from_be_bytes([ext[4], ext[5], ext[6], ext[7]]),
+                                proto: ext[0],
+                            },
+                            offset,
+                            len: (len - pos - 8) as u32,
+                            more,
+                        };
+                        self.check_fragment(frag)?;
+                    }
+                    8
+                }
+                _ => (ext[1] as usize + 1) * 8,
+            };
+            next_hdr = ext[0];
+            pos += ext_len;
+            if pos > len {
+                return Err(DropReason::Malformed);
+            }
+        }
+
+        let (ip, l4) = packet.split_at_mut(pos);
+        if first_fragment && next_hdr == IpProto::Tcp as u8 {
+            let (tcp_modified, not_ect) = self.scrub_tcp(l4, fragmented)?;
+            modified |= tcp_modified;
+            if not_ect && ip[1] & 0x30 != 0 {
+                ip[1] &= !0x30;
+                modified = true;
+            }
+        }
+        Ok(modified)
+    }
+
+    /// Normalizes the TCP segment `tcp`, returning whether it was modified
+    /// and whether it must not be ECN-capable. `fragmented` is set when
+    /// `tcp` is a first fragment, whose payload is incomplete.
+    fn scrub_tcp(&self, tcp: &mut [u8], fragmented: bool) -> Result<(bool, bool), DropReason> {
+        if tcp.len() < 20 {
+            // The TCP header must be in the first fragment.
+            return Err(if self.config.fragments && fragmented {
+                DropReason::TinyFragment
+            } else {
+                DropReason::Malformed
+            });
+        }
+        let mut modified = false;
+        let flags = tcp[13];
+        if self.config.tcp_flags {
+            let mut new = flags;
+            if flags & TCP_SYN != 0 {
+                if flags & TCP_RST != 0 {
+                    return Err(DropReason::TcpFlags);
+                }
+                new &= !TCP_FIN;
+            } else if flags & (TCP_ACK | TCP_RST) == 0 {
+                return Err(DropReason::TcpFlags);
+            }
+            if new & TCP_ACK == 0 {
+                new &= !(TCP_FIN | TCP_PSH | TCP_URG);
+            }
+            let old_word = u16::from_be_bytes([tcp[12], flags]);
+            let new_word = u16::from_be_bytes([tcp[12] & 0xf0, new]);
+            if new_word != old_word {
+                update_tcp_word(tcp, 12, new_word);
+                modified = true;
+            }
+            if new & TCP_URG == 0 && tcp[18..20] != [0, 0] {
+                update_tcp_word(tcp, 18, 0);
+                modified = true;
+            }
+        }
+
+        let doff = (tcp[12] >> 4) as usize * 4;
+        let pure_ack = flags & !TCP_PSH == TCP_ACK && !fragmented && tcp.len() <= doff;
+        let not_ect = self.config.ecn && (flags & (TCP_SYN | TCP_RST) != 0 || pure_ack);
+        Ok((modified, not_ect))
+    }
+
+    /// Records a fragment, failing if it overlaps a previous fragment of the
+    /// same datagram and the overlap policy drops it.
+    fn check_fragment(&mut self, frag: Fragment) -> Result<(), DropReason> {
+        if !self.config.fragments || N == 0 {
+            return Ok(());
+        }
+        // RFC 1858: a fragment offset of 8 bytes rewrites the TCP flags.
+        if frag.key.proto == IpProto::Tcp as u8 && frag.offset == 8 {
+            self.counters.overlapping_fragments += 1;
+            return Err(DropReason::OverlappingFragment);
+        }
+        let (start, end) = (frag.offset, frag.offset + frag.len);
+        let slot = match self
+            .frags
+            .iter()
+            .position(|e| e.is_some_and(|e| e.key == frag.key))
+        {
+            Some(slot) => slot,
+            None => {
+                let slot = self.next_slot;
+                self.next_slot = (slot + 1) % N;
+                self.frags[slot] = None;
+                slot
+            }
+        };
+        let entry = self.frags[slot].get_or_insert(FragEntry {
+            key: frag.key,
+            ranges: [(0, 0); MAX_FRAGMENTS],
+            count: 0,
+            received: 0,
+            total: None,
+            dropped: None,
+        });
+        if let Some(reason) = entry.dropped {
+            return Err(reason);
+        }
+        let ranges = &entry.ranges[..entry.count];
+        let overlaps = ranges.iter().any(|&(s, e)| start < e && s < end);
+        // Data past the end of the datagram, or a last fragment ending before
+        // data already received.
+        let inconsistent = entry.total.is_some_and(|total| end > total)
+            || (!frag.more && ranges.iter().any(|&(_, e)| e > end));
+        if overlaps {
+            self.counters.overlapping_fragments += 1;
+        }
+        if inconsistent || (overlaps && self.config.overlap == OverlapPolicy::Drop) {
+            entry.dropped = Some(DropReason::OverlappingFragment);
+            self.counters.dropped_datagrams += 1;
+            return Err(DropReason::OverlappingFragment);
+        }
+        if overlaps && self.config.overlap == OverlapPolicy::FirstWins {
+            return Err(DropReason::OverlappingFragment);
+        }
+        // Under `LastWins`, the fragment supersedes the data it overlaps,
+        // the datagram covering the union of both.
+        if !entry.insert(start, end) {
+            // Forgetting the datagram would let its later fragments through
+            // the overlap checks.
+            entry.dropped = Some(DropReason::TooManyFragments);
+            self.counters.dropped_datagrams += 1;
+            return Err(DropReason::TooManyFragments);
+        }
+        entry.received = entry.ranges[..entry.count]
+            .iter()
+            .map(|&(s, e)| e - s)
+            .sum();
+        if !frag.more {
+            entry.total = Some(end);
+        }
+        if entry.total == Some(entry.received) {
+            // Complete, the datagram no longer needs to be tracked.
+            self.frags[slot] = None;
+        }
+        Ok(())
+    }
+}
+
+/// Overwrites the 16-bit word at `offset` of a TCP segment, updating its
+/// checksum incrementally.
+fn update_tcp_word(tcp: &mut [u8], offset: usize, new: u16) {
+    let old = u16::from_be_bytes([tcp[offset], tcp[offset + 1]]);
+    let check = u16::from_be_bytes([tcp[16], tcp[17]]);
+    tcp[offset..offset + 2].copy_from_slice(&new.to_be_bytes());
+    tcp[16..18].copy_from_slice(&checksum::update(check, old, new).to_be_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DropReason, OverlapPolicy, ScrubConfig, Scrubber, Verdict};
+    use crate::{checksum, ip::IpProto};
+
+    fn ipv4(proto: u8, frag_off: u16, id: u16, payload: &[u8], buf: &mut [u8]) -> usize {
+        let len = 20 + payload.len();
+        buf[..20].copy_from_slice(&[
+            0x45, 0, 0, 0, 0, 0, 0, 0, 1, proto, 0, 0, 10, 0, 0, 1, 10, 0, 0, 2,
+        ]);
+        buf[2..4].copy_from_slice(&(len as u16).to_be_bytes());
+        buf[4..6].copy_from_slice(&id.to_be_bytes());
+        buf[6..8].copy_from_slice(&frag_off.to_be_bytes());
+        buf[20..len].copy_from_slice(payload);
+        len
+    }
+
+    fn tcp_checksum(packet: &[u8]) -> u16 {
+        let len = packet.len() as u16 - 20;
+        let pseudo = checksum::pseudo_header_v4(
+            [10, 0, 0, 1].into(),
+            [10, 0, 0, 2].into(),
+            IpProto::Tcp,
+            len,
+        );
+        checksum::fold(checksum::sum(&packet[20..], pseudo))
+    }
+
+    #[test]
+    fn test_scrub() {
+        let config = ScrubConfig {
+            min_ttl: 32,
+            ..ScrubConfig::default()
+        };
+        let mut scrubber = Scrubber::<4>::new(config);
+        let mut buf = [0u8; 64];
+
+        // SYN+FIN with a reserved bit, ECT(0) and the evil bit.
+        let mut tcp = [0u8; 20];
+        tcp[12] = 0x51;
+        tcp[13] = 0x03;
+        let len = ipv4(6, 0x8000, 1, &tcp, &mut buf);
+        buf[1] = 0x02;
+        let check = tcp_checksum(&buf[..len]);
+        buf[36..38].copy_from_slice(&check.to_be_bytes());
+        assert_eq!(scrubber.scrub(&mut buf[..len]), Verdict::Modified);
+        assert_eq!(buf[1], 0);
+        assert_eq!(&buf[6..9], &[0, 0, 32]);
+        assert_eq!(&buf[32..34], &[0x50, 0x02]);
+        assert_eq!(checksum::checksum(&buf[..20]), 0);
+        assert_eq!(tcp_checksum(&buf[..len]), 0);
+        assert_eq!(scrubber.scrub(&mut buf[..len]), Verdict::Pass);
+
+        // Null scan.
+        tcp[13] = 0;
+        let len = ipv4(6, 0, 2, &tcp, &mut buf);
+        assert_eq!(
+            scrubber.scrub(&mut buf[..len]),
+            Verdict::Drop(DropReason::TcpFlags)
+        );
+
+        // Overlapping UDP fragments.
+        let len = ipv4(17, 0x2000, 3, &[0; 16], &mut buf);
+        assert_eq!(scrubber.scrub(&mut buf[..len]), Verdict::Modified);
+        let len = ipv4(17, 0x0001, 3, &[0; 16], &mut buf);
+        assert_eq!(
+            scrubber.scrub(&mut buf[..len]),
+            Verdict::Drop(DropReason::OverlappingFragment)
+        );
+        // The rest of the datagram is dropped too.
+        let len = ipv4(17, 0x0004, 3, &[0; 8], &mut buf);
+        assert_eq!(
+            scrubber.scrub(&mut buf[..len]),
+            Verdict::Drop(DropReason::OverlappingFragment)
+        );
+        let len = ipv4(17, 0x2000, 4, &[0; 16], &mut buf);
+        scrubber.scrub(&mut buf[..len]);
+        let len = ipv4(17, 0x0002, 4, &[0; 8], &mut buf);
+        assert_eq!(scrubber.scrub(&mut buf[..len]), Verdict::Modified);
+        assert_eq!(scrubber.counters().overlapping_fragments, 1);
+        assert_eq!(scrubber.counters().dropped_datagrams, 1);
+
+        // First TCP fragment too small to hold the TCP header.
+        let len = ipv4(6, 0x2000, 5, &tcp[..8], &mut buf);
+        assert_eq!(
+            scrubber.scrub(&mut buf[..len]),
+            Verdict::Drop(DropReason::TinyFragment)
+        );
+
+        // First wins: the overlapping fragment is dropped, not the datagram.
+        let config = ScrubConfig {
+            overlap: OverlapPolicy::FirstWins,
+            ..ScrubConfig::default()
+        };
+        let mut scrubber = Scrubber::<4>::new(config);
+        let len = ipv4(17, 0x2000, 6, &[0; 16], &mut buf);
+        assert_eq!(scrubber.scrub(&mut buf[..len]), Verdict::Pass);
+        let len = ipv4(17, 0x2001, 6, &[0; 16], &mut buf);
+        assert_eq!(
+            scrubber.scrub(&mut buf[..len]),
+            Verdict::Drop(DropReason::OverlappingFragment)
+        );
+        let len = ipv4(17, 0x0002, 6, &[0; 8], &mut buf);
+        assert_eq!(scrubber.scrub(&mut buf[..len]), Verdict::Pass);
+
+        // Last wins: the overlapping fragment passes and completes the
+        // datagram with the first half of the first fragment.
+        let config = ScrubConfig {
+            overlap: OverlapPolicy::LastWins,
+            ..ScrubConfig::default()
+        };
+        let mut scrubber = Scrubber::<4>::new(config);
+        let len = ipv4(17, 0x2000, 7, &[0; 16], &mut buf);
+        scrubber.scrub(&mut buf[..len]);
+        let len = ipv4(17, 0x0001, 7, &[0; 16], &mut buf);
+        assert_eq!(scrubber.scrub(&mut buf[..len]), Verdict::Pass);
+        assert_eq!(scrubber.counters().overlapping_fragments, 1);
+        assert!(scrubber.frags.iter().all(Option::is_none));
+
+        // IPv6 atomic fragment.
+        let mut v6 = [0u8; 56];
+        v6[0] = 0x60;
+        v6[4..8].copy_from_slice(&[0, 16, 44, 64]);
+        v6[40] = IpProto::Udp as u8;
+        assert_eq!(scrubber.scrub(&mut v6), Verdict::Pass);
+        assert_eq!(scrubber.counters().atomic_fragments, 1);
+        let mut scrubber = Scrubber::<4>::new(ScrubConfig {
+            drop_atomic_fragments: true,
+            ..ScrubConfig::default()
+        });
+        assert_eq!(
+            scrubber.scrub(&mut v6),
+            Verdict::Drop(DropReason::AtomicFragment)
+        );
+    }
+
+    #[test]
+    fn test_fragment_ranges() {
+        let mut scrubber = Scrubber::<4>::new(ScrubConfig::default());
+        let mut buf = [0u8; 1500];
+
+        // Contiguous fragments are merged, however many there are.
+        for i in 0..20u16 {
+            let more = if i < 19 { 0x2000 } else { 0 };
+            let len = ipv4(17, more | (i * 185), 1, &[0; 1480], &mut buf);
+            assert_eq!(scrubber.scrub(&mut buf[..len]), Verdict::Pass);
+        }
+        assert!(scrubber.frags.iter().all(Option::is_none));
+
+        // Every other fragment leaves a hole, until there are too many.
+        for i in 0..17u16 {
+            let len = ipv4(17, 0x2000 | (i * 2), 2, &[0; 8], &mut buf);
+            let verdict = scrubber.scrub(&mut buf[..len]);
+            if i < 16 {
+                assert_eq!(verdict, Verdict::Pass);
+            } else {
+                assert_eq!(verdict, Verdict::Drop(DropReason::TooManyFragments));
+            }
+        }
+        // The datagram is still tracked, and the overlap is not let through.
+        let len = ipv4(17, 0x2000, 2, &[0; 16], &mut buf);
+        assert_eq!(
+            scrubber.scrub(&mut buf[..len]),
+            Verdict::Drop(DropReason::TooManyFragments)
+        );
+        assert_eq!(scrubber.counters().dropped_datagrams, 1);
+    }
+    #[test]
+    fn test_fragment_ranges_last_wins() {
+        let config = ScrubConfig {
+            overlap: OverlapPolicy::LastWins,
+            ..ScrubConfig::default()
+        };
+        let mut scrubber = Scrubber::<4>::new(config);
+        let mut buf = [0u8; 64];
+
+        // Each fragment overlaps the previous one, the ranges being merged.
+        for i in 0..20u16 {
+            let more = if i < 19 { 0x2000 } else { 0 };
+            let len = ipv4(17, more | (i * 2), 1, &[0; 24], &mut buf);
+            assert_eq!(scrubber.scrub(&mut buf[..len]), Verdict::Pass);
+        }
+        assert_eq!(scrubber.counters().overlapping_fragments, 19);
+        assert!(scrubber.frags.iter().all(Option::is_none));
+
+        // A fragment overlapping a range splits nothing, and a datagram with
+        // too many holes stays dropped rather than passing overlaps.
+        for i in 0..17u16 {
+            let len = ipv4(17, 0x2000 | (i * 4), 2, &[0; 16], &mut buf);
+            scrubber.scrub(&mut buf[..len]);
+            let len = ipv4(17, 0x2000 | (i * 4), 2, &[0; 8], &mut buf);
+            let verdict = scrubber.scrub(&mut buf[..len]);
+            if i < 16 {
+                assert_eq!(verdict, Verdict::Pass);
+            } else {
+                assert_eq!(verdict, Verdict::Drop(DropReason::TooManyFragments));
+            }
+        }
+        let len = ipv4(17, 0x2000, 2, &[0; 8], &mut buf);
+        assert_eq!(
+            scrubber.scrub(&mut buf[..len]),
+            Verdict::Drop(DropReason::TooManyFragments)
+        );
+        assert_eq!(scrubber.counters().dropped_datagrams, 1);
+    }
+}
@@ -0,0 +1,236 @@
+//! Stream Control Transmission Protocol
+//! ([RFC 9260](https://datatracker.ietf.org/doc/html/rfc9260)).
+
+use core::mem;
+
+use crate::{
+    header::impl_header,
+    types::{U16, U32},
+};
+
+/// SCTP common header, followed by the chunks.
+/// ```text
+/// +-------------------------------+-------------------------------+
+/// |     Source Port Number        |     Destination Port Number   |
+/// +-------------------------------+-------------------------------+
+/// |                      Verification Tag                         |
+/// +---------------------------------------------------------------+
+/// |                           Checksum                            |
+/// +---------------------------------------------------------------+
+/// ```
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct SctpHdr {
+    pub src_port: U16,
+    pub dst_port: U16,
+    pub verification_tag: U32,
+    /// CRC32c of the packet.
+    pub checksum: U32,
+}
+
+impl SctpHdr {
+    pub const LEN: usize = mem::size_of::<SctpHdr>();
+}
+
+impl_header!(SctpHdr);
+
+/// Table of the reflected CRC-32C (Castagnoli) polynomial.
+const CRC32C_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0x82f6_3b78
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+#[inline]
+fn crc32c_update(crc: u32, data: &[u8]) -> u32 {
+    data.iter().fold(crc, |crc, &b| {
+        (crc >> 8) ^ CRC32C_TABLE[((crc ^ b as u32) & 0xff) as usize]
+    })
+}
+
+/// CRC-32C of `data`.
+#[inline]
+pub fn crc32c(data: &[u8]) -> u32 {
+    !crc32c_update(!0, data)
+}
+
+/// Computes the checksum of the SCTP `packet`, common header included, its
+/// checksum field being taken as zero. The checksum is returned in the
+/// byte order of the wire, which is the little-endian order of the CRC
+/// ([RFC 9260, appendix A](https://datatracker.ietf.org/doc/html/rfc9260#appendix-A)).
+///
+/// Returns `None` if `packet` is shorter than [`SctpHdr::LEN`].
+pub fn checksum(packet: &[u8]) -> Option<[u8; 4]> {
+    let (hdr, chunks) = packet.split_at_checked(SctpHdr::LEN)?;
+    let crc = crc32c_update(!0, &hdr[..8]);
+    let crc = crc32c_update(crc, &[0; 4]);
+    Some((!crc32c_update(crc, chunks)).to_le_bytes())
+}
+
+/// SCTP chunk types.
+#[repr(u8)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum ChunkType {
+    Data = 0,
+    Init = 1,
+    InitAck = 2,
+    Sack = 3,
+    Heartbeat = 4,
+    HeartbeatAck = 5,
+    Abort = 6,
+    Shutdown = 7,
+    ShutdownAck = 8,
+    OperationError = 9,
+    CookieEcho = 10,
+    CookieAck = 11,
+    ShutdownComplete = 14,
+}
+
+impl TryFrom<u8> for ChunkType {
+    type Error = ();
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(ChunkType::Data),
+            1 => Ok(ChunkType::Init),
+            2 => Ok(ChunkType::InitAck),
+            3 => Ok(ChunkType::Sack),
+            4 => Ok(ChunkType::Heartbeat),
+            5 => Ok(ChunkType::HeartbeatAck),
+            6 => Ok(ChunkType::Abort),
+            7 => Ok(ChunkType::Shutdown),
+            8 => Ok(ChunkType::ShutdownAck),
+            9 => Ok(ChunkType::OperationError),
+            10 => Ok(ChunkType::CookieEcho),
+            11 => Ok(ChunkType::CookieAck),
+            14 => Ok(ChunkType::ShutdownComplete),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Header of an SCTP chunk.
+/// ```text
+/// +---------------+---------------+-------------------------------+
+/// |  Chunk Type   |  Chunk Flags  |         Chunk Length          |
+/// +---------------+---------------+-------------------------------+
+/// ```
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct SctpChunkHdr {
+    pub chunk_type: u8,
+    pub flags: u8,
+    /// Length of the chunk including this header, without the padding.
+    pub len: U16,
+}
+
+impl SctpChunkHdr {
+    pub const LEN: usize = mem::size_of::<SctpChunkHdr>();
+
+    #[inline]
+    pub fn chunk_type(&self) -> Option<ChunkType> {
+        self.chunk_type.try_into().ok()
+    }
+}
+
+impl_header!(SctpChunkHdr);
+
+/// A chunk of an SCTP packet.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub struct SctpChunk<'a> {
+    pub chunk_type: u8,
+    pub flags: u8,
+    /// Value of the chunk, without the header and the padding.
+    pub data: &'a [u8],
+}
+
+/// Iterator over the chunks following an SCTP common header.
+#[derive(Debug, Clone)]
+pub struct SctpChunks<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> SctpChunks<'a> {
+    #[inline]
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+}
+
+impl<'a> Iterator for SctpChunks<'a> {
+    type Item = SctpChunk<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let len = match self.data {
+            [_, _, hi, lo, ..] if u16::from_be_bytes([*hi, *lo]) >= 4 => {
+                u16::from_be_bytes([*hi, *lo]) as usize
+            }
+            _ => {
+                self.data = &[];
+                return None;
+            }
+        };
+        let Some(data) = self.data.get(4..len) else {
+            self.data = &[];
+            return None;
+        };
+        let chunk = SctpChunk {
+            chunk_type: self.data[0],
+            flags: self.data[1],
+            data,
+        };
+        // Chunks are padded to a multiple of 4 bytes.
+        self.data = self.data.get(len.next_multiple_of(4)..).unwrap_or(&[]);
+        Some(chunk)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{checksum, crc32c, ChunkType, SctpChunks, SctpHdr};
+    use crate::header::Header;
+
+    #[test]
+    fn test_sctp_chunks() {
+        #[rustfmt::skip]
+        let packet = [
+            0x0b, 0x59, 0x0b, 0x59, 0, 0, 0, 0, 0x12, 0x34, 0x56, 0x78,
+            // INIT with 3 bytes of value, padded.
+            1, 0, 0, 7, 1, 2, 3, 0,
+            // COOKIE ACK.
+            11, 0, 0, 4,
+        ];
+        let hdr = SctpHdr::from_bytes(&packet).unwrap();
+        assert_eq!(hdr.dst_port.to_bits(), 2905);
+        let mut chunks = SctpChunks::new(&packet[SctpHdr::LEN..]);
+        let init = chunks.next().unwrap();
+        assert_eq!(init.chunk_type.try_into(), Ok(ChunkType::Init));
+        assert_eq!(init.data, &[1, 2, 3]);
+        assert_eq!(chunks.next().unwrap().chunk_type, 11);
+        assert_eq!(chunks.next(), None);
+
+        // Test vectors of RFC 3720, appendix B.4.
+        assert_eq!(crc32c(&[0; 32]), 0x8a91_36aa);
+        assert_eq!(crc32c(b"123456789"), 0xe306_9283);
+        let mut zeroed = packet;
+        zeroed[8..12].fill(0);
+        assert_eq!(checksum(&packet), Some(crc32c(&zeroed).to_le_bytes()));
+        assert_eq!(checksum(&packet[..8]), None);
+    }
+}
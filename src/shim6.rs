@@ -0,0 +1,220 @@
+//! Shim6 multihoming protocol ([RFC 5533](https://datatracker.ietf.org/doc/html/rfc5533)),
+//! carried as an IPv6 extension header with [`IpProto::Shim6`](crate::ip::IpProto::Shim6).
+
+use core::mem;
+
+use crate::{checksum, header::impl_header, header::Header, ip::IpProto, types::U16};
+
+/// Shim6 Payload Extension Header, inserted in front of the upper-layer
+/// payload once a Shim6 context has been established.
+/// ```text
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |  Next Header  |  Hdr Ext Len  |1|                             |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+                             +
+/// |                    Receiver Context Tag                       |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct Shim6PayloadHdr {
+    pub next_hdr: u8,
+    /// Always 0, the header is 8 octets long.
+    pub hdr_ext_len: u8,
+    /// The P bit (set) followed by the 47-bit Receiver Context Tag.
+    pub p_context_tag: [u8; 6],
+}
+
+impl Shim6PayloadHdr {
+    pub const LEN: usize = mem::size_of::<Shim6PayloadHdr>();
+
+    #[inline]
+    pub fn next_hdr(&self) -> Option<IpProto> {
+        IpProto::from_u8(self.next_hdr)
+    }
+
+    /// Receiver Context Tag, identifying the Shim6 context at the receiver.
+    #[inline]
+    pub fn context_tag(&self) -> u64 {
+        context_tag(&self.p_context_tag)
+    }
+}
+
+/// Shim6 control message types.
+#[repr(u8)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum Shim6MsgType {
+    I1 = 1,
+    R1 = 2,
+    I2 = 3,
+    R2 = 4,
+    R1bis = 5,
+    I2bis = 6,
+    UpdateRequest = 64,
+    UpdateAck = 65,
+    Keepalive = 66,
+    Probe = 67,
+}
+
+impl TryFrom<u8> for Shim6MsgType {
+    type Error = ();
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(Shim6MsgType::I1),
+            2 => Ok(Shim6MsgType::R1),
+            3 => Ok(Shim6MsgType::I2),
+            4 => Ok(Shim6MsgType::R2),
+            5 => Ok(Shim6MsgType::R1bis),
+            6 => Ok(Shim6MsgType::I2bis),
+            64 => Ok(Shim6MsgType::UpdateRequest),
+            65 => Ok(Shim6MsgType::UpdateAck),
+            66 => Ok(Shim6MsgType::Keepalive),
+            67 => Ok(Shim6MsgType::Probe),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Common header of the Shim6 control messages.
+/// ```text
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |  Next Header  |  Hdr Ext Len  |0|  Type       |Type-specific|S|
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |            Checksum           |                               |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+                               |
+/// |                    Type-specific format                       |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct Shim6ControlHdr {
+    /// Always [`IpProto::Ipv6NoNxt`] for control messages.
+    pub next_hdr: u8,
+    /// Length of the message in 8-octet units, not including the first 8
+    /// octets.
+    pub hdr_ext_len: u8,
+    /// The P bit (clear) followed by the 7-bit message type.
+    pub p_type: u8,
+    /// 7 type-specific bits followed by the S bit, which is always 0 for
+    /// Shim6 (as opposed to HIP).
+    pub type_specific_s: u8,
+    /// Internet checksum over the whole Shim6 message.
+    pub checksum: U16,
+}
+
+impl Shim6ControlHdr {
+    pub const LEN: usize = mem::size_of::<Shim6ControlHdr>();
+
+    #[inline]
+    pub fn msg_type(&self) -> Option<Shim6MsgType> {
+        (self.p_type & 0x7f).try_into().ok()
+    }
+
+    /// Length of the whole message, in octets.
+    #[inline]
+    pub fn msg_len(&self) -> usize {
+        (self.hdr_ext_len as usize + 1) * 8
+    }
+}
+
+impl_header!(Shim6PayloadHdr, Shim6ControlHdr);
+
+/// A Shim6 header, distinguished by its P bit.
+#[derive(Debug, Copy, Clone)]
+pub enum Shim6<'a> {
+    /// Payload Extension Header, followed by the upper-layer payload.
+    Payload(&'a Shim6PayloadHdr),
+    /// Control message with its type-specific body (the octets following the
+    /// checksum, up to the message length).
+    Control(&'a Shim6ControlHdr, &'a [u8]),
+}
+
+impl<'a> Shim6<'a> {
+    /// Parses the Shim6 header at the start of `bytes`.
+    pub fn parse(bytes: &'a [u8]) -> Option<Self> {
+        if bytes.len() < 8 {
+            return None;
+        }
+        if bytes[2] & 0x80 != 0 {
+            return Shim6PayloadHdr::from_bytes(bytes).map(Shim6::Payload);
+        }
+        let hdr = Shim6ControlHdr::from_bytes(bytes)?;
+        let body = bytes.get(Shim6ControlHdr::LEN..hdr.msg_len())?;
+        Some(Shim6::Control(hdr, body))
+    }
+
+    /// The context tag carried by the header.
+    ///
+    /// For the payload header and most control messages this is the
+    /// Receiver Context Tag; I1 and I2 carry the Initiator Context Tag and R2
+    /// the Responder Context Tag. R1 messages carry no context tag.
+    pub fn context_tag(&self) -> Option<u64> {
+        match self {
+            Shim6::Payload(hdr) => Some(hdr.context_tag()),
+            Shim6::Control(hdr, body) => match hdr.msg_type()? {
+                Shim6MsgType::R1 => None,
+                _ => body.get(..6).map(context_tag),
+            },
+        }
+    }
+
+    /// Length of the Shim6 header, in octets.
+    pub fn hdr_len(&self) -> usize {
+        match self {
+            Shim6::Payload(_) => Shim6PayloadHdr::LEN,
+            Shim6::Control(hdr, _) => hdr.msg_len(),
+        }
+    }
+}
+
+/// Verifies the checksum of the control message at the start of `msg`.
+pub fn verify_checksum(msg: &[u8]) -> bool {
+    match Shim6::parse(msg) {
+        Some(Shim6::Control(hdr, _)) => checksum::checksum(&msg[..hdr.msg_len()]) == 0,
+        _ => false,
+    }
+}
+
+/// Extracts the 47-bit context tag stored after a leading flag bit.
+#[inline]
+fn context_tag(bytes: &[u8]) -> u64 {
+    let mut tag = [0u8; 8];
+    tag[2..].copy_from_slice(&bytes[..6]);
+    u64::from_be_bytes(tag) & 0x7fff_ffff_ffff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{verify_checksum, Shim6, Shim6MsgType};
+    use crate::{checksum, ip::IpProto};
+
+    #[test]
+    fn test_shim6() {
+        let payload = [6, 0, 0x80 | 0x12, 0x34, 0x56, 0x78, 0x9a, 0xbc];
+        let shim6 = Shim6::parse(&payload).unwrap();
+        assert!(matches!(shim6, Shim6::Payload(hdr) if hdr.next_hdr() == Some(IpProto::Tcp)));
+        assert_eq!(shim6.context_tag(), Some(0x1234_5678_9abc));
+
+        // Keepalive: 8 octets of common header and tag, 8 octets reserved.
+        let mut keepalive = [59, 1, 66, 0, 0, 0, 0x80, 0, 0, 0, 0, 0x2a, 0, 0, 0, 0];
+        let check = checksum::checksum(&keepalive);
+        keepalive[4..6].copy_from_slice(&check.to_be_bytes());
+        let shim6 = Shim6::parse(&keepalive).unwrap();
+        match shim6 {
+            Shim6::Control(hdr, body) => {
+                assert_eq!(hdr.msg_type(), Some(Shim6MsgType::Keepalive));
+                assert_eq!(body.len(), 10);
+            }
+            Shim6::Payload(_) => panic!("expected a control message"),
+        }
+        assert_eq!(shim6.context_tag(), Some(0x2a));
+        assert_eq!(shim6.hdr_len(), 16);
+        assert!(verify_checksum(&keepalive));
+    }
+}
@@ -0,0 +1,200 @@
+use core::mem;
+
+use crate::{
+    eth::EtherType,
+    header::impl_header,
+    types::{U16, U32},
+};
+
+/// Direction of a packet captured on a Linux cooked interface, relative to
+/// the capturing host.
+#[repr(u16)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum SllPacketType {
+    /// Sent to us.
+    Host = 0,
+    /// Broadcast by somebody else.
+    Broadcast = 1,
+    /// Multicast by somebody else.
+    Multicast = 2,
+    /// Sent by somebody else to somebody else.
+    OtherHost = 3,
+    /// Sent by us.
+    Outgoing = 4,
+}
+
+impl TryFrom<u16> for SllPacketType {
+    type Error = ();
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(SllPacketType::Host),
+            1 => Ok(SllPacketType::Broadcast),
+            2 => Ok(SllPacketType::Multicast),
+            3 => Ok(SllPacketType::OtherHost),
+            4 => Ok(SllPacketType::Outgoing),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Linux cooked capture header (`LINKTYPE_LINUX_SLL`), used instead of the
+/// Ethernet header by captures taken with `tcpdump -i any`.
+///
+/// [LINKTYPE_LINUX_SLL](https://www.tcpdump.org/linktypes/LINKTYPE_LINUX_SLL.html)
+/// ```text
+/// +---------------------------+
+/// |         Packet type       |
+/// |         (2 Octets)        |
+/// +---------------------------+
+/// |        ARPHRD_ type       |
+/// |         (2 Octets)        |
+/// +---------------------------+
+/// | Link-layer address length |
+/// |         (2 Octets)        |
+/// +---------------------------+
+/// |    Link-layer address     |
+/// |         (8 Octets)        |
+/// +---------------------------+
+/// |        Protocol type      |
+/// |         (2 Octets)        |
+/// +---------------------------+
+/// ```
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct SllHdr {
+    /// See [`SllPacketType`].
+    pub packet_type: U16,
+    /// Link-layer device type (`ARPHRD_*`), e.g. 1 for Ethernet.
+    pub arphrd_type: U16,
+    /// Number of valid bytes in `addr`.
+    pub addr_len: U16,
+    /// Link-layer source address, padded with zeros.
+    pub addr: [u8; 8],
+    /// Protocol of the payload, usually an [`EtherType`].
+    pub protocol: U16,
+}
+
+impl SllHdr {
+    pub const LEN: usize = mem::size_of::<SllHdr>();
+
+    #[inline]
+    pub fn packet_type(&self) -> Option<SllPacketType> {
+        self.packet_type.to_bits().try_into().ok()
+    }
+
+    /// Link-layer source address, truncated to its actual length.
+    #[inline]
+    pub fn addr(&self) -> &[u8] {
+        let len = (self.addr_len.to_bits() as usize).min(self.addr.len());
+        &self.addr[..len]
+    }
+
+    #[inline(always)]
+    pub const fn ether_type(&self) -> Option<EtherType> {
+        EtherType::from_u16(self.protocol.to_bits())
+    }
+}
+
+/// Linux cooked capture header, version 2 (`LINKTYPE_LINUX_SLL2`), which in
+/// addition records the index of the capturing interface.
+///
+/// [LINKTYPE_LINUX_SLL2](https://www.tcpdump.org/linktypes/LINKTYPE_LINUX_SLL2.html)
+/// ```text
+/// +---------------------------+
+/// |        Protocol type      |
+/// |         (2 Octets)        |
+/// +---------------------------+
+/// |       Reserved (MBZ)      |
+/// |         (2 Octets)        |
+/// +---------------------------+
+/// |       Interface index     |
+/// |         (4 Octets)        |
+/// +---------------------------+
+/// |        ARPHRD_ type       |
+/// |         (2 Octets)        |
+/// +---------------------------+
+/// |         Packet type       |
+/// |         (1 Octet)         |
+/// +---------------------------+
+/// | Link-layer address length |
+/// |         (1 Octet)         |
+/// +---------------------------+
+/// |    Link-layer address     |
+/// |         (8 Octets)        |
+/// +---------------------------+
+/// ```
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct Sll2Hdr {
+    /// Protocol of the payload, usually an [`EtherType`].
+    pub protocol: U16,
+    pub _reserved: U16,
+    /// Index of the interface the packet was captured on.
+    pub if_index: U32,
+    /// Link-layer device type (`ARPHRD_*`), e.g. 1 for Ethernet.
+    pub arphrd_type: U16,
+    /// See [`SllPacketType`].
+    pub packet_type: u8,
+    /// Number of valid bytes in `addr`.
+    pub addr_len: u8,
+    /// Link-layer source address, padded with zeros.
+    pub addr: [u8; 8],
+}
+
+impl Sll2Hdr {
+    pub const LEN: usize = mem::size_of::<Sll2Hdr>();
+
+    #[inline]
+    pub fn packet_type(&self) -> Option<SllPacketType> {
+        (self.packet_type as u16).try_into().ok()
+    }
+
+    #[inline]
+    pub const fn if_index(&self) -> u32 {
+        self.if_index.to_bits()
+    }
+
+    /// Link-layer source address, truncated to its actual length.
+    #[inline]
+    pub fn addr(&self) -> &[u8] {
+        let len = (self.addr_len as usize).min(self.addr.len());
+        &self.addr[..len]
+    }
+
+    #[inline(always)]
+    pub const fn ether_type(&self) -> Option<EtherType> {
+        EtherType::from_u16(self.protocol.to_bits())
+    }
+}
+
+impl_header!(SllHdr, Sll2Hdr);
+
+#[cfg(test)]
+mod tests {
+    use super::{Sll2Hdr, SllHdr, SllPacketType};
+    use crate::{eth::EtherType, header::Header};
+
+    #[test]
+    fn test_sll() {
+        let bytes = [
+            0x00, 0x04, 0x00, 0x01, 0x00, 0x06, 0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x00, 0x00,
+            0x08, 0x00,
+        ];
+        let sll = SllHdr::from_bytes(&bytes).unwrap();
+        assert_eq!(sll.packet_type(), Some(SllPacketType::Outgoing));
+        assert_eq!(sll.addr(), &[0x00, 0x11, 0x22, 0x33, 0x44, 0x55]);
+        assert_eq!(sll.ether_type(), Some(EtherType::Ipv4));
+
+        let bytes = [
+            0x86, 0xdd, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0x00, 0x01, 0x00, 0x06, 0x00, 0x11,
+            0x22, 0x33, 0x44, 0x55, 0x00, 0x00,
+        ];
+        let sll2 = Sll2Hdr::from_bytes(&bytes).unwrap();
+        assert_eq!(sll2.if_index(), 3);
+        assert_eq!(sll2.packet_type(), Some(SllPacketType::Host));
+        assert_eq!(sll2.ether_type(), Some(EtherType::Ipv6));
+    }
+}
@@ -0,0 +1,392 @@
+//! IEEE 802.3 Slow Protocols, carried with
+//! [`EtherType::LACP`](crate::eth::EtherType::LACP) (0x8809) and told apart by the subtype
+//! octet starting the payload.
+
+use core::mem;
+
+use crate::{
+    header::{impl_header, Header},
+    types::{U16, U32},
+};
+
+/// Destination MAC address of Slow Protocols frames.
+pub const SLOW_PROTOCOLS_MAC: [u8; 6] = [0x01, 0x80, 0xc2, 0x00, 0x00, 0x02];
+
+/// Slow Protocols subtypes.
+#[repr(u8)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum SlowSubtype {
+    /// Link Aggregation Control Protocol (IEEE 802.1AX).
+    Lacp = 1,
+    /// Link Aggregation Marker Protocol (IEEE 802.1AX).
+    Marker = 2,
+    /// Ethernet OAM (IEEE 802.3 clause 57).
+    Oam = 3,
+    /// Organization Specific Slow Protocol.
+    Ossp = 10,
+}
+
+impl TryFrom<u8> for SlowSubtype {
+    type Error = ();
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(SlowSubtype::Lacp),
+            2 => Ok(SlowSubtype::Marker),
+            3 => Ok(SlowSubtype::Oam),
+            10 => Ok(SlowSubtype::Ossp),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Subtype of the Slow Protocols PDU stored in `payload`.
+#[inline]
+pub fn subtype(payload: &[u8]) -> Option<SlowSubtype> {
+    (*payload.first()?).try_into().ok()
+}
+
+/// Marker protocol TLV types.
+#[repr(u8)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum MarkerTlvType {
+    /// Marker PDU, sent by the distributor.
+    Information = 1,
+    /// Marker Response PDU, echoing the information of a Marker PDU.
+    Response = 2,
+}
+
+impl TryFrom<u8> for MarkerTlvType {
+    type Error = ();
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(MarkerTlvType::Information),
+            2 => Ok(MarkerTlvType::Response),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Marker and Marker Response PDU, followed by 90 reserved octets.
+/// ```text
+/// +------------------------------+
+/// | Subtype = Marker (1)         |
+/// | Version Number (1)           |
+/// | TLV_type (1)                 |
+/// | Marker_Information_Length (1)|
+/// | Requester_Port (2)           |
+/// | Requester_System (6)         |
+/// | Requester_Transaction_ID (4) |
+/// | Pad (2)                      |
+/// | TLV_type = Terminator (1)    |
+/// | Terminator_Length (1)        |
+/// +------------------------------+
+/// ```
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct MarkerPdu {
+    /// Always [`SlowSubtype::Marker`].
+    pub subtype: u8,
+    pub version: u8,
+    /// See [`MarkerTlvType`].
+    pub tlv_type: u8,
+    /// Always 16.
+    pub info_len: u8,
+    pub requester_port: U16,
+    /// MAC address of the requesting system.
+    pub requester_system: [u8; 6],
+    pub requester_transaction_id: U32,
+    pub _pad: [u8; 2],
+    pub terminator_type: u8,
+    pub terminator_len: u8,
+}
+
+impl MarkerPdu {
+    pub const LEN: usize = mem::size_of::<MarkerPdu>();
+
+    #[inline]
+    pub fn tlv_type(&self) -> Option<MarkerTlvType> {
+        self.tlv_type.try_into().ok()
+    }
+
+    #[inline]
+    pub const fn requester_port(&self) -> u16 {
+        self.requester_port.to_bits()
+    }
+
+    #[inline]
+    pub const fn requester_transaction_id(&self) -> u32 {
+        self.requester_transaction_id.to_bits()
+    }
+
+    /// Parses the Marker or Marker Response PDU stored in `payload`.
+    pub fn parse(payload: &[u8]) -> Option<&MarkerPdu> {
+        let pdu = MarkerPdu::from_bytes(payload)?;
+        if pdu.subtype != SlowSubtype::Marker as u8 || pdu.tlv_type().is_none() {
+            return None;
+        }
+        Some(pdu)
+    }
+}
+
+impl_header!(MarkerPdu);
+
+/// OAMPDU codes.
+#[repr(u8)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum OamCode {
+    Information = 0x00,
+    EventNotification = 0x01,
+    VariableRequest = 0x02,
+    VariableResponse = 0x03,
+    LoopbackControl = 0x04,
+    OrganizationSpecific = 0xfe,
+}
+
+impl TryFrom<u8> for OamCode {
+    type Error = ();
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x00 => Ok(OamCode::Information),
+            0x01 => Ok(OamCode::EventNotification),
+            0x02 => Ok(OamCode::VariableRequest),
+            0x03 => Ok(OamCode::VariableResponse),
+            0x04 => Ok(OamCode::LoopbackControl),
+            0xfe => Ok(OamCode::OrganizationSpecific),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Information TLV types.
+pub const OAM_TLV_END: u8 = 0x00;
+pub const OAM_TLV_LOCAL_INFO: u8 = 0x01;
+pub const OAM_TLV_REMOTE_INFO: u8 = 0x02;
+/// Link event TLV types.
+pub const OAM_EVENT_ERRORED_SYMBOL_PERIOD: u8 = 0x01;
+pub const OAM_EVENT_ERRORED_FRAME: u8 = 0x02;
+pub const OAM_EVENT_ERRORED_FRAME_PERIOD: u8 = 0x03;
+pub const OAM_EVENT_ERRORED_FRAME_SECONDS: u8 = 0x04;
+
+/// Ethernet OAM PDU header (IEEE 802.3 clause 57), followed by the
+/// code-specific data.
+/// ```text
+/// +------------------+-----------+----------+
+/// | Subtype = 0x03   | Flags (2) | Code (1) |
+/// +------------------+-----------+----------+
+/// ```
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct OamPduHdr {
+    /// Always [`SlowSubtype::Oam`].
+    pub subtype: u8,
+    pub flags: U16,
+    /// See [`OamCode`].
+    pub code: u8,
+}
+
+impl OamPduHdr {
+    pub const LEN: usize = mem::size_of::<OamPduHdr>();
+
+    #[inline]
+    pub fn code(&self) -> Option<OamCode> {
+        self.code.try_into().ok()
+    }
+
+    /// **Link Fault**: the local device detected a fault in its receive path.
+    #[inline]
+    pub const fn link_fault(&self) -> bool {
+        self.flags.to_bits() & 0x0001 != 0
+    }
+
+    /// **Dying Gasp**: an unrecoverable local failure occurred.
+    #[inline]
+    pub const fn dying_gasp(&self) -> bool {
+        self.flags.to_bits() & 0x0002 != 0
+    }
+
+    /// **Critical Event**: an unspecified critical event occurred.
+    #[inline]
+    pub const fn critical_event(&self) -> bool {
+        self.flags.to_bits() & 0x0004 != 0
+    }
+
+    /// Local discovery state: 0 unsatisfied, 1 evaluating, 2 complete.
+    #[inline]
+    pub const fn local_discovery(&self) -> u8 {
+        ((self.flags.to_bits() >> 3) & 0x3) as u8
+    }
+
+    /// Remote discovery state: 0 unsatisfied, 1 evaluating, 2 complete.
+    #[inline]
+    pub const fn remote_discovery(&self) -> u8 {
+        ((self.flags.to_bits() >> 5) & 0x3) as u8
+    }
+
+    /// Parses the OAMPDU stored in `payload`, returning its header and data.
+    pub fn parse(payload: &[u8]) -> Option<(&OamPduHdr, &[u8])> {
+        let hdr = OamPduHdr::from_bytes(payload)?;
+        if hdr.subtype != SlowSubtype::Oam as u8 {
+            return None;
+        }
+        Some((hdr, &payload[Self::LEN..]))
+    }
+
+    /// Information TLVs of an Information OAMPDU with data `data`.
+    pub fn information<'a>(&self, data: &'a [u8]) -> Option<OamTlvs<'a>> {
+        (self.code == OamCode::Information as u8).then_some(OamTlvs { data })
+    }
+
+    /// Sequence number and link event TLVs of an Event Notification OAMPDU
+    /// with data `data`.
+    pub fn event_notification<'a>(&self, data: &'a [u8]) -> Option<(u16, OamTlvs<'a>)> {
+        if self.code != OamCode::EventNotification as u8 {
+            return None;
+        }
+        let seq = u16::from_be_bytes(data.get(..2)?.try_into().ok()?);
+        Some((seq, OamTlvs { data: &data[2..] }))
+    }
+}
+
+/// Local and Remote Information TLV.
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct OamInfoTlv {
+    /// [`OAM_TLV_LOCAL_INFO`] or [`OAM_TLV_REMOTE_INFO`].
+    pub tlv_type: u8,
+    /// Always 16.
+    pub length: u8,
+    pub oam_version: u8,
+    pub revision: U16,
+    /// Parser action (2 bits) and multiplexer action (1 bit).
+    pub state: u8,
+    /// Bit 0: active mode, 1: unidirectional support, 2: loopback support,
+    /// 3: link events, 4: variable retrieval.
+    pub oam_config: u8,
+    /// Largest OAMPDU supported, in octets (11 bits).
+    pub oampdu_config: U16,
+    pub oui: [u8; 3],
+    pub vendor_info: U32,
+}
+
+impl OamInfoTlv {
+    pub const LEN: usize = mem::size_of::<OamInfoTlv>();
+
+    /// The device is in active mode rather than passive mode.
+    #[inline]
+    pub const fn active(&self) -> bool {
+        self.oam_config & 0x01 != 0
+    }
+
+    #[inline]
+    pub const fn max_pdu_size(&self) -> u16 {
+        self.oampdu_config.to_bits() & 0x07ff
+    }
+}
+
+impl_header!(OamPduHdr, OamInfoTlv);
+
+/// An Information or link event TLV of an OAMPDU.
+#[derive(Debug, Copy, Clone)]
+pub struct OamTlv<'a> {
+    pub tlv_type: u8,
+    /// The whole TLV, type and length octets included.
+    pub bytes: &'a [u8],
+}
+
+impl<'a> OamTlv<'a> {
+    /// Local or Remote Information TLV.
+    pub fn info(&self) -> Option<&'a OamInfoTlv> {
+        match self.tlv_type {
+            OAM_TLV_LOCAL_INFO | OAM_TLV_REMOTE_INFO => OamInfoTlv::from_bytes(self.bytes),
+            _ => None,
+        }
+    }
+
+    /// Timestamp of a link event TLV, in units of 100 ms since the device
+    /// reset.
+    pub fn event_timestamp(&self) -> Option<u16> {
+        Some(u16::from_be_bytes(self.bytes.get(2..4)?.try_into().ok()?))
+    }
+}
+
+/// Iterator over the TLVs of an OAMPDU, stopping at the End TLV.
+#[derive(Debug, Clone)]
+pub struct OamTlvs<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Iterator for OamTlvs<'a> {
+    type Item = OamTlv<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let [tlv_type, len, ..] = *self.data else {
+            return None;
+        };
+        // The length includes the type and length octets.
+        let bytes = self
+            .data
+            .get(..len as usize)
+            .filter(|_| tlv_type != OAM_TLV_END && len >= 2);
+        self.data = bytes.map_or(&[], |bytes| &self.data[bytes.len()..]);
+        Some(OamTlv {
+            tlv_type,
+            bytes: bytes?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{subtype, MarkerPdu, MarkerTlvType, OamCode, OamPduHdr, SlowSubtype};
+
+    #[test]
+    fn test_marker_response() {
+        let mut payload = [0u8; 110];
+        payload[..20].copy_from_slice(&[
+            2, 1, 2, 16, 0, 3, 0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0, 0, 0, 42, 0, 0, 0, 0,
+        ]);
+        assert_eq!(subtype(&payload), Some(SlowSubtype::Marker));
+        let pdu = MarkerPdu::parse(&payload).unwrap();
+        assert_eq!(pdu.tlv_type(), Some(MarkerTlvType::Response));
+        assert_eq!(pdu.requester_port(), 3);
+        assert_eq!(pdu.requester_system, [0x00, 0x11, 0x22, 0x33, 0x44, 0x55]);
+        assert_eq!(pdu.requester_transaction_id(), 42);
+
+        payload[0] = 1;
+        assert!(MarkerPdu::parse(&payload).is_none());
+    }
+
+    #[test]
+    fn test_oampdu() {
+        let mut info = [0u8; 4 + 16 + 2];
+        info[..4].copy_from_slice(&[3, 0x00, 0x50, 0]); // local and remote stable
+        info[4..20].copy_from_slice(&[
+            1, 16, 1, 0, 0, 0, 0x15, 0x05, 0xee, 0, 0x10, 0x18, 0, 0, 0, 0,
+        ]);
+        assert_eq!(subtype(&info), Some(SlowSubtype::Oam));
+        let (hdr, data) = OamPduHdr::parse(&info).unwrap();
+        assert_eq!(hdr.code(), Some(OamCode::Information));
+        assert_eq!(hdr.local_discovery(), 2);
+        assert_eq!(hdr.remote_discovery(), 2);
+        let mut tlvs = hdr.information(data).unwrap();
+        let local = tlvs.next().unwrap().info().unwrap();
+        assert!(local.active());
+        assert_eq!(local.max_pdu_size(), 1518);
+        assert!(tlvs.next().is_none());
+
+        let event = [3, 0, 0x01, 1, 0, 7, 2, 26, 0, 42, 0, 0];
+        let (hdr, data) = OamPduHdr::parse(&event).unwrap();
+        assert!(hdr.link_fault());
+        assert!(hdr.information(data).is_none());
+        let (seq, mut tlvs) = hdr.event_notification(data).unwrap();
+        assert_eq!(seq, 7);
+        // Errored Frame event, truncated.
+        assert!(tlvs.next().is_none());
+    }
+}
@@ -0,0 +1,146 @@
+//! Simple Service Discovery Protocol, the discovery part of UPnP, carried
+//! over UDP port 1900.
+//!
+//! SSDP messages are HTTP-like; the start line and headers are parsed in
+//! place without allocating.
+
+use core::{
+    net::{Ipv4Addr, Ipv6Addr},
+    str,
+};
+
+pub const SSDP_PORT: u16 = 1900;
+/// Multicast group of SSDP over IPv4.
+pub const SSDP_MCAST_V4: Ipv4Addr = Ipv4Addr::new(239, 255, 255, 250);
+/// Link-local multicast group of SSDP over IPv6.
+pub const SSDP_MCAST_V6: Ipv6Addr = Ipv6Addr::new(0xff02, 0, 0, 0, 0, 0, 0, 0xc);
+
+/// Kind of an SSDP message, given by its start line.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum SsdpKind {
+    /// `M-SEARCH * HTTP/1.1` search request.
+    MSearch,
+    /// `NOTIFY * HTTP/1.1` advertisement.
+    Notify,
+    /// `HTTP/1.1 <status> <reason>` search response.
+    Response(u16),
+}
+
+/// An SSDP message.
+#[derive(Debug, Copy, Clone)]
+pub struct SsdpMessage<'a> {
+    pub kind: SsdpKind,
+    /// Header lines, following the start line.
+    headers: &'a str,
+}
+
+impl<'a> SsdpMessage<'a> {
+    /// Recognizes and parses the SSDP message stored in the UDP payload
+    /// `payload`. Returns `None` when it is not an SSDP message.
+    pub fn parse(payload: &'a [u8]) -> Option<Self> {
+        // Anything after the empty line ending the headers is ignored, and
+        // may not be UTF-8.
+        let end = payload
+            .windows(4)
+            .position(|w| w == b"\r\n\r\n")
+            .map_or(payload.len(), |pos| pos + 2);
+        let text = str::from_utf8(&payload[..end]).ok()?;
+        let (start, headers) = text.split_once('\n').unwrap_or((text, ""));
+        let start = start.trim_end_matches('\r');
+
+        let kind = match start {
+            "M-SEARCH * HTTP/1.1" => SsdpKind::MSearch,
+            "NOTIFY * HTTP/1.1" => SsdpKind::Notify,
+            _ => {
+                let status = start.strip_prefix("HTTP/1.1 ")?.get(..3)?;
+                SsdpKind::Response(status.parse().ok()?)
+            }
+        };
+        Some(SsdpMessage { kind, headers })
+    }
+
+    /// Iterator over the headers of the message, as `(name, value)` pairs
+    /// with surrounding whitespace trimmed.
+    pub fn headers(&self) -> impl Iterator<Item = (&'a str, &'a str)> {
+        self.headers
+            .lines()
+            .take_while(|line| !line.is_empty())
+            .filter_map(|line| {
+                let (name, value) = line.split_once(':')?;
+                Some((name.trim(), value.trim()))
+            })
+    }
+
+    /// Value of the header `name`, compared case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&'a str> {
+        self.headers()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value)
+    }
+
+    /// **ST**: search target of an M-SEARCH request or its response.
+    #[inline]
+    pub fn st(&self) -> Option<&'a str> {
+        self.header("ST")
+    }
+
+    /// **NT**: notification type of a NOTIFY message.
+    #[inline]
+    pub fn nt(&self) -> Option<&'a str> {
+        self.header("NT")
+    }
+
+    /// **NTS**: notification sub type, e.g. `ssdp:alive` or `ssdp:byebye`.
+    #[inline]
+    pub fn nts(&self) -> Option<&'a str> {
+        self.header("NTS")
+    }
+
+    /// **USN**: unique service name of the advertised device or service.
+    #[inline]
+    pub fn usn(&self) -> Option<&'a str> {
+        self.header("USN")
+    }
+
+    /// **LOCATION**: URL of the device description.
+    #[inline]
+    pub fn location(&self) -> Option<&'a str> {
+        self.header("LOCATION")
+    }
+
+    /// **SERVER**: operating system and product of the advertiser.
+    #[inline]
+    pub fn server(&self) -> Option<&'a str> {
+        self.header("SERVER")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SsdpKind, SsdpMessage};
+
+    #[test]
+    fn test_ssdp() {
+        let notify = b"NOTIFY * HTTP/1.1\r\n\
+            HOST: 239.255.255.250:1900\r\n\
+            nt: upnp:rootdevice\r\n\
+            NTS: ssdp:alive\r\n\
+            USN: uuid:1234::upnp:rootdevice\r\n\
+            LOCATION: http://192.168.1.10:8080/desc.xml\r\n\
+            \r\n";
+        let msg = SsdpMessage::parse(notify).unwrap();
+        assert_eq!(msg.kind, SsdpKind::Notify);
+        assert_eq!(msg.nt(), Some("upnp:rootdevice"));
+        assert_eq!(msg.nts(), Some("ssdp:alive"));
+        assert_eq!(msg.location(), Some("http://192.168.1.10:8080/desc.xml"));
+        assert_eq!(msg.headers().count(), 5);
+
+        let response = b"HTTP/1.1 200 OK\r\nST: ssdp:all\r\n\r\n\xff";
+        let msg = SsdpMessage::parse(response).unwrap();
+        assert_eq!(msg.kind, SsdpKind::Response(200));
+        assert_eq!(msg.st(), Some("ssdp:all"));
+        assert_eq!(msg.usn(), None);
+
+        assert!(SsdpMessage::parse(b"GET / HTTP/1.1\r\n\r\n").is_none());
+    }
+}
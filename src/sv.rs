@@ -0,0 +1,233 @@
+//! Sampled Values (IEC 61850-9-2), streaming the measurements of merging
+//! units to protection relays with
+//! [`EtherType::SV`](crate::eth::EtherType::SV).
+//!
+//! The APDU following the header is BER encoded and carries one or more
+//! ASDUs, each holding the counter and the dataset of a sample.
+
+use core::{mem, str};
+
+use crate::{
+    header::{impl_header, Header},
+    types::U16,
+};
+
+/// SV header.
+/// ```text
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |             APPID             |             Length            |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |           Reserved 1          |           Reserved 2          |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct SvHdr {
+    /// Application identifier, from 0x4000 to 0x7fff.
+    pub appid: U16,
+    /// Length of the PDU from the APPID, in bytes.
+    pub length: U16,
+    /// Simulation flag in the most significant bit.
+    pub reserved1: U16,
+    pub reserved2: U16,
+}
+
+impl SvHdr {
+    pub const LEN: usize = mem::size_of::<SvHdr>();
+
+    #[inline]
+    pub const fn appid(&self) -> u16 {
+        self.appid.to_bits()
+    }
+
+    #[inline]
+    pub const fn length(&self) -> u16 {
+        self.length.to_bits()
+    }
+
+    /// Whether the values are simulated, for testing.
+    #[inline]
+    pub const fn simulated(&self) -> bool {
+        self.reserved1.to_bits() & 0x8000 != 0
+    }
+}
+
+impl_header!(SvHdr);
+
+/// Splits the BER element at the start of `data` into its tag, its value
+/// and the following bytes.
+fn ber(data: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, rest) = data.split_first()?;
+    let (&first, rest) = rest.split_first()?;
+    let (len, rest) = match first {
+        0x00..=0x7f => (first as usize, rest),
+        0x81 => (*rest.first()? as usize, &rest[1..]),
+        0x82 => (
+            u16::from_be_bytes(*rest.first_chunk()?) as usize,
+            &rest[2..],
+        ),
+        _ => return None,
+    };
+    (len <= rest.len()).then(|| (tag, &rest[..len], &rest[len..]))
+}
+
+/// Big-endian unsigned integer of at most 8 bytes.
+#[inline]
+fn uint(value: &[u8]) -> Option<u64> {
+    (value.len() <= 8).then(|| value.iter().fold(0, |n, &b| n << 8 | b as u64))
+}
+
+/// A decoded SV PDU.
+#[derive(Debug, Clone)]
+pub struct SvPdu<'a> {
+    pub hdr: &'a SvHdr,
+    /// Number of ASDUs announced by the PDU.
+    pub no_asdu: u8,
+    pub asdus: SvAsdus<'a>,
+}
+
+/// An application service data unit, holding one sample of a dataset.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub struct SvAsdu<'a> {
+    /// Identifier of the sampled value control block.
+    pub sv_id: &'a str,
+    pub dat_set: Option<&'a str>,
+    /// Sample counter, wrapping at the sample rate.
+    pub smp_cnt: u16,
+    /// Configuration revision of the control block.
+    pub conf_rev: u32,
+    /// Refresh time, as a UTC time of IEC 61850.
+    pub refr_tm: Option<&'a [u8]>,
+    /// 0 if not synchronized, 1 to a local clock, 2 to a global clock.
+    pub smp_synch: u8,
+    pub smp_rate: Option<u16>,
+    /// Values of the dataset, e.g. 8 currents and voltages of 4-byte values
+    /// and 4-byte qualities with the 9-2LE profile.
+    pub sample: &'a [u8],
+    pub smp_mod: Option<u16>,
+}
+
+impl<'a> SvAsdu<'a> {
+    fn decode(mut data: &'a [u8]) -> Option<Self> {
+        let mut asdu = SvAsdu {
+            sv_id: "",
+            dat_set: None,
+            smp_cnt: 0,
+            conf_rev: 0,
+            refr_tm: None,
+            smp_synch: 0,
+            smp_rate: None,
+            sample: &[],
+            smp_mod: None,
+        };
+        let mut seen = 0u16;
+        while let Some((tag, value, rest)) = ber(data) {
+            match tag {
+                0x80 => asdu.sv_id = str::from_utf8(value).ok()?,
+                0x81 => asdu.dat_set = Some(str::from_utf8(value).ok()?),
+                0x82 => asdu.smp_cnt = uint(value)? as u16,
+                0x83 => asdu.conf_rev = uint(value)? as u32,
+                0x84 => asdu.refr_tm = Some(value),
+                0x85 => asdu.smp_synch = uint(value)? as u8,
+                0x86 => asdu.smp_rate = Some(uint(value)? as u16),
+                0x87 => asdu.sample = value,
+                0x88 => asdu.smp_mod = Some(uint(value)? as u16),
+                _ => {}
+            }
+            seen |= 1 << (tag & 0xf);
+            data = rest;
+        }
+        // svID, smpCnt, confRev, smpSynch and sample are mandatory.
+        (data.is_empty() && seen & 0b1010_1101 == 0b1010_1101).then_some(asdu)
+    }
+}
+
+/// Iterator over the ASDUs of an SV PDU, stopping at the first malformed
+/// one.
+#[derive(Debug, Clone)]
+pub struct SvAsdus<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Iterator for SvAsdus<'a> {
+    type Item = SvAsdu<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (tag, value, rest) = ber(self.data)?;
+        let asdu = (tag == 0x30).then(|| SvAsdu::decode(value)).flatten();
+        self.data = if asdu.is_some() { rest } else { &[] };
+        asdu
+    }
+}
+
+/// Parses the SV PDU stored in `pdu`, which starts with its header.
+///
+/// Fails if the header or the APDU are malformed; the ASDUs are decoded
+/// by the returned iterator.
+pub fn parse(pdu: &[u8]) -> Option<SvPdu<'_>> {
+    let hdr = SvHdr::from_bytes(pdu)?;
+    let apdu = pdu.get(SvHdr::LEN..hdr.length() as usize)?;
+    let (0x60, mut sav_pdu, _) = ber(apdu)? else {
+        return None;
+    };
+    let mut no_asdu = None;
+    while let Some((tag, value, rest)) = ber(sav_pdu) {
+        sav_pdu = rest;
+        match tag {
+            0x80 => no_asdu = Some(uint(value)? as u8),
+            0xa2 => {
+                return Some(SvPdu {
+                    hdr,
+                    no_asdu: no_asdu?,
+                    asdus: SvAsdus { data: value },
+                })
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse;
+
+    #[test]
+    fn test_sv_9_2le() {
+        #[rustfmt::skip]
+        let mut pdu = [
+            0x40, 0x00, 0, 58, 0x80, 0, 0, 0,
+            0x60, 48,
+            0x80, 1, 1,
+            0xa2, 43,
+            0x30, 41,
+            0x80, 8, b'M', b'U', b'0', b'1', b'/', b'L', b'D', b'0',
+            0x82, 2, 0x0f, 0x9f,
+            0x83, 4, 0, 0, 0, 1,
+            0x85, 1, 2,
+            0x87, 16, 0, 0, 0x03, 0xe8, 0, 0, 0, 0, 0xff, 0xff, 0xfc, 0x18, 0, 0, 0x20, 0,
+            0, 0,
+        ];
+        let sv = parse(&pdu).unwrap();
+        assert_eq!(
+            (sv.hdr.appid(), sv.hdr.simulated(), sv.no_asdu),
+            (0x4000, true, 1)
+        );
+        let mut asdus = sv.asdus;
+        let asdu = asdus.next().unwrap();
+        assert!(asdus.next().is_none());
+        assert_eq!((asdu.sv_id, asdu.dat_set), ("MU01/LD0", None));
+        assert_eq!((asdu.smp_cnt, asdu.conf_rev, asdu.smp_synch), (3999, 1, 2));
+        assert_eq!(&asdu.sample[..4], &[0, 0, 0x03, 0xe8]);
+
+        // Without its sample, the ASDU is malformed.
+        pdu[40] = 0x89;
+        assert_eq!(parse(&pdu).unwrap().asdus.count(), 0);
+        // Longer than the PDU.
+        pdu[3] = 61;
+        assert!(parse(&pdu).is_none());
+    }
+}
@@ -0,0 +1,178 @@
+//! Stateless SYN cookies, with the encoding of Linux
+//! (`net/ipv4/syncookies.c`, `net/ipv6/syncookies.c`).
+//!
+//! The initial sequence number of the SYN-ACK encodes a hash of the
+//! connection, the time in minutes and the index of the MSS in a small
+//! table, so that the connection can be accepted on the ACK without keeping
+//! any state for the SYN:
+//! ```text
+//! cookie = H(flow, 0, key0) + client_isn + (minute << 24)
+//!        + ((H(flow, minute, key1) + mss_index) mod 2^24)
+//! ```
+//! `H` is SipHash-2-4 keyed with the two halves of [`CookieSecret::keys`],
+//! over the same bytes as Linux on little-endian hosts.
+
+use core::net::IpAddr;
+
+use crate::{
+    flow::{siphash, FlowKey},
+    ip::IpProto,
+    tcp::TcpHdr,
+};
+
+const COOKIEBITS: u32 = 24;
+const COOKIEMASK: u32 = (1 << COOKIEBITS) - 1;
+
+/// Number of minutes a cookie stays valid.
+const MAX_SYNCOOKIE_AGE: u32 = 2;
+
+/// MSS values encodable in IPv4 cookies.
+pub const MSS_TABLE_V4: [u16; 4] = [536, 1300, 1440, 1460];
+
+/// MSS values encodable in IPv6 cookies.
+pub const MSS_TABLE_V6: [u16; 4] = [1280 - 60, 1480 - 60, 1500 - 60, 9000 - 60];
+
+/// Secret of the cookies, and the current time.
+#[derive(Debug, Copy, Clone)]
+pub struct CookieSecret {
+    /// SipHash keys of the connection hash and of the timed hash.
+    pub keys: [[u64; 2]; 2],
+    /// Current time in minutes, like `jiffies / (60 * HZ)` on Linux.
+    pub minute: u32,
+}
+
+/// Initial sequence number of a SYN-ACK and the MSS it stands for.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub struct SynCookie {
+    pub isn: u32,
+    /// Largest MSS of the table not above the MSS of the SYN, which the
+    /// SYN-ACK should advertise.
+    pub mss: u16,
+}
+
+/// Computes the cookie answering the SYN `syn` of `flow`, whose MSS option
+/// is `mss`.
+///
+/// Returns `None` if `flow` is not TCP or mixes address families.
+pub fn generate(
+    flow: &FlowKey,
+    syn: &TcpHdr,
+    mss: u16,
+    secret: &CookieSecret,
+) -> Option<SynCookie> {
+    let table = mss_table(flow)?;
+    let index = table.iter().rposition(|&m| m <= mss).unwrap_or(0);
+    let sseq = syn.seq.to_bits();
+    let isn = cookie_hash(flow, 0, &secret.keys[0])?
+        .wrapping_add(sseq)
+        .wrapping_add(secret.minute << COOKIEBITS)
+        .wrapping_add(
+            cookie_hash(flow, secret.minute, &secret.keys[1])?.wrapping_add(index as u32)
+                & COOKIEMASK,
+        );
+    Some(SynCookie {
+        isn,
+        mss: table[index],
+    })
+}
+
+/// Checks the cookie acknowledged by `ack`, the ACK completing the handshake
+/// of `flow`, returning the MSS it encodes.
+///
+/// Returns `None` if the cookie is forged or older than 2 minutes.
+pub fn validate(flow: &FlowKey, ack: &TcpHdr, secret: &CookieSecret) -> Option<u16> {
+    let table = mss_table(flow)?;
+    let cookie = ack.ack_seq.to_bits().wrapping_sub(1);
+    let sseq = ack.seq.to_bits().wrapping_sub(1);
+    let cookie = cookie.wrapping_sub(cookie_hash(flow, 0, &secret.keys[0])?.wrapping_add(sseq));
+    let diff = secret.minute.wrapping_sub(cookie >> COOKIEBITS) & (u32::MAX >> COOKIEBITS);
+    if diff >= MAX_SYNCOOKIE_AGE {
+        return None;
+    }
+    let count = secret.minute.wrapping_sub(diff);
+    let index = cookie.wrapping_sub(cookie_hash(flow, count, &secret.keys[1])?) & COOKIEMASK;
+    table.get(index as usize).copied()
+}
+
+#[inline]
+fn mss_table(flow: &FlowKey) -> Option<&'static [u16; 4]> {
+    match (flow.proto, flow.src_addr) {
+        (IpProto::Tcp, IpAddr::V4(_)) => Some(&MSS_TABLE_V4),
+        (IpProto::Tcp, IpAddr::V6(_)) => Some(&MSS_TABLE_V6),
+        _ => None,
+    }
+}
+
+/// Hash of the 4-tuple and `count`, laid out like the Linux
+/// `cookie_hash()` of each family.
+fn cookie_hash(flow: &FlowKey, count: u32, key: &[u64; 2]) -> Option<u32> {
+    let sport = flow.src_port.to_be_bytes();
+    let dport = flow.dst_port.to_be_bytes();
+    let mut msg = [0u8; 40];
+    let len = match (flow.src_addr, flow.dst_addr) {
+        (IpAddr::V4(src), IpAddr::V4(dst)) => {
+            // siphash_4u32(saddr, daddr, sport << 16 | dport, count)
+            msg[..4].copy_from_slice(&src.octets());
+            msg[4..8].copy_from_slice(&dst.octets());
+            msg[8..10].copy_from_slice(&dport);
+            msg[10..12].copy_from_slice(&sport);
+            msg[12..16].copy_from_slice(&count.to_le_bytes());
+            16
+        }
+        (IpAddr::V6(src), IpAddr::V6(dst)) => {
+            msg[..16].copy_from_slice(&src.octets());
+            msg[16..32].copy_from_slice(&dst.octets());
+            msg[32..36].copy_from_slice(&count.to_le_bytes());
+            msg[36..38].copy_from_slice(&sport);
+            msg[38..40].copy_from_slice(&dport);
+            40
+        }
+        _ => return None,
+    };
+    Some(siphash(key, &msg[..len]) as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use core::net::Ipv4Addr;
+
+    use super::{generate, validate, CookieSecret};
+    use crate::{flow::FlowKey, header::Header, ip::IpProto, tcp::TcpHdr};
+
+    fn tcp(seq: u32, ack_seq: u32) -> [u8; 20] {
+        let mut hdr = [0u8; 20];
+        hdr[4..8].copy_from_slice(&seq.to_be_bytes());
+        hdr[8..12].copy_from_slice(&ack_seq.to_be_bytes());
+        hdr[12] = 0x50;
+        hdr
+    }
+
+    #[test]
+    fn test_syncookie() {
+        let flow = FlowKey {
+            src_addr: Ipv4Addr::new(192, 0, 2, 1).into(),
+            dst_addr: Ipv4Addr::new(198, 51, 100, 1).into(),
+            proto: IpProto::Tcp,
+            src_port: 49152,
+            dst_port: 443,
+        };
+        let mut secret = CookieSecret {
+            keys: [[1, 2], [3, 4]],
+            minute: 1000,
+        };
+        let syn = tcp(0x12345678, 0);
+        let syn = TcpHdr::from_bytes(&syn).unwrap();
+        let cookie = generate(&flow, syn, 1452, &secret).unwrap();
+        assert_eq!(cookie.mss, 1440);
+
+        let ack = tcp(0x12345679, cookie.isn.wrapping_add(1));
+        let ack = TcpHdr::from_bytes(&ack).unwrap();
+        assert_eq!(validate(&flow, ack, &secret), Some(1440));
+        secret.minute += 1;
+        assert_eq!(validate(&flow, ack, &secret), Some(1440));
+        secret.minute += 1;
+        assert_eq!(validate(&flow, ack, &secret), None);
+        secret.minute -= 2;
+        assert_eq!(validate(&flow.reversed(), ack, &secret), None);
+    }
+}
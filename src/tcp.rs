@@ -1,140 +1,137 @@
 use core::mem;
 
-use crate::bitfield::BitfieldUnit;
+use crate::{
+    bitfield::BitfieldUnit,
+    header::impl_header,
+    types::{U16, U32},
+};
 
 pub const TCP_HDR_LEN: usize = mem::size_of::<TcpHdr>();
 
-/// TCP header, which is present after the IP header.
-#[repr(C)]
+/// TCP header, which is present after the IP header, followed by the
+/// options.
+/// ```text
+/// +-------------------------------+-------------------------------+
+/// |          Source Port          |       Destination Port        |
+/// +-------------------------------+-------------------------------+
+/// |                        Sequence Number                        |
+/// +---------------------------------------------------------------+
+/// |                    Acknowledgment Number                      |
+/// +-------+-------+-+-+-+-+-+-+-+-+-------------------------------+
+/// |  Data |       |C|E|U|A|P|R|S|F|                               |
+/// | Offset| Rsrvd |W|C|R|C|S|S|Y|I|            Window             |
+/// |       |       |R|E|G|K|H|T|N|N|                               |
+/// +-------+-------+-+-+-+-+-+-+-+-+-------------------------------+
+/// |           Checksum            |         Urgent Pointer        |
+/// +-------------------------------+-------------------------------+
+/// ```
+#[repr(C, packed)]
 #[derive(Debug, Copy, Clone)]
 #[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 pub struct TcpHdr {
-    pub source: u16,
-    pub dest: u16,
-    pub seq: u32,
-    pub ack_seq: u32,
+    pub source: U16,
+    pub dest: U16,
+    pub seq: U32,
+    pub ack_seq: U32,
     pub _bitfield_align_1: [u8; 0],
     pub _bitfield_1: BitfieldUnit<[u8; 2usize]>,
-    pub window: u16,
-    pub check: u16,
-    pub urg_ptr: u16,
+    pub window: U16,
+    pub check: U16,
+    pub urg_ptr: U16,
 }
 
 impl TcpHdr {
     pub const LEN: usize = mem::size_of::<TcpHdr>();
 
+    /// Length of the header including the options, in bytes.
     #[inline]
-    pub fn res1(&self) -> u16 {
-        unsafe { ::core::mem::transmute(self._bitfield_1.get(0usize, 4u8) as u16) }
+    pub const fn hdrlen(&self) -> usize {
+        self.doff() as usize * 4
     }
+
     #[inline]
-    pub fn set_res1(&mut self, val: u16) {
-        unsafe {
-            let val: u16 = ::core::mem::transmute(val);
-            self._bitfield_1.set(0usize, 4u8, val as u64)
-        }
+    pub const fn res1(&self) -> u16 {
+        self._bitfield_1.get(0usize, 4u8) as u16
     }
     #[inline]
-    pub fn doff(&self) -> u16 {
-        unsafe { ::core::mem::transmute(self._bitfield_1.get(4usize, 4u8) as u16) }
+    pub const fn set_res1(&mut self, val: u16) {
+        self._bitfield_1.set(0usize, 4u8, val as u64)
     }
     #[inline]
-    pub fn set_doff(&mut self, val: u16) {
-        unsafe {
-            let val: u16 = ::core::mem::transmute(val);
-            self._bitfield_1.set(4usize, 4u8, val as u64)
-        }
+    pub const fn doff(&self) -> u16 {
+        self._bitfield_1.get(4usize, 4u8) as u16
     }
     #[inline]
-    pub fn fin(&self) -> u16 {
-        unsafe { ::core::mem::transmute(self._bitfield_1.get(8usize, 1u8) as u16) }
+    pub const fn set_doff(&mut self, val: u16) {
+        self._bitfield_1.set(4usize, 4u8, val as u64)
     }
     #[inline]
-    pub fn set_fin(&mut self, val: u16) {
-        unsafe {
-            let val: u16 = ::core::mem::transmute(val);
-            self._bitfield_1.set(8usize, 1u8, val as u64)
-        }
+    pub const fn fin(&self) -> u16 {
+        self._bitfield_1.get(8usize, 1u8) as u16
     }
     #[inline]
-    pub fn syn(&self) -> u16 {
-        unsafe { ::core::mem::transmute(self._bitfield_1.get(9usize, 1u8) as u16) }
+    pub const fn set_fin(&mut self, val: u16) {
+        self._bitfield_1.set(8usize, 1u8, val as u64)
     }
     #[inline]
-    pub fn set_syn(&mut self, val: u16) {
-        unsafe {
-            let val: u16 = ::core::mem::transmute(val);
-            self._bitfield_1.set(9usize, 1u8, val as u64)
-        }
+    pub const fn syn(&self) -> u16 {
+        self._bitfield_1.get(9usize, 1u8) as u16
     }
     #[inline]
-    pub fn rst(&self) -> u16 {
-        unsafe { ::core::mem::transmute(self._bitfield_1.get(10usize, 1u8) as u16) }
+    pub const fn set_syn(&mut self, val: u16) {
+        self._bitfield_1.set(9usize, 1u8, val as u64)
     }
     #[inline]
-    pub fn set_rst(&mut self, val: u16) {
-        unsafe {
-            let val: u16 = ::core::mem::transmute(val);
-            self._bitfield_1.set(10usize, 1u8, val as u64)
-        }
+    pub const fn rst(&self) -> u16 {
+        self._bitfield_1.get(10usize, 1u8) as u16
     }
     #[inline]
-    pub fn psh(&self) -> u16 {
-        unsafe { ::core::mem::transmute(self._bitfield_1.get(11usize, 1u8) as u16) }
+    pub const fn set_rst(&mut self, val: u16) {
+        self._bitfield_1.set(10usize, 1u8, val as u64)
     }
     #[inline]
-    pub fn set_psh(&mut self, val: u16) {
-        unsafe {
-            let val: u16 = ::core::mem::transmute(val);
-            self._bitfield_1.set(11usize, 1u8, val as u64)
-        }
+    pub const fn psh(&self) -> u16 {
+        self._bitfield_1.get(11usize, 1u8) as u16
     }
     #[inline]
-    pub fn ack(&self) -> u16 {
-        unsafe { ::core::mem::transmute(self._bitfield_1.get(12usize, 1u8) as u16) }
+    pub const fn set_psh(&mut self, val: u16) {
+        self._bitfield_1.set(11usize, 1u8, val as u64)
     }
     #[inline]
-    pub fn set_ack(&mut self, val: u16) {
-        unsafe {
-            let val: u16 = ::core::mem::transmute(val);
-            self._bitfield_1.set(12usize, 1u8, val as u64)
-        }
+    pub const fn ack(&self) -> u16 {
+        self._bitfield_1.get(12usize, 1u8) as u16
     }
     #[inline]
-    pub fn urg(&self) -> u16 {
-        unsafe { ::core::mem::transmute(self._bitfield_1.get(13usize, 1u8) as u16) }
+    pub const fn set_ack(&mut self, val: u16) {
+        self._bitfield_1.set(12usize, 1u8, val as u64)
     }
     #[inline]
-    pub fn set_urg(&mut self, val: u16) {
-        unsafe {
-            let val: u16 = ::core::mem::transmute(val);
-            self._bitfield_1.set(13usize, 1u8, val as u64)
-        }
+    pub const fn urg(&self) -> u16 {
+        self._bitfield_1.get(13usize, 1u8) as u16
     }
     #[inline]
-    pub fn ece(&self) -> u16 {
-        unsafe { ::core::mem::transmute(self._bitfield_1.get(14usize, 1u8) as u16) }
+    pub const fn set_urg(&mut self, val: u16) {
+        self._bitfield_1.set(13usize, 1u8, val as u64)
     }
     #[inline]
-    pub fn set_ece(&mut self, val: u16) {
-        unsafe {
-            let val: u16 = ::core::mem::transmute(val);
-            self._bitfield_1.set(14usize, 1u8, val as u64)
-        }
+    pub const fn ece(&self) -> u16 {
+        self._bitfield_1.get(14usize, 1u8) as u16
     }
     #[inline]
-    pub fn cwr(&self) -> u16 {
-        unsafe { ::core::mem::transmute(self._bitfield_1.get(15usize, 1u8) as u16) }
+    pub const fn set_ece(&mut self, val: u16) {
+        self._bitfield_1.set(14usize, 1u8, val as u64)
     }
     #[inline]
-    pub fn set_cwr(&mut self, val: u16) {
-        unsafe {
-            let val: u16 = ::core::mem::transmute(val);
-            self._bitfield_1.set(15usize, 1u8, val as u64)
-        }
+    pub const fn cwr(&self) -> u16 {
+        self._bitfield_1.get(15usize, 1u8) as u16
+    }
+    #[inline]
+    pub const fn set_cwr(&mut self, val: u16) {
+        self._bitfield_1.set(15usize, 1u8, val as u64)
     }
     #[inline]
-    pub fn new_bitfield_1(
+    #[allow(clippy::too_many_arguments)]
+    pub const fn new_bitfield_1(
         res1: u16,
         doff: u16,
         fin: u16,
@@ -146,47 +143,40 @@ impl TcpHdr {
         ece: u16,
         cwr: u16,
     ) -> BitfieldUnit<[u8; 2usize]> {
-        let mut bitfield_unit: BitfieldUnit<[u8; 2usize]> = Default::default();
-        bitfield_unit.set(0usize, 4u8, {
-            let res1: u16 = unsafe { ::core::mem::transmute(res1) };
-            res1 as u64
-        });
-        bitfield_unit.set(4usize, 4u8, {
-            let doff: u16 = unsafe { ::core::mem::transmute(doff) };
-            doff as u64
-        });
-        bitfield_unit.set(8usize, 1u8, {
-            let fin: u16 = unsafe { ::core::mem::transmute(fin) };
-            fin as u64
-        });
-        bitfield_unit.set(9usize, 1u8, {
-            let syn: u16 = unsafe { ::core::mem::transmute(syn) };
-            syn as u64
-        });
-        bitfield_unit.set(10usize, 1u8, {
-            let rst: u16 = unsafe { ::core::mem::transmute(rst) };
-            rst as u64
-        });
-        bitfield_unit.set(11usize, 1u8, {
-            let psh: u16 = unsafe { ::core::mem::transmute(psh) };
-            psh as u64
-        });
-        bitfield_unit.set(12usize, 1u8, {
-            let ack: u16 = unsafe { ::core::mem::transmute(ack) };
-            ack as u64
-        });
-        bitfield_unit.set(13usize, 1u8, {
-            let urg: u16 = unsafe { ::core::mem::transmute(urg) };
-            urg as u64
-        });
-        bitfield_unit.set(14usize, 1u8, {
-            let ece: u16 = unsafe { ::core::mem::transmute(ece) };
-            ece as u64
-        });
-        bitfield_unit.set(15usize, 1u8, {
-            let cwr: u16 = unsafe { ::core::mem::transmute(cwr) };
-            cwr as u64
-        });
+        let mut bitfield_unit = BitfieldUnit::new([0u8; 2usize]);
+        bitfield_unit.set(0usize, 4u8, res1 as u64);
+        bitfield_unit.set(4usize, 4u8, doff as u64);
+        bitfield_unit.set(8usize, 1u8, fin as u64);
+        bitfield_unit.set(9usize, 1u8, syn as u64);
+        bitfield_unit.set(10usize, 1u8, rst as u64);
+        bitfield_unit.set(11usize, 1u8, psh as u64);
+        bitfield_unit.set(12usize, 1u8, ack as u64);
+        bitfield_unit.set(13usize, 1u8, urg as u64);
+        bitfield_unit.set(14usize, 1u8, ece as u64);
+        bitfield_unit.set(15usize, 1u8, cwr as u64);
         bitfield_unit
     }
 }
+
+impl_header!(TcpHdr, validate = |b: &[u8]| b[12] >> 4 >= 5);
+
+/// Kind of the timestamps option.
+pub const TCPOPT_TIMESTAMPS: u8 = 8;
+
+/// Finds the timestamps option ([RFC 7323](https://datatracker.ietf.org/doc/html/rfc7323))
+/// in the options following a TCP header, returning its TSval and TSecr.
+pub fn timestamps(mut options: &[u8]) -> Option<(u32, u32)> {
+    loop {
+        match *options {
+            [] | [0, ..] => return None,
+            [1, ref rest @ ..] => options = rest,
+            [TCPOPT_TIMESTAMPS, 10, ref rest @ ..] => {
+                let (tsval, rest) = rest.split_first_chunk()?;
+                let (tsecr, _) = rest.split_first_chunk()?;
+                return Some((u32::from_be_bytes(*tsval), u32::from_be_bytes(*tsecr)));
+            }
+            [_, len, ..] if len >= 2 => options = options.get(len as usize..)?,
+            _ => return None,
+        }
+    }
+}
@@ -0,0 +1,636 @@
+//! Passive TCP connection tracker, detecting retransmissions and
+//! out-of-order segments and estimating round-trip times.
+//!
+//! [`TcpTracker`] follows the sequence space of both directions of each
+//! connection, and classifies the segments which do not advance it like
+//! Wireshark's TCP analysis does:
+//!
+//! * a spurious retransmission carries data the receiver already
+//!   acknowledged,
+//! * a fast retransmission follows at least two duplicate ACKs of its
+//!   sequence number,
+//! * an out-of-order segment arrives shortly after the highest one, within
+//!   the reordering threshold,
+//! * any other segment is a retransmission.
+//!
+//! Round-trip times are measured from the capture point, on the SYN to the
+//! ACK of the SYN-ACK for the whole handshake, and on each direction from
+//! the echo of the TCP timestamps, or from the ACK of the data without
+//! them.
+//!
+//! The `now` arguments are the capture times of the packets, and
+//! [`TcpTracker::expire`] must be given a time on the same clock. The
+//! tracker only uses differences between these times: a segment is out of
+//! order if it comes less than the reordering threshold after the last one
+//! advancing its direction, round-trip times span a segment and its
+//! acknowledgment, and connections expire once `now` is `idle` past their
+//! last segment. Differences going backwards count as zero.
+
+use alloc::collections::BTreeMap;
+use core::time::Duration;
+
+use crate::{
+    flow::FlowKey,
+    ip::IpProto,
+    packet::{Packet, TransportHdr},
+    tcp::{self, TcpHdr},
+};
+
+/// Whether sequence number `a` is before `b`, modulo 2^32.
+#[inline]
+const fn seq_lt(a: u32, b: u32) -> bool {
+    (a.wrapping_sub(b) as i32) < 0
+}
+
+/// Kind of an anomalous segment.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum TcpEventKind {
+    Retransmission,
+    /// Retransmission following at least two duplicate ACKs.
+    FastRetransmission,
+    /// Retransmission of data which was already acknowledged.
+    SpuriousRetransmission,
+    OutOfOrder,
+}
+
+/// An anomalous segment, reported to the callback given to
+/// [`TcpTracker::new`].
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub struct TcpEvent {
+    /// Key of the segment, in its direction.
+    pub key: FlowKey,
+    pub kind: TcpEventKind,
+    pub seq: u32,
+    pub len: u32,
+    pub timestamp: Duration,
+}
+
+/// Counters of one direction of a connection.
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct TcpCounters {
+    pub segments: u64,
+    /// Bytes of payload, including the retransmitted ones.
+    pub bytes: u64,
+    /// All retransmissions, including the fast and spurious ones.
+    pub retransmissions: u64,
+    pub fast_retransmissions: u64,
+    pub spurious_retransmissions: u64,
+    pub out_of_order: u64,
+    /// Duplicate ACKs sent in this direction.
+    pub dup_acks: u64,
+}
+
+/// State of one direction of a connection.
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct TcpSide {
+    /// Sequence number following the highest segment sent.
+    pub next_seq: Option<u32>,
+    /// Highest acknowledgment number sent.
+    pub last_ack: Option<u32>,
+    /// Consecutive duplicates of `last_ack`.
+    pub dup_ack_run: u32,
+    /// Time of the last segment advancing `next_seq`.
+    pub last_advance: Duration,
+    pub counters: TcpCounters,
+    /// Round-trip time from the capture point to the receiver of this
+    /// direction and back.
+    pub rtt: RttStats,
+    /// Highest TSval sent.
+    pub last_tsval: Option<u32>,
+    /// End and time of the segment being timed, without timestamps.
+    seq_probe: Option<(u32, Duration)>,
+    /// TSval and time of the segment being timed.
+    ts_probe: Option<(u32, Duration)>,
+}
+
+/// Round-trip time samples, from the echo of TCP timestamps or, without
+/// them, from the ACK of a segment which was not retransmitted.
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct RttStats {
+    pub samples: u64,
+    pub last: Option<Duration>,
+    pub min: Option<Duration>,
+    /// Moving average, weighting each new sample by 1/8 like the smoothed
+    /// RTT of RFC 6298.
+    pub avg: Option<Duration>,
+}
+
+impl RttStats {
+    fn update(&mut self, rtt: Duration) {
+        self.samples += 1;
+        self.last = Some(rtt);
+        self.min = Some(self.min.map_or(rtt, |min| min.min(rtt)));
+        self.avg = Some(self.avg.map_or(rtt, |avg| avg - avg / 8 + rtt / 8));
+    }
+}
+
+/// A tracked connection, its sides being indexed by [`TcpConn::side`].
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct TcpConn {
+    /// Key of the client-to-server direction, as oriented by
+    /// [`FlowKey::canonicalize_tcp`] on the first segment seen.
+    pub key: FlowKey,
+    /// Client-to-server and server-to-client sides.
+    pub sides: [TcpSide; 2],
+    /// Time of the last segment, in either direction.
+    pub last_seen: Duration,
+    /// Time from the SYN to the ACK of the SYN-ACK.
+    pub handshake_rtt: Option<Duration>,
+    /// Side and time of the last SYN.
+    syn: Option<(usize, Duration)>,
+    syn_ack: Option<Duration>,
+}
+
+impl TcpConn {
+    /// Index in `sides` of the direction of `key`, one of the directions
+    /// of the connection.
+    #[inline]
+    pub fn side(&self, key: &FlowKey) -> usize {
+        (*key != self.key) as usize
+    }
+}
+
+/// A TCP segment, as seen by [`TcpTracker::segment`].
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub struct TcpSegment {
+    pub seq: u32,
+    /// Acknowledgment number, if the ACK flag is set.
+    pub ack: Option<u32>,
+    /// TCP flags, of which only FIN, SYN and RST are used.
+    pub flags: u8,
+    /// Payload length.
+    pub len: u32,
+    /// TSval and TSecr of the timestamps option.
+    pub timestamps: Option<(u32, u32)>,
+}
+
+impl TcpSegment {
+    /// Segment without timestamps.
+    #[inline]
+    pub const fn new(seq: u32, ack: Option<u32>, flags: u8, len: u32) -> Self {
+        Self {
+            seq,
+            ack,
+            flags,
+            len,
+            timestamps: None,
+        }
+    }
+
+    #[inline]
+    pub const fn with_timestamps(self, tsval: u32, tsecr: u32) -> Self {
+        Self {
+            timestamps: Some((tsval, tsecr)),
+            ..self
+        }
+    }
+}
+
+/// TCP connection tracker, reporting anomalous segments to a callback.
+///
+/// ```
+/// use core::time::Duration;
+/// use ether_packet::tcptrack::{TcpEventKind, TcpSegment, TcpTracker};
+///
+/// # let key = ether_packet::flow::FlowKey {
+/// #     src_addr: "10.0.0.1".parse().unwrap(),
+/// #     dst_addr: "10.0.0.2".parse().unwrap(),
+/// #     proto: ether_packet::ip::IpProto::Tcp,
+/// #     src_port: 40000,
+/// #     dst_port: 80,
+/// # };
+/// let mut events = Vec::new();
+/// let mut tracker = TcpTracker::new(Duration::from_millis(3), |event| events.push(event.kind));
+/// let segment = TcpSegment::new(1000, None, 0x18, 100);
+/// tracker.segment(&key, &segment, Duration::from_millis(0));
+/// tracker.segment(&key, &segment, Duration::from_millis(200));
+/// drop(tracker);
+/// assert_eq!(events, [TcpEventKind::Retransmission]);
+/// ```
+pub struct TcpTracker<F: FnMut(TcpEvent)> {
+    conns: BTreeMap<FlowKey, TcpConn>,
+    reorder_threshold: Duration,
+    on_event: F,
+}
+
+impl<F: FnMut(TcpEvent)> TcpTracker<F> {
+    /// Creates an empty tracker. Segments arriving less than
+    /// `reorder_threshold` after the highest one of their direction are
+    /// out of order rather than retransmitted.
+    pub fn new(reorder_threshold: Duration, on_event: F) -> Self {
+        Self {
+            conns: BTreeMap::new(),
+            reorder_threshold,
+            on_event,
+        }
+    }
+
+    /// Key under which the connection of `key` is stored, if it is
+    /// tracked.
+    fn conn_key(&self, key: &FlowKey) -> Option<FlowKey> {
+        [*key, key.reversed()]
+            .into_iter()
+            .find(|key| self.conns.contains_key(key))
+    }
+
+    /// Connection of the segments of `key`, in either direction.
+    #[inline]
+    pub fn get(&self, key: &FlowKey) -> Option<&TcpConn> {
+        self.conns.get(&self.conn_key(key)?)
+    }
+
+    /// Counters of the direction of `key`.
+    #[inline]
+    pub fn counters(&self, key: &FlowKey) -> Option<&TcpCounters> {
+        let conn = self.get(key)?;
+        Some(&conn.sides[conn.side(key)].counters)
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.conns.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.conns.is_empty()
+    }
+
+    /// Connections, keyed by their client-to-server direction.
+    pub fn iter(&self) -> impl Iterator<Item = (&FlowKey, &TcpConn)> {
+        self.conns.iter()
+    }
+
+    /// Stops tracking the connection of `key`.
+    pub fn remove(&mut self, key: &FlowKey) -> Option<TcpConn> {
+        self.conns.remove(&self.conn_key(key)?)
+    }
+
+    /// Removes the connections idle for `idle` at time `now`.
+    pub fn expire(&mut self, now: Duration, idle: Duration) {
+        self.conns
+            .retain(|_, conn| now.saturating_sub(conn.last_seen) < idle);
+    }
+
+    /// Tracks the TCP segment of an IP packet, ignoring the other packets.
+    ///
+    /// Returns the kind of the segment if it is anomalous. The payload
+    /// length is taken from the captured bytes, so truncated packets are
+    /// not supported.
+    pub fn packet(&mut self, packet: &Packet<'_>, now: Duration) -> Option<TcpEventKind> {
+        let (Some(network), Some(TransportHdr::Tcp(tcp))) = (packet.network(), packet.transport())
+        else {
+            return None;
+        };
+        let key = FlowKey {
+            src_addr: network.src_addr(),
+            dst_addr: network.dst_addr(),
+            proto: IpProto::Tcp,
+            src_port: tcp.source.to_bits(),
+            dst_port: tcp.dest.to_bits(),
+        };
+        let l4 = packet.payload();
+        let options = &l4[TcpHdr::LEN..tcp.hdrlen()];
+        let len = l4.len() - tcp.hdrlen();
+        self.tcp(&key, tcp, options, len as u32, now)
+    }
+
+    /// Tracks a segment of `key` with header `tcp`, followed by `options`
+    /// and `len` bytes of payload.
+    #[inline]
+    pub fn tcp(
+        &mut self,
+        key: &FlowKey,
+        tcp: &TcpHdr,
+        options: &[u8],
+        len: u32,
+        now: Duration,
+    ) -> Option<TcpEventKind> {
+        let segment = TcpSegment {
+            seq: tcp.seq.to_bits(),
+            ack: (tcp.ack() != 0).then(|| tcp.ack_seq.to_bits()),
+            flags: (tcp.fin() | tcp.syn() << 1 | tcp.rst() << 2) as u8,
+            len,
+            timestamps: tcp::timestamps(options),
+        };
+        self.segment(key, &segment, now)
+    }
+
+    /// Tracks a segment of `key`.
+    pub fn segment(
+        &mut self,
+        key: &FlowKey,
+        segment: &TcpSegment,
+        now: Duration,
+    ) -> Option<TcpEventKind> {
+        const FIN: u8 = 0x01;
+        const SYN: u8 = 0x02;
+        const RST: u8 = 0x04;
+        const ACK: u8 = 0x10;
+
+        let TcpSegment {
+            seq,
+            ack,
+            flags,
+            len,
+            timestamps,
+        } = *segment;
+        let conn_key = self.conn_key(key).unwrap_or_else(|| {
+            let ack_flag = if ack.is_some() { ACK } else { 0 };
+            key.canonicalize_tcp(flags | ack_flag).0
+        });
+        let conn = self.conns.entry(conn_key).or_insert_with(|| TcpConn {
+            key: conn_key,
+            sides: Default::default(),
+            last_seen: now,
+            handshake_rtt: None,
+            syn: None,
+            syn_ack: None,
+        });
+        conn.last_seen = now;
+        let index = conn.side(key);
+        let [first, second] = &mut conn.sides;
+        let (side, peer) = if index == 0 {
+            (first, second)
+        } else {
+            (second, first)
+        };
+        side.counters.segments += 1;
+        side.counters.bytes += len as u64;
+
+        // SYN and FIN take a sequence number each.
+        let seq_len = len + (flags & SYN != 0) as u32 + (flags & FIN != 0) as u32;
+        let mut kind = None;
+        if flags & RST == 0 && seq_len > 0 {
+            let end = seq.wrapping_add(seq_len);
+            match side.next_seq {
+                Some(next) if !seq_lt(next, end) => {
+                    let found = if peer.last_ack.is_some_and(|ack| !seq_lt(ack, end)) {
+                        TcpEventKind::SpuriousRetransmission
+                    } else if peer.dup_ack_run >= 2 && peer.last_ack == Some(seq) {
+                        TcpEventKind::FastRetransmission
+                    } else if now.saturating_sub(side.last_advance) < self.reorder_threshold {
+                        TcpEventKind::OutOfOrder
+                    } else {
+                        TcpEventKind::Retransmission
+                    };
+                    let counters = &mut side.counters;
+                    match found {
+                        TcpEventKind::OutOfOrder => counters.out_of_order += 1,
+                        TcpEventKind::FastRetransmission => counters.fast_retransmissions += 1,
+                        TcpEventKind::SpuriousRetransmission => {
+                            counters.spurious_retransmissions += 1
+                        }
+                        TcpEventKind::Retransmission => {}
+                    }
+                    if found != TcpEventKind::OutOfOrder {
+                        counters.retransmissions += 1;
+                        // The ACK of a retransmitted segment may be for
+                        // either copy (Karn's algorithm).
+                        side.seq_probe = None;
+                    }
+                    kind = Some(found);
+                }
+                _ => {
+                    side.next_seq = Some(end);
+                    side.last_advance = now;
+                    if side.seq_probe.is_none() && timestamps.is_none() {
+                        side.seq_probe = Some((end, now));
+                    }
+                }
+            }
+        }
+
+        if let Some(ack) = ack {
+            if let Some((end, sent)) = peer.seq_probe {
+                if !seq_lt(ack, end) {
+                    peer.rtt.update(now.saturating_sub(sent));
+                    peer.seq_probe = None;
+                }
+            }
+            match side.last_ack {
+                // A pure ACK repeating the previous one.
+                Some(last) if last == ack && seq_len == 0 && flags & RST == 0 => {
+                    side.dup_ack_run += 1;
+                    side.counters.dup_acks += 1;
+                }
+                Some(last) if !seq_lt(last, ack) => {}
+                _ => {
+                    side.last_ack = Some(ack);
+                    side.dup_ack_run = 0;
+                }
+            }
+        }
+
+        if let Some((tsval, tsecr)) = timestamps {
+            if let Some((probe, sent)) = peer.ts_probe {
+                if tsecr == probe {
+                    peer.rtt.update(now.saturating_sub(sent));
+                    peer.ts_probe = None;
+                } else if ack.is_some() && seq_lt(probe, tsecr) {
+                    peer.ts_probe = None;
+                }
+            }
+            // Pure ACKs are not answered, so only segments taking sequence
+            // numbers are timed, from the first one with a new TSval.
+            let new = side.last_tsval.is_none_or(|last| seq_lt(last, tsval));
+            if seq_len > 0 && new && side.ts_probe.is_none() {
+                side.ts_probe = Some((tsval, now));
+            }
+            if new {
+                side.last_tsval = Some(tsval);
+            }
+        }
+
+        if flags & SYN != 0 {
+            if ack.is_none() {
+                conn.syn = Some((index, now));
+                conn.syn_ack = None;
+            } else if conn.syn.is_some_and(|(syn_side, _)| syn_side != index) {
+                conn.syn_ack = Some(now);
+            }
+        } else if let (Some((syn_side, syn)), Some(_), None) =
+            (conn.syn, conn.syn_ack, conn.handshake_rtt)
+        {
+            // The ACK of the SYN-ACK completes the handshake.
+            if syn_side == index && ack.is_some() && ack == conn.sides[1 - index].next_seq {
+                conn.handshake_rtt = Some(now.saturating_sub(syn));
+            }
+        }
+
+        if let Some(kind) = kind {
+            (self.on_event)(TcpEvent {
+                key: *key,
+                kind,
+                seq,
+                len,
+                timestamp: now,
+            });
+        }
+        kind
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use core::{net::Ipv4Addr, time::Duration};
+
+    use super::{TcpEventKind, TcpSegment, TcpTracker};
+    use crate::{flow::FlowKey, ip::IpProto, tcp};
+
+    #[test]
+    fn test_tracker() {
+        let key = FlowKey {
+            src_addr: Ipv4Addr::new(10, 0, 0, 2).into(),
+            dst_addr: Ipv4Addr::new(10, 0, 0, 1).into(),
+            proto: IpProto::Tcp,
+            src_port: 80,
+            dst_port: 40000,
+        };
+        let back = key.reversed();
+        let ms = Duration::from_millis;
+        let mut events = Vec::new();
+        let mut tracker = TcpTracker::new(ms(3), |event| events.push((event.kind, event.seq)));
+
+        // Handshake.
+        assert_eq!(
+            tracker.segment(&back, &TcpSegment::new(99, None, 0x02, 0), ms(0)),
+            None
+        );
+        assert_eq!(
+            tracker.segment(&key, &TcpSegment::new(999, Some(100), 0x12, 0), ms(1)),
+            None
+        );
+        assert_eq!(
+            tracker.segment(&back, &TcpSegment::new(100, Some(1000), 0x10, 0), ms(2)),
+            None
+        );
+
+        // Segments 1000 and 2000 are reordered, 3000 is lost.
+        tracker.segment(&key, &TcpSegment::new(2000, Some(100), 0x10, 1000), ms(10));
+        tracker.segment(&key, &TcpSegment::new(1000, Some(100), 0x10, 1000), ms(11));
+        tracker.segment(&key, &TcpSegment::new(4000, Some(100), 0x10, 1000), ms(12));
+        for t in 13..16 {
+            tracker.segment(&back, &TcpSegment::new(100, Some(3000), 0x10, 0), ms(t));
+        }
+        tracker.segment(&key, &TcpSegment::new(3000, Some(100), 0x10, 1000), ms(20));
+        tracker.segment(&back, &TcpSegment::new(100, Some(5000), 0x10, 0), ms(30));
+        // Timeout of the sender while the ACK was in flight.
+        tracker.segment(&key, &TcpSegment::new(4000, Some(100), 0x10, 1000), ms(300));
+        // Retransmission of the FIN.
+        tracker.segment(&key, &TcpSegment::new(5000, Some(100), 0x11, 0), ms(400));
+        tracker.segment(&key, &TcpSegment::new(5000, Some(100), 0x11, 0), ms(800));
+
+        let counters = tracker.counters(&key).unwrap();
+        assert_eq!(
+            (
+                counters.segments,
+                counters.retransmissions,
+                counters.out_of_order
+            ),
+            (8, 3, 1)
+        );
+        assert_eq!(tracker.counters(&back).unwrap().dup_acks, 2);
+        let conn = tracker.get(&key).unwrap();
+        assert_eq!(conn.handshake_rtt, Some(ms(2)));
+        // The SYN was sent by `back`, the client.
+        assert_eq!((conn.key, conn.side(&key)), (back, 1));
+        let rtt = conn.sides[conn.side(&key)].rtt;
+        assert_eq!(
+            (rtt.samples, rtt.min, rtt.last),
+            (2, Some(ms(1)), Some(ms(3)))
+        );
+        assert_eq!(rtt.avg, Some(Duration::from_micros(1250)));
+
+        // RTT from the timestamps, on another connection.
+        let key = FlowKey {
+            dst_port: 40001,
+            ..key
+        };
+        let back = key.reversed();
+        let segments = [
+            (
+                back,
+                TcpSegment::new(0, None, 0x02, 0).with_timestamps(100, 0),
+                0,
+            ),
+            (
+                key,
+                TcpSegment::new(0, Some(1), 0x12, 0).with_timestamps(500, 100),
+                2,
+            ),
+            (
+                back,
+                TcpSegment::new(1, Some(1), 0x10, 0).with_timestamps(101, 500),
+                4,
+            ),
+            (
+                key,
+                TcpSegment::new(1, Some(1), 0x10, 100).with_timestamps(502, 101),
+                10,
+            ),
+            (
+                back,
+                TcpSegment::new(1, Some(101), 0x10, 0).with_timestamps(103, 502),
+                15,
+            ),
+        ];
+        for (key, segment, t) in segments {
+            tracker.segment(&key, &segment, ms(t));
+        }
+        let conn = tracker.get(&back).unwrap();
+        assert_eq!(conn.handshake_rtt, Some(ms(4)));
+        let rtt = conn.sides[conn.side(&key)].rtt;
+        assert_eq!(
+            (rtt.samples, rtt.min, rtt.last),
+            (2, Some(ms(2)), Some(ms(5)))
+        );
+        assert_eq!(conn.sides[conn.side(&back)].rtt.last, Some(ms(2)));
+
+        // A client on a lower port than its server is oriented by its SYN,
+        // and its connection is found from the other segments.
+        let client = FlowKey {
+            src_port: 80,
+            dst_port: 443,
+            ..key
+        };
+        tracker.segment(&client, &TcpSegment::new(0, None, 0x02, 0), ms(20));
+        tracker.segment(
+            &client.reversed(),
+            &TcpSegment::new(0, Some(1), 0x12, 0),
+            ms(21),
+        );
+        tracker.segment(&client, &TcpSegment::new(1, Some(1), 0x10, 0), ms(22));
+        let conn = tracker.get(&client.reversed()).unwrap();
+        assert_eq!((conn.key, conn.handshake_rtt), (client, Some(ms(2))));
+        assert_eq!(conn.sides[0].counters.segments, 2);
+        assert_eq!(
+            client.canonicalize_tcp(0x02),
+            (conn.key, crate::flow::Direction::ClientToServer)
+        );
+        assert!(tracker.remove(&client.reversed()).is_some());
+        let options = [2, 4, 5, 180, 1, 8, 10, 0, 0, 0, 1, 0, 0, 0, 2];
+        assert_eq!(tcp::timestamps(&options), Some((1, 2)));
+        assert_eq!(tcp::timestamps(&options[..12]), None);
+
+        assert_eq!(tracker.len(), 2);
+        tracker.expire(ms(10_000), ms(5_000));
+        assert!(tracker.is_empty());
+        drop(tracker);
+        assert_eq!(
+            events,
+            [
+                (TcpEventKind::OutOfOrder, 1000),
+                (TcpEventKind::FastRetransmission, 3000),
+                (TcpEventKind::SpuriousRetransmission, 4000),
+                (TcpEventKind::Retransmission, 5000),
+            ]
+        );
+    }
+}
@@ -0,0 +1,437 @@
+//! Precomputed header rewrites for load balancer and NAT hot paths.
+//!
+//! A load balancer rewrites every packet of a virtual service the same way:
+//! the destination MAC address for direct server return, the destination
+//! address and port for NAT. [`TemplateBuilder`] computes the checksum
+//! delta of such a rewrite once, so that [`RewriteTemplate::apply`] only
+//! copies the new fields and adds the delta to the checksums, instead of
+//! going through [`PacketEditor`](crate::edit::PacketEditor).
+
+use core::{fmt, net::IpAddr};
+
+use crate::{
+    checksum,
+    eth::{EthHdr, EtherType},
+    ip::IpProto,
+};
+
+/// Error returned when building or applying a [`RewriteTemplate`], in
+/// which case the frame is left untouched.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum TemplateError {
+    /// The frame is truncated or its headers are inconsistent.
+    Malformed,
+    /// The addresses of the template, or the frame, are not of the same
+    /// family.
+    AddressFamily,
+    /// The frame does not hold the original values of the template.
+    Mismatch,
+    /// Ports cannot be rewritten in a non-first fragment.
+    Fragment,
+    /// The frame has IPv6 extension headers, or its protocol has no ports
+    /// to rewrite.
+    Unsupported,
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateError::Malformed => f.write_str("malformed frame"),
+            TemplateError::AddressFamily => f.write_str("address family mismatch"),
+            TemplateError::Mismatch => f.write_str("frame does not match the template"),
+            TemplateError::Fragment => f.write_str("port rewrite on a fragment"),
+            TemplateError::Unsupported => f.write_str("unsupported frame"),
+        }
+    }
+}
+
+/// Ones' complement sum of the 16-bit words of `old` complemented and of
+/// `new`, i.e. the checksum delta of replacing `old` with `new`.
+fn delta(acc: u32, old: &[u8], new: &[u8]) -> u32 {
+    let acc = old
+        .chunks_exact(2)
+        .fold(acc, |acc, word| checksum::sum(&[!word[0], !word[1]], acc));
+    checksum::sum(new, acc)
+}
+
+/// Folds a ones' complement sum into 16 bits, without complementing it.
+#[inline]
+const fn fold16(sum: u32) -> u16 {
+    !checksum::fold(sum)
+}
+
+/// Adds the precomputed `delta` to the checksum at `at` in `data`.
+#[inline]
+fn adjust(data: &mut [u8], at: usize, delta: u16) -> u16 {
+    let check = u16::from_be_bytes([data[at], data[at + 1]]);
+    let check = checksum::fold(!check as u32 + delta as u32);
+    data[at..at + 2].copy_from_slice(&check.to_be_bytes());
+    check
+}
+
+#[inline]
+fn octets(addr: &IpAddr) -> ([u8; 16], usize) {
+    match addr {
+        IpAddr::V4(addr) => {
+            let mut octets = [0; 16];
+            octets[..4].copy_from_slice(&addr.octets());
+            (octets, 4)
+        }
+        IpAddr::V6(addr) => (addr.octets(), 16),
+    }
+}
+
+/// Builds a [`RewriteTemplate`] from the original and new values of the
+/// rewritten fields.
+#[derive(Debug, Clone, Default)]
+pub struct TemplateBuilder {
+    src_mac: Option<[u8; 6]>,
+    dst_mac: Option<[u8; 6]>,
+    src_addr: Option<(IpAddr, IpAddr)>,
+    dst_addr: Option<(IpAddr, IpAddr)>,
+    src_port: Option<(u16, u16)>,
+    dst_port: Option<(u16, u16)>,
+}
+
+impl TemplateBuilder {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the source MAC address, which is not covered by any checksum.
+    pub fn src_mac(&mut self, mac: [u8; 6]) -> &mut Self {
+        self.src_mac = Some(mac);
+        self
+    }
+
+    /// Sets the destination MAC address, e.g. of the real server with
+    /// direct server return.
+    pub fn dst_mac(&mut self, mac: [u8; 6]) -> &mut Self {
+        self.dst_mac = Some(mac);
+        self
+    }
+
+    /// Rewrites the source address `old` to `new`.
+    pub fn src_addr(&mut self, old: IpAddr, new: IpAddr) -> &mut Self {
+        self.src_addr = Some((old, new));
+        self
+    }
+
+    /// Rewrites the destination address `old`, e.g. the virtual IP of a
+    /// service, to `new`.
+    pub fn dst_addr(&mut self, old: IpAddr, new: IpAddr) -> &mut Self {
+        self.dst_addr = Some((old, new));
+        self
+    }
+
+    /// Rewrites the TCP or UDP source port `old` to `new`.
+    pub fn src_port(&mut self, old: u16, new: u16) -> &mut Self {
+        self.src_port = Some((old, new));
+        self
+    }
+
+    /// Rewrites the TCP or UDP destination port `old` to `new`.
+    pub fn dst_port(&mut self, old: u16, new: u16) -> &mut Self {
+        self.dst_port = Some((old, new));
+        self
+    }
+
+    /// Computes the template, failing if its addresses are not all of the
+    /// same family.
+    pub fn build(&self) -> Result<RewriteTemplate, TemplateError> {
+        let addrs = [self.src_addr, self.dst_addr].into_iter().flatten();
+        let v6 = addrs.clone().next().map(|(old, _)| old.is_ipv6());
+        if !addrs
+            .clone()
+            .all(|(old, new)| Some(old.is_ipv6()) == v6 && Some(new.is_ipv6()) == v6)
+        {
+            return Err(TemplateError::AddressFamily);
+        }
+
+        let mut addr_delta = 0;
+        for (old, new) in addrs {
+            let ((old, len), (new, _)) = (octets(&old), octets(&new));
+            addr_delta = delta(addr_delta, &old[..len], &new[..len]);
+        }
+        let mut port_delta = 0;
+        for (old, new) in [self.src_port, self.dst_port].into_iter().flatten() {
+            port_delta = delta(port_delta, &old.to_be_bytes(), &new.to_be_bytes());
+        }
+        Ok(RewriteTemplate {
+            src_mac: self.src_mac,
+            dst_mac: self.dst_mac,
+            v6,
+            src_addr: self.src_addr,
+            dst_addr: self.dst_addr,
+            src_port: self.src_port,
+            dst_port: self.dst_port,
+            addr_delta: fold16(addr_delta),
+            port_delta: fold16(port_delta),
+        })
+    }
+}
+
+/// A precomputed rewrite of the Ethernet, IP and TCP or UDP headers.
+///
+/// ```
+/// use core::net::Ipv4Addr;
+/// use ether_packet::{checksum, template::TemplateBuilder};
+///
+/// let vip = Ipv4Addr::new(192, 0, 2, 10);
+/// let real = Ipv4Addr::new(10, 0, 0, 7);
+/// let template = TemplateBuilder::new()
+///     .dst_mac([2, 0, 0, 0, 0, 7])
+///     .dst_addr(vip.into(), real.into())
+///     .dst_port(80, 8080)
+///     .build()
+///     .unwrap();
+///
+/// #[rustfmt::skip]
+/// let mut frame = [
+///     0, 1, 2, 3, 4, 5, 0, 6, 7, 8, 9, 10, 0x08, 0x00,
+///     0x45, 0, 0, 28, 0, 0, 0, 0, 64, 17, 0, 0, 198, 51, 100, 1, 192, 0, 2, 10,
+///     0x30, 0x39, 0, 80, 0, 8, 0, 0,
+/// ];
+/// let check = checksum::checksum(&frame[14..34]);
+/// frame[24..26].copy_from_slice(&check.to_be_bytes());
+///
+/// template.apply(&mut frame).unwrap();
+/// assert_eq!(&frame[..6], &[2, 0, 0, 0, 0, 7]);
+/// assert_eq!(&frame[30..34], &real.octets());
+/// assert_eq!(&frame[36..38], &8080u16.to_be_bytes());
+/// assert_eq!(checksum::checksum(&frame[14..34]), 0);
+/// // Already rewritten, the frame no longer matches.
+/// assert!(template.apply(&mut frame).is_err());
+/// ```
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub struct RewriteTemplate {
+    src_mac: Option<[u8; 6]>,
+    dst_mac: Option<[u8; 6]>,
+    /// Address family of the IP rewrites, if any.
+    v6: Option<bool>,
+    src_addr: Option<(IpAddr, IpAddr)>,
+    dst_addr: Option<(IpAddr, IpAddr)>,
+    src_port: Option<(u16, u16)>,
+    dst_port: Option<(u16, u16)>,
+    /// Checksum delta of the addresses, for the IPv4 header and the
+    /// pseudo-header.
+    addr_delta: u16,
+    port_delta: u16,
+}
+
+impl RewriteTemplate {
+    #[inline]
+    fn rewrites_ports(&self) -> bool {
+        self.src_port.is_some() || self.dst_port.is_some()
+    }
+
+    /// Whether the IP header `ip` and the upper-layer header `l4` hold the
+    /// original values of the template.
+    fn matches(&self, ip: &[u8], l4: &[u8], src_at: usize) -> bool {
+        let addr_ok = |at: usize, addr: Option<(IpAddr, IpAddr)>| match addr {
+            Some((old, _)) => {
+                let (old, len) = octets(&old);
+                ip[at..at + len] == old[..len]
+            }
+            None => true,
+        };
+        let port_ok = |at: usize, port: Option<(u16, u16)>| match port {
+            Some((old, _)) => l4[at..at + 2] == old.to_be_bytes(),
+            None => true,
+        };
+        let addr_len = if self.v6 == Some(true) { 16 } else { 4 };
+        addr_ok(src_at, self.src_addr)
+            && addr_ok(src_at + addr_len, self.dst_addr)
+            && port_ok(0, self.src_port)
+            && port_ok(2, self.dst_port)
+    }
+
+    /// Rewrites `frame`, an Ethernet frame possibly carrying VLAN tags,
+    /// updating the IPv4 header checksum and the TCP, UDP or ICMPv6
+    /// checksum incrementally.
+    ///
+    /// Fails if the frame does not hold the original addresses and ports of
+    /// the template.
+    pub fn apply(&self, frame: &mut [u8]) -> Result<(), TemplateError> {
+        if frame.len() < EthHdr::LEN {
+            return Err(TemplateError::Malformed);
+        }
+        if self.v6.is_none() && !self.rewrites_ports() {
+            self.apply_eth(frame);
+            return Ok(());
+        }
+
+        let mut pos = EthHdr::LEN - 2;
+        let ether_type = loop {
+            let ether_type = frame.get(pos..pos + 2).ok_or(TemplateError::Malformed)?;
+            let ether_type = u16::from_be_bytes([ether_type[0], ether_type[1]]);
+            if ether_type != EtherType::VLAN as u16 && ether_type != EtherType::QinQ as u16 {
+                break ether_type;
+            }
+            pos += 4;
+        };
+        let l3 = pos + 2;
+        let ip = frame.get(l3..).ok_or(TemplateError::Malformed)?;
+        // Port rewrites alone work with either family.
+        let (v6, hdr_len, proto, first_fragment) = match ether_type {
+            t if t == EtherType::Ipv4 as u16 && self.v6 != Some(true) => {
+                let ihl = (*ip.first().ok_or(TemplateError::Malformed)? & 0x0f) as usize * 4;
+                if ihl < 20 || ip.len() < ihl {
+                    return Err(TemplateError::Malformed);
+                }
+                let frag_off = u16::from_be_bytes([ip[6], ip[7]]);
+                (false, ihl, ip[9], frag_off & 0x1fff == 0)
+            }
+            t if t == EtherType::Ipv6 as u16 && self.v6 != Some(false) => {
+                if ip.len() < 40 {
+                    return Err(TemplateError::Malformed);
+                }
+                (true, 40, ip[6], true)
+            }
+            t if t == EtherType::Ipv4 as u16 || t == EtherType::Ipv6 as u16 => {
+                return Err(TemplateError::AddressFamily)
+            }
+            _ => return Err(TemplateError::Mismatch),
+        };
+
+        let proto = IpProto::from_u8(proto);
+        let l4_len =
+            match proto {
+                _ if !first_fragment => 0,
+                Some(IpProto::Tcp) => 20,
+                Some(IpProto::Udp) => 8,
+                Some(IpProto::Ipv6Icmp) if v6 => 4,
+                Some(
+                    IpProto::HopOpt | IpProto::Ipv6Route | IpProto::Ipv6Opts | IpProto::Ipv6Frag,
+                ) if v6 => return Err(TemplateError::Unsupported),
+                _ => 0,
+            };
+        if self.rewrites_ports() {
+            if !first_fragment {
+                return Err(TemplateError::Fragment);
+            }
+            if !matches!(proto, Some(IpProto::Tcp | IpProto::Udp)) {
+                return Err(TemplateError::Unsupported);
+            }
+        }
+        let l4 = l3 + hdr_len;
+        if frame.len() < l4 + l4_len {
+            return Err(TemplateError::Malformed);
+        }
+        let src_at = if v6 { 8 } else { 12 };
+        let (ip, l4_hdr) = frame[l3..].split_at(hdr_len);
+        if !self.matches(ip, l4_hdr, src_at) {
+            return Err(TemplateError::Mismatch);
+        }
+
+        // Everything was validated, the frame can now be modified.
+        self.apply_eth(frame);
+        let (ip, l4_hdr) = frame[l3..].split_at_mut(hdr_len);
+        let addr_len = if v6 { 16 } else { 4 };
+        for (at, addr) in [(src_at, self.src_addr), (src_at + addr_len, self.dst_addr)] {
+            if let Some((_, new)) = addr {
+                let (new, len) = octets(&new);
+                ip[at..at + len].copy_from_slice(&new[..len]);
+            }
+        }
+        if !v6 && self.v6.is_some() {
+            adjust(ip, 10, self.addr_delta);
+        }
+        for (at, port) in [(0, self.src_port), (2, self.dst_port)] {
+            if let Some((_, new)) = port {
+                l4_hdr[at..at + 2].copy_from_slice(&new.to_be_bytes());
+            }
+        }
+        let l4_delta = fold16(self.addr_delta as u32 + self.port_delta as u32);
+        match proto {
+            _ if l4_len == 0 => {}
+            Some(IpProto::Tcp) => {
+                adjust(l4_hdr, 16, l4_delta);
+            }
+            // A zero UDP checksum over IPv4 means no checksum.
+            Some(IpProto::Udp) if !v6 && l4_hdr[6..8] == [0, 0] => {}
+            Some(IpProto::Udp) => {
+                if adjust(l4_hdr, 6, l4_delta) == 0 {
+                    l4_hdr[6..8].copy_from_slice(&[0xff, 0xff]);
+                }
+            }
+            _ => {
+                adjust(l4_hdr, 2, self.addr_delta);
+            }
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn apply_eth(&self, frame: &mut [u8]) {
+        if let Some(mac) = self.dst_mac {
+            frame[..6].copy_from_slice(&mac);
+        }
+        if let Some(mac) = self.src_mac {
+            frame[6..12].copy_from_slice(&mac);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::net::Ipv6Addr;
+
+    use super::{TemplateBuilder, TemplateError};
+    use crate::{checksum, ip::IpProto};
+
+    #[test]
+    fn test_rewrite_template() {
+        let client = Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1);
+        let vip = Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0x80);
+        let real = Ipv6Addr::new(0x2001, 0xdb8, 1, 0, 0, 0, 0, 7);
+        #[rustfmt::skip]
+        let mut frame = [
+            0, 1, 2, 3, 4, 5, 0, 6, 7, 8, 9, 10,
+            0x81, 0x00, 0, 100, 0x86, 0xdd,
+            0x60, 0, 0, 0, 0, 24, 6, 64,
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0x30, 0x39, 1, 187, 0, 0, 0, 1, 0, 0, 0, 0, 0x50, 0x18, 0xff, 0xff, 0, 0, 0, 0,
+            b'a', b'b', b'c', b'd',
+        ];
+        frame[26..42].copy_from_slice(&client.octets());
+        frame[42..58].copy_from_slice(&vip.octets());
+        let check = checksum::l4_checksum_v6(client, vip, IpProto::Tcp, &frame[58..]);
+        frame[74..76].copy_from_slice(&check.to_be_bytes());
+        let orig = frame;
+
+        let template = TemplateBuilder::new()
+            .dst_addr(vip.into(), real.into())
+            .dst_port(443, 8443)
+            .build()
+            .unwrap();
+        template.apply(&mut frame).unwrap();
+        assert_eq!(&frame[42..58], &real.octets());
+        assert!(checksum::verify_l4_v6(
+            client,
+            real,
+            IpProto::Tcp,
+            &frame[58..]
+        ));
+
+        // Only matching frames are rewritten.
+        let mut other = orig;
+        other[61] = 80;
+        assert_eq!(template.apply(&mut other), Err(TemplateError::Mismatch));
+        assert_eq!(
+            template.apply(&mut other[..60]),
+            Err(TemplateError::Malformed)
+        );
+        let v4 = TemplateBuilder::new()
+            .src_addr([10, 0, 0, 1].into(), [10, 0, 0, 2].into())
+            .build()
+            .unwrap();
+        assert_eq!(v4.apply(&mut other), Err(TemplateError::AddressFamily));
+        let mixed = TemplateBuilder::new()
+            .src_addr([10, 0, 0, 1].into(), real.into())
+            .build();
+        assert_eq!(mixed, Err(TemplateError::AddressFamily));
+    }
+}
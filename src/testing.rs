@@ -0,0 +1,195 @@
+//! Conformance checks for [`Header`] implementations.
+//!
+//! A header round-trips when the bytes it is parsed from are the bytes it
+//! serializes back to, and when every buffer too short to hold it is
+//! reported as [`ParseError::Truncated`]. [`assert_roundtrip`] checks a
+//! single buffer, [`check_corpus`] a set of samples, and
+//! [`check_builtin_corpus`] runs the samples of the headers of this crate,
+//! so that a new protocol can be held to the same checks.
+
+use core::{fmt, mem};
+
+use crate::{
+    arp::ArpHdr,
+    bfd::BfdControlHdr,
+    dns::DnsHdr,
+    eth::{EthHdr, VlanHdr},
+    geneve::GeneveHdr,
+    gre::GreHdr,
+    header::{Header, ParseError},
+    icmp::IcmpHdr,
+    icmpv6::Icmpv6Hdr,
+    igmp::IgmpHdr,
+    ip::{Ipv4Hdr, Ipv6Hdr},
+    mpls::MplsHdr,
+    sctp::SctpHdr,
+    tcp::TcpHdr,
+    udp::UdpHdr,
+    vxlan::VxlanHdr,
+};
+
+/// Why a buffer does not round-trip.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum RoundtripError {
+    /// The buffer is shorter than the header.
+    TooShort,
+    /// The header rejected the buffer.
+    Rejected,
+    /// The serialized header differs from the buffer at this offset.
+    Mismatch { offset: usize },
+    /// A buffer of this length, too short for the header, was not reported
+    /// as truncated.
+    Truncation { len: usize },
+}
+
+impl fmt::Display for RoundtripError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RoundtripError::TooShort => f.write_str("buffer shorter than the header"),
+            RoundtripError::Rejected => f.write_str("header rejected"),
+            RoundtripError::Mismatch { offset } => write!(f, "byte {offset} differs"),
+            RoundtripError::Truncation { len } => write!(f, "{len} bytes not reported truncated"),
+        }
+    }
+}
+
+/// Parses the header at the start of `bytes`, serializes it back and
+/// compares the result with `bytes`, also checking that the shorter
+/// prefixes of `bytes` are reported as truncated.
+pub fn check_roundtrip<H: Header>(bytes: &[u8]) -> Result<H, RoundtripError> {
+    let len = mem::size_of::<H>();
+    if bytes.len() < len {
+        return Err(RoundtripError::TooShort);
+    }
+    let hdr = H::parse(bytes).map_err(|_| RoundtripError::Rejected)?;
+    let copy = H::read(bytes).ok_or(RoundtripError::Rejected)?;
+    for serialized in [hdr.as_bytes(), copy.as_bytes()] {
+        if let Some(offset) = (0..len).find(|&i| serialized[i] != bytes[i]) {
+            return Err(RoundtripError::Mismatch { offset });
+        }
+    }
+    if let Some(len) =
+        (0..len).find(|&n| !matches!(H::parse(&bytes[..n]), Err(ParseError::Truncated)))
+    {
+        return Err(RoundtripError::Truncation { len });
+    }
+    Ok(copy)
+}
+
+/// Like [`check_roundtrip`], but panics with the error.
+#[track_caller]
+pub fn assert_roundtrip<H: Header>(bytes: &[u8]) -> H {
+    match check_roundtrip(bytes) {
+        Ok(hdr) => hdr,
+        Err(err) => panic!("{} does not round-trip: {err}", core::any::type_name::<H>()),
+    }
+}
+
+/// Runs [`check_roundtrip`] on each sample of `corpus`, returning the index
+/// of the first failing sample.
+pub fn check_corpus<H: Header>(corpus: &[&[u8]]) -> CorpusResult {
+    for (i, bytes) in corpus.iter().enumerate() {
+        check_roundtrip::<H>(bytes).map_err(|err| (i, err))?;
+    }
+    Ok(())
+}
+
+/// Outcome of [`check_corpus`], with the index of the failing sample.
+pub type CorpusResult = Result<(), (usize, RoundtripError)>;
+
+/// Samples of a header type.
+#[derive(Debug, Copy, Clone)]
+pub struct CorpusEntry {
+    pub name: &'static str,
+    pub check: fn(&[&[u8]]) -> CorpusResult,
+    pub samples: &'static [&'static [u8]],
+}
+
+/// Sample of a header failing [`check_builtin_corpus`].
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub struct CorpusFailure {
+    pub name: &'static str,
+    pub index: usize,
+    pub error: RoundtripError,
+}
+
+macro_rules! corpus {
+    ($($ty:ty => [$($sample:expr),* $(,)?]),* $(,)?) => {
+        &[$(CorpusEntry {
+            name: stringify!($ty),
+            check: check_corpus::<$ty>,
+            samples: &[$(&$sample),*],
+        }),*]
+    };
+}
+
+/// Samples of the headers of this crate.
+#[rustfmt::skip]
+pub const BUILTIN_CORPUS: &[CorpusEntry] = corpus! {
+    EthHdr => [[0, 1, 2, 3, 4, 5, 0, 6, 7, 8, 9, 10, 0x08, 0x00]],
+    VlanHdr => [[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 2, 0, 0, 0, 0, 1, 0x81, 0x00, 0xa0, 0x64, 0x86, 0xdd]],
+    ArpHdr => [[0, 1, 0x08, 0x00, 6, 4, 0, 1, 2, 0, 0, 0, 0, 1, 10, 0, 0, 1, 0, 0, 0, 0, 0, 0, 10, 0, 0, 2]],
+    Ipv4Hdr => [
+        [0x45, 0, 0, 20, 0x12, 0x34, 0x40, 0, 64, 6, 0xab, 0xcd, 10, 0, 0, 1, 10, 0, 0, 2],
+        [0x46, 0xb8, 0, 24, 0, 0, 0x20, 0x10, 1, 17, 0, 0, 192, 0, 2, 1, 224, 0, 0, 5],
+    ],
+    Ipv6Hdr => [[
+        0x6b, 0x91, 0x23, 0x45, 0, 8, 17, 64,
+        0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1,
+        0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2,
+    ]],
+    TcpHdr => [
+        [0x04, 0xd2, 0, 80, 0, 0, 0, 1, 0, 0, 0, 0, 0x50, 0x02, 0xff, 0xff, 0x12, 0x34, 0, 0],
+        [0, 80, 0x04, 0xd2, 0xde, 0xad, 0xbe, 0xef, 0, 0, 0, 2, 0x80, 0x18, 0x01, 0xf5, 0, 0, 0, 0],
+    ],
+    UdpHdr => [[0x30, 0x39, 0, 53, 0, 8, 0xbe, 0xef]],
+    IcmpHdr => [[8, 0, 0xf7, 0xfd, 0, 1, 0, 1]],
+    Icmpv6Hdr => [[128, 0, 0x12, 0x34, 0, 1, 0, 1]],
+    SctpHdr => [[0x0b, 0x59, 0x0b, 0x59, 0, 0, 0, 0, 0x12, 0x34, 0x56, 0x78]],
+    IgmpHdr => [[0x16, 0, 0xfa, 0x04, 239, 1, 1, 1]],
+    GreHdr => [[0x20, 0, 0x65, 0x58]],
+    VxlanHdr => [[0x08, 0, 0, 0, 0, 0, 100, 0]],
+    GeneveHdr => [[0x01, 0, 0x65, 0x58, 0x12, 0x34, 0x56, 0]],
+    MplsHdr => [[0, 0x06, 0x41, 64]],
+    DnsHdr => [[0x12, 0x34, 0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0]],
+    BfdControlHdr => [[0x20, 0xc0, 3, 24, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0x0f, 0x42, 0x40, 0, 0x0f, 0x42, 0x40, 0, 0, 0, 0]],
+};
+
+/// Runs the samples of [`BUILTIN_CORPUS`], returning the number of samples
+/// checked.
+pub fn check_builtin_corpus() -> Result<usize, CorpusFailure> {
+    let mut count = 0;
+    for entry in BUILTIN_CORPUS {
+        (entry.check)(entry.samples).map_err(|(index, error)| CorpusFailure {
+            name: entry.name,
+            index,
+            error,
+        })?;
+        count += entry.samples.len();
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{assert_roundtrip, check_builtin_corpus, check_roundtrip, RoundtripError};
+    use crate::{tcp::TcpHdr, udp::UdpHdr};
+
+    #[test]
+    fn test_roundtrip() {
+        assert_eq!(check_builtin_corpus(), Ok(19));
+        let udp: UdpHdr = assert_roundtrip(&[0, 53, 0x30, 0x39, 0, 8, 0, 0, 0xff]);
+        assert_eq!(udp.source.to_bits(), 53);
+        // A data offset of 4 is invalid.
+        let mut tcp = [0u8; 20];
+        tcp[12] = 0x40;
+        assert_eq!(
+            check_roundtrip::<TcpHdr>(&tcp).err(),
+            Some(RoundtripError::Rejected)
+        );
+        assert_eq!(
+            check_roundtrip::<TcpHdr>(&tcp[..19]).err(),
+            Some(RoundtripError::TooShort)
+        );
+    }
+}
@@ -0,0 +1,500 @@
+//! Zero-copy capture through an `AF_PACKET` socket with a `TPACKET_V3`
+//! memory-mapped receive ring.
+//!
+//! The kernel fills the ring with blocks of packets, which [`Ring`] hands
+//! out one at a time as a [`Block`]. The frames of a block borrow the ring
+//! memory, and the block is given back to the kernel when dropped, so the
+//! borrow checker ensures no frame outlives its slot in the ring.
+//!
+//! ```no_run
+//! use std::time::Duration;
+//!
+//! use ether_packet::tpacket::{Ring, RingConfig};
+//!
+//! let mut ring = Ring::new(Some("eth0"), RingConfig::default())?;
+//! while let Some(block) = ring.next_block(Some(Duration::from_secs(1)))? {
+//!     for frame in block.frames() {
+//!         println!("{:?}: {} bytes", frame.meta.timestamp, frame.data.len());
+//!     }
+//! }
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use core::{
+    mem, ptr, slice,
+    sync::atomic::{fence, Ordering},
+    time::Duration,
+};
+use std::{
+    ffi::CString,
+    io,
+    os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd},
+};
+
+use crate::{
+    capture::Captured,
+    meta::{Direction, PacketMeta},
+    sll::SllPacketType,
+};
+
+const PACKET_RX_RING: libc::c_int = 5;
+const PACKET_STATISTICS: libc::c_int = 6;
+const PACKET_VERSION: libc::c_int = 10;
+const TPACKET_V3: libc::c_int = 2;
+
+const TP_STATUS_KERNEL: u32 = 0;
+const TP_STATUS_USER: u32 = 1 << 0;
+const TP_STATUS_VLAN_VALID: u32 = 1 << 4;
+const TP_FT_REQ_FILL_RXHASH: u32 = 1;
+
+/// `struct tpacket_req3`.
+#[repr(C)]
+struct TpacketReq3 {
+    block_size: u32,
+    block_nr: u32,
+    frame_size: u32,
+    frame_nr: u32,
+    retire_blk_tov: u32,
+    sizeof_priv: u32,
+    feature_req_word: u32,
+}
+
+/// `struct tpacket_stats_v3`.
+#[repr(C)]
+#[derive(Default)]
+struct TpacketStatsV3 {
+    packets: u32,
+    drops: u32,
+    freeze_q_cnt: u32,
+}
+
+// Offsets in `struct tpacket_block_desc`.
+const BLOCK_STATUS: usize = 8;
+const BLOCK_NUM_PKTS: usize = 12;
+const BLOCK_OFFSET_TO_FIRST_PKT: usize = 16;
+
+// Offsets in `struct tpacket3_hdr`, which is followed by a `sockaddr_ll`
+// at the next 16-byte boundary.
+const TPACKET3_HDRLEN: usize = 48;
+const SLL_IFINDEX: usize = TPACKET3_HDRLEN + 4;
+const SLL_PKTTYPE: usize = TPACKET3_HDRLEN + 10;
+
+#[inline]
+fn u32_at(b: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_ne_bytes(b.get(at..at + 4)?.try_into().ok()?))
+}
+
+#[inline]
+fn u16_at(b: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_ne_bytes(b.get(at..at + 2)?.try_into().ok()?))
+}
+
+/// Geometry of the receive ring.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub struct RingConfig {
+    /// Size of a block, a multiple of the page size.
+    pub block_size: u32,
+    pub block_count: u32,
+    /// Largest packet expected, which bounds the number of frames per block
+    /// the kernel accounts for.
+    pub frame_size: u32,
+    /// Delay after which the kernel hands out a block which is not full.
+    pub block_timeout: Duration,
+    /// Ask the kernel to report the RSS hash of each packet.
+    pub fill_rxhash: bool,
+}
+
+impl Default for RingConfig {
+    fn default() -> Self {
+        Self {
+            block_size: 1 << 20,
+            block_count: 64,
+            frame_size: 2048,
+            block_timeout: Duration::from_millis(100),
+            fill_rxhash: false,
+        }
+    }
+}
+
+/// Counters maintained by the kernel since the previous call to
+/// [`Ring::stats`].
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Default)]
+pub struct RingStats {
+    pub packets: u32,
+    pub drops: u32,
+    /// Number of times the ring was full, the kernel dropping packets until
+    /// a block was released.
+    pub freeze_count: u32,
+}
+
+/// `AF_PACKET` socket with a `TPACKET_V3` receive ring.
+#[derive(Debug)]
+pub struct Ring {
+    fd: OwnedFd,
+    map: *mut u8,
+    config: RingConfig,
+    current: usize,
+}
+
+// The ring memory is only accessed through `&mut Ring`.
+unsafe impl Send for Ring {}
+
+impl Ring {
+    /// Opens a socket capturing every protocol on `interface`, or on all
+    /// interfaces if `None`, and maps its receive ring. Requires
+    /// `CAP_NET_RAW`.
+    pub fn new(interface: Option<&str>, config: RingConfig) -> io::Result<Self> {
+        let protocol = (libc::ETH_P_ALL as u16).to_be();
+        let fd = unsafe { libc::socket(libc::AF_PACKET, libc::SOCK_RAW, protocol as libc::c_int) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+        setsockopt(fd.as_raw_fd(), PACKET_VERSION, &TPACKET_V3)?;
+        let frame_nr = config.block_size / config.frame_size.max(1) * config.block_count;
+        let req = TpacketReq3 {
+            block_size: config.block_size,
+            block_nr: config.block_count,
+            frame_size: config.frame_size,
+            frame_nr,
+            retire_blk_tov: config.block_timeout.as_millis().min(u32::MAX as u128) as u32,
+            sizeof_priv: 0,
+            feature_req_word: if config.fill_rxhash {
+                TP_FT_REQ_FILL_RXHASH
+            } else {
+                0
+            },
+        };
+        setsockopt(fd.as_raw_fd(), PACKET_RX_RING, &req)?;
+
+        let len = config.block_size as usize * config.block_count as usize;
+        let map = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                fd.as_raw_fd(),
+                0,
+            )
+        };
+        if map == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        // From here on, dropping `ring` unmaps the memory.
+        let ring = Self {
+            fd,
+            map: map.cast(),
+            config,
+            current: 0,
+        };
+
+        let ifindex = match interface {
+            Some(name) => {
+                let name =
+                    CString::new(name).map_err(|_| io::Error::from(io::ErrorKind::InvalidInput))?;
+                match unsafe { libc::if_nametoindex(name.as_ptr()) } {
+                    0 => return Err(io::Error::last_os_error()),
+                    index => index as libc::c_int,
+                }
+            }
+            None => 0,
+        };
+        let mut addr: libc::sockaddr_ll = unsafe { mem::zeroed() };
+        addr.sll_family = libc::AF_PACKET as u16;
+        addr.sll_protocol = protocol;
+        addr.sll_ifindex = ifindex;
+        let ret = unsafe {
+            libc::bind(
+                ring.fd.as_raw_fd(),
+                (&addr as *const libc::sockaddr_ll).cast(),
+                mem::size_of::<libc::sockaddr_ll>() as libc::socklen_t,
+            )
+        };
+        if ret < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(ring)
+    }
+
+    #[inline]
+    pub fn config(&self) -> &RingConfig {
+        &self.config
+    }
+
+    #[inline]
+    fn block_status(&self) -> *mut u32 {
+        let offset = self.current * self.config.block_size as usize + BLOCK_STATUS;
+        unsafe { self.map.add(offset).cast() }
+    }
+
+    /// Waits for the kernel to hand out the next block, up to `timeout`, or
+    /// forever if `None`. Returns `Ok(None)` if the timeout expired.
+    pub fn next_block(&mut self, timeout: Option<Duration>) -> io::Result<Option<Block<'_>>> {
+        let timeout = match timeout {
+            Some(timeout) => timeout.as_millis().min(libc::c_int::MAX as u128) as libc::c_int,
+            None => -1,
+        };
+        while unsafe { ptr::read_volatile(self.block_status()) } & TP_STATUS_USER == 0 {
+            let mut pfd = libc::pollfd {
+                fd: self.fd.as_raw_fd(),
+                events: libc::POLLIN | libc::POLLERR,
+                revents: 0,
+            };
+            match unsafe { libc::poll(&mut pfd, 1, timeout) } {
+                0 => return Ok(None),
+                ret if ret < 0 => {
+                    let err = io::Error::last_os_error();
+                    if err.kind() != io::ErrorKind::Interrupted {
+                        return Err(err);
+                    }
+                }
+                _ => {}
+            }
+        }
+        // Pairs with the kernel setting the status after filling the block.
+        fence(Ordering::Acquire);
+
+        let block_size = self.config.block_size as usize;
+        let data =
+            unsafe { slice::from_raw_parts(self.map.add(self.current * block_size), block_size) };
+        Ok(Some(Block {
+            data,
+            status: self.block_status(),
+            fill_rxhash: self.config.fill_rxhash,
+            next: &mut self.current,
+            count: self.config.block_count as usize,
+        }))
+    }
+
+    /// Returns the packet and drop counters, resetting them.
+    pub fn stats(&self) -> io::Result<RingStats> {
+        let mut stats = TpacketStatsV3::default();
+        let mut len = mem::size_of::<TpacketStatsV3>() as libc::socklen_t;
+        let ret = unsafe {
+            libc::getsockopt(
+                self.fd.as_raw_fd(),
+                libc::SOL_PACKET,
+                PACKET_STATISTICS,
+                (&mut stats as *mut TpacketStatsV3).cast(),
+                &mut len,
+            )
+        };
+        if ret < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(RingStats {
+            packets: stats.packets,
+            drops: stats.drops,
+            freeze_count: stats.freeze_q_cnt,
+        })
+    }
+}
+
+impl AsRawFd for Ring {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd.as_raw_fd()
+    }
+}
+
+impl Drop for Ring {
+    fn drop(&mut self) {
+        let len = self.config.block_size as usize * self.config.block_count as usize;
+        unsafe { libc::munmap(self.map.cast(), len) };
+    }
+}
+
+fn setsockopt<T>(fd: RawFd, name: libc::c_int, value: &T) -> io::Result<()> {
+    let ret = unsafe {
+        libc::setsockopt(
+            fd,
+            libc::SOL_PACKET,
+            name,
+            (value as *const T).cast(),
+            mem::size_of::<T>() as libc::socklen_t,
+        )
+    };
+    if ret < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// A block of the ring owned by userspace, given back to the kernel when
+/// dropped.
+#[derive(Debug)]
+pub struct Block<'a> {
+    data: &'a [u8],
+    status: *mut u32,
+    fill_rxhash: bool,
+    next: &'a mut usize,
+    count: usize,
+}
+
+impl Block<'_> {
+    /// Number of packets in the block.
+    #[inline]
+    pub fn len(&self) -> usize {
+        u32_at(self.data, BLOCK_NUM_PKTS).unwrap_or(0) as usize
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    #[inline]
+    pub fn frames(&self) -> Frames<'_> {
+        Frames::new(self.data, self.fill_rxhash)
+    }
+}
+
+impl Drop for Block<'_> {
+    fn drop(&mut self) {
+        // The frames must be read before the kernel reuses the block.
+        fence(Ordering::Release);
+        unsafe { ptr::write_volatile(self.status, TP_STATUS_KERNEL) };
+        *self.next = (*self.next + 1) % self.count;
+    }
+}
+
+/// A packet captured in a [`Block`].
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub struct Frame<'a> {
+    /// The timestamp, the interface, the direction and, when reported, the
+    /// stripped VLAN tag and the RSS hash.
+    pub meta: PacketMeta,
+    /// Length of the packet on the wire, `data` being truncated to the
+    /// snapshot length.
+    pub orig_len: u32,
+    pub data: &'a [u8],
+}
+
+impl<'a> Frame<'a> {
+    /// The packet data, along with its length on the wire.
+    #[inline]
+    pub fn captured(&self) -> Captured<'a> {
+        Captured::new(self.data, self.orig_len as usize)
+    }
+}
+
+/// Iterator over the frames of a [`Block`].
+#[derive(Debug, Clone)]
+pub struct Frames<'a> {
+    block: &'a [u8],
+    offset: usize,
+    remaining: u32,
+    fill_rxhash: bool,
+}
+
+impl<'a> Frames<'a> {
+    fn new(block: &'a [u8], fill_rxhash: bool) -> Self {
+        Self {
+            block,
+            offset: u32_at(block, BLOCK_OFFSET_TO_FIRST_PKT).unwrap_or(0) as usize,
+            remaining: u32_at(block, BLOCK_NUM_PKTS).unwrap_or(0),
+            fill_rxhash,
+        }
+    }
+
+    fn frame(&self) -> Option<(Frame<'a>, usize)> {
+        let hdr = self.block.get(self.offset..)?;
+        let next_offset = u32_at(hdr, 0)? as usize;
+        let status = u32_at(hdr, 20)?;
+        let mac = u16_at(hdr, 24)? as usize;
+        let snaplen = u32_at(hdr, 12)? as usize;
+
+        let mut meta = PacketMeta::new(Duration::new(
+            u32_at(hdr, 4)? as u64,
+            u32_at(hdr, 8)?.min(999_999_999),
+        ));
+        meta.ifindex = Some(u32_at(hdr, SLL_IFINDEX)?);
+        meta.direction = match SllPacketType::try_from(*hdr.get(SLL_PKTTYPE)? as u16) {
+            Ok(packet_type) => packet_type.into(),
+            Err(()) => Direction::Unknown,
+        };
+        if status & TP_STATUS_VLAN_VALID != 0 {
+            meta.vlan_stripped = Some(u32_at(hdr, 32)? as u16);
+        }
+        if self.fill_rxhash {
+            meta.rss_hash = Some(u32_at(hdr, 28)?);
+        }
+        let frame = Frame {
+            meta,
+            orig_len: u32_at(hdr, 16)?,
+            data: hdr.get(mac..mac + snaplen)?,
+        };
+        Some((frame, next_offset))
+    }
+}
+
+impl<'a> Iterator for Frames<'a> {
+    type Item = Frame<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        match self.frame() {
+            Some((frame, next_offset)) => {
+                self.remaining -= 1;
+                self.offset += next_offset;
+                Some(frame)
+            }
+            None => {
+                self.remaining = 0;
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use super::{Frames, TPACKET3_HDRLEN};
+    use crate::meta::Direction;
+
+    #[test]
+    fn test_frames() {
+        fn put(block: &mut [u8], at: usize, val: u32) {
+            block[at..at + 4].copy_from_slice(&val.to_ne_bytes());
+        }
+
+        let mut block = [0u8; 512];
+        put(&mut block, 12, 2);
+        put(&mut block, 16, 64);
+        for (offset, next, pkttype) in [(64, 160, 4), (224, 0, 0)] {
+            let hdr = &mut block[offset..];
+            put(hdr, 0, next);
+            put(hdr, 4, 1_700_000_000);
+            put(hdr, 8, 500);
+            put(hdr, 12, 4);
+            put(hdr, 16, 1514);
+            put(hdr, 20, 1 << 4);
+            hdr[24..26].copy_from_slice(&80u16.to_ne_bytes());
+            put(hdr, 28, 0xabcd);
+            put(hdr, 32, 0x2064);
+            put(hdr, TPACKET3_HDRLEN + 4, 3);
+            hdr[TPACKET3_HDRLEN + 10] = pkttype;
+            hdr[80..84].copy_from_slice(&[1, 2, 3, 4]);
+        }
+
+        let frames: Vec<_> = Frames::new(&block, true).collect();
+        assert_eq!(frames.len(), 2);
+        let frame = frames[0];
+        assert_eq!(frame.data, &[1, 2, 3, 4]);
+        assert_eq!(frame.orig_len, 1514);
+        assert_eq!(
+            frame.meta.timestamp,
+            Some(Duration::new(1_700_000_000, 500))
+        );
+        assert_eq!(frame.meta.ifindex, Some(3));
+        assert_eq!(frame.meta.direction, Direction::Outbound);
+        assert_eq!(frame.meta.stripped_vid(), Some(100));
+        assert_eq!(frame.meta.rss_hash, Some(0xabcd));
+        assert_eq!(frames[1].meta.direction, Direction::Inbound);
+    }
+}
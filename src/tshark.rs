@@ -0,0 +1,364 @@
+//! Export of parsed packets in the JSON format of `tshark -T json`.
+//!
+//! Each packet is an object whose `_source.layers` member holds one object
+//! per protocol, named like the Wireshark dissectors (`frame`, `eth`,
+//! `vlan`, `ip`, `ipv6`, `tcp`, `udp`, `icmp`, `icmpv6`, `sctp`, `data`),
+//! with the Wireshark field names as keys. As in tshark, every value is a
+//! string. Only the fields decoded by [`Packet`] are exported, and fields
+//! tshark computes from the conversation, such as relative TCP sequence
+//! numbers, are left out.
+
+use core::mem;
+use std::{fmt::Write, string::String, vec::Vec};
+
+use crate::{
+    meta::PacketMeta,
+    packet::{LinkType, NetworkHdr, Packet, TransportHdr},
+};
+
+/// Fields of a layer, in order.
+type Layer = (&'static str, Vec<(&'static str, String)>);
+
+/// Returns the packet in the format of `tshark -T json`, without the
+/// enclosing array.
+///
+/// ```
+/// use ether_packet::{packet::Packet, tshark};
+///
+/// #[rustfmt::skip]
+/// let frame = [
+///     0, 1, 2, 3, 4, 5, 0, 6, 7, 8, 9, 10, 0x08, 0x00,
+///     0x45, 0, 0, 28, 0, 1, 0x40, 0, 64, 17, 0, 0, 10, 0, 0, 1, 10, 0, 0, 2,
+///     0x30, 0x39, 0, 53, 0, 8, 0, 0,
+/// ];
+/// let json = tshark::to_json(&Packet::parse(&frame).unwrap(), &Default::default());
+/// assert!(json.contains(r#""udp":{"udp.srcport":"12345","udp.dstport":"53""#));
+/// ```
+pub fn to_json(packet: &Packet<'_>, meta: &PacketMeta) -> String {
+    let mut out = String::new();
+    write_json(&mut out, packet, meta);
+    out
+}
+
+/// Appends the packet to `out`, in the format of [`to_json`].
+pub fn write_json(out: &mut String, packet: &Packet<'_>, meta: &PacketMeta) {
+    let layers = layers(packet, meta);
+    out.push_str(r#"{"_index":"packets","_type":"doc","_score":null,"_source":{"layers":{"#);
+    for (i, (name, fields)) in layers.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        push_str(out, name);
+        out.push_str(":{");
+        for (j, (key, value)) in fields.iter().enumerate() {
+            if j > 0 {
+                out.push(',');
+            }
+            push_str(out, key);
+            out.push(':');
+            push_str(out, value);
+        }
+        out.push('}');
+    }
+    out.push_str("}}}");
+}
+
+/// Appends `s` as a JSON string.
+fn push_str(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+#[inline]
+fn hex(value: u32, digits: usize) -> String {
+    format!("0x{value:0digits$x}")
+}
+
+/// Bytes separated by colons, like MAC addresses and `data.data`.
+fn colon_hex(bytes: &[u8]) -> String {
+    let mut s = String::with_capacity(bytes.len() * 3);
+    for (i, b) in bytes.iter().enumerate() {
+        if i > 0 {
+            s.push(':');
+        }
+        let _ = write!(s, "{b:02x}");
+    }
+    s
+}
+
+#[inline]
+fn be16(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_be_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+#[inline]
+fn be32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes([
+        bytes[offset],
+        bytes[offset + 1],
+        bytes[offset + 2],
+        bytes[offset + 3],
+    ])
+}
+
+fn layers(packet: &Packet<'_>, meta: &PacketMeta) -> Vec<Layer> {
+    let data = packet.data();
+    let mut protocols = Vec::new();
+    let mut layers = Vec::new();
+
+    let tags = packet.vlan_tags();
+    let inner_type = |i: usize| match tags.get(i + 1) {
+        Some(tag) => tag.tpid,
+        None => packet
+            .ether_type()
+            .map_or(be16(data, 12 + 4 * tags.len()), |t| t as u16),
+    };
+    match (packet.link_type(), packet.eth()) {
+        (_, Some(eth)) => {
+            protocols.extend(["eth", "ethertype"]);
+            layers.push((
+                "eth",
+                vec![
+                    ("eth.dst", colon_hex(&eth.dst_addr)),
+                    ("eth.src", colon_hex(&eth.src_addr)),
+                    ("eth.type", hex(eth.ether_type.to_bits() as u32, 4)),
+                ],
+            ));
+        }
+        (LinkType::Null | LinkType::Loop, None) => {
+            let family = match packet.link_type() {
+                LinkType::Null if data[..2] != [0, 0] => {
+                    u32::from_le_bytes(data[..4].try_into().unwrap())
+                }
+                _ => be32(data, 0),
+            };
+            protocols.push("null");
+            layers.push(("null", vec![("null.family", family.to_string())]));
+        }
+        (LinkType::LinuxSll | LinkType::LinuxSll2, None) => {
+            protocols.extend(["sll", "ethertype"]);
+            let mut fields = Vec::new();
+            if packet.link_type() == LinkType::LinuxSll {
+                fields.push(("sll.pkttype", be16(data, 0).to_string()));
+                fields.push(("sll.hatype", be16(data, 2).to_string()));
+                fields.push(("sll.halen", be16(data, 4).to_string()));
+                fields.push(("sll.etype", hex(be16(data, 14) as u32, 4)));
+            } else {
+                fields.push(("sll.etype", hex(be16(data, 0) as u32, 4)));
+                fields.push(("sll.ifindex", be32(data, 4).to_string()));
+                fields.push(("sll.hatype", be16(data, 8).to_string()));
+                fields.push(("sll.pkttype", data[10].to_string()));
+                fields.push(("sll.halen", data[11].to_string()));
+            }
+            layers.push(("sll", fields));
+        }
+        (_, None) => protocols.push("raw"),
+    }
+    for (i, tag) in tags.iter().enumerate() {
+        protocols.extend(["vlan", "ethertype"]);
+        layers.push((
+            "vlan",
+            vec![
+                ("vlan.priority", tag.pcp().to_string()),
+                ("vlan.dei", (tag.dei() as u8).to_string()),
+                ("vlan.id", tag.vid().to_string()),
+                ("vlan.etype", hex(inner_type(i) as u32, 4)),
+            ],
+        ));
+    }
+
+    if let (Some(network), Some(l3)) = (packet.network(), packet.l3_offset()) {
+        let ip = &data[l3..];
+        match network {
+            NetworkHdr::Ipv4(hdr) => {
+                protocols.push("ip");
+                layers.push((
+                    "ip",
+                    vec![
+                        ("ip.version", "4".into()),
+                        ("ip.hdr_len", hdr.hdrlen().to_string()),
+                        ("ip.dsfield", hex(ip[1] as u32, 2)),
+                        ("ip.len", be16(ip, 2).to_string()),
+                        ("ip.id", hex(be16(ip, 4) as u32, 4)),
+                        ("ip.flags", hex((ip[6] & 0xe0) as u32, 2)),
+                        ("ip.frag_offset", ((be16(ip, 6) & 0x1fff) * 8).to_string()),
+                        ("ip.ttl", ip[8].to_string()),
+                        ("ip.proto", ip[9].to_string()),
+                        ("ip.checksum", hex(be16(ip, 10) as u32, 4)),
+                        ("ip.src", hdr.src_addr.to_string()),
+                        ("ip.dst", hdr.dst_addr.to_string()),
+                    ],
+                ));
+            }
+            NetworkHdr::Ipv6(hdr) => {
+                protocols.push("ipv6");
+                let tclass = (be16(ip, 0) >> 4) & 0xff;
+                layers.push((
+                    "ipv6",
+                    vec![
+                        ("ipv6.version", "6".into()),
+                        ("ipv6.tclass", hex(tclass as u32, 2)),
+                        ("ipv6.flow", hex(be32(ip, 0) & 0xfffff, 5)),
+                        ("ipv6.plen", be16(ip, 4).to_string()),
+                        ("ipv6.nxt", ip[6].to_string()),
+                        ("ipv6.hlim", ip[7].to_string()),
+                        ("ipv6.src", hdr.src_addr.to_string()),
+                        ("ipv6.dst", hdr.dst_addr.to_string()),
+                    ],
+                ));
+            }
+        }
+    }
+
+    // The payload of the packet starts with the transport header.
+    let mut payload = packet.payload();
+    match packet.transport() {
+        Some(TransportHdr::Tcp(hdr)) => {
+            let tcp = payload;
+            payload = &payload[hdr.hdrlen()..];
+            protocols.push("tcp");
+            layers.push((
+                "tcp",
+                vec![
+                    ("tcp.srcport", hdr.source.to_bits().to_string()),
+                    ("tcp.dstport", hdr.dest.to_bits().to_string()),
+                    ("tcp.len", payload.len().to_string()),
+                    ("tcp.seq_raw", hdr.seq.to_bits().to_string()),
+                    ("tcp.ack_raw", hdr.ack_seq.to_bits().to_string()),
+                    ("tcp.hdr_len", hdr.hdrlen().to_string()),
+                    ("tcp.flags", hex((be16(tcp, 12) & 0x0fff) as u32, 4)),
+                    ("tcp.window_size_value", hdr.window.to_bits().to_string()),
+                    ("tcp.checksum", hex(hdr.check.to_bits() as u32, 4)),
+                    ("tcp.urgent_pointer", hdr.urg_ptr.to_bits().to_string()),
+                ],
+            ));
+        }
+        Some(TransportHdr::Udp(hdr)) => {
+            payload = &payload[mem::size_of_val(hdr)..];
+            protocols.push("udp");
+            layers.push((
+                "udp",
+                vec![
+                    ("udp.srcport", hdr.source.to_bits().to_string()),
+                    ("udp.dstport", hdr.dest.to_bits().to_string()),
+                    ("udp.length", hdr.len.to_bits().to_string()),
+                    ("udp.checksum", hex(hdr.check.to_bits() as u32, 4)),
+                ],
+            ));
+        }
+        Some(TransportHdr::Icmp(hdr)) => {
+            payload = &payload[mem::size_of_val(hdr)..];
+            protocols.push("icmp");
+            layers.push((
+                "icmp",
+                vec![
+                    ("icmp.type", hdr.r#type.to_string()),
+                    ("icmp.code", hdr.code.to_string()),
+                    ("icmp.checksum", hex(hdr.checksum.to_bits() as u32, 4)),
+                ],
+            ));
+        }
+        Some(TransportHdr::Icmpv6(hdr)) => {
+            payload = &payload[mem::size_of_val(hdr)..];
+            protocols.push("icmpv6");
+            layers.push((
+                "icmpv6",
+                vec![
+                    ("icmpv6.type", hdr.r#type.to_string()),
+                    ("icmpv6.code", hdr.code.to_string()),
+                    ("icmpv6.checksum", hex(hdr.checksum.to_bits() as u32, 4)),
+                ],
+            ));
+        }
+        Some(TransportHdr::Sctp(hdr)) => {
+            payload = &payload[mem::size_of_val(hdr)..];
+            protocols.push("sctp");
+            layers.push((
+                "sctp",
+                vec![
+                    ("sctp.srcport", hdr.src_port.to_bits().to_string()),
+                    ("sctp.dstport", hdr.dst_port.to_bits().to_string()),
+                    (
+                        "sctp.verification_tag",
+                        hex(hdr.verification_tag.to_bits(), 8),
+                    ),
+                    ("sctp.checksum", hex(hdr.checksum.to_bits(), 8)),
+                ],
+            ));
+        }
+        None => {}
+    }
+
+    // SCTP chunks are not decoded, so they are not reported as data.
+    if !payload.is_empty() && !protocols.contains(&"sctp") {
+        protocols.push("data");
+        layers.push((
+            "data",
+            vec![
+                ("data.data", colon_hex(payload)),
+                ("data.len", payload.len().to_string()),
+            ],
+        ));
+    }
+
+    let mut frame = Vec::new();
+    if let Some(ts) = meta.timestamp {
+        let epoch = format!("{}.{:09}", ts.as_secs(), ts.subsec_nanos());
+        frame.push(("frame.time_epoch", epoch));
+    }
+    if let Some(ifindex) = meta.ifindex {
+        frame.push(("frame.interface_id", ifindex.to_string()));
+    }
+    if !packet.is_truncated() {
+        frame.push(("frame.len", data.len().to_string()));
+    }
+    frame.push(("frame.cap_len", data.len().to_string()));
+    frame.push(("frame.protocols", protocols.join(":")));
+    layers.insert(0, ("frame", frame));
+    layers
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use super::to_json;
+    use crate::{meta::PacketMeta, packet::Packet};
+
+    #[test]
+    fn test_tshark_json() {
+        #[rustfmt::skip]
+        let frame = [
+            0, 1, 2, 3, 4, 5, 0, 6, 7, 8, 9, 10,
+            0x81, 0x00, 0x20, 100, 0x86, 0xdd,
+            0x60, 0, 0, 1, 0, 24, 6, 64,
+            0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1,
+            0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2,
+            0x04, 0xd2, 0, 80, 0, 0, 0, 1, 0, 0, 0, 0, 0x50, 0x18, 0xff, 0xff, 0x12, 0x34, 0, 0,
+            b'h', b'i', b'!', b'\n',
+        ];
+        let packet = Packet::parse(&frame).unwrap();
+        let json = to_json(&packet, &PacketMeta::new(Duration::new(1700000000, 5)));
+        assert!(json.starts_with(
+            r#"{"_index":"packets","_type":"doc","_score":null,"_source":{"layers":{"frame":{"frame.time_epoch":"1700000000.000000005","frame.len":"82","frame.cap_len":"82","frame.protocols":"eth:ethertype:vlan:ethertype:ipv6:tcp:data"},"eth":{"eth.dst":"00:01:02:03:04:05","#
+        ));
+        assert!(json.contains(
+            r#""vlan":{"vlan.priority":"1","vlan.dei":"0","vlan.id":"100","vlan.etype":"0x86dd"}"#
+        ));
+        assert!(json.contains(r#""ipv6.flow":"0x00001","ipv6.plen":"24""#));
+        assert!(json.contains(r#""ipv6.src":"2001:db8::1""#));
+        assert!(json.contains(r#""tcp.len":"4","tcp.seq_raw":"1""#));
+        assert!(json.contains(r#""tcp.flags":"0x0018""#));
+        assert!(json.ends_with(r#""data":{"data.data":"68:69:21:0a","data.len":"4"}}}}"#));
+    }
+}
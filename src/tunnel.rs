@@ -0,0 +1,129 @@
+//! Decapsulation of overlay tunnels.
+//!
+//! The headers are defined in their own modules: [`VxlanHdr`],
+//! [`GeneveHdr`], [`GreHdr`] and [`MplsLabel`], the MPLS label stack entry.
+//! [`Tunnel`] recognizes them after an IP or UDP header and locates the
+//! encapsulated packet, to be parsed again as an Ethernet frame or an IP
+//! packet.
+
+use crate::{
+    eth::EtherType,
+    geneve::GENEVE_PORT,
+    gre::{Gre, GRE_IN_UDP_PORT, PROTO_TEB},
+    header::Header,
+    ip::IpProto,
+    mpls::{LabelStack, MPLS_IN_UDP_PORT},
+    vxlan::VXLAN_PORT,
+};
+pub use crate::{geneve::GeneveHdr, gre::GreHdr, mpls::MplsHdr as MplsLabel, vxlan::VxlanHdr};
+
+/// Packet carried by a tunnel.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum Inner<'a> {
+    /// An Ethernet frame, starting with its header.
+    Ethernet(&'a [u8]),
+    /// An IPv4 or IPv6 packet.
+    Ip(&'a [u8]),
+}
+
+/// Encapsulation header of a tunneled packet.
+#[derive(Debug, Clone)]
+pub enum Tunnel<'a> {
+    Vxlan(&'a VxlanHdr),
+    Geneve(&'a GeneveHdr),
+    Gre(Gre<'a>),
+    /// MPLS label stack, carried in UDP or in Ethernet.
+    Mpls(LabelStack<'a>),
+}
+
+impl<'a> Tunnel<'a> {
+    /// Recognizes a tunnel carried in UDP from its destination port, i.e.
+    /// VXLAN, Geneve, GRE-in-UDP or MPLS-in-UDP, `payload` following the
+    /// UDP header.
+    ///
+    /// Returns `None` for other ports, malformed headers and inner packets
+    /// of other protocols.
+    pub fn from_udp(dst_port: u16, payload: &'a [u8]) -> Option<(Self, Inner<'a>)> {
+        match dst_port {
+            VXLAN_PORT => {
+                let hdr = VxlanHdr::from_bytes(payload)?;
+                let inner = Inner::Ethernet(&payload[VxlanHdr::LEN..]);
+                Some((Tunnel::Vxlan(hdr), inner))
+            }
+            GENEVE_PORT => {
+                let hdr = GeneveHdr::from_bytes(payload)?;
+                let inner = payload.get(GeneveHdr::LEN + hdr.options_len()..)?;
+                let inner = inner_of(hdr.protocol_type.to_bits(), inner)?;
+                Some((Tunnel::Geneve(hdr), inner))
+            }
+            GRE_IN_UDP_PORT => Self::from_ip(IpProto::Gre, payload),
+            MPLS_IN_UDP_PORT => {
+                let stack = LabelStack::new(payload);
+                // A stack cut before its bottom has no inner packet.
+                stack.clone().last().filter(MplsLabel::bos)?;
+                let inner = &payload[stack.clone().count() * MplsLabel::LEN..];
+                Some((Tunnel::Mpls(stack), Inner::Ip(inner)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Recognizes a tunnel carried directly over IP, i.e. GRE, `payload`
+    /// following the IP header.
+    pub fn from_ip(proto: IpProto, payload: &'a [u8]) -> Option<(Self, Inner<'a>)> {
+        match proto {
+            IpProto::Gre => {
+                let gre = Gre::parse(payload)?;
+                let inner = inner_of(gre.hdr().protocol_type.to_bits(), gre.payload())?;
+                Some((Tunnel::Gre(gre), inner))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Inner packet of an encapsulation carrying an EtherType.
+#[inline]
+fn inner_of(protocol_type: u16, data: &[u8]) -> Option<Inner<'_>> {
+    match protocol_type {
+        PROTO_TEB => Some(Inner::Ethernet(data)),
+        proto if proto == EtherType::Ipv4 as u16 || proto == EtherType::Ipv6 as u16 => {
+            Some(Inner::Ip(data))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Inner, MplsLabel, Tunnel};
+
+    #[test]
+    fn test_decapsulate() {
+        #[rustfmt::skip]
+        let geneve = [
+            // One 4-byte option, Ethernet payload, VNI 0x123456.
+            0x01, 0, 0x65, 0x58, 0x12, 0x34, 0x56, 0,
+            0x01, 0x02, 0x03, 0x00,
+            0, 0, 0, 0, 0, 2,
+        ];
+        let Some((Tunnel::Geneve(hdr), inner)) = Tunnel::from_udp(6081, &geneve) else {
+            panic!("not Geneve");
+        };
+        assert_eq!((hdr.vni(), hdr.options_len()), (0x123456, 4));
+        assert_eq!(inner, Inner::Ethernet(&geneve[12..]));
+
+        // Two labels, then an IPv4 packet.
+        let mut mpls = [0u8; 10];
+        mpls[..4].copy_from_slice(&MplsLabel::new(6, 0, false, 64).entry.octets());
+        mpls[4..8].copy_from_slice(&MplsLabel::new(12, 0, true, 64).entry.octets());
+        mpls[8] = 0x45;
+        let Some((Tunnel::Mpls(stack), inner)) = Tunnel::from_udp(6635, &mpls) else {
+            panic!("not MPLS");
+        };
+        assert!(stack.map(|hdr| hdr.label()).eq([6, 12]));
+        assert_eq!(inner, Inner::Ip(&mpls[8..]));
+        // No bottom of stack.
+        assert!(Tunnel::from_udp(6635, &mpls[..4]).is_none());
+    }
+}
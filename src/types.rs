@@ -86,9 +86,10 @@ pub struct U64 {
 }
 
 impl U64 {
+    #[allow(clippy::too_many_arguments)]
     pub const fn new(a: u8, b: u8, c: u8, d: u8, e: u8, f: u8, g: u8, h: u8) -> Self {
         Self {
-            octets: [a, b, c, d, e, f, g, g],
+            octets: [a, b, c, d, e, f, g, h],
         }
     }
 
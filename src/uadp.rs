@@ -0,0 +1,291 @@
+//! UADP network message header of OPC UA PubSub (OPC 10000-14), published
+//! over UDP, usually to a multicast group on port [`UADP_PORT`], or
+//! directly over Ethernet with [`EtherType::UADP`](crate::eth::EtherType::UADP).
+//!
+//! The header has a variable layout, each optional part being announced by
+//! a flag, and uses the little-endian encoding of OPC UA. [`parse`]
+//! decodes it up to the dataset messages.
+
+/// UDP port of OPC UA PubSub.
+pub const UADP_PORT: u16 = 4840;
+
+/// Little-endian cursor over a network message.
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    #[inline]
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let (out, rest) = self.data.split_at_checked(len)?;
+        self.data = rest;
+        Some(out)
+    }
+
+    #[inline]
+    fn u8(&mut self) -> Option<u8> {
+        Some(self.take(1)?[0])
+    }
+
+    #[inline]
+    fn u16(&mut self) -> Option<u16> {
+        Some(u16::from_le_bytes(self.take(2)?.try_into().ok()?))
+    }
+
+    #[inline]
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    #[inline]
+    fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.take(8)?.try_into().ok()?))
+    }
+}
+
+/// Identifier of the publisher of a network message.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum PublisherId<'a> {
+    Byte(u8),
+    UInt16(u16),
+    UInt32(u32),
+    UInt64(u64),
+    String(&'a [u8]),
+}
+
+/// Type of a network message.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum UadpMessageType {
+    /// Dataset messages, the published data.
+    DataSet,
+    DiscoveryRequest,
+    DiscoveryResponse,
+}
+
+/// Group header, identifying the writer group of the message.
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct GroupHeader {
+    pub writer_group_id: Option<u16>,
+    /// Version of the writer group configuration.
+    pub group_version: Option<u32>,
+    pub network_message_number: Option<u16>,
+    pub sequence_number: Option<u16>,
+}
+
+/// Security header of signed or encrypted messages.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub struct SecurityHeader<'a> {
+    pub flags: u8,
+    pub security_token_id: u32,
+    pub nonce: &'a [u8],
+    /// Size of the footer ending the message.
+    pub footer_size: Option<u16>,
+}
+
+impl SecurityHeader<'_> {
+    #[inline]
+    pub const fn signed(&self) -> bool {
+        self.flags & 0x01 != 0
+    }
+
+    #[inline]
+    pub const fn encrypted(&self) -> bool {
+        self.flags & 0x02 != 0
+    }
+}
+
+/// IDs of the writers of the dataset messages of a network message, from
+/// its payload header.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct DataSetWriterIds<'a> {
+    data: &'a [u8],
+}
+
+impl Iterator for DataSetWriterIds<'_> {
+    type Item = u16;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (id, rest) = self.data.split_first_chunk::<2>()?;
+        self.data = rest;
+        Some(u16::from_le_bytes(*id))
+    }
+}
+
+/// A decoded UADP network message header.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct UadpHeader<'a> {
+    /// UADP version, 1.
+    pub version: u8,
+    /// UADPFlags, ExtendedFlags1 and ExtendedFlags2, zero when absent.
+    pub flags: [u8; 3],
+    pub message_type: UadpMessageType,
+    pub publisher_id: Option<PublisherId<'a>>,
+    pub dataset_class_id: Option<[u8; 16]>,
+    pub group: Option<GroupHeader>,
+    /// Writers of the dataset messages, announced by the payload header.
+    pub dataset_writer_ids: Option<DataSetWriterIds<'a>>,
+    /// Time of publication, in 100 ns intervals since 1601-01-01 UTC.
+    pub timestamp: Option<u64>,
+    pub picoseconds: Option<u16>,
+    /// Promoted fields, kept encoded.
+    pub promoted_fields: Option<&'a [u8]>,
+    pub security: Option<SecurityHeader<'a>>,
+}
+
+impl UadpHeader<'_> {
+    /// Whether the message carries a chunk of a larger message.
+    #[inline]
+    pub const fn is_chunk(&self) -> bool {
+        self.flags[2] & 0x01 != 0
+    }
+}
+
+/// Parses the UADP network message header at the start of `msg`, returning
+/// it with the following bytes, i.e. the payload sizes and dataset
+/// messages.
+///
+/// ```
+/// use ether_packet::uadp::{self, PublisherId};
+///
+/// // Version 1 with publisher ID and group header, writer group 100.
+/// let msg = [0x31, 42, 0x01, 100, 0, 0xaa];
+/// let (hdr, rest) = uadp::parse(&msg).unwrap();
+/// assert_eq!(hdr.publisher_id, Some(PublisherId::Byte(42)));
+/// assert_eq!(hdr.group.unwrap().writer_group_id, Some(100));
+/// assert_eq!(rest, &[0xaa]);
+/// ```
+pub fn parse(msg: &[u8]) -> Option<(UadpHeader<'_>, &[u8])> {
+    let mut r = Reader { data: msg };
+    let flags = r.u8()?;
+    let ext1 = if flags & 0x80 != 0 { r.u8()? } else { 0 };
+    let ext2 = if ext1 & 0x80 != 0 { r.u8()? } else { 0 };
+    let message_type = match (ext2 >> 2) & 0x7 {
+        0 => UadpMessageType::DataSet,
+        1 => UadpMessageType::DiscoveryRequest,
+        2 => UadpMessageType::DiscoveryResponse,
+        _ => return None,
+    };
+    let mut hdr = UadpHeader {
+        version: flags & 0x0f,
+        flags: [flags, ext1, ext2],
+        message_type,
+        publisher_id: None,
+        dataset_class_id: None,
+        group: None,
+        dataset_writer_ids: None,
+        timestamp: None,
+        picoseconds: None,
+        promoted_fields: None,
+        security: None,
+    };
+    if flags & 0x10 != 0 {
+        hdr.publisher_id = Some(match ext1 & 0x7 {
+            0 => PublisherId::Byte(r.u8()?),
+            1 => PublisherId::UInt16(r.u16()?),
+            2 => PublisherId::UInt32(r.u32()?),
+            3 => PublisherId::UInt64(r.u64()?),
+            4 => {
+                let len = r.u32()?;
+                PublisherId::String(r.take(len as usize)?)
+            }
+            _ => return None,
+        });
+    }
+    if ext1 & 0x08 != 0 {
+        hdr.dataset_class_id = Some(r.take(16)?.try_into().ok()?);
+    }
+    if flags & 0x20 != 0 {
+        let group_flags = r.u8()?;
+        let mut group = GroupHeader::default();
+        if group_flags & 0x01 != 0 {
+            group.writer_group_id = Some(r.u16()?);
+        }
+        if group_flags & 0x02 != 0 {
+            group.group_version = Some(r.u32()?);
+        }
+        if group_flags & 0x04 != 0 {
+            group.network_message_number = Some(r.u16()?);
+        }
+        if group_flags & 0x08 != 0 {
+            group.sequence_number = Some(r.u16()?);
+        }
+        hdr.group = Some(group);
+    }
+    if flags & 0x40 != 0 && message_type == UadpMessageType::DataSet {
+        // Chunks carry the ID of a single writer.
+        let count = if hdr.is_chunk() { 1 } else { r.u8()? };
+        hdr.dataset_writer_ids = Some(DataSetWriterIds {
+            data: r.take(count as usize * 2)?,
+        });
+    }
+    if ext1 & 0x20 != 0 {
+        hdr.timestamp = Some(r.u64()?);
+    }
+    if ext1 & 0x40 != 0 {
+        hdr.picoseconds = Some(r.u16()?);
+    }
+    if ext2 & 0x02 != 0 {
+        let size = r.u16()?;
+        hdr.promoted_fields = Some(r.take(size as usize)?);
+    }
+    if ext1 & 0x10 != 0 {
+        let flags = r.u8()?;
+        let security_token_id = r.u32()?;
+        let nonce_len = r.u8()?;
+        let nonce = r.take(nonce_len as usize)?;
+        let footer_size = if flags & 0x04 != 0 {
+            Some(r.u16()?)
+        } else {
+            None
+        };
+        hdr.security = Some(SecurityHeader {
+            flags,
+            security_token_id,
+            nonce,
+            footer_size,
+        });
+    }
+    Some((hdr, r.data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, PublisherId, UadpMessageType};
+
+    #[test]
+    fn test_uadp_header() {
+        #[rustfmt::skip]
+        let msg = [
+            // Publisher ID, group and payload headers, ExtendedFlags1 and 2.
+            0xf1, 0xa3, 0x00,
+            // UInt64 publisher ID.
+            1, 0, 0, 0, 0, 0, 0, 0,
+            // Group header: writer group, version and sequence number.
+            0x0b, 0x64, 0x00, 0x10, 0x27, 0, 0, 0x05, 0x00,
+            // Two dataset writers.
+            2, 0x01, 0x00, 0x02, 0x00,
+            // Timestamp.
+            0x00, 0x80, 0x3e, 0xd5, 0xde, 0xb1, 0x9d, 0x01,
+            // Payload sizes.
+            0x10, 0x00, 0x10, 0x00,
+        ];
+        let (hdr, rest) = parse(&msg).unwrap();
+        assert_eq!(hdr.version, 1);
+        assert_eq!(hdr.message_type, UadpMessageType::DataSet);
+        assert_eq!(hdr.publisher_id, Some(PublisherId::UInt64(1)));
+        let group = hdr.group.unwrap();
+        assert_eq!(group.writer_group_id, Some(100));
+        assert_eq!(group.group_version, Some(10000));
+        assert_eq!(group.network_message_number, None);
+        assert_eq!(group.sequence_number, Some(5));
+        assert!(hdr.dataset_writer_ids.clone().unwrap().eq([1, 2]));
+        assert_eq!(hdr.timestamp, Some(0x019d_b1de_d53e_8000));
+        assert!(hdr.security.is_none());
+        assert_eq!(rest.len(), 4);
+
+        // Cut in the payload header.
+        assert!(parse(&msg[..24]).is_none());
+    }
+}
@@ -1,16 +1,46 @@
 use core::mem;
 
+use crate::{
+    builder::{BuildError, Writer},
+    header::impl_header,
+    types::U16,
+};
+
 /// UDP header, which is present after the IP header.
-#[repr(C)]
+/// ```text
+/// +-------------------------------+-------------------------------+
+/// |          Source Port          |       Destination Port        |
+/// +-------------------------------+-------------------------------+
+/// |            Length             |           Checksum            |
+/// +-------------------------------+-------------------------------+
+/// ```
+#[repr(C, packed)]
 #[derive(Debug, Copy, Clone)]
 #[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 pub struct UdpHdr {
-    pub source: u16,
-    pub dest: u16,
-    pub len: u16,
-    pub check: u16,
+    pub source: U16,
+    pub dest: U16,
+    /// Length of the header and the payload, in bytes.
+    pub len: U16,
+    pub check: U16,
 }
 
 impl UdpHdr {
     pub const LEN: usize = mem::size_of::<UdpHdr>();
 }
+
+impl_header!(UdpHdr);
+
+/// Writes a UDP header without checksum, as used by the UDP-based tunnel
+/// encapsulations.
+pub(crate) fn write_udp(
+    w: &mut Writer<'_>,
+    src_port: u16,
+    dst_port: u16,
+    len: u16,
+) -> Result<(), BuildError> {
+    w.put(&src_port.to_be_bytes())?;
+    w.put(&dst_port.to_be_bytes())?;
+    w.put(&len.to_be_bytes())?;
+    w.put(&[0, 0])
+}
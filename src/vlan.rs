@@ -0,0 +1,259 @@
+//! VLAN translation, rewriting the VID and optionally the PCP of tagged
+//! frames in place, as done at NNI handoffs between provider networks.
+
+use core::{fmt, ops::RangeInclusive};
+
+use crate::eth::{EtherType, VlanHdr, VlanTagKind};
+
+/// Largest VID which can be mapped, 4095 being reserved.
+pub const MAX_VID: u16 = 4094;
+
+const NO_MAPPING: u16 = u16::MAX;
+
+/// Error returned when adding a mapping to a [`VlanMap`].
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum VlanMapError {
+    /// The VID is 0, reserved or larger than 12 bits.
+    InvalidVid(u16),
+    /// The source and translated ranges do not have the same length.
+    RangeMismatch,
+}
+
+impl fmt::Display for VlanMapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VlanMapError::InvalidVid(vid) => write!(f, "invalid VID {vid}"),
+            VlanMapError::RangeMismatch => f.write_str("ranges of different lengths"),
+        }
+    }
+}
+
+/// What [`VlanMap::apply`] did to a frame.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum VlanAction {
+    /// The VID was rewritten.
+    Translated { from: u16, to: u16 },
+    /// No mapping exists for the VID, which was left untouched.
+    Unmatched(u16),
+    /// The frame carries a priority tag, with a VID of 0, which was left
+    /// untouched apart from the PCP map.
+    PriorityTagged,
+    /// The frame is not VLAN-tagged.
+    Untagged,
+}
+
+/// Number of frames handled by a [`VlanMap`].
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct VlanCounters {
+    pub translated: u64,
+    pub unmatched: u64,
+    pub untagged: u64,
+    pub priority_tagged: u64,
+    /// Frames whose PCP was changed by the PCP map.
+    pub pcp_remapped: u64,
+}
+
+/// VID translation table, with an optional PCP map applied to every tagged
+/// frame.
+///
+/// ```
+/// use ether_packet::{eth::VlanHdr, header::Header, vlan::{VlanAction, VlanMap}};
+///
+/// let mut map = VlanMap::new();
+/// map.map(100, 2100).unwrap();
+/// map.map_range(200..=299, 3200).unwrap();
+///
+/// let mut frame = [0u8; VlanHdr::LEN];
+/// frame[12..16].copy_from_slice(&[0x81, 0x00, 0x00, 0xfa]); // VID 250
+/// let hdr = VlanHdr::from_bytes_mut(&mut frame).unwrap();
+/// assert_eq!(map.apply(hdr), VlanAction::Translated { from: 250, to: 3250 });
+/// assert_eq!(map.counters().translated, 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct VlanMap {
+    vids: [u16; 4096],
+    pcp: Option<[u8; 8]>,
+    counters: VlanCounters,
+}
+
+impl VlanMap {
+    /// Creates a map without any mapping, leaving every VID untouched.
+    pub const fn new() -> Self {
+        Self {
+            vids: [NO_MAPPING; 4096],
+            pcp: None,
+            counters: VlanCounters {
+                translated: 0,
+                unmatched: 0,
+                untagged: 0,
+                priority_tagged: 0,
+                pcp_remapped: 0,
+            },
+        }
+    }
+
+    #[inline]
+    fn check_vid(vid: u16) -> Result<u16, VlanMapError> {
+        match vid {
+            1..=MAX_VID => Ok(vid),
+            _ => Err(VlanMapError::InvalidVid(vid)),
+        }
+    }
+
+    /// Translates `from` to `to`, replacing any previous mapping of `from`.
+    pub fn map(&mut self, from: u16, to: u16) -> Result<&mut Self, VlanMapError> {
+        self.vids[Self::check_vid(from)? as usize] = Self::check_vid(to)?;
+        Ok(self)
+    }
+
+    /// Translates each VID of `from` to the VID at the same position in the
+    /// range starting at `to`.
+    pub fn map_range(
+        &mut self,
+        from: RangeInclusive<u16>,
+        to: u16,
+    ) -> Result<&mut Self, VlanMapError> {
+        let (start, end) = (
+            Self::check_vid(*from.start())?,
+            Self::check_vid(*from.end())?,
+        );
+        if start > end {
+            return Err(VlanMapError::RangeMismatch);
+        }
+        let to_end = to
+            .checked_add(end - start)
+            .ok_or(VlanMapError::RangeMismatch)?;
+        Self::check_vid(to)?;
+        Self::check_vid(to_end)?;
+        for (i, vid) in (start..=end).enumerate() {
+            self.vids[vid as usize] = to + i as u16;
+        }
+        Ok(self)
+    }
+
+    /// Removes the mapping of `vid`.
+    pub fn unmap(&mut self, vid: u16) -> &mut Self {
+        if let Some(slot) = self.vids.get_mut(vid as usize) {
+            *slot = NO_MAPPING;
+        }
+        self
+    }
+
+    /// Rewrites the PCP of tagged frames, `map` being indexed by the
+    /// original PCP.
+    pub fn pcp_map(&mut self, map: [u8; 8]) -> &mut Self {
+        self.pcp = Some(map.map(|pcp| pcp & 0x7));
+        self
+    }
+
+    /// Returns the VID `vid` is translated to.
+    #[inline]
+    pub fn translate(&self, vid: u16) -> Option<u16> {
+        match self.vids.get(vid as usize) {
+            Some(&NO_MAPPING) | None => None,
+            Some(&to) => Some(to),
+        }
+    }
+
+    /// Translates the outermost tag of the frame whose header is `hdr`,
+    /// i.e. the S-tag of QinQ frames.
+    pub fn apply(&mut self, hdr: &mut VlanHdr) -> VlanAction {
+        let tagged = EtherType::try_from(hdr.tpid).is_ok_and(|tpid| tpid.is_vlan());
+        if !tagged {
+            self.counters.untagged += 1;
+            return VlanAction::Untagged;
+        }
+        if let Some(map) = self.pcp {
+            let pcp = hdr.pcp();
+            if map[pcp as usize] != pcp {
+                hdr.set_pcp(map[pcp as usize]);
+                self.counters.pcp_remapped += 1;
+            }
+        }
+        if hdr.kind() == VlanTagKind::PriorityOnly {
+            self.counters.priority_tagged += 1;
+            return VlanAction::PriorityTagged;
+        }
+        let from = hdr.vid();
+        match self.translate(from) {
+            Some(to) => {
+                hdr.set_vid(to);
+                self.counters.translated += 1;
+                VlanAction::Translated { from, to }
+            }
+            None => {
+                self.counters.unmatched += 1;
+                VlanAction::Unmatched(from)
+            }
+        }
+    }
+
+    #[inline]
+    pub fn counters(&self) -> &VlanCounters {
+        &self.counters
+    }
+
+    #[inline]
+    pub fn reset_counters(&mut self) {
+        self.counters = VlanCounters::default();
+    }
+}
+
+impl Default for VlanMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{VlanAction, VlanMap, VlanMapError};
+    use crate::{eth::VlanHdr, header::Header};
+
+    #[test]
+    fn test_vlan_map() {
+        let mut map = VlanMap::new();
+        map.map(10, 20).unwrap().pcp_map([0, 1, 2, 3, 4, 5, 5, 5]);
+        assert_eq!(map.map(0, 20).unwrap_err(), VlanMapError::InvalidVid(0));
+        assert_eq!(
+            map.map_range(1..=4094, 2).unwrap_err(),
+            VlanMapError::InvalidVid(4095)
+        );
+        assert_eq!(
+            map.map_range(4090..=4094, 4093).unwrap_err(),
+            VlanMapError::InvalidVid(4097)
+        );
+
+        // PCP 7, DEI, VID 10.
+        let mut frame = [0u8; VlanHdr::LEN];
+        frame[12..16].copy_from_slice(&[0x81, 0x00, 0xf0, 0x0a]);
+        let hdr = VlanHdr::from_bytes_mut(&mut frame).unwrap();
+        assert_eq!(map.apply(hdr), VlanAction::Translated { from: 10, to: 20 });
+        assert!(hdr.dei());
+        assert_eq!(&frame[14..16], &[0xb0, 0x14]);
+
+        let hdr = VlanHdr::from_bytes_mut(&mut frame).unwrap();
+        assert_eq!(map.apply(hdr), VlanAction::Unmatched(20));
+        // Priority tag, PCP 6: the PCP map still applies.
+        frame[14..16].copy_from_slice(&[0xc0, 0x00]);
+        let hdr = VlanHdr::from_bytes_mut(&mut frame).unwrap();
+        assert_eq!(map.apply(hdr), VlanAction::PriorityTagged);
+        assert_eq!(hdr.pcp(), 5);
+        frame[12..14].copy_from_slice(&[0x08, 0x00]);
+        let hdr = VlanHdr::from_bytes_mut(&mut frame).unwrap();
+        assert_eq!(map.apply(hdr), VlanAction::Untagged);
+
+        let counters = map.counters();
+        assert_eq!(
+            (
+                counters.translated,
+                counters.unmatched,
+                counters.untagged,
+                counters.priority_tagged,
+                counters.pcp_remapped
+            ),
+            (1, 1, 1, 1, 2)
+        );
+    }
+}
@@ -0,0 +1,218 @@
+//! Virtual Router Redundancy Protocol, version 2
+//! ([RFC 3768](https://datatracker.ietf.org/doc/html/rfc3768)) and version 3 for IPv4 and IPv6
+//! ([RFC 5798](https://datatracker.ietf.org/doc/html/rfc5798)), carried directly over IP with
+//! [`IpProto::Vrrp`].
+
+use core::{
+    mem,
+    net::{Ipv4Addr, Ipv6Addr},
+};
+
+use crate::{
+    bitfield::BitfieldUnit,
+    checksum,
+    header::{impl_header, Header},
+    ip::IpProto,
+    types::U16,
+};
+
+/// Destination address of VRRP advertisements sent over IPv4.
+pub const VRRP_MCAST_V4: Ipv4Addr = Ipv4Addr::new(224, 0, 0, 18);
+/// Destination address of VRRP advertisements sent over IPv6.
+pub const VRRP_MCAST_V6: Ipv6Addr = Ipv6Addr::new(0xff02, 0, 0, 0, 0, 0, 0, 0x12);
+
+/// Priority of the router owning the virtual router addresses.
+pub const VRRP_PRIORITY_OWNER: u8 = 255;
+/// Priority signalling that the current master stopped participating.
+pub const VRRP_PRIORITY_RELEASE: u8 = 0;
+
+/// Length of the authentication data trailing VRRPv2 messages.
+const V2_AUTH_LEN: usize = 8;
+
+/// VRRP message header, followed by `count_addrs` IPv4 or IPv6 addresses
+/// (and 8 octets of authentication data for VRRPv2).
+/// ```text
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |Version| Type  | Virtual Rtr ID|   Priority    |Count IPvX Addr|
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |(rsvd) |     Max Adver Int     |          Checksum             |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+/// In VRRPv2 the second word holds the Auth Type and the advertisement
+/// interval in seconds instead.
+#[repr(C, packed)]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct VrrpHdr {
+    /// **Version** (4 bits) and **Type** (4 bits).
+    pub vers_type: BitfieldUnit<[u8; 1usize]>,
+    pub vrid: u8,
+    pub priority: u8,
+    pub count_addrs: u8,
+    /// VRRPv3: 4 reserved bits and the 12-bit maximum advertisement
+    /// interval. VRRPv2: Auth Type and advertisement interval.
+    pub adver_int: U16,
+    pub checksum: U16,
+}
+
+impl VrrpHdr {
+    pub const LEN: usize = mem::size_of::<VrrpHdr>();
+
+    #[inline]
+    pub const fn version(&self) -> u8 {
+        self.vers_type.get(4, 4) as u8
+    }
+
+    #[inline]
+    pub const fn set_version(&mut self, val: u8) {
+        self.vers_type.set(4, 4, val as u64)
+    }
+
+    /// Message type, 1 (ADVERTISEMENT) is the only one defined.
+    #[inline]
+    pub const fn msg_type(&self) -> u8 {
+        self.vers_type.get(0, 4) as u8
+    }
+
+    #[inline]
+    pub const fn set_msg_type(&mut self, val: u8) {
+        self.vers_type.set(0, 4, val as u64)
+    }
+
+    /// Advertisement interval, in centiseconds.
+    ///
+    /// VRRPv3 carries sub-second intervals in centiseconds while VRRPv2
+    /// carries whole seconds, which are converted.
+    #[inline]
+    pub const fn adver_int_centis(&self) -> u16 {
+        let val = self.adver_int.to_bits();
+        if self.version() == 2 {
+            (val & 0xff) * 100
+        } else {
+            val & 0x0fff
+        }
+    }
+
+    /// Sets the VRRPv3 maximum advertisement interval, in centiseconds.
+    #[inline]
+    pub const fn set_max_adver_int(&mut self, centis: u16) {
+        self.adver_int = U16::from_bits(centis & 0x0fff)
+    }
+
+    #[inline]
+    pub const fn checksum(&self) -> u16 {
+        self.checksum.to_bits()
+    }
+
+    /// Length of the whole message when carried over IPv6 (`ipv6`) or IPv4.
+    #[inline]
+    pub const fn msg_len(&self, ipv6: bool) -> usize {
+        let addr_len = if ipv6 { 16 } else { 4 };
+        let auth_len = if self.version() == 2 { V2_AUTH_LEN } else { 0 };
+        Self::LEN + self.count_addrs as usize * addr_len + auth_len
+    }
+
+    /// Virtual router IPv4 addresses of the message stored in `msg`, which
+    /// starts with this header.
+    pub fn ipv4_addrs<'a>(&self, msg: &'a [u8]) -> Option<impl Iterator<Item = Ipv4Addr> + 'a> {
+        let addrs = msg.get(Self::LEN..Self::LEN + self.count_addrs as usize * 4)?;
+        Some(
+            addrs
+                .chunks_exact(4)
+                .map(|a| Ipv4Addr::new(a[0], a[1], a[2], a[3])),
+        )
+    }
+
+    /// Virtual router IPv6 addresses of the message stored in `msg`, which
+    /// starts with this header.
+    pub fn ipv6_addrs<'a>(&self, msg: &'a [u8]) -> Option<impl Iterator<Item = Ipv6Addr> + 'a> {
+        let addrs = msg.get(Self::LEN..Self::LEN + self.count_addrs as usize * 16)?;
+        Some(
+            addrs
+                .chunks_exact(16)
+                .map(|a| Ipv6Addr::from(<[u8; 16]>::try_from(a).unwrap())),
+        )
+    }
+}
+
+impl_header!(VrrpHdr);
+
+/// Computes the checksum of the VRRP message at the start of `msg`, carried
+/// over IPv4 from `src` to `dst`.
+///
+/// VRRPv3 includes the IPv4 pseudo-header, VRRPv2 only covers the message.
+/// The checksum field is expected to be zero. Returns `None` when `msg` is
+/// truncated.
+pub fn checksum_v4(msg: &[u8], src: Ipv4Addr, dst: Ipv4Addr) -> Option<u16> {
+    let hdr = VrrpHdr::from_bytes(msg)?;
+    let msg = msg.get(..hdr.msg_len(false))?;
+    let initial = if hdr.version() == 2 {
+        0
+    } else {
+        checksum::pseudo_header_v4(src, dst, IpProto::Vrrp, msg.len() as u16)
+    };
+    Some(checksum::fold(checksum::sum(msg, initial)))
+}
+
+/// Computes the checksum of the VRRPv3 message at the start of `msg`,
+/// carried over IPv6 from `src` to `dst`, including the IPv6 pseudo-header.
+///
+/// The checksum field is expected to be zero. Returns `None` when `msg` is
+/// truncated.
+pub fn checksum_v6(msg: &[u8], src: Ipv6Addr, dst: Ipv6Addr) -> Option<u16> {
+    let hdr = VrrpHdr::from_bytes(msg)?;
+    let msg = msg.get(..hdr.msg_len(true))?;
+    let initial = checksum::pseudo_header_v6(src, dst, IpProto::Vrrp, msg.len() as u32);
+    Some(checksum::fold(checksum::sum(msg, initial)))
+}
+
+/// Verifies the checksum of the VRRP message at the start of `msg`, carried
+/// over IPv4 from `src` to `dst`.
+pub fn verify_checksum_v4(msg: &[u8], src: Ipv4Addr, dst: Ipv4Addr) -> bool {
+    checksum_v4(msg, src, dst) == Some(0)
+}
+
+/// Verifies the checksum of the VRRPv3 message at the start of `msg`,
+/// carried over IPv6 from `src` to `dst`.
+pub fn verify_checksum_v6(msg: &[u8], src: Ipv6Addr, dst: Ipv6Addr) -> bool {
+    checksum_v6(msg, src, dst) == Some(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use core::net::{Ipv4Addr, Ipv6Addr};
+
+    use super::{checksum_v6, verify_checksum_v4, verify_checksum_v6, VrrpHdr, VRRP_MCAST_V6};
+    use crate::header::Header;
+
+    #[test]
+    fn test_vrrp_v3_ipv6() {
+        let src = Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1);
+        let mut msg = [0u8; VrrpHdr::LEN + 16];
+        msg[..6].copy_from_slice(&[0x31, 7, 100, 1, 0, 50]);
+        msg[8..].copy_from_slice(&Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 0x100).octets());
+        let check = checksum_v6(&msg, src, VRRP_MCAST_V6).unwrap();
+        msg[6..8].copy_from_slice(&check.to_be_bytes());
+        assert!(verify_checksum_v6(&msg, src, VRRP_MCAST_V6));
+        assert!(!verify_checksum_v4(
+            &msg,
+            Ipv4Addr::UNSPECIFIED,
+            Ipv4Addr::UNSPECIFIED
+        ));
+
+        let hdr = VrrpHdr::from_bytes(&msg).unwrap();
+        assert_eq!(hdr.version(), 3);
+        assert_eq!(hdr.msg_type(), 1);
+        assert_eq!(hdr.vrid, 7);
+        assert_eq!(hdr.adver_int_centis(), 50);
+        assert_eq!(hdr.msg_len(true), msg.len());
+        let mut addrs = hdr.ipv6_addrs(&msg).unwrap();
+        assert_eq!(
+            addrs.next(),
+            Some(Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 0x100))
+        );
+        assert_eq!(addrs.next(), None);
+    }
+}
@@ -1,15 +1,33 @@
 use core::mem;
 
-use crate::bitfield::BitfieldUnit;
+use crate::{bitfield::BitfieldUnit, header::impl_header, types::U16};
+
+/// IANA-assigned UDP destination port of VXLAN.
+pub const VXLAN_PORT: u16 = 4789;
 
 /// VXLAN header, which is present at the beginning of every UDP payload containing VXLAN packets.
+///
+/// With the Group-Based Policy extension
+/// ([draft-smith-vxlan-group-policy](https://datatracker.ietf.org/doc/html/draft-smith-vxlan-group-policy)),
+/// the first word carries the group of the source endpoint:
+/// ```text
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-------------------------------+
+/// |G|R|R|R|I|R|R|R|R|D|R|R|A|R|R|R|        Group Policy ID        |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+---------------+---------------+
+/// |          VXLAN Network Identifier (VNI)       |   Reserved    |
+/// +-----------------------------------------------+---------------+
+/// ```
 #[repr(C, packed)]
 #[derive(Debug, Copy, Clone)]
 #[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 pub struct VxlanHdr {
     /// VXLAN flags. See [`VxlanHdr::vni_valid`] and [`VxlanHdr::set_vni_valid`].
     pub flags: BitfieldUnit<[u8; 1usize]>,
-    pub _reserved: [u8; 3],
+    /// Group-Based Policy flags, see [`VxlanHdr::policy_applied`] and
+    /// [`VxlanHdr::dont_learn`]. Reserved without the extension.
+    pub gbp_flags: u8,
+    /// Group-Based Policy ID, only meaningful if [`VxlanHdr::gbp`] is set.
+    pub group_policy_id: U16,
     /// VXLAN Virtual Network Identifier.
     ///
     /// This is a 24-bit number combined with reserved bytes, see [`VxlanHdr::vni`] and
@@ -20,23 +38,132 @@ pub struct VxlanHdr {
 impl VxlanHdr {
     pub const LEN: usize = mem::size_of::<Self>();
 
+    /// Parses a header from its wire representation in const contexts.
+    #[inline]
+    pub const fn parse_const(bytes: &[u8; Self::LEN]) -> Self {
+        // SAFETY: `VxlanHdr` is packed and every bit pattern is valid.
+        unsafe { mem::transmute::<[u8; Self::LEN], Self>(*bytes) }
+    }
+
+    /// Flags, in host byte order.
+    #[inline]
+    const fn flags_bits(&self) -> u8 {
+        self.flags.get(0, 8) as u8
+    }
+
+    #[inline]
+    const fn set_flag(&mut self, mask: u8, val: bool) {
+        let flags = if val {
+            self.flags_bits() | mask
+        } else {
+            self.flags_bits() & !mask
+        };
+        self.flags.set(0, 8, flags as u64)
+    }
+
+    /// **I**: the VNI is valid.
     #[inline]
-    pub fn vni_valid(&self) -> bool {
-        self.flags.get_bit(4)
+    pub const fn vni_valid(&self) -> bool {
+        self.flags_bits() & 0x08 != 0
     }
 
     #[inline]
-    pub fn set_vni_valid(&mut self, val: bool) {
-        self.flags.set_bit(4, val)
+    pub const fn set_vni_valid(&mut self, val: bool) {
+        self.set_flag(0x08, val)
     }
 
     #[inline]
-    pub fn vni(&self) -> u32 {
+    pub const fn vni(&self) -> u32 {
         u32::from_be(self.vni) >> 8
     }
 
     #[inline]
-    pub fn set_vni(&mut self, vni: u32) -> u32 {
-        (vni << 8).to_be()
+    pub const fn set_vni(&mut self, vni: u32) {
+        self.vni = ((vni << 8) | (u32::from_be(self.vni) & 0xff)).to_be()
+    }
+
+    /// **G**: the Group Policy ID is present.
+    #[inline]
+    pub const fn gbp(&self) -> bool {
+        self.flags_bits() & 0x80 != 0
+    }
+
+    #[inline]
+    pub const fn set_gbp(&mut self, val: bool) {
+        self.set_flag(0x80, val)
+    }
+
+    /// **D**: the egress VTEP must not learn the source address.
+    #[inline]
+    pub const fn dont_learn(&self) -> bool {
+        self.gbp_flags & 0x40 != 0
+    }
+
+    #[inline]
+    pub const fn set_dont_learn(&mut self, val: bool) {
+        self.gbp_flags = if val {
+            self.gbp_flags | 0x40
+        } else {
+            self.gbp_flags & !0x40
+        };
+    }
+
+    /// **A**: the group policy was already applied, so devices down the
+    /// path must not apply it again.
+    #[inline]
+    pub const fn policy_applied(&self) -> bool {
+        self.gbp_flags & 0x08 != 0
+    }
+
+    #[inline]
+    pub const fn set_policy_applied(&mut self, val: bool) {
+        self.gbp_flags = if val {
+            self.gbp_flags | 0x08
+        } else {
+            self.gbp_flags & !0x08
+        };
+    }
+
+    /// Group Policy ID of the source endpoint, `None` if the G flag is not
+    /// set.
+    #[inline]
+    pub const fn group_policy_id(&self) -> Option<u16> {
+        if self.gbp() {
+            Some(self.group_policy_id.to_bits())
+        } else {
+            None
+        }
+    }
+
+    /// Sets the Group Policy ID and the G flag.
+    #[inline]
+    pub const fn set_group_policy_id(&mut self, id: u16) {
+        self.group_policy_id = U16::from_bits(id);
+        self.set_gbp(true);
+    }
+}
+
+impl_header!(VxlanHdr);
+
+#[cfg(test)]
+mod tests {
+    use super::VxlanHdr;
+    use crate::header::Header;
+
+    #[test]
+    fn test_vxlan_gbp() {
+        let mut bytes = [0x88, 0x48, 0x12, 0x34, 0x00, 0x00, 0x64, 0x00];
+        let hdr = VxlanHdr::from_bytes_mut(&mut bytes).unwrap();
+        assert!(hdr.vni_valid());
+        assert_eq!(hdr.vni(), 100);
+        assert_eq!(hdr.group_policy_id(), Some(0x1234));
+        assert!(hdr.dont_learn());
+        assert!(hdr.policy_applied());
+
+        hdr.set_vni(0xabcdef);
+        hdr.set_dont_learn(false);
+        hdr.set_gbp(false);
+        assert_eq!(hdr.group_policy_id(), None);
+        assert_eq!(bytes, [0x08, 0x08, 0x12, 0x34, 0xab, 0xcd, 0xef, 0x00]);
     }
 }